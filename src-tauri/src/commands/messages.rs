@@ -1,65 +1,395 @@
 //! # 消息读写 Tauri Commands
 //!
 //! 提供会话消息的读取、编辑、删除、插入、搜索、导出等 Tauri command 处理函数：
-//! - `read_session_messages` - 读取会话并返回 TransformedSession
-//! - `delete_message` - 删除单条消息并返回更新后的 TransformedSession
-//! - `delete_messages` - 批量删除消息并返回更新后的 TransformedSession
-//! - `edit_message_content` - 编辑消息文本内容并返回更新后的 TransformedSession
-//! - `insert_message` - 在指定位置插入新消息并返回更新后的 TransformedSession
+//! - `read_session_messages` - 读取会话并返回 TransformedSession（可只读取指定分支）
+//! - `get_session_branches` - 枚举会话的分叉点与所有分支路径，并标注主线
+//! - `read_full_block` - 按需读取被折叠为 `oversized_text` 占位的超长文本块全文
+//! - `read_session_messages_streamed` - 流式读取会话：先返回外壳，再通过事件分批推送 DisplayMessage
+//! - `delete_message` - 删除单条消息并返回更新后的 TransformedSession（或只含差异的回执）
+//! - `delete_messages` - 批量删除消息并返回更新后的 TransformedSession（或只含差异的回执）
+//! - `truncate_session_after` - 截断会话：删除指定消息之后（可含自身）的全部行
+//! - `merge_messages` - 把相邻的多条同类型消息合并为一条（content 块按顺序拼接）
+//! - `edit_message_content` - 编辑消息文本内容并返回更新后的 TransformedSession 或回执（旧版本记入编辑历史）
+//! - `edit_message_raw` - 用原始 JSON 整体替换一条消息（校验 JSON 合法且 uuid 不变）
+//! - `undo_last_operation` / `get_undo_history` - 撤销最近一次删除 / 编辑消息（会话级撤销栈，最多 10 步）
+//! - `get_message_edit_history` - 获取某条消息被 CCR 编辑前的历史版本
+//! - `revert_message_to_version` - 用历史版本覆盖当前消息
+//! - `insert_message` - 在指定位置插入后端构造的 user / assistant 消息并返回更新后的 TransformedSession
+//! - `insert_raw_message` - 在指定位置插入前端构造的原始条目（custom-title、tag 等）
+//! - `copy_messages_to_session` - 把选中的消息复制（或移动）到另一个会话的指定位置，重新生成 uuid 并串接 parentUuid
+//! - `append_raw_lines` - 将外部 JSONL 片段（剪贴板 / 拖拽）追加到会话末尾
 //! - `delete_session` - 删除整个会话文件
-//! - `search_session` - 在缓存的搜索文本上执行 SIMD 加速子串搜索
-//! - `export_session` - 导出会话为 Markdown 或 JSON 格式
+//! - `search_session` - 在缓存的搜索文本上执行 SIMD 加速子串搜索，可按消息类型与时间范围过滤
+//! - `search_all_sessions` - 跨所有会话的全局搜索，支持正则、项目 / 时间范围 / 消息类型过滤
+//! - `cancel_global_search` - 取消进行中的全局搜索（返回部分结果）
+//! - `export_session` - 导出会话为 Markdown 或 JSON 格式，可只导出搜索命中的消息（及上下文）
+//! - `export_and_share` - 导出到 TEMP 后在系统文件管理器中定位文件，便于直接分享
+//! - `suggest_export_file_name` - 根据会话名称生成安全的导出文件名
+//! - `format_message` - 将单条消息格式化为 Markdown / 纯文本 / JSON（供复制到剪贴板）
+//! - `get_session_versions` - 统计会话中出现过的 Claude Code 版本及各自条数
+//! - `extract_links` - 提取会话中引用的 http/https 链接（去重，附首次出现位置与上下文）
+//! - `extract_code_blocks` / `save_code_blocks` - 提取 assistant 回复中的围栏代码块，并把选中的块保存为文件
+//! - `get_session_keywords` - 基于搜索文本的词频统计提取会话关键词
+//! - `get_session_stats` - 会话统计：Token / 工具调用汇总、按工具名的失败率与 stop_reason 分布
+//! - `get_failed_tool_calls` - 筛选失败的工具调用（失败的 tool_result 及发起调用的消息）
+//! - `get_tool_invocation` - 查询某个 tool_use 的完整输入与输出配对详情
+//! - `diff_tool_result_with_disk` - 对比工具结果中的文件快照与磁盘上的当前内容
+//! - `create_trimmed_copy` - 只保留主线最后 N 条消息生成瘦身副本（新会话或导出），原文件不变
+//! - `archive_and_delete_session` - 导出会话到归档目录并校验，成功后再删除原文件
+//! - `duplicate_session` - 复制整个会话为同项目下的新 UUID 会话（改写 sessionId），原文件不变
+//! - `merge_sessions` - 把同一项目下的另一个会话并入目标会话（按时间戳或追加，可选删除源会话）
+//! - `verify_roundtrip` - 诊断会话文件的「读取 → 写回」往返是否无损
+//! - `convert_session_encoding` - 把 UTF-16 / GBK 等非 UTF-8 会话文件转存为 UTF-8（经 file_guard 备份）
 //!
 //! ## 数据流
 //! - **读取路径**：文件 → parse → transform → 缓存 → IPC 返回 TransformedSession
+//! - **流式读取路径**：文件 → parse → IPC 返回外壳 → 后台分块 transform → `session-chunk` 事件
+//!   → 缓存 → `session-stream-done` 事件
 //! - **写入路径**：从文件重新读取原始 Vec<Value> → 修改 → 写回文件 → 重新 transform → 更新缓存 → IPC 返回
 //! - **搜索路径**：前端查询词 → Rust 在缓存搜索文本上 SIMD 搜索 → 返回匹配 display_id 列表
 //!
 //! ## 写入安全保证
 //! 写入操作始终从文件重新读取原始 `Vec<Value>`，经用户编辑后写回。
 //! 整个写入路径完全不经过 transformer，原始数据中不可能出现任何额外字段。
+//! 读取前先获取 `AppCache::lock_session` 会话写锁，同一文件的写操作串行执行。
+//! 非 UTF-8 的会话（读取时已转码）在经 `convert_session_encoding` 转存之前拒绝修改（`parser::read_messages_for_write`）。
 
 use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use serde_json::Value;
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow};
 
-use crate::models::display::TransformedSession;
-use crate::services::cache::AppCache;
-use crate::services::{export, file_guard, parser, transformer};
+use crate::models::display::{
+    AppendLinesResult, CopyMessagesResult, DisplayMessage, MessageOpResult, SessionChunk, SessionStats,
+    SessionStreamDone, SessionStreamStart, ToolInvocation, TransformedSession, UndoResult,
+};
+use crate::models::project::Session;
+use crate::commands::validation::validated_session_path;
+use crate::services::cache::{self, AppCache, SearchHit, SessionSearchFilter};
+use crate::services::command_trace;
+use crate::services::branches::{self, SessionBranches};
+use crate::services::compat::{self, SessionVersionStats};
+use crate::services::global_search::{self, GlobalSearchFilter, GlobalSearchOptions, GlobalSearchResult};
+use crate::services::keywords::SessionKeyword;
+use crate::services::code_blocks::{self, CodeBlock};
+use crate::services::links::{self, SessionLink};
+use crate::services::export::{self, ExportFilter, ExportOptions};
+use crate::services::limits::AppLimits;
+use crate::services::edit_history::{self, MessageEditVersion};
+use crate::services::share::{self, ShareResult};
+use crate::services::session_archive;
+use crate::services::session_merge::{self, MergePosition, MergeSessionsResult};
+use crate::services::trimmed_copy::{self, TrimmedCopyResult};
+use crate::services::{audit, file_guard, parser, session_cleanup, tool_diff, transformer};
+use crate::utils::{encoding, filename, time};
 
 /// 读取指定会话的所有消息并返回转换后的 TransformedSession
 ///
 /// 高性能读取 JSONL 文件，经过分类、转换后返回前端可直接渲染的数据。
 /// 优先从缓存获取，缓存未命中时从文件系统读取、转换并存入缓存。
 ///
+/// 指定 `branch_leaf` 时只转换从根到该叶子路径上的消息（分支视图），
+/// 结果不写入缓存，缓存始终保存完整会话供搜索、统计等命令使用。
+///
+/// 指定 `offset` 或 `limit` 时只返回 display_messages 的一个窗口（见 `TransformedSession::page`），
+/// tool_use_map 与 token_stats 仍为全量，并附带 `total_count`。分页只影响 IPC 返回的切片，
+/// 缓存中仍保存完整结果。
+///
 /// # 参数
 /// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `branch_leaf` - 分支叶子消息 uuid（来自 `get_session_branches`），None 时读取完整会话
+/// - `offset` - display_messages 的起始偏移（0 为最新一条）；None 表示 0
+/// - `limit` - 本页最多返回的 display_messages 条数；None 表示取到末尾
 /// - `cache` - Tauri managed state，内存缓存
 ///
 /// # 返回值
-/// 返回 TransformedSession，包含倒序的 display_messages、tool_use_map 和 token_stats
+/// 返回 TransformedSession，包含倒序的 display_messages、tool_use_map 和 token_stats；
+/// 分页时 `total_count` 为 display_messages 总条数
 ///
 /// # 错误
-/// 文件读取失败时返回错误
+/// 文件读取失败，或 `branch_leaf` 不在会话中时返回错误
 #[tauri::command]
 pub async fn read_session_messages(
     session_file_path: String,
+    branch_leaf: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
     cache: State<'_, AppCache>,
 ) -> Result<TransformedSession, String> {
-    // 优先尝试从缓存获取
+    command_trace::traced(
+        "read_session_messages",
+        command_trace::path_arg(&session_file_path),
+        read_session_messages_inner(session_file_path, branch_leaf, offset, limit, cache),
+    )
+    .await
+}
+
+/// `read_session_messages` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn read_session_messages_inner(
+    session_file_path: String,
+    branch_leaf: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    cache: State<'_, AppCache>,
+) -> Result<TransformedSession, String> {
+    let paged = offset.is_some() || limit.is_some();
+    let offset = offset.unwrap_or(0);
+    validated_session_path(&session_file_path)?;
+    let Some(leaf) = branch_leaf else {
+        if !paged {
+            return load_session(&session_file_path, &cache).await;
+        }
+        if let Some(page) = cache.get_session_page(&session_file_path, offset, limit) {
+            return Ok(page);
+        }
+        return Ok(load_session(&session_file_path, &cache).await?.page(offset, limit));
+    };
+
+    let (messages, reencoded_from) = parser::read_messages_decoded(&session_file_path).await?;
+    let path = branches::branch_path(&messages, &leaf)
+        .ok_or_else(|| format!("会话中不存在分支叶子消息: {}", leaf))?;
+    let on_branch: Vec<Value> = messages
+        .into_iter()
+        .filter(|m| {
+            m.get("uuid")
+                .and_then(|v| v.as_str())
+                .is_some_and(|u| path.contains(u))
+        })
+        .collect();
+    let mut transformed = transformer::transform_session(&on_branch).0;
+    transformed.reencoded_from = reencoded_from.map(str::to_string);
+    Ok(if paged { transformed.page(offset, limit) } else { transformed })
+}
+
+/// 枚举会话的分叉点与所有分支
+///
+/// 基于 parentUuid 构建消息树，返回子消息数大于 1 的分叉点、每条根 → 叶子路径的
+/// uuid 序列与长度，并按叶子时间戳标注 Claude Code 实际采用的主线。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+///
+/// # 返回值
+/// 分叉点与分支列表（主线在前）
+///
+/// # 错误
+/// 文件读取失败时返回错误
+#[tauri::command]
+pub async fn get_session_branches(session_file_path: String) -> Result<SessionBranches, String> {
+    command_trace::traced(
+        "get_session_branches",
+        command_trace::path_arg(&session_file_path),
+        get_session_branches_inner(session_file_path),
+    )
+    .await
+}
+
+/// `get_session_branches` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn get_session_branches_inner(session_file_path: String) -> Result<SessionBranches, String> {
+    validated_session_path(&session_file_path)?;
+    let messages = parser::read_messages(&session_file_path).await?;
+    Ok(branches::build_branches(&messages))
+}
+
+/// 读取一个文本块的完整内容
+///
+/// 超长 text 块在 DisplayMessage 中被替换为 `oversized_text` 占位（见 `services::transformer`），
+/// 前端展开时调用本命令从文件读取全文，不经过会话缓存。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `message_uuid` - 消息的 UUID
+/// - `block_index` - 占位块的 `block_index`（原始 `message.content` 中的索引；content 为字符串时为 0）
+///
+/// # 返回值
+/// 该文本块的完整文本
+///
+/// # 错误
+/// 文件读取失败、找不到该消息或该位置不是文本块时返回错误
+#[tauri::command]
+pub async fn read_full_block(
+    session_file_path: String,
+    message_uuid: String,
+    block_index: usize,
+) -> Result<String, String> {
+    command_trace::traced(
+        "read_full_block",
+        format!("{} block={}", command_trace::path_arg(&session_file_path), block_index),
+        read_full_block_inner(session_file_path, message_uuid, block_index),
+    )
+    .await
+}
+
+/// `read_full_block` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn read_full_block_inner(
+    session_file_path: String,
+    message_uuid: String,
+    block_index: usize,
+) -> Result<String, String> {
+    validated_session_path(&session_file_path)?;
+    let messages = parser::read_messages(&session_file_path).await?;
+    let message = messages
+        .iter()
+        .find(|msg| msg.get("uuid").and_then(|v| v.as_str()) == Some(message_uuid.as_str()))
+        .ok_or_else(|| format!("未找到消息: {}", message_uuid))?;
+    block_text(message, block_index).ok_or_else(|| format!("消息 {} 的第 {} 个块不是文本块", message_uuid, block_index))
+}
+
+/// 取消息 `message.content` 中指定位置的文本（content 为字符串时只接受索引 0）
+fn block_text(message: &Value, block_index: usize) -> Option<String> {
+    match message.get("message")?.get("content")? {
+        Value::String(text) if block_index == 0 => Some(text.clone()),
+        Value::Array(blocks) => blocks.get(block_index)?.get("text")?.as_str().map(str::to_string),
+        _ => None,
+    }
+}
+
+/// 流式加载中每批 DisplayMessage 的事件名
+pub const SESSION_CHUNK_EVENT: &str = "session-chunk";
+
+/// 流式加载完成的事件名
+pub const SESSION_STREAM_DONE_EVENT: &str = "session-stream-done";
+
+/// 流 ID 生成器（进程内单调递增）
+static NEXT_STREAM_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 流式读取会话：解析完成后立即返回外壳，转换结果通过事件分批推送
+///
+/// 大会话首次打开时，完整 transform 可能耗时较长。该命令在后台线程执行
+/// `transformer::transform_session_chunked`，每产出 `chunk_size` 条 DisplayMessage
+/// 就发送一次 `session-chunk` 事件（带 `seq` 序号），全部完成后写入缓存并发送
+/// `session-stream-done` 事件。缓存命中时直接在返回值中携带完整会话，不发送事件。
+///
+/// 事件可能早于本命令的返回值到达前端，前端应在调用前注册监听并按 `stream_id` 缓冲。
+/// 事件只发往发起读取的窗口，多窗口同时加载时互不干扰。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `chunk_size` - 每批 DisplayMessage 条数（0 视为 1）
+/// - `app` - Tauri AppHandle，用于发送事件与在后台任务中获取缓存
+/// - `window` - 发起读取的窗口，事件只发往该窗口
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 流 ID、原始消息条数，以及缓存命中时的完整会话
+///
+/// # 错误
+/// 文件读取失败时返回错误（此时不会发送任何事件）
+#[tauri::command]
+pub async fn read_session_messages_streamed(
+    session_file_path: String,
+    chunk_size: usize,
+    app: AppHandle,
+    window: WebviewWindow,
+    cache: State<'_, AppCache>,
+) -> Result<SessionStreamStart, String> {
+    let span = command_trace::CommandSpan::start(
+        "read_session_messages_streamed",
+        format!("{} chunk={}", command_trace::path_arg(&session_file_path), chunk_size),
+    );
+    read_session_messages_streamed_inner(session_file_path, chunk_size, app, window, cache, span).await
+}
+
+/// `read_session_messages_streamed` 的实现
+///
+/// `span` 随后台转换任务移动，在完成事件发出后才结束计时，记录的耗时覆盖整个流式加载；
+/// 缓存命中时立即结束，提前返回错误时按失败记录。
+async fn read_session_messages_streamed_inner(
+    session_file_path: String,
+    chunk_size: usize,
+    app: AppHandle,
+    window: WebviewWindow,
+    cache: State<'_, AppCache>,
+    span: command_trace::CommandSpan,
+) -> Result<SessionStreamStart, String> {
+    validated_session_path(&session_file_path)?;
+    let stream_id = NEXT_STREAM_ID.fetch_add(1, Ordering::Relaxed);
+
     if let Some(cached) = cache.get_session(&session_file_path) {
+        span.finish(true);
+        return Ok(SessionStreamStart {
+            stream_id,
+            total_raw: cached.display_messages.len(),
+            cached: Some(cached),
+            reencoded_from: None,
+        });
+    }
+
+    // 读取前记录 mtime：转换期间文件被追加时，缓存条目在下次读取时失效而不是带着旧内容存活
+    let stamp = cache::file_stamp(&session_file_path);
+    let (messages, reencoded_from) = parser::read_messages_decoded(&session_file_path).await?;
+    let total_raw = messages.len();
+    let label = window.label().to_string();
+
+    // transform 为 CPU 密集型（内部使用 rayon），放到阻塞线程池执行
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut chunks = 0u64;
+        let (mut transformed, search_texts, original_texts) =
+            transformer::transform_session_chunked(&messages, chunk_size, |chunk| {
+                let _ = app.emit_to(
+                    label.as_str(),
+                    SESSION_CHUNK_EVENT,
+                    SessionChunk {
+                        stream_id,
+                        seq: chunk.seq,
+                        display_messages: chunk.display_messages.to_vec(),
+                        tool_use_map: chunk.tool_uses,
+                    },
+                );
+                chunks += 1;
+            });
+
+        transformed.reencoded_from = reencoded_from.map(str::to_string);
+        let done = SessionStreamDone {
+            stream_id,
+            chunks,
+            token_stats: transformed.token_stats.clone(),
+            cwd_changes: transformed.cwd_changes.clone(),
+            cwds: transformed.cwds.clone(),
+        };
+        // 先写缓存再通知完成，保证前端收到完成事件后的搜索、统计请求能命中缓存
+        app.state::<AppCache>().set_session_stamped(
+            &session_file_path,
+            stamp,
+            transformed,
+            search_texts,
+            original_texts,
+        );
+        let _ = app.emit_to(label.as_str(), SESSION_STREAM_DONE_EVENT, done);
+        span.finish(true);
+    });
+
+    Ok(SessionStreamStart {
+        stream_id,
+        total_raw,
+        cached: None,
+        reencoded_from: reencoded_from.map(str::to_string),
+    })
+}
+
+/// 获取会话的 TransformedSession：优先读缓存，未命中时从文件加载并存入缓存
+///
+/// # 错误
+/// 文件读取失败时返回错误
+async fn load_session(session_file_path: &str, cache: &AppCache) -> Result<TransformedSession, String> {
+    // 优先尝试从缓存获取
+    if let Some(cached) = cache.get_session(session_file_path) {
         return Ok(cached);
     }
 
-    // 缓存未命中，从文件系统读取
-    let messages = parser::read_messages(&session_file_path).await?;
+    // 缓存未命中，从文件系统读取（非 UTF-8 文件已转码，并在结果中标注原始编码）；
+    // mtime 在读取前记录，读取期间的修改会让缓存条目在下次访问时失效
+    let stamp = cache::file_stamp(session_file_path);
+    let (messages, reencoded_from) = parser::read_messages_decoded(session_file_path).await?;
 
     // 转换为 TransformedSession + 搜索文本
-    let (transformed, search_texts, original_texts) = transformer::transform_session(&messages);
+    let (mut transformed, search_texts, original_texts) = transformer::transform_session(&messages);
+    transformed.reencoded_from = reencoded_from.map(str::to_string);
 
     // 存入缓存
-    cache.set_session(&session_file_path, transformed.clone(), search_texts, original_texts);
+    cache.set_session_stamped(session_file_path, stamp, transformed.clone(), search_texts, original_texts);
 
     Ok(transformed)
 }
@@ -69,14 +399,17 @@ pub async fn read_session_messages(
 /// 根据消息 UUID 从会话文件中移除一条消息，并修复 parentUuid 链：
 /// 将原本指向被删除消息的子消息重新链接到被删除消息的父消息，
 /// 保持 Claude Code 对话树结构的连续性（A → [deleted B] → C 变为 A → C）。
+/// 删除前的消息快照压入撤销栈，可通过 `undo_last_operation` 恢复。
 ///
 /// # 参数
 /// - `session_file_path` - 会话 JSONL 文件的绝对路径
 /// - `message_uuid` - 要删除的消息的 UUID
+/// - `receipt` - 为 true 时只返回差异回执（`OperationReceipt`），缺省为 false
 /// - `cache` - Tauri managed state，内存缓存
 ///
 /// # 返回值
-/// 返回删除后重新转换的 TransformedSession
+/// 返回删除后重新转换的 TransformedSession；回执模式下返回被移除的 display_id、
+/// 有变化的显示消息与新的 Token 统计
 ///
 /// # 错误
 /// 文件读写失败时返回错误
@@ -84,10 +417,44 @@ pub async fn read_session_messages(
 pub async fn delete_message(
     session_file_path: String,
     message_uuid: String,
+    receipt: Option<bool>,
     cache: State<'_, AppCache>,
-) -> Result<TransformedSession, String> {
-    // 从文件读取原始数据
-    let messages = parser::read_messages(&session_file_path).await?;
+) -> Result<MessageOpResult, String> {
+    command_trace::traced(
+        "delete_message",
+        command_trace::path_arg(&session_file_path),
+        delete_message_inner(session_file_path, message_uuid, receipt, cache),
+    )
+    .await
+}
+
+/// `delete_message` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn delete_message_inner(
+    session_file_path: String,
+    message_uuid: String,
+    receipt: Option<bool>,
+    cache: State<'_, AppCache>,
+) -> Result<MessageOpResult, String> {
+    validated_session_path(&session_file_path)?;
+    remove_message(&session_file_path, &message_uuid, receipt.unwrap_or(false), &cache).await
+}
+
+/// 删除单条消息的实现（持有会话写锁完成读取 → 修改 → 写回）
+///
+/// # 错误
+/// 文件读写失败时返回错误
+async fn remove_message(
+    session_file_path: &str,
+    message_uuid: &str,
+    receipt: bool,
+    cache: &AppCache,
+) -> Result<MessageOpResult, String> {
+    let _guard = cache.lock_session(session_file_path).await;
+
+    // 从文件读取原始数据（修改前的快照写回后压入撤销栈）
+    let messages = parser::read_messages_for_write(session_file_path).await?;
+    let snapshot = messages.clone();
+    let before = display_messages_before(session_file_path, &snapshot, receipt, cache);
 
     // ---- 修复 parentUuid 链 ----
     // 查找被删除消息的 parentUuid，用于将其子消息重新链接到其父消息。
@@ -97,7 +464,7 @@ pub async fn delete_message(
         .find(|msg| {
             msg.get("uuid")
                 .and_then(|v| v.as_str())
-                .map(|uuid| uuid == message_uuid.as_str())
+                .map(|uuid| uuid == message_uuid)
                 .unwrap_or(false)
         })
         .and_then(|msg| msg.get("parentUuid"))
@@ -121,7 +488,7 @@ pub async fn delete_message(
         let points_to_deleted = msg
             .get("parentUuid")
             .and_then(|v| v.as_str())
-            .map(|uuid| uuid == message_uuid.as_str())
+            .map(|uuid| uuid == message_uuid)
             .unwrap_or(false);
 
         if points_to_deleted {
@@ -132,13 +499,30 @@ pub async fn delete_message(
     }
 
     // 写回文件（通过 file_guard 安全写入）
-    parser::write_messages(&session_file_path, &filtered, "delete_message", &cache).await?;
+    parser::write_messages(session_file_path, &filtered, "delete_message", cache).await?;
+    cache.push_undo(session_file_path, "删除 1 条消息".to_string(), snapshot);
 
     // 重新 transform 并更新缓存
     let (transformed, search_texts, original_texts) = transformer::transform_session(&filtered);
-    cache.set_session(&session_file_path, transformed.clone(), search_texts, original_texts);
+    cache.set_session(session_file_path, transformed.clone(), search_texts, original_texts);
 
-    Ok(transformed)
+    Ok(MessageOpResult::new(transformed, before))
+}
+
+/// 回执模式下取写入前的显示消息：优先使用缓存，未命中时由写入前的快照重新转换
+///
+/// 须在写回文件之前调用（写回后缓存即失效）；非回执模式返回 None，不产生额外开销。
+fn display_messages_before(
+    session_file_path: &str,
+    snapshot: &[Value],
+    receipt: bool,
+    cache: &AppCache,
+) -> Option<Vec<DisplayMessage>> {
+    if !receipt {
+        return None;
+    }
+    let cached = cache.get_session(session_file_path).map(|transformed| transformed.display_messages);
+    Some(cached.unwrap_or_else(|| transformer::transform_session(snapshot).0.display_messages))
 }
 
 /// 批量删除多条消息
@@ -151,13 +535,16 @@ pub async fn delete_message(
 /// 需要沿被删除消息链向上查找最近的未被删除祖先，将 D 的 parentUuid
 /// 从 C 跳过整个被删除链，最终指向 A。
 ///
+/// 删除前的消息快照压入撤销栈，可通过 `undo_last_operation` 恢复。
+///
 /// # 参数
 /// - `session_file_path` - 会话 JSONL 文件的绝对路径
 /// - `message_uuids` - 要删除的消息 UUID 列表
+/// - `receipt` - 为 true 时只返回差异回执（`OperationReceipt`），缺省为 false
 /// - `cache` - Tauri managed state，内存缓存
 ///
 /// # 返回值
-/// 返回删除后重新转换的 TransformedSession
+/// 返回删除后重新转换的 TransformedSession；回执模式下返回差异回执
 ///
 /// # 错误
 /// 文件读写失败时返回错误
@@ -169,13 +556,126 @@ pub async fn delete_message(
 pub async fn delete_messages(
     session_file_path: String,
     message_uuids: Vec<String>,
+    receipt: Option<bool>,
     cache: State<'_, AppCache>,
-) -> Result<TransformedSession, String> {
-    let messages = parser::read_messages(&session_file_path).await?;
+) -> Result<MessageOpResult, String> {
+    command_trace::traced(
+        "delete_messages",
+        format!("{} count={}", command_trace::path_arg(&session_file_path), message_uuids.len()),
+        audit::with_batch(delete_messages_inner(session_file_path, message_uuids, receipt, cache)),
+    )
+    .await
+}
+
+/// `delete_messages` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn delete_messages_inner(
+    session_file_path: String,
+    message_uuids: Vec<String>,
+    receipt: Option<bool>,
+    cache: State<'_, AppCache>,
+) -> Result<MessageOpResult, String> {
+    validated_session_path(&session_file_path)?;
+    let _guard = cache.lock_session(&session_file_path).await;
+
+    let messages = parser::read_messages_for_write(&session_file_path).await?;
+    let snapshot = messages.clone();
+    let before = display_messages_before(&session_file_path, &snapshot, receipt.unwrap_or(false), &cache);
 
     // 将 UUID 列表转换为 HashSet，实现 O(1) 查找
     let uuid_set: HashSet<&str> = message_uuids.iter().map(|s| s.as_str()).collect();
+    let filtered = remove_and_relink(messages, &uuid_set);
+
+    parser::write_messages(&session_file_path, &filtered, "delete_messages", &cache).await?;
+    let removed = snapshot.len() - filtered.len();
+    cache.push_undo(&session_file_path, format!("删除 {} 条消息", removed), snapshot);
+
+    // 重新 transform 并更新缓存
+    let (transformed, search_texts, original_texts) = transformer::transform_session(&filtered);
+    cache.set_session(&session_file_path, transformed.clone(), search_texts, original_texts);
+
+    Ok(MessageOpResult::new(transformed, before))
+}
+
+/// 截断会话：删除某条消息之后的全部内容
+///
+/// 在原始消息列表（文件行顺序）中定位 `message_uuid`，丢弃其后的所有行（`inclusive` 为 true 时连同自身），
+/// 用于「回滚到某个时间点再继续」。其后的 summary、custom-title 等元数据行同样被丢弃。
+/// 写回经 `file_guard` 双重备份，操作标记为 `truncate_session`；截断前的快照压入撤销栈。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `message_uuid` - 截断位置的消息 UUID
+/// - `inclusive` - 是否同时删除该消息本身
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 返回截断后重新转换的 TransformedSession
+///
+/// # 错误
+/// 路径验证失败、找不到该 UUID 或文件读写失败时返回错误
+#[tauri::command]
+pub async fn truncate_session_after(
+    session_file_path: String,
+    message_uuid: String,
+    inclusive: bool,
+    cache: State<'_, AppCache>,
+) -> Result<TransformedSession, String> {
+    command_trace::traced(
+        "truncate_session_after",
+        format!("{} inclusive={}", command_trace::path_arg(&session_file_path), inclusive),
+        truncate_session_after_inner(session_file_path, message_uuid, inclusive, cache),
+    )
+    .await
+}
+
+/// `truncate_session_after` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn truncate_session_after_inner(
+    session_file_path: String,
+    message_uuid: String,
+    inclusive: bool,
+    cache: State<'_, AppCache>,
+) -> Result<TransformedSession, String> {
+    validated_session_path(&session_file_path)?;
+    let _guard = cache.lock_session(&session_file_path).await;
+
+    let messages = parser::read_messages_for_write(&session_file_path).await?;
+    let keep = truncate_position(&messages, &message_uuid, inclusive)?;
+    let snapshot = messages.clone();
+    let truncated: Vec<Value> = messages.into_iter().take(keep).collect();
+
+    parser::write_messages(&session_file_path, &truncated, "truncate_session", &cache).await?;
+    let removed = snapshot.len() - truncated.len();
+    cache.push_undo(&session_file_path, format!("截断会话（删除 {} 行）", removed), snapshot);
+
+    let (transformed, search_texts, original_texts) = transformer::transform_session(&truncated);
+    cache.set_session(&session_file_path, transformed.clone(), search_texts, original_texts);
+
+    Ok(transformed)
+}
 
+/// 截断后保留的行数：`message_uuid` 所在行之前（`inclusive`）或之后（不含）的位置
+///
+/// # 错误
+/// 找不到该 UUID 时返回错误
+fn truncate_position(messages: &[Value], message_uuid: &str, inclusive: bool) -> Result<usize, String> {
+    let index = messages
+        .iter()
+        .position(|m| m.get("uuid").and_then(|v| v.as_str()) == Some(message_uuid))
+        .ok_or_else(|| format!("未找到消息: {}", message_uuid))?;
+    Ok(if inclusive { index } else { index + 1 })
+}
+
+/// 移除指定 UUID 的消息，并把子消息的 parentUuid 重新链接到最近的未被删除祖先
+///
+/// 供 `delete_messages` 与 `copy_messages_to_session`（移动模式）共用，链修复规则见 `delete_messages`。
+///
+/// # 参数
+/// - `messages` - 会话的全部原始消息
+/// - `uuid_set` - 要移除的消息 UUID 集合
+///
+/// # 返回值
+/// 移除并修复 parentUuid 链后的消息列表
+fn remove_and_relink(messages: Vec<Value>, uuid_set: &HashSet<&str>) -> Vec<Value> {
     // ---- 修复 parentUuid 链（支持级联） ----
     // 构建被删除消息的 uuid → parentUuid 映射表。
     // 用于在删除后将子消息的 parentUuid 重新链接到最近的未被删除祖先。
@@ -235,15 +735,147 @@ pub async fn delete_messages(
         }
     }
 
-    parser::write_messages(&session_file_path, &filtered, "delete_messages", &cache).await?;
+    filtered
+}
 
-    // 重新 transform 并更新缓存
-    let (transformed, search_texts, original_texts) = transformer::transform_session(&filtered);
+/// 把相邻的多条同类型消息合并为一条
+///
+/// 用于整理历史会话：把连续几条零碎的 user（或 assistant）消息压缩成一条，resume 时上下文更干净。
+/// 所选消息的 content 块按文件顺序拼接进第一条，其余条目删除，
+/// 原本指向被删除条目的 parentUuid 改为指向合并后的消息。
+///
+/// 合并前的消息快照压入撤销栈，可通过 `undo_last_operation` 恢复。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `message_uuids` - 要合并的消息 UUID 列表（至少 2 条，顺序不限，按文件顺序合并）
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 返回合并后重新转换的 TransformedSession
+///
+/// # 错误
+/// 消息不存在、类型不一致（须都为 user 或都为 assistant）、中间夹有未选中的对话消息或文件读写失败时返回错误
+#[tauri::command]
+pub async fn merge_messages(
+    session_file_path: String,
+    message_uuids: Vec<String>,
+    cache: State<'_, AppCache>,
+) -> Result<TransformedSession, String> {
+    command_trace::traced(
+        "merge_messages",
+        format!("{} count={}", command_trace::path_arg(&session_file_path), message_uuids.len()),
+        merge_messages_inner(session_file_path, message_uuids, cache),
+    )
+    .await
+}
+
+/// `merge_messages` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn merge_messages_inner(
+    session_file_path: String,
+    message_uuids: Vec<String>,
+    cache: State<'_, AppCache>,
+) -> Result<TransformedSession, String> {
+    validated_session_path(&session_file_path)?;
+    let _guard = cache.lock_session(&session_file_path).await;
+
+    let messages = parser::read_messages_for_write(&session_file_path).await?;
+    let snapshot = messages.clone();
+    let merged = combine_adjacent_messages(messages, &message_uuids)?;
+
+    parser::write_messages(&session_file_path, &merged, "merge_messages", &cache).await?;
+    cache.push_undo(&session_file_path, format!("合并 {} 条消息", snapshot.len() - merged.len() + 1), snapshot);
+
+    let (transformed, search_texts, original_texts) = transformer::transform_session(&merged);
     cache.set_session(&session_file_path, transformed.clone(), search_texts, original_texts);
 
     Ok(transformed)
 }
 
+/// 合并相邻的同类型消息（`merge_messages` 的纯数据部分）
+///
+/// 「相邻」指按文件顺序，首尾两条所选消息之间的 user / assistant 消息全部被选中；
+/// 夹在中间的其他条目（如 system、progress）保持不动。
+/// 全部为字符串 content 时以空行拼接为字符串，否则统一转为内容块数组后拼接。
+///
+/// # 参数
+/// - `messages` - 会话的全部原始消息
+/// - `message_uuids` - 要合并的消息 UUID 列表
+///
+/// # 返回值
+/// 合并后的消息列表
+///
+/// # 错误
+/// 不足 2 条、消息不存在、类型不一致或不相邻时返回说明原因的错误
+fn combine_adjacent_messages(mut messages: Vec<Value>, message_uuids: &[String]) -> Result<Vec<Value>, String> {
+    let uuid_set: HashSet<&str> = message_uuids.iter().map(|s| s.as_str()).collect();
+    if uuid_set.len() < 2 {
+        return Err("至少需要选择 2 条消息才能合并".to_string());
+    }
+
+    let uuid_of = |msg: &Value| msg.get("uuid").and_then(|v| v.as_str()).map(str::to_string);
+    let type_of = |msg: &Value| msg.get("type").and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let indices: Vec<usize> = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, msg)| uuid_of(msg).is_some_and(|u| uuid_set.contains(u.as_str())))
+        .map(|(i, _)| i)
+        .collect();
+    if indices.len() != uuid_set.len() {
+        return Err("部分消息在会话中不存在，可能已被删除".to_string());
+    }
+
+    let merge_type = type_of(&messages[indices[0]]);
+    if merge_type != "user" && merge_type != "assistant" {
+        return Err(format!("只能合并 user 或 assistant 消息，所选消息类型为 {}", merge_type));
+    }
+    if let Some(other) = indices.iter().map(|&i| type_of(&messages[i])).find(|t| *t != merge_type) {
+        return Err(format!("所选消息类型不一致（{} 与 {}），只能合并同类型的消息", merge_type, other));
+    }
+    let (first, last) = (indices[0], indices[indices.len() - 1]);
+    if messages[first..=last]
+        .iter()
+        .any(|msg| matches!(type_of(msg).as_str(), "user" | "assistant") && !uuid_of(msg).is_some_and(|u| uuid_set.contains(u.as_str())))
+    {
+        return Err("所选消息之间夹有未选中的对话消息，只能合并相邻的消息".to_string());
+    }
+
+    // 按文件顺序收集所有 content，拼接进第一条
+    let contents: Vec<Value> = indices
+        .iter()
+        .map(|&i| messages[i].get("message").and_then(|m| m.get("content")).cloned().unwrap_or(Value::Null))
+        .collect();
+    let combined = if contents.iter().all(|c| c.is_string()) {
+        let texts: Vec<&str> = contents.iter().filter_map(|c| c.as_str()).collect();
+        Value::String(texts.join("\n\n"))
+    } else {
+        let blocks: Vec<Value> = contents
+            .into_iter()
+            .flat_map(|content| match content {
+                Value::Array(blocks) => blocks,
+                Value::String(text) if !text.is_empty() => vec![serde_json::json!({"type": "text", "text": text})],
+                _ => Vec::new(),
+            })
+            .collect();
+        Value::Array(blocks)
+    };
+    let merged_uuid = uuid_of(&messages[first]).unwrap_or_default();
+    if let Some(message) = messages[first].get_mut("message").and_then(|m| m.as_object_mut()) {
+        message.insert("content".to_string(), combined);
+    }
+
+    // 删除其余条目，并把指向它们的 parentUuid 改为合并后的消息
+    let removed: HashSet<String> = indices[1..].iter().filter_map(|&i| uuid_of(&messages[i])).collect();
+    messages.retain(|msg| !uuid_of(msg).is_some_and(|u| removed.contains(&u)));
+    for msg in messages.iter_mut() {
+        let points_to_removed = msg.get("parentUuid").and_then(|v| v.as_str()).is_some_and(|p| removed.contains(p));
+        if points_to_removed && let Some(obj) = msg.as_object_mut() {
+            obj.insert("parentUuid".to_string(), Value::String(merged_uuid.clone()));
+        }
+    }
+    Ok(messages)
+}
+
 /// 单个内容块的编辑数据
 ///
 /// 前端按块编辑时，每个被修改的内容块通过此结构体描述：
@@ -270,26 +902,56 @@ pub struct BlockEdit {
 /// - `tool_result` 类型块：更新 `content` 字段为纯文本
 /// - 字符串格式 content：整体替换为第一个编辑项的文本
 ///
+/// 写回成功后把被修改消息的旧版本追加到编辑历史（`~/.mo/CCR/edit-history/`），
+/// 可通过 `get_message_edit_history` 查看、`revert_message_to_version` 恢复；
+/// 整个会话编辑前的快照同时压入撤销栈，可通过 `undo_last_operation` 撤销。
+///
 /// # 参数
 /// - `session_file_path` - 会话 JSONL 文件的绝对路径
 /// - `message_uuid` - 要编辑的消息的 UUID
 /// - `block_edits` - 按块索引的编辑列表，每项包含 (index, text)
+/// - `receipt` - 为 true 时只返回差异回执（`OperationReceipt`），缺省为 false
 /// - `cache` - Tauri managed state，内存缓存
 ///
 /// # 返回值
-/// 返回更新后重新转换的 TransformedSession
+/// 返回更新后重新转换的 TransformedSession；回执模式下返回差异回执
 ///
 /// # 错误
-/// 文件读写失败时返回错误
+/// 文件读写失败时返回错误（编辑历史记录失败只记日志）
 #[tauri::command]
 pub async fn edit_message_content(
     session_file_path: String,
     message_uuid: String,
     block_edits: Vec<BlockEdit>,
+    receipt: Option<bool>,
     cache: State<'_, AppCache>,
-) -> Result<TransformedSession, String> {
-    // 从文件读取原始数据
-    let messages = parser::read_messages(&session_file_path).await?;
+) -> Result<MessageOpResult, String> {
+    command_trace::traced(
+        "edit_message_content",
+        command_trace::path_arg(&session_file_path),
+        edit_message_content_inner(session_file_path, message_uuid, block_edits, receipt, cache),
+    )
+    .await
+}
+
+/// `edit_message_content` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn edit_message_content_inner(
+    session_file_path: String,
+    message_uuid: String,
+    block_edits: Vec<BlockEdit>,
+    receipt: Option<bool>,
+    cache: State<'_, AppCache>,
+) -> Result<MessageOpResult, String> {
+    validated_session_path(&session_file_path)?;
+    let _guard = cache.lock_session(&session_file_path).await;
+
+    // 从文件读取原始数据（修改前的快照写回后压入撤销栈）
+    let messages = parser::read_messages_for_write(&session_file_path).await?;
+    let snapshot = messages.clone();
+    let before = display_messages_before(&session_file_path, &snapshot, receipt.unwrap_or(false), &cache);
+
+    // 编辑前的目标消息原文，写回成功后记入编辑历史
+    let mut previous: Option<Value> = None;
 
     let updated: Vec<Value> = messages
         .into_iter()
@@ -304,6 +966,7 @@ pub async fn edit_message_content(
             if !is_target {
                 return msg;
             }
+            previous = Some(msg.clone());
 
             // 检查是否有 message 字段
             if let Some(message) = msg.get_mut("message") {
@@ -387,216 +1050,2104 @@ pub async fn edit_message_content(
         })
         .collect();
 
+    // 旧版本是否需要记录（内容未变化时不记录，避免产生重复历史）
+    let previous = previous.filter(|previous| {
+        updated
+            .iter()
+            .find(|msg| msg.get("uuid").and_then(|v| v.as_str()) == Some(message_uuid.as_str()))
+            != Some(previous)
+    });
+
     // 写回文件（通过 file_guard 安全写入）
     parser::write_messages(&session_file_path, &updated, "edit_message", &cache).await?;
+    cache.push_undo(&session_file_path, "编辑 1 条消息".to_string(), snapshot);
+    // 写入成功后才记录旧版本，写入失败时历史中不会出现从未生效的版本
+    if let Some(previous) = previous {
+        record_edit_history(&session_file_path, &previous).await;
+    }
 
     // 重新 transform 并更新缓存
     let (transformed, search_texts, original_texts) = transformer::transform_session(&updated);
     cache.set_session(&session_file_path, transformed.clone(), search_texts, original_texts);
 
-    Ok(transformed)
+    Ok(MessageOpResult::new(transformed, before))
 }
 
-/// 在指定位置插入一条新消息
+/// 用原始 JSON 整体替换一条消息
 ///
-/// 在 `after_uuid` 指定的消息之后插入一条前端构造好的新消息。
-/// 如果 `after_uuid` 为空字符串，则插入到消息列表的最前方（index 0）。
-/// 操作完成后重新 transform 并更新缓存，返回新的 TransformedSession。
+/// 用于修改 `edit_message_content` 覆盖不到的顶层字段（如 `isMeta`、`parentUuid`，或删除某个字段）。
+/// `raw_json` 必须是 JSON 对象；缺少 `uuid` 时沿用原值，`uuid` 与原消息不同时拒绝。
+/// 旧版本记入编辑历史，整个会话的快照压入撤销栈，与 `edit_message_content` 相同。
 ///
 /// # 参数
 /// - `session_file_path` - 会话 JSONL 文件的绝对路径
-/// - `after_uuid` - 插入到此 UUID 消息之后（空字符串表示插入到开头）
-/// - `new_message` - 前端构造好的完整 SessionMessage JSON 对象
+/// - `message_uuid` - 要替换的消息的 UUID
+/// - `raw_json` - 新的消息 JSON 文本
 /// - `cache` - Tauri managed state，内存缓存
 ///
 /// # 返回值
-/// 返回插入后重新转换的 TransformedSession
+/// 返回更新后重新转换的 TransformedSession
 ///
 /// # 错误
-/// - 文件读写失败时返回错误
-/// - `after_uuid` 非空但未找到匹配消息时返回错误
+/// JSON 解析失败（错误信息含行列号）、不是对象、uuid 被修改、找不到该消息，
+/// 或文件读写失败时返回错误
 #[tauri::command]
-pub async fn insert_message(
+pub async fn edit_message_raw(
     session_file_path: String,
-    after_uuid: String,
-    new_message: Value,
+    message_uuid: String,
+    raw_json: String,
     cache: State<'_, AppCache>,
 ) -> Result<TransformedSession, String> {
-    // 从文件读取原始数据
-    let mut messages = parser::read_messages(&session_file_path).await?;
+    command_trace::traced(
+        "edit_message_raw",
+        command_trace::path_arg(&session_file_path),
+        edit_message_raw_inner(session_file_path, message_uuid, raw_json, cache),
+    )
+    .await
+}
 
-    // 计算插入位置
-    let insert_index = if after_uuid.is_empty() {
-        // 空字符串：插入到最前方
-        0
-    } else {
-        // 查找 after_uuid 对应的消息索引
-        let found = messages.iter().position(|msg| {
-            msg.get("uuid")
-                .and_then(|v| v.as_str())
-                .map(|uuid| uuid == after_uuid)
-                .unwrap_or(false)
-        });
+/// `edit_message_raw` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn edit_message_raw_inner(
+    session_file_path: String,
+    message_uuid: String,
+    raw_json: String,
+    cache: State<'_, AppCache>,
+) -> Result<TransformedSession, String> {
+    validated_session_path(&session_file_path)?;
+    let replacement = parse_raw_message(&raw_json, &message_uuid)?;
+    let _guard = cache.lock_session(&session_file_path).await;
 
-        match found {
-            Some(idx) => idx + 1, // 在目标消息之后插入
-            None => return Err(format!("未找到 UUID 为 '{}' 的消息", after_uuid)),
-        }
-    };
+    let mut messages = parser::read_messages_for_write(&session_file_path).await?;
+    let snapshot = messages.clone();
+    let target = messages
+        .iter_mut()
+        .find(|msg| msg.get("uuid").and_then(|v| v.as_str()) == Some(message_uuid.as_str()))
+        .ok_or_else(|| format!("未找到消息: {}", message_uuid))?;
+    let previous = (*target != replacement).then(|| std::mem::replace(target, replacement));
 
-    // 在计算好的位置插入新消息
-    messages.insert(insert_index, new_message);
+    parser::write_messages(&session_file_path, &messages, "edit_message", &cache).await?;
+    cache.push_undo(&session_file_path, "编辑 1 条消息".to_string(), snapshot);
+    if let Some(previous) = previous {
+        record_edit_history(&session_file_path, &previous).await;
+    }
 
-    // ---- 更新父消息链 ----
-    // Claude Code 的 JSONL 格式中，消息通过 parentUuid 构成对话树。
-    // 在 A 和 B 之间插入 NEW 时：
-    //   - NEW 的 parentUuid 已由前端设置为 A 的 uuid
-    //   - 需要将原本指向 A 的后续消息（B）的 parentUuid 更新为 NEW 的 uuid
-    //
-    // 仅更新紧跟在插入位置之后、且 parentUuid 原本指向 after_uuid 的消息。
-    // 这保证了对话树的链式结构不被打断。
-    if !after_uuid.is_empty() {
-        // 获取新消息的 uuid
-        let new_uuid = messages[insert_index]
-            .get("uuid")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+    let (transformed, search_texts, original_texts) = transformer::transform_session(&messages);
+    cache.set_session(&session_file_path, transformed.clone(), search_texts, original_texts);
 
-        if let Some(new_uuid) = new_uuid {
-            // 扫描插入位置之后的消息，找到第一条 parentUuid == after_uuid 的消息并更新
-            for msg in messages.iter_mut().skip(insert_index + 1) {
-                let is_child_of_after = msg
-                    .get("parentUuid")
-                    .and_then(|v| v.as_str())
-                    .map(|uuid| uuid == after_uuid)
-                    .unwrap_or(false);
-
-                if is_child_of_after {
-                    // 将其 parentUuid 指向新消息
-                    msg.as_object_mut().map(|obj| {
-                        obj.insert("parentUuid".to_string(), Value::String(new_uuid.clone()));
-                    });
-                    // 只更新第一条匹配的（主链中的直接后继），避免影响分支（sidechain）
-                    break;
-                }
-            }
+    Ok(transformed)
+}
+
+/// 解析并校验 `edit_message_raw` 传入的消息 JSON
+///
+/// # 错误
+/// 解析失败（含行列号）、不是 JSON 对象或 uuid 与原消息不同时返回错误
+fn parse_raw_message(raw_json: &str, message_uuid: &str) -> Result<Value, String> {
+    let mut value: Value = serde_json::from_str(raw_json)
+        .map_err(|e| format!("JSON 解析失败（第 {} 行，第 {} 列）: {}", e.line(), e.column(), e))?;
+    let object = value.as_object_mut().ok_or("消息必须是 JSON 对象")?;
+    match object.get("uuid") {
+        None => {
+            object.insert("uuid".to_string(), Value::String(message_uuid.to_string()));
         }
+        Some(uuid) if uuid.as_str() == Some(message_uuid) => {}
+        Some(uuid) => return Err(format!("不允许修改消息 uuid（原 {}，新 {}）", message_uuid, uuid)),
     }
+    Ok(value)
+}
 
-    // 写回文件（通过 file_guard 安全写入）
-    parser::write_messages(&session_file_path, &messages, "insert_message", &cache).await?;
+/// 撤销会话最近一次删除 / 编辑消息操作
+///
+/// 从撤销栈弹出操作前的消息快照，经 file_guard 写回文件后重新 transform 并更新缓存。
+/// 文件在该操作之后被外部程序或其他操作修改时拒绝撤销（见 `services::cache` 的撤销栈说明）。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `cache` - Tauri managed state，内存缓存（含撤销栈）
+///
+/// # 返回值
+/// 被撤销的操作描述、剩余可撤销操作与恢复后的 TransformedSession
+///
+/// # 错误
+/// 没有可撤销的操作、文件已被修改（以 `UNDO_CONFLICT_ERROR` 开头）或写回失败时返回错误
+#[tauri::command]
+pub async fn undo_last_operation(
+    session_file_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<UndoResult, String> {
+    command_trace::traced(
+        "undo_last_operation",
+        command_trace::path_arg(&session_file_path),
+        undo_last_operation_inner(session_file_path, &cache),
+    )
+    .await
+}
 
-    // 重新 transform 并更新缓存
-    let (transformed, search_texts, original_texts) = transformer::transform_session(&messages);
+/// `undo_last_operation` 的实现，由 command 包进 `command_trace::traced` 调用
+///
+/// 写回失败（如会话已锁定）时把弹出的一步放回栈顶，撤销记录不丢失。
+async fn undo_last_operation_inner(
+    session_file_path: String,
+    cache: &AppCache,
+) -> Result<UndoResult, String> {
+    validated_session_path(&session_file_path)?;
+    let _guard = cache.lock_session(&session_file_path).await;
+
+    let step = cache.pop_undo(&session_file_path)?;
+    if let Err(e) = parser::write_messages(&session_file_path, &step.messages, "undo", cache).await {
+        cache.restore_undo(&session_file_path, step);
+        return Err(e);
+    }
+    cache.finish_undo(&session_file_path);
+
+    let (transformed, search_texts, original_texts) = transformer::transform_session(&step.messages);
     cache.set_session(&session_file_path, transformed.clone(), search_texts, original_texts);
 
-    Ok(transformed)
+    Ok(UndoResult {
+        undone: step.description,
+        remaining: cache.undo_descriptions(&session_file_path),
+        session: transformed,
+    })
 }
 
-/// 删除指定的会话文件
+/// 获取会话撤销栈中各步的操作描述
 ///
-/// 从文件系统中永久移除会话的 JSONL 文件。
-/// 删除前通过 `file_guard` 自动创建临时备份（强制）和主动备份（可选），
-/// 确保用户在应用关闭前可以反悔恢复。
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `cache` - Tauri managed state，内存缓存（含撤销栈）
+///
+/// # 返回值
+/// 操作描述列表（最近的在前）；没有可撤销操作时为空
+#[tauri::command]
+pub fn get_undo_history(session_file_path: String, cache: State<'_, AppCache>) -> Vec<String> {
+    cache.undo_descriptions(&session_file_path)
+}
+
+/// 获取某条消息被 CCR 编辑前的历史版本
 ///
 /// # 参数
-/// - `session_file_path` - 要删除的会话 JSONL 文件的绝对路径
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `message_uuid` - 消息的 UUID
+///
+/// # 返回值
+/// 历史版本列表（最新在前），每项包含保存时间、文本摘要与完整原文
+///
+/// # 错误
+/// 编辑历史文件读取失败时返回错误
+#[tauri::command]
+pub async fn get_message_edit_history(
+    session_file_path: String,
+    message_uuid: String,
+) -> Result<Vec<MessageEditVersion>, String> {
+    command_trace::traced(
+        "get_message_edit_history",
+        command_trace::path_arg(&session_file_path),
+        get_message_edit_history_inner(session_file_path, message_uuid),
+    )
+    .await
+}
+
+/// `get_message_edit_history` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn get_message_edit_history_inner(
+    session_file_path: String,
+    message_uuid: String,
+) -> Result<Vec<MessageEditVersion>, String> {
+    validated_session_path(&session_file_path)?;
+    edit_history::list(&session_file_path, &message_uuid).await
+}
+
+/// 用历史版本覆盖当前消息
+///
+/// 覆盖成功后被替换的版本同样记入编辑历史，因此回滚本身也可以再撤销；
+/// 覆盖前的快照同时压入撤销栈，与 `edit_message_content` 一样可通过 `undo_last_operation` 撤销。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `message_uuid` - 消息的 UUID
+/// - `version_id` - 历史版本 ID（来自 `get_message_edit_history`）
 /// - `cache` - Tauri managed state，内存缓存
 ///
+/// # 返回值
+/// 返回回滚后重新转换的 TransformedSession
+///
 /// # 错误
-/// 路径验证失败、备份失败或文件删除失败时返回错误
+/// 找不到历史版本或当前消息、文件读写失败时返回错误
 #[tauri::command]
-pub async fn delete_session(
+pub async fn revert_message_to_version(
     session_file_path: String,
+    message_uuid: String,
+    version_id: String,
     cache: State<'_, AppCache>,
-) -> Result<(), String> {
-    // 通过 file_guard 安全删除（含路径验证 + 双重备份）
-    file_guard::safe_delete_file(&session_file_path, "delete_session", &cache).await?;
+) -> Result<TransformedSession, String> {
+    command_trace::traced(
+        "revert_message_to_version",
+        command_trace::path_arg(&session_file_path),
+        revert_message_to_version_inner(session_file_path, message_uuid, version_id, cache),
+    )
+    .await
+}
 
-    // 清除相关缓存
-    cache.invalidate_session(&session_file_path);
-    cache.invalidate_projects();
+/// `revert_message_to_version` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn revert_message_to_version_inner(
+    session_file_path: String,
+    message_uuid: String,
+    version_id: String,
+    cache: State<'_, AppCache>,
+) -> Result<TransformedSession, String> {
+    validated_session_path(&session_file_path)?;
+    let _guard = cache.lock_session(&session_file_path).await;
 
-    Ok(())
+    let version = edit_history::find_version(&session_file_path, &message_uuid, &version_id)
+        .await?
+        .ok_or_else(|| format!("未找到历史版本: {}", version_id))?;
+
+    let mut messages = parser::read_messages_for_write(&session_file_path).await?;
+    let snapshot = messages.clone();
+    let target = messages
+        .iter_mut()
+        .find(|msg| msg.get("uuid").and_then(|v| v.as_str()) == Some(message_uuid.as_str()))
+        .ok_or_else(|| format!("未找到消息: {}", message_uuid))?;
+
+    if *target != version {
+        let previous = std::mem::replace(target, version);
+        parser::write_messages(&session_file_path, &messages, "revert_message", &cache).await?;
+        cache.push_undo(&session_file_path, "编辑 1 条消息".to_string(), snapshot);
+        record_edit_history(&session_file_path, &previous).await;
+    }
+
+    let (transformed, search_texts, original_texts) = transformer::transform_session(&messages);
+    cache.set_session(&session_file_path, transformed.clone(), search_texts, original_texts);
+
+    Ok(transformed)
 }
 
-/// 在缓存中搜索会话消息
+/// 在编辑写入成功后记录消息的旧版本
 ///
-/// 在 Rust 端搜索预计算的搜索文本，仅返回匹配的 display_id 列表，
-/// 避免大量文本通过 IPC 传输。
+/// 此时修改已经落盘，记录失败只记日志，不让已成功的编辑报错。
+async fn record_edit_history(session_file_path: &str, previous: &Value) {
+    if let Err(e) = edit_history::record(session_file_path, previous).await {
+        log::warn!("记录消息编辑历史失败: {}", e);
+    }
+}
+
+/// 新消息的插入位置
 ///
-/// 支持 4 种搜索模式（由 `case_sensitive` 和 `use_regex` 组合决定）：
-/// - 字面量 + 大小写不敏感（默认）：memchr SIMD 加速，在小写化 search_texts 上匹配
-/// - 字面量 + 大小写敏感：memchr SIMD 加速，在 original_texts 上精确匹配
-/// - 正则 + 大小写不敏感：`(?i)pattern` 正则，在 original_texts 上匹配
-/// - 正则 + 大小写敏感：`pattern` 正则，在 original_texts 上匹配
+/// 由前端显式指定开头 / 某条消息之后 / 末尾，不再用空字符串兼表两种含义。
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum InsertPosition {
+    /// 会话开头：新消息成为根消息，原来的第一条根消息改为接在它之后
+    Start,
+    /// 指定 UUID 的消息之后
+    After {
+        /// 前一条消息的 UUID
+        uuid: String,
+    },
+    /// 会话末尾：接在最后一条带 uuid 的消息之后
+    End,
+}
+
+/// 在指定位置插入一条新的对话消息
 ///
-/// 如果缓存中没有该会话的数据，会先加载并缓存。
+/// 由后端按 Claude Code JSONL 结构构造 user / assistant 消息，插入到 `position` 指定的位置。
+/// 构造规则见 `build_conversation_message`，parentUuid 链的修正见 `link_inserted`。
+/// 插入前的快照压入撤销栈。
 ///
 /// # 参数
 /// - `session_file_path` - 会话 JSONL 文件的绝对路径
-/// - `query` - 搜索查询词
-/// - `case_sensitive` - 是否大小写敏感
-/// - `use_regex` - 是否使用正则表达式模式
+/// - `position` - 插入位置
+/// - `role` - 消息角色："user" 或 "assistant"
+/// - `text` - 消息文本
 /// - `cache` - Tauri managed state，内存缓存
 ///
 /// # 返回值
-/// 返回匹配的 display_id 字符串列表
+/// 返回插入后重新转换的 TransformedSession
 ///
 /// # 错误
-/// - 会话数据加载失败时返回错误
-/// - 正则表达式编译失败时返回错误（包含无效 pattern 详情）
+/// - 角色不支持或文本为空时返回错误
+/// - `position` 指定的 UUID 未找到时返回错误
+/// - 文件读写失败时返回错误
 #[tauri::command]
-pub async fn search_session(
+pub async fn insert_message(
     session_file_path: String,
-    query: String,
-    case_sensitive: bool,
-    use_regex: bool,
+    position: InsertPosition,
+    role: String,
+    text: String,
     cache: State<'_, AppCache>,
-) -> Result<Vec<String>, String> {
-    // 空查询返回空结果（trim 后判断，避免纯空白字符查询）
-    if query.trim().is_empty() {
-        return Ok(vec![]);
-    }
+) -> Result<TransformedSession, String> {
+    command_trace::traced(
+        "insert_message",
+        format!("{} {}", command_trace::path_arg(&session_file_path), role),
+        insert_message_inner(session_file_path, position, role, text, cache),
+    )
+    .await
+}
 
-    // 确保缓存中有数据（缓存预热）
-    if cache.get_session(&session_file_path).is_none() {
-        let messages = parser::read_messages(&session_file_path).await?;
-        let (transformed, search_texts, original_texts) = transformer::transform_session(&messages);
-        cache.set_session(&session_file_path, transformed, search_texts, original_texts);
-    }
+/// `insert_message` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn insert_message_inner(
+    session_file_path: String,
+    position: InsertPosition,
+    role: String,
+    text: String,
+    cache: State<'_, AppCache>,
+) -> Result<TransformedSession, String> {
+    validated_session_path(&session_file_path)?;
+    let _guard = cache.lock_session(&session_file_path).await;
 
-    // 在缓存中执行搜索，处理 Err（正则编译失败）和 None（缓存未命中）两种失败情形
-    cache
-        .search_in_cache(&session_file_path, &query, case_sensitive, use_regex)
-        .map_err(|e| e)?          // 将正则错误直接传递给前端
-        .ok_or_else(|| "会话未在缓存中找到".into())
+    let mut messages = parser::read_messages_for_write(&session_file_path).await?;
+    let snapshot = messages.clone();
+    let session_id = Path::new(&session_file_path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    insert_conversation_message(&mut messages, &position, &role, &text, &session_id)?;
+
+    parser::write_messages(&session_file_path, &messages, "insert_message", &cache).await?;
+    cache.push_undo(&session_file_path, "插入 1 条消息".to_string(), snapshot);
+
+    let (transformed, search_texts, original_texts) = transformer::transform_session(&messages);
+    cache.set_session(&session_file_path, transformed.clone(), search_texts, original_texts);
+
+    Ok(transformed)
 }
 
-/// 导出会话为 Markdown 或 JSON 格式
+/// 在指定位置插入一条前端构造好的原始消息
 ///
-/// 从文件直接读取原始消息数据进行导出，不经过 transformer。
+/// 用于插入 custom-title、tag、file-history-snapshot 等非对话条目；
+/// user / assistant 消息必须走 `insert_message`，由后端构造。
+/// 新消息带 uuid 时，其 parentUuid 由后端按 `position` 设置并修正后继链。
+/// 操作完成后重新 transform 并更新缓存，返回新的 TransformedSession；插入前的快照压入撤销栈。
 ///
 /// # 参数
 /// - `session_file_path` - 会话 JSONL 文件的绝对路径
-/// - `session_name` - 会话名称（用于 Markdown 标题）
-/// - `format` - 导出格式："markdown" 或 "json"
+/// - `position` - 插入位置
+/// - `new_message` - 前端构造好的完整 SessionMessage JSON 对象
+/// - `cache` - Tauri managed state，内存缓存
 ///
 /// # 返回值
-/// 返回导出的字符串内容
+/// 返回插入后重新转换的 TransformedSession
 ///
 /// # 错误
-/// 文件读取失败或不支持的格式时返回错误
+/// - `new_message` 是 user / assistant 消息时返回错误
+/// - `position` 指定的 UUID 未找到时返回错误
+/// - 文件读写失败时返回错误
 #[tauri::command]
-pub async fn export_session(
+pub async fn insert_raw_message(
     session_file_path: String,
-    session_name: String,
-    format: String,
-) -> Result<String, String> {
-    let messages = parser::read_messages(&session_file_path).await?;
-    match format.as_str() {
-        "markdown" => Ok(export::to_markdown(&messages, &session_name)),
-        "json" => Ok(export::to_json(&messages)),
-        _ => Err(format!("不支持的导出格式: {}", format)),
+    position: InsertPosition,
+    new_message: Value,
+    cache: State<'_, AppCache>,
+) -> Result<TransformedSession, String> {
+    command_trace::traced(
+        "insert_raw_message",
+        command_trace::path_arg(&session_file_path),
+        insert_raw_message_inner(session_file_path, position, new_message, cache),
+    )
+    .await
+}
+
+/// `insert_raw_message` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn insert_raw_message_inner(
+    session_file_path: String,
+    position: InsertPosition,
+    new_message: Value,
+    cache: State<'_, AppCache>,
+) -> Result<TransformedSession, String> {
+    validated_session_path(&session_file_path)?;
+    let _guard = cache.lock_session(&session_file_path).await;
+
+    // 从文件读取原始数据（插入前的快照写回后压入撤销栈）
+    let mut messages = parser::read_messages_for_write(&session_file_path).await?;
+    let snapshot = messages.clone();
+    insert_raw_entry(&mut messages, &position, new_message)?;
+
+    // 写回文件（通过 file_guard 安全写入）
+    parser::write_messages(&session_file_path, &messages, "insert_raw_message", &cache).await?;
+    cache.push_undo(&session_file_path, "插入 1 条消息".to_string(), snapshot);
+
+    // 重新 transform 并更新缓存
+    let (transformed, search_texts, original_texts) = transformer::transform_session(&messages);
+    cache.set_session(&session_file_path, transformed.clone(), search_texts, original_texts);
+
+    Ok(transformed)
+}
+
+/// 把原始条目插入到 `position`（`insert_raw_message` 的纯数据部分）
+///
+/// # 错误
+/// 条目是 user / assistant 消息或 `position` 指定的 UUID 未找到时返回错误
+fn insert_raw_entry(messages: &mut Vec<Value>, position: &InsertPosition, new_message: Value) -> Result<(), String> {
+    if let Some(kind @ ("user" | "assistant")) = new_message.get("type").and_then(|v| v.as_str()) {
+        return Err(format!("{} 消息请通过 insert_message 插入", kind));
+    }
+    let (insert_index, parent) = resolve_insert_position(messages, position)?;
+    messages.insert(insert_index, new_message);
+    link_inserted(messages, insert_index, parent.as_deref());
+    Ok(())
+}
+
+/// 新消息的插入位置：`after_uuid` 所在行之后；`after_uuid` 为空时为 `empty_index`
+///
+/// # 错误
+/// `after_uuid` 非空但未找到匹配消息时返回错误
+fn insertion_index(messages: &[Value], after_uuid: &str, empty_index: usize) -> Result<usize, String> {
+    if after_uuid.is_empty() {
+        return Ok(empty_index);
+    }
+    messages
+        .iter()
+        .position(|msg| msg.get("uuid").and_then(|v| v.as_str()) == Some(after_uuid))
+        .map(|idx| idx + 1)
+        .ok_or_else(|| format!("未找到 UUID 为 '{}' 的消息", after_uuid))
+}
+
+/// 把 `position` 解析为 `(插入下标, 新消息的 parentUuid)`
+///
+/// - `Start`：下标 0，无父消息
+/// - `After`：该消息所在行之后，父消息为它
+/// - `End`：文件末尾，父消息为最后一条带 uuid 的消息（保持 resume 时的主链连续）
+///
+/// # 错误
+/// `After` 指定的 UUID 未找到时返回错误
+fn resolve_insert_position(messages: &[Value], position: &InsertPosition) -> Result<(usize, Option<String>), String> {
+    match position {
+        InsertPosition::Start => Ok((0, None)),
+        InsertPosition::After { uuid } => {
+            if uuid.is_empty() {
+                return Err("插入位置缺少 UUID".to_string());
+            }
+            Ok((insertion_index(messages, uuid, 0)?, Some(uuid.clone())))
+        }
+        InsertPosition::End => {
+            let parent = messages.iter().rev().find_map(|m| m.get("uuid").and_then(|v| v.as_str())).map(str::to_string);
+            Ok((messages.len(), parent))
+        }
+    }
+}
+
+/// 修正 `messages[index]` 处新插入消息前后的 parentUuid 链
+///
+/// 新消息没有 uuid（纯元数据条目）时不参与对话链，不做任何修改。否则：
+/// - 新消息的 parentUuid 设为 `parent`
+/// - 有父消息时，把原本指向父消息的第一条后继改为指向新消息
+/// - 插在开头时，把原来的第一条根消息改为指向新消息，使新消息成为唯一的根
+fn link_inserted(messages: &mut [Value], index: usize, parent: Option<&str>) {
+    let Some(new_uuid) = messages[index].get("uuid").and_then(|v| v.as_str()).map(str::to_string) else {
+        return;
+    };
+    if let Some(obj) = messages[index].as_object_mut() {
+        obj.insert("parentUuid".to_string(), parent.map_or(Value::Null, |p| Value::String(p.to_string())));
+    }
+    let following = &mut messages[index + 1..];
+    match parent {
+        Some(parent) => relink_first_child(following, parent, &new_uuid),
+        None => {
+            let root = following.iter_mut().find(|msg| {
+                msg.get("uuid").is_some_and(|v| v.is_string()) && msg.get("parentUuid").is_none_or(|v| v.is_null())
+            });
+            if let Some(obj) = root.and_then(|msg| msg.as_object_mut()) {
+                obj.insert("parentUuid".to_string(), Value::String(new_uuid));
+            }
+        }
+    }
+}
+
+/// 把 `following` 中第一条 parentUuid 指向 `old_parent` 的消息改为指向 `new_parent`
+///
+/// 只更新第一条匹配的（主链中的直接后继），避免影响分支（sidechain）。
+fn relink_first_child(following: &mut [Value], old_parent: &str, new_parent: &str) {
+    let child = following
+        .iter_mut()
+        .find(|msg| msg.get("parentUuid").and_then(|v| v.as_str()) == Some(old_parent));
+    if let Some(obj) = child.and_then(|msg| msg.as_object_mut()) {
+        obj.insert("parentUuid".to_string(), Value::String(new_parent.to_string()));
+    }
+}
+
+/// 构造 user / assistant 消息并插入到 `position`，修正前后的 parentUuid 链
+///
+/// 环境字段（cwd 等）取自插入点之前最近一条带 `cwd` 的消息；插在开头时取之后的第一条。
+///
+/// # 错误
+/// `position` 指定的 UUID 未找到、角色不支持或文本为空时返回错误
+fn insert_conversation_message(
+    messages: &mut Vec<Value>,
+    position: &InsertPosition,
+    role: &str,
+    text: &str,
+    session_id: &str,
+) -> Result<(), String> {
+    let (insert_index, parent) = resolve_insert_position(messages, position)?;
+    let context = messages[..insert_index]
+        .iter()
+        .rev()
+        .chain(&messages[insert_index..])
+        .find(|m| m.get("cwd").is_some());
+    let new_message = build_conversation_message(role, text, parent.as_deref(), context, session_id)?;
+    messages.insert(insert_index, new_message);
+    link_inserted(messages, insert_index, parent.as_deref());
+    Ok(())
+}
+
+/// 按 Claude Code JSONL 结构构造一条 user / assistant 消息
+///
+/// - 公共字段：`type`、`uuid`（新 v4）、`parentUuid`、`timestamp`（当前时间）、`sessionId`、`isSidechain: false`
+/// - `cwd`、`version`、`gitBranch`、`userType` 取自 `context`（插入点附近带 `cwd` 的消息），
+///   与 Claude Code 写入的普通消息一致，resume 时不会因缺字段而被过滤
+/// - user：`message: {role, content: 文本}`
+/// - assistant：`message` 含 `id`、`type: "message"`、`model: "<synthetic>"`、文本内容块、
+///   `stop_reason: "end_turn"` 与零用量（与 Claude Code 自身生成的合成消息相同）
+///
+/// # 参数
+/// - `role` - "user" 或 "assistant"
+/// - `text` - 消息文本
+/// - `parent` - parentUuid（None 表示根消息）
+/// - `context` - 继承环境字段的消息（None 表示不继承）
+/// - `session_id` - 会话 ID
+///
+/// # 错误
+/// 角色不支持或文本为空时返回错误
+fn build_conversation_message(
+    role: &str,
+    text: &str,
+    parent: Option<&str>,
+    context: Option<&Value>,
+    session_id: &str,
+) -> Result<Value, String> {
+    if text.trim().is_empty() {
+        return Err("消息内容不能为空".to_string());
+    }
+    let message = match role {
+        "user" => serde_json::json!({ "role": "user", "content": text }),
+        "assistant" => serde_json::json!({
+            "id": format!("msg_{}", uuid::Uuid::new_v4().simple()),
+            "type": "message",
+            "role": "assistant",
+            "model": "<synthetic>",
+            "content": [{ "type": "text", "text": text }],
+            "stop_reason": "end_turn",
+            "stop_sequence": null,
+            "usage": { "input_tokens": 0, "output_tokens": 0 },
+        }),
+        _ => return Err(format!("不支持的消息角色: {}", role)),
+    };
+
+    let mut msg = serde_json::json!({
+        "parentUuid": parent,
+        "isSidechain": false,
+        "type": role,
+        "message": message,
+        "uuid": uuid::Uuid::new_v4().to_string(),
+        "timestamp": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        "sessionId": session_id,
+    });
+    if let Some(context) = context
+        && let Some(obj) = msg.as_object_mut()
+    {
+        for key in ["cwd", "version", "gitBranch", "userType"] {
+            if let Some(value) = context.get(key) {
+                obj.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+    Ok(msg)
+}
+
+/// 把选中的消息复制（或移动）到另一个会话
+///
+/// 从源文件取出指定 uuid 的完整原始行（按源文件顺序），为每条副本生成新的 uuid，
+/// `sessionId` 改为目标会话 ID，然后插入目标文件中 `insert_after_uuid` 之后。
+///
+/// ## 时间戳
+/// 原始时间戳落在插入点前后两条目标消息的时间之间（且按顺序递增）时原样保留；
+/// 否则在两者之间均匀插值（插到末尾时从前一条起逐条递增 1 毫秒，插到开头时反之），
+/// 使目标会话的时间顺序与文件顺序一致。
+///
+/// ## parentUuid 链
+/// 副本依次串成一条链：首条指向 `insert_after_uuid`（插入到开头时为 null），之后每条指向前一条；
+/// 目标会话中原本紧跟在插入点后的消息改为指向最后一条副本。
+/// 移动模式下源会话按 `delete_messages` 的规则删除并修复链。
+///
+/// 两个文件都经 file_guard 备份后写回，并清除两个会话的缓存；写回全部成功后，
+/// 把目标会话（移动模式下还有源会话）修改前的快照压入各自的撤销栈。移动模式下源会话写回失败时
+/// 把目标会话恢复为插入前的内容，不会留下两边都有这些消息的状态。
+///
+/// # 参数
+/// - `source_path` - 源会话 JSONL 文件的绝对路径
+/// - `message_uuids` - 要复制的消息 UUID 列表
+/// - `target_path` - 目标会话 JSONL 文件的绝对路径
+/// - `insert_after_uuid` - 插入到目标会话中此 UUID 消息之后（空字符串表示插入到开头）
+/// - `move` - 为 true 时同时从源会话删除这些消息（移动模式）
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 返回复制条数与副本的新 uuid（按插入顺序）
+///
+/// # 错误
+/// 源与目标相同、消息或插入点不存在、文件读写失败时返回错误
+#[tauri::command]
+pub async fn copy_messages_to_session(
+    source_path: String,
+    message_uuids: Vec<String>,
+    target_path: String,
+    insert_after_uuid: String,
+    r#move: bool,
+    cache: State<'_, AppCache>,
+) -> Result<CopyMessagesResult, String> {
+    let args = format!("{} -> {} count={}", command_trace::path_arg(&source_path), command_trace::path_arg(&target_path), message_uuids.len());
+    command_trace::traced(
+        "copy_messages_to_session",
+        args,
+        audit::with_batch(copy_messages_to_session_inner(source_path, message_uuids, target_path, insert_after_uuid, r#move, cache)),
+    )
+    .await
+}
+
+/// `copy_messages_to_session` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn copy_messages_to_session_inner(
+    source_path: String,
+    message_uuids: Vec<String>,
+    target_path: String,
+    insert_after_uuid: String,
+    r#move: bool,
+    cache: State<'_, AppCache>,
+) -> Result<CopyMessagesResult, String> {
+    validated_session_path(&source_path)?;
+    validated_session_path(&target_path)?;
+    if source_path == target_path {
+        return Err("源会话与目标会话不能相同".to_string());
+    }
+    // 按路径顺序获取两个会话的写锁，避免相向复制时死锁
+    let (first, second) = if source_path < target_path { (&source_path, &target_path) } else { (&target_path, &source_path) };
+    let _first_guard = cache.lock_session(first).await;
+    let _second_guard = cache.lock_session(second).await;
+
+    let source = if r#move {
+        parser::read_messages_for_write(&source_path).await?
+    } else {
+        parser::read_messages(&source_path).await?
+    };
+    let uuid_set: HashSet<&str> = message_uuids.iter().map(|s| s.as_str()).collect();
+    let selected: Vec<Value> = source
+        .iter()
+        .filter(|msg| msg.get("uuid").and_then(|v| v.as_str()).is_some_and(|u| uuid_set.contains(u)))
+        .cloned()
+        .collect();
+    if selected.is_empty() || selected.len() != uuid_set.len() {
+        return Err("部分消息在源会话中不存在，可能已被删除".to_string());
+    }
+
+    let target = parser::read_messages_for_write(&target_path).await?;
+    let original_target = target.clone();
+    let session_id = Path::new(&target_path).file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let (updated, new_uuids) = insert_copies(target, selected, &insert_after_uuid, session_id)?;
+    parser::write_messages(&target_path, &updated, "copy_messages_to_session", &cache).await?;
+    cache.invalidate_session(&target_path);
+
+    if r#move {
+        let original_source = source.clone();
+        let remaining = remove_and_relink(source, &uuid_set);
+        if let Err(e) = parser::write_messages(&source_path, &remaining, "copy_messages_to_session", &cache).await {
+            // 源会话未能删除这些消息：撤回目标会话中的副本，移动要么完成要么不发生
+            let rollback = parser::write_messages(&target_path, &original_target, "copy_messages_to_session_rollback", &cache).await;
+            cache.invalidate_session(&target_path);
+            return Err(match rollback {
+                Ok(_) => {
+                    // 目标会话已恢复为插入前的内容，原有撤销步骤仍然有效，只需更新栈顶记录的 mtime
+                    cache.finish_undo(&target_path);
+                    format!("从源会话移除消息失败，已撤回复制: {}", e)
+                }
+                Err(rollback_error) => {
+                    cache.clear_undo(&target_path, "移动消息");
+                    format!(
+                        "从源会话移除消息失败: {}；撤回复制也失败: {}（插入前的目标会话可从备份恢复）",
+                        e, rollback_error
+                    )
+                }
+            });
+        }
+        cache.invalidate_session(&source_path);
+        cache.push_undo(&target_path, format!("移入 {} 条消息", new_uuids.len()), original_target);
+        cache.push_undo(&source_path, format!("移出 {} 条消息", new_uuids.len()), original_source);
+    } else {
+        cache.push_undo(&target_path, format!("复制 {} 条消息", new_uuids.len()), original_target);
+    }
+
+    Ok(CopyMessagesResult {
+        copied: new_uuids.len(),
+        new_uuids,
+    })
+}
+
+/// 为选中的消息生成副本并插入目标会话（`copy_messages_to_session` 的纯数据部分）
+///
+/// # 参数
+/// - `target` - 目标会话的全部原始消息
+/// - `selected` - 按源文件顺序排列的待复制消息
+/// - `after_uuid` - 插入到此 UUID 消息之后（空字符串表示插入到开头）
+/// - `session_id` - 目标会话 ID，写入副本的 `sessionId`
+///
+/// # 返回值
+/// `(插入后的消息列表, 副本的新 uuid)`
+///
+/// # 错误
+/// `after_uuid` 非空但未找到匹配消息时返回错误
+fn insert_copies(mut target: Vec<Value>, selected: Vec<Value>, after_uuid: &str, session_id: &str) -> Result<(Vec<Value>, Vec<String>), String> {
+    let insert_index = insertion_index(&target, after_uuid, 0)?;
+    let timestamps = copy_timestamps(&target[..insert_index], &target[insert_index..], &selected);
+
+    let mut parent = if after_uuid.is_empty() { Value::Null } else { Value::String(after_uuid.to_string()) };
+    let mut new_uuids = Vec::with_capacity(selected.len());
+    let copies: Vec<Value> = selected
+        .into_iter()
+        .enumerate()
+        .map(|(i, mut msg)| {
+            let new_uuid = uuid::Uuid::new_v4().to_string();
+            if let Some(obj) = msg.as_object_mut() {
+                obj.insert("uuid".to_string(), Value::String(new_uuid.clone()));
+                obj.insert("parentUuid".to_string(), std::mem::replace(&mut parent, Value::String(new_uuid.clone())));
+                if let Some(timestamp) = timestamps.as_ref().and_then(|list| list.get(i)) {
+                    obj.insert("timestamp".to_string(), Value::String(timestamp.clone()));
+                }
+                if obj.contains_key("sessionId") {
+                    obj.insert("sessionId".to_string(), Value::String(session_id.to_string()));
+                }
+            }
+            new_uuids.push(new_uuid);
+            msg
+        })
+        .collect();
+
+    // 插入点之后原本指向 after_uuid 的第一条消息改为指向最后一条副本（与 insert_message 相同，不影响分支）
+    if !after_uuid.is_empty() && let Some(last_uuid) = new_uuids.last() {
+        relink_first_child(&mut target[insert_index..], after_uuid, last_uuid);
+    }
+
+    target.splice(insert_index..insert_index, copies);
+    Ok((target, new_uuids))
+}
+
+/// 计算副本的时间戳（规则见 `copy_messages_to_session`）
+///
+/// # 参数
+/// - `before` - 目标会话中插入点之前的消息
+/// - `after` - 目标会话中插入点之后的消息
+/// - `selected` - 待复制的消息（按源文件顺序）
+///
+/// # 返回值
+/// None 表示保留原始时间戳；否则为每条副本的新时间戳
+fn copy_timestamps(before: &[Value], after: &[Value], selected: &[Value]) -> Option<Vec<String>> {
+    let millis_of = |msg: &Value| msg.get("timestamp").and_then(|v| v.as_str()).and_then(time::parse_iso8601_millis);
+    let lower = before.iter().rev().find_map(millis_of);
+    let upper = after.iter().find_map(millis_of);
+
+    let originals: Option<Vec<i64>> = selected.iter().map(millis_of).collect();
+    if let Some(originals) = &originals {
+        let ordered = originals.windows(2).all(|pair| pair[0] <= pair[1]);
+        let within = originals.first().zip(originals.last()).is_some_and(|(first, last)| {
+            lower.is_none_or(|lower| *first >= lower) && upper.is_none_or(|upper| *last <= upper)
+        });
+        if ordered && within {
+            return None;
+        }
+    }
+
+    let count = selected.len() as i64;
+    let millis: Vec<i64> = match (lower, upper) {
+        (Some(lower), Some(upper)) if upper >= lower => {
+            (1..=count).map(|i| lower + (upper - lower) * i / (count + 1)).collect()
+        }
+        (Some(lower), _) => (1..=count).map(|i| lower + i).collect(),
+        (None, Some(upper)) => (0..count).map(|i| upper - (count - i)).collect(),
+        (None, None) => {
+            let now = chrono::Utc::now().timestamp_millis();
+            (0..count).map(|i| now + i).collect()
+        }
+    };
+    Some(
+        millis
+            .into_iter()
+            .filter_map(chrono::DateTime::from_timestamp_millis)
+            .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+            .collect(),
+    )
+}
+
+/// 将外部 JSONL 片段追加到会话末尾
+///
+/// 用于把他人贴来的报错片段导入本地会话复现。输入按行严格解析，
+/// 任一坏行都会中止操作并在错误中指出行号。每条消息：
+/// - 缺少 `uuid`（或为空字符串）时补齐新的 v4 UUID
+/// - `sessionId` 统一改写为目标会话 ID（即会话文件名）
+///
+/// 追加通过「读取全量 → 追加 → 写回」实现，写回经过 `file_guard`，
+/// 原文件同样会先被备份。追加前的快照压入撤销栈。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `raw_text` - 要追加的 JSONL 文本
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 新增条数与追加后重新转换的 TransformedSession
+///
+/// # 错误
+/// - 输入中存在坏行或没有任何消息时返回错误
+/// - 文件读写失败时返回错误
+#[tauri::command]
+pub async fn append_raw_lines(
+    session_file_path: String,
+    raw_text: String,
+    cache: State<'_, AppCache>,
+) -> Result<AppendLinesResult, String> {
+    command_trace::traced(
+        "append_raw_lines",
+        command_trace::path_arg(&session_file_path),
+        append_raw_lines_inner(session_file_path, raw_text, cache),
+    )
+    .await
+}
+
+/// `append_raw_lines` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn append_raw_lines_inner(
+    session_file_path: String,
+    raw_text: String,
+    cache: State<'_, AppCache>,
+) -> Result<AppendLinesResult, String> {
+    validated_session_path(&session_file_path)?;
+    let mut new_messages = parser::parse_jsonl_strict(&raw_text)?;
+    if new_messages.is_empty() {
+        return Err("没有可追加的消息".to_string());
+    }
+
+    let session_id = std::path::Path::new(&session_file_path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string();
+
+    for msg in new_messages.iter_mut() {
+        if let Some(obj) = msg.as_object_mut() {
+            let has_uuid = obj
+                .get("uuid")
+                .and_then(|v| v.as_str())
+                .is_some_and(|uuid| !uuid.is_empty());
+            if !has_uuid {
+                obj.insert(
+                    "uuid".to_string(),
+                    Value::String(uuid::Uuid::new_v4().to_string()),
+                );
+            }
+            obj.insert("sessionId".to_string(), Value::String(session_id.clone()));
+        }
+    }
+
+    let _guard = cache.lock_session(&session_file_path).await;
+
+    let mut messages = parser::read_messages_for_write(&session_file_path).await?;
+    let snapshot = messages.clone();
+    let appended = new_messages.len();
+    messages.extend(new_messages);
+
+    // 写回文件（通过 file_guard 安全写入）
+    parser::write_messages(&session_file_path, &messages, "append_raw_lines", &cache).await?;
+    cache.push_undo(&session_file_path, format!("追加 {} 条消息", appended), snapshot);
+
+    // 重新 transform 并更新缓存
+    let (transformed, search_texts, original_texts) = transformer::transform_session(&messages);
+    cache.set_session(&session_file_path, transformed.clone(), search_texts, original_texts);
+
+    Ok(AppendLinesResult {
+        appended,
+        session: transformed,
+    })
+}
+
+/// 删除指定的会话文件
+///
+/// 从文件系统中永久移除会话的 JSONL 文件。
+/// 删除前通过 `file_guard` 自动创建临时备份（强制）和主动备份（可选），
+/// 确保用户在应用关闭前可以反悔恢复。删除成功后经 `session_cleanup::forget_session`
+/// 一并清除该会话的 CCR 别名、查看记录、阅读状态、草稿与缓存。
+///
+/// # 参数
+/// - `session_file_path` - 要删除的会话 JSONL 文件的绝对路径
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 错误
+/// 路径验证失败、备份失败或文件删除失败时返回错误
+#[tauri::command]
+pub async fn delete_session(
+    session_file_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<(), String> {
+    validated_session_path(&session_file_path)?;
+    let _guard = cache.lock_session(&session_file_path).await;
+
+    // 通过 file_guard 安全删除（含路径验证 + 双重备份）
+    file_guard::safe_delete_file(&session_file_path, "delete_session", &cache).await?;
+    session_cleanup::forget_session(&session_file_path, &cache).await;
+
+    Ok(())
+}
+
+/// 归档导出并删除会话
+///
+/// 依次把会话导出到归档目录（每种格式一个文件，文件名带日期与预览摘要）、校验导出文件存在且非空、
+/// 再经 `file_guard` 安全删除原文件；任何一步失败都中止且不删除。流程细节见 `services::session_archive`。
+/// 批量清理时前端逐个会话调用，每个会话独立成败。
+///
+/// # 参数
+/// - `session_file_path` - 要归档删除的会话 JSONL 文件的绝对路径
+/// - `archive_dir` - 归档目录（不能位于 Claude 数据目录内，不存在时自动创建）
+/// - `formats` - 导出格式列表："markdown" 和/或 "json"
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 导出文件的绝对路径列表
+///
+/// # 错误
+/// 路径验证失败、归档目录位于 Claude 数据目录内、格式无效、导出或校验失败、删除失败时返回错误
+#[tauri::command]
+pub async fn archive_and_delete_session(
+    session_file_path: String,
+    archive_dir: String,
+    formats: Vec<String>,
+    cache: State<'_, AppCache>,
+) -> Result<Vec<String>, String> {
+    command_trace::traced(
+        "archive_and_delete_session",
+        format!("{} {}", command_trace::path_arg(&session_file_path), formats.join(",")),
+        audit::with_batch(archive_and_delete_session_inner(session_file_path, archive_dir, formats, cache)),
+    )
+    .await
+}
+
+/// `archive_and_delete_session` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn archive_and_delete_session_inner(
+    session_file_path: String,
+    archive_dir: String,
+    formats: Vec<String>,
+    cache: State<'_, AppCache>,
+) -> Result<Vec<String>, String> {
+    validated_session_path(&session_file_path)?;
+    if file_guard::is_within_data_sources(&archive_dir)? {
+        return Err("归档目录不能位于 Claude 数据目录内".to_string());
+    }
+    session_archive::archive_and_delete(&session_file_path, &archive_dir, &formats, &cache).await
+}
+
+/// 在缓存中搜索会话消息
+///
+/// 在 Rust 端搜索预计算的搜索文本，返回命中消息的 display_id、匹配次数与前几个匹配的
+/// 位置和片段，避免大量文本通过 IPC 传输，前端高亮也无需再扫描全文。
+///
+/// 支持 4 种搜索模式（由 `case_sensitive` 和 `use_regex` 组合决定）：
+/// - 字面量 + 大小写不敏感（默认）：memchr SIMD 加速，在小写化 search_texts 上匹配
+/// - 字面量 + 大小写敏感：memchr SIMD 加速，在 original_texts 上精确匹配
+/// - 正则 + 大小写不敏感：`(?i)pattern` 正则，在 original_texts 上匹配
+/// - 正则 + 大小写敏感：`pattern` 正则，在 original_texts 上匹配
+///
+/// 查询写成 `tool:bash`、`model:opus`、`cwd:src` 时按字段限定匹配元数据（工具名 / 模型 / 工作目录）。
+///
+/// 如果缓存中没有该会话的数据，会先加载并缓存。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `query` - 搜索查询词
+/// - `case_sensitive` - 是否大小写敏感
+/// - `use_regex` - 是否使用正则表达式模式
+/// - `filter` - 消息类型与时间范围过滤（见 `SessionSearchFilter`）：只搜索 `types` 中的 `display_type`，
+///   以及 `after` / `before`（ISO 8601，均含边界）之间的消息，时间戳无法解析的消息不计入；缺省表示不过滤
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 按消息顺序的命中列表（见 `SearchHit`）
+///
+/// # 错误
+/// - 会话数据加载失败时返回错误
+/// - 正则表达式编译失败时返回错误（包含无效 pattern 详情）
+/// - `after` / `before` 不是合法的 ISO 8601 时间时返回错误
+#[tauri::command]
+pub async fn search_session(
+    session_file_path: String,
+    query: String,
+    case_sensitive: bool,
+    use_regex: bool,
+    filter: Option<SessionSearchFilter>,
+    cache: State<'_, AppCache>,
+) -> Result<Vec<SearchHit>, String> {
+    command_trace::traced(
+        "search_session",
+        command_trace::path_arg(&session_file_path),
+        search_session_inner(session_file_path, query, case_sensitive, use_regex, filter.unwrap_or_default(), cache),
+    )
+    .await
+}
+
+/// `search_session` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn search_session_inner(
+    session_file_path: String,
+    query: String,
+    case_sensitive: bool,
+    use_regex: bool,
+    filter: SessionSearchFilter,
+    cache: State<'_, AppCache>,
+) -> Result<Vec<SearchHit>, String> {
+    validated_session_path(&session_file_path)?;
+    // 空查询返回空结果（trim 后判断，避免纯空白字符查询）
+    if query.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    // 确保缓存中有数据（缓存预热）
+    if cache.get_session(&session_file_path).is_none() {
+        let messages = parser::read_messages(&session_file_path).await?;
+        let (transformed, search_texts, original_texts) = transformer::transform_session(&messages);
+        cache.set_session(&session_file_path, transformed, search_texts, original_texts);
+    }
+
+    // 在缓存中执行搜索，处理 Err（正则编译失败）和 None（缓存未命中）两种失败情形
+    cache
+        .search_in_cache(&session_file_path, &query, case_sensitive, use_regex, &filter)
+        .map_err(|e| e)?          // 将正则错误直接传递给前端
+        .ok_or_else(|| "会话未在缓存中找到".into())
+}
+
+/// 在所有会话中搜索
+///
+/// 并行读取会话文件并转换后匹配（见 `services::global_search`），不占用会话 LRU 缓存；
+/// 已在缓存中的会话直接复用缓存的搜索文本。
+/// 默认排除 system 类型的命中（system-reminder 等注入文本），可通过 `filter.include_system` 关闭。
+/// 整个搜索持有一个全局 I/O 许可。
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径
+/// - `query` - 查询词（字面量或正则表达式）
+/// - `case_sensitive` - 是否大小写敏感
+/// - `filter` - 项目 / 时间范围 / 消息类型过滤器，未传时只启用噪音过滤
+/// - `options` - 正则模式、搜索 ID（用于 `cancel_global_search`）与超时
+/// - `cache` - Tauri managed state，全局缓存实例
+/// - `limits` - Tauri managed state，全局 I/O 并发信号量
+///
+/// # 返回值
+/// 按匹配次数降序的命中列表（最多 `MAX_GLOBAL_SEARCH_HITS` 条）；被取消或超时时为部分结果
+///
+/// # 错误
+/// 时间参数或正则表达式无效、项目目录无法读取时返回错误
+#[tauri::command]
+pub async fn search_all_sessions(
+    claude_path: String,
+    query: String,
+    case_sensitive: bool,
+    filter: Option<GlobalSearchFilter>,
+    options: Option<GlobalSearchOptions>,
+    cache: State<'_, AppCache>,
+    limits: State<'_, AppLimits>,
+) -> Result<GlobalSearchResult, String> {
+    command_trace::traced(
+        "search_all_sessions",
+        format!("query_len={}", query.chars().count()),
+        search_all_sessions_inner(claude_path, query, case_sensitive, filter, options, cache, limits),
+    )
+    .await
+}
+
+/// `search_all_sessions` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn search_all_sessions_inner(
+    claude_path: String,
+    query: String,
+    case_sensitive: bool,
+    filter: Option<GlobalSearchFilter>,
+    options: Option<GlobalSearchOptions>,
+    cache: State<'_, AppCache>,
+    limits: State<'_, AppLimits>,
+) -> Result<GlobalSearchResult, String> {
+    let _permit = limits.acquire_io().await;
+    global_search::search_all_sessions(
+        &cache,
+        &claude_path,
+        &query,
+        case_sensitive,
+        &filter.unwrap_or_default(),
+        &options.unwrap_or_default(),
+    )
+    .await
+}
+
+/// 取消进行中的全局搜索
+///
+/// 被取消的 `search_all_sessions` 返回已收集到的部分结果（`cancelled` 为 true）。
+///
+/// # 参数
+/// - `search_id` - 发起搜索时传入的 `options.search_id`
+///
+/// # 返回值
+/// 找到对应的进行中搜索时返回 true
+#[tauri::command]
+pub async fn cancel_global_search(search_id: String) -> Result<bool, String> {
+    Ok(global_search::cancel_search(&search_id))
+}
+
+/// 导出会话为 Markdown 或 JSON 格式
+///
+/// 从文件直接读取原始消息数据进行导出，不经过 transformer。
+/// 传入 `filter` 时只导出搜索命中的消息：在缓存上执行与 `search_session` 相同的匹配，
+/// 由后端把命中的 display_id 映射回原始消息 uuid，前端无需自行映射。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `session_name` - 会话名称（用于 Markdown 标题）
+/// - `format` - 导出格式："markdown" 或 "json"
+/// - `options` - 导出选项（时间显示方式与文案语言），未传时使用默认值；JSON 格式保留原始数据，不受影响
+/// - `filter` - 按搜索结果导出的过滤条件，未传时导出整个会话
+/// - `cache` - Tauri managed state，按搜索结果导出时用于匹配
+/// - `limits` - Tauri managed state，全局 I/O 并发信号量
+///
+/// # 返回值
+/// 返回导出的字符串内容
+///
+/// # 错误
+/// 文件读取失败、不支持的格式、正则表达式无效或没有命中的消息时返回错误
+#[tauri::command]
+pub async fn export_session(
+    session_file_path: String,
+    session_name: String,
+    format: String,
+    options: Option<ExportOptions>,
+    filter: Option<ExportFilter>,
+    cache: State<'_, AppCache>,
+    limits: State<'_, AppLimits>,
+) -> Result<String, String> {
+    command_trace::traced(
+        "export_session",
+        format!("{} {}", command_trace::path_arg(&session_file_path), format),
+        export_session_inner(session_file_path, session_name, format, options, filter, cache, limits),
+    )
+    .await
+}
+
+/// `export_session` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn export_session_inner(
+    session_file_path: String,
+    session_name: String,
+    format: String,
+    options: Option<ExportOptions>,
+    filter: Option<ExportFilter>,
+    cache: State<'_, AppCache>,
+    limits: State<'_, AppLimits>,
+) -> Result<String, String> {
+    validated_session_path(&session_file_path)?;
+    let _permit = limits.acquire_io().await;
+    let messages = parser::read_messages(&session_file_path).await?;
+    let messages = match filter {
+        Some(filter) => filter_by_search(&session_file_path, messages, &filter, &cache)?,
+        None => messages,
+    };
+    render_export(&messages, &session_name, &format, &options.unwrap_or_default())
+}
+
+/// 只保留搜索命中的消息（及可选的上下文），保持文件顺序
+///
+/// # 错误
+/// 正则表达式无效或没有命中的消息时返回错误
+fn filter_by_search(session_file_path: &str, messages: Vec<Value>, filter: &ExportFilter, cache: &AppCache) -> Result<Vec<Value>, String> {
+    if filter.query.trim().is_empty() {
+        return Err("搜索词不能为空".to_string());
+    }
+    // 缓存未命中时用已读取的消息预热，避免重复读取文件
+    if cache.get_session(session_file_path).is_none() {
+        let (transformed, search_texts, original_texts) = transformer::transform_session(&messages);
+        cache.set_session(session_file_path, transformed, search_texts, original_texts);
+    }
+
+    let context = usize::from(filter.include_context);
+    let uuids = cache
+        .search_in_cache(session_file_path, &filter.query, filter.case_sensitive, filter.use_regex, &SessionSearchFilter::default())?
+        .and_then(|hits| {
+            let display_ids: Vec<String> = hits.into_iter().map(|hit| hit.display_id).collect();
+            cache.display_source_uuids(session_file_path, &display_ids, context)
+        })
+        .ok_or("会话未在缓存中找到")?;
+    if uuids.is_empty() {
+        return Err("没有匹配的消息可导出".to_string());
+    }
+    Ok(export::select_messages(&messages, &uuids))
+}
+
+/// 导出会话并调起系统文件管理器，方便直接分享
+///
+/// 导出文件写入 TEMP 下的 `ccr-exports/`，随后按平台在文件管理器中定位该文件
+/// （macOS Finder / Windows 资源管理器 / Linux 所在目录），平台差异见 `services::share`。
+/// 调起失败时仍返回文件路径，由前端提示用户手动处理。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `format` - 导出格式："markdown" 或 "json"
+/// - `options` - 导出选项，未传时使用默认值
+/// - `cache` - Tauri managed state，用于取会话的显示名称作为标题与文件名
+/// - `limits` - Tauri managed state，全局 I/O 并发信号量
+///
+/// # 返回值
+/// 导出文件路径与是否已成功调起文件管理器
+///
+/// # 错误
+/// 会话读取失败、不支持的格式或导出文件写入失败时返回错误
+#[tauri::command]
+pub async fn export_and_share(
+    session_file_path: String,
+    format: String,
+    options: Option<ExportOptions>,
+    cache: State<'_, AppCache>,
+    limits: State<'_, AppLimits>,
+) -> Result<ShareResult, String> {
+    command_trace::traced(
+        "export_and_share",
+        format!("{} {}", command_trace::path_arg(&session_file_path), format),
+        export_and_share_inner(session_file_path, format, options, cache, limits),
+    )
+    .await
+}
+
+/// `export_and_share` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn export_and_share_inner(
+    session_file_path: String,
+    format: String,
+    options: Option<ExportOptions>,
+    cache: State<'_, AppCache>,
+    limits: State<'_, AppLimits>,
+) -> Result<ShareResult, String> {
+    validated_session_path(&session_file_path)?;
+    let _permit = limits.acquire_io().await;
+    let messages = parser::read_messages(&session_file_path).await?;
+
+    // 标题与文件名：CCR 别名 > 原生标题 > 会话 ID
+    let session_name = cache
+        .get_cached_session(&session_file_path)
+        .and_then(|s| s.alias.or(s.name))
+        .unwrap_or_else(|| command_trace::path_arg(&session_file_path).trim_end_matches(".jsonl").to_string());
+    let content = render_export(&messages, &session_name, &format, &options.unwrap_or_default())?;
+    let extension = if format == "json" { "json" } else { "md" };
+    share::write_and_share(&format!("{}.{}", session_name, extension), &content).await
+}
+
+/// 按格式渲染导出内容
+///
+/// # 错误
+/// 不支持的格式返回错误
+fn render_export(messages: &[Value], session_name: &str, format: &str, options: &ExportOptions) -> Result<String, String> {
+    match format {
+        "markdown" => Ok(export::to_markdown(messages, session_name, options)),
+        "json" => Ok(export::to_json(messages)),
+        _ => Err(format!("不支持的导出格式: {}", format)),
+    }
+}
+
+/// 根据会话名称生成安全的导出文件名
+///
+/// 会话名称来自用户输入或会话预览文本，可能含有冒号、表情、换行或超长内容，
+/// 直接用作保存对话框的默认文件名会在 Windows 上写入失败。
+///
+/// # 参数
+/// - `session_name` - 会话名称
+/// - `extension` - 扩展名（不含点，如 "md"）
+///
+/// # 返回值
+/// 清理后的 `<名称>.<扩展名>`，不超过 255 字节
+#[tauri::command]
+pub fn suggest_export_file_name(session_name: String, extension: String) -> String {
+    filename::sanitize_filename(
+        &format!("{}.{}", session_name, extension),
+        filename::MAX_FILENAME_BYTES,
+    )
+}
+
+/// 将单条消息格式化为 Markdown / 纯文本 / JSON 字符串
+///
+/// 基于缓存的 TransformedSession 定位 DisplayMessage（缓存未命中时先加载），
+/// 复用 `export::render_message` 渲染，工具调用与嵌套的 tool_result 内容完整展开。
+/// 前端只负责把返回值放进剪贴板。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `display_id` - 目标 DisplayMessage 的 display_id
+/// - `format` - 格式："markdown"、"plain" 或 "json"
+/// - `options` - 时间显示选项（仅 Markdown 标题中的时间受影响），未传时使用默认值
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 格式化后的字符串
+///
+/// # 错误
+/// 不支持的格式、文件读取失败或找不到该消息时返回错误
+#[tauri::command]
+pub async fn format_message(
+    session_file_path: String,
+    display_id: String,
+    format: String,
+    options: Option<ExportOptions>,
+    cache: State<'_, AppCache>,
+) -> Result<String, String> {
+    validated_session_path(&session_file_path)?;
+    let format = export::MessageFormat::parse(&format)?;
+    let session = load_session(&session_file_path, &cache).await?;
+    let dm = session
+        .display_messages
+        .iter()
+        .find(|dm| dm.display_id == display_id)
+        .ok_or_else(|| format!("未找到消息: {}", display_id))?;
+
+    let time = options
+        .unwrap_or_default()
+        .format_time(&dm.timestamp, export::now_millis());
+    Ok(export::render_message(
+        export::role_label(&dm.display_type),
+        &time,
+        &dm.content,
+        format,
+        true,
+    ))
+}
+
+/// 统计会话中出现过的 Claude Code 版本及各自条数
+///
+/// 从文件读取原始消息，按每条消息的 `version` 字段分组计数。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+///
+/// # 返回值
+/// 版本统计（版本按新到旧排列，附带无 version 字段的条目数）
+///
+/// # 错误
+/// 文件读取失败时返回错误
+#[tauri::command]
+pub async fn get_session_versions(
+    session_file_path: String,
+) -> Result<SessionVersionStats, String> {
+    validated_session_path(&session_file_path)?;
+    let messages = parser::read_messages(&session_file_path).await?;
+    Ok(compat::count_versions(&messages))
+}
+
+/// 获取会话统计
+///
+/// 基于缓存的 TransformedSession 计算，缓存未命中时先加载。
+/// 消息数与字数默认剔除噪音消息（压缩摘要、计划以外的系统注入）；Token 统计是真实消耗，始终全量计算。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `exclude_noise` - 消息数与字数是否剔除噪音消息，缺省为 true
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// Token 与工具调用汇总（含失败调用计数）、按工具名的调用次数与失败率、stop_reason 分布，
+/// 以及消息数与字数
+///
+/// # 错误
+/// 文件读取失败时返回错误
+#[tauri::command]
+pub async fn get_session_stats(
+    session_file_path: String,
+    exclude_noise: Option<bool>,
+    cache: State<'_, AppCache>,
+) -> Result<SessionStats, String> {
+    command_trace::traced(
+        "get_session_stats",
+        command_trace::path_arg(&session_file_path),
+        get_session_stats_inner(session_file_path, exclude_noise, cache),
+    )
+    .await
+}
+
+/// `get_session_stats` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn get_session_stats_inner(
+    session_file_path: String,
+    exclude_noise: Option<bool>,
+    cache: State<'_, AppCache>,
+) -> Result<SessionStats, String> {
+    validated_session_path(&session_file_path)?;
+    let session = load_session(&session_file_path, &cache).await?;
+    let (message_count, text_chars) = transformer::message_counts(&session, exclude_noise.unwrap_or(true));
+    Ok(SessionStats {
+        tools: transformer::tool_stats(&session),
+        stop_reasons: transformer::stop_reason_stats(&session),
+        token_stats: session.token_stats,
+        message_count,
+        text_chars,
+    })
+}
+
+/// 筛选会话中失败的工具调用
+///
+/// 返回带 `is_error` 的 tool_result 消息及发起对应调用的 assistant 消息的 display_id，
+/// 前端与类型筛选取交集实现「只看失败的调用」。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 按消息顺序排列的 display_id 列表
+///
+/// # 错误
+/// 文件读取失败时返回错误
+#[tauri::command]
+pub async fn get_failed_tool_calls(
+    session_file_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<Vec<String>, String> {
+    validated_session_path(&session_file_path)?;
+    let session = load_session(&session_file_path, &cache).await?;
+    Ok(transformer::failed_tool_display_ids(&session))
+}
+
+/// 提取会话中引用的所有 http/https 链接
+///
+/// 基于缓存的 TransformedSession 扫描 text、thinking 与 tool_result 内容，
+/// 按首次出现顺序去重。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 链接列表，每项含首次出现的消息 display_id、上下文片段与出现次数
+///
+/// # 错误
+/// 文件读取失败时返回错误
+#[tauri::command]
+pub async fn extract_links(
+    session_file_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<Vec<SessionLink>, String> {
+    validated_session_path(&session_file_path)?;
+    let session = load_session(&session_file_path, &cache).await?;
+    Ok(links::extract_links(&session))
+}
+
+/// 提取会话中 assistant 回复里的围栏代码块
+///
+/// 基于缓存的 TransformedSession 解析 assistant text 块，按内容哈希去重
+/// （解析规则见 `services::code_blocks`）。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 代码块列表，每项含语言标记、行数、首次出现的消息 display_id 与内容哈希
+///
+/// # 错误
+/// 文件读取失败时返回错误
+#[tauri::command]
+pub async fn extract_code_blocks(
+    session_file_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<Vec<CodeBlock>, String> {
+    validated_session_path(&session_file_path)?;
+    let session = load_session(&session_file_path, &cache).await?;
+    Ok(code_blocks::extract_code_blocks(&session))
+}
+
+/// 把选中的代码块保存为 `snippet_<n>.<ext>` 文件
+///
+/// 扩展名由语言标记决定，编号跳过目标目录中已存在的文件，不覆盖任何文件。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `block_ids` - 要保存的代码块 ID（`CodeBlock.id`），按此顺序编号
+/// - `target_dir` - 用户选择的目标目录
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 写入的文件路径列表
+///
+/// # 错误
+/// 会话读取失败、块 ID 不存在或文件写入失败时返回错误
+#[tauri::command]
+pub async fn save_code_blocks(
+    session_file_path: String,
+    block_ids: Vec<String>,
+    target_dir: String,
+    cache: State<'_, AppCache>,
+) -> Result<Vec<String>, String> {
+    command_trace::traced(
+        "save_code_blocks",
+        format!("{} count={}", command_trace::path_arg(&session_file_path), block_ids.len()),
+        save_code_blocks_inner(session_file_path, block_ids, target_dir, cache),
+    )
+    .await
+}
+
+/// `save_code_blocks` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn save_code_blocks_inner(
+    session_file_path: String,
+    block_ids: Vec<String>,
+    target_dir: String,
+    cache: State<'_, AppCache>,
+) -> Result<Vec<String>, String> {
+    validated_session_path(&session_file_path)?;
+    let session = load_session(&session_file_path, &cache).await?;
+    let blocks = code_blocks::extract_code_blocks(&session);
+    code_blocks::save_code_blocks(&blocks, &block_ids, Path::new(&target_dir)).await
+}
+
+/// 提取会话的主题关键词
+///
+/// 基于缓存的小写化搜索文本做分词与词频统计（缓存未命中时先加载），
+/// 结果保存在会话缓存条目中，会话文件修改后随缓存一起失效。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `top_n` - 返回的关键词数量（最多 100 个）
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 按词频降序排列的关键词及出现次数
+///
+/// # 错误
+/// 文件读取失败时返回错误
+#[tauri::command]
+pub async fn get_session_keywords(
+    session_file_path: String,
+    top_n: usize,
+    cache: State<'_, AppCache>,
+) -> Result<Vec<SessionKeyword>, String> {
+    validated_session_path(&session_file_path)?;
+    load_session(&session_file_path, &cache).await?;
+    cache
+        .session_keywords(&session_file_path, top_n)
+        .ok_or_else(|| "会话未在缓存中找到".to_string())
+}
+
+/// 查询某个 tool_use 的完整输入与输出配对详情
+///
+/// 基于缓存的 TransformedSession 定位（缓存未命中时先加载），
+/// 返回完整的 tool_use 块与 tool_result 块、两者所在消息的 uuid 及时间差。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `tool_use_id` - 工具调用 ID
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 返回配对详情；仅找到一侧时 `missing` 字段说明缺失的是 tool_use 还是 tool_result
+///
+/// # 错误
+/// 文件读取失败，或会话中既没有该调用也没有对应结果时返回错误
+#[tauri::command]
+pub async fn get_tool_invocation(
+    session_file_path: String,
+    tool_use_id: String,
+    cache: State<'_, AppCache>,
+) -> Result<ToolInvocation, String> {
+    validated_session_path(&session_file_path)?;
+    let session = load_session(&session_file_path, &cache).await?;
+    transformer::find_tool_invocation(&session, &tool_use_id).ok_or_else(|| {
+        format!("会话中不存在 tool_use 与 tool_result: {}", tool_use_id)
+    })
+}
+
+/// 对比工具结果中的文件内容与磁盘上的当前文件
+///
+/// 从 Read / Write / Edit 的 tool_result 还原文件快照，与磁盘内容做行级 diff。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `display_id` - tool_result 消息的 display_id
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 返回 diff 片段与统计；文件已删除或为二进制时通过 `status` 标明
+///
+/// # 错误
+/// 消息不含文件快照、文件超过 5MB 或读取失败时返回错误
+#[tauri::command]
+pub async fn diff_tool_result_with_disk(
+    session_file_path: String,
+    display_id: String,
+    cache: State<'_, AppCache>,
+) -> Result<tool_diff::ToolResultDiff, String> {
+    validated_session_path(&session_file_path)?;
+    let session = load_session(&session_file_path, &cache).await?;
+    tool_diff::diff_with_disk(&session, &display_id).await
+}
+
+/// 生成只保留最近 N 条消息的会话瘦身副本
+///
+/// 自动补齐被截断的 tool_use 配对与 assistant 回复，保证副本可以正常 resume。
+/// 截取规则见 `services::trimmed_copy`。
+///
+/// # 参数
+/// - `session_file_path` - 源会话 JSONL 文件的绝对路径（不会被修改）
+/// - `keep_last_n` - 保留主线上最后多少条消息
+/// - `output_path` - 可选的导出路径（须在 Claude 数据目录之外）；省略时在同项目下生成新 UUID 会话
+/// - `cache` - Tauri managed state，写入备份注册与项目列表缓存失效
+///
+/// # 返回值
+/// 返回副本路径、会话 ID 与实际包含的消息条数
+///
+/// # 错误
+/// 保留条数为 0、会话没有对话消息、导出路径不合法或读写失败时返回错误
+#[tauri::command]
+pub async fn create_trimmed_copy(
+    session_file_path: String,
+    keep_last_n: usize,
+    output_path: Option<String>,
+    cache: State<'_, AppCache>,
+) -> Result<TrimmedCopyResult, String> {
+    validated_session_path(&session_file_path)?;
+    trimmed_copy::create_trimmed_copy(&session_file_path, keep_last_n, output_path.as_deref(), &cache).await
+}
+
+/// 复制整个会话为同项目下的新会话
+///
+/// 新文件名为新生成的 UUID（与已有文件冲突时重新生成），每行的 `sessionId` 改写为新 UUID，
+/// 用于在副本上做实验性修剪而不破坏原文件。项目列表缓存随之失效，新会话立即出现在列表中。
+///
+/// # 参数
+/// - `session_file_path` - 源会话 JSONL 文件的绝对路径（不会被修改）
+/// - `cache` - Tauri managed state，写入备份注册与项目列表缓存失效
+///
+/// # 返回值
+/// 返回新会话的 Session 结构
+///
+/// # 错误
+/// 路径验证失败、会话没有消息、文件名多次冲突或读写失败时返回错误
+#[tauri::command]
+pub async fn duplicate_session(
+    session_file_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<Session, String> {
+    command_trace::traced(
+        "duplicate_session",
+        command_trace::path_arg(&session_file_path),
+        duplicate_session_inner(session_file_path, cache),
+    )
+    .await
+}
+
+/// `duplicate_session` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn duplicate_session_inner(
+    session_file_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<Session, String> {
+    validated_session_path(&session_file_path)?;
+    trimmed_copy::duplicate_session(&session_file_path, &cache).await
+}
+
+/// 把源会话合并到目标会话
+///
+/// 用于把 Claude Code 崩溃后被拆成两段的同一任务重新拼接。合并规则（uuid 冲突、
+/// parentUuid 链修正、插入位置）见 `services::session_merge`。
+///
+/// # 参数
+/// - `source_path` - 源会话 JSONL 文件的绝对路径
+/// - `target_path` - 目标会话 JSONL 文件的绝对路径（须与源会话位于同一项目目录）
+/// - `position` - 插入位置：`"timestamp"` 按时间戳归并，`"append"` 追加到末尾
+/// - `delete_source` - 合并成功后是否删除源会话（经 file_guard 备份）
+/// - `cache` - Tauri managed state，会话写锁、备份注册与缓存更新
+///
+/// # 返回值
+/// 返回并入的消息条数、重新生成 uuid 的条数与源会话是否已删除
+///
+/// # 错误
+/// 路径验证失败、两个会话不在同一项目目录、任一会话为空或读写失败时返回错误
+#[tauri::command]
+pub async fn merge_sessions(
+    source_path: String,
+    target_path: String,
+    position: MergePosition,
+    delete_source: bool,
+    cache: State<'_, AppCache>,
+) -> Result<MergeSessionsResult, String> {
+    let args = format!("{} -> {}", command_trace::path_arg(&source_path), command_trace::path_arg(&target_path));
+    command_trace::traced(
+        "merge_sessions",
+        args,
+        merge_sessions_inner(source_path, target_path, position, delete_source, cache),
+    )
+    .await
+}
+
+/// `merge_sessions` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn merge_sessions_inner(
+    source_path: String,
+    target_path: String,
+    position: MergePosition,
+    delete_source: bool,
+    cache: State<'_, AppCache>,
+) -> Result<MergeSessionsResult, String> {
+    validated_session_path(&source_path)?;
+    validated_session_path(&target_path)?;
+    session_merge::merge_sessions(&source_path, &target_path, position, delete_source, &cache).await
+}
+
+/// 诊断会话文件的「读取 → 写回」往返是否无损
+///
+/// 在内存中用写入路径相同的解析/序列化逻辑处理文件，逐行比对，
+/// 不修改任何文件。用于确认编辑、删除等写操作不会丢失未知字段或坏行。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+///
+/// # 返回值
+/// 返回往返校验报告（总行数、原样保留行数、不一致行明细）
+///
+/// # 错误
+/// 文件读取失败时返回错误
+#[tauri::command]
+pub async fn verify_roundtrip(
+    session_file_path: String,
+) -> Result<parser::RoundtripReport, String> {
+    validated_session_path(&session_file_path)?;
+    parser::verify_roundtrip(&session_file_path).await
+}
+
+/// 把非 UTF-8 的会话文件转存为 UTF-8
+///
+/// 读取时的转码只发生在内存中；本命令把探测出的编码（见 `utils::encoding`）转码后的文本
+/// 经 `file_guard` 写回（自动创建备份），之后 Claude Code 也能正常读取该会话。
+/// 只转换编码，行内容不经过解析与重新序列化。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `cache` - Tauri managed state，转存后使该会话的缓存失效
+///
+/// # 返回值
+/// 转换前的原始编码；文件本身已是 UTF-8 时返回 None 且不写入
+///
+/// # 错误
+/// 路径校验失败、编码无法识别、会话已锁定、备份或写入失败时返回错误
+#[tauri::command]
+pub async fn convert_session_encoding(
+    session_file_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<Option<String>, String> {
+    command_trace::traced(
+        "convert_session_encoding",
+        command_trace::path_arg(&session_file_path),
+        convert_session_encoding_inner(session_file_path, cache),
+    )
+    .await
+}
+
+/// `convert_session_encoding` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn convert_session_encoding_inner(
+    session_file_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<Option<String>, String> {
+    validated_session_path(&session_file_path)?;
+    let _guard = cache.lock_session(&session_file_path).await;
+
+    let bytes = tokio::fs::read(&session_file_path)
+        .await
+        .map_err(|e| format!("读取会话文件失败: {}", e))?;
+    let decoded = encoding::decode_bytes(bytes)?;
+    let Some(from) = decoded.reencoded_from else {
+        return Ok(None);
+    };
+
+    file_guard::safe_write_file(&session_file_path, decoded.text.as_bytes(), "convert_session_encoding", &cache).await?;
+    cache.invalidate_session(&session_file_path);
+    cache.clear_undo(&session_file_path, "转换会话编码");
+    cache.sync_session_file(&session_file_path, None).await;
+    Ok(Some(from.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_copies_rechains_parent_uuid() {
+        let target = vec![
+            serde_json::json!({"type": "user", "uuid": "t1", "parentUuid": null, "sessionId": "target"}),
+            serde_json::json!({"type": "assistant", "uuid": "t2", "parentUuid": "t1", "sessionId": "target"}),
+        ];
+        let selected = vec![
+            serde_json::json!({"type": "user", "uuid": "s1", "parentUuid": "s0", "sessionId": "source", "timestamp": "2025-01-01T00:00:00Z"}),
+            serde_json::json!({"type": "assistant", "uuid": "s3", "parentUuid": "s2", "sessionId": "source", "timestamp": "2025-01-01T00:00:09Z"}),
+        ];
+        let (merged, new_uuids) = insert_copies(target.clone(), selected.clone(), "t1", "target").unwrap();
+        assert_eq!(new_uuids.len(), 2);
+        assert_eq!(merged.len(), 4);
+        assert_eq!(merged[1]["uuid"], new_uuids[0].as_str());
+        assert_eq!(merged[1]["parentUuid"], "t1");
+        assert_eq!(merged[2]["parentUuid"], new_uuids[0].as_str());
+        assert_eq!(merged[3]["parentUuid"], new_uuids[1].as_str());
+        assert!(merged[1..3].iter().all(|m| m["sessionId"] == "target"));
+        assert!(merged[1]["timestamp"].as_str().unwrap() < merged[2]["timestamp"].as_str().unwrap());
+
+        let (at_start, _) = insert_copies(target.clone(), selected.clone(), "", "target").unwrap();
+        assert_eq!(at_start[0]["parentUuid"], Value::Null);
+        assert_eq!(at_start[2]["parentUuid"], Value::Null);
+        assert!(insert_copies(target, selected, "missing", "target").is_err());
+    }
+
+    #[test]
+    fn test_copy_timestamps_keep_or_interpolate() {
+        let at = |ts: &str| serde_json::json!({"type": "user", "timestamp": ts});
+        let before = vec![at("2025-01-01T00:00:00Z")];
+        let after = vec![at("2025-01-01T00:00:30Z")];
+
+        // 原始时间戳落在插入点前后之间：保留
+        let fits = vec![at("2025-01-01T00:00:10Z"), at("2025-01-01T00:00:20Z")];
+        assert_eq!(copy_timestamps(&before, &after, &fits), None);
+
+        // 落在区间外：在前后两条之间均匀插值
+        let outside = vec![at("2025-02-01T00:00:00Z"), at("2025-02-01T00:00:01Z")];
+        assert_eq!(
+            copy_timestamps(&before, &after, &outside),
+            Some(vec!["2025-01-01T00:00:10.000Z".to_string(), "2025-01-01T00:00:20.000Z".to_string()])
+        );
+        // 插到末尾：从前一条起逐条递增 1 毫秒
+        assert_eq!(
+            copy_timestamps(&after, &[], &fits),
+            Some(vec!["2025-01-01T00:00:30.001Z".to_string(), "2025-01-01T00:00:30.002Z".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_truncate_position() {
+        let messages = vec![
+            serde_json::json!({"type": "user", "uuid": "a"}),
+            serde_json::json!({"type": "assistant", "uuid": "b"}),
+            serde_json::json!({"type": "summary"}),
+        ];
+        assert_eq!(truncate_position(&messages, "b", false), Ok(2));
+        assert_eq!(truncate_position(&messages, "b", true), Ok(1));
+        assert!(truncate_position(&messages, "x", false).is_err());
+    }
+
+    #[test]
+    fn test_parse_raw_message() {
+        let parsed = parse_raw_message(r#"{"type": "user", "isMeta": true}"#, "u1").unwrap();
+        assert_eq!(parsed["uuid"], "u1");
+        assert!(parse_raw_message(r#"{"uuid": "u1"}"#, "u1").is_ok());
+        assert!(parse_raw_message(r#"{"uuid": "u2"}"#, "u1").unwrap_err().contains("uuid"));
+        assert!(parse_raw_message("[1]", "u1").is_err());
+        let err = parse_raw_message("{\n  \"type\": ,\n}", "u1").unwrap_err();
+        assert!(err.contains("第 2 行"), "{}", err);
+    }
+
+    #[test]
+    fn test_insert_conversation_message_keeps_chain() {
+        let mut messages = vec![
+            serde_json::json!({"type": "user", "uuid": "a", "parentUuid": null, "cwd": "/p", "version": "2.0.0"}),
+            serde_json::json!({"type": "assistant", "uuid": "b", "parentUuid": "a"}),
+            serde_json::json!({"type": "summary", "summary": "s"}),
+        ];
+        let after_a = InsertPosition::After { uuid: "a".to_string() };
+        insert_conversation_message(&mut messages, &after_a, "user", "补充说明", "sid").unwrap();
+        let inserted = &messages[1];
+        assert_eq!(inserted["type"], "user");
+        assert_eq!(inserted["message"]["role"], "user");
+        assert_eq!(inserted["message"]["content"], "补充说明");
+        assert_eq!(inserted["parentUuid"], "a");
+        assert_eq!(inserted["sessionId"], "sid");
+        assert_eq!(inserted["cwd"], "/p");
+        assert_eq!(messages[2]["parentUuid"], inserted["uuid"]);
+
+        // 追加到末尾：接在最后一条带 uuid 的消息之后
+        insert_conversation_message(&mut messages, &InsertPosition::End, "assistant", "好的", "sid").unwrap();
+        let appended = messages.last().unwrap();
+        assert_eq!(appended["parentUuid"], "b");
+        assert_eq!(appended["message"]["content"][0]["text"], "好的");
+        assert!(appended["timestamp"].as_str().is_some_and(|t| t.ends_with('Z')));
+
+        let missing = InsertPosition::After { uuid: "x".to_string() };
+        assert!(insert_conversation_message(&mut messages, &missing, "user", "t", "sid").is_err());
+        let empty = InsertPosition::After { uuid: String::new() };
+        assert!(insert_conversation_message(&mut messages, &empty, "user", "t", "sid").is_err());
+        assert!(insert_conversation_message(&mut messages, &after_a, "system", "t", "sid").is_err());
+    }
+
+    /// 从最后一条带 uuid 的消息沿 parentUuid 回溯到根，返回经过的 uuid（resume 时加载的主链）
+    fn resume_chain(messages: &[Value]) -> Vec<String> {
+        let parents: HashMap<&str, Option<&str>> = messages
+            .iter()
+            .filter_map(|m| Some((m.get("uuid")?.as_str()?, m.get("parentUuid").and_then(|v| v.as_str()))))
+            .collect();
+        let mut chain = Vec::new();
+        let mut current = messages.iter().rev().find_map(|m| m.get("uuid").and_then(|v| v.as_str()));
+        while let Some(uuid) = current {
+            assert!(!chain.iter().any(|seen| seen == uuid), "parentUuid 链成环");
+            chain.push(uuid.to_string());
+            current = parents.get(uuid).copied().flatten();
+        }
+        chain.reverse();
+        chain
+    }
+
+    #[test]
+    fn test_inserted_messages_keep_session_resumable() {
+        let mut messages = vec![
+            serde_json::json!({"type": "summary", "summary": "s", "leafUuid": "b"}),
+            serde_json::json!({"type": "user", "uuid": "a", "parentUuid": null, "sessionId": "sid", "cwd": "/p", "version": "2.0.0", "gitBranch": "main"}),
+            serde_json::json!({"type": "assistant", "uuid": "b", "parentUuid": "a", "sessionId": "sid", "cwd": "/p", "version": "2.0.0"}),
+        ];
+        insert_conversation_message(&mut messages, &InsertPosition::Start, "user", "开场", "sid").unwrap();
+        insert_conversation_message(&mut messages, &InsertPosition::After { uuid: "a".to_string() }, "assistant", "中间", "sid").unwrap();
+        insert_conversation_message(&mut messages, &InsertPosition::End, "user", "结尾", "sid").unwrap();
+        insert_raw_entry(&mut messages, &InsertPosition::Start, serde_json::json!({"type": "custom-title", "customTitle": "t"})).unwrap();
+
+        // 主链从唯一的根一直连到最后一条消息，覆盖全部对话消息
+        let chain = resume_chain(&messages);
+        let conversation: Vec<&Value> = messages.iter().filter(|m| m.get("uuid").is_some()).collect();
+        assert_eq!(chain.len(), conversation.len());
+        assert_eq!(conversation.iter().filter(|m| m["parentUuid"].is_null()).count(), 1);
+        assert_eq!(chain[1], "a");
+        assert_eq!(chain[3], "b");
+
+        // 新消息都带 resume 所需的字段；插在开头的消息从后继继承环境
+        for msg in conversation {
+            assert_eq!(msg["sessionId"], "sid");
+            assert_eq!(msg["cwd"], "/p");
+            assert_eq!(msg["version"], "2.0.0");
+            assert!(msg.get("timestamp").is_some() || ["a", "b"].contains(&msg["uuid"].as_str().unwrap()));
+        }
+        assert_eq!(messages[0]["type"], "custom-title");
+        assert!(messages[0].get("parentUuid").is_none());
+
+        // 对话消息不能走原始条目入口
+        let raw_user = serde_json::json!({"type": "user", "uuid": "u", "message": {"role": "user", "content": "x"}});
+        assert!(insert_raw_entry(&mut messages, &InsertPosition::End, raw_user).is_err());
+    }
+
+    #[test]
+    fn test_combine_adjacent_messages() {
+        let messages = vec![
+            serde_json::json!({"type": "assistant", "uuid": "a0", "parentUuid": null, "message": {"content": [{"type": "text", "text": "你好"}]}}),
+            serde_json::json!({"type": "user", "uuid": "u1", "parentUuid": "a0", "message": {"content": "第一句"}}),
+            serde_json::json!({"type": "system", "uuid": "s1", "parentUuid": "u1"}),
+            serde_json::json!({"type": "user", "uuid": "u2", "parentUuid": "s1", "message": {"content": [{"type": "text", "text": "第二句"}]}}),
+            serde_json::json!({"type": "user", "uuid": "u3", "parentUuid": "u2", "message": {"content": "第三句"}}),
+            serde_json::json!({"type": "assistant", "uuid": "a1", "parentUuid": "u3", "message": {"content": []}}),
+        ];
+        let uuids = |ids: &[&str]| ids.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let merged = combine_adjacent_messages(messages.clone(), &uuids(&["u3", "u1", "u2"])).unwrap();
+        assert_eq!(merged.len(), 4);
+        assert_eq!(
+            merged[1]["message"]["content"],
+            serde_json::json!([
+                {"type": "text", "text": "第一句"},
+                {"type": "text", "text": "第二句"},
+                {"type": "text", "text": "第三句"},
+            ])
+        );
+        assert_eq!(merged[2]["uuid"], "s1");
+        assert_eq!(merged[3]["parentUuid"], "u1");
+
+        let not_adjacent = combine_adjacent_messages(messages.clone(), &uuids(&["u1", "u3"]));
+        assert!(not_adjacent.unwrap_err().contains("相邻"));
+        assert!(combine_adjacent_messages(messages.clone(), &uuids(&["a0", "u1"])).unwrap_err().contains("类型不一致"));
+        assert!(combine_adjacent_messages(messages.clone(), &uuids(&["u1", "x"])).unwrap_err().contains("不存在"));
+        assert!(combine_adjacent_messages(messages, &uuids(&["u1"])).is_err());
+    }
+
+    #[test]
+    fn test_operation_receipt_diff() {
+        use crate::models::display::OperationReceipt;
+        let line = |uuid: &str, kind: &str, text: &str| serde_json::json!({
+            "type": kind, "uuid": uuid, "parentUuid": null, "timestamp": "2025-01-01T00:00:00.000Z",
+            "message": { "role": kind, "content": [{"type": "text", "text": text}] }
+        });
+        let before = vec![line("u0", "user", "问"), line("a0", "assistant", "旧回答"), line("u1", "user", "追问")];
+        let after = vec![line("u0", "user", "问"), line("a0", "assistant", "新回答")];
+        let (old, _, _) = transformer::transform_session(&before);
+        let (new, _, _) = transformer::transform_session(&after);
+
+        let receipt = OperationReceipt::diff(&old.display_messages, &new);
+        assert_eq!(receipt.removed_display_ids, vec!["u1"]);
+        let updated: Vec<&str> = receipt.updated_messages.iter().map(|dm| dm.display_id.as_str()).collect();
+        assert_eq!(updated, vec!["a0"]);
+    }
+
+    /// 多个并发删除不同消息的写操作经会话写锁串行化后都应生效
+    ///
+    /// file_guard 要求写入路径位于 `~/.claude/` 下，测试中主目录为 `platform::test_home`。
+    #[tokio::test]
+    async fn test_concurrent_deletes_are_serialized() {
+        let project_dir = crate::utils::platform::test_home()
+            .join(".claude")
+            .join("projects")
+            .join("lock-test");
+        std::fs::create_dir_all(&project_dir).unwrap();
+
+        // 10 条消息组成的单链：m0 → m1 → ... → m9
+        let file = project_dir.join("session.jsonl");
+        let content: String = (0..10)
+            .map(|i| {
+                let parent = if i == 0 { "null".to_string() } else { format!("\"m{}\"", i - 1) };
+                format!(
+                    r#"{{"type":"user","uuid":"m{}","parentUuid":{},"message":{{"role":"user","content":"{}"}}}}"#,
+                    i, parent, i
+                ) + "\n"
+            })
+            .collect();
+        std::fs::write(&file, content).unwrap();
+        let path = file.to_string_lossy().to_string();
+
+        // 并发删除奇数编号的消息
+        let cache = AppCache::new();
+        let targets: Vec<String> = (1..10).step_by(2).map(|i| format!("m{}", i)).collect();
+        let results = futures_util::future::join_all(
+            targets.iter().map(|uuid| remove_message(&path, uuid, false, &cache)),
+        )
+        .await;
+        for result in results {
+            result.unwrap();
+        }
+
+        let remaining = parser::read_messages(&path).await.unwrap();
+        let uuids: Vec<&str> = remaining
+            .iter()
+            .filter_map(|m| m.get("uuid").and_then(|v| v.as_str()))
+            .collect();
+        assert_eq!(uuids, vec!["m0", "m2", "m4", "m6", "m8"]);
+        // parentUuid 链跳过被删除的消息
+        assert_eq!(remaining[1]["parentUuid"], "m0");
+        assert_eq!(remaining[4]["parentUuid"], "m6");
+
+        let _ = std::fs::remove_dir_all(&project_dir);
+    }
+
+    /// 会话锁定时撤销写回失败，弹出的一步放回栈顶，解锁后仍可撤销
+    #[tokio::test]
+    async fn test_undo_on_locked_session_keeps_step() {
+        let project_dir = crate::utils::platform::test_home()
+            .join(".claude")
+            .join("projects")
+            .join("undo-lock-test");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        let file = project_dir.join("session.jsonl");
+        std::fs::write(
+            &file,
+            concat!(
+                r#"{"type":"user","uuid":"m0","parentUuid":null,"message":{"role":"user","content":"a"}}"#, "\n",
+                r#"{"type":"user","uuid":"m1","parentUuid":"m0","message":{"role":"user","content":"b"}}"#, "\n",
+            ),
+        )
+        .unwrap();
+        let path = file.to_string_lossy().to_string();
+
+        let cache = AppCache::new();
+        remove_message(&path, "m1", false, &cache).await.unwrap();
+        assert_eq!(cache.undo_descriptions(&path).len(), 1);
+
+        crate::services::session_lock::set_locked(&path, true).await.unwrap();
+        let err = undo_last_operation_inner(path.clone(), &cache).await.unwrap_err();
+        assert!(err.starts_with(crate::services::session_lock::SESSION_LOCKED_ERROR));
+        assert_eq!(cache.undo_descriptions(&path).len(), 1);
+
+        crate::services::session_lock::set_locked(&path, false).await.unwrap();
+        let result = undo_last_operation_inner(path.clone(), &cache).await.unwrap();
+        assert!(result.remaining.is_empty());
+        assert_eq!(parser::read_messages(&path).await.unwrap().len(), 2);
+
+        let _ = std::fs::remove_dir_all(&project_dir);
     }
 }
@@ -9,6 +9,8 @@
 //! - `skill` - Skills 管理相关 commands（扫描、查看详情）
 //! - `pet` - 宠物管理相关 commands（查看、清除宠物）
 //! - `plugin` - Plugins 管理相关 commands（列表、启用/禁用、marketplace）
+//! - `window` - 多窗口相关 commands（在独立窗口中打开会话）
+//! - `validation` - command 输入校验（会话路径必须位于数据源的 projects 目录内）
 
 pub mod messages;
 pub mod pet;
@@ -19,3 +21,5 @@ pub mod retrospect;
 pub mod settings;
 pub mod skill;
 pub mod tools;
+pub mod validation;
+pub mod window;
@@ -1,54 +1,1043 @@
 //! # 项目和会话 Tauri Commands
 //!
 //! 提供项目扫描相关的 Tauri command 处理函数：
-//! - `scan_projects` - 一次性并行扫描所有项目和会话元数据
+//! - `scan_projects` - 并行扫描项目和会话元数据（支持分页，按目录 / 文件 mtime 增量扫描，可选多数据源），
+//!   扫描时推送 `scan-progress` 进度事件
+//! - `scan_project_sessions` - 按项目分页加载会话列表（配合 `scan_projects` 的项目索引模式）
+//! - `filter_projects` - 按项目路径、会话 ID、会话名称过滤项目树
+//! - `get_counts` - 项目与会话数量（仅统计目录条目，供侧边栏角标与启动画面）
+//! - `export_projects_overview` - 导出项目清单为 CSV 或 JSON 文件
+//! - `get_data_compat_report` - 汇总数据目录中各 Claude Code 版本的会话分布
+//! - `get_project_summary` - 读取项目目录下 CLAUDE.md / README.md 的开头部分
+//! - `get_project_stats` - 汇总项目下全部会话的数量、行数、字节数与 token 用量（缓存 5 分钟）
+//! - `set_session_locked` - 锁定 / 解锁会话（锁定后拒绝一切写入）
+//! - `save_session_draft` / `get_session_draft` / `delete_session_draft` - 会话草稿箱读写
+//! - `save_view_state` / `get_view_state` - 会话阅读状态（滚动位置、展开块、过滤器）读写
+//! - `rename_session` - 设置会话别名，可选写回 Claude Code 原生标题
+//! - `merge_fragment_project` - 将子目录中误启动产生的碎片项目归并回父项目
+//! - `archive_project` / `unarchive_project` - 归档 / 取消归档项目（只影响 CCR 的列表视图）
+//! - `resolve_project_real_path` / `set_project_path_override` - 比对项目解码路径与会话 cwd，写入用户确认的真实路径
+//! - `toggle_pin_project` / `toggle_pin_session` - 切换项目 / 会话的置顶状态
+//! - `record_session_opened` - 记录会话的最近查看时间（清除「查看后又有新消息」标记）
+//! - `start_watching` / `stop_watching` - 开关数据目录监听（变化时推送 `claude-data-changed` 事件）
 //!
 //! 集成了内存缓存层，避免重复扫描。
 
-use tauri::State;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
-use crate::models::project::Project;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::commands::validation::validated_session_path;
+use crate::models::project::{Project, ProjectCounts, ProjectPage, ProjectSummary, ScanProgress, SessionPage};
+use crate::services::archive::Archived;
 use crate::services::cache::AppCache;
-use crate::services::scanner;
+use crate::services::compat::{self, DataCompatReport};
+use crate::services::limits::AppLimits;
+use crate::services::project_filter;
+use crate::services::project_stats::{self, ProjectStats};
+use crate::services::session_title::{self, RenameSessionResult};
+use crate::services::scanner::{ScanOptions, SessionSortBy, SortOrder};
+use crate::services::watcher::WatcherState;
+use crate::services::project_meta::{self, ProjectPathResolution};
+use crate::services::{archive, command_trace, data_sources, drafts, export, fragments, last_viewed, parser, pins, project_summary, scanner, session_lock, transformer, view_state};
+use crate::utils::{filename, path};
 
-/// 一次性并行扫描所有项目和会话元数据
+/// 并行扫描项目和会话元数据（支持分页）
 ///
 /// 这是整个性能优化的核心 command。通过一次 IPC 调用完成以下工作：
-/// 1. 检查缓存，如果缓存有效则直接返回
-/// 2. 缓存无效时，扫描 `~/.claude/projects/` 目录下的所有项目子目录
-/// 3. 对每个项目并行扫描其会话 `.jsonl` 文件
-/// 4. 并行获取每个文件的 metadata（修改时间）
-/// 5. 将结果存入缓存并返回
+/// 1. 检查缓存，如果缓存有效且覆盖请求范围则直接返回
+/// 2. 缓存无效时，列出 `~/.claude/projects/` 下的所有项目子目录并按目录 mtime 倒序排列
+/// 3. 对请求范围内的每个项目并行扫描其会话 `.jsonl` 文件
+///    （增量：目录 mtime 未变时沿用上次的文件列表，强制刷新时重新枚举）
+/// 4. 并行获取每个文件的 metadata（修改时间），只有 mtime / 大小变化的文件才重新轻量读取
+/// 5. 将结果与扫描快照合并进缓存并返回
 ///
 /// # 性能对比
 /// - **优化前**：前端需要 N 次 readDir + N*M 次 stat（1000+ 次 IPC 往返）
 /// - **优化后**：前端仅需 1 次 `invoke('scan_projects')`，
 ///   Rust 后端使用 tokio 并行完成所有 I/O 操作
 ///
+/// ## 分页
+/// 项目目录数量很大（如 monorepo 每个子目录都生成一个项目）时，
+/// 前端可传入 `max_projects` 只扫描最近修改的 N 个目录，
+/// 再以 `offset` 继续「加载更多」。已加载的页保存在缓存中，
+/// 取更多页或强制刷新时按范围合并，不会互相覆盖。
+///
+/// ## 多数据源
+/// 省略 `source_id`（或传 "default"）时只扫描 `claude_path`，行为与单数据源时一致。
+/// 传入额外数据源 ID 时扫描该目录，传入 "all" 时扫描全部数据源后合并（见 `services::data_sources`），
+/// 这两种情况的结果不进入项目分页缓存。
+///
+/// ## 消息数
+/// `include_message_count` 为 true 时逐行读取每个会话文件填充 `Session.message_count` 与 `Session.line_count`
+/// （同时读取的文件数受 `AppLimits` 限制，文件未变化时沿用上次的统计）。
+/// 缓存中的页面可能未统计过消息数，因此这种请求不读取页面缓存，扫描结果照常合并进缓存。
+///
+/// ## 子 agent 会话
+/// `agent-` 前缀的子 agent 会话文件默认不列出；`include_agent_sessions` 为 true 时
+/// 作为 `is_agent = true` 的会话返回。该模式同样不进入项目分页缓存。
+///
+/// ## 项目索引
+/// 项目与会话很多时整棵树的 IPC 载荷可达数 MB。`index_only` 为 true 时返回的项目不含 `sessions`，
+/// 只带 `sessionCount` 与 `latestTimestamp`，会话列表再由 `scan_project_sessions` 按项目分页加载。
+/// 缓存中仍保存完整的项目树，两种返回形式共用同一份缓存。
+///
+/// ## 扫描进度
+/// 缓存未命中、实际扫描默认数据源时，按 `scanner` 的节流规则（每 50 个项目或 200ms）
+/// 向所有窗口推送 `scan-progress` 事件（载荷为 `ScanProgress`），前端可显示进度条。
+/// 缓存命中时不推送；多数据源等不进入分页缓存的模式同样不推送。
+///
+/// ## 归档项目
+/// 已归档的项目（见 `services::archive`）默认不列出，也不计入 `total_projects`；
+/// `include_archived` 为 true 时一并返回并以 `archived` 标记，该模式不进入项目分页缓存。
+///
+/// ## 会话排序
+/// 项目内的会话默认置顶优先、按时间戳降序。传入 `sort_by` / `order` 时改为按大小、消息数或名称排序
+/// （规则见 `scanner::sort_sessions_by`），只影响返回结果，缓存中的顺序不变。
+///
 /// # 参数
 /// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
+/// - `max_projects` - 本页最多扫描的项目数；None 表示扫描全部
+/// - `offset` - 起始偏移（目录 mtime 倒序）；None 表示 0
+/// - `force_refresh` - 为 true 时跳过缓存重新扫描请求范围
+/// - `source_id` - 数据源 ID，或 "all" 表示全部数据源；None 表示默认数据源
+/// - `include_message_count` - 是否统计每个会话的消息数；None 表示 false
+/// - `include_agent_sessions` - 是否包含子 agent 会话；None 表示 false
+/// - `index_only` - 是否只返回项目索引（不含会话列表）；None 表示 false
+/// - `include_archived` - 是否包含已归档的项目；None 表示 false
+/// - `sort_by` - 会话排序键（"time" | "size" | "count" | "name"）；None 表示 "time"
+/// - `order` - 排序方向（"asc" | "desc"）；None 表示 "desc"
+/// - `app` - 应用句柄，用于推送扫描进度事件
 /// - `cache` - Tauri managed state，内存缓存
+/// - `limits` - Tauri managed state，统计消息数时的并发读取上限
 ///
 /// # 返回值
-/// 返回按最新会话时间倒序排列的 Project 数组
+/// 返回 ProjectPage：页内项目按最新会话时间倒序排列，并附带项目总数和是否还有更多；
+/// 无法读取的项目目录（权限不足、符号链接损坏等）放在 `warnings` 中，命中缓存时返回缓存记录的警告
 ///
 /// # 错误
-/// 如果 projects 目录不可读，返回错误信息
+/// 如果 projects 目录不可读或数据源不存在，返回错误信息
 #[tauri::command]
+#[allow(clippy::too_many_arguments)] // 参数与前端 invoke 的具名参数一一对应
 pub async fn scan_projects(
     claude_path: String,
+    max_projects: Option<usize>,
+    offset: Option<usize>,
+    force_refresh: Option<bool>,
+    source_id: Option<String>,
+    include_message_count: Option<bool>,
+    include_agent_sessions: Option<bool>,
+    index_only: Option<bool>,
+    include_archived: Option<bool>,
+    sort_by: Option<SessionSortBy>,
+    order: Option<SortOrder>,
+    app: AppHandle,
+    cache: State<'_, AppCache>,
+    limits: State<'_, AppLimits>,
+) -> Result<ProjectPage, String> {
+    let mut page = command_trace::traced(
+        "scan_projects",
+        format!("offset={:?} max={:?}", offset, max_projects),
+        scan_projects_inner(claude_path, max_projects, offset, force_refresh, source_id, include_message_count, include_agent_sessions, include_archived, app, cache, limits),
+    )
+    .await?;
+
+    if sort_by.is_some() || order.is_some() {
+        for project in page.projects.iter_mut() {
+            scanner::sort_sessions_by(&mut project.sessions, sort_by.unwrap_or_default(), order.unwrap_or_default());
+        }
+    }
+    if index_only.unwrap_or(false) {
+        for project in page.projects.iter_mut() {
+            project.sessions = Vec::new();
+        }
+    }
+    Ok(page)
+}
+
+/// `scan_projects` 的实现，由 command 包进 `command_trace::traced` 调用
+#[allow(clippy::too_many_arguments)] // 参数与前端 invoke 的具名参数一一对应
+async fn scan_projects_inner(
+    claude_path: String,
+    max_projects: Option<usize>,
+    offset: Option<usize>,
+    force_refresh: Option<bool>,
+    source_id: Option<String>,
+    include_message_count: Option<bool>,
+    include_agent_sessions: Option<bool>,
+    include_archived: Option<bool>,
+    app: AppHandle,
+    cache: State<'_, AppCache>,
+    limits: State<'_, AppLimits>,
+) -> Result<ProjectPage, String> {
+    let offset = offset.unwrap_or(0);
+    let force_refresh = force_refresh.unwrap_or(false);
+    let options = ScanOptions {
+        count_lines: include_message_count.unwrap_or(false),
+        io_concurrency: limits.io_concurrency(),
+        include_agents: include_agent_sessions.unwrap_or(false),
+        include_archived: include_archived.unwrap_or(false),
+    };
+
+    let source_id = source_id.filter(|id| id != data_sources::DEFAULT_SOURCE_ID);
+    if source_id.is_some() || options.include_agents || options.include_archived {
+        let source_id = source_id.as_deref().unwrap_or(data_sources::DEFAULT_SOURCE_ID);
+        let sources = data_sources::select(source_id).await?;
+        return data_sources::scan_sources(&sources, offset, max_projects, force_refresh, options, &cache).await;
+    }
+
+    scan_default_source(&claude_path, offset, max_projects, force_refresh, options, &cache, |progress| {
+        let _ = app.emit(SCAN_PROGRESS_EVENT, progress);
+    })
+    .await
+}
+
+/// 按关键词过滤项目树
+///
+/// 在默认数据源的完整项目列表上，按项目路径、会话 ID、会话名称（别名、标题、摘要、首条消息）
+/// 做大小写不敏感的匹配（规则见 `services::project_filter`）。项目列表优先取自分页缓存，
+/// 缓存不完整或已过期时先完整扫描一次（结果同样写入缓存）。
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
+/// - `query` - 过滤关键词；为空时返回完整列表
+/// - `cache` - Tauri managed state，项目列表缓存
+///
+/// # 返回值
+/// 过滤后的项目列表（按最新会话时间倒序），项目只包含命中的会话（项目路径命中时包含全部会话）
+///
+/// # 错误
+/// projects 目录不可读时返回错误
+#[tauri::command]
+pub async fn filter_projects(
+    claude_path: String,
+    query: String,
     cache: State<'_, AppCache>,
 ) -> Result<Vec<Project>, String> {
+    command_trace::traced(
+        "filter_projects",
+        format!("len={}", query.chars().count()),
+        filter_projects_inner(claude_path, query, cache),
+    )
+    .await
+}
+
+/// `filter_projects` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn filter_projects_inner(
+    claude_path: String,
+    query: String,
+    cache: State<'_, AppCache>,
+) -> Result<Vec<Project>, String> {
+    let page = scan_default_source(&claude_path, 0, None, false, ScanOptions::default(), &cache, |_| {}).await?;
+    Ok(project_filter::filter_projects(page.projects, &query))
+}
+
+/// 项目扫描进度的事件名
+pub const SCAN_PROGRESS_EVENT: &str = "scan-progress";
+
+/// 扫描默认数据源的一页项目（优先读项目分页缓存，未命中时增量扫描并合并进缓存）
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径
+/// - `offset` - 起始偏移（目录 mtime 倒序）
+/// - `max_projects` - 本页最多扫描的项目数；None 表示扫描全部
+/// - `force_refresh` - 为 true 时跳过缓存重新扫描请求范围
+/// - `options` - 扫描选项（统计消息数时不读取页面缓存）
+/// - `cache` - 内存缓存
+/// - `on_progress` - 实际扫描时的进度回调
+///
+/// # 错误
+/// projects 目录不可读时返回错误
+async fn scan_default_source(
+    claude_path: &str,
+    offset: usize,
+    max_projects: Option<usize>,
+    force_refresh: bool,
+    options: ScanOptions,
+    cache: &AppCache,
+    on_progress: impl FnMut(ScanProgress),
+) -> Result<ProjectPage, String> {
     // 优先尝试从缓存获取
-    if let Some(cached) = cache.get_projects() {
+    let cached = (!force_refresh && !options.count_lines)
+        .then(|| cache.get_projects_page(offset, max_projects))
+        .flatten();
+    if let Some(cached) = cached {
         return Ok(cached);
     }
 
-    // 缓存未命中，执行完整扫描
-    let projects = scanner::scan_all_projects(&claude_path).await?;
+    // 缓存未命中，列出项目目录并扫描请求范围
+    let (mut project_dirs, mut warnings) = scanner::list_project_dirs_with_warnings(claude_path).await?;
+    let archived = archive::load_active().await.unwrap_or_default();
+    archived.retain_unarchived(&mut project_dirs);
+    let total_projects = project_dirs.len();
+    let start = offset.min(total_projects);
+    let end = max_projects.map_or(total_projects, |max| start.saturating_add(max).min(total_projects));
+
+    // 增量扫描：复用上次扫描的快照，只重新读取变化的会话；强制刷新时重新枚举每个目录
+    let existing: HashSet<&str> = project_dirs.iter().map(|(name, _)| name.as_str()).collect();
+    let page_dirs: Vec<(String, PathBuf)> = project_dirs[start..end].to_vec();
+    let snapshots = cache.get_project_snapshots(
+        data_sources::DEFAULT_SOURCE_ID,
+        page_dirs.iter().map(|(name, _)| name.as_str()),
+        !force_refresh,
+    );
+    let mut scanned =
+        scanner::scan_project_dirs_with_progress(page_dirs, &snapshots, options, &archived, on_progress).await;
+    cache.update_project_snapshots(data_sources::DEFAULT_SOURCE_ID, scanned.snapshots, &existing);
+    let mut projects = scanned.projects;
+    warnings.append(&mut scanned.warnings);
+
+    // 合并进缓存（缓存保持目录 mtime 顺序）
+    cache.merge_projects(&Path::new(claude_path).join("projects"), start, projects.clone(), total_projects, warnings.clone());
+
+    scanner::sort_by_latest_session(&mut projects);
+
+    Ok(ProjectPage {
+        projects,
+        total_projects,
+        offset: start,
+        has_more: end < total_projects,
+        warnings,
+    })
+}
+
+/// 按需分页加载单个项目的会话列表
+///
+/// 与 `scan_projects(index_only = true)` 配合使用：先取项目索引，展开项目时再加载其会话。
+/// 增量扫描规则与 `scan_projects` 相同（复用同一份扫描快照），结果缓存在单项目会话列表缓存中，
+/// 翻页时不再重新扫描。包含子 agent 会话时不读写该缓存。
+///
+/// # 参数
+/// - `project_name` - 项目目录名
+/// - `source_id` - 项目所属数据源 ID（即 `Project.source`）；None 表示默认数据源
+/// - `offset` - 起始偏移；None 表示 0
+/// - `limit` - 本页最多返回的会话数；None 表示返回全部
+/// - `force_refresh` - 为 true 时跳过缓存重新扫描该项目
+/// - `include_agent_sessions` - 是否包含子 agent 会话；None 表示 false
+/// - `sort_by` - 排序键（"time" | "size" | "count" | "name"）；None 表示 "time"
+/// - `order` - 排序方向（"asc" | "desc"）；None 表示 "desc"
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 返回 SessionPage：会话置顶优先，其余按排序键排列（缺少排序键的排在末尾），
+/// 并附带会话总数和是否还有更多。分页在排序之后进行
+///
+/// # 错误
+/// 项目名称无效、数据源或项目目录不存在，或项目目录无法读取（权限不足、符号链接损坏等）时返回错误
+#[tauri::command]
+#[allow(clippy::too_many_arguments)] // 参数与前端 invoke 的具名参数一一对应
+pub async fn scan_project_sessions(
+    project_name: String,
+    source_id: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    force_refresh: Option<bool>,
+    include_agent_sessions: Option<bool>,
+    sort_by: Option<SessionSortBy>,
+    order: Option<SortOrder>,
+    cache: State<'_, AppCache>,
+) -> Result<SessionPage, String> {
+    command_trace::traced(
+        "scan_project_sessions",
+        format!("offset={:?} limit={:?}", offset, limit),
+        scan_project_sessions_inner(project_name, source_id, offset, limit, force_refresh, include_agent_sessions, sort_by, order, cache),
+    )
+    .await
+}
+
+/// `scan_project_sessions` 的实现，由 command 包进 `command_trace::traced` 调用
+#[allow(clippy::too_many_arguments)] // 参数与前端 invoke 的具名参数一一对应
+async fn scan_project_sessions_inner(
+    project_name: String,
+    source_id: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    force_refresh: Option<bool>,
+    include_agent_sessions: Option<bool>,
+    sort_by: Option<SessionSortBy>,
+    order: Option<SortOrder>,
+    cache: State<'_, AppCache>,
+) -> Result<SessionPage, String> {
+    let source_id = source_id.unwrap_or_else(|| data_sources::DEFAULT_SOURCE_ID.to_string());
+    let project_dir = resolve_project_dir(&project_name, &source_id).await?;
 
-    // 存入缓存
-    cache.set_projects(projects.clone());
+    let force_refresh = force_refresh.unwrap_or(false);
+    let options = ScanOptions {
+        include_agents: include_agent_sessions.unwrap_or(false),
+        ..ScanOptions::default()
+    };
+    let use_cache = !options.include_agents;
+    let cached = (use_cache && !force_refresh)
+        .then(|| cache.get_project_sessions(&project_dir))
+        .flatten();
+    let mut sessions = match cached {
+        Some(sessions) => sessions,
+        None => {
+            let snapshots = cache.get_project_snapshots(&source_id, [project_name.as_str()], !force_refresh);
+            let scanned =
+                scanner::scan_project_dirs(vec![(project_name.clone(), project_dir.clone())], &snapshots, options, &Archived::default())
+                    .await;
+            if let Some(warning) = scanned.warnings.first() {
+                return Err(format!("项目目录无法读取: {}", warning.message));
+            }
+            cache.insert_project_snapshots(&source_id, scanned.snapshots);
+            let sessions = scanned.projects.into_iter().next().map(|p| p.sessions).unwrap_or_default();
+            if use_cache {
+                cache.set_project_sessions(project_dir, sessions.clone());
+            }
+            sessions
+        }
+    };
 
-    Ok(projects)
+    if sort_by.is_some() || order.is_some() {
+        scanner::sort_sessions_by(&mut sessions, sort_by.unwrap_or_default(), order.unwrap_or_default());
+    }
+
+    let total_sessions = sessions.len();
+    let start = offset.unwrap_or(0).min(total_sessions);
+    let end = limit.map_or(total_sessions, |limit| start.saturating_add(limit).min(total_sessions));
+    Ok(SessionPage {
+        sessions: sessions[start..end].to_vec(),
+        total_sessions,
+        offset: start,
+        has_more: end < total_sessions,
+    })
+}
+
+/// 统计项目与会话数量（侧边栏角标）
+///
+/// 只列出项目目录与会话文件，不读取会话内容；已归档的项目不计入，
+/// 指向会话文件的符号链接按目标计入（规则见 `scanner::count_projects`）。
+/// 冷启动时可先于 `scan_projects` 调用显示总数。结果与项目列表缓存同 TTL 缓存，
+/// 项目列表缓存失效（删除、合并等）时一并失效。
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 项目目录数、会话文件数与子 agent 会话文件数
+///
+/// # 错误
+/// projects 目录存在但不可读时返回错误
+#[tauri::command]
+pub async fn get_counts(claude_path: String, cache: State<'_, AppCache>) -> Result<ProjectCounts, String> {
+    command_trace::traced("get_counts", String::new(), get_counts_inner(claude_path, cache)).await
+}
+
+/// `get_counts` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn get_counts_inner(claude_path: String, cache: State<'_, AppCache>) -> Result<ProjectCounts, String> {
+    if let Some(counts) = cache.get_project_counts(&claude_path) {
+        return Ok(counts);
+    }
+    let counts = scanner::count_projects(&claude_path).await?;
+    cache.set_project_counts(&claude_path, counts);
+    Ok(counts)
+}
+
+/// 导出项目清单为 CSV 或 JSON 文件
+///
+/// 基于全部项目的扫描结果（缓存已完整加载时直接复用，否则完整扫描一次），
+/// 汇总每个项目的会话数、消息数、总大小、最近活跃与最早创建时间（本地时区），
+/// 写入用户选择的路径。
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
+/// - `format` - 导出格式："csv" 或 "json"
+/// - `output_path` - 输出文件路径（由前端保存对话框选择）
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 导出的项目数量
+///
+/// # 错误
+/// 不支持的格式、扫描失败或写入文件失败时返回错误
+#[tauri::command]
+pub async fn export_projects_overview(
+    claude_path: String,
+    format: String,
+    output_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<usize, String> {
+    let projects = match cache.get_projects_page(0, None) {
+        Some(page) => page.projects,
+        None => scanner::scan_all_projects(&claude_path).await?.projects,
+    };
+
+    let rows = export::projects_overview(&projects);
+    let content = match format.as_str() {
+        "csv" => export::projects_overview_to_csv(&rows),
+        "json" => export::projects_overview_to_json(&rows),
+        _ => return Err(format!("不支持的导出格式: {}", format)),
+    };
+
+    tokio::fs::write(filename::to_long_path(&output_path), content)
+        .await
+        .map_err(|e| format!("写入导出文件失败: {}", e))?;
+
+    Ok(rows.len())
+}
+
+/// 汇总数据目录中各 Claude Code 版本的会话分布
+///
+/// 以每个会话末条消息的 `version` 为准分组，统计各版本的会话数、涉及项目数
+/// 和最近活跃时间，帮助判断分类器需要兼容哪些旧格式。
+/// 缓存已完整加载全部项目时直接复用，否则完整扫描一次。
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 数据兼容性报告（版本按新到旧排列）
+///
+/// # 错误
+/// 项目扫描失败时返回错误
+#[tauri::command]
+pub async fn get_data_compat_report(
+    claude_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<DataCompatReport, String> {
+    let projects = match cache.get_projects_page(0, None) {
+        Some(page) => page.projects,
+        None => scanner::scan_all_projects(&claude_path).await?.projects,
+    };
+
+    Ok(compat::compat_report(&projects))
+}
+
+/// 读取项目摘要：项目目录下 CLAUDE.md（优先）或 README.md 的开头部分
+///
+/// 项目目录位于 `~/.claude/` 之外，仅做只读访问；最多读取 64KB，
+/// 二进制或非 UTF-8 内容直接拒绝。结果缓存在 AppCache 中，来源文件未修改时不再重复读取。
+///
+/// # 参数
+/// - `project_path` - 项目的真实文件系统路径（已解码）
+/// - `cache` - Tauri managed state，项目摘要缓存
+///
+/// # 返回值
+/// 摘要文本与来源文件路径；两个文件都不存在时返回 None
+///
+/// # 错误
+/// 文件读取失败或内容不是 UTF-8 文本时返回错误
+#[tauri::command]
+pub async fn get_project_summary(
+    project_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<Option<ProjectSummary>, String> {
+    if let Some(cached) = cache.get_project_summary(&project_path) {
+        return Ok(cached);
+    }
+
+    let Some(file_path) = project_summary::find_summary_file(&project_path) else {
+        cache.set_project_summary(&project_path, None, None);
+        return Ok(None);
+    };
+    let mtime = tokio::fs::metadata(&file_path)
+        .await
+        .and_then(|m| m.modified())
+        .ok();
+    let summary = project_summary::read_summary(&file_path).await?;
+    // 拿不到 mtime 时无法判断失效，不缓存
+    if let Some(mtime) = mtime {
+        cache.set_project_summary(&project_path, Some(summary.clone()), Some((file_path, mtime)));
+    }
+    Ok(Some(summary))
+}
+
+/// 汇总项目下全部会话的统计（项目概览面板）
+///
+/// 并行读取项目目录下的全部会话文件（不含子 agent 会话），汇总会话数、JSONL 总行数、
+/// 文件总字节数与所有 assistant 消息的 token 用量。结果缓存 5 分钟，期间的重复调用直接返回。
+///
+/// # 参数
+/// - `project_name` - 项目目录名
+/// - `source_id` - 项目所属数据源 ID；None 表示默认数据源
+/// - `force_refresh` - 为 true 时忽略缓存重新统计
+/// - `cache` - Tauri managed state，项目统计缓存
+/// - `limits` - Tauri managed state，统计期间占用一个全局 I/O 许可
+///
+/// # 返回值
+/// 项目统计结果
+///
+/// # 错误
+/// 项目名称无效、数据源或项目目录不存在，以及项目目录无法读取时返回错误
+#[tauri::command]
+pub async fn get_project_stats(
+    project_name: String,
+    source_id: Option<String>,
+    force_refresh: Option<bool>,
+    cache: State<'_, AppCache>,
+    limits: State<'_, AppLimits>,
+) -> Result<ProjectStats, String> {
+    command_trace::traced(
+        "get_project_stats",
+        format!("refresh={:?}", force_refresh),
+        get_project_stats_inner(project_name, source_id, force_refresh, cache, limits),
+    )
+    .await
+}
+
+/// `get_project_stats` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn get_project_stats_inner(
+    project_name: String,
+    source_id: Option<String>,
+    force_refresh: Option<bool>,
+    cache: State<'_, AppCache>,
+    limits: State<'_, AppLimits>,
+) -> Result<ProjectStats, String> {
+    let source_id = source_id.unwrap_or_else(|| data_sources::DEFAULT_SOURCE_ID.to_string());
+    let project_dir = resolve_project_dir(&project_name, &source_id).await?;
+    if !force_refresh.unwrap_or(false)
+        && let Some(cached) = cache.get_project_stats(&project_dir)
+    {
+        return Ok(cached);
+    }
+
+    let _permit = limits.acquire_io().await;
+    let stats = project_stats::collect_project_stats(&project_dir).await?;
+    cache.set_project_stats(project_dir, stats.clone());
+    Ok(stats)
+}
+
+/// 锁定或解锁会话
+///
+/// 锁定列表保存在 `~/.mo/CCR/locked-sessions.json`。锁定后所有经 file_guard 的写入
+/// （编辑、删除、修复、恢复备份等）都返回 `SessionLocked` 错误，`force` 参数也无法绕过；
+/// 解锁只能通过本 command 显式完成。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `locked` - true 锁定，false 解锁
+/// - `cache` - Tauri managed state，同步更新项目列表缓存中的锁定标记
+///
+/// # 错误
+/// 锁定列表读取、解析或写入失败时返回错误
+#[tauri::command]
+pub async fn set_session_locked(
+    session_file_path: String,
+    locked: bool,
+    cache: State<'_, AppCache>,
+) -> Result<(), String> {
+    session_lock::set_locked(&session_file_path, locked).await?;
+    cache.set_session_locked(&session_file_path, locked);
+    Ok(())
+}
+
+/// 保存会话草稿（下次 resume 时要补充的内容）
+///
+/// 草稿保存在 `~/.mo/CCR/drafts.json`，绝不写入会话 JSONL，因此锁定的会话也可以保存草稿。
+/// 去除首尾空白后为空时等同于删除草稿。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `text` - 草稿文本
+/// - `cache` - Tauri managed state，同步更新项目列表缓存中的草稿标记
+///
+/// # 错误
+/// 草稿文件读取、解析或写入失败时返回错误
+#[tauri::command]
+pub async fn save_session_draft(
+    session_file_path: String,
+    text: String,
+    cache: State<'_, AppCache>,
+) -> Result<(), String> {
+    let has_draft = drafts::save(&session_file_path, &text).await?;
+    cache.set_session_has_draft(&session_file_path, has_draft);
+    Ok(())
+}
+
+/// 读取会话草稿
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+///
+/// # 返回值
+/// 草稿文本；没有草稿时返回 None
+///
+/// # 错误
+/// 草稿文件读取或解析失败时返回错误
+#[tauri::command]
+pub async fn get_session_draft(session_file_path: String) -> Result<Option<String>, String> {
+    drafts::get(&session_file_path).await
+}
+
+/// 删除会话草稿
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `cache` - Tauri managed state，同步更新项目列表缓存中的草稿标记
+///
+/// # 错误
+/// 草稿文件读取、解析或写入失败时返回错误
+#[tauri::command]
+pub async fn delete_session_draft(
+    session_file_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<(), String> {
+    drafts::delete(&session_file_path).await?;
+    cache.set_session_has_draft(&session_file_path, false);
+    Ok(())
+}
+
+/// 保存会话阅读状态
+///
+/// 状态由前端序列化为 JSON（滚动到的 display_id、展开的块、激活的过滤器），
+/// 保存在 `~/.mo/CCR/view-state/` 下（按会话路径区分），后端不解析其含义。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `state_json` - 阅读状态 JSON，最大 64KB
+///
+/// # 错误
+/// 路径不是有效的会话文件、状态过大、不是合法 JSON 或写入失败时返回错误
+#[tauri::command]
+pub async fn save_view_state(session_file_path: String, state_json: String) -> Result<(), String> {
+    validated_session_path(&session_file_path)?;
+    view_state::save(&session_file_path, &state_json).await
+}
+
+/// 读取会话阅读状态
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+///
+/// # 返回值
+/// 保存时的 JSON 原文；没有保存过时返回 None
+///
+/// # 错误
+/// 路径不是有效的会话文件或状态文件读取失败时返回错误
+#[tauri::command]
+pub async fn get_view_state(session_file_path: String) -> Result<Option<String>, String> {
+    validated_session_path(&session_file_path)?;
+    view_state::get(&session_file_path).await
+}
+
+/// 重命名会话
+///
+/// 标题总是保存为 CCR 别名（`~/.mo/CCR/session-aliases.json`）。`write_native` 为 true 时，
+/// 还会按会话中实际存在的格式在 JSONL 中插入 / 更新 `custom-title` 或 `summary` 行
+/// （经 `file_guard` 写入，其余条目保持不变），让 Claude Code 的 `--resume` 列表也显示新名字。
+/// 无法确定格式时拒绝写回，此时别名也不会保存。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `title` - 新标题；去除首尾空白后为空时清除别名
+/// - `write_native` - 是否写回 Claude Code 原生标题
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 保存后的别名与写回时使用的原生标题格式
+///
+/// # 错误
+/// - `write_native` 为 true 但标题为空，或无法确定原生标题格式时返回错误
+/// - 会话被锁定、文件读写失败或别名文件写入失败时返回错误
+#[tauri::command]
+pub async fn rename_session(
+    session_file_path: String,
+    title: String,
+    write_native: bool,
+    cache: State<'_, AppCache>,
+) -> Result<RenameSessionResult, String> {
+    let title = title.trim();
+    let mut native_format = None;
+
+    if write_native {
+        if title.is_empty() {
+            return Err("标题为空，无法写回原生标题".to_string());
+        }
+        let session_id = Path::new(&session_file_path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let _guard = cache.lock_session(&session_file_path).await;
+        let mut messages = parser::read_messages_for_write(&session_file_path).await?;
+        native_format = Some(session_title::apply_native_title(&mut messages, title, &session_id)?);
+
+        // 写回文件（通过 file_guard 安全写入）
+        parser::write_messages(&session_file_path, &messages, "rename_session", &cache).await?;
+        cache.clear_undo(&session_file_path, "重命名会话");
+
+        // 重新 transform 并更新缓存
+        let (transformed, search_texts, original_texts) = transformer::transform_session(&messages);
+        cache.set_session(&session_file_path, transformed, search_texts, original_texts);
+    }
+
+    let alias = session_title::set_alias(&session_file_path, title).await?;
+    if native_format.is_some() {
+        // 原生标题影响 scanner 提取的 name / summary，需要重新扫描
+        cache.invalidate_projects();
+    } else {
+        cache.set_session_alias(&session_file_path, alias.clone());
+    }
+
+    Ok(RenameSessionResult { alias, native_format })
+}
+
+/// 将碎片项目归并回父项目
+///
+/// 碎片项目由 `scan_projects` 在 `Project.fragmentOf` 中标记。归并前会重新扫描并复核判定，
+/// 然后把碎片目录中的会话文件（及其子目录）移入父项目目录、迁移草稿与别名，最后删除空的碎片目录。
+///
+/// # 参数
+/// - `fragment_name` - 碎片项目目录名
+/// - `target_name` - 父项目目录名（即 `fragmentOf` 的值）
+/// - `source_id` - 两个项目所属的数据源 ID（即 `Project.source`）；None 表示默认数据源
+/// - `cache` - Tauri managed state，内存缓存
+///
+/// # 返回值
+/// 移动的会话文件数
+///
+/// # 错误
+/// 复核不通过、父项目中有同名文件、会话已锁定或文件移动失败时返回错误
+#[tauri::command]
+pub async fn merge_fragment_project(
+    fragment_name: String,
+    target_name: String,
+    source_id: Option<String>,
+    cache: State<'_, AppCache>,
+) -> Result<usize, String> {
+    let claude_path = match source_id {
+        Some(source_id) => PathBuf::from(data_sources::find(&source_id).await?.path),
+        None => path::get_claude_data_path()?,
+    };
+    let projects_dir = claude_path.join("projects");
+    fragments::merge_fragment(&projects_dir, &fragment_name, &target_name, &cache).await
+}
+
+/// 归档项目：默认不再出现在 `scan_projects` 的结果中
+///
+/// 归档状态保存在 `~/.mo/CCR/archived.json`，以项目目录的绝对路径为键，
+/// 不移动、不修改任何 Claude 原始文件。
+///
+/// # 参数
+/// - `project_name` - 项目目录名
+/// - `source_id` - 项目所属数据源 ID；None 表示默认数据源
+/// - `cache` - Tauri managed state，归档状态变化后使项目列表缓存失效
+///
+/// # 返回值
+/// 归档状态是否发生了变化（已归档时返回 false）
+///
+/// # 错误
+/// 项目名称无效、数据源或项目目录不存在，以及归档列表读写失败时返回错误
+#[tauri::command]
+pub async fn archive_project(
+    project_name: String,
+    source_id: Option<String>,
+    cache: State<'_, AppCache>,
+) -> Result<bool, String> {
+    set_project_archived(&project_name, source_id, true, &cache).await
+}
+
+/// 取消归档项目
+///
+/// # 参数
+/// - `project_name` - 项目目录名
+/// - `source_id` - 项目所属数据源 ID；None 表示默认数据源
+/// - `cache` - Tauri managed state，归档状态变化后使项目列表缓存失效
+///
+/// # 返回值
+/// 归档状态是否发生了变化（未归档时返回 false）
+///
+/// # 错误
+/// 项目名称无效、数据源或项目目录不存在，以及归档列表读写失败时返回错误
+#[tauri::command]
+pub async fn unarchive_project(
+    project_name: String,
+    source_id: Option<String>,
+    cache: State<'_, AppCache>,
+) -> Result<bool, String> {
+    set_project_archived(&project_name, source_id, false, &cache).await
+}
+
+/// 设置项目归档状态的公共实现
+async fn set_project_archived(
+    project_name: &str,
+    source_id: Option<String>,
+    archived: bool,
+    cache: &AppCache,
+) -> Result<bool, String> {
+    let source_id = source_id.unwrap_or_else(|| data_sources::DEFAULT_SOURCE_ID.to_string());
+    let project_dir = resolve_project_dir(project_name, &source_id).await?;
+    let changed = archive::set_archived(&project_dir, archived).await?;
+    // 归档改变的是项目列表本身（分页与总数），需要重新列出
+    if changed {
+        cache.invalidate_projects();
+    }
+    Ok(changed)
+}
+
+/// 比对项目目录名的解码路径与最新会话记录的 cwd
+///
+/// 读取项目最新会话中最后一条带 `cwd` 的消息，与目录名的解码结果比对。
+/// 两者不一致时前端可让用户确认，再通过 `set_project_path_override` 写入真实路径。
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
+/// - `project_name` - 项目目录名
+///
+/// # 返回值
+/// 解码路径、cwd 路径、当前覆盖路径与两者是否一致
+///
+/// # 错误
+/// 项目名称无效、项目目录不存在或读取失败时返回错误
+#[tauri::command]
+pub async fn resolve_project_real_path(claude_path: String, project_name: String) -> Result<ProjectPathResolution, String> {
+    fragments::validate_project_name(&project_name)?;
+    let project_dir = PathBuf::from(&claude_path).join("projects").join(&project_name);
+    if !project_dir.is_dir() {
+        return Err(format!("项目目录不存在: {}", project_name));
+    }
+    project_meta::resolve_real_path(&project_dir, &project_name).await
+}
+
+/// 设置或清除项目的真实路径覆盖
+///
+/// 覆盖写入 `~/.mo/CCR/projects-meta.json`，之后扫描得到的 `Project.path` 直接使用该路径，
+/// Resume、在文件管理器中打开等依赖项目路径的功能随之生效。
+///
+/// # 参数
+/// - `project_name` - 项目目录名
+/// - `path` - 真实路径（绝对路径）；为空字符串时清除覆盖
+/// - `cache` - Tauri managed state，覆盖变化后使项目列表缓存失效
+///
+/// # 返回值
+/// 覆盖是否发生了变化
+///
+/// # 错误
+/// 项目名称无效、路径不是绝对路径或项目元数据读写失败时返回错误
+#[tauri::command]
+pub async fn set_project_path_override(
+    project_name: String,
+    path: String,
+    cache: State<'_, AppCache>,
+) -> Result<bool, String> {
+    fragments::validate_project_name(&project_name)?;
+    let path = path.trim();
+    if !path.is_empty() && !Path::new(path).is_absolute() {
+        return Err(format!("项目路径必须是绝对路径: {}", path));
+    }
+    let changed = project_meta::set_override(&project_name, path).await?;
+    if changed {
+        cache.invalidate_projects();
+    }
+    Ok(changed)
+}
+
+/// 校验项目名称并定位项目目录
+///
+/// # 错误
+/// 项目名称无效、数据源或项目目录不存在时返回错误
+async fn resolve_project_dir(project_name: &str, source_id: &str) -> Result<PathBuf, String> {
+    fragments::validate_project_name(project_name)?;
+    let source = data_sources::find(source_id).await?;
+    let project_dir = PathBuf::from(&source.path).join("projects").join(project_name);
+    if !project_dir.is_dir() {
+        return Err(format!("项目目录不存在: {}", project_name));
+    }
+    Ok(project_dir)
+}
+
+/// 切换项目的置顶状态
+///
+/// 置顶列表保存在 `~/.mo/CCR/pins.json`，以项目目录的绝对路径为键。
+///
+/// # 参数
+/// - `project_name` - 项目目录名
+/// - `source_id` - 项目所属数据源 ID；None 表示默认数据源
+/// - `cache` - Tauri managed state，同步更新项目列表缓存中的置顶标记
+///
+/// # 返回值
+/// 切换后是否置顶
+///
+/// # 错误
+/// 项目名称无效、数据源或项目目录不存在，以及置顶列表读写失败时返回错误
+#[tauri::command]
+pub async fn toggle_pin_project(
+    project_name: String,
+    source_id: Option<String>,
+    cache: State<'_, AppCache>,
+) -> Result<bool, String> {
+    let source_id = source_id.unwrap_or_else(|| data_sources::DEFAULT_SOURCE_ID.to_string());
+    let project_dir = resolve_project_dir(&project_name, &source_id).await?;
+
+    let pinned = pins::toggle_project(&project_dir).await?;
+    if source_id == data_sources::DEFAULT_SOURCE_ID {
+        cache.set_project_pinned(&project_name, pinned);
+    }
+    Ok(pinned)
+}
+
+/// 切换会话的置顶状态
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `cache` - Tauri managed state，同步更新项目列表缓存中的置顶标记与会话顺序
+///
+/// # 返回值
+/// 切换后是否置顶
+///
+/// # 错误
+/// 会话路径校验失败或置顶列表读写失败时返回错误
+#[tauri::command]
+pub async fn toggle_pin_session(
+    session_file_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<bool, String> {
+    validated_session_path(&session_file_path)?;
+    let pinned = pins::toggle_session(&session_file_path).await?;
+    cache.set_session_pinned(&session_file_path, pinned);
+    Ok(pinned)
+}
+
+/// 记录会话在 CCR 中被打开
+///
+/// 最近查看时间记为当前时间，之后文件再被修改时扫描结果与 `claude-data-changed` 事件
+/// 中的 `updated_since_viewed` 为 true（见 `services::last_viewed`）。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `cache` - Tauri managed state，同步清除项目列表缓存中的标记
+///
+/// # 错误
+/// 会话路径校验失败或查看记录读写失败时返回错误
+#[tauri::command]
+pub async fn record_session_opened(
+    session_file_path: String,
+    cache: State<'_, AppCache>,
+) -> Result<(), String> {
+    validated_session_path(&session_file_path)?;
+    last_viewed::mark_viewed(&session_file_path).await?;
+    cache.set_session_updated_since_viewed(&session_file_path, false);
+    Ok(())
+}
+
+/// 开始监听全部数据源的 `projects/` 目录
+///
+/// 文件变化经防抖后自动失效缓存，并向前端广播 `claude-data-changed` 事件。
+///
+/// # 参数
+/// - `app` - Tauri AppHandle，监听任务用它访问缓存与广播事件
+/// - `watcher` - Tauri managed state，监听状态
+///
+/// # 返回值
+/// 本次是否新启动了监听（已在监听时返回 false）
+///
+/// # 错误
+/// 没有可监听的目录或创建 watcher 失败时返回错误
+#[tauri::command]
+pub async fn start_watching(app: AppHandle, watcher: State<'_, WatcherState>) -> Result<bool, String> {
+    watcher.start(app)
+}
+
+/// 停止监听数据目录
+///
+/// # 返回值
+/// 本次是否停止了正在运行的监听
+#[tauri::command]
+pub async fn stop_watching(watcher: State<'_, WatcherState>) -> Result<bool, String> {
+    Ok(watcher.stop())
 }
@@ -16,10 +16,14 @@
 //! - 内部获取 Mutex 锁，检查初始化状态后调用 services 层函数
 //! - 错误统一转为 `String` 类型返回给前端
 
+use std::path::Path;
+
 use tauri::State;
 
 use crate::models::retrospect::{FileTreeNode, RetrospectTimeline};
+use crate::services::limits::AppLimits;
 use crate::services::retrospect::RetrospectState;
+use crate::utils::disk;
 
 /// 初始化回溯：扫描所有会话，提取操作列表
 ///
@@ -217,15 +221,18 @@ pub async fn retrospect_save_file(
 /// - `index` - 目标操作序号（0-based）
 /// - `save_to` - ZIP 文件保存路径（绝对路径）
 /// - `state` - Tauri managed state（自动注入）
+/// - `limits` - 全局 I/O 并发信号量（自动注入），写 ZIP 前获取许可
 ///
 /// # 错误
 /// - 未初始化
+/// - 目标分区剩余空间不足（按未压缩总大小预估）
 /// - ZIP 创建或写入失败
 #[tauri::command]
 pub async fn retrospect_export_zip(
     index: usize,
     save_to: String,
     state: State<'_, RetrospectState>,
+    limits: State<'_, AppLimits>,
 ) -> Result<(), String> {
     // 在 Mutex 锁内收集文件数据，然后立即释放锁
     let files = {
@@ -251,7 +258,12 @@ pub async fn retrospect_export_zip(
     };
     // guard 在此处已释放，可以安全地执行 async I/O
 
-    // 异步写入 ZIP 文件
+    // 磁盘空间预估：以未压缩总大小作为 ZIP 体积上限
+    let total_bytes: u64 = files.iter().map(|(_, content)| content.len() as u64).sum();
+    disk::ensure_free_space(Path::new(&save_to), total_bytes).await?;
+
+    // 异步写入 ZIP 文件（受全局 I/O 并发数限制）
+    let _permit = limits.acquire_io().await;
     crate::services::retrospect::export_zip_from_files(files, &save_to).await?;
 
     log::info!("retrospect_export_zip: index={} → {}", index, save_to);
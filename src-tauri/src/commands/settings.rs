@@ -2,23 +2,36 @@
 //!
 //! 提供设置文件和环境配置的读写 Tauri command 处理函数：
 //! - `get_claude_data_path` - 获取 `~/.claude/` 路径
+//! - `list_data_sources` / `save_data_sources` - 读写 Claude 数据源列表（默认目录 + 额外目录）
+//! - `get_display_timezone` / `save_display_timezone` - 读写时间显示与按天切分的时区（local / utc / ±HH:MM）
 //! - `read_settings` / `save_settings` - 读写 Claude Code 的 settings.json
+//! - `get_appearance_config` / `save_appearance_config` - 读写状态栏（statusLine）与输出样式（outputStyle）
+//! - `read_output_style` / `save_output_style` - 读写 `output-styles/` 下的自定义输出样式文件
 //! - `read_env_config` / `save_env_config` - 读写 CCR 环境切换器配置
 //! - `read_history` - 读取命令历史记录
 //! - `check_file_exists` - 检查文件是否存在
+//! - `check_files_exist` - 批量并发检查一批文件是否存在（单次 IPC）
+//!
+//! settings.json 与 env-profiles.json 严格解析失败时退回 JSONC 容错解析（见 `utils::jsonc`）；
+//! 覆盖这类文件前需要前端传入 `discard_comments` 确认注释将丢失。
 //!
 //! 注意：文件管理器定位功能（原 `open_in_explorer`）已迁移到 `tauri-plugin-opener`，
 //! 使用 OS 原生 API 替代手动拼接 shell 命令。
 
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 use tauri::State;
+use tokio::task::JoinSet;
 
 use crate::models::message::HistoryEntry;
-use crate::models::settings::{ClaudeSettings, EnvSwitcherConfig};
+use crate::models::settings::{ClaudeSettings, EnvSwitcherConfig, LenientParsed};
+use crate::services::appearance::{self, AppearanceConfig, StatusLineConfig};
 use crate::services::cache::AppCache;
+use crate::services::data_sources::{self, DataSource};
 use crate::services::file_guard;
-use crate::utils::path;
+use crate::utils::time::{self, DisplayTimezone};
+use crate::utils::{app_config, jsonc, path};
 
 /// 获取 Claude Code 数据目录的绝对路径
 ///
@@ -36,33 +49,112 @@ pub async fn get_claude_data_path() -> Result<String, String> {
     Ok(path.to_string_lossy().to_string())
 }
 
+/// 列出全部 Claude 数据源
+///
+/// 第一项始终是默认数据源 `~/.claude/`（ID 为 "default"），
+/// 其后是 `~/.mo/CCR/data-sources.json` 中登记的额外数据目录。
+///
+/// # 返回值
+/// 数据源列表
+///
+/// # 错误
+/// 无法确定主目录，或配置文件存在但读取、解析失败时返回错误
+#[tauri::command]
+pub async fn list_data_sources() -> Result<Vec<DataSource>, String> {
+    data_sources::load_sources().await
+}
+
+/// 保存额外数据源（整体替换，不含默认数据源）
+///
+/// # 参数
+/// - `sources` - 额外数据源列表
+/// - `cache` - Tauri managed state，数据源变化后使项目列表缓存失效
+///
+/// # 返回值
+/// 保存后的全部数据源（含默认数据源）
+///
+/// # 错误
+/// ID 为空、重复或为保留值，目录不存在或重复，以及写入失败时返回错误
+#[tauri::command]
+pub async fn save_data_sources(
+    sources: Vec<DataSource>,
+    cache: State<'_, AppCache>,
+) -> Result<Vec<DataSource>, String> {
+    let saved = data_sources::save_sources(sources).await?;
+    cache.invalidate_projects();
+    Ok(saved)
+}
+
+/// 获取当前显示时区
+///
+/// # 返回值
+/// `"local"`、`"utc"` 或 `"±HH:MM"`
+#[tauri::command]
+pub async fn get_display_timezone() -> DisplayTimezone {
+    time::display_timezone()
+}
+
+/// 保存显示时区并立即生效
+///
+/// 写入 `~/.mo/CCR/app-config.json` 的 `displayTimezone` 字段（保留文件中的其他字段），
+/// 随后切换进程内的显示时区，并使依赖日期切分的统计缓存失效。
+///
+/// # 参数
+/// - `timezone` - `"local"`、`"utc"` 或固定偏移（如 `"+08:00"`）
+/// - `cache` - Tauri managed state，用于使统计缓存失效
+///
+/// # 返回值
+/// 规范化后的时区
+///
+/// # 错误
+/// 时区格式无效、现有配置文件不是合法 JSON 对象或写入失败时返回错误
+#[tauri::command]
+pub async fn save_display_timezone(timezone: String, cache: State<'_, AppCache>) -> Result<DisplayTimezone, String> {
+    let tz = DisplayTimezone::parse(&timezone)?;
+    app_config::set_field("displayTimezone", serde_json::Value::String(tz.into())).await?;
+
+    time::set_display_timezone(tz);
+    cache.invalidate_project_stats();
+    Ok(tz)
+}
+
 /// 读取 Claude Code 设置文件
 ///
 /// 从 `~/.claude/settings.json` 加载用户设置。
 /// 如果文件不存在（如首次安装 Claude Code），返回空的 JSON 对象 `{}`。
+/// 严格解析失败时退回 JSONC 容错解析（剥离注释与尾逗号），并以 `was_lenient` 标记。
 ///
 /// # 参数
 /// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
 ///
 /// # 返回值
-/// 返回解析后的设置 JSON 对象
+/// 返回解析后的设置 JSON 对象及是否经过容错解析
 ///
 /// # 错误
-/// 文件存在但无法读取或 JSON 解析失败时返回错误
+/// 文件存在但无法读取，或严格与容错解析都失败时返回错误
 #[tauri::command]
-pub async fn read_settings(claude_path: String) -> Result<ClaudeSettings, String> {
-    let settings_path = Path::new(&claude_path).join("settings.json");
+pub async fn read_settings(claude_path: String) -> Result<LenientParsed<ClaudeSettings>, String> {
+    let (value, was_lenient) = read_settings_file(Path::new(&claude_path)).await?;
+    Ok(LenientParsed { value, was_lenient })
+}
+
+/// 读取并解析 settings.json（支持 JSONC），文件不存在时返回空对象
+///
+/// # 返回值
+/// `(设置对象, 是否经过容错解析)`
+async fn read_settings_file(claude_path: &Path) -> Result<(ClaudeSettings, bool), String> {
+    let settings_path = claude_path.join("settings.json");
 
     // 文件不存在时返回空的 JSON 对象，与前端行为保持一致
     if !settings_path.exists() {
-        return Ok(serde_json::json!({}));
+        return Ok((serde_json::json!({}), false));
     }
 
     let content = tokio::fs::read_to_string(&settings_path)
         .await
         .map_err(|e| format!("读取设置文件失败: {}", e))?;
 
-    serde_json::from_str(&content).map_err(|e| format!("解析设置文件失败: {}", e))
+    jsonc::parse_lenient(&content).map_err(|e| format!("解析设置文件失败: {}", e))
 }
 
 /// 保存 Claude Code 设置文件
@@ -73,17 +165,21 @@ pub async fn read_settings(claude_path: String) -> Result<ClaudeSettings, String
 /// # 参数
 /// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
 /// - `settings` - 要保存的完整设置对象
+/// - `discard_comments` - 现有文件含注释 / 尾逗号时是否确认丢弃（省略视为未确认）
 /// - `cache` - Tauri managed state，用于 file_guard 注册临时备份
 ///
 /// # 错误
-/// 序列化失败、路径验证失败、备份失败或文件写入失败时返回错误
+/// - 现有文件含注释且未确认丢弃时返回 `CommentsWillBeLost: ...` 错误
+/// - 序列化失败、路径验证失败、备份失败或文件写入失败时返回错误
 #[tauri::command]
 pub async fn save_settings(
     claude_path: String,
     settings: ClaudeSettings,
+    discard_comments: Option<bool>,
     cache: State<'_, AppCache>,
 ) -> Result<(), String> {
     let settings_path = Path::new(&claude_path).join("settings.json");
+    jsonc::ensure_no_comments_lost(&settings_path, discard_comments.unwrap_or(false)).await?;
 
     // 使用 2 空格缩进格式化 JSON，与前端 JSON.stringify(settings, null, 2) 保持一致
     let content = serde_json::to_string_pretty(&settings)
@@ -96,32 +192,154 @@ pub async fn save_settings(
         "save_settings",
         &cache,
     )
-    .await
+    .await?;
+
+    Ok(())
+}
+
+/// 读取外观配置
+///
+/// 聚合 settings.json 的 `statusLine` / `outputStyle` 字段与 `output-styles/` 下的自定义样式列表。
+/// settings.json 不存在或字段缺失时返回默认值。
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
+///
+/// # 返回值
+/// 状态栏配置、当前输出样式名与自定义样式列表
+///
+/// # 错误
+/// settings.json 存在但无法读取或解析时返回错误
+#[tauri::command]
+pub async fn get_appearance_config(claude_path: String) -> Result<AppearanceConfig, String> {
+    let claude_path = Path::new(&claude_path);
+    let (settings, _) = read_settings_file(claude_path).await?;
+    Ok(appearance::load_appearance(claude_path, &settings).await)
+}
+
+/// 保存外观配置
+///
+/// 只改动 settings.json 的 `statusLine` / `outputStyle` 字段，其余字段原样保留，
+/// 经 `file_guard` 安全写入。
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
+/// - `status_line` - 状态栏配置，省略表示移除 `statusLine`
+/// - `output_style` - 输出样式名，省略或为 "default" 表示移除 `outputStyle`
+/// - `discard_comments` - settings.json 含注释 / 尾逗号时是否确认丢弃（省略视为未确认）
+/// - `cache` - Tauri managed state，用于 file_guard 注册临时备份
+///
+/// # 错误
+/// - settings.json 含注释且未确认丢弃时返回 `CommentsWillBeLost: ...` 错误
+/// - settings.json 无法解析、路径验证失败或写入失败时返回错误
+#[tauri::command]
+pub async fn save_appearance_config(
+    claude_path: String,
+    status_line: Option<StatusLineConfig>,
+    output_style: Option<String>,
+    discard_comments: Option<bool>,
+    cache: State<'_, AppCache>,
+) -> Result<(), String> {
+    let claude_path = Path::new(&claude_path);
+    let settings_path = claude_path.join("settings.json");
+    jsonc::ensure_no_comments_lost(&settings_path, discard_comments.unwrap_or(false)).await?;
+    let (mut settings, _) = read_settings_file(claude_path).await?;
+    appearance::apply_appearance(&mut settings, status_line.as_ref(), output_style.as_deref())?;
+
+    let content = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("序列化设置失败: {}", e))?;
+    file_guard::safe_write_file(
+        &settings_path.to_string_lossy(),
+        content.as_bytes(),
+        "save_appearance_config",
+        &cache,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// 读取自定义输出样式文件
+///
+/// # 参数
+/// - `path` - 样式文件的绝对路径（必须位于 `~/.claude/output-styles/` 下）
+///
+/// # 返回值
+/// 样式文件的完整内容（含 frontmatter）
+///
+/// # 错误
+/// 路径不是样式文件或读取失败时返回错误
+#[tauri::command]
+pub async fn read_output_style(path: String) -> Result<String, String> {
+    let claude_path = path::get_claude_data_path()?;
+    let style_path = appearance::validate_style_path(&claude_path, &path)?;
+
+    tokio::fs::read_to_string(&style_path)
+        .await
+        .map_err(|e| format!("读取输出样式失败: {}", e))
+}
+
+/// 保存（或新建）自定义输出样式文件
+///
+/// 样式目录不存在时自动创建，写入经 `file_guard`（覆盖已有文件前自动备份）。
+///
+/// # 参数
+/// - `path` - 样式文件的绝对路径（必须位于 `~/.claude/output-styles/` 下的 `.md` 文件）
+/// - `content` - 完整文件内容（含 frontmatter）
+/// - `cache` - Tauri managed state，用于 file_guard 注册临时备份
+///
+/// # 错误
+/// 路径不是样式文件、目录创建失败或写入失败时返回错误
+#[tauri::command]
+pub async fn save_output_style(
+    path: String,
+    content: String,
+    cache: State<'_, AppCache>,
+) -> Result<(), String> {
+    let claude_path = path::get_claude_data_path()?;
+    let style_path = appearance::validate_style_path(&claude_path, &path)?;
+
+    tokio::fs::create_dir_all(claude_path.join(appearance::OUTPUT_STYLES_DIR))
+        .await
+        .map_err(|e| format!("创建输出样式目录失败: {}", e))?;
+    file_guard::safe_write_file(
+        &style_path.to_string_lossy(),
+        content.as_bytes(),
+        "save_output_style",
+        &cache,
+    )
+    .await?;
+
+    Ok(())
 }
 
 /// 读取环境切换器配置
 ///
 /// 从 `~/.mo/CCR/env-profiles.json` 加载所有环境配置组及激活状态。
 /// 如果配置文件不存在（首次使用），返回空的默认配置。
+/// 严格解析失败时退回 JSONC 容错解析，并以 `was_lenient` 标记。
 ///
 /// # 参数
 /// - `_claude_path` - Claude 数据路径（保留参数，保持前端 API 一致性）
 ///
 /// # 返回值
-/// 返回包含所有配置组和激活 ID 的 EnvSwitcherConfig 对象
+/// 返回包含所有配置组和激活 ID 的 EnvSwitcherConfig 对象及是否经过容错解析
 ///
 /// # 错误
-/// 文件存在但无法读取或 JSON 解析失败时返回错误
+/// 文件存在但无法读取，或严格与容错解析都失败时返回错误
 #[tauri::command]
-pub async fn read_env_config(_claude_path: String) -> Result<EnvSwitcherConfig, String> {
+pub async fn read_env_config(_claude_path: String) -> Result<LenientParsed<EnvSwitcherConfig>, String> {
     let ccr_path = path::get_ccr_config_path()?;
     let config_path = ccr_path.join("env-profiles.json");
 
     // 配置文件不存在时返回空的默认配置，与前端行为保持一致
     if !config_path.exists() {
-        return Ok(EnvSwitcherConfig {
-            profiles: vec![],
-            active_profile_id: None,
+        return Ok(LenientParsed {
+            value: EnvSwitcherConfig {
+                profiles: vec![],
+                active_profile_id: None,
+            },
+            was_lenient: false,
         });
     }
 
@@ -129,7 +347,9 @@ pub async fn read_env_config(_claude_path: String) -> Result<EnvSwitcherConfig,
         .await
         .map_err(|e| format!("读取环境配置文件失败: {}", e))?;
 
-    serde_json::from_str(&content).map_err(|e| format!("解析环境配置文件失败: {}", e))
+    let (value, was_lenient) =
+        jsonc::parse_lenient(&content).map_err(|e| format!("解析环境配置文件失败: {}", e))?;
+    Ok(LenientParsed { value, was_lenient })
 }
 
 /// 保存环境切换器配置到文件
@@ -140,15 +360,20 @@ pub async fn read_env_config(_claude_path: String) -> Result<EnvSwitcherConfig,
 /// # 参数
 /// - `_claude_path` - Claude 数据路径（保留参数，保持前端 API 一致性）
 /// - `config` - 要保存的完整环境切换器配置对象
+/// - `discard_comments` - 现有文件含注释 / 尾逗号时是否确认丢弃（省略视为未确认）
 ///
 /// # 错误
-/// 目录创建失败或文件写入失败时返回错误
+/// - 现有文件含注释且未确认丢弃时返回 `CommentsWillBeLost: ...` 错误
+/// - 目录创建失败或文件写入失败时返回错误
 #[tauri::command]
 pub async fn save_env_config(
     _claude_path: String,
     config: EnvSwitcherConfig,
+    discard_comments: Option<bool>,
 ) -> Result<(), String> {
     let ccr_path = path::get_ccr_config_path()?;
+    jsonc::ensure_no_comments_lost(&ccr_path.join("env-profiles.json"), discard_comments.unwrap_or(false))
+        .await?;
 
     // 确保 CCR 配置目录存在，递归创建所有缺失的父目录
     if !ccr_path.exists() {
@@ -219,3 +444,49 @@ pub async fn read_history(claude_path: String) -> Result<Vec<HistoryEntry>, Stri
 pub async fn check_file_exists(file_path: String) -> bool {
     std::path::Path::new(&file_path).exists()
 }
+
+/// `check_files_exist` 单次调用允许的最大路径数（去重后）
+const MAX_BATCH_PATHS: usize = 1000;
+
+/// 批量检查一批路径是否存在
+///
+/// 渲染大量工具结果时，前端把可见消息中的文件路径合并为一次调用，
+/// 避免每个"打开文件位置"按钮各发一次 `check_file_exists` 造成 IPC 风暴。
+/// 路径先去重，再使用 JoinSet 并发 stat；单个 stat 失败视为不存在。
+///
+/// # 参数
+/// - `paths` - 要检查的文件绝对路径列表（可重复）
+///
+/// # 返回值
+/// 路径 → 是否存在 的映射（每个去重后的路径一项）
+///
+/// # 错误
+/// 去重后的路径数超过 `MAX_BATCH_PATHS` 时返回错误
+#[tauri::command]
+pub async fn check_files_exist(paths: Vec<String>) -> Result<HashMap<String, bool>, String> {
+    let unique: HashSet<String> = paths.into_iter().collect();
+    if unique.len() > MAX_BATCH_PATHS {
+        return Err(format!(
+            "单次最多检查 {} 个路径，实际 {} 个",
+            MAX_BATCH_PATHS,
+            unique.len()
+        ));
+    }
+
+    let mut join_set = JoinSet::new();
+    for file_path in unique {
+        join_set.spawn(async move {
+            let exists = tokio::fs::metadata(&file_path).await.is_ok();
+            (file_path, exists)
+        });
+    }
+
+    let mut result = HashMap::new();
+    while let Some(joined) = join_set.join_next().await {
+        // 任务异常终止时无法得知路径，前端对缺失的条目同样按不存在处理
+        if let Ok((file_path, exists)) = joined {
+            result.insert(file_path, exists);
+        }
+    }
+    Ok(result)
+}
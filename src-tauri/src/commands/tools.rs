@@ -2,23 +2,50 @@
 //!
 //! 提供实用工具相关的 Tauri command 处理函数：
 //! - `read_resume_config` / `save_resume_config` - 一键 Resume 配置读写
-//! - `build_resume_command` - 构建 resume 命令字符串（供复制到剪贴板）
-//! - `open_resume_terminal` - 打开终端执行 claude --resume 命令
+//! - `build_resume_command` - 构建 resume 命令字符串（供复制到剪贴板），可附带会话草稿
+//! - `open_resume_terminal` - 打开终端执行 claude --resume 命令，可附带会话草稿并在启动后清除
+//! - `reveal_in_file_manager` - 在系统文件管理器中定位文件（移动端不支持）
 //! - `read_backup_config` / `save_backup_config` - 备份配置读写
 //! - `get_temp_backups` - 获取本次运行期间的临时备份列表
+//! - `restore_temp_backup` / `restore_auto_backup` - 预检后从临时备份 / 主动备份恢复文件
+//! - `list_all_backups` / `delete_backup` - 跨所有会话的备份列表与删除（备份管理页）
+//! - `read_audit_log` - 按路径 / 批次过滤、分页读取写入与删除的审计日志
+//! - `cleanup_temp_artifacts` - 按保留策略手动清理 TEMP 下的临时资源
+//! - `get_recovery_candidates` / `resolve_recovery` - 检测并处理上次异常退出留下的写入残留
+//! - `run_startup_diagnostics` - 启动自检：数据目录、权限、磁盘空间、CLI 可用性
+//! - `get_onboarding_state` - 引导状态：未安装 Claude Code / 尚无项目 / 正常，以及 claude CLI 是否在 PATH 中
+//! - `generate_diagnostic_bundle` - 生成脱敏的反馈诊断包（ZIP），供报 issue 时附上
 //! - `list_fixers` - 获取所有可用的一键修复项列表
 //! - `execute_fixer` - 执行指定的一键修复
+//! - `get_command_trace` - 获取最近的命令调用记录（名称、参数摘要、耗时、结果状态）
+//! - `get_cache_stats` - 获取会话缓存的估算内存占用与淘汰次数
+//! - `verify_cache_consistency` - 对比缓存与磁盘状态，报告不一致的条目并可选自动失效（诊断用）
+//! - `make_session_fixture` - 把会话脱敏为测试样本（开发用，仅 debug 构建，前端无入口）
+//! - `run_benchmark` - 扫描 / 读取 / 搜索的端到端基准测试（诊断用，前端无入口）
 //!
 //! 所有 CCR 配置存储在 `~/.mo/CCR/` 目录下，
 //! 与 Claude Code 的 `settings.json` 完全隔离。
 
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
 
-use crate::services::cache::AppCache;
-use crate::services::file_guard::{self, BackupConfig, TempBackupEntry};
+use crate::commands::validation::validated_session_path;
+use crate::services::audit::{self, AuditFilter, AuditLogPage};
+use crate::services::backups::{self, BackupItem};
+use crate::services::benchmark::{self, BenchmarkReport, BenchmarkScenario};
+use crate::services::cache::{AppCache, CacheStats};
+use crate::services::cache_check::{self, CacheConsistencyReport};
+use crate::services::command_trace::{self, CommandTraceEntry};
+use crate::services::diagnostic_bundle::{self, DiagnosticBundleManifest};
+use crate::services::diagnostics::{self, DiagnosticItem, OnboardingState};
+use crate::services::drafts;
+use crate::services::file_guard::{self, BackupConfig, RestoreResult, TempBackupEntry};
 use crate::services::fixers::{self, FixDefinition, FixResult};
-use crate::utils::path;
+use crate::services::limits::AppLimits;
+use crate::services::recovery::{self, RecoveryAction, RecoveryCandidate};
+use crate::services::{fixture, parser};
+use crate::services::temp_cleanup::{self, CleanupReport};
+use crate::utils::{filename, path, platform};
 
 /// 一键 Resume 功能的配置数据结构
 ///
@@ -121,10 +148,11 @@ pub async fn save_resume_config(config: ResumeConfig) -> Result<(), String> {
 /// 内部函数：根据 session_id 和用户配置构建完整的 resume 命令字符串
 ///
 /// 读取 `~/.mo/CCR/resume-config.json` 中的 flags 和自定义参数，
-/// 拼接为 `claude --resume <session_id> <flags> <custom_args>` 格式。
+/// 拼接为 `claude --resume <session_id> <flags> <custom_args> ["<draft>"]` 格式，
+/// 草稿作为首条输入追加在末尾，并按 `shell` 的转义规则转义为单个参数。
 ///
 /// 供 `open_resume_terminal` 和 `build_resume_command` 共同复用。
-async fn build_resume_command_string(session_id: String) -> String {
+async fn build_resume_command_string(session_id: String, draft: Option<&str>, shell: ShellKind) -> String {
     // 读取 Resume 配置
     let config = read_resume_config_internal().await;
 
@@ -148,22 +176,141 @@ async fn build_resume_command_string(session_id: String) -> String {
         }
     }
 
+    // 追加草稿作为首条输入
+    if let Some(draft) = draft.map(str::trim).filter(|d| !d.is_empty()) {
+        cmd_parts.push(shell.quote(draft));
+    }
+
     cmd_parts.join(" ")
 }
 
+/// 读取 resume 时要附带的草稿
+///
+/// # 参数
+/// - `draft_session_path` - 会话 JSONL 文件路径；None 表示不附带草稿
+///
+/// # 错误
+/// 草稿文件读取或解析失败时返回错误
+async fn load_resume_draft(draft_session_path: Option<&str>) -> Result<Option<String>, String> {
+    match draft_session_path {
+        Some(session_path) => drafts::get(session_path).await,
+        None => Ok(None),
+    }
+}
+
+/// 解释 resume 命令的 shell，决定草稿参数的转义方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShellKind {
+    /// POSIX shell（macOS / Linux 终端）
+    Posix,
+    /// Windows cmd.exe
+    Cmd,
+    /// Windows 上经 cmd.exe → wsl.exe 交给 WSL 中的 shell
+    WslFromCmd,
+}
+
+impl ShellKind {
+    /// 当前平台终端的 shell
+    fn native() -> Self {
+        if cfg!(target_os = "windows") {
+            ShellKind::Cmd
+        } else {
+            ShellKind::Posix
+        }
+    }
+
+    /// 在项目目录中打开终端时实际使用的 shell（Windows 上的 WSL 项目经 wsl.exe 启动，见 `windows_cmd_line`）
+    fn for_project(project_path: &str) -> Self {
+        match Self::native() {
+            ShellKind::Cmd if path::wsl_linux_path(project_path).is_some() => ShellKind::WslFromCmd,
+            shell => shell,
+        }
+    }
+
+    /// 将文本转义为该 shell 中的单个命令行参数
+    fn quote(self, arg: &str) -> String {
+        match self {
+            ShellKind::Posix => quote_posix_arg(arg),
+            ShellKind::Cmd => quote_cmd_arg(arg),
+            ShellKind::WslFromCmd => quote_wsl_arg(arg),
+        }
+    }
+}
+
+/// POSIX shell：单引号包裹，内部单引号写作 `'\''`，换行原样保留
+fn quote_posix_arg(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Windows cmd.exe：双引号包裹
+///
+/// cmd.exe 没有可靠的引号内转义方式，换行也会截断命令，因此：
+/// - 换行折叠为空格，内部双引号替换为单引号
+/// - `%` 放到引号外写作 `^%`（`"a"^%"b"`），参数解析时相邻的引号段拼回一个参数；
+///   两个 `%` 之间的「变量名」总会包含引号与脱字符，不会被展开为环境变量
+/// - 每个引号段末尾的反斜杠加倍，避免转义结尾的引号（MSVC 参数解析规则）
+fn quote_cmd_arg(arg: &str) -> String {
+    flatten_lines(arg)
+        .replace('"', "'")
+        .split('%')
+        .map(|segment| {
+            let trailing = segment.len() - segment.trim_end_matches('\\').len();
+            format!("\"{}{}\"", segment, "\\".repeat(trailing))
+        })
+        .collect::<Vec<_>>()
+        .join("^%")
+}
+
+/// WSL（cmd.exe → wsl.exe → WSL 中的 shell）
+///
+/// 先按 POSIX 规则单引号包裹供 WSL 中的 shell 解析，再为外层 cmd.exe 以 `^` 转义元字符。
+/// 结果不含双引号，cmd.exe 全程处于引号外，`^` 转义始终生效：
+/// - 换行折叠为空格，内部双引号替换为单引号（与 `quote_cmd_arg` 一致）
+/// - `%` 写作 `^%`，两个 `%` 之间的「变量名」以 `^` 结尾，不会被展开
+fn quote_wsl_arg(arg: &str) -> String {
+    let posix = quote_posix_arg(&flatten_lines(arg).replace('"', "'"));
+    let mut escaped = String::with_capacity(posix.len());
+    for c in posix.chars() {
+        if matches!(c, '^' | '&' | '|' | '<' | '>' | '(' | ')' | '%') {
+            escaped.push('^');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// 多行文本折叠为一行：去掉每行首尾空白与空行，以空格连接
+fn flatten_lines(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// 构建 resume 命令字符串（不执行，仅返回）
 ///
 /// 供前端「复制 Resume 指令」功能使用，返回完整的命令字符串，
 /// 用户可将其粘贴到终端手动执行。
 ///
+/// 复制命令不等于已经 resume，因此不会清除草稿。
+///
 /// # 参数
 /// - `session_id` - 会话 UUID
+/// - `draft_session_path` - 附带该会话的草稿作为首条输入；None 表示不附带
 ///
 /// # 返回值
-/// 完整的 `claude --resume <session_id> <flags> <custom_args>` 命令字符串
+/// 完整的 `claude --resume <session_id> <flags> <custom_args> ["<draft>"]` 命令字符串
+///
+/// # 错误
+/// 草稿文件读取或解析失败时返回错误
 #[tauri::command]
-pub async fn build_resume_command(session_id: String) -> Result<String, String> {
-    Ok(build_resume_command_string(session_id).await)
+pub async fn build_resume_command(
+    session_id: String,
+    draft_session_path: Option<String>,
+) -> Result<String, String> {
+    let draft = load_resume_draft(draft_session_path.as_deref()).await?;
+    Ok(build_resume_command_string(session_id, draft.as_deref(), ShellKind::native()).await)
 }
 
 /// 打开系统终端并执行 claude --resume 命令
@@ -174,27 +321,66 @@ pub async fn build_resume_command(session_id: String) -> Result<String, String>
 /// 使用 `std::process::Command::spawn()` 非阻塞启动子进程，
 /// 不等待终端关闭即返回。
 ///
+/// 指定 `draft_session_path` 时，该会话的草稿作为首条输入追加到命令末尾，
+/// 终端启动成功后自动清除草稿。
+///
 /// # 参数
 /// - `project_path` - 项目的真实文件系统路径（已解码）
 /// - `session_id` - 会话 UUID
+/// - `draft_session_path` - 附带该会话的草稿；None 表示不附带
+/// - `cache` - Tauri managed state，清除草稿后同步更新项目列表缓存
 ///
 /// # 平台行为
-/// - **Windows**: `cmd /c start cmd /k "cd /d <path> && <command>"`
+/// - **Windows**: `cmd /c start cmd /k "cd /d <path> && <command>"`；
+///   WSL 路径改为 `cmd /k wsl.exe [-d <distro>] --cd <path> -- <command>`
 /// - **macOS**: 通过 AppleScript 调用 Terminal.app
 /// - **Linux**: 依次尝试 x-terminal-emulator / gnome-terminal / konsole / xterm
+/// - **移动端**: 不支持，返回 `platform::UNSUPPORTED_ERROR` 错误
 ///
 /// # 错误
-/// 终端启动失败时返回错误
+/// 当前平台不支持、草稿读取失败或终端启动失败时返回错误
 #[tauri::command]
 pub async fn open_resume_terminal(
     project_path: String,
     session_id: String,
+    draft_session_path: Option<String>,
+    cache: State<'_, AppCache>,
 ) -> Result<(), String> {
-    // 1. 构建完整的 resume 命令字符串
-    let full_command = build_resume_command_string(session_id).await;
+    // 1. 构建完整的 resume 命令字符串（可附带草稿）
+    let draft = load_resume_draft(draft_session_path.as_deref()).await?;
+    let shell = ShellKind::for_project(&project_path);
+    let full_command = build_resume_command_string(session_id, draft.as_deref(), shell).await;
 
     // 2. 按平台打开终端
-    open_terminal_with_command(&project_path, &full_command)
+    open_terminal_with_command(&project_path, &full_command)?;
+
+    // 3. 草稿已作为首条输入发送，清除草稿（清除失败不影响已启动的终端）
+    if let (Some(session_path), Some(_)) = (draft_session_path, draft) {
+        match drafts::delete(&session_path).await {
+            Ok(()) => cache.set_session_has_draft(&session_path, false),
+            Err(e) => log::warn!("resume 后清除草稿失败: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// 在系统文件管理器中定位文件
+///
+/// 经 opener 插件调用系统原生 API，无需拼接命令行：
+/// - **Windows**: Shell COM API (SHOpenFolderAndSelectItems)
+/// - **macOS**: NSWorkspace selectFile
+/// - **Linux**: D-Bus org.freedesktop.FileManager1
+/// - **移动端**: 不支持，返回 `platform::UNSUPPORTED_ERROR` 错误
+///
+/// # 参数
+/// - `file_path` - 要定位的文件绝对路径
+///
+/// # 错误
+/// 当前平台不支持、文件不存在或调起失败时返回错误
+#[tauri::command]
+pub async fn reveal_in_file_manager(file_path: String) -> Result<(), String> {
+    platform::require_desktop("在文件管理器中定位文件")?;
+    tauri_plugin_opener::reveal_item_in_dir(&file_path).map_err(|e| format!("打开文件管理器失败: {}", e))
 }
 
 /// 内部函数：读取 Resume 配置（不经过 Tauri command 层）
@@ -224,6 +410,8 @@ async fn read_resume_config_internal() -> ResumeConfig {
 /// - `working_dir` - 终端的工作目录
 /// - `command` - 要在终端中执行的完整命令字符串
 fn open_terminal_with_command(working_dir: &str, command: &str) -> Result<(), String> {
+    platform::require_desktop("打开终端")?;
+
     #[cfg(target_os = "windows")]
     {
         use std::os::windows::process::CommandExt;
@@ -235,11 +423,13 @@ fn open_terminal_with_command(working_dir: &str, command: &str) -> Result<(), St
         // 使用 .raw_arg() 传递命令（而非 .args()），
         // 避免 Rust 的 MSVC 风格参数转义与 cmd.exe 的引号解析规则冲突。
         // /k 参数使窗口在命令执行后保持打开（用户可以看到输出并继续交互）
-        std::process::Command::new("cmd")
-            .raw_arg(format!("/k {}", command))
-            .current_dir(working_dir)
-            .creation_flags(CREATE_NEW_CONSOLE)
-            .spawn()
+        let (cmd_line, current_dir) = windows_cmd_line(working_dir, command);
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.raw_arg(cmd_line).creation_flags(CREATE_NEW_CONSOLE);
+        if let Some(dir) = current_dir {
+            cmd.current_dir(dir);
+        }
+        cmd.spawn()
             .map_err(|e| format!("启动 Windows 终端失败: {}", e))?;
     }
 
@@ -298,6 +488,29 @@ fn open_terminal_with_command(working_dir: &str, command: &str) -> Result<(), St
     Ok(())
 }
 
+/// 构建 Windows 下 `cmd` 的参数与工作目录
+///
+/// WSL 路径（`/mnt/<盘符>/...` 或 `\\wsl$\<distro>\...`）中的项目由 WSL 里的 Claude Code 创建，
+/// 需要改为 `wsl.exe [-d <distro>] --cd <Linux 路径> -- <命令>` 在 WSL 中启动；
+/// `/mnt/...` 不能作为 Windows 进程的工作目录，此时不设置工作目录。
+///
+/// # 参数
+/// - `working_dir` - 项目路径
+/// - `command` - 要执行的完整命令字符串
+///
+/// # 返回值
+/// `(cmd 的原始参数, 工作目录)`
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn windows_cmd_line<'a>(working_dir: &'a str, command: &str) -> (String, Option<&'a str>) {
+    match path::wsl_linux_path(working_dir) {
+        Some((distro, linux_dir)) => {
+            let distro_arg = distro.map(|d| format!("-d \"{}\" ", d)).unwrap_or_default();
+            (format!("/k wsl.exe {}--cd \"{}\" -- {}", distro_arg, linux_dir, command), None)
+        }
+        None => (format!("/k {}", command), Some(working_dir)),
+    }
+}
+
 // ============ 备份配置 Commands ============
 
 /// 读取备份配置
@@ -350,6 +563,328 @@ pub async fn get_temp_backups(cache: State<'_, AppCache>) -> Result<Vec<TempBack
     Ok(cache.get_all_temp_backups())
 }
 
+/// 从临时备份恢复原文件
+///
+/// 原始路径取自临时备份注册表，只能恢复本次运行期间登记过的备份。
+/// 写回前先预检备份内容（见 `file_guard::safe_restore_backup`），
+/// 问题行比例超过阈值时需传入 `force` 才会执行。
+///
+/// # 参数
+/// - `temp_path` - 临时备份文件的绝对路径（`TempBackupEntry.temp_path`）
+/// - `force` - 预检未通过时是否仍然恢复（默认 false）
+/// - `cache` - 全局缓存状态
+///
+/// # 返回值
+/// 预检结果与是否已恢复
+///
+/// # 错误
+/// 备份未登记、读取失败或写回失败时返回错误
+#[tauri::command]
+pub async fn restore_temp_backup(
+    temp_path: String,
+    force: Option<bool>,
+    cache: State<'_, AppCache>,
+) -> Result<RestoreResult, String> {
+    let entry = cache
+        .get_all_temp_backups()
+        .into_iter()
+        .find(|entry| entry.temp_path == temp_path)
+        .ok_or_else(|| format!("未找到临时备份记录: {}", temp_path))?;
+
+    restore_backup(&temp_path, &entry.original_path, "restore_temp_backup", force, &cache).await
+}
+
+/// 从主动备份（`.ccbak` 文件）恢复原文件
+///
+/// 原始路径由备份文件名去掉 `.ccbak<timestamp>` 后缀得到。
+/// 预检规则与 `restore_temp_backup` 相同。
+///
+/// # 参数
+/// - `backup_path` - `.ccbak` 备份文件的绝对路径
+/// - `force` - 预检未通过时是否仍然恢复（默认 false）
+/// - `cache` - 全局缓存状态
+///
+/// # 返回值
+/// 预检结果与是否已恢复
+///
+/// # 错误
+/// 路径不是主动备份、不在 Claude 数据目录下、读取失败或写回失败时返回错误
+#[tauri::command]
+pub async fn restore_auto_backup(
+    backup_path: String,
+    force: Option<bool>,
+    cache: State<'_, AppCache>,
+) -> Result<RestoreResult, String> {
+    file_guard::validate_claude_path(&backup_path)?;
+    let original_path = file_guard::auto_backup_original_path(&backup_path)
+        .ok_or_else(|| format!("不是有效的主动备份文件: {}", backup_path))?
+        .to_string();
+
+    restore_backup(&backup_path, &original_path, "restore_auto_backup", force, &cache).await
+}
+
+/// 恢复命令的公共流程：持有会话写锁执行预检与写回，成功写回后使会话缓存失效，
+/// 并以恢复后的文件重新读取项目列表缓存中的该会话（原文件已删除时重新加入）
+async fn restore_backup(
+    backup_path: &str,
+    original_path: &str,
+    operation: &str,
+    force: Option<bool>,
+    cache: &AppCache,
+) -> Result<RestoreResult, String> {
+    let _guard = cache.lock_session(original_path).await;
+
+    let result = file_guard::safe_restore_backup(
+        backup_path,
+        original_path,
+        operation,
+        force.unwrap_or(false),
+        cache,
+    )
+    .await?;
+
+    if result.restored {
+        cache.invalidate_session(original_path);
+        cache.clear_undo(original_path, "恢复备份");
+        cache.add_session_file(original_path).await;
+    }
+    Ok(result)
+}
+
+/// 列出所有会话的备份
+///
+/// 汇总 TEMP 下的临时备份与数据目录中的 `.ccbak` 主动备份（见 `services::backups`），
+/// 只读取目录项与元数据。恢复分别使用 `restore_temp_backup` / `restore_auto_backup`，
+/// 删除使用 `delete_backup`。
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径
+/// - `session_file_path` - 只列出该文件的备份（省略时列出全部）
+/// - `oldest_first` - 是否按时间升序排列（默认最新的在前）
+/// - `cache` - Tauri managed state，提供本次运行期间的临时备份注册表
+///
+/// # 返回值
+/// 按创建时间排序的备份列表
+#[tauri::command]
+pub async fn list_all_backups(
+    claude_path: String,
+    session_file_path: Option<String>,
+    oldest_first: Option<bool>,
+    cache: State<'_, AppCache>,
+) -> Result<Vec<BackupItem>, String> {
+    Ok(backups::list_all_backups(
+        claude_path.into(),
+        cache.get_all_temp_backups(),
+        session_file_path,
+        oldest_first.unwrap_or(false),
+    )
+    .await)
+}
+
+/// 审计日志单页默认记录数
+const DEFAULT_AUDIT_PAGE_SIZE: usize = 100;
+
+/// 分页读取审计日志
+///
+/// 记录来自 `file_guard` 的每次写入与删除（见 `services::audit`），最新的在前。
+///
+/// # 参数
+/// - `filter` - 查询条件（路径子串、批次 ID、偏移）；省略时不过滤
+/// - `limit` - 本页最多返回的记录数（默认 `DEFAULT_AUDIT_PAGE_SIZE`）
+///
+/// # 返回值
+/// 本页记录、符合条件的总数与是否还有更早的记录
+///
+/// # 错误
+/// 审计日志存在但读取失败时返回错误
+#[tauri::command]
+pub async fn read_audit_log(filter: Option<AuditFilter>, limit: Option<usize>) -> Result<AuditLogPage, String> {
+    audit::read(&filter.unwrap_or_default(), limit.unwrap_or(DEFAULT_AUDIT_PAGE_SIZE)).await
+}
+
+/// 删除一个备份文件
+///
+/// 只允许删除临时备份目录下的文件，或 Claude 数据目录下符合 `.ccbak<timestamp>` 命名的主动备份，
+/// 不会误删会话文件本身。符号链接一律拒绝。临时备份同时从注册表中移除。
+///
+/// # 参数
+/// - `backup_path` - 备份文件的绝对路径（`BackupItem.backup_path`）
+/// - `cache` - Tauri managed state，AppCache 实例
+///
+/// # 错误
+/// 路径不是备份文件或删除失败时返回错误
+#[tauri::command]
+pub async fn delete_backup(backup_path: String, cache: State<'_, AppCache>) -> Result<(), String> {
+    // 备份本身是符号链接时，规范化路径指向的是链接目标而不是备份，直接拒绝
+    let metadata = std::fs::symlink_metadata(&backup_path)
+        .map_err(|e| format!("路径解析失败: {}", e))?;
+    if !metadata.is_file() {
+        return Err(format!("不是有效的备份文件: {}", backup_path));
+    }
+    // 以下校验与删除都针对同一个规范化路径
+    let canonical = std::fs::canonicalize(&backup_path)
+        .map_err(|e| format!("路径解析失败: {}", e))?;
+    let in_temp_dir = std::fs::canonicalize(file_guard::temp_backup_dir())
+        .is_ok_and(|dir| canonical.parent() == Some(dir.as_path()));
+
+    if !in_temp_dir {
+        let canonical_str = canonical.to_string_lossy();
+        file_guard::validate_claude_path(&canonical_str)?;
+        if file_guard::auto_backup_original_path(&canonical_str).is_none() {
+            return Err(format!("不是有效的备份文件: {}", backup_path));
+        }
+    }
+
+    tokio::fs::remove_file(&canonical)
+        .await
+        .map_err(|e| format!("删除备份失败: {}", e))?;
+    cache.remove_temp_backup(&backup_path);
+    Ok(())
+}
+
+/// 手动清理 TEMP 下的临时资源
+///
+/// 按备份配置中的 `temp_retention_days` 删除超龄的临时图片与导出残留，
+/// 临时备份（`ccr-backups`）不受影响。保留天数为 0 时清理全部临时资源。
+///
+/// # 返回值
+/// 删除文件数与释放的空间
+///
+/// # 错误
+/// 后台清理任务异常终止时返回错误
+#[tauri::command]
+pub async fn cleanup_temp_artifacts() -> Result<CleanupReport, String> {
+    let config = file_guard::read_backup_config_internal().await;
+    let max_age = temp_cleanup::retention(config.temp_retention_days);
+
+    tokio::task::spawn_blocking(move || temp_cleanup::cleanup_expired(max_age))
+        .await
+        .map_err(|e| format!("清理临时资源失败: {}", e))
+}
+
+/// 获取上次异常退出留下的写入残留
+///
+/// 前端启动时调用一次，非空时提示用户「检测到上次可能未完成的写入」。
+///
+/// # 返回值
+/// 残留列表（已排除正在写入的文件）
+///
+/// # 错误
+/// 无法确定 Claude 数据目录时返回错误
+#[tauri::command]
+pub async fn get_recovery_candidates() -> Result<Vec<RecoveryCandidate>, String> {
+    recovery::scan_candidates().await
+}
+
+/// 处理一个写入残留
+///
+/// 处理后使相关会话的缓存失效，并在项目列表缓存中重新读取保留下来的会话文件。
+///
+/// # 参数
+/// - `tmp_path` - 残留 `.tmp` 文件的绝对路径
+/// - `action` - 处理方式：`discard` / `overwrite` / `keepBoth`
+/// - `cache` - Tauri managed state，AppCache 实例
+///
+/// # 返回值
+/// 处理后保留下来的会话文件路径（丢弃时为 None）
+///
+/// # 错误
+/// 路径无效、文件正在写入、会话被锁定或文件操作失败时返回错误
+#[tauri::command]
+pub async fn resolve_recovery(
+    tmp_path: String,
+    action: RecoveryAction,
+    cache: State<'_, AppCache>,
+) -> Result<Option<String>, String> {
+    let kept = recovery::resolve(&tmp_path, action, &cache).await?;
+    if let Some(path) = &kept {
+        cache.invalidate_session(path);
+        cache.clear_undo(path, "恢复中断的写入");
+        cache.add_session_file(path).await;
+    }
+    Ok(kept)
+}
+
+/// 启动自检
+///
+/// 并行检查 Claude 数据目录、项目数、settings.json、CCR 配置目录与临时备份目录的读写权限、
+/// 磁盘剩余空间以及 claude CLI 是否可用。前端在空状态页展示结果与修复建议。
+/// 单项检查超时记为失败，不影响其他检查项。
+///
+/// # 返回值
+/// 逐项检查结果（固定顺序）
+#[tauri::command]
+pub async fn run_startup_diagnostics() -> Vec<DiagnosticItem> {
+    diagnostics::run_all().await
+}
+
+/// 获取引导状态
+///
+/// 区分 `~/.claude/` 不存在、目录存在但没有项目、已有项目三种状态，并检测 claude CLI 是否在 PATH 中。
+/// 前端在项目列表为空时据此展示「安装 Claude Code」或「开始第一个会话」的引导页。
+///
+/// # 返回值
+/// 引导状态、数据目录路径、项目数与 CLI 路径
+#[tauri::command]
+pub async fn get_onboarding_state() -> OnboardingState {
+    diagnostics::onboarding_state().await
+}
+
+/// 生成反馈诊断包
+///
+/// 收集版本、启动自检、缓存统计、最近命令耗时、日志尾部与勾选会话的结构统计（不含正文），
+/// 脱敏后打包为 ZIP。单项收集失败只在清单中记为缺失，内容见 `services::diagnostic_bundle`。
+///
+/// # 参数
+/// - `output_path` - ZIP 输出路径，不允许位于 Claude 数据目录或数据源内
+/// - `session_file_paths` - 可选，需要附带结构统计的问题会话
+/// - `app` - Tauri AppHandle，用于定位日志目录
+/// - `cache` - Tauri managed state，读取缓存统计
+///
+/// # 返回值
+/// 诊断包清单（各项是否收集成功）
+///
+/// # 错误
+/// 输出路径位于 Claude 数据目录或数据源内（按规范化路径判断）或 ZIP 写入失败时返回错误
+#[tauri::command]
+pub async fn generate_diagnostic_bundle(
+    output_path: String,
+    session_file_paths: Option<Vec<String>>,
+    app: AppHandle,
+    cache: State<'_, AppCache>,
+) -> Result<DiagnosticBundleManifest, String> {
+    let args = format!(
+        "{} sessions={}",
+        command_trace::path_arg(&output_path),
+        session_file_paths.as_ref().map_or(0, Vec::len)
+    );
+    command_trace::traced(
+        "generate_diagnostic_bundle",
+        args,
+        generate_diagnostic_bundle_inner(output_path, session_file_paths, app, cache),
+    )
+    .await
+}
+
+/// `generate_diagnostic_bundle` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn generate_diagnostic_bundle_inner(
+    output_path: String,
+    session_file_paths: Option<Vec<String>>,
+    app: AppHandle,
+    cache: State<'_, AppCache>,
+) -> Result<DiagnosticBundleManifest, String> {
+    if file_guard::is_within_data_sources(&output_path)? {
+        return Err("诊断包不能写入 Claude 数据目录".to_string());
+    }
+    let sessions = session_file_paths
+        .unwrap_or_default()
+        .iter()
+        .map(|p| validated_session_path(p).map(|canonical| canonical.to_string_lossy().to_string()))
+        .collect();
+    let log_dir = app.path().app_log_dir().ok();
+    diagnostic_bundle::generate(&output_path, sessions, log_dir, &cache).await
+}
+
 // ============ 一键修复 Commands ============
 
 /// 获取所有可用的一键修复项列表
@@ -374,6 +909,7 @@ pub async fn list_fixers() -> Result<Vec<FixDefinition>, String> {
 /// - `session_file_path` - 要修复的会话 JSONL 文件的绝对路径
 /// - `options` - 可选的修复参数（JSON 对象），无参数时为 None
 /// - `cache` - Tauri managed state，传递给 file_guard 进行备份注册
+/// - `limits` - Tauri managed state，全局 I/O 并发信号量（批量修复时节流）
 ///
 /// # 返回值
 /// 返回 FixResult，包含修复是否成功、结果消息和受影响行数
@@ -386,7 +922,196 @@ pub async fn execute_fixer(
     session_file_path: String,
     options: Option<serde_json::Value>,
     cache: State<'_, AppCache>,
+    limits: State<'_, AppLimits>,
+) -> Result<FixResult, String> {
+    command_trace::traced(
+        "execute_fixer",
+        format!("{} {}", fixer_id, command_trace::path_arg(&session_file_path)),
+        execute_fixer_inner(fixer_id, session_file_path, options, cache, limits),
+    )
+    .await
+}
+
+/// `execute_fixer` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn execute_fixer_inner(
+    fixer_id: String,
+    session_file_path: String,
+    options: Option<serde_json::Value>,
+    cache: State<'_, AppCache>,
+    limits: State<'_, AppLimits>,
 ) -> Result<FixResult, String> {
+    validated_session_path(&session_file_path)?;
     let opts = options.unwrap_or(serde_json::Value::Null);
+    // 修复涉及整文件读写与备份，受全局 I/O 并发数限制
+    let _permit = limits.acquire_io().await;
     fixers::execute_by_id(&fixer_id, &session_file_path, &cache, &opts).await
 }
+
+/// 获取最近的命令调用记录
+///
+/// 返回内存环形缓冲中的全部记录（最多 500 条，最旧的在前），用于排查慢命令。
+/// 只有接入 `services::command_trace` 的主要命令会被记录。
+///
+/// # 返回值
+/// 调用记录列表
+#[tauri::command]
+pub fn get_command_trace() -> Vec<CommandTraceEntry> {
+    command_trace::entries()
+}
+
+/// 获取会话缓存的占用统计
+///
+/// 占用为写入时的估算值，超过 `limits.json` 的 `cacheMemoryMb` 时缓存会主动淘汰条目。
+///
+/// # 参数
+/// - `cache` - Tauri managed state，全局缓存实例
+///
+/// # 返回值
+/// 条目数、估算总占用、内存上限、累计淘汰次数与审计日志写入失败次数
+#[tauri::command]
+pub fn get_cache_stats(cache: State<'_, AppCache>) -> CacheStats {
+    cache.session_cache_stats()
+}
+
+/// 缓存与文件系统一致性自检（诊断用，常规路径不执行）
+///
+/// 会话缓存的每个条目重新 stat 比较 mtime / 大小，并重新 parse + transform 比对 display_id；
+/// 项目列表缓存与一次快速重扫的会话文件列表对比。规则见 `services::cache_check`。
+///
+/// # 参数
+/// - `invalidate` - 是否自动失效不一致的缓存，未传时只报告
+/// - `cache` - Tauri managed state，全局缓存实例
+///
+/// # 返回值
+/// 检查范围、发现的不一致与失效情况
+#[tauri::command]
+pub async fn verify_cache_consistency(
+    invalidate: Option<bool>,
+    cache: State<'_, AppCache>,
+) -> Result<CacheConsistencyReport, String> {
+    command_trace::traced(
+        "verify_cache_consistency",
+        String::new(),
+        verify_cache_consistency_inner(invalidate, cache),
+    )
+    .await
+}
+
+/// `verify_cache_consistency` 的实现，由 command 包进 `command_trace::traced` 调用
+async fn verify_cache_consistency_inner(
+    invalidate: Option<bool>,
+    cache: State<'_, AppCache>,
+) -> Result<CacheConsistencyReport, String> {
+    Ok(cache_check::verify(&cache, invalidate.unwrap_or(false)).await)
+}
+
+/// 把会话脱敏为可提交的测试样本（开发用）
+///
+/// 前端没有入口，需要时在开发者工具中执行
+/// `__TAURI_INTERNALS__.invoke('make_session_fixture', { sessionFilePath, outputPath })`，
+/// 再将生成的 JSONL 放入 `src-tauri/tests/fixtures/`。脱敏规则见 `services::fixture`。
+/// 仅 debug 构建可用，release 构建直接返回错误。
+///
+/// # 参数
+/// - `session_file_path` - 源会话 JSONL 文件的绝对路径（只读）
+/// - `output_path` - 样本输出路径，不允许位于 Claude 数据目录内
+///
+/// # 返回值
+/// 写入的消息行数
+///
+/// # 错误
+/// release 构建、输出路径位于 `~/.claude/` 内、会话读取失败或写入失败时返回错误
+#[tauri::command]
+pub async fn make_session_fixture(session_file_path: String, output_path: String) -> Result<usize, String> {
+    if !cfg!(debug_assertions) {
+        return Err("make_session_fixture 仅在 debug 构建中可用".to_string());
+    }
+    validated_session_path(&session_file_path)?;
+    if std::path::Path::new(&output_path).starts_with(path::get_claude_data_path()?) {
+        return Err("测试样本不能写入 Claude 数据目录".to_string());
+    }
+
+    let messages = parser::read_messages(&session_file_path).await?;
+    let home = platform::home_dir().map(|h| h.to_string_lossy().to_string());
+    let sanitized = fixture::sanitize_session(&messages, home.as_deref());
+    let content = parser::serialize_jsonl(&sanitized)?;
+
+    tokio::fs::write(filename::to_long_path(&output_path), content)
+        .await
+        .map_err(|e| format!("写入测试样本失败: {}", e))?;
+    Ok(sanitized.len())
+}
+
+/// 在用户数据目录上运行端到端基准测试（诊断用）
+///
+/// 前端没有入口，需要时在开发者工具中执行
+/// `__TAURI_INTERNALS__.invoke('run_benchmark', { claudePath, scenario: 'scan', save: true })`。
+/// debug 构建始终可用；release 构建需在 `~/.mo/CCR/app-config.json` 中设置 `"enableBenchmark": true`。
+/// 场景与计时规则见 `services::benchmark`。
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
+/// - `scenario` - 场景：`scan` / `read_largest` / `search`
+/// - `save` - 是否把报告另存到 `~/.mo/CCR/benchmarks/`；None 表示不保存
+///
+/// # 返回值
+/// 各项计时的 P50 / P95 与数据规模
+///
+/// # 错误
+/// 基准测试未开启、数据目录不可读、没有会话文件或报告保存失败时返回错误
+#[tauri::command]
+pub async fn run_benchmark(
+    claude_path: String,
+    scenario: BenchmarkScenario,
+    save: Option<bool>,
+) -> Result<BenchmarkReport, String> {
+    if !benchmark::is_enabled() {
+        return Err("基准测试未开启：请在 ~/.mo/CCR/app-config.json 中设置 \"enableBenchmark\": true".to_string());
+    }
+    benchmark::run(&claude_path, scenario, save.unwrap_or(false)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_arg_per_shell() {
+        let cases: &[(ShellKind, &str, &str)] = &[
+            (ShellKind::Posix, "补充 $HOME `ls`", "'补充 $HOME `ls`'"),
+            (ShellKind::Posix, "it's", "'it'\\''s'"),
+            (ShellKind::Posix, "a\nb", "'a\nb'"),
+            (ShellKind::Posix, "50% off", "'50% off'"),
+            (ShellKind::Cmd, "say \"hi\" & exit", "\"say 'hi' & exit\""),
+            (ShellKind::Cmd, "line1\r\n  line2\n", "\"line1 line2\""),
+            (ShellKind::Cmd, "C:\\dir\\", "\"C:\\dir\\\\\""),
+            (ShellKind::Cmd, "it's", "\"it's\""),
+            (ShellKind::Cmd, "echo %PATH%", "\"echo \"^%\"PATH\"^%\"\""),
+            (ShellKind::Cmd, "C:\\dir\\%x", "\"C:\\dir\\\\\"^%\"x\""),
+            (ShellKind::WslFromCmd, "it's", "'it'\\''s'"),
+            (ShellKind::WslFromCmd, "say \"hi\" & exit", "'say '\\''hi'\\'' ^& exit'"),
+            (ShellKind::WslFromCmd, "echo %PATH% | (a<b>c) ^", "'echo ^%PATH^% ^| ^(a^<b^>c^) ^^'"),
+            (ShellKind::WslFromCmd, "line1\n line2", "'line1 line2'"),
+        ];
+        for (shell, input, expected) in cases {
+            assert_eq!(shell.quote(input), *expected, "{:?} {:?}", shell, input);
+        }
+    }
+
+    #[test]
+    fn test_windows_cmd_line_uses_wsl_for_wsl_paths() {
+        let command = "claude --resume abc";
+        assert_eq!(
+            windows_cmd_line(r"D:\projects\foo", command),
+            ("/k claude --resume abc".to_string(), Some(r"D:\projects\foo"))
+        );
+        assert_eq!(
+            windows_cmd_line("/mnt/d/projects/foo", command),
+            ("/k wsl.exe --cd \"/mnt/d/projects/foo\" -- claude --resume abc".to_string(), None)
+        );
+        assert_eq!(
+            windows_cmd_line(r"\\wsl$\Ubuntu\mnt\d\projects\foo", command),
+            ("/k wsl.exe -d \"Ubuntu\" --cd \"/mnt/d/projects/foo\" -- claude --resume abc".to_string(), None)
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! # Command 输入校验
+//!
+//! 前端传入的会话路径在进入业务逻辑前统一经 `validated_session_path` 校验，
+//! 读操作与写操作使用同一套规则，不再依赖写入时才由 `file_guard` 拦截：
+//! 1. `canonicalize` 解析 `..` 与符号链接（文件必须存在）
+//! 2. 解析后的路径位于某个数据源的 `projects/` 目录内（见 `data_sources::source_roots`）
+//! 3. 是扩展名为 `.jsonl` 的普通文件
+//!
+//! 校验只负责拒绝，command 继续使用前端传入的原始路径字符串：
+//! 缓存、事件与草稿都以该字符串为键，而 Windows 上的规范化结果带 `\\?\` 前缀，会与之对不上。
+//! 明确需要读取数据目录之外文件的入口（如导出、测试样本的输出路径）不经过本模块。
+
+use std::path::{Path, PathBuf};
+
+use crate::services::data_sources;
+
+/// 校验会话文件路径
+///
+/// # 参数
+/// - `session_file_path` - 前端传入的会话 JSONL 文件路径
+///
+/// # 返回值
+/// 规范化后的绝对路径
+///
+/// # 错误
+/// 文件不存在、不在任何数据源的 projects 目录内，或不是 `.jsonl` 文件时返回错误
+pub(crate) fn validated_session_path(session_file_path: &str) -> Result<PathBuf, String> {
+    let projects_dirs: Vec<PathBuf> = data_sources::source_roots()?
+        .into_iter()
+        .map(|root| root.join("projects"))
+        .collect();
+    validate_under(session_file_path, &projects_dirs)
+}
+
+/// 校验 `file_path` 是位于 `allowed_dirs` 之一内的已存在 `.jsonl` 文件
+fn validate_under(file_path: &str, allowed_dirs: &[PathBuf]) -> Result<PathBuf, String> {
+    if Path::new(file_path).extension().and_then(|e| e.to_str()) != Some("jsonl") {
+        return Err(format!("不是会话文件: {}", file_path));
+    }
+    let canonical = std::fs::canonicalize(file_path).map_err(|e| format!("会话文件不存在或无法访问 {}: {}", file_path, e))?;
+    // 符号链接可能指向非 .jsonl 文件，按解析后的路径再检查一次
+    if !canonical.is_file() || canonical.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+        return Err(format!("不是会话文件: {}", file_path));
+    }
+    let allowed = allowed_dirs
+        .iter()
+        .filter_map(|dir| std::fs::canonicalize(dir).ok())
+        .any(|dir| canonical.starts_with(dir));
+    if !allowed {
+        return Err(format!("安全检查失败：{} 不在 Claude 数据目录的 projects 下", file_path));
+    }
+    Ok(canonical)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_under_rejects_escapes() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        let projects = root.join("me").join(".claude").join("projects");
+        let project = projects.join("-w-app");
+        let other_home = root.join("other").join(".claude").join("projects").join("-w-app");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::create_dir_all(&other_home).unwrap();
+        let session = project.join("s.jsonl");
+        std::fs::write(&session, "").unwrap();
+        std::fs::write(project.join("notes.txt"), "").unwrap();
+        std::fs::write(other_home.join("s.jsonl"), "").unwrap();
+        std::fs::write(root.join("outside.jsonl"), "").unwrap();
+        let allowed = [projects.clone()];
+        let check = |p: &Path| validate_under(&p.to_string_lossy(), &allowed);
+
+        assert_eq!(check(&session).unwrap(), std::fs::canonicalize(&session).unwrap());
+        // `..` 跳出 projects 目录
+        assert!(check(&project.join("../../../../outside.jsonl")).is_err());
+        // 其他用户主目录下的同名结构
+        assert!(check(&other_home.join("s.jsonl")).is_err());
+        // 扩展名与存在性
+        assert!(check(&project.join("notes.txt")).is_err());
+        assert!(check(&project.join("missing.jsonl")).is_err());
+        assert!(check(&project).is_err());
+
+        // 符号链接逃逸：projects 内的链接指向外部文件
+        #[cfg(unix)]
+        {
+            let link = project.join("link.jsonl");
+            std::os::unix::fs::symlink(root.join("outside.jsonl"), &link).unwrap();
+            assert!(check(&link).is_err());
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! # 多窗口 Tauri Commands
+//!
+//! 提供在独立窗口中打开会话的 command 处理函数：
+//! - `open_session_window` - 为指定会话创建（或聚焦已有的）独立窗口
+//!
+//! ## 多窗口下的共享状态
+//! - `AppCache` 等 managed state 属于整个应用，所有窗口共享同一份缓存；
+//!   会话 LRU 容量同样由所有窗口共享，对照查看两三个会话时不会互相挤出
+//! - 写操作统一经过 `file_guard`，写入成功后向所有窗口广播 `session-updated`（见 `services::events`）
+//! - 流式读取的 `session-chunk` / `session-stream-done` 事件只发往发起读取的窗口
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+#[cfg(desktop)]
+use tauri::{WebviewUrl, WebviewWindowBuilder};
+use tauri::{AppHandle, Manager, State};
+
+use crate::services::cache::AppCache;
+use crate::services::session_lock;
+#[cfg(mobile)]
+use crate::utils::platform;
+
+/// 会话窗口的默认尺寸（逻辑像素）
+#[cfg(desktop)]
+const SESSION_WINDOW_SIZE: (f64, f64) = (1000.0, 760.0);
+
+/// 会话窗口的最小尺寸（与主窗口一致）
+#[cfg(desktop)]
+const SESSION_WINDOW_MIN_SIZE: (f64, f64) = (800.0, 600.0);
+
+/// 在独立窗口中打开会话
+///
+/// 窗口 label 由会话路径确定，同一会话重复打开时聚焦已有窗口而不是再建一个。
+/// 目标会话通过初始化脚本注入为 `window.__CCR_SESSION_WINDOW__`，前端启动后据此直接打开该会话。
+/// 子窗口与主窗口相互独立，关闭子窗口不影响主窗口。
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `app` - Tauri AppHandle，用于创建和查找窗口
+/// - `cache` - Tauri managed state，从项目列表缓存中获取会话名称作为窗口标题
+///
+/// # 返回值
+/// 会话窗口的 label
+///
+/// # 错误
+/// 窗口创建或聚焦失败时返回错误；移动端返回以 `UNSUPPORTED_ERROR` 开头的错误
+#[tauri::command]
+pub async fn open_session_window(
+    session_file_path: String,
+    app: AppHandle,
+    cache: State<'_, AppCache>,
+) -> Result<String, String> {
+    let label = session_window_label(&session_file_path);
+
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.unminimize();
+        window
+            .set_focus()
+            .map_err(|e| format!("聚焦会话窗口失败: {}", e))?;
+        return Ok(label);
+    }
+
+    // 窗口标题：CCR 别名 > 会话名称 > 会话 ID 前 8 位
+    let title = cache
+        .get_cached_session(&session_file_path)
+        .and_then(|s| s.alias.or(s.name))
+        .unwrap_or_else(|| {
+            std::path::Path::new(&session_file_path)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(|id| id.chars().take(8).collect())
+                .unwrap_or_default()
+        });
+    let target = serde_json::to_string(&session_file_path)
+        .map_err(|e| format!("序列化会话路径失败: {}", e))?;
+    let script = format!("window.__CCR_SESSION_WINDOW__ = {};", target);
+
+    build_session_window(&app, &label, &title, &script)?;
+
+    Ok(label)
+}
+
+/// 创建会话窗口
+///
+/// # 参数
+/// - `app` - Tauri AppHandle
+/// - `label` - 窗口 label
+/// - `title` - 会话标题（窗口标题为 `<title> - Claude Code Reader`）
+/// - `script` - 注入目标会话的初始化脚本
+#[cfg(desktop)]
+fn build_session_window(app: &AppHandle, label: &str, title: &str, script: &str) -> Result<(), String> {
+    WebviewWindowBuilder::new(app, label, WebviewUrl::App("index.html".into()))
+        .title(format!("{} - Claude Code Reader", title))
+        .inner_size(SESSION_WINDOW_SIZE.0, SESSION_WINDOW_SIZE.1)
+        .min_inner_size(SESSION_WINDOW_MIN_SIZE.0, SESSION_WINDOW_MIN_SIZE.1)
+        .disable_drag_drop_handler()
+        .initialization_script(script)
+        .build()
+        .map(|_| ())
+        .map_err(|e| format!("创建会话窗口失败: {}", e))
+}
+
+/// 移动端没有多窗口，返回 `UNSUPPORTED_ERROR` 开头的错误
+#[cfg(mobile)]
+fn build_session_window(_app: &AppHandle, _label: &str, _title: &str, _script: &str) -> Result<(), String> {
+    Err(platform::unsupported("独立窗口"))
+}
+
+/// 由会话路径生成窗口 label（`session-<16 位十六进制>`，只含 label 允许的字符）
+fn session_window_label(session_file_path: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    session_lock::normalize(session_file_path).hash(&mut hasher);
+    format!("session-{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_window_label() {
+        let label = session_window_label("/home/u/.claude/projects/p/a.jsonl");
+        assert_eq!(label, session_window_label("/home/u/.claude/projects/p/a.jsonl"));
+        assert_ne!(label, session_window_label("/home/u/.claude/projects/p/b.jsonl"));
+        assert!(label.starts_with("session-"));
+        assert!(label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+    }
+}
@@ -23,7 +23,9 @@ mod utils;
 
 use commands::proxy::ProxyState;
 use services::cache::AppCache;
+use services::limits::{AppLimits, LimitsConfig};
 use services::retrospect::RetrospectState;
+use services::watcher::WatcherState;
 
 // `#[cfg_attr(mobile, tauri::mobile_entry_point)]`：条件编译属性
 // 当目标平台为移动端（Android/iOS）时，此属性将 `run()` 函数标记为
@@ -38,12 +40,16 @@ use services::retrospect::RetrospectState;
 /// 3. 初始化应用全局状态（AppCache 内存缓存）
 /// 4. 注册所有自定义 Tauri commands
 /// 5. 在 `setup` 钩子中按需注册调试专用插件（日志）
-/// 6. 生成应用上下文并启动主事件循环
+/// 6. 生成应用上下文并启动主事件循环，在退出请求时清理过期临时资源
 ///
 /// # Panics
 /// 如果 Tauri 应用启动失败（例如配置文件缺失或窗口创建失败），
 /// 将通过 `.expect()` 触发 panic 并输出错误信息。
 pub fn run() {
+    let limits = LimitsConfig::load();
+    // 展示层的超长文本块折叠阈值（全局生效，修改 limits.json 后重启生效）
+    services::transformer::set_oversized_text_threshold(limits.oversized_text_bytes());
+
     tauri::Builder::default()
         // === 官方插件注册 ===
         // 文件系统插件：允许前端通过 Tauri API 安全地读写本地文件
@@ -60,47 +66,137 @@ pub fn run() {
         // 注册 AppCache 为 Tauri managed state，所有 command 函数可通过
         // `State<AppCache>` 参数注入访问。AppCache 包含：
         // - 项目列表缓存（TTL 30 秒）
-        // - 会话消息 LRU 缓存（最多 20 个会话）
-        .manage(AppCache::new())
+        // - 会话消息 LRU 缓存（最多 20 个会话，总内存不超过 limits.json 的 cacheMemoryMb）
+        .manage(AppCache::with_memory_limit(limits.cache_memory_bytes()))
         // 注册 ProxyState 为代理全局状态
         .manage(ProxyState::new())
         // 注册 RetrospectState 为项目回溯全局状态
         .manage(RetrospectState::new())
+        // 注册 AppLimits：重 I/O 操作（修复、导出）共享的全局并发信号量
+        .manage(AppLimits::new())
+        // 注册 WatcherState：数据目录监听（由前端 start_watching / stop_watching 控制）
+        .manage(WatcherState::new())
         // === 自定义 Tauri Commands 注册 ===
         // 所有 command 函数通过 `invoke_handler` 注册，前端通过 `invoke()` 调用
         .invoke_handler(tauri::generate_handler![
             // 设置和配置 commands
             commands::settings::get_claude_data_path,
+            commands::settings::list_data_sources,
+            commands::settings::save_data_sources,
+            commands::settings::get_display_timezone,
+            commands::settings::save_display_timezone,
             commands::settings::read_settings,
             commands::settings::save_settings,
+            commands::settings::get_appearance_config,
+            commands::settings::save_appearance_config,
+            commands::settings::read_output_style,
+            commands::settings::save_output_style,
             commands::settings::read_env_config,
             commands::settings::save_env_config,
             commands::settings::read_history,
             // 项目扫描 commands
             commands::projects::scan_projects,
+            commands::projects::scan_project_sessions,
+            commands::projects::filter_projects,
+            commands::projects::get_counts,
+            commands::projects::export_projects_overview,
+            commands::projects::get_data_compat_report,
+            commands::projects::get_project_summary,
+            commands::projects::get_project_stats,
+            commands::projects::set_session_locked,
+            commands::projects::save_session_draft,
+            commands::projects::get_session_draft,
+            commands::projects::delete_session_draft,
+            commands::projects::save_view_state,
+            commands::projects::get_view_state,
+            commands::projects::rename_session,
+            commands::projects::merge_fragment_project,
+            commands::projects::resolve_project_real_path,
+            commands::projects::set_project_path_override,
+            commands::projects::archive_project,
+            commands::projects::unarchive_project,
+            commands::projects::toggle_pin_project,
+            commands::projects::toggle_pin_session,
+            commands::projects::record_session_opened,
+            commands::projects::start_watching,
+            commands::projects::stop_watching,
             // 消息读写 commands
             commands::messages::read_session_messages,
+            commands::messages::read_session_messages_streamed,
+            commands::messages::get_session_branches,
+            commands::messages::read_full_block,
             commands::messages::delete_message,
             commands::messages::delete_messages,
+            commands::messages::truncate_session_after,
+            commands::messages::merge_messages,
             commands::messages::edit_message_content,
+            commands::messages::edit_message_raw,
+            commands::messages::undo_last_operation,
+            commands::messages::get_undo_history,
+            commands::messages::get_message_edit_history,
+            commands::messages::revert_message_to_version,
             commands::messages::insert_message,
+            commands::messages::insert_raw_message,
+            commands::messages::copy_messages_to_session,
+            commands::messages::append_raw_lines,
             commands::messages::delete_session,
             // 搜索和导出 commands
             commands::messages::search_session,
+            commands::messages::search_all_sessions,
+            commands::messages::cancel_global_search,
             commands::messages::export_session,
+            commands::messages::export_and_share,
+            commands::messages::suggest_export_file_name,
+            commands::messages::format_message,
+            commands::messages::get_session_versions,
+            commands::messages::get_tool_invocation,
+            commands::messages::diff_tool_result_with_disk,
+            commands::messages::create_trimmed_copy,
+            commands::messages::duplicate_session,
+            commands::messages::archive_and_delete_session,
+            commands::messages::merge_sessions,
+            commands::messages::extract_links,
+            commands::messages::extract_code_blocks,
+            commands::messages::save_code_blocks,
+            commands::messages::get_session_keywords,
+            commands::messages::get_session_stats,
+            commands::messages::get_failed_tool_calls,
+            commands::messages::verify_roundtrip,
+            commands::messages::convert_session_encoding,
             // 文件系统辅助 commands
             commands::settings::check_file_exists,
+            commands::settings::check_files_exist,
             // 实用工具 commands
             commands::tools::read_resume_config,
             commands::tools::save_resume_config,
             commands::tools::open_resume_terminal,
+            commands::tools::reveal_in_file_manager,
             commands::tools::build_resume_command,
             commands::tools::read_backup_config,
             commands::tools::save_backup_config,
             commands::tools::get_temp_backups,
+            commands::tools::restore_temp_backup,
+            commands::tools::restore_auto_backup,
+            commands::tools::list_all_backups,
+            commands::tools::delete_backup,
+            commands::tools::read_audit_log,
+            commands::tools::cleanup_temp_artifacts,
+            commands::tools::run_startup_diagnostics,
+            commands::tools::get_onboarding_state,
+            commands::tools::generate_diagnostic_bundle,
+            commands::tools::get_recovery_candidates,
+            commands::tools::resolve_recovery,
             // 一键修复 commands
             commands::tools::list_fixers,
             commands::tools::execute_fixer,
+            // 开发用 commands（前端无入口）
+            commands::tools::get_command_trace,
+            commands::tools::get_cache_stats,
+            commands::tools::verify_cache_consistency,
+            commands::tools::make_session_fixture,
+            commands::tools::run_benchmark,
+            // 多窗口 commands
+            commands::window::open_session_window,
             // 中转抓包代理 commands
             commands::proxy::start_proxy,
             commands::proxy::stop_proxy,
@@ -134,6 +230,16 @@ pub fn run() {
         ])
         // `setup` 闭包：在应用窗口创建之前执行的初始化钩子
         .setup(|app| {
+            // 移动端没有用户主目录与系统 TEMP：改用应用沙箱内的数据目录与缓存目录（见 `utils::platform`）
+            #[cfg(mobile)]
+            {
+                use tauri::Manager;
+                utils::platform::init_mobile_dirs(app.path().app_data_dir()?, app.path().app_cache_dir()?);
+            }
+
+            // 注册 AppHandle，供 file_guard 写入后向所有窗口广播 session-updated
+            services::events::init(app.handle().clone());
+
             // 仅在开发调试模式下启用日志插件
             if cfg!(debug_assertions) {
                 app.handle().plugin(
@@ -156,6 +262,12 @@ pub fn run() {
         })
         // `tauri::generate_context!()` 宏：在编译时读取 `tauri.conf.json` 配置文件，
         // 生成包含应用名称、窗口配置、安全策略等信息的上下文对象。
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        // 退出前按保留策略清理 TEMP 下超龄的临时资源（后台线程执行，带超时）
+        .run(|_app, event| {
+            if let tauri::RunEvent::ExitRequested { .. } = event {
+                services::temp_cleanup::cleanup_on_exit();
+            }
+        });
 }
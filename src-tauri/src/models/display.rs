@@ -16,11 +16,22 @@
 //!      → 前端直接渲染
 //! ```
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use serde::Serialize;
+use serde::{Serialize, Serializer};
 use serde_json::Value;
 
+/// 按键排序序列化 HashMap
+///
+/// HashMap 的迭代顺序每次运行都不同，直接序列化会让同一会话的输出不稳定，
+/// 导致黄金快照无法逐字比对。内存中仍保留 HashMap 以获得 O(1) 查询。
+fn serialize_sorted<S: Serializer, V: Serialize>(
+    map: &HashMap<String, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
 /// 单条显示消息（独立 struct，与 serde_json::Value 无关联）
 ///
 /// 由 `transformer::transform_session` 生成，是原始 `SessionMessage`（`serde_json::Value`）
@@ -34,7 +45,7 @@ use serde_json::Value;
 /// ## 字段说明
 /// - `display_type`：决定前端渲染样式（"user" | "assistant" | "tool_result" | "compact_summary" | "system"）
 /// - `block_index_map`：`content[i]` 对应原始消息 `content[block_index_map[i]]`，编辑时用于精确回写
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct DisplayMessage {
     /// 原始消息的 UUID，用于编辑/删除操作时映射回原始数据
@@ -69,6 +80,12 @@ pub struct DisplayMessage {
     /// 待办事项列表（仅包含 todos 的消息）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub todos: Option<Vec<Value>>,
+    /// 停止原因（仅 assistant 消息的 `message.stop_reason`，如 "end_turn"、"tool_use"、"max_tokens"）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+    /// API 响应消息 ID（仅 assistant 消息的 `message.id`，流式拆分的多条记录共享同一 ID）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
 
     // ---- system 专属字段 ----
     /// 系统消息子类型标签：'技能' | '计划' | '系统'
@@ -85,6 +102,8 @@ pub struct DisplayMessage {
     /// 是否为遗弃消息（不在主链上）
     /// 主链 = 从 JSONL 最后一条消息沿 parentUuid 回溯到根的路径
     pub is_abandoned: bool,
+    /// 是否为失败的工具结果（tool_result 块带 `is_error: true`，仅 tool_result 消息可能为 true）
+    pub is_error: bool,
 }
 
 /// tool_use 块摘要信息
@@ -123,6 +142,10 @@ pub struct TokenStats {
     pub web_search_requests: u64,
     /// 服务端网页获取请求总次数（来自 usage.server_tool_use.web_fetch_requests）
     pub web_fetch_requests: u64,
+    /// 工具调用总次数（会话中 tool_use 块的数量）
+    pub tool_calls: u64,
+    /// 失败的工具调用次数（带 `is_error: true` 的 tool_result 数量）
+    pub failed_tool_calls: u64,
 }
 
 impl TokenStats {
@@ -165,6 +188,21 @@ impl TokenStats {
             }
         }
     }
+
+    /// 把另一份统计（如另一个会话的汇总）合并进来
+    ///
+    /// # 参数
+    /// - `other` - 要合并的统计
+    pub fn merge(&mut self, other: &TokenStats) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+        self.web_search_requests += other.web_search_requests;
+        self.web_fetch_requests += other.web_fetch_requests;
+        self.tool_calls += other.tool_calls;
+        self.failed_tool_calls += other.failed_tool_calls;
+    }
 }
 
 /// IPC 返回的完整转换结果（前端唯一数据源）
@@ -173,13 +211,306 @@ impl TokenStats {
 /// - `display_messages`：倒序排列（最新在前），配合 CSS `column-reverse` 实现优先渲染最新消息
 /// - `tool_use_map`：tool_use_id → ToolUseInfo 映射，供工具结果渲染器查询工具名称
 /// - `token_stats`：整个会话的 Token 使用量汇总
+/// - `cwd_changes` / `cwds`：工作目录切换点与会话涉及的全部工作目录
 #[derive(Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct TransformedSession {
     /// 倒序排列的显示消息列表（最新在前），配合 CSS `column-reverse`
     pub display_messages: Vec<DisplayMessage>,
-    /// tool_use_id → ToolUseInfo 映射
+    /// tool_use_id → ToolUseInfo 映射（按键排序序列化）
+    #[serde(serialize_with = "serialize_sorted")]
+    pub tool_use_map: HashMap<String, ToolUseInfo>,
+    /// Token 统计汇总
+    pub token_stats: TokenStats,
+    /// 工作目录切换点列表（按消息顺序），会话中途 cd 到其他目录时产生
+    pub cwd_changes: Vec<CwdChange>,
+    /// 会话涉及的全部工作目录（按首次出现顺序去重）
+    pub cwds: Vec<String>,
+    /// 会话文件不是 UTF-8 时的原始编码（如 "UTF-16LE"、"GBK"），内容已在读取时转码
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reencoded_from: Option<String>,
+    /// 分页读取时的 display_messages 总条数（未分页时省略）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_count: Option<usize>,
+}
+
+impl TransformedSession {
+    /// 截取 display_messages 的一个窗口，其余字段保持全量
+    ///
+    /// display_messages 为倒序（最新在前），因此 `offset = 0` 是最新的一页，
+    /// 偏移越大越早；前端滚动到顶部时以 `offset + 已加载条数` 请求更早的消息。
+    ///
+    /// # 参数
+    /// - `offset` - 起始偏移（超出总数时返回空窗口）
+    /// - `limit` - 最多条数；None 表示取到末尾
+    ///
+    /// # 返回值
+    /// 只含窗口内消息、`total_count` 为总条数的副本
+    pub fn page(&self, offset: usize, limit: Option<usize>) -> TransformedSession {
+        let total = self.display_messages.len();
+        let start = offset.min(total);
+        let end = limit.map_or(total, |limit| start.saturating_add(limit).min(total));
+        TransformedSession {
+            display_messages: self.display_messages[start..end].to_vec(),
+            tool_use_map: self.tool_use_map.clone(),
+            token_stats: self.token_stats.clone(),
+            cwd_changes: self.cwd_changes.clone(),
+            cwds: self.cwds.clone(),
+            reencoded_from: self.reencoded_from.clone(),
+            total_count: Some(total),
+        }
+    }
+}
+
+/// 消息写操作的回执
+///
+/// `delete_message` / `delete_messages` / `edit_message_content` 以回执模式调用时返回，
+/// 只含前端局部更新所需的差异，避免大会话每次写入都回传完整的 TransformedSession；
+/// 缓存中仍保存完整数据。对应前端 TypeScript 接口 `OperationReceipt`。
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationReceipt {
+    /// 写入后不再存在的 display_id（按写入前的顺序）
+    pub removed_display_ids: Vec<String>,
+    /// 新增或内容有变化的显示消息（按写入后 display_messages 的顺序，即最新在前）
+    pub updated_messages: Vec<DisplayMessage>,
+    /// 写入后的 Token 统计汇总
+    pub token_stats: TokenStats,
+}
+
+impl OperationReceipt {
+    /// 对比写入前后的显示消息生成回执
+    ///
+    /// # 参数
+    /// - `before` - 写入前的 display_messages
+    /// - `after` - 写入后重新转换的 TransformedSession
+    pub fn diff(before: &[DisplayMessage], after: &TransformedSession) -> Self {
+        let previous: HashMap<&str, &DisplayMessage> =
+            before.iter().map(|dm| (dm.display_id.as_str(), dm)).collect();
+        let remaining: HashSet<&str> = after.display_messages.iter().map(|dm| dm.display_id.as_str()).collect();
+        OperationReceipt {
+            removed_display_ids: before
+                .iter()
+                .filter(|dm| !remaining.contains(dm.display_id.as_str()))
+                .map(|dm| dm.display_id.clone())
+                .collect(),
+            updated_messages: after
+                .display_messages
+                .iter()
+                .filter(|dm| previous.get(dm.display_id.as_str()) != Some(dm))
+                .cloned()
+                .collect(),
+            token_stats: after.token_stats.clone(),
+        }
+    }
+}
+
+/// 消息写操作的返回值：完整会话或回执
+///
+/// 由命令的 `receipt` 开关决定，序列化时不加标签（前端按调用时的开关确定类型）。
+#[derive(Serialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum MessageOpResult {
+    /// 完整的 TransformedSession（默认）
+    Session(TransformedSession),
+    /// 只含差异的回执
+    Receipt(OperationReceipt),
+}
+
+impl MessageOpResult {
+    /// 按是否提供写入前的显示消息构造返回值
+    ///
+    /// # 参数
+    /// - `transformed` - 写入后重新转换的 TransformedSession
+    /// - `before` - 回执模式下写入前的 display_messages；None 时返回完整会话
+    pub fn new(transformed: TransformedSession, before: Option<Vec<DisplayMessage>>) -> Self {
+        match before {
+            Some(before) => MessageOpResult::Receipt(OperationReceipt::diff(&before, &transformed)),
+            None => MessageOpResult::Session(transformed),
+        }
+    }
+}
+
+/// 流式加载会话的首个响应
+///
+/// 由 `read_session_messages_streamed` 在解析完成后立即返回，
+/// 之后的 DisplayMessage 通过 `session-chunk` 事件分批推送。
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStreamStart {
+    /// 本次加载的流 ID，事件载荷中携带相同 ID，前端据此过滤过期事件
+    pub stream_id: u64,
+    /// 原始消息条数，供前端估算加载进度（缓存命中时为 DisplayMessage 条数）
+    pub total_raw: usize,
+    /// 缓存命中时直接返回完整会话，此时不会推送任何事件
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached: Option<TransformedSession>,
+    /// 会话文件不是 UTF-8 时的原始编码（缓存命中时见 `cached.reencoded_from`）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reencoded_from: Option<String>,
+}
+
+/// `session-chunk` 事件载荷：一批按时间顺序排列的 DisplayMessage
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionChunk {
+    /// 所属流 ID
+    pub stream_id: u64,
+    /// 分块序号（从 0 开始），前端按序号拼接以保证顺序
+    pub seq: u64,
+    /// 本批 DisplayMessage
+    pub display_messages: Vec<DisplayMessage>,
+    /// 本批新增的 tool_use_id → ToolUseInfo 映射（按键排序序列化）
+    #[serde(serialize_with = "serialize_sorted")]
     pub tool_use_map: HashMap<String, ToolUseInfo>,
+}
+
+/// `session-stream-done` 事件载荷：转换完成后的会话级汇总
+///
+/// 发送前完整结果已写入缓存，后续搜索、统计等命令可直接命中。
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStreamDone {
+    /// 所属流 ID
+    pub stream_id: u64,
+    /// 已推送的分块总数，前端收齐后再合并汇总数据
+    pub chunks: u64,
     /// Token 统计汇总
     pub token_stats: TokenStats,
+    /// 工作目录切换点列表
+    pub cwd_changes: Vec<CwdChange>,
+    /// 会话涉及的全部工作目录
+    pub cwds: Vec<String>,
+}
+
+/// 工作目录切换点
+///
+/// 由 transformer 在顺序 reduce 阶段比较相邻 DisplayMessage 的 `cwd` 生成。
+/// 前端在 `display_id` 对应的消息之前插入分隔条，提示用户工作目录已切换。
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CwdChange {
+    /// 切换后第一条消息的 display_id
+    pub display_id: String,
+    /// 切换前的工作目录
+    pub from: String,
+    /// 切换后的工作目录
+    pub to: String,
+}
+
+/// 单次工具调用的输入输出配对详情
+///
+/// 由 `transformer::find_tool_invocation` 从 TransformedSession 中定位生成，
+/// 包含完整的 tool_use 块（含 input）与 tool_result 块（content 不截断）。
+/// 任一侧在会话中找不到时对应字段为 None，并由 `missing` 说明缺失的一侧。
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolInvocation {
+    /// 工具调用 ID（tool_use 块的 `id`，即 tool_result 块的 `tool_use_id`）
+    pub tool_use_id: String,
+    /// 工具名称（来自 tool_use_map，tool_use 缺失时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 完整的 tool_use 内容块
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_use: Option<Value>,
+    /// 完整的 tool_result 内容块
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_result: Option<Value>,
+    /// tool_use 所在消息的 uuid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_use_uuid: Option<String>,
+    /// tool_result 所在消息的 uuid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_result_uuid: Option<String>,
+    /// tool_result 与 tool_use 的时间差（毫秒），任一侧缺失或时间戳无法解析时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<i64>,
+    /// 缺失的一侧："tool_use" 或 "tool_result"；配对完整时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub missing: Option<String>,
+}
+
+/// 向会话追加 JSONL 片段的结果
+///
+/// 由 `append_raw_lines` command 返回。
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AppendLinesResult {
+    /// 新增的消息条数
+    pub appended: usize,
+    /// 追加后重新转换的会话数据
+    pub session: TransformedSession,
+}
+
+/// 撤销操作的结果
+///
+/// 由 `undo_last_operation` command 返回。
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoResult {
+    /// 被撤销的操作描述
+    pub undone: String,
+    /// 剩余可撤销操作的描述（栈顶在前）
+    pub remaining: Vec<String>,
+    /// 撤销后重新转换的会话数据
+    pub session: TransformedSession,
+}
+
+/// 跨会话复制 / 移动消息的结果
+///
+/// 由 `copy_messages_to_session` command 返回。
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyMessagesResult {
+    /// 复制到目标会话的消息条数
+    pub copied: usize,
+    /// 副本的新 uuid（按插入顺序）
+    pub new_uuids: Vec<String>,
+}
+
+/// 单个工具的调用与失败统计
+///
+/// 由 `transformer::tool_stats` 生成，供 `get_session_stats` 按工具名展示失败率。
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolStat {
+    /// 工具名称（找不到对应 tool_use 的失败结果归入「未知工具」）
+    pub name: String,
+    /// 调用次数
+    pub calls: u64,
+    /// 失败次数
+    pub failures: u64,
+    /// 失败率（failures / calls，calls 为 0 时为 0.0）
+    pub failure_rate: f64,
+}
+
+/// 单个 stop_reason 的出现次数
+///
+/// 由 `transformer::stop_reason_stats` 生成，供 `get_session_stats` 展示停止原因分布。
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StopReasonStat {
+    /// 停止原因（如 "end_turn"、"tool_use"、"max_tokens"）
+    pub stop_reason: String,
+    /// 出现次数（按 API 响应计，同一 `message.id` 只计一次）
+    pub count: u64,
+}
+
+/// 会话统计
+///
+/// 由 `get_session_stats` command 返回：会话级汇总 + 按工具名的失败率 + 停止原因分布。
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStats {
+    /// Token 与工具调用汇总
+    pub token_stats: TokenStats,
+    /// 按工具名的调用与失败统计（按失败次数降序，其次按调用次数降序）
+    pub tools: Vec<ToolStat>,
+    /// 停止原因分布（按次数降序）
+    pub stop_reasons: Vec<StopReasonStat>,
+    /// 显示消息条数（`exclude_noise` 时不含压缩摘要与计划以外的系统消息）
+    pub message_count: usize,
+    /// text 块字符数（统计范围同 `message_count`）
+    pub text_chars: usize,
 }
@@ -6,9 +6,12 @@
 //! 这些结构体采用 `serde_json::Value` 处理 Claude Code JSONL 文件中的动态字段，
 //! 避免因 Claude Code 版本升级添加新字段而导致反序列化失败。
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 
+/// 小于此值的时间戳视为秒（1e11 毫秒约为 1973 年，1e11 秒则远在公元 5000 年后）
+const SECONDS_TIMESTAMP_LIMIT: f64 = 1e11;
+
 /// 会话消息数据结构
 ///
 /// 对应 Claude Code 会话 JSONL 文件中的每一行记录。
@@ -63,11 +66,101 @@ pub struct HistoryEntry {
     pub pasted_contents: serde_json::Map<String, Value>,
 
     /// 时间戳：Unix 毫秒时间戳
+    ///
+    /// 旧版条目可能写成整数秒、浮点秒或数字字符串，反序列化时统一归一化为毫秒；
+    /// 缺失或无法识别时为 0。
+    #[serde(default, deserialize_with = "deserialize_timestamp_millis")]
     pub timestamp: u64,
 
-    /// 项目路径：该历史记录关联的项目目录路径
+    /// 项目路径：该历史记录关联的项目目录路径（缺失时为空串）
+    #[serde(default)]
     pub project: String,
 
-    /// 会话 ID：该历史记录所属的会话标识符
+    /// 会话 ID：该历史记录所属的会话标识符（缺失时为空串）
+    #[serde(default)]
     pub session_id: String,
 }
+
+/// 把整数毫秒 / 整数秒 / 浮点秒 / 数字字符串形式的时间戳归一化为毫秒
+///
+/// 整数与浮点数都按 `SECONDS_TIMESTAMP_LIMIT` 区分秒与毫秒（浮点毫秒如 `1699999999123.5` 不会被再乘 1000）。
+/// 无法识别的值返回 0，不让整行解析失败。
+fn deserialize_timestamp_millis<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    let number = match &value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+
+    let millis = match number {
+        Some(f) if f.is_finite() && f > 0.0 => {
+            if f < SECONDS_TIMESTAMP_LIMIT {
+                f * 1000.0
+            } else {
+                f
+            }
+        }
+        _ => 0.0,
+    };
+    Ok(millis.round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamp_of(line: &str) -> u64 {
+        serde_json::from_str::<HistoryEntry>(line).unwrap().timestamp
+    }
+
+    #[test]
+    fn test_history_timestamp_integer_millis() {
+        assert_eq!(
+            timestamp_of(r#"{"display":"hi","timestamp":1699999999123,"project":"/p","sessionId":"s"}"#),
+            1699999999123
+        );
+    }
+
+    #[test]
+    fn test_history_timestamp_integer_seconds() {
+        assert_eq!(
+            timestamp_of(r#"{"display":"hi","timestamp":1699999999,"project":"/p","sessionId":"s"}"#),
+            1699999999000
+        );
+    }
+
+    #[test]
+    fn test_history_timestamp_float_seconds() {
+        assert_eq!(
+            timestamp_of(r#"{"display":"hi","timestamp":1699999999.123,"project":"/p","sessionId":"s"}"#),
+            1699999999123
+        );
+    }
+
+    #[test]
+    fn test_history_timestamp_numeric_string() {
+        assert_eq!(timestamp_of(r#"{"display":"hi","timestamp":"1699999999123"}"#), 1699999999123);
+        assert_eq!(timestamp_of(r#"{"display":"hi","timestamp":" 1699999999.5 "}"#), 1699999999500);
+        assert_eq!(timestamp_of(r#"{"display":"hi","timestamp":"1699999999"}"#), 1699999999000);
+    }
+
+    #[test]
+    fn test_history_timestamp_float_millis() {
+        assert_eq!(timestamp_of(r#"{"display":"hi","timestamp":1699999999123.4}"#), 1699999999123);
+        assert_eq!(timestamp_of(r#"{"display":"hi","timestamp":1.699999999123e12}"#), 1699999999123);
+        assert_eq!(timestamp_of(r#"{"display":"hi","timestamp":"1699999999123.6"}"#), 1699999999124);
+    }
+
+    #[test]
+    fn test_history_missing_fields_default() {
+        let entry: HistoryEntry = serde_json::from_str(r#"{"display":"hi","timestamp":"yesterday"}"#).unwrap();
+        assert_eq!(entry.timestamp, 0);
+        assert_eq!(entry.project, "");
+        assert_eq!(entry.session_id, "");
+        assert_eq!(timestamp_of(r#"{"display":"hi"}"#), 0);
+    }
+}
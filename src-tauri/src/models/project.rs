@@ -20,6 +20,12 @@ use serde::{Deserialize, Serialize};
 ///   name: string;
 ///   path: string;
 ///   sessions: Session[];
+///   fragmentOf?: string;
+///   source?: string;
+///   pinned: boolean;
+///   sessionCount: number;
+///   latestTimestamp?: string;
+///   archived: boolean;
 /// }
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,11 +34,39 @@ pub struct Project {
     /// 同时也是 `~/.claude/projects/` 下的子目录名
     pub name: String,
 
-    /// 项目路径：解码还原后的完整文件系统路径（如 "G:\ClaudeProjects\Test"）
+    /// 项目路径：完整文件系统路径（如 "G:\ClaudeProjects\Test"）
+    /// 优先取用户覆盖（见 `services::project_meta`），其次是会话记录的 cwd，
+    /// 都没有时为目录名的解码结果（见 `path::resolve_project_path`）
     pub path: String,
 
-    /// 会话列表：该项目下的所有聊天会话，按时间戳降序排列
+    /// 会话列表：该项目下的所有聊天会话，置顶会话在前，其余按时间戳降序排列
     pub sessions: Vec<Session>,
+
+    /// 碎片项目的父项目名称：在子目录中误启动 Claude Code 产生的「空项目」
+    /// （判定规则见 `services::fragments`），前端据此提示归并；非碎片项目为 None
+    #[serde(rename = "fragmentOf", default, skip_serializing_if = "Option::is_none")]
+    pub fragment_of: Option<String>,
+
+    /// 所属数据源 ID（见 `services::data_sources`）；默认数据源 `~/.claude/` 的项目为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// 是否被用户置顶：来自 `~/.mo/CCR/pins.json`，置顶项目在列表中排在最前
+    #[serde(default)]
+    pub pinned: bool,
+
+    /// 会话数量：扫描时按 `sessions` 填充；`scan_projects` 只返回项目索引时 `sessions` 为空，
+    /// 前端以此显示数量，再通过 `scan_project_sessions` 按需加载会话列表
+    #[serde(rename = "sessionCount", default)]
+    pub session_count: usize,
+
+    /// 最新会话的时间戳（ISO 8601），没有会话时为 None；填充时机同 `session_count`
+    #[serde(rename = "latestTimestamp", default, skip_serializing_if = "Option::is_none")]
+    pub latest_timestamp: Option<String>,
+
+    /// 是否已被用户归档：来自 `~/.mo/CCR/archived.json`，归档项目默认不出现在扫描结果中
+    #[serde(default)]
+    pub archived: bool,
 }
 
 /// 会话数据结构
@@ -44,15 +78,15 @@ pub struct Project {
 /// - **基础字段**（id, timestamp, file_path）：来自文件系统 stat 元数据
 /// - **轻量读取字段**（summary, first_prompt, git_branch, cwd, tag 等）：
 ///   来自 scanner 的 head+tail 轻量读取策略，读取 JSONL 文件的前 64KB 和后 64KB
-/// - **message_count**：扫描时设为 0，读取完整会话内容后更新
+/// - **message_count**：扫描时要求统计（`include_message_count`）时为消息记录数，否则为 0；
+///   随扫描快照缓存，文件变化后重新统计；CCR 改写会话后按写入的记录就地更新
 ///
 /// ## 标题优先级（对应 Claude Code 源码 `parseSessionInfoFromLite`）
 /// `name` 字段按以下优先级填充：
 /// 1. `custom-title` 条目中的 `customTitle`（用户手动设置的标题）
 /// 2. `ai-title` 条目中的 `aiTitle`（AI 自动生成的标题）
 /// 3. `last-prompt` 条目中的 `lastPrompt`（最后一条用户输入）
-///
-/// 如果以上都不存在，前端可回退使用 `summary` 或 `first_prompt` 作为显示文本。
+/// 4. 首条普通用户消息（`first_prompt`）的前 50 个字符
 ///
 /// 对应前端 TypeScript 接口：
 /// ```typescript
@@ -70,6 +104,7 @@ pub struct Project {
 ///   createdAt?: string;
 ///   fileSize?: number;
 ///   isSidechain: boolean;
+///   version?: string;
 /// }
 /// ```
 ///
@@ -82,7 +117,7 @@ pub struct Session {
     pub id: String,
 
     /// 会话名称：用户自定义的显示名称（可选）
-    /// 优先级：customTitle > aiTitle > lastPrompt
+    /// 优先级：customTitle > aiTitle > lastPrompt > 首条用户消息前 50 字符
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
 
@@ -90,8 +125,8 @@ pub struct Session {
     /// 前端接收后需要通过 `new Date(timestamp)` 转换为 Date 对象
     pub timestamp: String,
 
-    /// 消息数量：该会话中包含的消息条数
-    /// 扫描时设为 0，读取会话内容后更新
+    /// 消息数量：含 `uuid` 的记录行数，不含 summary 等元数据行（`line_count` 才是全部行数）
+    /// 扫描时要求统计或 CCR 改写该会话后填充，否则为 0
     pub message_count: u32,
 
     /// 文件路径：JSONL 文件的完整绝对路径，用于后续读取会话内容
@@ -140,4 +175,185 @@ pub struct Session {
     /// 默认为 false（向后兼容：旧数据中无此信息时视为主链会话）
     #[serde(default)]
     pub is_sidechain: bool,
+
+    /// 写入该会话的 Claude Code 版本：取 JSONL 尾部最后一条带 `version` 字段的消息
+    /// 不同版本的 JSONL 字段存在差异，用于排障时判断会话由哪个版本写入
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+
+    /// 是否被用户锁定为只读：来自 `~/.mo/CCR/locked-sessions.json`
+    /// 锁定的会话拒绝一切经 file_guard 的写入，前端显示锁图标
+    #[serde(default)]
+    pub locked: bool,
+
+    /// 是否有待发送的草稿：来自 `~/.mo/CCR/drafts.json`，草稿不写入会话 JSONL
+    #[serde(default)]
+    pub has_draft: bool,
+
+    /// 自上次在 CCR 中查看后文件是否又被修改：来自 `~/.mo/CCR/last-viewed.json`，前端显示未读圆点
+    /// 从未在 CCR 中打开过的会话为 false
+    #[serde(default)]
+    pub updated_since_viewed: bool,
+
+    /// CCR 会话别名：来自 `~/.mo/CCR/session-aliases.json`，前端显示时优先于 `name`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+
+    /// 是否被用户置顶：来自 `~/.mo/CCR/pins.json`，置顶会话在项目内排在最前
+    #[serde(default)]
+    pub pinned: bool,
+
+    /// 是否为子 agent 会话：文件名以 `agent-` 开头，仅在扫描时要求包含子 agent 会话才会出现
+    #[serde(default)]
+    pub is_agent: bool,
+
+    /// 文件行数：仅在扫描时要求统计（`include_message_count`）才与 `message_count` 一起逐行统计填充
+    /// 与 `file_size` 一起在文件 mtime / 大小变化时重新统计
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_count: Option<u64>,
+}
+
+/// 项目分页扫描结果
+///
+/// 由 `scan_projects` 返回。项目目录按 mtime 倒序分页，
+/// 页内项目再按最新会话时间倒序排列；前端通过 `offset + projects.len()` 请求下一页。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectPage {
+    /// 本页的项目列表
+    pub projects: Vec<Project>,
+    /// `~/.claude/projects/` 下的项目目录总数
+    pub total_projects: usize,
+    /// 本页在全部项目目录（按 mtime 倒序）中的起始偏移
+    pub offset: usize,
+    /// 是否还有未加载的项目
+    pub has_more: bool,
+    /// 本次实际扫描时无法读取的项目目录（命中缓存时为空）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ScanWarning>,
+}
+
+/// 完整扫描结果
+///
+/// 由 `scanner::scan_all_projects` 返回：能读取的项目与跳过的目录分开给出。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanResult {
+    /// 项目列表（按最新会话时间倒序）
+    pub projects: Vec<Project>,
+    /// 无法读取的项目目录
+    pub warnings: Vec<ScanWarning>,
+}
+
+/// 扫描时跳过某个目录的原因
+///
+/// 对应前端 TypeScript 类型 `ScanWarningKind`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScanWarningKind {
+    /// 权限不足
+    PermissionDenied,
+    /// 符号链接指向的目标不存在
+    BrokenSymlink,
+    /// 其他 I/O 错误
+    Unreadable,
+}
+
+/// 扫描时跳过的目录
+///
+/// 单个项目目录不可读不会让整次扫描失败，而是作为警告随结果返回，
+/// 前端据此提示「有 N 个项目无法读取」。对应前端 TypeScript 接口 `ScanWarning`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanWarning {
+    /// 目录的绝对路径
+    pub path: String,
+    /// 跳过原因
+    pub kind: ScanWarningKind,
+    /// 原始错误信息
+    pub message: String,
+}
+
+/// 单个项目的会话分页结果
+///
+/// 由 `scan_project_sessions` 返回，会话按置顶优先、时间戳降序排列。
+/// 对应前端 TypeScript 接口 `SessionPage`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionPage {
+    /// 本页的会话列表
+    pub sessions: Vec<Session>,
+    /// 项目下的会话总数
+    pub total_sessions: usize,
+    /// 本页在全部会话中的起始偏移
+    pub offset: usize,
+    /// 是否还有未加载的会话
+    pub has_more: bool,
+}
+
+/// 项目扫描进度（`scan-progress` 事件载荷）
+///
+/// 由 `scan_projects` 在缓存未命中、实际扫描时节流推送。
+/// 对应前端 TypeScript 接口 `ScanProgress`。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanProgress {
+    /// 已扫描完成的项目数
+    pub done: usize,
+    /// 本次需要扫描的项目总数
+    pub total: usize,
+    /// 最近完成的项目目录名
+    pub current_project: String,
+}
+
+/// 项目与会话计数（侧边栏角标）
+///
+/// 由 `get_counts` 返回，只统计目录条目，不读取会话内容。
+/// 对应前端 TypeScript 接口 `ProjectCounts`。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectCounts {
+    /// `projects/` 下的项目目录数（不含已归档项目）
+    pub projects: usize,
+    /// 会话文件数（不含子 agent 会话）
+    pub sessions: usize,
+    /// `agent-` 前缀的子 agent 会话文件数
+    pub agent_sessions: usize,
+}
+
+/// 项目摘要：项目目录下 CLAUDE.md / README.md 的开头部分
+///
+/// 由 `get_project_summary` command 返回，对应前端 TypeScript 接口 `ProjectSummary`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSummary {
+    /// 摘要来源文件的绝对路径
+    pub file_path: String,
+    /// 文件开头的文本（行数与字节数均有上限）
+    pub text: String,
+    /// 文件内容是否超出上限被截断
+    pub truncated: bool,
+}
+
+/// 项目清单导出的单行数据
+///
+/// 由 `export::projects_overview` 从扫描结果汇总生成，
+/// CSV 与 JSON 两种导出格式共用该结构。时间字段已转换为本地时区。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectOverviewRow {
+    /// 项目名称（编码后的目录名）
+    pub name: String,
+    /// 项目路径（解码后的文件系统路径）
+    pub path: String,
+    /// 会话数量
+    pub session_count: usize,
+    /// 所有会话的消息数量之和（扫描阶段未统计的会话计为 0）
+    pub message_count: u64,
+    /// 所有会话文件的总字节数
+    pub total_size: u64,
+    /// 最近活跃时间（最新会话文件修改时间，本地时区）
+    pub last_active: Option<String>,
+    /// 最早创建时间（各会话首条消息时间的最小值，本地时区）
+    pub first_created: Option<String>,
 }
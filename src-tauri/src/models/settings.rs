@@ -83,3 +83,26 @@ pub struct EnvSwitcherConfig {
     /// 当前激活的配置组 ID：为 `null`（None）表示没有激活任何配置组
     pub active_profile_id: Option<String>,
 }
+
+/// 宽容读取的配置文件
+///
+/// 由 `read_settings` / `read_env_config` 返回。严格 JSON 解析失败时会退回 JSONC 容错解析
+/// （剥离注释与尾逗号，见 `utils::jsonc`），此时 `was_lenient` 为 true，
+/// 前端应提示用户保存后注释将丢失。
+///
+/// 对应前端 TypeScript 接口：
+/// ```typescript
+/// interface LenientParsed<T> {
+///   value: T;
+///   wasLenient: boolean;
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LenientParsed<T> {
+    /// 解析得到的配置内容
+    pub value: T,
+
+    /// 是否经过 JSONC 容错解析（文件含注释或尾逗号）
+    pub was_lenient: bool,
+}
@@ -0,0 +1,246 @@
+//! # 外观配置服务
+//!
+//! 管理 Claude Code 的状态栏与输出样式：
+//! - `settings.json` 的 `statusLine`（状态栏命令）与 `outputStyle`（当前输出样式名）字段
+//! - `~/.claude/output-styles/` 下的自定义输出样式（带 YAML frontmatter 的 Markdown 文件）
+//!
+//! 读取时字段缺失或格式不符一律返回默认值；写入 settings.json 时只改动这两个字段，
+//! 其余字段原样保留。
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::services::skill::split_frontmatter;
+
+/// 自定义输出样式目录名（位于 Claude 数据目录下）
+pub const OUTPUT_STYLES_DIR: &str = "output-styles";
+
+/// 未设置 `outputStyle` 时 Claude Code 使用的样式名
+pub const DEFAULT_OUTPUT_STYLE: &str = "default";
+
+/// 状态栏配置（`settings.json` 的 `statusLine` 字段）
+///
+/// 对应前端 TypeScript 接口 `StatusLineConfig`。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusLineConfig {
+    /// 状态栏类型，目前只有 "command"
+    #[serde(rename = "type", default = "default_status_line_type")]
+    pub kind: String,
+    /// 生成状态栏内容的命令
+    pub command: String,
+    /// 状态栏左右内边距（字符数，省略时由 Claude Code 决定）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub padding: Option<u32>,
+}
+
+/// 状态栏类型默认值（serde default）
+fn default_status_line_type() -> String {
+    "command".to_string()
+}
+
+/// 自定义输出样式文件
+///
+/// 对应前端 TypeScript 接口 `OutputStyleFile`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputStyleFile {
+    /// 样式名（frontmatter 的 `name`，缺失时取文件名）
+    pub name: String,
+    /// 样式描述（frontmatter 的 `description`）
+    pub description: Option<String>,
+    /// 样式文件的绝对路径
+    pub file_path: String,
+}
+
+/// 外观配置
+///
+/// 由 `get_appearance_config` command 返回，对应前端 TypeScript 接口 `AppearanceConfig`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppearanceConfig {
+    /// 状态栏配置（未配置时为 None）
+    pub status_line: Option<StatusLineConfig>,
+    /// 当前输出样式名（未设置时为 `DEFAULT_OUTPUT_STYLE`）
+    pub output_style: String,
+    /// `output-styles/` 下的自定义样式，按名称排序
+    pub output_styles: Vec<OutputStyleFile>,
+}
+
+/// 从 settings.json 与样式目录聚合外观配置
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录
+/// - `settings` - 已解析的 settings.json（文件不存在时传空对象）
+pub async fn load_appearance(claude_path: &Path, settings: &Value) -> AppearanceConfig {
+    let status_line = settings
+        .get("statusLine")
+        .and_then(|v| serde_json::from_value(v.clone()).ok());
+    let output_style = settings
+        .get("outputStyle")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or(DEFAULT_OUTPUT_STYLE)
+        .to_string();
+
+    AppearanceConfig {
+        status_line,
+        output_style,
+        output_styles: list_output_styles(&claude_path.join(OUTPUT_STYLES_DIR)).await,
+    }
+}
+
+/// 把外观字段写入 settings 对象（只改动 `statusLine` / `outputStyle`）
+///
+/// # 参数
+/// - `settings` - 原 settings.json 内容（非对象时替换为空对象）
+/// - `status_line` - 状态栏配置，None 表示移除该字段
+/// - `output_style` - 输出样式名，None、空串或 `DEFAULT_OUTPUT_STYLE` 表示移除该字段
+pub fn apply_appearance(
+    settings: &mut Value,
+    status_line: Option<&StatusLineConfig>,
+    output_style: Option<&str>,
+) -> Result<(), String> {
+    if !settings.is_object() {
+        *settings = Value::Object(serde_json::Map::new());
+    }
+    let Some(map) = settings.as_object_mut() else {
+        return Ok(());
+    };
+
+    match status_line {
+        Some(config) => {
+            let value = serde_json::to_value(config)
+                .map_err(|e| format!("序列化状态栏配置失败: {}", e))?;
+            map.insert("statusLine".to_string(), value);
+        }
+        None => {
+            map.remove("statusLine");
+        }
+    }
+
+    match output_style.map(str::trim) {
+        Some(style) if !style.is_empty() && style != DEFAULT_OUTPUT_STYLE => {
+            map.insert("outputStyle".to_string(), Value::String(style.to_string()));
+        }
+        _ => {
+            map.remove("outputStyle");
+        }
+    }
+    Ok(())
+}
+
+/// 校验样式文件路径：必须是 `<claude_path>/output-styles/` 下直接存放的 `.md` 文件
+///
+/// # 返回值
+/// 校验通过的样式文件路径
+///
+/// # 错误
+/// 路径不在样式目录下或不是 `.md` 文件时返回错误
+pub fn validate_style_path(claude_path: &Path, file_path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(file_path);
+    let in_styles_dir = path.parent() == Some(claude_path.join(OUTPUT_STYLES_DIR).as_path());
+    let is_markdown = path.extension().and_then(|e| e.to_str()) == Some("md");
+    let file_name_ok = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.len() > ".md".len() && !n.starts_with('.'));
+
+    if !in_styles_dir || !is_markdown || !file_name_ok {
+        return Err(format!("不是有效的输出样式文件: {}", file_path));
+    }
+    Ok(path)
+}
+
+/// 列出样式目录下的 `.md` 文件（目录不存在时返回空列表）
+async fn list_output_styles(dir: &Path) -> Vec<OutputStyleFile> {
+    let mut styles = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return styles;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("md") {
+            continue;
+        }
+        let Ok(content) = tokio::fs::read_to_string(&path).await else {
+            continue;
+        };
+        let file_stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (name, description) = parse_style_frontmatter(&content);
+        styles.push(OutputStyleFile {
+            name: name.unwrap_or(file_stem),
+            description,
+            file_path: path.to_string_lossy().to_string(),
+        });
+    }
+    styles.sort_by(|a, b| a.name.cmp(&b.name));
+    styles
+}
+
+/// 从样式文件的 frontmatter 中提取 `name` 与 `description`
+fn parse_style_frontmatter(content: &str) -> (Option<String>, Option<String>) {
+    let Some((yaml, _)) = split_frontmatter(content) else {
+        return (None, None);
+    };
+    let Ok(serde_yaml::Value::Mapping(map)) = serde_yaml::from_str::<serde_yaml::Value>(yaml) else {
+        return (None, None);
+    };
+    let get = |key: &str| {
+        map.get(serde_yaml::Value::String(key.to_string()))
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    (get("name"), get("description"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_appearance_keeps_other_fields() {
+        let mut settings = json!({"model": "opus", "outputStyle": "Learning"});
+        let status_line = StatusLineConfig {
+            kind: "command".to_string(),
+            command: "~/.claude/statusline.sh".to_string(),
+            padding: None,
+        };
+        apply_appearance(&mut settings, Some(&status_line), Some("Explanatory")).unwrap();
+        assert_eq!(
+            settings,
+            json!({
+                "model": "opus",
+                "outputStyle": "Explanatory",
+                "statusLine": {"type": "command", "command": "~/.claude/statusline.sh"}
+            })
+        );
+
+        apply_appearance(&mut settings, None, Some(DEFAULT_OUTPUT_STYLE)).unwrap();
+        assert_eq!(settings, json!({"model": "opus"}));
+    }
+
+    #[test]
+    fn test_validate_style_path_and_frontmatter() {
+        let claude = Path::new("/home/u/.claude");
+        assert!(validate_style_path(claude, "/home/u/.claude/output-styles/concise.md").is_ok());
+        assert!(validate_style_path(claude, "/home/u/.claude/output-styles/a/b.md").is_err());
+        assert!(validate_style_path(claude, "/home/u/.claude/settings.json").is_err());
+        assert!(validate_style_path(claude, "/home/u/.claude/output-styles/.md").is_err());
+
+        let content = "---\nname: Concise\ndescription: Short answers\n---\n\nBe brief.";
+        assert_eq!(
+            parse_style_frontmatter(content),
+            (Some("Concise".to_string()), Some("Short answers".to_string()))
+        );
+        assert_eq!(parse_style_frontmatter("Be brief."), (None, None));
+    }
+}
@@ -0,0 +1,119 @@
+//! # 项目归档
+//!
+//! 长期不用的项目可以归档：默认不出现在 `scan_projects` 的结果中，但不移动、不修改任何
+//! Claude 原始文件，只是 CCR 侧的视图状态。归档列表持久化在 `~/.mo/CCR/archived.json`：
+//!
+//! ```json
+//! { "projects": ["<项目目录绝对路径>"] }
+//! ```
+//!
+//! 与置顶列表（`pins`）相同，路径经 `session_lock::normalize` 归一化，以目录绝对路径为键；
+//! 读写由 `path_set_store` 完成，读取时只在结果中剔除已删除项目的记录，下一次修改时才从文件中移除。
+//!
+//! 一次扫描只读取一次归档列表：调用方先 `load_active`，再用同一份结果过滤目录（`retain_unarchived`）
+//! 并交给 `scanner` 标记项目的归档状态。
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::services::path_set_store::{PathSetStore, PathSets};
+use crate::services::session_lock;
+
+/// 归档列表文件（位于 `~/.mo/CCR/`）
+static STORE: PathSetStore = PathSetStore::new("archived.json", "归档列表");
+
+/// 归档列表
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Archived {
+    /// 已归档项目的目录路径（归一化）
+    #[serde(default)]
+    pub projects: BTreeSet<String>,
+}
+
+impl Archived {
+    /// 项目目录是否已归档
+    pub fn is_archived(&self, project_dir: &Path) -> bool {
+        self.projects.contains(&session_lock::normalize(&project_dir.to_string_lossy()))
+    }
+
+    /// 从项目目录列表中移除已归档的项目
+    ///
+    /// # 参数
+    /// - `project_dirs` - `(目录名, 完整路径)` 列表，如 `scanner::list_project_dirs` 的结果
+    pub fn retain_unarchived(&self, project_dirs: &mut Vec<(String, PathBuf)>) {
+        if !self.projects.is_empty() {
+            project_dirs.retain(|(_, dir)| !self.is_archived(dir));
+        }
+    }
+}
+
+impl PathSets for Archived {
+    fn sets_mut(&mut self) -> Vec<&mut BTreeSet<String>> {
+        vec![&mut self.projects]
+    }
+}
+
+/// 读取归档列表，剔除已删除项目的记录（只读，不写回文件）
+///
+/// # 错误
+/// 文件存在但读取、解析失败时返回错误
+pub async fn load_active() -> Result<Archived, String> {
+    STORE.load_active().await
+}
+
+/// 设置项目的归档状态
+///
+/// # 参数
+/// - `project_dir` - 项目目录的绝对路径
+/// - `archived` - true 归档，false 取消归档
+///
+/// # 返回值
+/// 状态是否发生了变化（重复归档 / 取消归档返回 false）
+///
+/// # 错误
+/// 归档列表读取、解析或写入失败时返回错误
+pub async fn set_archived(project_dir: &Path, archived: bool) -> Result<bool, String> {
+    let key = session_lock::normalize(&project_dir.to_string_lossy());
+    STORE
+        .update(|list: &mut Archived| {
+            if archived {
+                list.projects.insert(key)
+            } else {
+                list.projects.remove(&key)
+            }
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_set_archived_and_retain_unarchived() {
+        let projects = crate::utils::platform::test_home().join("archive-test");
+        let (kept, hidden) = (projects.join("-w-kept"), projects.join("-w-hidden"));
+        std::fs::create_dir_all(&kept).unwrap();
+        std::fs::create_dir_all(&hidden).unwrap();
+
+        assert!(set_archived(&hidden, true).await.unwrap());
+        // 重复归档不算变化
+        assert!(!set_archived(&hidden, true).await.unwrap());
+
+        let archived = load_active().await.unwrap();
+        assert!(archived.is_archived(&hidden) && !archived.is_archived(&kept));
+        let mut dirs = vec![("-w-kept".to_string(), kept.clone()), ("-w-hidden".to_string(), hidden.clone())];
+        archived.retain_unarchived(&mut dirs);
+        assert_eq!(dirs, [("-w-kept".to_string(), kept.clone())]);
+
+        // 项目目录被删除后，读取结果中不再包含其记录
+        std::fs::remove_dir_all(&hidden).unwrap();
+        assert!(!load_active().await.unwrap().is_archived(&hidden));
+        assert!(set_archived(&hidden, false).await.unwrap());
+        assert!(!set_archived(&hidden, false).await.unwrap());
+
+        std::fs::remove_dir_all(&projects).unwrap();
+    }
+}
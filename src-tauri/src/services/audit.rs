@@ -0,0 +1,448 @@
+//! # 敏感操作审计日志
+//!
+//! 记录所有经过 `file_guard` 的写入与删除，回答「某次操作何时改过这个会话」。
+//! 每次写入 / 删除结束后追加一行 JSON 到 `~/.mo/CCR/audit.jsonl`：
+//!
+//! ```json
+//! {"timestamp":"2025-01-01T00:00:00.000Z","operation":"delete_message","action":"write",
+//!  "targetPath":"...","contentSize":1024,"backupPath":"...","success":true,"batchId":"..."}
+//! ```
+//!
+//! ## 批次
+//! 一次操作可能修改多个文件（如 File / Full 档位的修复项）。在 `with_batch` 中执行的写入
+//! 共享同一个批次 ID，批次 ID 通过 tokio task-local 传递，`file_guard` 的调用方无需改动签名。
+//!
+//! ## 失败处理
+//! 审计写入失败只写 warn 日志并累加 `AppCache` 的失败计数（见 `get_cache_stats`），
+//! 不影响已完成的主操作。
+//!
+//! 批量删除消息、跨会话复制 / 移动、合并会话与归档删除会话都在 command 层包进 `with_batch`，
+//! 同一次操作涉及的多个文件可按批次 ID 一并查出。
+//!
+//! ## 分页读取
+//! 日志只追加，`read` 在内存中保留已解析的记录与已解析到的字节偏移，翻页时只解析新追加的完整行；
+//! 文件变短（被清理或外部改动）时整份重新解析。
+//!
+//! ## 保留策略
+//! 按 `BackupConfig::audit_retention_days` 在应用退出时滚动清理超龄记录（0 表示永久保留）。
+
+use std::future::Future;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use crate::services::cache::AppCache;
+use crate::utils::path;
+
+/// 审计日志文件名（位于 `~/.mo/CCR/`）
+const AUDIT_LOG_FILE: &str = "audit.jsonl";
+
+/// 串行化审计日志的追加与清理
+static WRITE_LOCK: LazyLock<tokio::sync::Mutex<()>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+/// `read` 已解析的日志（见模块文档「分页读取」）
+static PARSED: tokio::sync::Mutex<Option<ParsedLog>> = tokio::sync::Mutex::const_new(None);
+
+tokio::task_local! {
+    /// 当前任务所属的批次 ID（由 `with_batch` 设置）
+    static BATCH_ID: String;
+}
+
+/// 已解析的审计日志
+struct ParsedLog {
+    /// 日志文件路径
+    path: PathBuf,
+    /// 已解析的字节数（截至最后一个完整行）
+    offset: u64,
+    /// 已解析的记录（文件顺序）
+    entries: Vec<AuditEntry>,
+}
+
+/// 审计记录的动作类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditAction {
+    /// 写入（含新建与覆盖）
+    Write,
+    /// 删除
+    Delete,
+}
+
+/// 单条审计记录
+///
+/// 对应前端 TypeScript 接口 `AuditEntry`。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// 记录时间（RFC 3339，UTC）
+    pub timestamp: String,
+    /// 操作描述（与临时备份记录的 operation 一致，如 "delete_message"）
+    pub operation: String,
+    /// 动作类型
+    pub action: AuditAction,
+    /// 目标文件的绝对路径
+    pub target_path: String,
+    /// 写入内容的字节数（删除时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_size: Option<u64>,
+    /// 本次操作前创建的临时备份路径（目标文件原本不存在时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup_path: Option<String>,
+    /// 操作是否成功
+    pub success: bool,
+    /// 失败原因
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 所属批次 ID（不在 `with_batch` 中执行时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+}
+
+/// 审计日志查询条件
+///
+/// 对应前端 TypeScript 接口 `AuditFilter`。
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditFilter {
+    /// 目标路径包含的子串（大小写不敏感，`\` 与 `/` 视为相同）
+    #[serde(default)]
+    pub path: Option<String>,
+    /// 只返回指定批次的记录
+    #[serde(default)]
+    pub batch_id: Option<String>,
+    /// 跳过的记录数（按时间倒序）
+    #[serde(default)]
+    pub offset: usize,
+}
+
+/// 审计日志的一页查询结果
+///
+/// 对应前端 TypeScript 接口 `AuditLogPage`。
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogPage {
+    /// 本页记录（最新的在前）
+    pub entries: Vec<AuditEntry>,
+    /// 符合条件的记录总数
+    pub total: usize,
+    /// 是否还有更早的记录
+    pub has_more: bool,
+}
+
+impl AuditEntry {
+    /// 以当前时间与当前批次创建一条记录
+    ///
+    /// # 参数
+    /// - `operation` - 操作描述
+    /// - `action` - 动作类型
+    /// - `target_path` - 目标文件路径
+    /// - `content_size` - 写入内容的字节数（删除时为 None）
+    /// - `backup_path` - 临时备份路径
+    /// - `result` - 主操作的结果
+    pub fn new<T>(
+        operation: &str,
+        action: AuditAction,
+        target_path: &str,
+        content_size: Option<u64>,
+        backup_path: Option<String>,
+        result: &Result<T, String>,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            operation: operation.to_string(),
+            action,
+            target_path: target_path.to_string(),
+            content_size,
+            backup_path,
+            success: result.is_ok(),
+            error: result.as_ref().err().cloned(),
+            batch_id: current_batch(),
+        }
+    }
+}
+
+/// 在同一个批次中执行一组文件操作
+///
+/// 已处于批次中时沿用外层批次 ID，不生成新的 ID。
+///
+/// # 参数
+/// - `f` - 要执行的操作
+///
+/// # 返回值
+/// `f` 的返回值
+pub async fn with_batch<F: Future>(f: F) -> F::Output {
+    if current_batch().is_some() {
+        return f.await;
+    }
+    BATCH_ID.scope(uuid::Uuid::new_v4().to_string(), f).await
+}
+
+/// 当前任务所属的批次 ID
+fn current_batch() -> Option<String> {
+    BATCH_ID.try_with(String::clone).ok()
+}
+
+/// 追加一条审计记录
+///
+/// 写入失败时只写 warn 日志并累加 `AppCache` 的审计失败计数，不向调用方返回错误。
+///
+/// # 参数
+/// - `entry` - 审计记录
+/// - `cache` - AppCache 引用，用于记录失败次数
+pub async fn record(entry: AuditEntry, cache: &AppCache) {
+    if let Err(e) = append(&entry).await {
+        cache.record_audit_failure();
+        log::warn!("写入审计日志失败 ({} {}): {}", entry.operation, entry.target_path, e);
+    }
+}
+
+/// 按条件分页读取审计日志（最新的在前）
+///
+/// 无法解析的行被跳过；翻页时只解析上次读取后新追加的行。
+///
+/// # 参数
+/// - `filter` - 查询条件
+/// - `limit` - 本页最多返回的记录数
+///
+/// # 返回值
+/// 本页记录、符合条件的总数与是否还有更早的记录
+///
+/// # 错误
+/// 日志文件存在但读取失败时返回错误
+pub async fn read(filter: &AuditFilter, limit: usize) -> Result<AuditLogPage, String> {
+    read_from(&audit_path()?, filter, limit).await
+}
+
+/// `read` 的实现，日志文件路径由调用方给出
+async fn read_from(file_path: &Path, filter: &AuditFilter, limit: usize) -> Result<AuditLogPage, String> {
+    let mut parsed = PARSED.lock().await;
+    let len = match tokio::fs::metadata(file_path).await {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            *parsed = None;
+            return Ok(AuditLogPage::default());
+        }
+        Err(e) => return Err(format!("读取审计日志失败: {}", e)),
+    };
+    let log = match parsed.take() {
+        Some(log) if log.path == file_path && log.offset <= len => parsed.insert(log),
+        _ => parsed.insert(ParsedLog { path: file_path.to_path_buf(), offset: 0, entries: Vec::new() }),
+    };
+    if log.offset < len {
+        parse_appended(log).await?;
+    }
+
+    let path_query = filter.path.as_deref().map(normalize_path).filter(|q| !q.is_empty());
+    let matches = |entry: &&AuditEntry| {
+        path_query.as_ref().is_none_or(|q| normalize_path(&entry.target_path).contains(q))
+            && filter.batch_id.as_ref().is_none_or(|id| entry.batch_id.as_ref() == Some(id))
+    };
+
+    let total = log.entries.iter().filter(matches).count();
+    let entries: Vec<AuditEntry> = log.entries.iter().rev().filter(matches).skip(filter.offset).take(limit).cloned().collect();
+    Ok(AuditLogPage {
+        has_more: filter.offset + entries.len() < total,
+        entries,
+        total,
+    })
+}
+
+/// 从已解析的偏移处读取新追加的内容，解析其中的完整行（末尾未写完的半行留到下次）
+async fn parse_appended(log: &mut ParsedLog) -> Result<(), String> {
+    let mut file = tokio::fs::File::open(&log.path)
+        .await
+        .map_err(|e| format!("读取审计日志失败: {}", e))?;
+    file.seek(SeekFrom::Start(log.offset))
+        .await
+        .map_err(|e| format!("读取审计日志失败: {}", e))?;
+    let mut appended = Vec::new();
+    file.read_to_end(&mut appended)
+        .await
+        .map_err(|e| format!("读取审计日志失败: {}", e))?;
+
+    let complete = appended.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    log.entries.extend(parse_entries(&String::from_utf8_lossy(&appended[..complete])));
+    log.offset += complete as u64;
+    Ok(())
+}
+
+/// 删除早于保留天数的审计记录
+///
+/// 先写同目录临时文件再重命名覆盖，无法解析的行一并移除。
+///
+/// # 参数
+/// - `retention_days` - 保留天数；0 表示永久保留，不做任何处理
+///
+/// # 返回值
+/// 移除的行数
+///
+/// # 错误
+/// 日志文件读取或写回失败时返回错误
+pub async fn cleanup_expired(retention_days: u32) -> Result<usize, String> {
+    if retention_days == 0 {
+        return Ok(0);
+    }
+    let _guard = WRITE_LOCK.lock().await;
+    let file_path = audit_path()?;
+    if !file_path.exists() {
+        return Ok(0);
+    }
+    let content = tokio::fs::read_to_string(&file_path)
+        .await
+        .map_err(|e| format!("读取审计日志失败: {}", e))?;
+
+    let cutoff = Utc::now() - chrono::Duration::days(i64::from(retention_days));
+    let (kept, removed) = retain_since(&content, cutoff);
+    if removed == 0 {
+        return Ok(0);
+    }
+
+    let tmp_path = file_path.with_extension("jsonl.tmp");
+    tokio::fs::write(&tmp_path, kept)
+        .await
+        .map_err(|e| format!("写入审计日志失败: {}", e))?;
+    tokio::fs::rename(&tmp_path, &file_path)
+        .await
+        .map_err(|e| format!("替换审计日志失败: {}", e))?;
+    // 内容已整体改写，下次读取时重新解析
+    *PARSED.lock().await = None;
+    Ok(removed)
+}
+
+/// 追加一行记录到审计日志
+async fn append(entry: &AuditEntry) -> Result<(), String> {
+    let mut line = serde_json::to_string(entry).map_err(|e| format!("序列化审计记录失败: {}", e))?;
+    line.push('\n');
+
+    let _guard = WRITE_LOCK.lock().await;
+    let file_path = audit_path()?;
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("创建 CCR 配置目录失败: {}", e))?;
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&file_path)
+        .await
+        .map_err(|e| format!("打开审计日志失败: {}", e))?;
+    file.write_all(line.as_bytes())
+        .await
+        .map_err(|e| format!("写入审计日志失败: {}", e))
+}
+
+/// 逐行解析审计日志，跳过空行与无法解析的行
+fn parse_entries(content: &str) -> impl Iterator<Item = AuditEntry> + '_ {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+}
+
+/// 保留时间不早于 `cutoff` 的记录
+///
+/// # 返回值
+/// (保留的日志内容, 移除的行数)
+fn retain_since(content: &str, cutoff: DateTime<Utc>) -> (String, usize) {
+    let mut kept = String::with_capacity(content.len());
+    let mut removed = 0;
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let fresh = serde_json::from_str::<AuditEntry>(line)
+            .ok()
+            .and_then(|entry| DateTime::parse_from_rfc3339(&entry.timestamp).ok())
+            .is_some_and(|time| time >= cutoff);
+        if fresh {
+            kept.push_str(line);
+            kept.push('\n');
+        } else {
+            removed += 1;
+        }
+    }
+    (kept, removed)
+}
+
+/// 小写化并把 `\` 统一为 `/`
+fn normalize_path(path: &str) -> String {
+    path.to_lowercase().replace('\\', "/")
+}
+
+/// 审计日志路径：`~/.mo/CCR/audit.jsonl`
+fn audit_path() -> Result<PathBuf, String> {
+    Ok(path::get_ccr_config_path()?.join(AUDIT_LOG_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_with_batch_shares_id_and_retain_since() {
+        let ok: Result<(), String> = Ok(());
+        let outside = AuditEntry::new("edit_message", AuditAction::Write, "/a.jsonl", Some(3), None, &ok);
+        assert_eq!(outside.batch_id, None);
+
+        let (first, second) = with_batch(async {
+            let first = AuditEntry::new("fixer_x", AuditAction::Write, "/a.jsonl", Some(1), None, &ok);
+            let second = with_batch(async {
+                AuditEntry::new("fixer_x", AuditAction::Delete, "/b.jsonl", None, None, &Err::<(), _>("busy".to_string()))
+            })
+            .await;
+            (first, second)
+        })
+        .await;
+        assert!(first.batch_id.is_some());
+        assert_eq!(first.batch_id, second.batch_id);
+        assert_eq!((second.success, second.error.as_deref()), (false, Some("busy")));
+
+        let mut old = outside.clone();
+        old.timestamp = "2020-01-01T00:00:00.000Z".to_string();
+        let content = [&old, &first]
+            .iter()
+            .map(|entry| serde_json::to_string(entry).unwrap())
+            .chain(["not json".to_string()])
+            .collect::<Vec<_>>()
+            .join("\n");
+        let (kept, removed) = retain_since(&content, Utc::now() - chrono::Duration::days(1));
+        assert_eq!(removed, 2);
+        assert_eq!(parse_entries(&kept).map(|e| e.operation).collect::<Vec<_>>(), ["fixer_x"]);
+    }
+
+    #[tokio::test]
+    async fn test_read_parses_appended_lines_only() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        let file_path = dir.join(AUDIT_LOG_FILE);
+        let ok: Result<(), String> = Ok(());
+        let line = |operation: &str| {
+            let entry = AuditEntry::new(operation, AuditAction::Write, "/p/a.jsonl", Some(1), None, &ok);
+            serde_json::to_string(&entry).unwrap() + "\n"
+        };
+        let operations = |page: &AuditLogPage| page.entries.iter().map(|e| e.operation.clone()).collect::<Vec<_>>();
+
+        std::fs::write(&file_path, [line("op1"), line("op2"), line("op3")].concat()).unwrap();
+        let page = read_from(&file_path, &AuditFilter::default(), 2).await.unwrap();
+        assert_eq!((operations(&page), page.total, page.has_more), (vec!["op3".to_string(), "op2".to_string()], 3, true));
+
+        // 追加一行与半行：半行不计入，补全后才解析
+        let op5 = line("op5");
+        let (head, tail) = op5.split_at(10);
+        let mut file = std::fs::OpenOptions::new().append(true).open(&file_path).unwrap();
+        std::io::Write::write_all(&mut file, (line("op4") + head).as_bytes()).unwrap();
+        let filter = AuditFilter { offset: 2, ..Default::default() };
+        let page = read_from(&file_path, &filter, 10).await.unwrap();
+        assert_eq!((operations(&page), page.total), (vec!["op2".to_string(), "op1".to_string()], 4));
+        std::io::Write::write_all(&mut file, tail.as_bytes()).unwrap();
+        assert_eq!(read_from(&file_path, &AuditFilter::default(), 10).await.unwrap().total, 5);
+
+        // 文件变短（如被清理）时整份重新解析
+        std::fs::write(&file_path, line("only")).unwrap();
+        let page = read_from(&file_path, &AuditFilter::default(), 10).await.unwrap();
+        assert_eq!(operations(&page), ["only"]);
+    }
+}
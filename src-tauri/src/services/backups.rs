@@ -0,0 +1,269 @@
+//! # 备份浏览服务
+//!
+//! 汇总所有会话的备份，供前端备份管理页统一展示、搜索和恢复：
+//! - **临时备份**：`%TEMP%/ccr-backups/<原始完整文件名>_<timestamp>.bak`。
+//!   本次运行期间创建的备份可从注册表取得原始路径与操作名；
+//!   此前运行留下的备份只能从文件名推断出原始文件名
+//! - **主动备份**：Claude 数据目录下的 `<原始文件路径>.ccbak<timestamp>`，
+//!   覆盖根目录（`settings.json` 等）与 `projects/` 下各项目目录（含 `subagents/` 等子目录）
+//!
+//! 扫描只读取目录项与元数据，不读取备份内容；各项目目录并行扫描。
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::Serialize;
+use tokio::task::JoinSet;
+
+use crate::services::file_guard::{self, TempBackupEntry};
+use crate::services::scanner::system_time_to_iso8601;
+use crate::services::session_lock;
+
+/// 项目目录内查找 `.ccbak` 的最大递归深度（`<project>/<session>/subagents/` 为 2 层）
+const MAX_PROJECT_SCAN_DEPTH: usize = 2;
+
+/// 备份来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackupSource {
+    /// 系统 TEMP 目录下的临时备份（每次修改前强制创建）
+    Temp,
+    /// 原文件同目录的 `.ccbak` 主动备份（设置中启用后创建）
+    Auto,
+}
+
+/// 备份列表中的单条记录
+///
+/// 由 `list_all_backups` command 返回，对应前端 TypeScript 接口 `BackupItem`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupItem {
+    /// 备份文件的绝对路径
+    pub backup_path: String,
+    /// 原始文件的绝对路径（此前运行留下的临时备份无法确定，为 None）
+    pub original_path: Option<String>,
+    /// 原始文件名（含扩展名）
+    pub original_file_name: String,
+    /// 备份文件大小（字节）
+    pub size: u64,
+    /// 备份创建时间（ISO 8601 格式），取自文件名中的时间戳，解析失败时取文件修改时间
+    pub created_at: String,
+    /// 备份来源
+    pub source: BackupSource,
+    /// 触发备份的操作（仅本次运行期间登记的临时备份有此信息）
+    pub operation: Option<String>,
+}
+
+/// 汇总所有备份
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录
+/// - `registry` - 本次运行期间登记的临时备份（`AppCache::get_all_temp_backups`）
+/// - `session_file_path` - 只返回该文件的备份（None 表示全部）
+/// - `oldest_first` - 是否按时间升序排列（默认最新的在前）
+///
+/// # 返回值
+/// 按创建时间排序的备份列表；目录不存在或不可读时对应来源为空
+pub async fn list_all_backups(
+    claude_path: PathBuf,
+    registry: Vec<TempBackupEntry>,
+    session_file_path: Option<String>,
+    oldest_first: bool,
+) -> Vec<BackupItem> {
+    let mut join_set = JoinSet::new();
+
+    join_set.spawn_blocking(move || scan_temp_backups(&file_guard::temp_backup_dir(), &registry));
+    // 根目录只看一层（settings.json 等），projects 下每个项目目录一个任务
+    let root = claude_path.clone();
+    join_set.spawn_blocking(move || scan_auto_backups(&root, 0));
+    if let Ok(entries) = std::fs::read_dir(claude_path.join("projects")) {
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                let dir = entry.path();
+                join_set.spawn_blocking(move || scan_auto_backups(&dir, MAX_PROJECT_SCAN_DEPTH));
+            }
+        }
+    }
+
+    let mut items = Vec::new();
+    while let Some(result) = join_set.join_next().await {
+        match result {
+            Ok(found) => items.extend(found),
+            Err(e) => log::warn!("扫描备份任务失败: {}", e),
+        }
+    }
+
+    if let Some(session_file_path) = session_file_path {
+        items.retain(|item| matches_session(item, &session_file_path));
+    }
+
+    // ISO 8601 字符串格式固定，可直接按字典序比较
+    items.sort_by(|a, b| {
+        a.created_at
+            .cmp(&b.created_at)
+            .then_with(|| a.backup_path.cmp(&b.backup_path))
+    });
+    if !oldest_first {
+        items.reverse();
+    }
+    items
+}
+
+/// 判断备份是否属于指定会话
+///
+/// 原始路径已知时比较规范化路径；未知时（此前运行的临时备份）比较文件名，
+/// 会话文件名包含完整 UUID，不同会话不会重名。
+fn matches_session(item: &BackupItem, session_file_path: &str) -> bool {
+    match &item.original_path {
+        Some(original) => session_lock::normalize(original) == session_lock::normalize(session_file_path),
+        None => Path::new(session_file_path)
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy() == item.original_file_name),
+    }
+}
+
+/// 扫描临时备份目录
+fn scan_temp_backups(dir: &Path, registry: &[TempBackupEntry]) -> Vec<BackupItem> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut items = Vec::new();
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else { continue };
+        if !metadata.is_file() {
+            continue;
+        }
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some((original_file_name, timestamp)) = parse_temp_backup_name(&file_name) else {
+            continue;
+        };
+        let backup_path = entry.path().to_string_lossy().to_string();
+        let registered = registry
+            .iter()
+            .find(|r| Path::new(&r.temp_path) == entry.path());
+
+        items.push(BackupItem {
+            backup_path,
+            original_path: registered.map(|r| r.original_path.clone()),
+            original_file_name: original_file_name.to_string(),
+            size: metadata.len(),
+            created_at: backup_time(Some(timestamp), &metadata),
+            source: BackupSource::Temp,
+            operation: registered.map(|r| r.operation.clone()),
+        });
+    }
+    items
+}
+
+/// 扫描目录下的 `.ccbak` 主动备份
+///
+/// # 参数
+/// - `dir` - 起始目录
+/// - `max_depth` - 继续向下递归的层数（0 表示只看当前目录）
+fn scan_auto_backups(dir: &Path, max_depth: usize) -> Vec<BackupItem> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut items = Vec::new();
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else { continue };
+        if file_type.is_dir() {
+            if max_depth > 0 {
+                items.extend(scan_auto_backups(&entry.path(), max_depth - 1));
+            }
+            continue;
+        }
+        if !file_type.is_file() {
+            continue;
+        }
+
+        let backup_path = entry.path().to_string_lossy().to_string();
+        let Some(original_path) = file_guard::auto_backup_original_path(&backup_path) else {
+            continue;
+        };
+        let Ok(metadata) = entry.metadata() else { continue };
+        let timestamp = backup_path[original_path.len() + ".ccbak".len()..].parse().ok();
+        let original_file_name = Path::new(original_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        items.push(BackupItem {
+            original_path: Some(original_path.to_string()),
+            backup_path,
+            original_file_name,
+            size: metadata.len(),
+            created_at: backup_time(timestamp, &metadata),
+            source: BackupSource::Auto,
+            operation: None,
+        });
+    }
+    items
+}
+
+/// 解析临时备份文件名 `<原始完整文件名>_<timestamp>.bak`
+///
+/// # 返回值
+/// `(原始文件名, Unix 时间戳)`，不符合命名规则时返回 None
+fn parse_temp_backup_name(file_name: &str) -> Option<(&str, u64)> {
+    let stem = file_name.strip_suffix(".bak")?;
+    let (original, timestamp) = stem.rsplit_once('_')?;
+    if original.is_empty() || timestamp.is_empty() || !timestamp.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((original, timestamp.parse().ok()?))
+}
+
+/// 备份创建时间：优先使用文件名中的时间戳，否则使用文件修改时间
+fn backup_time(timestamp: Option<u64>, metadata: &std::fs::Metadata) -> String {
+    match timestamp {
+        Some(secs) => system_time_to_iso8601(UNIX_EPOCH + Duration::from_secs(secs)),
+        None => system_time_to_iso8601(metadata.modified().unwrap_or(UNIX_EPOCH)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_temp_backup_name() {
+        assert_eq!(
+            parse_temp_backup_name("a9fb-01.jsonl_1740000000.bak"),
+            Some(("a9fb-01.jsonl", 1740000000))
+        );
+        assert_eq!(
+            parse_temp_backup_name("my_settings.json_1740000000.bak"),
+            Some(("my_settings.json", 1740000000))
+        );
+        assert_eq!(parse_temp_backup_name("a.jsonl_.bak"), None);
+        assert_eq!(parse_temp_backup_name("a.jsonl_17x.bak"), None);
+        assert_eq!(parse_temp_backup_name("a.jsonl_1740000000"), None);
+        assert_eq!(parse_temp_backup_name("_1740000000.bak"), None);
+    }
+
+    #[test]
+    fn test_scan_auto_backups_and_filter() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        let nested = dir.join("s1").join("subagents");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(dir.join("a.jsonl"), "{}").unwrap();
+        std::fs::write(dir.join("a.jsonl.ccbak1740000000"), "{}").unwrap();
+        std::fs::write(dir.join("a.jsonl.ccbakx"), "{}").unwrap();
+        std::fs::write(nested.join("agent.jsonl.ccbak1740000001"), "{}").unwrap();
+
+        let shallow = scan_auto_backups(dir, 0);
+        assert_eq!(shallow.len(), 1);
+        assert_eq!(shallow[0].original_file_name, "a.jsonl");
+        assert_eq!(shallow[0].created_at, "2025-02-19T21:20:00.000Z");
+        assert_eq!(shallow[0].source, BackupSource::Auto);
+
+        let all = scan_auto_backups(dir, MAX_PROJECT_SCAN_DEPTH);
+        assert_eq!(all.len(), 2);
+        let session = dir.join("a.jsonl").to_string_lossy().to_string();
+        assert_eq!(all.iter().filter(|i| matches_session(i, &session)).count(), 1);
+    }
+}
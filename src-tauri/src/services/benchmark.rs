@@ -0,0 +1,315 @@
+//! # 端到端基准测试
+//!
+//! 为「我这儿很慢」类反馈提供标准化数据，在用户的真实数据目录上跑固定场景：
+//! - `scan`：不带扫描快照地重复完整扫描 projects 目录（`SCAN_ITERATIONS` 次）
+//! - `read_largest`：找到最大的会话文件，重复执行「读取 → 解码解析 → transform」并分阶段计时
+//! - `search`：最大会话转换后放入独立的缓存实例，字面量与正则搜索各跑 `SEARCH_ITERATIONS` 次
+//!
+//! 每项计时报告样本数、P50 / P95、最小与最大值（毫秒）。报告可另存到
+//! `~/.mo/CCR/benchmarks/<场景>-<时间>.json`，附在 issue 中。
+//!
+//! ## 开启方式
+//! 基准测试会完整读取用户数据、占用大量 CPU，不对普通用户开放：debug 构建始终可用，
+//! release 构建需在 `~/.mo/CCR/app-config.json` 中设置 `"enableBenchmark": true`。
+//! 报告中的会话只保留文件名，不包含项目路径。
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::services::archive::Archived;
+use crate::services::cache::{AppCache, SessionSearchFilter};
+use crate::services::scanner::{self, ScanOptions};
+use crate::services::{command_trace, parser, transformer};
+use crate::utils::{app_config, encoding, path};
+
+/// 报告保存目录（位于 `~/.mo/CCR/`）
+const BENCHMARKS_DIR: &str = "benchmarks";
+
+/// `scan` 场景的重复次数
+const SCAN_ITERATIONS: usize = 10;
+
+/// `read_largest` 场景的重复次数
+const READ_ITERATIONS: usize = 5;
+
+/// `search` 场景每种搜索的重复次数
+const SEARCH_ITERATIONS: usize = 100;
+
+/// `search` 场景的字面量搜索词
+const LITERAL_QUERY: &str = "error";
+
+/// `search` 场景的正则搜索词
+const REGEX_QUERY: &str = r"err(or)?s?\b|fail(ed|ure)?";
+
+/// 基准测试场景
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BenchmarkScenario {
+    /// 重复完整扫描 projects 目录
+    Scan,
+    /// 最大会话的读取与转换分阶段计时
+    ReadLargest,
+    /// 最大会话上的字面量与正则搜索
+    Search,
+}
+
+/// 单项计时的统计
+///
+/// 对应前端 TypeScript 接口 `BenchmarkTiming`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkTiming {
+    /// 计时项名称（如 "scan"、"read"、"transform"）
+    pub name: String,
+    /// 样本数
+    pub samples: usize,
+    /// 中位数（毫秒）
+    pub p50_ms: f64,
+    /// 95 分位（毫秒）
+    pub p95_ms: f64,
+    /// 最小值（毫秒）
+    pub min_ms: f64,
+    /// 最大值（毫秒）
+    pub max_ms: f64,
+}
+
+/// 基准测试报告
+///
+/// 对应前端 TypeScript 接口 `BenchmarkReport`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkReport {
+    /// 场景
+    pub scenario: BenchmarkScenario,
+    /// 开始时间（RFC 3339，UTC）
+    pub started_at: String,
+    /// CCR 版本
+    pub app_version: &'static str,
+    /// 操作系统（`std::env::consts::OS`）
+    pub os: &'static str,
+    /// 逻辑 CPU 数
+    pub cpus: usize,
+    /// 各项计时
+    pub timings: Vec<BenchmarkTiming>,
+    /// 场景相关的数据规模（项目数、文件大小、消息数、命中数等）
+    pub details: BTreeMap<String, String>,
+    /// 报告另存的路径（未保存时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saved_path: Option<String>,
+}
+
+/// 应用配置中与基准测试相关的部分
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppConfig {
+    /// release 构建中是否开放基准测试
+    #[serde(default)]
+    enable_benchmark: bool,
+}
+
+/// 基准测试是否可用（debug 构建，或 app-config.json 中已开启）
+pub fn is_enabled() -> bool {
+    cfg!(debug_assertions) || app_config::load::<AppConfig>().enable_benchmark
+}
+
+/// 在数据目录上运行指定场景
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
+/// - `scenario` - 场景
+/// - `save` - 是否把报告另存到 `~/.mo/CCR/benchmarks/`
+///
+/// # 返回值
+/// 结构化的基准测试报告
+///
+/// # 错误
+/// projects 目录不可读、没有任何会话（`read_largest` / `search`）、
+/// 会话读取失败或报告保存失败时返回错误
+pub async fn run(claude_path: &str, scenario: BenchmarkScenario, save: bool) -> Result<BenchmarkReport, String> {
+    let started_at = Utc::now();
+    let mut details = BTreeMap::new();
+    let timings = match scenario {
+        BenchmarkScenario::Scan => run_scan(claude_path, &mut details).await?,
+        BenchmarkScenario::ReadLargest => run_read_largest(claude_path, &mut details).await?,
+        BenchmarkScenario::Search => run_search(claude_path, &mut details).await?,
+    };
+
+    let mut report = BenchmarkReport {
+        scenario,
+        started_at: started_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        app_version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        cpus: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        timings,
+        details,
+        saved_path: None,
+    };
+    if save {
+        let file_name = format!("{}-{}.json", scenario_name(scenario), started_at.format("%Y%m%d-%H%M%S"));
+        report.saved_path = Some(save_report(&report, &file_name).await?);
+    }
+    Ok(report)
+}
+
+/// `scan` 场景：不复用快照，重复完整扫描
+async fn run_scan(claude_path: &str, details: &mut BTreeMap<String, String>) -> Result<Vec<BenchmarkTiming>, String> {
+    let mut samples = Vec::with_capacity(SCAN_ITERATIONS);
+    for _ in 0..SCAN_ITERATIONS {
+        let start = Instant::now();
+        let dirs = scanner::list_project_dirs(claude_path).await?;
+        let projects = scanner::scan_project_dirs(dirs, &HashMap::new(), ScanOptions::default(), &Archived::default()).await.projects;
+        samples.push(start.elapsed());
+
+        details.insert("projects".to_string(), projects.len().to_string());
+        details.insert(
+            "sessions".to_string(),
+            projects.iter().map(|p| p.sessions.len()).sum::<usize>().to_string(),
+        );
+    }
+    Ok(vec![timing("scan", &samples)])
+}
+
+/// `read_largest` 场景：读取、解码解析、transform 分阶段计时
+async fn run_read_largest(claude_path: &str, details: &mut BTreeMap<String, String>) -> Result<Vec<BenchmarkTiming>, String> {
+    let (file, size) = largest_session(claude_path).await?;
+    describe_file(details, &file, size);
+
+    let (mut read, mut parse, mut transform) = (Vec::new(), Vec::new(), Vec::new());
+    for _ in 0..READ_ITERATIONS {
+        let start = Instant::now();
+        let bytes = tokio::fs::read(&file)
+            .await
+            .map_err(|e| format!("读取会话文件失败: {}", e))?;
+        read.push(start.elapsed());
+
+        let start = Instant::now();
+        let messages = parser::parse_jsonl(&encoding::decode_bytes(bytes)?.text);
+        parse.push(start.elapsed());
+
+        let start = Instant::now();
+        let (transformed, _, _) = transformer::transform_session(&messages);
+        transform.push(start.elapsed());
+
+        details.insert("messages".to_string(), messages.len().to_string());
+        details.insert("displayMessages".to_string(), transformed.display_messages.len().to_string());
+    }
+    Ok(vec![timing("read", &read), timing("parse", &parse), timing("transform", &transform)])
+}
+
+/// `search` 场景：最大会话上的字面量与正则搜索
+async fn run_search(claude_path: &str, details: &mut BTreeMap<String, String>) -> Result<Vec<BenchmarkTiming>, String> {
+    let (file, size) = largest_session(claude_path).await?;
+    describe_file(details, &file, size);
+
+    let file = file.to_string_lossy().to_string();
+    let messages = parser::read_messages(&file).await?;
+    let (transformed, search_texts, original_texts) = transformer::transform_session(&messages);
+    // 独立的缓存实例，不影响应用缓存的 LRU 与内存统计
+    let cache = AppCache::new();
+    cache.set_session(&file, transformed, search_texts, original_texts);
+
+    let mut timings = Vec::new();
+    for (name, query, use_regex) in [("searchLiteral", LITERAL_QUERY, false), ("searchRegex", REGEX_QUERY, true)] {
+        let mut samples = Vec::with_capacity(SEARCH_ITERATIONS);
+        let mut hits = 0;
+        for _ in 0..SEARCH_ITERATIONS {
+            let start = Instant::now();
+            hits = cache.search_in_cache(&file, query, false, use_regex, &SessionSearchFilter::default())?.map_or(0, |hits| hits.len());
+            samples.push(start.elapsed());
+        }
+        details.insert(format!("{}Hits", name), hits.to_string());
+        timings.push(timing(name, &samples));
+    }
+    Ok(timings)
+}
+
+/// 找到数据目录中最大的会话文件（不含子 agent 会话）
+async fn largest_session(claude_path: &str) -> Result<(PathBuf, u64), String> {
+    let mut largest: Option<(PathBuf, u64)> = None;
+    for (_, dir) in scanner::list_project_dirs(claude_path).await? {
+        let Ok(files) = scanner::list_session_files(&dir, false).await else {
+            continue;
+        };
+        for file in files {
+            let Ok(size) = tokio::fs::metadata(&file).await.map(|m| m.len()) else {
+                continue;
+            };
+            if largest.as_ref().is_none_or(|(_, max)| size > *max) {
+                largest = Some((file, size));
+            }
+        }
+    }
+    largest.ok_or_else(|| "数据目录中没有会话文件".to_string())
+}
+
+/// 记录被测会话的文件名（脱敏）与大小
+fn describe_file(details: &mut BTreeMap<String, String>, file: &std::path::Path, size: u64) {
+    details.insert("file".to_string(), command_trace::path_arg(&file.to_string_lossy()));
+    details.insert("fileBytes".to_string(), size.to_string());
+}
+
+/// 由样本计算计时统计
+fn timing(name: &str, samples: &[Duration]) -> BenchmarkTiming {
+    let mut ms: Vec<f64> = samples.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+    ms.sort_by(f64::total_cmp);
+    BenchmarkTiming {
+        name: name.to_string(),
+        samples: ms.len(),
+        p50_ms: percentile(&ms, 50.0),
+        p95_ms: percentile(&ms, 95.0),
+        min_ms: ms.first().copied().unwrap_or(0.0),
+        max_ms: ms.last().copied().unwrap_or(0.0),
+    }
+}
+
+/// 已排序样本的分位数（最近秩法，空样本为 0）
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// 场景在文件名中使用的名称
+fn scenario_name(scenario: BenchmarkScenario) -> &'static str {
+    match scenario {
+        BenchmarkScenario::Scan => "scan",
+        BenchmarkScenario::ReadLargest => "read_largest",
+        BenchmarkScenario::Search => "search",
+    }
+}
+
+/// 把报告写入 `~/.mo/CCR/benchmarks/`
+async fn save_report(report: &BenchmarkReport, file_name: &str) -> Result<String, String> {
+    let dir = path::get_ccr_config_path()?.join(BENCHMARKS_DIR);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("创建基准测试目录失败: {}", e))?;
+    let file_path = dir.join(file_name);
+    let content = serde_json::to_string_pretty(report).map_err(|e| format!("序列化基准测试报告失败: {}", e))?;
+    tokio::fs::write(&file_path, content)
+        .await
+        .map_err(|e| format!("写入基准测试报告失败: {}", e))?;
+    Ok(file_path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing_percentiles() {
+        let samples: Vec<Duration> = (1..=20).rev().map(Duration::from_millis).collect();
+        let t = timing("scan", &samples);
+        assert_eq!(t.samples, 20);
+        assert_eq!((t.p50_ms, t.p95_ms, t.min_ms, t.max_ms), (10.0, 19.0, 1.0, 20.0));
+
+        assert_eq!(percentile(&[], 95.0), 0.0);
+        assert_eq!(percentile(&[3.0], 50.0), 3.0);
+        assert_eq!(serde_json::to_value(BenchmarkScenario::ReadLargest).unwrap(), "read_largest");
+    }
+}
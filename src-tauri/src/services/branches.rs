@@ -0,0 +1,290 @@
+//! # 会话分叉服务
+//!
+//! 在 Claude Code 中按 Esc 回退后重新提问，会让同一条消息拥有多个子消息，对话由此分叉。
+//! 本模块基于 `parentUuid` 构建消息树，枚举所有分叉点与叶子路径：
+//! - **分叉点**：子消息数大于 1 的节点
+//! - **分支**：从根到每个叶子的消息 uuid 序列
+//! - **主线**：最新时间戳的叶子所在路径，即 Claude Code resume 时实际接续的分支
+//!
+//! ## 建树规则
+//! - 只统计带 `uuid` 的对话消息；侧链（`isSidechain`）消息属于子 agent，不参与
+//! - 压缩边界（`parentUuid` 为 null）通过 `logicalParentUuid` 接回压缩前的消息，避免被误判为新的根
+//! - 父消息不在文件中的消息视为根
+//! - `summary` 条目的 `leafUuid` 用作对应分支的标题
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::models::message::SessionMessage;
+
+/// 分叉点：拥有多个子消息的节点
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkPoint {
+    /// 分叉节点的 uuid
+    pub uuid: String,
+    /// 子消息 uuid（按文件顺序）
+    pub child_uuids: Vec<String>,
+}
+
+/// 一条从根到叶子的分支
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBranch {
+    /// 叶子消息 uuid，作为分支标识（传给 `read_session_messages` 的 `branch_leaf`）
+    pub leaf_uuid: String,
+    /// 从根到叶子的消息 uuid 序列
+    pub uuids: Vec<String>,
+    /// 路径上的消息条数
+    pub length: usize,
+    /// 叶子消息的时间戳（ISO 8601）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_timestamp: Option<String>,
+    /// 分支标题（来自 `leafUuid` 指向路径上消息的 summary 条目）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// 是否为当前 Claude Code 实际采用的主线
+    pub is_main: bool,
+}
+
+/// 会话的分叉结构
+///
+/// 由 `get_session_branches` command 返回，对应前端 TypeScript 接口 `SessionBranches`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBranches {
+    /// 所有分叉点（按文件顺序）
+    pub fork_points: Vec<ForkPoint>,
+    /// 所有分支（主线在前，其余按叶子时间倒序）
+    pub branches: Vec<SessionBranch>,
+}
+
+/// 消息树中的节点
+struct Node<'a> {
+    /// 父节点 uuid（根节点为 None）
+    parent: Option<&'a str>,
+    /// 时间戳
+    timestamp: Option<&'a str>,
+    /// 子节点 uuid（按文件顺序）
+    children: Vec<&'a str>,
+    /// 在文件中的位置，时间戳相同时用于排序
+    position: usize,
+}
+
+/// 基于 parentUuid 构建的消息树
+struct MessageTree<'a> {
+    nodes: HashMap<&'a str, Node<'a>>,
+    /// 按文件顺序排列的节点 uuid
+    order: Vec<&'a str>,
+}
+
+impl<'a> MessageTree<'a> {
+    fn build(messages: &'a [SessionMessage]) -> Self {
+        let mut nodes: HashMap<&str, Node> = HashMap::new();
+        let mut order = Vec::new();
+        for msg in messages {
+            if msg.get("isSidechain").and_then(|v| v.as_bool()).unwrap_or(false) {
+                continue;
+            }
+            let Some(uuid) = msg.get("uuid").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let parent = msg
+                .get("parentUuid")
+                .and_then(|v| v.as_str())
+                .or_else(|| msg.get("logicalParentUuid").and_then(|v| v.as_str()));
+            let position = order.len();
+            // 重复 uuid 以首次出现为准
+            if nodes.contains_key(uuid) {
+                continue;
+            }
+            nodes.insert(uuid, Node {
+                parent,
+                timestamp: msg.get("timestamp").and_then(|v| v.as_str()),
+                children: Vec::new(),
+                position,
+            });
+            order.push(uuid);
+        }
+
+        // 父节点不存在的视为根
+        for &uuid in &order {
+            let parent = nodes[uuid].parent.filter(|p| *p != uuid && nodes.contains_key(p));
+            if let Some(node) = nodes.get_mut(uuid) {
+                node.parent = parent;
+            }
+            if let Some(parent) = parent.and_then(|p| nodes.get_mut(p)) {
+                parent.children.push(uuid);
+            }
+        }
+        Self { nodes, order }
+    }
+
+    /// 从叶子回溯到根，返回根 → 叶子的 uuid 序列
+    fn path_to_root(&self, leaf: &'a str) -> Vec<&'a str> {
+        let mut path = Vec::new();
+        let mut seen = HashSet::new();
+        let mut current = Some(leaf);
+        while let Some(uuid) = current {
+            if !seen.insert(uuid) {
+                break; // 防御循环引用
+            }
+            path.push(uuid);
+            current = self.nodes.get(uuid).and_then(|n| n.parent);
+        }
+        path.reverse();
+        path
+    }
+
+    /// 所有叶子节点（按文件顺序）
+    fn leaves(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.order.iter().copied().filter(|u| self.nodes[u].children.is_empty())
+    }
+
+    /// 主线叶子：时间戳最新的叶子，时间戳相同或缺失时取文件中靠后的
+    fn main_leaf(&self) -> Option<&'a str> {
+        self.leaves()
+            .max_by_key(|u| (self.nodes[u].timestamp.unwrap_or(""), self.nodes[u].position))
+    }
+}
+
+/// 枚举会话的分叉点与所有分支
+///
+/// # 参数
+/// - `messages` - 原始会话消息
+///
+/// # 返回值
+/// 分叉点与分支列表；没有分叉的会话只有一条分支（即主线）
+pub fn build_branches(messages: &[SessionMessage]) -> SessionBranches {
+    let tree = MessageTree::build(messages);
+
+    let fork_points = tree
+        .order
+        .iter()
+        .filter(|u| tree.nodes[*u].children.len() > 1)
+        .map(|u| ForkPoint {
+            uuid: u.to_string(),
+            child_uuids: tree.nodes[u].children.iter().map(|c| c.to_string()).collect(),
+        })
+        .collect();
+
+    // leafUuid → summary 标题（后出现的覆盖先出现的）
+    let summaries: HashMap<&str, &str> = messages
+        .iter()
+        .filter(|m| m.get("type").and_then(|v| v.as_str()) == Some("summary"))
+        .filter_map(|m| {
+            Some((
+                m.get("leafUuid")?.as_str()?,
+                m.get("summary")?.as_str()?,
+            ))
+        })
+        .collect();
+
+    let main_leaf = tree.main_leaf();
+    let mut branches: Vec<SessionBranch> = tree
+        .leaves()
+        .map(|leaf| {
+            let path = tree.path_to_root(leaf);
+            // 取路径上最深的 summary 作为标题
+            let title = path
+                .iter()
+                .rev()
+                .find_map(|u| summaries.get(u))
+                .map(|s| s.to_string());
+            SessionBranch {
+                leaf_uuid: leaf.to_string(),
+                length: path.len(),
+                uuids: path.into_iter().map(String::from).collect(),
+                last_timestamp: tree.nodes[leaf].timestamp.map(String::from),
+                title,
+                is_main: Some(leaf) == main_leaf,
+            }
+        })
+        .collect();
+    branches.sort_by(|a, b| {
+        b.is_main
+            .cmp(&a.is_main)
+            .then_with(|| b.last_timestamp.cmp(&a.last_timestamp))
+    });
+
+    SessionBranches { fork_points, branches }
+}
+
+/// 计算某条分支路径上的消息 uuid 集合
+///
+/// # 参数
+/// - `messages` - 原始会话消息
+/// - `leaf_uuid` - 分支的叶子消息 uuid
+///
+/// # 返回值
+/// 从根到该叶子路径上的 uuid 集合；叶子不存在时返回 None
+pub fn branch_path(messages: &[SessionMessage], leaf_uuid: &str) -> Option<HashSet<String>> {
+    let tree = MessageTree::build(messages);
+    if !tree.nodes.contains_key(leaf_uuid) {
+        return None;
+    }
+    Some(
+        tree.path_to_root(leaf_uuid)
+            .into_iter()
+            .map(String::from)
+            .collect(),
+    )
+}
+
+/// 主线叶子消息的 uuid（即 Claude Code resume 时实际接续的消息）
+///
+/// # 参数
+/// - `messages` - 原始会话消息
+///
+/// # 返回值
+/// 主线叶子的 uuid；会话中没有任何带 uuid 的对话消息时返回 None
+pub fn main_leaf_uuid(messages: &[SessionMessage]) -> Option<String> {
+    MessageTree::build(messages).main_leaf().map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn msg(uuid: &str, parent: Option<&str>, ts: &str) -> SessionMessage {
+        json!({ "type": "user", "uuid": uuid, "parentUuid": parent, "timestamp": ts })
+    }
+
+    #[test]
+    fn test_build_branches() {
+        // a → b → c（旧提问）
+        //       ↘ d → e（Esc 回退后重新提问，最新）
+        // 压缩边界 f 通过 logicalParentUuid 接在 e 之后；侧链 s 不参与
+        let messages = vec![
+            msg("a", None, "2026-01-01T00:00:01Z"),
+            msg("b", Some("a"), "2026-01-01T00:00:02Z"),
+            msg("c", Some("b"), "2026-01-01T00:00:03Z"),
+            msg("d", Some("b"), "2026-01-01T00:00:04Z"),
+            msg("e", Some("d"), "2026-01-01T00:00:05Z"),
+            json!({ "type": "system", "uuid": "f", "parentUuid": null, "logicalParentUuid": "e", "timestamp": "2026-01-01T00:00:06Z" }),
+            json!({ "type": "user", "uuid": "s", "parentUuid": null, "isSidechain": true, "timestamp": "2026-01-01T00:00:07Z" }),
+            json!({ "type": "summary", "summary": "旧提问", "leafUuid": "c" }),
+        ];
+        let result = build_branches(&messages);
+
+        assert_eq!(result.fork_points.len(), 1);
+        assert_eq!(result.fork_points[0].uuid, "b");
+        assert_eq!(result.fork_points[0].child_uuids, vec!["c", "d"]);
+
+        assert_eq!(result.branches.len(), 2);
+        let main = &result.branches[0];
+        assert!(main.is_main);
+        assert_eq!(main.uuids, vec!["a", "b", "d", "e", "f"]);
+        assert_eq!(main.length, 5);
+        let old = &result.branches[1];
+        assert_eq!(old.leaf_uuid, "c");
+        assert_eq!(old.title.as_deref(), Some("旧提问"));
+
+        let path = branch_path(&messages, "c").unwrap();
+        assert!(path.contains("a") && path.contains("c") && !path.contains("d"));
+        assert!(branch_path(&messages, "missing").is_none());
+        assert_eq!(main_leaf_uuid(&messages).as_deref(), Some("f"));
+    }
+}
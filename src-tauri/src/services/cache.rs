@@ -1,17 +1,61 @@
 //! # 内存缓存管理服务
 //!
 //! 提供基于内存的缓存层，减少重复的文件系统 I/O 操作：
-//! - **项目列表缓存**：存储上次扫描结果，带时间戳用于判断有效性
+//! - **项目列表缓存**：存储上次扫描结果，带时间戳用于判断有效性；
+//!   分页扫描时记录已加载的前缀（部分结果），后续页或强制刷新按范围合并
 //! - **会话缓存**：LRU 缓存最近查看的会话转换结果和搜索文本
+//! - **单项目会话列表缓存**：`scan_project_sessions` 按需加载的某个项目的全部会话
+//! - **项目摘要缓存**：项目目录下 CLAUDE.md / README.md 的开头部分
+//! - **项目统计缓存**：`get_project_stats` 汇总的会话数、行数、字节数与 token 用量
+//! - **撤销栈**：删除 / 编辑消息前的完整消息快照，每个会话最多 `MAX_UNDO_STEPS` 步
 //!
 //! ## 缓存失效策略
 //! - 项目列表缓存：基于 TTL（生存时间），超过阈值后重新扫描
+//! - 单项目会话列表缓存：与项目列表缓存同 TTL，`invalidate_projects` 时一并清空
+//! - 新建 / 删除 / 改写单个会话后不整体失效：`update_project_session` 按项目目录定位缓存中的项目，
+//!   就地加入、移除或更新该会话并重算会话数与最新时间，侧边栏无需全量重扫即可反映变化；
+//!   整个项目目录被移除时（碎片归并）由 `remove_project` 从缓存中摘除
+//! - 项目统计缓存：独立的 5 分钟 TTL，不随项目列表失效（统计允许滞后，重算代价高）
 //! - 会话缓存：基于文件 mtime（最后修改时间），文件变化时重新解析
+//! - 项目摘要缓存：有摘要时基于来源文件 mtime；没有摘要文件时基于 TTL，以便发现新建的文件
+//!
+//! ## 会话缓存内存上限
+//! 单个超长会话的转换结果加上两份搜索文本可达数百 MB，仅按条数淘汰时 20 个大会话就可能
+//! 把内存撑爆。每个条目写入时估算其占用（`estimate_entry_bytes`：消息内容、工具结果、
+//! 三份搜索文本与 tool_use_map 中的字符串和 JSON 节点），缓存维护总量；写入后总量超过
+//! 上限（`limits.json` 的 `cacheMemoryMb`，默认 1 GB）时按 LRU 继续淘汰其他条目，
+//! 即使条数未满。刚写入的条目始终保留（搜索依赖它在缓存中），因此单个会话本身超过上限时
+//! 缓存中只剩它一个。总量与淘汰次数通过 `get_cache_stats` 暴露给前端。
 //!
 //! ## 线程安全
 //! 使用 `std::sync::RwLock` 保证多线程安全访问。
 //! Tauri 的 command 可能在不同线程上并发执行，RwLock 允许多个读操作并发进行。
 //!
+//! 会话缓存的锁只保护 HashMap 本身：条目以 `Arc` 存放，读路径（`get_session`、搜索、关键词）
+//! 在读锁内取出 `Arc` 后立即释放锁，TransformedSession 的克隆与搜索都在锁外完成；
+//! LRU 访问时间是原子计数（`SessionCache::clock`），读取无需写锁。
+//! 写锁只在插入、淘汰、移除条目时短暂持有，元数据文本与占用估算也在加锁前算好。
+//! 因此搜索一个大会话期间，其他会话的读取与写入不会被阻塞。
+//!
+//! ## 会话写锁
+//! 所有会话写路径（删除/编辑/插入/修复/恢复）都是「读取 → 修改 → 写回」，
+//! 对同一文件交错执行会互相覆盖。`lock_session` 为每个会话路径提供一把
+//! `tokio::sync::Mutex`，写路径在读取前获取、写回后释放，保证同一文件的写操作串行化。
+//! 锁表只持有弱引用，无人持有或等待的锁会在下次加锁时被清理，避免无限增长。
+//!
+//! ## 撤销栈
+//! 删除、编辑、插入、追加、截断、合并消息等写操作写回成功后，把修改前的
+//! `Vec<Value>` 连同操作描述与写回后的文件 mtime 压入该会话的撤销栈（超过 `MAX_UNDO_STEPS`
+//! 时丢弃最早的一步）；不压栈的写操作（修复、重命名写入标题、会话合并、转码、恢复备份等）
+//! 写回后调用 `clear_undo` 清空撤销栈并记下操作名，下次撤销时说明原因。
+//! `undo_last_operation` 弹栈前比对 mtime：文件在此之后被外部程序改过时拒绝撤销
+//! （错误以 `UNDO_CONFLICT_ERROR` 开头）并清空该会话的撤销栈，避免用旧快照覆盖新内容。
+//! 撤销写回失败时由 `restore_undo` 把这一步放回栈顶；写回成功后由 `finish_undo`
+//! 把新栈顶的 mtime 更新为写回后的值，连续撤销不会被误判为冲突。
+//! 快照的估算占用计入会话缓存的内存上限：压栈后超过上限时先按 LRU 淘汰其他会话的缓存条目，
+//! 仍超出时丢弃其他会话的撤销栈，最后丢弃本会话较早的步骤（刚压入的一步始终保留）。
+//! 撤销栈只存在于内存中，应用重启后清空。
+//!
 //! ## 搜索架构
 //! 搜索文本在 transform 阶段预计算并以双版本形式缓存在 Rust 端：
 //! - `search_texts`：小写化版本，用于大小写不敏感搜索（`memchr::memmem` SIMD 加速）
@@ -24,16 +68,32 @@
 //! 4. **字面量 + 大小写不敏感**：`memchr::memmem` 在 `search_texts`（已小写）上匹配
 //!
 //! 小数组（< `PARALLEL_THRESHOLD`）使用顺序迭代，大数组使用 rayon 并行迭代。
+//! 每条命中返回 `SearchHit`：匹配次数与前几个匹配的字节区间及上下文片段。
+//!
+//! 另有 `meta_texts`（`tool:` / `model:` / `cwd:` 前缀行）单独缓存，
+//! 查询写成 `tool:bash`、`model:opus`、`cwd:src` 时只在对应字段上匹配，普通查询不受影响。
 
-use std::collections::HashMap;
-use std::sync::RwLock;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock, Weak};
 use std::time::{Instant, SystemTime};
 
 use rayon::prelude::*;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 
-use crate::models::display::TransformedSession;
-use crate::models::project::Project;
+use crate::models::display::{DisplayMessage, ToolUseInfo, TransformedSession};
+use crate::models::project::{Project, ProjectCounts, ProjectPage, ProjectSummary, ScanWarning, Session};
+use crate::services::scanner::{self, ProjectSnapshot};
 use crate::services::file_guard::TempBackupEntry;
+use crate::services::global_search;
+use crate::services::keywords::{self, SessionKeyword};
+use crate::services::limits::DEFAULT_CACHE_MEMORY_MB;
+use crate::services::project_stats::ProjectStats;
+use crate::services::transformer::{self, SearchField};
+use crate::utils::time;
 
 /// 项目列表缓存的默认有效期（秒）
 ///
@@ -41,11 +101,22 @@ use crate::services::file_guard::TempBackupEntry;
 /// 用户可以通过显式刷新操作强制重新扫描。
 const PROJECT_CACHE_TTL_SECS: u64 = 30;
 
+/// 项目统计缓存的有效期（秒）
+///
+/// 统计需要完整读取项目下的全部会话，代价远高于扫描；概览面板的数字允许几分钟的滞后。
+const PROJECT_STATS_TTL_SECS: u64 = 300;
+
 /// 会话缓存的最大容量
 ///
 /// 最多缓存这么多个会话的转换结果和搜索文本。当缓存满时，最久未访问的会话将被淘汰。
 const SESSION_CACHE_MAX_ENTRIES: usize = 20;
 
+/// 每个会话撤销栈的最大步数
+pub const MAX_UNDO_STEPS: usize = 10;
+
+/// 会话文件在压栈后被修改、无法撤销时的错误码（错误字符串前缀）
+pub const UNDO_CONFLICT_ERROR: &str = "UndoConflict";
+
 /// 并行搜索的数组长度阈值
 ///
 /// 当 display_messages 数量小于此阈值时，使用顺序迭代（`.iter()`）搜索；
@@ -55,6 +126,93 @@ const SESSION_CACHE_MAX_ENTRIES: usize = 20;
 /// 因此小数组场景下顺序搜索反而更快。
 const PARALLEL_THRESHOLD: usize = 100;
 
+/// 会话内搜索每条命中消息最多返回的片段数
+const MAX_SNIPPETS_PER_HIT: usize = 5;
+
+/// 会话内搜索的一个匹配
+///
+/// 对应前端 TypeScript 接口 `SearchSnippet`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSnippet {
+    /// 匹配在该消息搜索文本中的起始字节位置
+    pub start: usize,
+    /// 匹配在该消息搜索文本中的结束字节位置（不含）
+    pub end: usize,
+    /// 匹配位置前后各 40 个字符的片段（按字符边界截取，换行替换为空格）
+    pub preview: String,
+}
+
+/// 会话内搜索的一条命中消息
+///
+/// 由 `search_session` command 返回，对应前端 TypeScript 接口 `SearchHit`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    /// 命中消息的 display_id
+    pub display_id: String,
+    /// 该消息内的匹配次数（不重叠）
+    pub match_count: usize,
+    /// 前 `MAX_SNIPPETS_PER_HIT` 个匹配的位置与片段
+    pub snippets: Vec<SearchSnippet>,
+}
+
+/// 会话内搜索的消息过滤条件（在文本匹配之外按消息类型与时间范围限定）
+///
+/// 对应前端 `SearchOptions` 中的 `types` / `after` / `before`，所有字段均可省略，默认值表示不过滤。
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchFilter {
+    /// 只搜索这些 `display_type` 的消息，空表示不过滤
+    #[serde(default, deserialize_with = "deserialize_types")]
+    pub types: Vec<String>,
+    /// 只搜索该时间之后（含）的消息（前端传 ISO 8601，反序列化为 Unix 毫秒）
+    #[serde(default, deserialize_with = "deserialize_iso8601_millis")]
+    pub after: Option<i64>,
+    /// 只搜索该时间之前（含）的消息（前端传 ISO 8601，反序列化为 Unix 毫秒）
+    #[serde(default, deserialize_with = "deserialize_iso8601_millis")]
+    pub before: Option<i64>,
+}
+
+/// 反序列化消息类型列表，`null` 视为不过滤
+fn deserialize_types<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<Vec<String>>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// 把 ISO 8601 时间（可带 `Z` 或 `±HH:MM` 时区后缀）反序列化为 Unix 毫秒，`null` 表示不限
+///
+/// # 错误
+/// 不是合法的 ISO 8601 时间时返回错误
+fn deserialize_iso8601_millis<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| time::parse_iso8601_millis(&s).ok_or_else(|| D::Error::custom(format!("无效的时间: {}", s))))
+        .transpose()
+}
+
+impl SessionSearchFilter {
+    /// 判断一条显示消息是否参与匹配
+    ///
+    /// 设置了时间范围时，时间戳无法解析的消息按不匹配处理。
+    fn accepts(&self, message: &DisplayMessage) -> bool {
+        if !self.types.is_empty() && !self.types.contains(&message.display_type) {
+            return false;
+        }
+        if self.after.is_none() && self.before.is_none() {
+            return true;
+        }
+        let Some(millis) = time::parse_iso8601_millis(&message.timestamp) else {
+            return false;
+        };
+        self.after.is_none_or(|after| millis >= after) && self.before.is_none_or(|before| millis <= before)
+    }
+}
+
 /// 应用全局缓存状态
 ///
 /// 通过 Tauri 的 `manage()` 方法注册为应用状态，
@@ -63,7 +221,7 @@ const PARALLEL_THRESHOLD: usize = 100;
 /// # 线程安全
 /// 内部使用 `RwLock` 包装，支持多读单写的并发访问模式。
 pub struct AppCache {
-    /// 项目列表缓存：存储最近一次完整扫描的结果和扫描时间
+    /// 项目列表缓存：存储已扫描的项目（可能只是前几页）和扫描时间
     projects: RwLock<Option<ProjectCacheEntry>>,
 
     /// 会话缓存：以文件路径为 key，缓存转换后的 TransformedSession 和搜索文本
@@ -73,23 +231,218 @@ pub struct AppCache {
     /// 临时备份注册表：记录本次应用运行期间所有临时备份的映射关系
     /// 应用关闭后注册表清空，但 TEMP 目录下的备份文件仍由 OS 管理
     temp_backups: RwLock<Vec<TempBackupEntry>>,
+
+    /// 会话写锁表：会话文件路径 → 写锁（弱引用，持有者全部释放后可被清理）
+    session_locks: Mutex<HashMap<String, Weak<tokio::sync::Mutex<()>>>>,
+
+    /// 项目摘要缓存：项目路径 → 摘要（或「没有摘要文件」）
+    project_summaries: RwLock<HashMap<String, ProjectSummaryCacheEntry>>,
+
+    /// 项目目录扫描快照：数据源 ID → 目录名 → 快照（供增量扫描复用，不随项目列表 TTL 或失效清除）
+    project_snapshots: RwLock<HashMap<String, HashMap<String, ProjectSnapshot>>>,
+
+    /// 项目与会话计数：(数据目录, 计数, 统计时间)，与项目列表缓存同 TTL、一并失效
+    project_counts: RwLock<Option<(String, ProjectCounts, Instant)>>,
+
+    /// 单项目会话列表：项目目录绝对路径 → (全部会话, 扫描时间)，与项目列表缓存同 TTL、一并失效
+    project_sessions: RwLock<HashMap<PathBuf, (Vec<Session>, Instant)>>,
+
+    /// 项目统计：项目目录绝对路径 → (统计结果, 统计时间)，按 `PROJECT_STATS_TTL_SECS` 过期
+    project_stats: RwLock<HashMap<PathBuf, (ProjectStats, Instant)>>,
+
+    /// 审计日志写入失败次数（审计失败不阻塞主操作，只在统计中暴露）
+    audit_failures: AtomicU64,
+
+    /// 撤销栈：会话文件路径 → 操作前快照（栈顶在末尾）
+    undo_stacks: Mutex<HashMap<String, VecDeque<UndoStep>>>,
+
+    /// 撤销栈被不可撤销的写操作清空的会话：会话文件路径 → 该操作的描述（下次撤销时告知用户）
+    undo_cleared: Mutex<HashMap<String, String>>,
+}
+
+/// 撤销栈中的一步
+#[derive(Debug)]
+pub struct UndoStep {
+    /// 操作描述（如「删除 3 条消息」），供前端展示撤销了什么
+    pub description: String,
+    /// 操作前的完整消息列表
+    pub messages: Vec<Value>,
+    /// 操作写回后的文件 mtime（撤销前据此判断文件是否又被修改）
+    file_mtime: SystemTime,
+    /// 快照的估算占用（字节，计入会话缓存的内存上限）
+    size_bytes: usize,
+}
+
+/// 项目摘要缓存条目
+struct ProjectSummaryCacheEntry {
+    /// 摘要内容；None 表示项目目录下没有摘要文件
+    summary: Option<ProjectSummary>,
+    /// 摘要来源文件及其 mtime（用于判断缓存是否仍然有效）
+    source: Option<(PathBuf, SystemTime)>,
+    /// 缓存创建的时间点（没有摘要文件时按 TTL 失效）
+    cached_at: Instant,
 }
 
 /// 项目列表缓存条目
+///
+/// `data` 是按目录 mtime 倒序排列的全部项目目录的一个前缀：
+/// `data.len() < total_projects` 表示这是部分结果，其余项目尚未加载。
 struct ProjectCacheEntry {
-    /// 缓存的项目数据
+    /// 扫描的 projects 目录（`<数据目录>/projects`），定点更新时据此确认会话属于缓存中的项目
+    projects_dir: PathBuf,
+    /// 缓存的项目数据（目录 mtime 倒序，从偏移 0 开始连续）
     data: Vec<Project>,
+    /// 项目目录名 → 在 `data` 中的下标（`data` 变化后由 `reindex` 重建）
+    index: HashMap<String, usize>,
+    /// 扫描时的项目目录总数
+    total_projects: usize,
+    /// 扫描时跳过的条目（按路径去重），缓存命中时随页面返回
+    warnings: Vec<ScanWarning>,
     /// 缓存创建的时间点（用于 TTL 判断）
     cached_at: Instant,
 }
 
+impl ProjectCacheEntry {
+    /// 以一页项目建立新的缓存条目
+    fn new(projects_dir: PathBuf, data: Vec<Project>, total_projects: usize, warnings: Vec<ScanWarning>) -> Self {
+        let mut entry = Self {
+            projects_dir,
+            data,
+            index: HashMap::new(),
+            total_projects,
+            warnings,
+            cached_at: Instant::now(),
+        };
+        entry.reindex();
+        entry
+    }
+
+    /// 重建目录名索引
+    fn reindex(&mut self) {
+        self.index = self.data.iter().enumerate().map(|(i, p)| (p.name.clone(), i)).collect();
+    }
+
+    /// 按项目目录名查找项目
+    fn project_mut(&mut self, name: &str) -> Option<&mut Project> {
+        let i = *self.index.get(name)?;
+        self.data.get_mut(i)
+    }
+}
+
+/// 会话文件的变更（`AppCache::update_project_session` 的参数）
+#[derive(Debug, Clone)]
+pub enum SessionChange {
+    /// 新建了会话文件（副本、恢复的残留等）：加入缓存，已存在同 ID 的会话时替换
+    Added(Box<Session>),
+    /// 会话文件已删除：从缓存中移除该会话
+    Removed,
+    /// 会话文件已改写：更新修改时间、文件大小、行数与消息数
+    Updated {
+        /// 新的文件 mtime（ISO 8601）
+        timestamp: String,
+        /// 新的文件大小（字节）
+        file_size: u64,
+        /// 写入的行数；None 表示未知，保留原值
+        line_count: Option<u64>,
+        /// 写入的消息记录数（带 `uuid` 的行）；None 表示未知，保留原值
+        message_count: Option<usize>,
+    },
+}
+
+impl SessionChange {
+    /// 读取会话文件的当前状态生成变更（文件已不存在时为 `Removed`）
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `written` - 刚写入的全部记录，用于更新行数与消息数（未知时为 None）
+    pub async fn from_file(file_path: &Path, written: Option<&[Value]>) -> Self {
+        match tokio::fs::metadata(file_path).await {
+            Ok(metadata) => Self::Updated {
+                timestamp: metadata
+                    .modified()
+                    .map(scanner::system_time_to_iso8601)
+                    .unwrap_or_else(|_| scanner::system_time_to_iso8601(SystemTime::now())),
+                file_size: metadata.len(),
+                line_count: written.map(|records| records.len() as u64),
+                message_count: written.map(|records| {
+                    records.iter().filter(|r| r.get("uuid").and_then(Value::as_str).is_some()).count()
+                }),
+            },
+            Err(_) => Self::Removed,
+        }
+    }
+
+    /// 把变更应用到一个项目的会话列表（改写时找不到会话不做任何事），并重新排列
+    fn apply(&self, sessions: &mut Vec<Session>, session_id: &str) {
+        match self {
+            Self::Added(session) => {
+                sessions.retain(|s| s.id != session_id);
+                sessions.push(Session::clone(session));
+                scanner::sort_sessions(sessions);
+            }
+            Self::Removed => sessions.retain(|s| s.id != session_id),
+            Self::Updated { timestamp, file_size, line_count, message_count } => {
+                for session in sessions.iter_mut().filter(|s| s.id == session_id) {
+                    session.timestamp = timestamp.clone();
+                    session.file_size = Some(*file_size);
+                    if let Some(count) = *message_count {
+                        session.message_count = u32::try_from(count).unwrap_or(u32::MAX);
+                    }
+                    // 行数只在扫描时要求统计才有值，保持「未统计」的语义
+                    if let Some(lines) = *line_count
+                        && session.line_count.is_some()
+                    {
+                        session.line_count = Some(lines);
+                    }
+                }
+                scanner::sort_sessions(sessions);
+            }
+        }
+    }
+}
+
 /// 会话缓存
 ///
 /// 简化版 LRU 缓存实现，使用 HashMap 存储数据，
-/// 通过 `last_accessed` 时间戳实现 LRU 淘汰策略。
+/// 通过 `last_accessed` 访问序号实现 LRU 淘汰策略。
+/// 条目的增删都经过 `insert` / `remove`，以保持 `total_bytes` 与条目一致。
 struct SessionCache {
-    /// 缓存条目映射：文件路径 → 缓存条目
-    entries: HashMap<String, SessionCacheEntry>,
+    /// 缓存条目映射：文件路径 → 缓存条目（共享所有权，读取方可在锁外使用）
+    entries: HashMap<String, Arc<SessionCacheEntry>>,
+    /// 访问时钟：每次访问递增，作为条目的 `last_accessed`（读锁下即可更新）
+    clock: AtomicU64,
+    /// 全部条目的估算占用之和（字节）
+    total_bytes: usize,
+    /// 撤销栈快照的估算占用之和（字节，与条目共用内存上限）
+    undo_bytes: usize,
+    /// 内存上限（字节）
+    max_bytes: usize,
+    /// 累计淘汰次数（含按条数与按内存淘汰）
+    evictions: u64,
+    /// 其中因超过内存上限而淘汰的次数
+    memory_evictions: u64,
+}
+
+/// 会话缓存的占用统计
+///
+/// 对应前端 TypeScript 接口 `CacheStats`。
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    /// 当前缓存的会话数
+    pub entries: usize,
+    /// 当前估算占用（字节）
+    pub total_bytes: usize,
+    /// 撤销栈快照的估算占用（字节，计入内存上限）
+    pub undo_bytes: usize,
+    /// 内存上限（字节）
+    pub max_bytes: usize,
+    /// 累计淘汰次数（含按条数与按内存淘汰）
+    pub evictions: u64,
+    /// 其中因超过内存上限而淘汰的次数
+    pub memory_evictions: u64,
+    /// 审计日志写入失败次数（见 `services::audit`）
+    pub audit_failures: u64,
 }
 
 /// 单个会话缓存条目
@@ -103,56 +456,228 @@ struct SessionCacheEntry {
     search_texts: Vec<String>,
     /// 原始大小写搜索文本（用于大小写敏感和正则搜索模式）
     original_texts: Vec<String>,
+    /// 元数据搜索文本（`tool:` / `model:` / `cwd:` 前缀行，用于字段限定查询）
+    meta_texts: Vec<String>,
+    /// 关键词排行（首次查询时由 search_texts 计算，最多 `keywords::MAX_KEYWORDS` 个）
+    keywords: OnceLock<Vec<SessionKeyword>>,
     /// 文件的最后修改时间（用于判断缓存是否仍然有效）
     file_mtime: SystemTime,
-    /// 最后访问时间（用于 LRU 淘汰）
-    last_accessed: Instant,
+    /// 写入缓存时的文件大小（字节，仅供一致性自检比对）
+    file_size: u64,
+    /// 最后一次访问的时钟值（用于 LRU 淘汰）
+    last_accessed: AtomicU64,
+    /// 写入时估算的内存占用（字节）
+    size_bytes: usize,
+}
+
+/// 会话缓存条目的摘要（`session_cache_snapshots` 的返回项）
+#[derive(Debug, Clone)]
+pub struct SessionCacheSnapshot {
+    /// 会话 JSONL 文件的绝对路径
+    pub file_path: String,
+    /// 写入缓存时的文件 mtime
+    pub file_mtime: SystemTime,
+    /// 写入缓存时的文件大小（字节）
+    pub file_size: u64,
+    /// 缓存的 display_messages 的 display_id（按顺序）
+    pub display_ids: Vec<String>,
+}
+
+impl SessionCache {
+    /// 取出条目并记录一次访问（只需读锁）
+    fn touch(&self, key: &str) -> Option<Arc<SessionCacheEntry>> {
+        let entry = self.entries.get(key)?;
+        entry.last_accessed.store(self.clock.fetch_add(1, Ordering::Relaxed) + 1, Ordering::Relaxed);
+        Some(Arc::clone(entry))
+    }
+
+    /// 插入或替换条目，并累加其占用
+    fn insert(&mut self, key: String, entry: Arc<SessionCacheEntry>) {
+        self.total_bytes += entry.size_bytes;
+        if let Some(old) = self.entries.insert(key, entry) {
+            self.total_bytes -= old.size_bytes;
+        }
+    }
+
+    /// 移除条目，并扣减其占用
+    fn remove(&mut self, key: &str) -> Option<Arc<SessionCacheEntry>> {
+        let entry = self.entries.remove(key)?;
+        self.total_bytes -= entry.size_bytes;
+        Some(entry)
+    }
+
+    /// 条目与撤销栈快照的总占用是否超过内存上限
+    fn over_limit(&self) -> bool {
+        self.total_bytes + self.undo_bytes > self.max_bytes
+    }
+
+    /// 淘汰除 `keep` 外最久未访问的条目；没有可淘汰的条目时返回 false
+    fn evict_lru(&mut self, keep: &str) -> bool {
+        let Some(oldest_key) = self
+            .entries
+            .iter()
+            .filter(|(key, _)| key.as_str() != keep)
+            .min_by_key(|(_, entry)| entry.last_accessed.load(Ordering::Relaxed))
+            .map(|(key, _)| key.clone())
+        else {
+            return false;
+        };
+        self.remove(&oldest_key);
+        self.evictions += 1;
+        true
+    }
 }
 
 impl AppCache {
-    /// 创建新的空缓存实例
+    /// 创建新的空缓存实例（会话缓存使用默认内存上限）
     pub fn new() -> Self {
+        Self::with_memory_limit((DEFAULT_CACHE_MEMORY_MB * 1024 * 1024) as usize)
+    }
+
+    /// 以指定的会话缓存内存上限创建空缓存实例
+    ///
+    /// # 参数
+    /// - `max_bytes` - 会话缓存的估算占用上限（字节）
+    pub fn with_memory_limit(max_bytes: usize) -> Self {
         Self {
             projects: RwLock::new(None),
             sessions: RwLock::new(SessionCache {
                 entries: HashMap::new(),
+                clock: AtomicU64::new(0),
+                total_bytes: 0,
+                undo_bytes: 0,
+                max_bytes,
+                evictions: 0,
+                memory_evictions: 0,
             }),
             temp_backups: RwLock::new(Vec::new()),
+            session_locks: Mutex::new(HashMap::new()),
+            project_summaries: RwLock::new(HashMap::new()),
+            project_snapshots: RwLock::new(HashMap::new()),
+            project_counts: RwLock::new(None),
+            project_sessions: RwLock::new(HashMap::new()),
+            project_stats: RwLock::new(HashMap::new()),
+            audit_failures: AtomicU64::new(0),
+            undo_stacks: Mutex::new(HashMap::new()),
+            undo_cleared: Mutex::new(HashMap::new()),
         }
     }
 
     // ======== 项目列表缓存方法 ========
 
-    /// 获取缓存的项目列表（如果缓存仍然有效）
+    /// 从缓存获取一页项目（如果缓存仍然有效且覆盖了请求范围）
+    ///
+    /// # 参数
+    /// - `offset` - 起始偏移（目录 mtime 倒序）
+    /// - `max_projects` - 本页最多项目数；None 表示取到末尾
     ///
     /// # 返回值
-    /// - `Some(projects)` - 缓存有效时返回缓存数据的克隆
-    /// - `None` - 缓存无效（不存在或已过期）时返回 None
-    pub fn get_projects(&self) -> Option<Vec<Project>> {
+    /// - `Some(page)` - 缓存在 TTL 内且已加载的前缀覆盖了 `[offset, offset + max_projects)`
+    /// - `None` - 缓存无效，或请求范围包含尚未加载的项目
+    ///
+    /// 命中时 `warnings` 为缓存中记录的全部扫描警告（不只是本页的）。
+    pub fn get_projects_page(&self, offset: usize, max_projects: Option<usize>) -> Option<ProjectPage> {
         let cache = self.projects.read().ok()?;
         let entry = cache.as_ref()?;
 
         // 检查缓存是否在 TTL 内
-        if entry.cached_at.elapsed().as_secs() <= PROJECT_CACHE_TTL_SECS {
-            Some(entry.data.clone())
-        } else {
-            None
+        if entry.cached_at.elapsed().as_secs() > PROJECT_CACHE_TTL_SECS {
+            return None;
+        }
+
+        let total = entry.total_projects;
+        let start = offset.min(total);
+        let end = max_projects.map_or(total, |max| start.saturating_add(max).min(total));
+        if end > entry.data.len() {
+            return None;
         }
+
+        let mut projects = entry.data[start..end].to_vec();
+        scanner::sort_by_latest_session(&mut projects);
+        Some(ProjectPage {
+            projects,
+            total_projects: total,
+            offset: start,
+            has_more: end < total,
+            warnings: entry.warnings.clone(),
+        })
     }
 
-    /// 更新项目列表缓存
+    /// 将新扫描的一页项目合并进缓存
+    ///
+    /// - 缓存无效时：仅当 `offset == 0` 时以本页建立新的（可能是部分的）缓存
+    /// - 缓存有效时：用本页替换 `[offset, offset + projects.len())` 范围，
+    ///   其余已加载的页保留；同名项目在范围外的旧副本被移除（目录 mtime 变化导致换页）。
+    ///   只有本页覆盖了全部已加载的项目时才刷新缓存时间，部分合并保留较早的时间，
+    ///   避免未重新扫描的旧页随新页一起续期
+    /// - `offset` 超出已加载前缀（中间有空洞）或 projects 目录与缓存不同时，
+    ///   仅当 `offset == 0` 时重建缓存，否则不缓存
     ///
     /// # 参数
-    /// - `projects` - 新的项目列表数据
-    pub fn set_projects(&self, projects: Vec<Project>) {
-        if let Ok(mut cache) = self.projects.write() {
-            *cache = Some(ProjectCacheEntry {
-                data: projects,
-                cached_at: Instant::now(),
-            });
+    /// - `projects_dir` - 扫描的 projects 目录（`<数据目录>/projects`）
+    /// - `offset` - 本页起始偏移
+    /// - `projects` - 本页项目（目录 mtime 倒序）
+    /// - `total_projects` - 本次扫描得到的项目目录总数
+    /// - `warnings` - 本次扫描跳过的条目，替换缓存中同路径的旧警告
+    pub fn merge_projects(
+        &self,
+        projects_dir: &Path,
+        offset: usize,
+        projects: Vec<Project>,
+        total_projects: usize,
+        warnings: Vec<ScanWarning>,
+    ) {
+        let Ok(mut cache) = self.projects.write() else {
+            return;
+        };
+
+        let valid = cache
+            .as_ref()
+            .is_some_and(|e| e.projects_dir == projects_dir && e.cached_at.elapsed().as_secs() <= PROJECT_CACHE_TTL_SECS);
+
+        match cache.as_mut() {
+            Some(entry) if valid && offset <= entry.data.len() => {
+                let fresh_names: std::collections::HashSet<String> =
+                    projects.iter().map(|p| p.name.clone()).collect();
+                let end = (offset + projects.len()).min(entry.data.len());
+                let fresh_len = projects.len();
+                let covers_all = offset == 0 && fresh_len >= entry.data.len();
+                entry.data.splice(offset..end, projects);
+
+                // 移除范围外的同名旧副本
+                let mut index = 0;
+                entry.data.retain(|p| {
+                    let keep = (offset..offset + fresh_len).contains(&index)
+                        || !fresh_names.contains(&p.name);
+                    index += 1;
+                    keep
+                });
+
+                entry.data.truncate(total_projects);
+                entry.reindex();
+                entry.total_projects = total_projects;
+                entry.warnings.retain(|old| !warnings.iter().any(|w| w.path == old.path));
+                entry.warnings.extend(warnings);
+                if covers_all {
+                    entry.cached_at = Instant::now();
+                }
+            }
+            _ if offset == 0 => {
+                *cache = Some(ProjectCacheEntry::new(projects_dir.to_path_buf(), projects, total_projects, warnings));
+            }
+            _ => {}
         }
     }
 
+    /// 取出项目列表缓存中已加载的全部项目（不检查 TTL，供一致性自检使用）
+    pub fn cached_projects(&self) -> Vec<Project> {
+        self.projects
+            .read()
+            .ok()
+            .and_then(|cache| cache.as_ref().map(|entry| entry.data.clone()))
+            .unwrap_or_default()
+    }
+
     /// 使项目列表缓存失效
     ///
     /// 在执行修改操作（如删除会话）后调用，确保下次查询会重新扫描
@@ -160,257 +685,1070 @@ impl AppCache {
         if let Ok(mut cache) = self.projects.write() {
             *cache = None;
         }
+        if let Ok(mut counts) = self.project_counts.write() {
+            *counts = None;
+        }
+        if let Ok(mut sessions) = self.project_sessions.write() {
+            sessions.clear();
+        }
     }
 
-    // ======== 会话缓存方法 ========
-
-    /// 获取缓存的会话转换结果（如果缓存仍然有效）
-    ///
-    /// 通过比对文件 mtime 判断缓存是否有效：
-    /// - 如果文件未被修改，直接返回缓存数据
-    /// - 如果文件已被修改（mtime 变化），返回 None 表示需要重新读取
+    /// 获取缓存的项目与会话计数（TTL 内且数据目录一致时）
     ///
     /// # 参数
-    /// - `file_path` - 会话 JSONL 文件的绝对路径
-    ///
-    /// # 返回值
-    /// - `Some(transformed)` - 缓存有效时返回 TransformedSession 的克隆
-    /// - `None` - 缓存无效时返回 None
-    pub fn get_session(&self, file_path: &str) -> Option<TransformedSession> {
-        let mut cache = self.sessions.write().ok()?;
-        let entry = cache.entries.get_mut(file_path)?;
-
-        // 检查文件是否被外部修改
-        let current_mtime = std::fs::metadata(file_path).ok()?.modified().ok()?;
+    /// - `claude_path` - Claude 数据目录路径
+    pub fn get_project_counts(&self, claude_path: &str) -> Option<ProjectCounts> {
+        let cache = self.project_counts.read().ok()?;
+        let (path, counts, cached_at) = cache.as_ref()?;
+        (path == claude_path && cached_at.elapsed().as_secs() <= PROJECT_CACHE_TTL_SECS).then_some(*counts)
+    }
 
-        if current_mtime == entry.file_mtime {
-            // 更新最后访问时间（LRU）
-            entry.last_accessed = Instant::now();
-            Some(entry.transformed.clone())
-        } else {
-            // 文件已被修改，缓存失效
-            cache.entries.remove(file_path);
-            None
+    /// 缓存项目与会话计数
+    ///
+    /// # 参数
+    /// - `claude_path` - Claude 数据目录路径
+    /// - `counts` - 统计结果
+    pub fn set_project_counts(&self, claude_path: &str, counts: ProjectCounts) {
+        if let Ok(mut cache) = self.project_counts.write() {
+            *cache = Some((claude_path.to_string(), counts, Instant::now()));
         }
     }
 
-    /// 更新会话缓存
-    ///
-    /// 如果缓存已满，先淘汰最久未访问的条目。
+    /// 获取缓存的单项目会话列表（TTL 内）
     ///
     /// # 参数
-    /// - `file_path` - 会话 JSONL 文件的绝对路径
-    /// - `transformed` - 转换后的 TransformedSession
-    /// - `search_texts` - 小写化的搜索文本列表（用于大小写不敏感搜索）
-    /// - `original_texts` - 原始大小写搜索文本列表（用于大小写敏感和正则搜索）
-    pub fn set_session(
-        &self,
-        file_path: &str,
-        transformed: TransformedSession,
-        search_texts: Vec<String>,
-        original_texts: Vec<String>,
-    ) {
-        if let Ok(mut cache) = self.sessions.write() {
-            // 获取文件的当前 mtime
-            let file_mtime = std::fs::metadata(file_path)
-                .ok()
-                .and_then(|m| m.modified().ok())
-                .unwrap_or(SystemTime::UNIX_EPOCH);
-
-            // 如果缓存已满且不是更新现有条目，淘汰最久未访问的条目
-            if cache.entries.len() >= SESSION_CACHE_MAX_ENTRIES
-                && !cache.entries.contains_key(file_path)
-            {
-                // 找到最久未访问的条目并移除
-                if let Some(oldest_key) = cache
-                    .entries
-                    .iter()
-                    .min_by_key(|(_, entry)| entry.last_accessed)
-                    .map(|(key, _)| key.clone())
-                {
-                    cache.entries.remove(&oldest_key);
-                }
-            }
+    /// - `project_dir` - 项目目录的绝对路径
+    pub fn get_project_sessions(&self, project_dir: &Path) -> Option<Vec<Session>> {
+        let cache = self.project_sessions.read().ok()?;
+        let (sessions, cached_at) = cache.get(project_dir)?;
+        (cached_at.elapsed().as_secs() <= PROJECT_CACHE_TTL_SECS).then(|| sessions.clone())
+    }
 
-            cache.entries.insert(
-                file_path.to_string(),
-                SessionCacheEntry {
-                    transformed,
-                    search_texts,
-                    original_texts,
-                    file_mtime,
-                    last_accessed: Instant::now(),
-                },
-            );
+    /// 缓存单项目会话列表，并用这次扫描结果刷新项目列表缓存中的同一项目
+    ///
+    /// # 参数
+    /// - `project_dir` - 项目目录的绝对路径
+    /// - `sessions` - 该项目的全部会话（已排序）
+    pub fn set_project_sessions(&self, project_dir: PathBuf, sessions: Vec<Session>) {
+        self.update_listed_project(&project_dir, |listed| *listed = sessions.clone());
+        if let Ok(mut cache) = self.project_sessions.write() {
+            cache.insert(project_dir, (sessions, Instant::now()));
         }
     }
 
-    /// 使指定会话的缓存失效
+    /// 新建、删除或改写单个会话后定点更新缓存（代替 `invalidate_projects` 的全量失效）
     ///
-    /// 在消息被编辑或删除后调用
+    /// 按项目目录定位项目列表缓存中的项目、按目录路径定位单项目会话列表缓存，
+    /// 加入、移除或更新该会话后重新排列，并重算项目的会话数与最新时间。
+    /// 会话增删时项目计数缓存失效；该项目的统计缓存一并失效。
     ///
     /// # 参数
-    /// - `file_path` - 会话 JSONL 文件的绝对路径
-    pub fn invalidate_session(&self, file_path: &str) {
-        if let Ok(mut cache) = self.sessions.write() {
-            cache.entries.remove(file_path);
+    /// - `project_dir` - 会话所在项目目录的绝对路径
+    /// - `session_id` - 会话 ID（JSONL 文件名去掉扩展名）
+    /// - `change` - 会话变更
+    pub fn update_project_session(&self, project_dir: &Path, session_id: &str, change: SessionChange) {
+        self.update_project_entries(project_dir, |sessions| change.apply(sessions, session_id));
+        if matches!(change, SessionChange::Added(_) | SessionChange::Removed)
+            && let Ok(mut counts) = self.project_counts.write()
+        {
+            *counts = None;
+        }
+        if let Ok(mut stats) = self.project_stats.write() {
+            stats.remove(project_dir);
         }
     }
 
-    /// 在缓存的搜索文本上执行搜索，支持 4 种搜索模式
+    /// 读取会话文件的当前状态并定点更新缓存（见 `update_project_session`）
     ///
-    /// 根据 `case_sensitive` 和 `use_regex` 参数的组合，选择不同的搜索策略：
+    /// 用于改写或删除已有会话；新建的会话文件改用 `add_session_file`。
     ///
-    /// | use_regex | case_sensitive | 搜索文本        | 方法                  |
-    /// |-----------|----------------|-----------------|----------------------|
-    /// | true      | false          | original_texts  | regex `(?i)pattern`  |
-    /// | true      | true           | original_texts  | regex `pattern`      |
-    /// | false     | true           | original_texts  | memchr::memmem 精确  |
-    /// | false     | false          | search_texts    | memchr::memmem 小写  |
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `written` - 刚写入的全部记录，用于更新行数与消息数（未知或已删除时为 None）
+    pub async fn sync_session_file(&self, file_path: &str, written: Option<&[Value]>) {
+        let path = Path::new(file_path);
+        let (Some(project_dir), Some(session_id)) = (path.parent(), path.file_stem().and_then(|s| s.to_str())) else {
+            return;
+        };
+        let change = SessionChange::from_file(path, written).await;
+        self.update_project_session(project_dir, session_id, change);
+    }
+
+    /// 轻量读取新建的会话文件并加入缓存（见 `update_project_session`）
     ///
-    /// 小数组（< `PARALLEL_THRESHOLD`）使用顺序迭代，大数组使用 rayon 并行迭代。
+    /// 同 ID 的会话已在缓存中时（如恢复备份覆盖了原文件）以重新读取的元数据替换。
+    /// 子 agent 会话不在项目列表缓存中，只读取不加入。
     ///
     /// # 参数
     /// - `file_path` - 会话 JSONL 文件的绝对路径
-    /// - `query` - 搜索查询词
-    /// - `case_sensitive` - 是否大小写敏感
-    /// - `use_regex` - 是否使用正则表达式模式
     ///
     /// # 返回值
-    /// - `Ok(Some(display_ids))` - 匹配的 display_id 列表
-    /// - `Ok(None)` - 缓存中没有该会话的数据
-    /// - `Err(msg)` - 正则表达式编译失败，msg 为错误描述
-    pub fn search_in_cache(
-        &self,
-        file_path: &str,
-        query: &str,
-        case_sensitive: bool,
-        use_regex: bool,
-    ) -> Result<Option<Vec<String>>, String> {
-        // 获取缓存读锁，缓存不存在时返回 Ok(None)
-        let cache = self.sessions.read().map_err(|e| format!("缓存读锁获取失败: {}", e))?;
-        let entry = match cache.entries.get(file_path) {
-            Some(e) => e,
-            None => return Ok(None),
-        };
-
-        let dm = &entry.transformed.display_messages;
-        // 元素数量决定使用顺序还是并行搜索
-        let n = entry.search_texts.len();
-
-        if use_regex {
-            // ---- 正则表达式搜索模式 ----
-            // 根据大小写敏感选项构建正则表达式 pattern
-            let pattern = if case_sensitive {
-                // 大小写敏感：直接使用原始 query 作为 pattern
-                query.to_string()
-            } else {
-                // 大小写不敏感：在 pattern 前加 `(?i)` 修饰符
-                format!("(?i){}", query)
-            };
+    /// 新会话的元数据（与扫描结果一致）；文件无法读取时为 None
+    pub async fn add_session_file(&self, file_path: &str) -> Option<Session> {
+        let path = Path::new(file_path);
+        let file_name = path.file_name()?.to_string_lossy().to_string();
+        let session = scanner::scan_single_session(file_name, path.to_path_buf()).await?;
+        if let Some(project_dir) = path.parent()
+            && !session.is_agent
+        {
+            let id = session.id.clone();
+            self.update_project_session(project_dir, &id, SessionChange::Added(Box::new(session.clone())));
+        }
+        Some(session)
+    }
 
-            // 编译正则表达式，失败时返回 Err
-            let re = regex::Regex::new(&pattern)
-                .map_err(|e| format!("无效正则表达式: {}", e))?;
+    /// 从项目列表缓存中摘除一个已不存在的项目目录（如碎片归并后删除的碎片项目）
+    ///
+    /// # 参数
+    /// - `project_dir` - 项目目录的绝对路径
+    pub fn remove_project(&self, project_dir: &Path) {
+        if let Ok(mut cache) = self.projects.write()
+            && let Some(entry) = cache.as_mut()
+            && project_dir.parent() == Some(entry.projects_dir.as_path())
+            && let Some(name) = project_dir.file_name().and_then(|n| n.to_str())
+            && let Some(&i) = entry.index.get(name)
+        {
+            entry.data.remove(i);
+            entry.total_projects = entry.total_projects.saturating_sub(1);
+            entry.reindex();
+        }
+        if let Ok(mut counts) = self.project_counts.write() {
+            *counts = None;
+        }
+        if let Ok(mut sessions) = self.project_sessions.write() {
+            sessions.remove(project_dir);
+        }
+        if let Ok(mut stats) = self.project_stats.write() {
+            stats.remove(project_dir);
+        }
+    }
 
-            // 在 original_texts 上执行正则匹配（保留原始大小写供 regex 处理）
-            let results: Vec<String> = if n < PARALLEL_THRESHOLD {
-                // 小数组：顺序迭代，避免并行化开销
-                entry
-                    .original_texts
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, text)| re.is_match(text))
-                    .map(|(i, _)| dm[i].display_id.clone())
-                    .collect()
-            } else {
-                // 大数组：rayon 并行迭代，利用多核加速
-                entry
-                    .original_texts
-                    .par_iter()
-                    .enumerate()
-                    .filter(|(_, text)| re.is_match(text))
-                    .map(|(i, _)| dm[i].display_id.clone())
-                    .collect()
-            };
+    /// 修改项目列表缓存与单项目会话列表缓存中某个项目的会话列表（两份缓存共用的更新原语）
+    fn update_project_entries(&self, project_dir: &Path, update: impl Fn(&mut Vec<Session>)) {
+        self.update_listed_project(project_dir, &update);
+        if let Ok(mut lists) = self.project_sessions.write()
+            && let Some((sessions, _)) = lists.get_mut(project_dir)
+        {
+            update(sessions);
+        }
+    }
 
-            Ok(Some(results))
-        } else if case_sensitive {
-            // ---- 字面量 + 大小写敏感搜索 ----
-            // 使用 memchr::memmem::find 在 original_texts 上精确匹配（needle 不小写化）
-            let needle = query.as_bytes();
-
-            let results: Vec<String> = if n < PARALLEL_THRESHOLD {
-                // 小数组：顺序迭代
-                entry
-                    .original_texts
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, text)| memchr::memmem::find(text.as_bytes(), needle).is_some())
-                    .map(|(i, _)| dm[i].display_id.clone())
-                    .collect()
-            } else {
-                // 大数组：rayon 并行迭代
-                entry
-                    .original_texts
-                    .par_iter()
-                    .enumerate()
-                    .filter(|(_, text)| memchr::memmem::find(text.as_bytes(), needle).is_some())
-                    .map(|(i, _)| dm[i].display_id.clone())
-                    .collect()
-            };
+    /// 修改项目列表缓存中某个项目的会话列表，并重算会话数与最新时间
+    ///
+    /// 项目列表缓存只包含默认数据源的项目，其他数据源可能有同名目录：
+    /// 只有 `project_dir` 位于缓存扫描的 projects 目录下时才修改（会话列表为空的项目同样适用）。
+    fn update_listed_project(&self, project_dir: &Path, update: impl FnOnce(&mut Vec<Session>)) {
+        let Some(name) = project_dir.file_name().and_then(|n| n.to_str()) else {
+            return;
+        };
+        if let Ok(mut cache) = self.projects.write()
+            && let Some(entry) = cache.as_mut()
+            && project_dir.parent() == Some(entry.projects_dir.as_path())
+            && let Some(project) = entry.project_mut(name)
+        {
+            update(&mut project.sessions);
+            scanner::refresh_project_totals(project);
+        }
+    }
 
-            Ok(Some(results))
-        } else {
-            // ---- 字面量 + 大小写不敏感搜索（原有逻辑）----
-            // 在预计算的小写化 search_texts 上匹配，needle 也需小写化
-            let needle_lower = query.to_lowercase();
-            let needle = needle_lower.as_bytes();
-
-            let results: Vec<String> = if n < PARALLEL_THRESHOLD {
-                // 小数组：顺序迭代
-                entry
-                    .search_texts
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, text)| memchr::memmem::find(text.as_bytes(), needle).is_some())
-                    .map(|(i, _)| dm[i].display_id.clone())
-                    .collect()
-            } else {
-                // 大数组：rayon 并行迭代，SIMD memchr 加速
-                entry
-                    .search_texts
-                    .par_iter()
-                    .enumerate()
-                    .filter(|(_, text)| memchr::memmem::find(text.as_bytes(), needle).is_some())
-                    .map(|(i, _)| dm[i].display_id.clone())
-                    .collect()
-            };
+    /// 获取缓存的项目统计（`PROJECT_STATS_TTL_SECS` 内）
+    ///
+    /// # 参数
+    /// - `project_dir` - 项目目录的绝对路径
+    pub fn get_project_stats(&self, project_dir: &Path) -> Option<ProjectStats> {
+        let cache = self.project_stats.read().ok()?;
+        let (stats, cached_at) = cache.get(project_dir)?;
+        (cached_at.elapsed().as_secs() <= PROJECT_STATS_TTL_SECS).then(|| stats.clone())
+    }
 
-            Ok(Some(results))
+    /// 清空全部项目统计缓存（显示时区等影响统计口径的设置变更后调用）
+    pub fn invalidate_project_stats(&self) {
+        if let Ok(mut cache) = self.project_stats.write() {
+            cache.clear();
         }
     }
 
-    // ======== 临时备份注册表方法 ========
-
-    /// 注册一条临时备份记录
+    /// 缓存项目统计
     ///
-    /// 由 `file_guard` 在创建临时备份后调用。
-    pub fn register_temp_backup(&self, entry: TempBackupEntry) {
-        if let Ok(mut backups) = self.temp_backups.write() {
-            backups.push(entry);
+    /// # 参数
+    /// - `project_dir` - 项目目录的绝对路径
+    /// - `stats` - 统计结果
+    pub fn set_project_stats(&self, project_dir: PathBuf, stats: ProjectStats) {
+        if let Ok(mut cache) = self.project_stats.write() {
+            cache.retain(|_, (_, cached_at)| cached_at.elapsed().as_secs() <= PROJECT_STATS_TTL_SECS);
+            cache.insert(project_dir, (stats, Instant::now()));
         }
     }
 
-    /// 获取所有临时备份记录（供前端展示）
+    /// 获取指定项目目录的扫描快照（供增量扫描复用）
     ///
-    /// 返回本次应用运行期间所有临时备份的完整列表。
-    pub fn get_all_temp_backups(&self) -> Vec<TempBackupEntry> {
+    /// # 参数
+    /// - `source_id` - 数据源 ID（见 `data_sources`）
+    /// - `dir_names` - 本次要扫描的项目目录名
+    /// - `trust_listing` - 为 false 时（强制刷新）要求重新枚举目录，会话元数据仍可复用
+    pub fn get_project_snapshots<'a>(
+        &self,
+        source_id: &str,
+        dir_names: impl IntoIterator<Item = &'a str>,
+        trust_listing: bool,
+    ) -> HashMap<String, ProjectSnapshot> {
+        let Ok(all) = self.project_snapshots.read() else {
+            return HashMap::new();
+        };
+        let Some(snapshots) = all.get(source_id) else {
+            return HashMap::new();
+        };
+        dir_names
+            .into_iter()
+            .filter_map(|name| {
+                let mut snapshot = snapshots.get(name)?.clone();
+                if !trust_listing {
+                    snapshot.distrust_listing();
+                }
+                Some((name.to_string(), snapshot))
+            })
+            .collect()
+    }
+
+    /// 保存本次扫描的快照，并移除已不存在的项目目录的快照
+    ///
+    /// # 参数
+    /// - `source_id` - 数据源 ID（见 `data_sources`）
+    /// - `fresh` - 本次扫描得到的快照（目录名 → 快照）
+    /// - `existing` - 该数据源 `projects/` 下的全部目录名
+    pub fn update_project_snapshots(
+        &self,
+        source_id: &str,
+        fresh: HashMap<String, ProjectSnapshot>,
+        existing: &std::collections::HashSet<&str>,
+    ) {
+        if let Ok(mut all) = self.project_snapshots.write() {
+            let snapshots = all.entry(source_id.to_string()).or_default();
+            snapshots.retain(|name, _| existing.contains(name.as_str()));
+            snapshots.extend(fresh);
+        }
+    }
+
+    /// 保存部分项目目录的扫描快照（不清理其他目录的快照，用于只扫描单个项目时）
+    ///
+    /// # 参数
+    /// - `source_id` - 数据源 ID（见 `data_sources`）
+    /// - `fresh` - 本次扫描得到的快照（目录名 → 快照）
+    pub fn insert_project_snapshots(&self, source_id: &str, fresh: HashMap<String, ProjectSnapshot>) {
+        if let Ok(mut all) = self.project_snapshots.write() {
+            all.entry(source_id.to_string()).or_default().extend(fresh);
+        }
+    }
+
+    /// 更新项目列表缓存中某个会话的锁定标记
+    ///
+    /// 锁定状态只影响元数据，无需使整个项目缓存失效重新扫描。
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `locked` - 新的锁定状态
+    pub fn set_session_locked(&self, file_path: &str, locked: bool) {
+        self.update_cached_session(file_path, |session| session.locked = locked);
+    }
+
+    /// 更新项目列表缓存中某个会话的草稿标记
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `has_draft` - 是否有草稿
+    pub fn set_session_has_draft(&self, file_path: &str, has_draft: bool) {
+        self.update_cached_session(file_path, |session| session.has_draft = has_draft);
+    }
+
+    /// 更新项目列表缓存中某个会话的「查看后又有新消息」标记
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `updated` - 是否在查看后又被修改
+    pub fn set_session_updated_since_viewed(&self, file_path: &str, updated: bool) {
+        self.update_cached_session(file_path, |session| session.updated_since_viewed = updated);
+    }
+
+    /// 更新项目列表缓存中某个会话的 CCR 别名
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `alias` - 新别名（None 表示已清除）
+    pub fn set_session_alias(&self, file_path: &str, alias: Option<String>) {
+        self.update_cached_session(file_path, |session| session.alias = alias.clone());
+    }
+
+    /// 更新项目列表缓存中某个会话的置顶标记，并重新排列所在项目的会话
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `pinned` - 是否置顶
+    pub fn set_session_pinned(&self, file_path: &str, pinned: bool) {
+        let Some(project_dir) = Path::new(file_path).parent() else {
+            return;
+        };
+        self.update_project_entries(project_dir, |sessions| {
+            for session in sessions.iter_mut().filter(|s| s.file_path == file_path) {
+                session.pinned = pinned;
+            }
+            scanner::sort_sessions(sessions);
+        });
+    }
+
+    /// 更新项目列表缓存中某个项目的置顶标记（缓存只包含默认数据源的项目）
+    ///
+    /// # 参数
+    /// - `project_name` - 项目目录名
+    /// - `pinned` - 是否置顶
+    pub fn set_project_pinned(&self, project_name: &str, pinned: bool) {
+        if let Ok(mut cache) = self.projects.write()
+            && let Some(entry) = cache.as_mut()
+            && let Some(project) = entry.project_mut(project_name)
+        {
+            project.pinned = pinned;
+        }
+    }
+
+    /// 从项目列表缓存中查找某个会话的元数据（缓存为空或找不到会话时返回 None）
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    pub fn get_cached_session(&self, file_path: &str) -> Option<Session> {
+        let cache = self.projects.read().ok()?;
+        cache
+            .as_ref()?
+            .data
+            .iter()
+            .flat_map(|p| p.sessions.iter())
+            .find(|s| s.file_path == file_path)
+            .cloned()
+    }
+
+    /// 就地修改项目列表缓存与单项目会话列表缓存中的某个会话元数据（找不到会话时不做任何事）
+    fn update_cached_session(&self, file_path: &str, update: impl Fn(&mut Session)) {
+        if let Ok(mut cache) = self.projects.write()
+            && let Some(entry) = cache.as_mut()
+            && let Some(session) = entry
+                .data
+                .iter_mut()
+                .flat_map(|p| p.sessions.iter_mut())
+                .find(|s| s.file_path == file_path)
+        {
+            update(session);
+        }
+        if let Ok(mut lists) = self.project_sessions.write()
+            && let Some(session) = lists
+                .values_mut()
+                .flat_map(|(sessions, _)| sessions.iter_mut())
+                .find(|s| s.file_path == file_path)
+        {
+            update(session);
+        }
+    }
+
+    // ======== 会话缓存方法 ========
+
+    /// 获取缓存的会话转换结果（如果缓存仍然有效）
+    ///
+    /// 通过比对文件 mtime 判断缓存是否有效：
+    /// - 如果文件未被修改，直接返回缓存数据
+    /// - 如果文件已被修改（mtime 变化），返回 None 表示需要重新读取
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    ///
+    /// # 返回值
+    /// - `Some(transformed)` - 缓存有效时返回 TransformedSession 的克隆
+    /// - `None` - 缓存无效时返回 None
+    pub fn get_session(&self, file_path: &str) -> Option<TransformedSession> {
+        self.valid_session_entry(file_path).map(|entry| entry.transformed.clone())
+    }
+
+    /// 获取缓存会话的一页 display_messages（缓存有效性判断同 `get_session`）
+    ///
+    /// 只克隆窗口内的消息，大会话分页读取时不必先克隆完整结果。
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `offset` / `limit` - 分页参数，含义见 `TransformedSession::page`
+    pub fn get_session_page(&self, file_path: &str, offset: usize, limit: Option<usize>) -> Option<TransformedSession> {
+        self.valid_session_entry(file_path).map(|entry| entry.transformed.page(offset, limit))
+    }
+
+    /// 取出 mtime 仍与文件一致的会话缓存条目（文件已被修改时移除该条目）
+    fn valid_session_entry(&self, file_path: &str) -> Option<Arc<SessionCacheEntry>> {
+        // 读锁内只取出条目（同时记录访问），stat 与克隆都在锁外进行
+        let entry = self.sessions.read().ok()?.touch(file_path)?;
+
+        // 检查文件是否被外部修改
+        let current_mtime = std::fs::metadata(file_path).ok()?.modified().ok()?;
+
+        if current_mtime == entry.file_mtime {
+            Some(entry)
+        } else {
+            // 文件已被修改，缓存失效（期间已被替换为新条目时不动）
+            if let Ok(mut cache) = self.sessions.write()
+                && cache.entries.get(file_path).is_some_and(|current| Arc::ptr_eq(current, &entry))
+            {
+                cache.remove(file_path);
+            }
+            None
+        }
+    }
+
+    /// 在缓存仍有效的会话上执行只读计算，直接复用预计算的搜索文本
+    ///
+    /// 不记录访问、不移除失效条目，批量遍历（如全局搜索）不会打乱 LRU 顺序。
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `f` - 计算函数，参数依次为 `display_messages`、`search_texts`、`original_texts`
+    ///
+    /// # 返回值
+    /// 缓存未命中或文件已被修改时返回 None
+    pub fn with_cached_texts<R>(
+        &self,
+        file_path: &str,
+        f: impl FnOnce(&[DisplayMessage], &[String], &[String]) -> R,
+    ) -> Option<R> {
+        let entry = self.sessions.read().ok()?.entries.get(file_path).cloned()?;
+        let current_mtime = std::fs::metadata(file_path).ok()?.modified().ok()?;
+        (current_mtime == entry.file_mtime)
+            .then(|| f(&entry.transformed.display_messages, &entry.search_texts, &entry.original_texts))
+    }
+
+    /// 取出会话缓存条目并记录一次访问（不检查 mtime）
+    fn session_entry(&self, file_path: &str) -> Option<Arc<SessionCacheEntry>> {
+        self.sessions.read().ok()?.touch(file_path)
+    }
+
+    /// 更新会话缓存（以写入缓存时的文件 mtime 与大小作为有效性依据）
+    ///
+    /// 读取与转换耗时较长时，应在读取前调用 `file_stamp` 并改用 `set_session_stamped`，
+    /// 以免期间文件被追加后旧内容配上新 mtime 被当作有效缓存。
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `transformed` - 转换后的 TransformedSession
+    /// - `search_texts` - 小写化的搜索文本列表（用于大小写不敏感搜索）
+    /// - `original_texts` - 原始大小写搜索文本列表（用于大小写敏感和正则搜索）
+    pub fn set_session(
+        &self,
+        file_path: &str,
+        transformed: TransformedSession,
+        search_texts: Vec<String>,
+        original_texts: Vec<String>,
+    ) {
+        self.set_session_stamped(file_path, file_stamp(file_path), transformed, search_texts, original_texts);
+    }
+
+    /// 以读取前记录的文件 mtime 与大小更新会话缓存
+    ///
+    /// 如果缓存已满，先淘汰最久未访问的条目；写入后估算总占用超过内存上限时，
+    /// 继续按 LRU 淘汰其他条目直到回到上限以内（刚写入的条目不会被淘汰）。
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `stamp` - 读取文件前由 `file_stamp` 取得的 `(mtime, 大小)`
+    /// - `transformed` / `search_texts` / `original_texts` - 同 `set_session`
+    pub fn set_session_stamped(
+        &self,
+        file_path: &str,
+        stamp: (SystemTime, u64),
+        transformed: TransformedSession,
+        search_texts: Vec<String>,
+        original_texts: Vec<String>,
+    ) {
+        let (file_mtime, file_size) = stamp;
+
+        // 元数据搜索文本由 transformed 直接派生，在此统一生成，调用方无需关心（锁外计算）
+        let meta_texts: Vec<String> = transformed
+            .display_messages
+            .par_iter()
+            .map(|dm| transformer::extract_search_metadata(dm, &transformed.tool_use_map))
+            .collect();
+        let size_bytes = estimate_entry_bytes(&transformed, [&search_texts, &original_texts, &meta_texts]);
+
+        if let Ok(mut cache) = self.sessions.write() {
+            // 如果缓存已满且不是更新现有条目，淘汰最久未访问的条目
+            if cache.entries.len() >= SESSION_CACHE_MAX_ENTRIES
+                && !cache.entries.contains_key(file_path)
+            {
+                cache.evict_lru(file_path);
+            }
+
+            let accessed = cache.clock.fetch_add(1, Ordering::Relaxed) + 1;
+            cache.insert(
+                file_path.to_string(),
+                Arc::new(SessionCacheEntry {
+                    transformed,
+                    search_texts,
+                    original_texts,
+                    meta_texts,
+                    keywords: OnceLock::new(),
+                    file_mtime,
+                    file_size,
+                    last_accessed: AtomicU64::new(accessed),
+                    size_bytes,
+                }),
+            );
+
+            // 超过内存上限：即使条数未满也继续淘汰
+            while cache.over_limit() && cache.evict_lru(file_path) {
+                cache.memory_evictions += 1;
+            }
+            if cache.over_limit() {
+                log::warn!(
+                    "会话缓存条目 {} 估算占用 {} MB，超过内存上限 {} MB",
+                    file_path,
+                    size_bytes / (1024 * 1024),
+                    cache.max_bytes / (1024 * 1024)
+                );
+            }
+        }
+    }
+
+    /// 列出会话缓存的全部条目（不检查 mtime、不记录访问，供一致性自检使用）
+    pub fn session_cache_snapshots(&self) -> Vec<SessionCacheSnapshot> {
+        let Ok(cache) = self.sessions.read() else {
+            return Vec::new();
+        };
+        cache
+            .entries
+            .iter()
+            .map(|(file_path, entry)| SessionCacheSnapshot {
+                file_path: file_path.clone(),
+                file_mtime: entry.file_mtime,
+                file_size: entry.file_size,
+                display_ids: entry.transformed.display_messages.iter().map(|dm| dm.display_id.clone()).collect(),
+            })
+            .collect()
+    }
+
+    /// 获取会话缓存的占用统计
+    pub fn session_cache_stats(&self) -> CacheStats {
+        self.sessions
+            .read()
+            .map(|cache| CacheStats {
+                entries: cache.entries.len(),
+                total_bytes: cache.total_bytes,
+                undo_bytes: cache.undo_bytes,
+                max_bytes: cache.max_bytes,
+                evictions: cache.evictions,
+                memory_evictions: cache.memory_evictions,
+                audit_failures: self.audit_failures.load(Ordering::Relaxed),
+            })
+            .unwrap_or_default()
+    }
+
+    /// 记录一次审计日志写入失败
+    pub fn record_audit_failure(&self) {
+        self.audit_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 获取会话的关键词（首次调用时计算并保存在缓存条目中）
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `top_n` - 返回的关键词数量
+    ///
+    /// # 返回值
+    /// - `Some(keywords)` - 按词频降序排列的关键词
+    /// - `None` - 缓存中没有该会话的数据
+    pub fn session_keywords(&self, file_path: &str, top_n: usize) -> Option<Vec<SessionKeyword>> {
+        let entry = self.session_entry(file_path)?;
+        let ranked = entry
+            .keywords
+            .get_or_init(|| keywords::extract_keywords(&entry.search_texts, keywords::MAX_KEYWORDS));
+        Some(ranked.iter().take(top_n).cloned().collect())
+    }
+
+    /// 使指定会话的缓存失效
+    ///
+    /// 在消息被编辑或删除后调用
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    pub fn invalidate_session(&self, file_path: &str) {
+        if let Ok(mut cache) = self.sessions.write() {
+            cache.remove(file_path);
+        }
+    }
+
+    /// 把 display_id 映射回原始消息的 uuid
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `display_ids` - DisplayMessage 的 display_id 列表（通常是 `search_in_cache` 的结果）
+    /// - `context` - 每条命中额外包含前后各多少条 DisplayMessage 的来源消息
+    ///
+    /// # 返回值
+    /// - `Some(uuids)` - 命中（及上下文）消息的原始 uuid 集合
+    /// - `None` - 缓存中没有该会话的数据
+    pub fn display_source_uuids(&self, file_path: &str, display_ids: &[String], context: usize) -> Option<HashSet<String>> {
+        let entry = self.session_entry(file_path)?;
+        let messages = &entry.transformed.display_messages;
+        let wanted: HashSet<&str> = display_ids.iter().map(String::as_str).collect();
+        let mut uuids = HashSet::new();
+        for (index, dm) in messages.iter().enumerate().filter(|(_, dm)| wanted.contains(dm.display_id.as_str())) {
+            let range = index.saturating_sub(context)..=(index + context).min(messages.len() - 1);
+            uuids.extend(messages[range].iter().map(|m| m.source_uuid.clone()));
+            uuids.insert(dm.source_uuid.clone());
+        }
+        Some(uuids)
+    }
+
+    /// 在缓存的搜索文本上执行搜索，支持 4 种搜索模式
+    ///
+    /// 根据 `case_sensitive` 和 `use_regex` 参数的组合，选择不同的搜索策略：
+    ///
+    /// | use_regex | case_sensitive | 搜索文本        | 方法                  |
+    /// |-----------|----------------|-----------------|----------------------|
+    /// | true      | false          | original_texts  | regex `(?i)pattern`  |
+    /// | true      | true           | original_texts  | regex `pattern`      |
+    /// | false     | true           | original_texts  | memchr::memmem 精确  |
+    /// | false     | false          | search_texts    | memchr::memmem 小写  |
+    ///
+    /// 每条命中消息统计全部（不重叠）匹配次数，并为前 `MAX_SNIPPETS_PER_HIT` 个匹配
+    /// 返回字节区间与前后各 40 个字符的片段，前端无需再扫描全文。
+    ///
+    /// 小数组（< `PARALLEL_THRESHOLD`）使用顺序迭代，大数组使用 rayon 并行迭代。
+    ///
+    /// 查询以 `tool:` / `model:` / `cwd:` 开头时走字段限定路径（`search_meta_field`），
+    /// 只在元数据文本的对应字段上匹配，同样遵循大小写与正则选项。
+    ///
+    /// `filter` 在文本匹配之前按 `display_type` 与时间范围筛选消息（如只搜最近几天的 user / assistant 消息）。
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `query` - 搜索查询词
+    /// - `case_sensitive` - 是否大小写敏感
+    /// - `use_regex` - 是否使用正则表达式模式
+    /// - `filter` - 消息类型与时间范围过滤（见 `SessionSearchFilter`）
+    ///
+    /// # 返回值
+    /// - `Ok(Some(hits))` - 按消息顺序的命中列表
+    /// - `Ok(None)` - 缓存中没有该会话的数据
+    /// - `Err(msg)` - 正则表达式编译失败，msg 为错误描述
+    pub fn search_in_cache(
+        &self,
+        file_path: &str,
+        query: &str,
+        case_sensitive: bool,
+        use_regex: bool,
+        filter: &SessionSearchFilter,
+    ) -> Result<Option<Vec<SearchHit>>, String> {
+        // 读锁内取出条目后即释放，搜索在锁外进行；缓存不存在时返回 Ok(None)
+        let entry = match self.session_entry(file_path) {
+            Some(e) => e,
+            None => return Ok(None),
+        };
+
+        if let Some((field, value)) = SearchField::parse_query(query) {
+            return search_meta_field(&entry, field, value, case_sensitive, use_regex, filter).map(Some);
+        }
+
+        if use_regex {
+            // ---- 正则表达式搜索模式 ----
+            // 根据大小写敏感选项构建正则表达式 pattern
+            let pattern = if case_sensitive {
+                // 大小写敏感：直接使用原始 query 作为 pattern
+                query.to_string()
+            } else {
+                // 大小写不敏感：在 pattern 前加 `(?i)` 修饰符
+                format!("(?i){}", query)
+            };
+
+            // 编译正则表达式，失败时返回 Err
+            let re = regex::Regex::new(&pattern)
+                .map_err(|e| format!("无效正则表达式: {}", e))?;
+
+            // 在 original_texts 上执行正则匹配（保留原始大小写供 regex 处理），find_iter 收集所有匹配区间
+            Ok(Some(collect_search_hits(&entry, &entry.original_texts, filter, |text| {
+                let mut found = re.find_iter(text);
+                let ranges: Vec<(usize, usize)> =
+                    found.by_ref().take(MAX_SNIPPETS_PER_HIT).map(|m| (m.start(), m.end())).collect();
+                (ranges.len() + found.count(), ranges)
+            })))
+        } else {
+            // ---- 字面量搜索 ----
+            // 大小写敏感：needle 不小写化，在 original_texts 上精确匹配；
+            // 大小写不敏感：在预计算的小写化 search_texts 上匹配，needle 也需小写化
+            let (needle, haystacks) = if case_sensitive {
+                (query.to_string(), &entry.original_texts)
+            } else {
+                (query.to_lowercase(), &entry.search_texts)
+            };
+            let finder = memchr::memmem::Finder::new(needle.as_bytes());
+
+            Ok(Some(collect_search_hits(&entry, haystacks, filter, |text| {
+                let mut found = finder.find_iter(text.as_bytes());
+                let ranges: Vec<(usize, usize)> =
+                    found.by_ref().take(MAX_SNIPPETS_PER_HIT).map(|start| (start, start + needle.len())).collect();
+                (ranges.len() + found.count(), ranges)
+            })))
+        }
+    }
+
+    // ======== 会话写锁方法 ========
+
+    /// 获取指定会话文件的写锁
+    ///
+    /// 同一路径的写操作在此排队，直到前一个持有者释放守卫。
+    /// 每次调用时顺带清理已无人持有的锁条目。
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    ///
+    /// # 返回值
+    /// 写锁守卫；在读取原始数据前获取，写回并更新缓存后释放（drop）
+    pub async fn lock_session(&self, file_path: &str) -> tokio::sync::OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self
+                .session_locks
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+            // 清理已无人持有或等待的锁
+            locks.retain(|_, weak| weak.strong_count() > 0);
+
+            match locks.get(file_path).and_then(Weak::upgrade) {
+                Some(lock) => lock,
+                None => {
+                    let lock = Arc::new(tokio::sync::Mutex::new(()));
+                    locks.insert(file_path.to_string(), Arc::downgrade(&lock));
+                    lock
+                }
+            }
+        };
+
+        lock.lock_owned().await
+    }
+
+    // ======== 撤销栈方法 ========
+
+    /// 把操作前的消息快照压入会话的撤销栈
+    ///
+    /// 在写回成功后调用（持有会话写锁），记录此刻的文件 mtime；超过 `MAX_UNDO_STEPS` 时丢弃最早的一步。
+    /// 快照占用计入内存上限，超出时依次淘汰其他会话的缓存条目、其他会话的撤销栈与本会话较早的步骤。
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `description` - 操作描述
+    /// - `messages` - 操作前的完整消息列表
+    pub fn push_undo(&self, file_path: &str, description: String, messages: Vec<Value>) {
+        let Some(file_mtime) = std::fs::metadata(file_path).and_then(|m| m.modified()).ok() else {
+            return;
+        };
+        let size_bytes = messages.iter().map(value_bytes).sum();
+        let mut stacks = self.undo_stacks.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.forget_undo_cleared(file_path);
+        let stack = stacks.entry(file_path.to_string()).or_default();
+        stack.push_back(UndoStep { description, messages, file_mtime, size_bytes });
+        let dropped = if stack.len() > MAX_UNDO_STEPS {
+            stack.pop_front().map_or(0, |step| step.size_bytes)
+        } else {
+            0
+        };
+
+        // 锁顺序：撤销栈 → 会话缓存（会话缓存的写路径不会反过来获取撤销栈的锁）
+        let Ok(mut cache) = self.sessions.write() else {
+            return;
+        };
+        cache.undo_bytes = cache.undo_bytes + size_bytes - dropped;
+        while cache.over_limit() && cache.evict_lru(file_path) {
+            cache.memory_evictions += 1;
+        }
+        let others: Vec<String> = stacks.keys().filter(|key| key.as_str() != file_path).cloned().collect();
+        for key in others {
+            if !cache.over_limit() {
+                break;
+            }
+            if let Some(removed) = stacks.remove(&key) {
+                cache.undo_bytes -= removed.iter().map(|step| step.size_bytes).sum::<usize>();
+            }
+        }
+        if let Some(stack) = stacks.get_mut(file_path) {
+            while cache.over_limit() && stack.len() > 1 {
+                if let Some(step) = stack.pop_front() {
+                    cache.undo_bytes -= step.size_bytes;
+                }
+            }
+        }
+    }
+
+    /// 弹出会话撤销栈的栈顶
+    ///
+    /// 文件 mtime 与压栈时不一致说明文件已被外部程序修改（CCR 自身的写操作要么压栈，要么调用
+    /// `clear_undo`），此时清空该会话的撤销栈并返回错误。
+    /// 撤销写回成功后应调用 `finish_undo`，否则下一次撤销会因写回改变了 mtime 而被拒绝；
+    /// 写回失败时应调用 `restore_undo` 把这一步放回栈顶。
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    ///
+    /// # 返回值
+    /// 栈顶的一步（包含要恢复的消息列表）
+    ///
+    /// # 错误
+    /// 撤销栈为空（被 `clear_undo` 清空时说明原因），或文件在压栈后被修改时返回错误（后者以 `UNDO_CONFLICT_ERROR` 开头）
+    pub fn pop_undo(&self, file_path: &str) -> Result<UndoStep, String> {
+        let mut stacks = self.undo_stacks.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let Some(expected) = stacks.get(file_path).and_then(|stack| stack.back()).map(|step| step.file_mtime) else {
+            let cleared = self.undo_cleared.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            return Err(match cleared.get(file_path) {
+                Some(operation) => format!("没有可撤销的操作：「{}」不支持撤销，之前的撤销记录已清空", operation),
+                None => "没有可撤销的操作".to_string(),
+            });
+        };
+        let current = std::fs::metadata(file_path).and_then(|m| m.modified()).ok();
+        if current != Some(expected) {
+            if let Some(removed) = stacks.remove(file_path) {
+                self.release_undo_bytes(removed.iter().map(|step| step.size_bytes).sum());
+            }
+            return Err(format!(
+                "{}: 会话文件在上次操作后已被外部程序修改，为避免覆盖新内容已拒绝撤销",
+                UNDO_CONFLICT_ERROR
+            ));
+        }
+        let stack = stacks.get_mut(file_path).ok_or("没有可撤销的操作")?;
+        let step = stack.pop_back().ok_or("没有可撤销的操作")?;
+        if stack.is_empty() {
+            stacks.remove(file_path);
+        }
+        self.release_undo_bytes(step.size_bytes);
+        Ok(step)
+    }
+
+    /// 撤销写回成功后，把新栈顶的 mtime 更新为写回后的文件 mtime
+    ///
+    /// 在持有会话写锁、`pop_undo` 的写回完成后调用：这次 mtime 变化来自撤销本身，不是外部修改。
+    /// 把文件恢复为栈顶记录时的内容（如撤回失败的移动）后同样适用。
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    pub fn finish_undo(&self, file_path: &str) {
+        let Some(file_mtime) = std::fs::metadata(file_path).and_then(|m| m.modified()).ok() else {
+            return;
+        };
+        let mut stacks = self.undo_stacks.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(step) = stacks.get_mut(file_path).and_then(|stack| stack.back_mut()) {
+            step.file_mtime = file_mtime;
+        }
+    }
+
+    /// 撤销写回失败时把弹出的一步放回栈顶
+    ///
+    /// 写回失败时文件未被改动（file_guard 原子写入），这一步记录的 mtime 仍然有效。
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `step` - `pop_undo` 弹出的一步
+    pub fn restore_undo(&self, file_path: &str, step: UndoStep) {
+        let size_bytes = step.size_bytes;
+        let mut stacks = self.undo_stacks.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        stacks.entry(file_path.to_string()).or_default().push_back(step);
+        if let Ok(mut cache) = self.sessions.write() {
+            cache.undo_bytes += size_bytes;
+        }
+    }
+
+    /// 不可撤销的写操作写回后清空会话的撤销栈
+    ///
+    /// 这类写操作（修复、重命名写入标题、合并、转码、恢复备份等）不压入快照，旧快照已不能安全恢复；
+    /// 清空后下一次撤销会说明是哪个操作清空了记录，而不是误报为外部修改。
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    /// - `operation` - 操作描述（如「修复会话」）
+    pub fn clear_undo(&self, file_path: &str, operation: &str) {
+        let mut stacks = self.undo_stacks.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(removed) = stacks.remove(file_path) {
+            self.release_undo_bytes(removed.iter().map(|step| step.size_bytes).sum());
+            let mut cleared = self.undo_cleared.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            cleared.insert(file_path.to_string(), operation.to_string());
+        }
+    }
+
+    /// 会话重新有了可撤销的步骤后，不再提示撤销记录被清空
+    fn forget_undo_cleared(&self, file_path: &str) {
+        let mut cleared = self.undo_cleared.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        cleared.remove(file_path);
+    }
+
+    /// 从内存占用中扣减已移出撤销栈的快照
+    fn release_undo_bytes(&self, bytes: usize) {
+        if let Ok(mut cache) = self.sessions.write() {
+            cache.undo_bytes -= bytes;
+        }
+    }
+
+    /// 会话撤销栈中各步的描述（栈顶在前）
+    ///
+    /// # 参数
+    /// - `file_path` - 会话 JSONL 文件的绝对路径
+    pub fn undo_descriptions(&self, file_path: &str) -> Vec<String> {
+        let stacks = self.undo_stacks.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        stacks
+            .get(file_path)
+            .map(|stack| stack.iter().rev().map(|step| step.description.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    // ======== 临时备份注册表方法 ========
+
+    /// 注册一条临时备份记录
+    ///
+    /// 由 `file_guard` 在创建临时备份后调用。
+    pub fn register_temp_backup(&self, entry: TempBackupEntry) {
+        if let Ok(mut backups) = self.temp_backups.write() {
+            backups.push(entry);
+        }
+    }
+
+    /// 移除一条临时备份记录（备份文件被删除后调用）
+    pub fn remove_temp_backup(&self, temp_path: &str) {
+        if let Ok(mut backups) = self.temp_backups.write() {
+            backups.retain(|entry| entry.temp_path != temp_path);
+        }
+    }
+
+    /// 获取所有临时备份记录（供前端展示）
+    ///
+    /// 返回本次应用运行期间所有临时备份的完整列表。
+    pub fn get_all_temp_backups(&self) -> Vec<TempBackupEntry> {
         self.temp_backups
             .read()
             .map(|backups| backups.clone())
             .unwrap_or_default()
     }
+
+    // ======== 项目摘要缓存方法 ========
+
+    /// 获取缓存的项目摘要（如果缓存仍然有效）
+    ///
+    /// # 返回值
+    /// - `Some(summary)` - 缓存有效，`summary` 为 None 表示项目没有摘要文件
+    /// - `None` - 未缓存或已失效（来源文件 mtime 变化，或「无摘要」结果超过 TTL）
+    pub fn get_project_summary(&self, project_path: &str) -> Option<Option<ProjectSummary>> {
+        let cache = self.project_summaries.read().ok()?;
+        let entry = cache.get(project_path)?;
+        let valid = match &entry.source {
+            Some((file_path, mtime)) => std::fs::metadata(file_path)
+                .and_then(|m| m.modified())
+                .is_ok_and(|current| current == *mtime),
+            None => entry.cached_at.elapsed().as_secs() < PROJECT_CACHE_TTL_SECS,
+        };
+        valid.then(|| entry.summary.clone())
+    }
+
+    /// 更新项目摘要缓存
+    ///
+    /// # 参数
+    /// - `project_path` - 项目路径
+    /// - `summary` - 摘要内容；None 表示没有摘要文件
+    /// - `source` - 摘要来源文件及其 mtime；没有摘要文件时为 None
+    pub fn set_project_summary(
+        &self,
+        project_path: &str,
+        summary: Option<ProjectSummary>,
+        source: Option<(PathBuf, SystemTime)>,
+    ) {
+        if let Ok(mut cache) = self.project_summaries.write() {
+            cache.insert(
+                project_path.to_string(),
+                ProjectSummaryCacheEntry {
+                    summary,
+                    source,
+                    cached_at: Instant::now(),
+                },
+            );
+        }
+    }
+}
+
+/// 字段限定搜索：在元数据文本中前缀为 `field` 的行上匹配 `value`
+///
+/// 命中的 `match_count` 为匹配的字段行数；元数据不属于消息的搜索文本，因此不返回片段。
+///
+/// # 错误
+/// 正则表达式编译失败时返回错误
+fn search_meta_field(
+    entry: &SessionCacheEntry,
+    field: SearchField,
+    value: &str,
+    case_sensitive: bool,
+    use_regex: bool,
+    filter: &SessionSearchFilter,
+) -> Result<Vec<SearchHit>, String> {
+    let re = if use_regex {
+        let pattern = if case_sensitive {
+            value.to_string()
+        } else {
+            format!("(?i){}", value)
+        };
+        Some(regex::Regex::new(&pattern).map_err(|e| format!("无效正则表达式: {}", e))?)
+    } else {
+        None
+    };
+    let needle_lower = value.to_lowercase();
+
+    let matches = |meta: &str| {
+        let count = meta
+            .lines()
+            .filter_map(|line| line.strip_prefix(field.prefix()))
+            .filter(|field_value| match &re {
+                Some(re) => re.is_match(field_value),
+                None if case_sensitive => field_value.contains(value),
+                None => field_value.to_lowercase().contains(&needle_lower),
+            })
+            .count();
+        (count, Vec::new())
+    };
+
+    Ok(collect_search_hits(entry, &entry.meta_texts, filter, matches))
+}
+
+/// 在每条消息的搜索文本上执行匹配并组装命中列表
+///
+/// `find` 返回 `(匹配总数, 前若干个匹配的字节区间)`，区间基于传入的 `haystacks`。
+/// 片段从 `original_texts` 截取以保留原始大小写；小写化改变了字节长度时退回 `haystacks` 本身。
+/// 未通过 `filter` 的消息不执行匹配。
+fn collect_search_hits<F>(entry: &SessionCacheEntry, haystacks: &[String], filter: &SessionSearchFilter, find: F) -> Vec<SearchHit>
+where
+    F: Fn(&str) -> (usize, Vec<(usize, usize)>) + Sync,
+{
+    let dm = &entry.transformed.display_messages;
+    let hit = |(i, text): (usize, &String)| {
+        if !filter.accepts(&dm[i]) {
+            return None;
+        }
+        let (match_count, ranges) = find(text);
+        if match_count == 0 {
+            return None;
+        }
+        let original = entry.original_texts.get(i).map(String::as_str).unwrap_or_default();
+        let source = if original.len() == text.len()
+            && ranges.iter().all(|&(start, end)| original.is_char_boundary(start) && original.is_char_boundary(end))
+        {
+            original
+        } else {
+            text.as_str()
+        };
+        let snippets = ranges
+            .into_iter()
+            .map(|(start, end)| SearchSnippet { start, end, preview: global_search::snippet(source, start, end - start) })
+            .collect();
+        Some(SearchHit { display_id: dm[i].display_id.clone(), match_count, snippets })
+    };
+
+    if haystacks.len() < PARALLEL_THRESHOLD {
+        // 小数组：顺序迭代，避免并行化开销
+        haystacks.iter().enumerate().filter_map(hit).collect()
+    } else {
+        // 大数组：rayon 并行迭代，利用多核加速
+        haystacks.par_iter().enumerate().filter_map(hit).collect()
+    }
 }
 
 impl Default for AppCache {
@@ -418,3 +1756,452 @@ impl Default for AppCache {
         Self::new()
     }
 }
+
+/// 文件当前的 mtime 与大小（无法读取时为 `(UNIX_EPOCH, 0)`，对应缓存条目在下次读取时失效）
+pub fn file_stamp(file_path: &str) -> (SystemTime, u64) {
+    let metadata = std::fs::metadata(file_path).ok();
+    let file_mtime = metadata
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    (file_mtime, metadata.map_or(0, |m| m.len()))
+}
+
+/// 估算会话缓存条目的内存占用（字节）
+///
+/// 统计字符串内容与容器/节点的固定开销，不追踪分配器的额外开销，结果偏保守但量级准确。
+/// 计算一遍的开销与 transform 相比可以忽略。
+///
+/// # 参数
+/// - `transformed` - 转换结果（display_messages 与 tool_use_map）
+/// - `texts` - 搜索文本（小写、原始、元数据三份）
+fn estimate_entry_bytes(transformed: &TransformedSession, texts: [&Vec<String>; 3]) -> usize {
+    let messages: usize = transformed.display_messages.iter().map(message_bytes).sum();
+    let tool_uses: usize = transformed
+        .tool_use_map
+        .iter()
+        .map(|(id, info)| string_bytes(id) + std::mem::size_of::<ToolUseInfo>() + info.name.len() + value_bytes(&info.input))
+        .sum();
+    let search: usize = texts.iter().flat_map(|list| list.iter()).map(|t| string_bytes(t)).sum();
+    messages + tool_uses + search
+}
+
+/// 单条 DisplayMessage 的估算占用
+fn message_bytes(dm: &DisplayMessage) -> usize {
+    let strings = [&dm.source_uuid, &dm.display_id, &dm.display_type, &dm.timestamp]
+        .into_iter()
+        .map(|s| s.len())
+        .sum::<usize>()
+        + [&dm.model, &dm.stop_reason, &dm.message_id, &dm.system_label, &dm.plan_source_path, &dm.cwd]
+            .into_iter()
+            .flatten()
+            .map(|s| s.len())
+            .sum::<usize>();
+    std::mem::size_of::<DisplayMessage>()
+        + strings
+        + dm.content.iter().map(value_bytes).sum::<usize>()
+        + dm.block_index_map.len() * std::mem::size_of::<usize>()
+        + dm.usage.as_ref().map_or(0, value_bytes)
+        + dm.tool_use_result.as_ref().map_or(0, value_bytes)
+        + dm.todos.iter().flatten().map(value_bytes).sum::<usize>()
+}
+
+/// String 的估算占用（内容 + 结构体本身）
+fn string_bytes(s: &str) -> usize {
+    std::mem::size_of::<String>() + s.len()
+}
+
+/// JSON 值的估算占用（递归统计字符串、键与节点开销）
+fn value_bytes(value: &Value) -> usize {
+    std::mem::size_of::<Value>()
+        + match value {
+            Value::String(s) => s.len(),
+            Value::Array(items) => items.iter().map(value_bytes).sum(),
+            Value::Object(map) => map.iter().map(|(k, v)| string_bytes(k) + value_bytes(v)).sum(),
+            Value::Null | Value::Bool(_) | Value::Number(_) => 0,
+        }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// 构造一个每条消息带 `text_len` 字节正文的会话
+    fn large_session(messages: usize, text_len: usize) -> Vec<Value> {
+        (0..messages)
+            .map(|i| {
+                json!({
+                    "type": if i % 2 == 0 { "user" } else { "assistant" },
+                    "uuid": format!("u{}", i),
+                    "parentUuid": (i > 0).then(|| format!("u{}", i - 1)),
+                    "timestamp": "2025-01-01T00:00:00Z",
+                    "message": { "role": if i % 2 == 0 { "user" } else { "assistant" }, "content": [{ "type": "text", "text": "x".repeat(text_len) }] }
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_memory_limit_evicts_before_entry_limit() {
+        let (transformed, search, original) = transformer::transform_session(&large_session(200, 10_000));
+        let size = estimate_entry_bytes(&transformed, [&search, &original, &Vec::new()]);
+        // 正文在 content 与两份搜索文本中各有一份
+        assert!(size >= 3 * 200 * 10_000, "估算偏小: {}", size);
+
+        // 上限只容得下两个这样的会话
+        let cache = AppCache::with_memory_limit(size * 5 / 2);
+        for i in 0..3 {
+            cache.set_session(&format!("/nonexistent/{}.jsonl", i), transformed.clone(), search.clone(), original.clone());
+        }
+        let stats = cache.session_cache_stats();
+        assert_eq!((stats.entries, stats.evictions, stats.memory_evictions), (2, 1, 1));
+        assert!(stats.total_bytes <= stats.max_bytes);
+
+        cache.invalidate_session("/nonexistent/2.jsonl");
+        let stats = cache.session_cache_stats();
+        assert_eq!(stats.entries, 1);
+        assert!(stats.total_bytes >= size && stats.total_bytes < 2 * size);
+
+        // 单个会话超过上限时仍保留刚写入的条目
+        let tiny = AppCache::with_memory_limit(1024);
+        tiny.set_session("/nonexistent/a.jsonl", transformed.clone(), search.clone(), original.clone());
+        tiny.set_session("/nonexistent/b.jsonl", transformed, search, original);
+        let stats = tiny.session_cache_stats();
+        assert_eq!((stats.entries, stats.memory_evictions), (1, 1));
+    }
+
+    #[test]
+    fn test_search_and_writes_do_not_block_each_other() {
+        let cache = Arc::new(AppCache::new());
+        let (big, search, original) = transformer::transform_session(&large_session(2000, 2_000));
+        cache.set_session("/nonexistent/big.jsonl", big, search, original);
+        let (small, small_search, small_original) = transformer::transform_session(&large_session(10, 10));
+        let stop = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        // 一个线程持续在大会话上搜索，完成第一轮后通知写入线程开始
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+        let searcher = {
+            let (cache, stop) = (cache.clone(), stop.clone());
+            std::thread::spawn(move || {
+                let mut rounds = 0;
+                loop {
+                    let hits = cache.search_in_cache("/nonexistent/big.jsonl", "xxxx", false, false, &SessionSearchFilter::default()).unwrap().unwrap();
+                    assert_eq!(hits.len(), 2000);
+                    rounds += 1;
+                    if rounds == 1 {
+                        started_tx.send(()).unwrap();
+                    }
+                    if stop.load(Ordering::Relaxed) {
+                        return rounds;
+                    }
+                }
+            })
+        };
+
+        // 另一个线程在搜索进行期间写入、读取、失效其他会话
+        let (tx, rx) = std::sync::mpsc::channel();
+        let writer = {
+            let cache = cache.clone();
+            std::thread::spawn(move || {
+                started_rx.recv().unwrap();
+                for i in 0..200 {
+                    let path = format!("/nonexistent/small-{}.jsonl", i % 5);
+                    cache.set_session(&path, small.clone(), small_search.clone(), small_original.clone());
+                    assert!(cache.session_keywords(&path, 3).is_some());
+                    cache.invalidate_session(&path);
+                }
+                tx.send(()).unwrap();
+            })
+        };
+
+        rx.recv_timeout(std::time::Duration::from_secs(60)).expect("写入线程被阻塞");
+        stop.store(true, Ordering::Relaxed);
+        writer.join().unwrap();
+        assert!(searcher.join().unwrap() > 0);
+        assert_eq!(cache.session_cache_stats().entries, 1);
+    }
+
+    #[test]
+    fn test_display_source_uuids_with_context() {
+        let (transformed, search, original) = transformer::transform_session(&large_session(6, 4));
+        let cache = AppCache::new();
+        cache.set_session("/nonexistent/s.jsonl", transformed.clone(), search, original);
+        let hit: Vec<String> = transformed
+            .display_messages
+            .iter()
+            .filter(|dm| dm.source_uuid == "u3")
+            .map(|dm| dm.display_id.clone())
+            .collect();
+
+        let only = cache.display_source_uuids("/nonexistent/s.jsonl", &hit, 0).unwrap();
+        assert_eq!(only, HashSet::from(["u3".to_string()]));
+        let with_context = cache.display_source_uuids("/nonexistent/s.jsonl", &hit, 1).unwrap();
+        assert_eq!(with_context, HashSet::from(["u2".to_string(), "u3".to_string(), "u4".to_string()]));
+        assert!(cache.display_source_uuids("/nonexistent/other.jsonl", &hit, 1).is_none());
+    }
+
+    #[test]
+    fn test_partial_merge_keeps_older_cached_at() {
+        let project = |name: &str| -> Project {
+            serde_json::from_value(json!({ "name": name, "path": format!("/work/{}", name), "sessions": [] })).unwrap()
+        };
+        let cache = AppCache::new();
+        let projects_dir = Path::new("/data/projects");
+        let warning = |message: &str| ScanWarning {
+            path: "/data/projects/broken".to_string(),
+            kind: crate::models::project::ScanWarningKind::BrokenSymlink,
+            message: message.to_string(),
+        };
+        cache.merge_projects(projects_dir, 0, vec![project("a"), project("b")], 3, vec![warning("first")]);
+        let cached_at = |cache: &AppCache| cache.projects.read().unwrap().as_ref().unwrap().cached_at;
+        let first = Instant::now() - std::time::Duration::from_secs(10);
+        cache.projects.write().unwrap().as_mut().unwrap().cached_at = first;
+
+        // 只重新扫描了第二页：未重新扫描的第一页不续期
+        cache.merge_projects(projects_dir, 2, vec![project("c")], 3, vec![warning("second")]);
+        assert_eq!(cached_at(&cache), first);
+        assert_eq!(cache.cached_projects().len(), 3);
+
+        // 命中时返回扫描警告，同路径的旧警告被新一次扫描替换
+        let warnings = cache.get_projects_page(0, Some(1)).unwrap().warnings;
+        assert_eq!(warnings.iter().map(|w| w.message.as_str()).collect::<Vec<_>>(), ["second"]);
+
+        // 覆盖全部已加载项目的合并刷新缓存时间
+        cache.merge_projects(projects_dir, 0, vec![project("a"), project("b"), project("c")], 3, Vec::new());
+        assert!(cached_at(&cache) > first);
+    }
+
+    #[test]
+    fn test_update_project_session_in_place() {
+        let session = |id: &str, timestamp: &str| -> Session {
+            serde_json::from_value(json!({
+                "id": id,
+                "timestamp": timestamp,
+                "messageCount": 0,
+                "filePath": format!("/data/projects/p/{}.jsonl", id),
+            }))
+            .unwrap()
+        };
+        let project: Project = serde_json::from_value(json!({
+            "name": "p",
+            "path": "/work/p",
+            "sessions": [session("a", "2025-01-02T00:00:00Z"), session("b", "2025-01-01T00:00:00Z")],
+            "sessionCount": 2,
+            "latestTimestamp": "2025-01-02T00:00:00Z",
+        }))
+        .unwrap();
+        let cache = AppCache::new();
+        let empty: Project = serde_json::from_value(json!({ "name": "q", "path": "/work/q", "sessions": [] })).unwrap();
+        cache.merge_projects(Path::new("/data/projects"), 0, vec![project.clone(), empty], 2, Vec::new());
+        cache.set_project_sessions(PathBuf::from("/data/projects/p"), project.sessions.clone());
+
+        let dir = Path::new("/data/projects/p");
+        let updated = SessionChange::Updated {
+            timestamp: "2025-01-03T00:00:00Z".to_string(),
+            file_size: 42,
+            line_count: Some(9),
+            message_count: Some(7),
+        };
+        cache.update_project_session(dir, "b", updated);
+        let page = cache.get_projects_page(0, None).unwrap();
+        let listed = &page.projects[0];
+        assert_eq!(listed.latest_timestamp.as_deref(), Some("2025-01-03T00:00:00Z"));
+        assert_eq!((listed.sessions[0].id.as_str(), listed.sessions[0].file_size, listed.sessions[0].message_count), ("b", Some(42), 7));
+        assert_eq!(cache.get_project_sessions(dir).unwrap()[0].id, "b");
+
+        // 其他数据源的同名目录不影响项目列表缓存
+        cache.update_project_session(Path::new("/other/projects/p"), "a", SessionChange::Removed);
+        assert_eq!(cache.get_projects_page(0, None).unwrap().projects[0].session_count, 2);
+
+        cache.update_project_session(dir, "b", SessionChange::Removed);
+        let listed = &cache.get_projects_page(0, None).unwrap().projects[0];
+        assert_eq!((listed.session_count, listed.latest_timestamp.as_deref()), (1, Some("2025-01-02T00:00:00Z")));
+        assert_eq!(cache.get_project_sessions(dir).unwrap().len(), 1);
+
+        // 会话列表为空的项目同样可以加入新会话
+        let mut added = session("c", "2025-01-04T00:00:00Z");
+        added.file_path = "/data/projects/q/c.jsonl".to_string();
+        cache.update_project_session(Path::new("/data/projects/q"), "c", SessionChange::Added(Box::new(added)));
+        let page = cache.get_projects_page(0, None).unwrap();
+        let listed = page.projects.iter().find(|p| p.name == "q").unwrap();
+        assert_eq!((listed.session_count, listed.latest_timestamp.as_deref()), (1, Some("2025-01-04T00:00:00Z")));
+
+        cache.remove_project(Path::new("/data/projects/q"));
+        let page = cache.get_projects_page(0, None).unwrap();
+        assert_eq!((page.projects.len(), page.total_projects), (1, 1));
+    }
+
+    #[test]
+    fn test_undo_stack_limit_and_conflict() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("session.jsonl");
+        std::fs::write(&file, "{}\n").unwrap();
+        let path = file.to_string_lossy().to_string();
+        let cache = AppCache::new();
+
+        assert!(cache.pop_undo(&path).is_err());
+        for i in 0..MAX_UNDO_STEPS + 2 {
+            cache.push_undo(&path, format!("步骤 {}", i), vec![json!({ "step": i })]);
+        }
+        let descriptions = cache.undo_descriptions(&path);
+        assert_eq!(descriptions.len(), MAX_UNDO_STEPS);
+        assert_eq!(descriptions[0], format!("步骤 {}", MAX_UNDO_STEPS + 1));
+
+        let step = cache.pop_undo(&path).unwrap();
+        assert_eq!(step.messages, vec![json!({ "step": MAX_UNDO_STEPS + 1 })]);
+
+        // 文件在压栈后被修改：拒绝撤销并清空撤销栈
+        let later = SystemTime::now() + std::time::Duration::from_secs(60);
+        std::fs::File::options().write(true).open(&file).unwrap().set_modified(later).unwrap();
+        let error = cache.pop_undo(&path).unwrap_err();
+        assert!(error.starts_with(UNDO_CONFLICT_ERROR), "{}", error);
+        assert!(cache.undo_descriptions(&path).is_empty());
+        assert_eq!(cache.session_cache_stats().undo_bytes, 0);
+    }
+
+    #[test]
+    fn test_consecutive_undos() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("session.jsonl");
+        let path = file.to_string_lossy().to_string();
+        let cache = AppCache::new();
+        let set_mtime = |secs: u64| {
+            let mtime = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+            std::fs::File::options().write(true).open(&file).unwrap().set_modified(mtime).unwrap();
+        };
+
+        // 两次操作各自写回后压栈
+        std::fs::write(&file, "{}\n").unwrap();
+        set_mtime(1_000);
+        cache.push_undo(&path, "步骤 0".to_string(), vec![json!({ "step": 0 })]);
+        set_mtime(2_000);
+        cache.push_undo(&path, "步骤 1".to_string(), vec![json!({ "step": 1 })]);
+
+        // 第一次撤销的写回改变了 mtime，finish_undo 之后第二次撤销不应被判为冲突
+        assert_eq!(cache.pop_undo(&path).unwrap().description, "步骤 1");
+        set_mtime(3_000);
+        cache.finish_undo(&path);
+        assert_eq!(cache.pop_undo(&path).unwrap().description, "步骤 0");
+        assert!(cache.undo_descriptions(&path).is_empty());
+    }
+
+    /// 不可撤销的写操作清空撤销栈后，撤销说明原因而不是报告外部修改；重新压栈后提示消失
+    #[test]
+    fn test_clear_undo_reports_operation() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("session.jsonl");
+        std::fs::write(&file, "{}\n").unwrap();
+        let path = file.to_string_lossy().to_string();
+        let cache = AppCache::new();
+
+        cache.clear_undo(&path, "修复会话");
+        assert_eq!(cache.pop_undo(&path).unwrap_err(), "没有可撤销的操作");
+
+        cache.push_undo(&path, "删除 1 条消息".to_string(), vec![json!({})]);
+        std::fs::write(&file, "{}\n{}\n").unwrap();
+        cache.clear_undo(&path, "修复会话");
+        let err = cache.pop_undo(&path).unwrap_err();
+        assert!(!err.starts_with(UNDO_CONFLICT_ERROR));
+        assert!(err.contains("修复会话"));
+        assert_eq!(cache.sessions.read().unwrap().undo_bytes, 0);
+
+        cache.push_undo(&path, "删除 1 条消息".to_string(), vec![json!({})]);
+        let step = cache.pop_undo(&path).unwrap();
+        cache.restore_undo(&path, step);
+        assert_eq!(cache.undo_descriptions(&path), vec!["删除 1 条消息"]);
+        cache.pop_undo(&path).unwrap();
+        assert_eq!(cache.pop_undo(&path).unwrap_err(), "没有可撤销的操作");
+    }
+
+    #[test]
+    fn test_undo_snapshots_count_toward_memory_limit() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("session.jsonl");
+        std::fs::write(&file, "{}\n").unwrap();
+        let path = file.to_string_lossy().to_string();
+        let snapshot = large_session(20, 1_000);
+        let snapshot_bytes: usize = snapshot.iter().map(value_bytes).sum();
+
+        // 上限容得下两份快照：缓存条目先被淘汰，之后只丢弃较早的步骤
+        let cache = AppCache::with_memory_limit(snapshot_bytes * 5 / 2);
+        let (transformed, search, original) = transformer::transform_session(&snapshot);
+        cache.set_session("/nonexistent/other.jsonl", transformed, search, original);
+        for i in 0..4 {
+            cache.push_undo(&path, format!("步骤 {}", i), snapshot.clone());
+        }
+        let stats = cache.session_cache_stats();
+        assert_eq!(stats.entries, 0);
+        assert_eq!(stats.undo_bytes, snapshot_bytes * 2);
+        assert_eq!(cache.undo_descriptions(&path), vec!["步骤 3", "步骤 2"]);
+
+        cache.pop_undo(&path).unwrap();
+        assert_eq!(cache.session_cache_stats().undo_bytes, snapshot_bytes);
+    }
+
+    #[test]
+    fn test_search_hits_have_snippets() {
+        let text = format!("{}Needle 中 needle {}", "前".repeat(50), "needle ".repeat(6));
+        let messages = vec![json!({
+            "type": "user", "uuid": "u0", "parentUuid": null, "timestamp": "2025-01-01T00:00:00.000Z",
+            "message": { "role": "user", "content": text }
+        })];
+        let (transformed, search, original) = transformer::transform_session(&messages);
+        let cache = AppCache::new();
+        cache.set_session("/nonexistent/s.jsonl", transformed, search, original);
+
+        let hits = cache.search_in_cache("/nonexistent/s.jsonl", "needle", false, false, &SessionSearchFilter::default()).unwrap().unwrap();
+        assert_eq!((hits.len(), hits[0].match_count, hits[0].snippets.len()), (1, 8, MAX_SNIPPETS_PER_HIT));
+        // 片段保留原始大小写，前文按字符截取为 40 个
+        let first = &hits[0].snippets[0];
+        assert_eq!(first.end - first.start, "needle".len());
+        assert!(first.preview.starts_with(&format!("…{}Needle", "前".repeat(40))));
+
+        let hits = cache.search_in_cache("/nonexistent/s.jsonl", "N[a-z]+", true, true, &SessionSearchFilter::default()).unwrap().unwrap();
+        assert_eq!((hits[0].match_count, hits[0].snippets.len()), (1, 1));
+
+        // 按消息类型过滤：只搜 assistant 时 user 消息不命中
+        let only = |t: &str| SessionSearchFilter { types: vec![t.to_string()], ..Default::default() };
+        assert!(cache.search_in_cache("/nonexistent/s.jsonl", "needle", false, false, &only("assistant")).unwrap().unwrap().is_empty());
+        assert_eq!(cache.search_in_cache("/nonexistent/s.jsonl", "needle", false, false, &only("user")).unwrap().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_search_time_range_filter() {
+        // 第 2 条以 +08:00 书写，等于 2025-03-02T00:00:00Z；最后一条时间戳无法解析
+        let timestamps = ["2025-03-01T00:00:00.000Z", "2025-03-02T08:00:00+08:00", "2025-03-03T00:00:00.000Z", "bad"];
+        let messages: Vec<Value> = timestamps
+            .iter()
+            .enumerate()
+            .map(|(i, ts)| json!({
+                "type": "user", "uuid": format!("u{}", i), "parentUuid": null, "timestamp": ts,
+                "message": { "role": "user", "content": "needle" }
+            }))
+            .collect();
+        let (transformed, search, original) = transformer::transform_session(&messages);
+        let ids: Vec<String> = transformed.display_messages.iter().map(|dm| dm.display_id.clone()).collect();
+        assert_eq!(ids.len(), 4);
+        let cache = AppCache::new();
+        cache.set_session("/nonexistent/t.jsonl", transformed, search, original);
+
+        let filter = |after: Option<&str>, before: Option<&str>| {
+            serde_json::from_value::<SessionSearchFilter>(json!({ "types": null, "after": after, "before": before }))
+        };
+        let search = |after: Option<&str>, before: Option<&str>| -> Vec<String> {
+            let filter = filter(after, before).unwrap();
+            let hits = cache.search_in_cache("/nonexistent/t.jsonl", "needle", false, false, &filter).unwrap().unwrap();
+            hits.into_iter().map(|hit| hit.display_id).collect()
+        };
+
+        // 不限时间时时间戳无效的消息也参与匹配
+        assert_eq!(search(None, None), ids);
+        // 边界恰好等于 after / before 时计入，Z 与 +08:00 混用按同一时刻比较
+        assert_eq!(search(Some("2025-03-02T00:00:00Z"), Some("2025-03-03T08:00:00+08:00")), ids[1..3]);
+        assert_eq!(search(Some("2025-03-02T08:00:00.000+08:00"), None), ids[1..3]);
+        assert_eq!(search(None, Some("2025-03-01T00:00:00Z")), ids[..1]);
+        // 差 1 毫秒即不计入
+        assert!(search(Some("2025-03-03T00:00:00.001Z"), None).is_empty());
+        let err = filter(None, Some("昨天")).unwrap_err().to_string();
+        assert!(err.contains("无效的时间"), "{}", err);
+    }
+}
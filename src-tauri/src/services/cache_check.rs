@@ -0,0 +1,222 @@
+//! # 缓存一致性自检
+//!
+//! 排查「显示内容和文件不一致」时，把缓存状态与磁盘状态逐项对比（仅用于诊断，常规路径不执行）：
+//! - **会话缓存**：对每个条目重新 stat 文件比较 mtime 与大小；两者一致时再重新 parse + transform
+//!   一份，与缓存的 display_messages 比较条数与逐条 display_id
+//! - **项目列表缓存**：对每个已加载的项目重新枚举会话文件（一次快速重扫，不读取内容），
+//!   找出缓存中有但磁盘上已不存在、磁盘上有但缓存中缺失的会话，以及修改时间或大小不一致的会话
+//!
+//! `invalidate` 为 true 时，不一致的会话缓存条目被逐个移除；项目列表有任何不一致时整体失效。
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::models::project::Session;
+use crate::services::cache::{AppCache, SessionCacheSnapshot};
+use crate::services::{parser, scanner, transformer};
+
+/// 不一致的类别
+///
+/// 序列化为 camelCase 字符串，对应前端 `CacheInconsistencyKind` 联合类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CacheInconsistencyKind {
+    /// 会话缓存条目对应的文件已不存在
+    SessionFileMissing,
+    /// 会话文件的 mtime 或大小与缓存时不同（缓存已过期，下次读取会自动重载）
+    SessionStale,
+    /// 文件未变，但重新转换的 display_messages 与缓存不同
+    SessionContentMismatch,
+    /// 项目列表缓存中的会话在磁盘上已不存在
+    ProjectSessionMissing,
+    /// 磁盘上的会话不在项目列表缓存中
+    ProjectSessionUnlisted,
+    /// 项目列表缓存中会话的修改时间或大小与磁盘不同
+    ProjectSessionStale,
+}
+
+/// 一处不一致
+///
+/// 对应前端 TypeScript 接口 `CacheInconsistency`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheInconsistency {
+    /// 不一致的类别
+    pub kind: CacheInconsistencyKind,
+    /// 会话文件的绝对路径
+    pub file_path: String,
+    /// 差异说明
+    pub detail: String,
+}
+
+/// 缓存一致性自检报告
+///
+/// 对应前端 TypeScript 接口 `CacheConsistencyReport`。
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheConsistencyReport {
+    /// 检查的会话缓存条目数
+    pub checked_sessions: usize,
+    /// 检查的项目数（项目列表缓存中已加载的项目）
+    pub checked_projects: usize,
+    /// 发现的不一致
+    pub issues: Vec<CacheInconsistency>,
+    /// 移除的会话缓存条目数（未要求失效时为 0）
+    pub invalidated_sessions: usize,
+    /// 是否因不一致使项目列表缓存失效
+    pub invalidated_projects: bool,
+}
+
+/// 对比缓存与磁盘状态
+///
+/// # 参数
+/// - `cache` - AppCache 引用
+/// - `invalidate` - 是否自动失效不一致的缓存
+///
+/// # 返回值
+/// 检查范围、发现的不一致与失效情况
+pub async fn verify(cache: &AppCache, invalidate: bool) -> CacheConsistencyReport {
+    let mut report = CacheConsistencyReport::default();
+
+    let snapshots = cache.session_cache_snapshots();
+    report.checked_sessions = snapshots.len();
+    for snapshot in &snapshots {
+        if let Some(issue) = check_session(snapshot).await {
+            if invalidate {
+                cache.invalidate_session(&snapshot.file_path);
+                report.invalidated_sessions += 1;
+            }
+            report.issues.push(issue);
+        }
+    }
+
+    let projects = cache.cached_projects();
+    report.checked_projects = projects.len();
+    let before = report.issues.len();
+    for project in &projects {
+        check_project_sessions(&project.sessions, &mut report.issues).await;
+    }
+    if invalidate && report.issues.len() > before {
+        cache.invalidate_projects();
+        report.invalidated_projects = true;
+    }
+
+    report
+}
+
+/// 检查单个会话缓存条目
+async fn check_session(snapshot: &SessionCacheSnapshot) -> Option<CacheInconsistency> {
+    let issue = |kind, detail: String| Some(CacheInconsistency { kind, file_path: snapshot.file_path.clone(), detail });
+
+    let Ok(metadata) = tokio::fs::metadata(&snapshot.file_path).await else {
+        return issue(CacheInconsistencyKind::SessionFileMissing, "文件已不存在".to_string());
+    };
+    let mtime = metadata.modified().ok();
+    if mtime != Some(snapshot.file_mtime) || metadata.len() != snapshot.file_size {
+        return issue(
+            CacheInconsistencyKind::SessionStale,
+            format!(
+                "缓存时 {} 字节，当前 {} 字节，mtime {}",
+                snapshot.file_size,
+                metadata.len(),
+                if mtime == Some(snapshot.file_mtime) { "相同" } else { "已变化" }
+            ),
+        );
+    }
+
+    let messages = match parser::read_messages(&snapshot.file_path).await {
+        Ok(messages) => messages,
+        Err(e) => return issue(CacheInconsistencyKind::SessionContentMismatch, format!("重新读取失败: {}", e)),
+    };
+    let (fresh, _, _) = transformer::transform_session(&messages);
+    let fresh_ids: Vec<&str> = fresh.display_messages.iter().map(|dm| dm.display_id.as_str()).collect();
+    compare_display_ids(&snapshot.display_ids, &fresh_ids)
+        .and_then(|detail| issue(CacheInconsistencyKind::SessionContentMismatch, detail))
+}
+
+/// 比较缓存与重新转换的 display_id 序列，不同时返回差异说明
+fn compare_display_ids(cached: &[String], fresh: &[&str]) -> Option<String> {
+    if cached.len() != fresh.len() {
+        return Some(format!("缓存 {} 条显示消息，重新转换 {} 条", cached.len(), fresh.len()));
+    }
+    let index = cached.iter().zip(fresh).position(|(a, b)| a != b)?;
+    Some(format!("第 {} 条显示消息的 display_id 不同：缓存 {}，重新转换 {}", index + 1, cached[index], fresh[index]))
+}
+
+/// 把一个项目缓存的会话列表与重新枚举的会话文件对比
+///
+/// 项目目录取自缓存会话的所在目录；没有会话的项目无法定位目录，跳过。
+/// 缓存中含子 agent 会话时重扫也包含它们，与扫描选项保持一致。
+async fn check_project_sessions(sessions: &[Session], issues: &mut Vec<CacheInconsistency>) {
+    let Some(project_dir) = sessions.first().and_then(|s| Path::new(&s.file_path).parent()) else {
+        return;
+    };
+    let include_agents = sessions.iter().any(|s| s.is_agent);
+    let Ok(files) = scanner::list_session_files(project_dir, include_agents).await else {
+        for session in sessions {
+            issues.push(CacheInconsistency {
+                kind: CacheInconsistencyKind::ProjectSessionMissing,
+                file_path: session.file_path.clone(),
+                detail: "项目目录已无法读取".to_string(),
+            });
+        }
+        return;
+    };
+
+    let cached: HashMap<&str, &Session> =
+        sessions.iter().map(|s| (s.file_path.as_str(), s)).collect();
+    let on_disk: HashSet<PathBuf> = files.into_iter().collect();
+
+    for path in &on_disk {
+        let file_path = path.to_string_lossy();
+        let Some(session) = cached.get(file_path.as_ref()) else {
+            issues.push(CacheInconsistency {
+                kind: CacheInconsistencyKind::ProjectSessionUnlisted,
+                file_path: file_path.to_string(),
+                detail: "磁盘上存在，但不在项目列表缓存中".to_string(),
+            });
+            continue;
+        };
+        let Ok(metadata) = tokio::fs::metadata(path).await else {
+            continue;
+        };
+        let timestamp = metadata.modified().map(scanner::system_time_to_iso8601).ok();
+        if timestamp.as_deref() != Some(session.timestamp.as_str()) || session.file_size.is_some_and(|size| size != metadata.len()) {
+            issues.push(CacheInconsistency {
+                kind: CacheInconsistencyKind::ProjectSessionStale,
+                file_path: file_path.to_string(),
+                detail: format!(
+                    "缓存 {} / {} 字节，磁盘 {} / {} 字节",
+                    session.timestamp,
+                    session.file_size.unwrap_or_default(),
+                    timestamp.unwrap_or_default(),
+                    metadata.len()
+                ),
+            });
+        }
+    }
+    for session in sessions {
+        if !on_disk.contains(Path::new(&session.file_path)) {
+            issues.push(CacheInconsistency {
+                kind: CacheInconsistencyKind::ProjectSessionMissing,
+                file_path: session.file_path.clone(),
+                detail: "项目列表缓存中存在，但磁盘上已不存在".to_string(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_display_ids() {
+        let cached = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(compare_display_ids(&cached, &["a", "b"]), None);
+        assert!(compare_display_ids(&cached, &["a"]).unwrap().contains("缓存 2 条"));
+        assert!(compare_display_ids(&cached, &["a", "c"]).unwrap().contains("第 2 条"));
+    }
+}
@@ -329,3 +329,22 @@ pub fn classify(msg: &Value) -> Classification {
     // P7：默认 → 普通用户消息
     Classification::User
 }
+
+/// 是否为统计 / 导出中的噪音消息
+///
+/// 压缩摘要与计划以外的系统注入（附件、system 条目、CLI 注入）大多重复已有内容，
+/// 计入消息数或字数会使数据虚高；计划消息承载用户确认过的方案，不视为噪音。
+/// 计划消息以是否引用源会话（`plan_source_path`）识别，不依赖显示标签文案。
+///
+/// 统计（DisplayMessage）与导出（原始消息分类）共用本判断，保证两边口径一致。
+///
+/// # 参数
+/// - `display_type` - 显示类型（与 `DisplayMessage.display_type` 相同）
+/// - `plan_source_path` - 计划消息引用的源会话路径；非计划消息为 None
+pub fn is_noise(display_type: &str, plan_source_path: Option<&str>) -> bool {
+    match display_type {
+        "compact_summary" => true,
+        "system" => plan_source_path.is_none(),
+        _ => false,
+    }
+}
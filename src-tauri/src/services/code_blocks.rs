@@ -0,0 +1,356 @@
+//! # 会话代码块提取
+//!
+//! 从 assistant 消息的 text 块中解析 Markdown 围栏代码块，供前端「代码片段」面板
+//! 预览并批量保存为文件。相同内容的代码块按内容哈希去重，只保留首次出现的位置。
+//!
+//! ## 围栏解析
+//! 按 CommonMark 的围栏规则逐行解析：
+//! - 开始围栏：缩进不超过 3 个空格，至少 3 个连续的 `` ` `` 或 `~`，其后为信息串（首个单词为语言）；
+//!   反引号围栏的信息串不能含反引号
+//! - 结束围栏：与开始围栏字符相同、长度不小于开始围栏、其后只有空白
+//!
+//! 因此 ```` ```` ```` 围栏内的 ```` ``` ```` 行、带语言标记的 ```` ```python ```` 行都属于内容，
+//! 不会提前结束外层代码块。未闭合的围栏一直延续到文本末尾（标记为 `closed: false`）。
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+
+use crate::models::display::TransformedSession;
+use crate::utils::filename;
+
+/// 保存文件名的前缀（`snippet_<n>.<ext>`）
+const SNIPPET_PREFIX: &str = "snippet_";
+
+/// 会话中的单个代码块
+///
+/// 对应前端 TypeScript 接口 `CodeBlock`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeBlock {
+    /// 内容哈希（16 位十六进制），同时作为保存时的块 ID
+    pub id: String,
+    /// 围栏信息串中的语言标记（没有时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// 代码内容（不含围栏行）
+    pub content: String,
+    /// 行数
+    pub line_count: usize,
+    /// 首次出现的消息 display_id
+    pub display_id: String,
+    /// 在整个会话中出现的次数
+    pub occurrences: usize,
+    /// 围栏是否正常闭合（未闭合时内容延续到该文本块末尾）
+    pub closed: bool,
+}
+
+/// 从单段文本中解析出的围栏代码块
+#[derive(Debug, PartialEq, Eq)]
+struct FencedBlock {
+    /// 语言标记
+    language: Option<String>,
+    /// 代码内容
+    content: String,
+    /// 是否闭合
+    closed: bool,
+}
+
+/// 解析过程中尚未闭合的围栏
+struct OpenFence<'a> {
+    /// 围栏字符（`` ` `` 或 `~`）
+    fence_char: char,
+    /// 围栏长度
+    fence_len: usize,
+    /// 开始围栏的缩进（内容行去掉同样多的前导空格）
+    indent: usize,
+    /// 语言标记
+    language: Option<String>,
+    /// 已收集的内容行
+    lines: Vec<&'a str>,
+}
+
+impl OpenFence<'_> {
+    /// 结束收集，生成代码块
+    fn finish(self, closed: bool) -> FencedBlock {
+        FencedBlock {
+            language: self.language,
+            content: self.lines.join("\n"),
+            closed,
+        }
+    }
+}
+
+/// 提取会话中 assistant 文本里的所有代码块
+///
+/// # 参数
+/// - `session` - 已转换的会话数据
+///
+/// # 返回值
+/// 按首次出现顺序排列、按内容去重的代码块列表（空代码块不计入）
+pub fn extract_code_blocks(session: &TransformedSession) -> Vec<CodeBlock> {
+    let mut blocks: Vec<CodeBlock> = Vec::new();
+    // 内容哈希 → 在 blocks 中的下标
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for msg in session.display_messages.iter().filter(|m| m.display_type == "assistant") {
+        for block in &msg.content {
+            if block.get("type").and_then(|v| v.as_str()) != Some("text") {
+                continue;
+            }
+            let Some(text) = block.get("text").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            for fenced in parse_fenced_blocks(text) {
+                if fenced.content.trim().is_empty() {
+                    continue;
+                }
+                let id = content_hash(&fenced.content);
+                if let Some(&i) = index.get(&id) {
+                    blocks[i].occurrences += 1;
+                    continue;
+                }
+                index.insert(id.clone(), blocks.len());
+                blocks.push(CodeBlock {
+                    id,
+                    language: fenced.language,
+                    line_count: fenced.content.lines().count(),
+                    content: fenced.content,
+                    display_id: msg.display_id.clone(),
+                    occurrences: 1,
+                    closed: fenced.closed,
+                });
+            }
+        }
+    }
+
+    blocks
+}
+
+/// 把选中的代码块写成 `snippet_<n>.<ext>` 文件
+///
+/// 编号从 1 开始，以 `create_new` 创建文件，跳过目标目录中已存在的同名文件，不覆盖任何文件。
+///
+/// # 参数
+/// - `blocks` - 会话中的全部代码块（`extract_code_blocks` 的结果）
+/// - `block_ids` - 要保存的块 ID，按此顺序编号
+/// - `target_dir` - 目标目录（不存在时自动创建）
+///
+/// # 返回值
+/// 写入的文件路径列表
+///
+/// # 错误
+/// 块 ID 不存在、目标目录创建失败或文件写入失败时返回错误
+pub async fn save_code_blocks(blocks: &[CodeBlock], block_ids: &[String], target_dir: &Path) -> Result<Vec<String>, String> {
+    let selected = block_ids
+        .iter()
+        .map(|id| {
+            blocks
+                .iter()
+                .find(|b| &b.id == id)
+                .ok_or_else(|| format!("代码块不存在: {}", id))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    tokio::fs::create_dir_all(filename::to_long_path(target_dir))
+        .await
+        .map_err(|e| format!("创建目标目录失败: {}", e))?;
+
+    let mut saved = Vec::with_capacity(selected.len());
+    let mut n = 0;
+    for block in selected {
+        let ext = extension_for(block.language.as_deref());
+        // create_new 原子地占用文件名：同名文件已存在（含并发写入方刚创建的）时换下一个编号
+        let (file_path, mut file) = loop {
+            n += 1;
+            let candidate: PathBuf = target_dir.join(format!("{}{}.{}", SNIPPET_PREFIX, n, ext));
+            match tokio::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(filename::to_long_path(&candidate))
+                .await
+            {
+                Ok(file) => break (candidate, file),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(format!("创建代码片段文件失败: {}", e)),
+            }
+        };
+        let mut content = block.content.clone();
+        content.push('\n');
+        file.write_all(content.as_bytes())
+            .await
+            .map_err(|e| format!("写入代码片段失败: {}", e))?;
+        saved.push(file_path.to_string_lossy().to_string());
+    }
+    Ok(saved)
+}
+
+/// 按围栏规则解析文本中的代码块（见模块文档）
+fn parse_fenced_blocks(text: &str) -> Vec<FencedBlock> {
+    let mut blocks = Vec::new();
+    let mut open: Option<OpenFence> = None;
+
+    for line in text.lines() {
+        match open.take() {
+            Some(fence) if is_closing_fence(line, fence.fence_char, fence.fence_len) => {
+                blocks.push(fence.finish(true));
+            }
+            Some(mut fence) => {
+                fence.lines.push(strip_indent(line, fence.indent));
+                open = Some(fence);
+            }
+            None => open = opening_fence(line),
+        }
+    }
+
+    if let Some(fence) = open {
+        blocks.push(fence.finish(false));
+    }
+    blocks
+}
+
+/// 识别开始围栏（不是开始围栏时返回 None）
+fn opening_fence(line: &str) -> Option<OpenFence<'_>> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let fence_char = rest.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let fence_len = rest.len() - rest.trim_start_matches(fence_char).len();
+    if fence_len < 3 {
+        return None;
+    }
+    let info = rest[fence_len..].trim();
+    if fence_char == '`' && info.contains('`') {
+        return None;
+    }
+    Some(OpenFence {
+        fence_char,
+        fence_len,
+        indent,
+        language: info.split_whitespace().next().map(str::to_string),
+        lines: Vec::new(),
+    })
+}
+
+/// 是否为与开始围栏匹配的结束围栏
+fn is_closing_fence(line: &str, fence_char: char, fence_len: usize) -> bool {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return false;
+    }
+    let rest = trimmed.trim_start_matches(fence_char);
+    trimmed.len() - rest.len() >= fence_len && rest.trim().is_empty()
+}
+
+/// 去掉内容行开头不超过 `indent` 个空格（与开始围栏的缩进对齐）
+fn strip_indent(line: &str, indent: usize) -> &str {
+    let spaces = line.len() - line.trim_start_matches(' ').len();
+    &line[spaces.min(indent)..]
+}
+
+/// 代码内容的哈希（16 位十六进制）
+fn content_hash(content: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// 由语言标记确定文件扩展名
+///
+/// 常见语言使用惯用扩展名；其他由字母数字组成的标记直接作为扩展名；没有标记时为 `txt`。
+fn extension_for(language: Option<&str>) -> String {
+    let Some(language) = language.map(str::to_lowercase) else {
+        return "txt".to_string();
+    };
+    let ext = match language.as_str() {
+        "python" | "py" => "py",
+        "rust" | "rs" => "rs",
+        "typescript" | "ts" => "ts",
+        "javascript" | "js" | "node" => "js",
+        "bash" | "sh" | "shell" | "zsh" | "console" => "sh",
+        "powershell" | "ps1" | "pwsh" => "ps1",
+        "batch" | "bat" | "cmd" => "bat",
+        "csharp" | "c#" | "cs" => "cs",
+        "cpp" | "c++" | "cxx" => "cpp",
+        "golang" | "go" => "go",
+        "kotlin" | "kt" => "kt",
+        "ruby" | "rb" => "rb",
+        "yaml" | "yml" => "yaml",
+        "markdown" | "md" => "md",
+        "diff" | "patch" => "diff",
+        "text" | "plaintext" | "plain" => "txt",
+        other if !other.is_empty() && other.len() <= 10 && other.chars().all(|c| c.is_ascii_alphanumeric()) => other,
+        _ => "txt",
+    };
+    ext.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fenced_blocks_nested_and_unclosed() {
+        let text = concat!(
+            "说明\n",
+            "````markdown\n",
+            "```python\n",
+            "print(1)\n",
+            "```\n",
+            "````\n",
+            "  ```rust ignore\n",
+            "  fn main() {}\n",
+            "    let x = 1;\n",
+            "  ``` \n",
+            "``not a fence\n",
+            "~~~\n",
+            "tail\n",
+        );
+        let blocks = parse_fenced_blocks(text);
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].language.as_deref(), Some("markdown"));
+        assert_eq!(blocks[0].content, "```python\nprint(1)\n```");
+        assert!(blocks[0].closed);
+        assert_eq!(blocks[1].language.as_deref(), Some("rust"));
+        assert_eq!(blocks[1].content, "fn main() {}\n  let x = 1;");
+        assert_eq!(blocks[2], FencedBlock { language: None, content: "tail".to_string(), closed: false });
+    }
+
+    #[test]
+    fn test_extension_for() {
+        assert_eq!(extension_for(Some("Python")), "py");
+        assert_eq!(extension_for(Some("tsx")), "tsx");
+        assert_eq!(extension_for(Some("c++")), "cpp");
+        assert_eq!(extension_for(Some("../x")), "txt");
+        assert_eq!(extension_for(None), "txt");
+    }
+
+    #[tokio::test]
+    async fn test_save_code_blocks_skips_existing_files() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        std::fs::write(dir.join("snippet_1.py"), "keep").unwrap();
+        let block = |id: &str, content: &str| CodeBlock {
+            id: id.to_string(),
+            language: Some("python".to_string()),
+            content: content.to_string(),
+            line_count: 1,
+            display_id: "d1".to_string(),
+            occurrences: 1,
+            closed: true,
+        };
+        let blocks = [block("a", "print(1)"), block("b", "print(2)")];
+
+        let saved = save_code_blocks(&blocks, &["b".to_string(), "a".to_string()], dir).await.unwrap();
+        let names: Vec<_> = saved.iter().map(|p| Path::new(p).file_name().unwrap().to_string_lossy().to_string()).collect();
+        assert_eq!(names, ["snippet_2.py", "snippet_3.py"]);
+        assert_eq!(std::fs::read_to_string(dir.join("snippet_1.py")).unwrap(), "keep");
+        assert_eq!(std::fs::read_to_string(dir.join("snippet_2.py")).unwrap(), "print(2)\n");
+    }
+}
@@ -0,0 +1,178 @@
+//! # 命令级请求日志
+//!
+//! 记录主要 Tauri command 的调用：名称、参数摘要、耗时与结果状态，写入内存环形缓冲
+//! （最多 `MAX_ENTRIES` 条，超出时丢弃最旧的记录），由 `get_command_trace` 返回给前端，
+//! 用于排查真实用户机器上哪些命令最慢。耗时超过 `SLOW_THRESHOLD` 的命令额外写 warn 日志。
+//!
+//! ## 接入方式
+//! command 只做一层薄包装，原有逻辑放在同名 `_inner` 函数中：
+//!
+//! ```ignore
+//! command_trace::traced("delete_message", command_trace::path_arg(&session_file_path), delete_message_inner(...)).await
+//! ```
+//!
+//! 返回后仍在后台继续工作的命令（如流式读取会话）改用 `CommandSpan`：开始时创建，
+//! 随后台任务移动，在工作真正结束时调用 `finish`，记录的耗时覆盖整个过程。
+//!
+//! 参数摘要中的路径只保留文件名（`path_arg`），避免把用户名、项目路径写进日志。
+//! 正常路径的额外开销是两次 `Instant::now` 与一次短暂的互斥锁，为微秒级。
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+
+/// 环形缓冲保留的最大记录数
+const MAX_ENTRIES: usize = 500;
+
+/// 慢命令阈值：超过该耗时的命令写 warn 日志
+const SLOW_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// 调用记录的环形缓冲
+static TRACE: LazyLock<Mutex<VecDeque<CommandTraceEntry>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(MAX_ENTRIES)));
+
+/// 单次 command 调用记录
+///
+/// 对应前端 TypeScript 接口 `CommandTraceEntry`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandTraceEntry {
+    /// command 名称
+    pub command: &'static str,
+    /// 参数摘要（路径已脱敏为文件名）
+    pub args: String,
+    /// 开始时间（RFC 3339，UTC）
+    pub started_at: String,
+    /// 耗时（毫秒，保留小数）
+    pub duration_ms: f64,
+    /// 是否成功返回
+    pub ok: bool,
+}
+
+/// 执行 command 逻辑并记录调用
+///
+/// # 参数
+/// - `command` - command 名称
+/// - `args` - 参数摘要，路径参数应先经 `path_arg` 脱敏
+/// - `future` - command 的原有逻辑
+///
+/// # 返回值
+/// 原样返回 `future` 的结果
+pub async fn traced<T, F>(command: &'static str, args: String, future: F) -> Result<T, String>
+where
+    F: Future<Output = Result<T, String>>,
+{
+    let span = CommandSpan::start(command, args);
+    let result = future.await;
+    span.finish(result.is_ok());
+    result
+}
+
+/// 一次进行中的 command 调用：`start` 时计时，`finish` 时写入记录
+///
+/// 未调用 `finish` 就被丢弃时（提前返回错误、任务被取消）按失败记录，保证每次调用都留下一条记录。
+pub struct CommandSpan {
+    command: &'static str,
+    args: String,
+    started_at: DateTime<Utc>,
+    start: Instant,
+    ok: bool,
+}
+
+impl CommandSpan {
+    /// 开始计时
+    ///
+    /// # 参数
+    /// - `command` - command 名称
+    /// - `args` - 参数摘要，路径参数应先经 `path_arg` 脱敏
+    pub fn start(command: &'static str, args: String) -> Self {
+        Self {
+            command,
+            args,
+            started_at: Utc::now(),
+            start: Instant::now(),
+            ok: false,
+        }
+    }
+
+    /// 结束计时并写入记录
+    ///
+    /// # 参数
+    /// - `ok` - command 是否成功完成
+    pub fn finish(mut self, ok: bool) {
+        self.ok = ok;
+    }
+}
+
+impl Drop for CommandSpan {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        if elapsed >= SLOW_THRESHOLD {
+            log::warn!("慢命令 {}({}) 耗时 {} ms", self.command, self.args, elapsed.as_millis());
+        }
+        record(CommandTraceEntry {
+            command: self.command,
+            args: std::mem::take(&mut self.args),
+            started_at: self.started_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+            duration_ms: elapsed.as_secs_f64() * 1000.0,
+            ok: self.ok,
+        });
+    }
+}
+
+/// 把路径参数脱敏为文件名（无文件名部分时返回空字符串）
+pub fn path_arg(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// 按时间顺序返回缓冲中的全部记录（最旧的在前）
+pub fn entries() -> Vec<CommandTraceEntry> {
+    TRACE
+        .lock()
+        .map(|trace| trace.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// 追加一条记录，缓冲已满时丢弃最旧的
+fn record(entry: CommandTraceEntry) {
+    if let Ok(mut trace) = TRACE.lock() {
+        if trace.len() >= MAX_ENTRIES {
+            trace.pop_front();
+        }
+        trace.push_back(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_traced_records_and_caps_buffer() {
+        assert_eq!(path_arg("/home/alice/.claude/projects/-w-app/s.jsonl"), "s.jsonl");
+
+        let result: Result<(), String> = traced("test_failing", path_arg("/a/b.jsonl"), async { Err("x".to_string()) }).await;
+        assert!(result.is_err());
+        let last = entries().pop().unwrap();
+        assert_eq!((last.command, last.args.as_str(), last.ok), ("test_failing", "b.jsonl", false));
+
+        // span 在 finish 时按给定结果记录，未 finish 就丢弃时按失败记录
+        CommandSpan::start("test_span", "finished".to_string()).finish(true);
+        drop(CommandSpan::start("test_span", "dropped".to_string()));
+        let spans: Vec<_> = entries().into_iter().rev().take(2).map(|e| (e.args, e.ok)).collect();
+        assert_eq!(spans, [("dropped".to_string(), false), ("finished".to_string(), true)]);
+
+        for _ in 0..MAX_ENTRIES + 10 {
+            let _ = traced("test_ok", String::new(), async { Ok(()) }).await;
+        }
+        assert_eq!(entries().len(), MAX_ENTRIES);
+    }
+}
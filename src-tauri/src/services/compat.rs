@@ -0,0 +1,169 @@
+//! # 版本兼容性统计服务
+//!
+//! 统计 Claude Code 写入 JSONL 时附带的 `version` 字段，帮助排障和决定分类器需兼容的旧格式：
+//! - **单会话**：会话中出现过的所有 version 值及各自的消息条数
+//! - **数据目录**：按会话末条消息的 version 汇总各版本的会话分布
+//!
+//! 版本号按数字段比较（`2.1.10` > `2.1.9`），非数字段按字符串比较。
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::models::message::SessionMessage;
+use crate::models::project::Project;
+
+/// 单个 version 值在会话中出现的条数
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionCount {
+    /// Claude Code 版本号
+    pub version: String,
+    /// 带该版本号的消息条数
+    pub count: usize,
+}
+
+/// 单个会话的版本统计
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionVersionStats {
+    /// 出现过的版本及条数（新版本在前）
+    pub versions: Vec<VersionCount>,
+    /// 没有 version 字段的条目数（元数据条目、原样保留的坏行等）
+    pub unversioned: usize,
+}
+
+/// 单个版本在整个数据目录中的会话分布
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionDistribution {
+    /// Claude Code 版本号
+    pub version: String,
+    /// 末条消息为该版本的会话数
+    pub session_count: usize,
+    /// 涉及的项目数
+    pub project_count: usize,
+    /// 这些会话中最新的文件修改时间（ISO 8601）
+    pub last_active: String,
+}
+
+/// 数据目录的版本兼容性报告
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataCompatReport {
+    /// 会话总数
+    pub total_sessions: usize,
+    /// 无法确定版本的会话数（尾部没有任何带 version 字段的消息）
+    pub unknown_version_sessions: usize,
+    /// 各版本的会话分布（新版本在前）
+    pub versions: Vec<VersionDistribution>,
+}
+
+/// 统计会话消息中出现过的 version 值及各自条数
+///
+/// # 参数
+/// - `messages` - 会话的全部消息
+///
+/// # 返回值
+/// 版本统计，版本按新到旧排列
+pub fn count_versions(messages: &[SessionMessage]) -> SessionVersionStats {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut unversioned = 0;
+
+    for msg in messages {
+        match msg.get("version").and_then(|v| v.as_str()) {
+            Some(version) => *counts.entry(version).or_insert(0) += 1,
+            None => unversioned += 1,
+        }
+    }
+
+    let mut versions: Vec<VersionCount> = counts
+        .into_iter()
+        .map(|(version, count)| VersionCount {
+            version: version.to_string(),
+            count,
+        })
+        .collect();
+    versions.sort_by(|a, b| compare_versions(&b.version, &a.version));
+
+    SessionVersionStats {
+        versions,
+        unversioned,
+    }
+}
+
+/// 按会话的 `version`（末条消息版本）汇总整个数据目录的版本分布
+///
+/// # 参数
+/// - `projects` - 扫描得到的全部项目
+///
+/// # 返回值
+/// 数据兼容性报告，版本按新到旧排列
+pub fn compat_report(projects: &[Project]) -> DataCompatReport {
+    // version → (会话数, 项目名集合, 最新修改时间)
+    let mut groups: HashMap<&str, (usize, HashSet<&str>, &str)> = HashMap::new();
+    let mut total_sessions = 0;
+    let mut unknown_version_sessions = 0;
+
+    for project in projects {
+        for session in &project.sessions {
+            total_sessions += 1;
+            let Some(version) = session.version.as_deref() else {
+                unknown_version_sessions += 1;
+                continue;
+            };
+
+            let group = groups.entry(version).or_insert((0, HashSet::new(), ""));
+            group.0 += 1;
+            group.1.insert(project.name.as_str());
+            // ISO 8601 字符串可直接按字典序比较
+            if session.timestamp.as_str() > group.2 {
+                group.2 = session.timestamp.as_str();
+            }
+        }
+    }
+
+    let mut versions: Vec<VersionDistribution> = groups
+        .into_iter()
+        .map(|(version, (session_count, projects, last_active))| VersionDistribution {
+            version: version.to_string(),
+            session_count,
+            project_count: projects.len(),
+            last_active: last_active.to_string(),
+        })
+        .collect();
+    versions.sort_by(|a, b| compare_versions(&b.version, &a.version));
+
+    DataCompatReport {
+        total_sessions,
+        unknown_version_sessions,
+        versions,
+    }
+}
+
+/// 比较两个版本号
+///
+/// 按 `.` 和 `-` 拆分后逐段比较：两段都是数字时按数值比较，否则按字符串比较；
+/// 前缀相同时段数多的更大。
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut left = a.split(['.', '-']);
+    let mut right = b.split(['.', '-']);
+
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => {
+                let ord = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    _ => x.cmp(y),
+                };
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+        }
+    }
+}
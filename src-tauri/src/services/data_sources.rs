@@ -0,0 +1,367 @@
+//! # 多数据源服务
+//!
+//! 除默认的 `~/.claude/` 外，用户可以登记额外的 Claude 数据目录（其他机器同步来的副本、
+//! WSL 中的 `~/.claude` 等），CCR 同时读取并合并展示。
+//!
+//! - 额外数据源持久化在 `~/.mo/CCR/data-sources.json`，默认数据源不写入该文件、始终存在
+//! - 扫描时可指定单个数据源 ID，或以 `ALL_SOURCES_ID` 扫描全部数据源后合并；
+//!   额外数据源的项目在 `Project.source` 中标明来源 ID，默认数据源的项目该字段为空
+//! - `file_guard` 的路径验证认可全部数据源目录（见 `source_roots`）
+//!
+//! 未配置额外数据源时行为与单目录完全一致。
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::project::{Project, ProjectPage};
+use crate::services::archive;
+use crate::services::cache::AppCache;
+use crate::services::file_guard;
+use crate::services::scanner::{self, ScanOptions};
+use crate::utils::{path, platform};
+
+/// 默认数据源（`~/.claude/`）的 ID
+pub const DEFAULT_SOURCE_ID: &str = "default";
+
+/// 扫描时表示「全部数据源」的特殊 ID
+pub const ALL_SOURCES_ID: &str = "all";
+
+/// 数据源配置文件名（位于 `~/.mo/CCR/`）
+const DATA_SOURCES_FILE: &str = "data-sources.json";
+
+/// 串行化配置文件的写入
+static WRITE_LOCK: LazyLock<tokio::sync::Mutex<()>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+/// 一个 Claude 数据目录
+///
+/// 对应前端 TypeScript 接口 `DataSource`。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataSource {
+    /// 数据源 ID（`DEFAULT_SOURCE_ID` 保留给 `~/.claude/`）
+    pub id: String,
+    /// 显示名称
+    pub name: String,
+    /// Claude 数据目录的绝对路径（其下应有 `projects/`）
+    pub path: String,
+}
+
+/// 读取全部数据源：默认数据源在前，其后是配置文件中的额外数据源
+///
+/// # 错误
+/// 无法确定主目录，或配置文件存在但读取、解析失败时返回错误
+pub async fn load_sources() -> Result<Vec<DataSource>, String> {
+    let mut sources = vec![default_source()?];
+    let file_path = sources_path()?;
+    if file_path.exists() {
+        let content = tokio::fs::read_to_string(&file_path)
+            .await
+            .map_err(|e| format!("读取数据源配置失败: {}", e))?;
+        let extra: Vec<DataSource> =
+            serde_json::from_str(&content).map_err(|e| format!("解析数据源配置失败: {}", e))?;
+        sources.extend(extra);
+    }
+    Ok(sources)
+}
+
+/// 校验并保存额外数据源（整体替换）
+///
+/// # 参数
+/// - `extra` - 额外数据源列表，不含默认数据源
+///
+/// # 返回值
+/// 保存后的全部数据源（含默认数据源）
+///
+/// 数据源目录会加入 `file_guard` 的可写范围，因此只接受真正的 Claude 数据目录：
+/// 目录下必须有 `projects/`，且不能是根目录或主目录及其祖先。
+/// 保存的是规范化后的绝对路径（解析符号链接与 `..`），与路径验证时的比较方式一致。
+///
+/// # 错误
+/// ID 为空、重复或使用保留 ID，路径不是 Claude 数据目录、范围过大或与其他数据源重复，以及写入失败时返回错误
+pub async fn save_sources(extra: Vec<DataSource>) -> Result<Vec<DataSource>, String> {
+    let default = default_source()?;
+    let home = platform::home_dir().ok_or_else(|| "无法获取用户主目录".to_string())?;
+    let extra = validate_extra(extra, Path::new(&default.path), &home)?;
+
+    let _guard = WRITE_LOCK.lock().await;
+    let file_path = sources_path()?;
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("创建 CCR 配置目录失败: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(&extra)
+        .map_err(|e| format!("序列化数据源配置失败: {}", e))?;
+    // 原子写入：`source_roots` 随时可能在路径验证中读取该文件
+    file_guard::write_atomic(&file_path.to_string_lossy(), content.as_bytes())
+        .await
+        .map_err(|e| format!("写入数据源配置失败: {}", e))?;
+
+    let mut sources = vec![default];
+    sources.extend(extra);
+    Ok(sources)
+}
+
+/// 按 ID 选择要扫描的数据源
+///
+/// # 参数
+/// - `source_id` - 数据源 ID，或 `ALL_SOURCES_ID` 表示全部
+///
+/// # 错误
+/// 数据源配置读取失败或 ID 不存在时返回错误
+pub async fn select(source_id: &str) -> Result<Vec<DataSource>, String> {
+    if source_id == ALL_SOURCES_ID {
+        return load_sources().await;
+    }
+    Ok(vec![find(source_id).await?])
+}
+
+/// 按 ID 查找单个数据源
+///
+/// # 错误
+/// 数据源配置读取失败或 ID 不存在时返回错误
+pub async fn find(source_id: &str) -> Result<DataSource, String> {
+    load_sources()
+        .await?
+        .into_iter()
+        .find(|s| s.id == source_id)
+        .ok_or_else(|| format!("未知的数据源: {}", source_id))
+}
+
+/// 全部数据源目录（同步读取，供 `file_guard` 路径验证使用）
+///
+/// 配置文件缺失或损坏时只返回默认数据源，不会因此放宽验证。
+///
+/// # 错误
+/// 无法确定用户主目录时返回错误
+pub fn source_roots() -> Result<Vec<PathBuf>, String> {
+    let mut roots = vec![path::get_claude_data_path()?];
+    let extra = sources_path()
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|content| serde_json::from_str::<Vec<DataSource>>(&content).ok())
+        .unwrap_or_default();
+    roots.extend(extra.into_iter().map(|s| PathBuf::from(s.path)));
+    Ok(roots)
+}
+
+/// 分页扫描一个或多个数据源并合并
+///
+/// 各数据源的项目目录依次拼接（每个数据源内部按目录 mtime 倒序）后统一分页；
+/// 单个额外数据源不可读时记录日志并跳过。增量扫描快照按数据源分别保存。
+/// 结果不进入项目分页缓存（该缓存只服务默认数据源）。
+///
+/// # 参数
+/// - `sources` - 要扫描的数据源
+/// - `offset` / `max_projects` - 分页参数，含义同 `scan_projects`
+/// - `force_refresh` - 为 true 时重新枚举每个项目目录
+/// - `options` - 扫描选项（是否包含子 agent 会话、是否统计消息数、是否包含归档项目）
+/// - `cache` - 内存缓存（读写扫描快照）
+///
+/// # 错误
+/// 只扫描一个数据源且其 projects 目录不可读时返回错误
+pub async fn scan_sources(
+    sources: &[DataSource],
+    offset: usize,
+    max_projects: Option<usize>,
+    force_refresh: bool,
+    options: ScanOptions,
+    cache: &AppCache,
+) -> Result<ProjectPage, String> {
+    let mut listed = Vec::new();
+    let mut existing: Vec<HashSet<String>> = Vec::new();
+    let mut warnings = Vec::new();
+    // 归档列表整次扫描只读取一次（读取失败时视为没有归档项目）
+    let archived = archive::load_active().await.unwrap_or_default();
+    for (index, source) in sources.iter().enumerate() {
+        let mut dirs = match scanner::list_project_dirs_with_warnings(&source.path).await {
+            Ok((dirs, skipped)) => {
+                warnings.extend(skipped);
+                dirs
+            }
+            Err(e) if sources.len() > 1 => {
+                log::warn!("跳过不可读的数据源 {}: {}", source.path, e);
+                Vec::new()
+            }
+            Err(e) => return Err(e),
+        };
+        existing.push(dirs.iter().map(|(name, _)| name.clone()).collect());
+        if !options.include_archived {
+            archived.retain_unarchived(&mut dirs);
+        }
+        listed.extend(dirs.into_iter().map(|(name, dir)| (index, name, dir)));
+    }
+
+    let total_projects = listed.len();
+    let start = offset.min(total_projects);
+    let end = max_projects.map_or(total_projects, |max| start.saturating_add(max).min(total_projects));
+
+    let mut projects: Vec<Project> = Vec::new();
+    for (index, source) in sources.iter().enumerate() {
+        let page_dirs: Vec<(String, PathBuf)> = listed[start..end]
+            .iter()
+            .filter(|(i, _, _)| *i == index)
+            .map(|(_, name, dir)| (name.clone(), dir.clone()))
+            .collect();
+        if page_dirs.is_empty() {
+            continue;
+        }
+
+        let snapshots = cache.get_project_snapshots(
+            &source.id,
+            page_dirs.iter().map(|(name, _)| name.as_str()),
+            !force_refresh,
+        );
+        let mut scanned = scanner::scan_project_dirs(page_dirs, &snapshots, options, &archived).await;
+        let names: HashSet<&str> = existing[index].iter().map(String::as_str).collect();
+        cache.update_project_snapshots(&source.id, scanned.snapshots, &names);
+
+        if source.id != DEFAULT_SOURCE_ID {
+            for project in &mut scanned.projects {
+                project.source = Some(source.id.clone());
+            }
+        }
+        projects.extend(scanned.projects);
+        warnings.extend(scanned.warnings);
+    }
+
+    scanner::sort_by_latest_session(&mut projects);
+    Ok(ProjectPage {
+        projects,
+        total_projects,
+        offset: start,
+        has_more: end < total_projects,
+        warnings,
+    })
+}
+
+/// 默认数据源 `~/.claude/`
+fn default_source() -> Result<DataSource, String> {
+    Ok(DataSource {
+        id: DEFAULT_SOURCE_ID.to_string(),
+        name: "~/.claude".to_string(),
+        path: path::get_claude_data_path()?.to_string_lossy().to_string(),
+    })
+}
+
+/// 校验额外数据源并把路径替换为规范化后的绝对路径（规则见 `save_sources`）
+fn validate_extra(mut extra: Vec<DataSource>, default_path: &Path, home: &Path) -> Result<Vec<DataSource>, String> {
+    let home = canonical_or_self(home);
+    let mut ids = HashSet::new();
+    let mut paths = HashSet::from([canonical_or_self(default_path)]);
+    for source in &mut extra {
+        let id = source.id.as_str();
+        if id.trim().is_empty() || id.trim() != id || id == DEFAULT_SOURCE_ID || id == ALL_SOURCES_ID || !ids.insert(id.to_string()) {
+            return Err(format!("无效或重复的数据源 ID: {}", source.id));
+        }
+        let dir = std::fs::canonicalize(&source.path)
+            .map_err(|_| format!("数据源目录不存在: {}", source.path))?;
+        if dir.parent().is_none() || home.starts_with(&dir) {
+            return Err(format!("数据源目录范围过大（根目录或主目录）: {}", source.path));
+        }
+        if !dir.join("projects").is_dir() {
+            return Err(format!("不是 Claude 数据目录（缺少 projects/ 子目录）: {}", source.path));
+        }
+        if !paths.insert(dir.clone()) {
+            return Err(format!("数据源目录重复: {}", source.path));
+        }
+        source.path = canonical_string(&dir);
+    }
+    Ok(extra)
+}
+
+/// 规范化路径的字符串形式（去掉 Windows `canonicalize` 添加的 `\\?\` 前缀，UNC 路径保持原样）
+fn canonical_string(dir: &Path) -> String {
+    let text = dir.to_string_lossy();
+    match text.strip_prefix(r"\\?\") {
+        Some(rest) if !rest.starts_with(r"UNC\") => rest.to_string(),
+        _ => text.to_string(),
+    }
+}
+
+/// 规范化路径用于去重（目录不存在时按原样比较）
+fn canonical_or_self(dir: &Path) -> PathBuf {
+    std::fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
+
+/// 数据源配置文件路径：`~/.mo/CCR/data-sources.json`
+fn sources_path() -> Result<PathBuf, String> {
+    Ok(path::get_ccr_config_path()?.join(DATA_SOURCES_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scan_sources_merges_and_labels() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        let mut sources = Vec::new();
+        for id in [DEFAULT_SOURCE_ID, "wsl"] {
+            // 两个数据源使用同名项目目录，快照按数据源隔离
+            let project = root.join(id).join("projects").join("-w-app");
+            std::fs::create_dir_all(&project).unwrap();
+            std::fs::write(project.join(format!("{}.jsonl", id)), r#"{"type":"user"}"#).unwrap();
+            sources.push(DataSource {
+                id: id.to_string(),
+                name: id.to_string(),
+                path: root.join(id).to_string_lossy().to_string(),
+            });
+        }
+        let cache = AppCache::new();
+
+        let page = scan_sources(&sources, 0, None, false, ScanOptions::default(), &cache).await.unwrap();
+        assert_eq!(page.total_projects, 2);
+        let mut labels: Vec<Option<&str>> = page.projects.iter().map(|p| p.source.as_deref()).collect();
+        labels.sort();
+        assert_eq!(labels, vec![None, Some("wsl")]);
+        assert!(page.projects.iter().all(|p| p.sessions.len() == 1));
+
+        let second = scan_sources(&sources, 1, Some(1), false, ScanOptions::default(), &cache).await.unwrap();
+        assert_eq!(second.projects.len(), 1);
+        assert_eq!(second.projects[0].source.as_deref(), Some("wsl"));
+        assert_eq!(second.projects[0].sessions[0].id, "wsl");
+        assert!(!second.has_more);
+    }
+
+    #[test]
+    fn test_validate_extra_requires_claude_data_dir() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        let home = root.join("home");
+        let default = home.join(".claude");
+        let wsl = root.join("wsl-claude");
+        for dir in [default.join("projects"), wsl.join("projects"), root.join("plain")] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        let source = |id: &str, path: &Path| DataSource {
+            id: id.to_string(),
+            name: id.to_string(),
+            path: path.to_string_lossy().to_string(),
+        };
+
+        // 路径规范化：`..` 被解析
+        let saved = validate_extra(vec![source("wsl", &root.join("plain").join("..").join("wsl-claude"))], &default, &home).unwrap();
+        assert_eq!(Path::new(&saved[0].path), std::fs::canonicalize(&wsl).unwrap());
+
+        let cases = [
+            (root.join("plain"), "projects/"),
+            (root.join("missing"), "不存在"),
+            (root.to_path_buf(), "范围过大"),
+            (home.clone(), "范围过大"),
+            (PathBuf::from("/"), "范围过大"),
+            (default.clone(), "重复"),
+        ];
+        for (path, expected) in cases {
+            let err = validate_extra(vec![source("x", &path)], &default, &home).unwrap_err();
+            assert!(err.contains(expected), "{} → {}", path.display(), err);
+        }
+        let err = validate_extra(vec![source("a", &wsl), source("a", &wsl)], &default, &home).unwrap_err();
+        assert!(err.contains("ID"));
+    }
+}
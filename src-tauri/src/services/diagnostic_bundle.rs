@@ -0,0 +1,248 @@
+//! # 反馈诊断包
+//!
+//! 用户报 issue 时一键收集诊断信息，脱敏后打包为一个 ZIP：
+//! - `system.json` - CCR 版本、操作系统、CPU 架构与逻辑核数
+//! - `diagnostics.json` - 启动自检结果（`services::diagnostics`）
+//! - `cache-stats.json` - 会话缓存统计
+//! - `command-trace.json` - 最近的命令调用耗时记录（`services::command_trace`）
+//! - `log-tail.txt` - 最新日志文件的最后 `LOG_TAIL_LINES` 行（仅 debug 构建启用日志插件）
+//! - `sessions/<n>.json` - 用户勾选的问题会话的结构统计：行数、类型分布、坏行位置，**不含消息正文**
+//! - `manifest.json` - 以上每一项是否收集成功，失败时注明原因
+//!
+//! 单项收集失败不影响其他项，只在 manifest 中记为缺失。
+//! 除 manifest 外的所有文本都经 `fixture::sanitize_text` 脱敏（主目录、UUID、API Key、邮箱等）。
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+
+use crate::services::cache::AppCache;
+use crate::services::{command_trace, diagnostics, fixture, parser, retrospect};
+use crate::utils::{filename, platform};
+
+/// 日志尾部保留的行数
+const LOG_TAIL_LINES: usize = 500;
+
+/// 诊断包中单个条目的收集结果
+///
+/// 对应前端 TypeScript 接口 `DiagnosticBundleItem`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticBundleItem {
+    /// ZIP 内的文件名
+    pub name: String,
+    /// 是否收集成功并写入 ZIP
+    pub included: bool,
+    /// 收集失败的原因（成功时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 诊断包清单（同时写入 ZIP 的 `manifest.json`）
+///
+/// 对应前端 TypeScript 接口 `DiagnosticBundleManifest`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticBundleManifest {
+    /// 生成时间（RFC 3339，UTC）
+    pub created_at: String,
+    /// CCR 版本
+    pub app_version: &'static str,
+    /// 各条目的收集结果
+    pub items: Vec<DiagnosticBundleItem>,
+}
+
+/// 单个会话的结构统计（不含消息正文）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionStructure {
+    /// 文件名（脱敏后）
+    file_name: String,
+    /// 文件大小（字节）
+    size_bytes: u64,
+    /// 非空行数
+    line_count: usize,
+    /// 空行数
+    blank_lines: usize,
+    /// 按 `type` 字段统计的行数（没有 type 的 JSON 对象记为 `(none)`）
+    type_counts: BTreeMap<String, usize>,
+    /// 无法解析为 JSON 对象的行号（从 1 开始）
+    bad_lines: Vec<usize>,
+    /// 非 UTF-8 字节是否被替换（文件编码异常）
+    invalid_utf8: bool,
+}
+
+/// 生成诊断包并写到 `output_path`
+///
+/// # 参数
+/// - `output_path` - ZIP 输出路径（已由调用方校验不在 Claude 数据目录内）
+/// - `sessions` - 需要附带结构统计的会话文件（已校验的绝对路径；校验失败的为错误，记入清单）
+/// - `log_dir` - 日志目录（无法确定时为 None，日志项记为缺失）
+/// - `cache` - AppCache 引用，读取缓存统计
+///
+/// # 返回值
+/// 诊断包清单
+///
+/// # 错误
+/// 仅在 ZIP 写入失败时返回错误；各条目的收集失败记录在清单中
+pub async fn generate(
+    output_path: &str,
+    sessions: Vec<Result<String, String>>,
+    log_dir: Option<PathBuf>,
+    cache: &AppCache,
+) -> Result<DiagnosticBundleManifest, String> {
+    let home = platform::home_dir().map(|h| h.to_string_lossy().to_string());
+    let mut files: Vec<(String, String)> = Vec::new();
+    let mut items: Vec<DiagnosticBundleItem> = Vec::new();
+    let mut collect = |name: String, content: Result<String, String>| {
+        match content {
+            Ok(text) => {
+                files.push((name.clone(), fixture::sanitize_text(&text, home.as_deref())));
+                items.push(DiagnosticBundleItem { name, included: true, error: None });
+            }
+            Err(e) => items.push(DiagnosticBundleItem {
+                name,
+                included: false,
+                error: Some(fixture::sanitize_text(&e, home.as_deref())),
+            }),
+        }
+    };
+
+    collect("system.json".to_string(), to_json(&system_info()));
+    collect("diagnostics.json".to_string(), to_json(&diagnostics::run_all().await));
+    collect("cache-stats.json".to_string(), to_json(&cache.session_cache_stats()));
+    collect("command-trace.json".to_string(), to_json(&command_trace::entries()));
+    let log_tail = match log_dir {
+        Some(dir) => read_log_tail(&dir).await,
+        None => Err("无法确定日志目录".to_string()),
+    };
+    collect("log-tail.txt".to_string(), log_tail);
+    for (i, session) in sessions.into_iter().enumerate() {
+        let structure = match session {
+            Ok(session_path) => session_structure(&session_path).await.and_then(|s| to_json(&s)),
+            Err(e) => Err(e),
+        };
+        collect(format!("sessions/{}.json", i + 1), structure);
+    }
+
+    let manifest = DiagnosticBundleManifest {
+        created_at: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        app_version: env!("CARGO_PKG_VERSION"),
+        items,
+    };
+    files.push(("manifest.json".to_string(), to_json(&manifest)?));
+    retrospect::export_zip_from_files(files, output_path).await?;
+    Ok(manifest)
+}
+
+/// 序列化为格式化的 JSON 文本
+fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| format!("序列化失败: {}", e))
+}
+
+/// 运行环境信息
+fn system_info() -> BTreeMap<&'static str, String> {
+    BTreeMap::from([
+        ("appVersion", env!("CARGO_PKG_VERSION").to_string()),
+        ("os", std::env::consts::OS.to_string()),
+        ("arch", std::env::consts::ARCH.to_string()),
+        ("cpus", std::thread::available_parallelism().map_or(1, |n| n.get()).to_string()),
+        ("debugBuild", cfg!(debug_assertions).to_string()),
+    ])
+}
+
+/// 读取日志目录中最新 `.log` 文件的最后 `LOG_TAIL_LINES` 行
+///
+/// # 错误
+/// 目录不可读或没有日志文件时返回错误
+async fn read_log_tail(log_dir: &Path) -> Result<String, String> {
+    let mut entries = tokio::fs::read_dir(filename::to_long_path(log_dir))
+        .await
+        .map_err(|e| format!("读取日志目录失败: {}", e))?;
+    let mut latest: Option<(std::time::SystemTime, PathBuf)> = None;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "log") {
+            continue;
+        }
+        let Ok(modified) = entry.metadata().await.and_then(|m| m.modified()) else {
+            continue;
+        };
+        if latest.as_ref().is_none_or(|(t, _)| modified > *t) {
+            latest = Some((modified, path));
+        }
+    }
+    let (_, path) = latest.ok_or("日志目录中没有日志文件（release 构建默认不写日志）")?;
+
+    let bytes = tokio::fs::read(&path).await.map_err(|e| format!("读取日志文件失败: {}", e))?;
+    let content = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = content.lines().collect();
+    Ok(lines[lines.len().saturating_sub(LOG_TAIL_LINES)..].join("\n"))
+}
+
+/// 统计会话文件的结构（逐行判断类型与可解析性，不保留正文）
+///
+/// # 错误
+/// 文件读取失败时返回错误
+async fn session_structure(session_path: &str) -> Result<SessionStructure, String> {
+    let bytes = tokio::fs::read(filename::to_long_path(session_path))
+        .await
+        .map_err(|e| format!("读取会话文件失败: {}", e))?;
+    let content = String::from_utf8_lossy(&bytes);
+
+    let mut structure = SessionStructure {
+        file_name: command_trace::path_arg(session_path),
+        size_bytes: bytes.len() as u64,
+        line_count: 0,
+        blank_lines: 0,
+        type_counts: BTreeMap::new(),
+        bad_lines: Vec::new(),
+        invalid_utf8: matches!(content, std::borrow::Cow::Owned(_)),
+    };
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            structure.blank_lines += 1;
+            continue;
+        }
+        structure.line_count += 1;
+        let msg = parser::parse_line(line);
+        if parser::raw_line(&msg).is_some() {
+            structure.bad_lines.push(index + 1);
+            continue;
+        }
+        let msg_type = msg.get("type").and_then(|v| v.as_str()).unwrap_or("(none)");
+        *structure.type_counts.entry(msg_type.to_string()).or_default() += 1;
+    }
+    Ok(structure)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_session_structure_has_no_content() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("session.jsonl");
+        let content = concat!(
+            r#"{"type":"user","message":{"content":"机密正文"}}"#,
+            "\n\n",
+            r#"{"type":"assistant","message":{"content":[]}}"#,
+            "\n",
+            "{broken\n",
+            r#"{"type":"user","message":{"content":"第二条"}}"#,
+            "\n",
+        );
+        std::fs::write(&file, content).unwrap();
+
+        let structure = session_structure(&file.to_string_lossy()).await.unwrap();
+        assert_eq!(structure.line_count, 4);
+        assert_eq!(structure.blank_lines, 1);
+        assert_eq!(structure.bad_lines, vec![4]);
+        assert_eq!(structure.type_counts.get("user"), Some(&2));
+        assert_eq!(structure.type_counts.get("assistant"), Some(&1));
+        assert!(!to_json(&structure).unwrap().contains("机密正文"));
+    }
+}
@@ -0,0 +1,392 @@
+//! # 启动自检服务
+//!
+//! 新用户首次打开 CCR 时若界面空白，可能是未安装 Claude Code、数据目录不可读、
+//! 配置目录不可写等多种原因。本模块一次性检查这些前置条件，逐项返回结果与修复建议。
+//!
+//! ## 检查项
+//! - Claude 数据目录（`~/.claude/`）是否存在且可读
+//! - `projects/` 下的项目数量
+//! - `settings.json` 是否可解析
+//! - CCR 配置目录（`~/.mo/CCR/`）是否可写
+//! - TEMP 临时备份目录（`ccr-backups`）是否可写
+//! - 数据目录所在磁盘的剩余空间
+//! - `claude` CLI 是否在 PATH 中且可执行
+//!
+//! 各项检查并行执行，单项超过 `CHECK_TIMEOUT` 即记为失败，不影响其他检查项返回。
+//!
+//! ## 引导状态
+//! `onboarding_state` 只做最快的几项判断（数据目录、项目数、PATH 中的 `claude`），
+//! 把结果归为 `OnboardingStatus` 三种状态，供前端在空列表时选择展示哪种引导页。
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::services::file_guard;
+use crate::utils::{disk, jsonc, path, platform};
+
+/// 单项检查的超时时间
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 磁盘剩余空间低于该值时给出警告（500 MB）
+const MIN_FREE_BYTES: u64 = 500 * 1024 * 1024;
+
+/// 单个检查项的结果
+///
+/// 对应前端 TypeScript 接口 `DiagnosticItem`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticItem {
+    /// 检查项名称（用于界面展示）
+    pub name: String,
+    /// 是否通过
+    pub ok: bool,
+    /// 检查结果详情（路径、数量、错误信息等）
+    pub detail: String,
+    /// 修复建议（通过时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl DiagnosticItem {
+    fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            ok: true,
+            detail: detail.into(),
+            suggestion: None,
+        }
+    }
+
+    fn fail(name: &str, detail: impl Into<String>, suggestion: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            ok: false,
+            detail: detail.into(),
+            suggestion: Some(suggestion.into()),
+        }
+    }
+}
+
+/// 引导状态
+///
+/// 序列化为 camelCase 字符串，对应前端 `OnboardingStatus` 联合类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OnboardingStatus {
+    /// `~/.claude/` 不存在（未安装或从未运行过 Claude Code）
+    ClaudeNotInstalled,
+    /// 数据目录存在，但 `projects/` 下没有项目
+    NoProjects,
+    /// 已有项目
+    Ok,
+}
+
+/// 引导状态检查结果
+///
+/// 由 `get_onboarding_state` command 返回，对应前端 TypeScript 接口 `OnboardingState`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingState {
+    /// 数据目录与项目状态
+    pub status: OnboardingStatus,
+    /// Claude 数据目录路径（无法获取主目录时为空字符串）
+    pub claude_dir: String,
+    /// `projects/` 下的项目目录数
+    pub project_count: usize,
+    /// PATH 中 `claude` CLI 的路径（未找到时为 None）
+    pub cli_path: Option<String>,
+}
+
+/// 检查引导状态：数据目录是否存在、是否已有项目、`claude` CLI 是否在 PATH 中
+///
+/// 只做目录枚举与 PATH 查找，不执行外部命令。
+pub async fn onboarding_state() -> OnboardingState {
+    let claude_dir = path::get_claude_data_path().ok();
+    let cli_path = path::which("claude").map(|p| p.to_string_lossy().to_string());
+
+    let (status, project_count) = match &claude_dir {
+        Some(dir) if dir.is_dir() => {
+            let count = count_project_dirs(&dir.join("projects")).await.unwrap_or(0);
+            (if count == 0 { OnboardingStatus::NoProjects } else { OnboardingStatus::Ok }, count)
+        }
+        _ => (OnboardingStatus::ClaudeNotInstalled, 0),
+    };
+    OnboardingState {
+        status,
+        claude_dir: claude_dir.map(|d| d.display().to_string()).unwrap_or_default(),
+        project_count,
+        cli_path,
+    }
+}
+
+/// 并行执行所有检查项
+///
+/// # 返回值
+/// 按固定顺序排列的检查结果列表
+pub async fn run_all() -> Vec<DiagnosticItem> {
+    let claude_dir = path::get_claude_data_path().ok();
+    let ccr_dir = path::get_ccr_config_path().ok();
+
+    let (claude, projects, settings, ccr, temp, disk_space, cli) = tokio::join!(
+        with_timeout("Claude 数据目录", check_claude_dir(claude_dir.clone())),
+        with_timeout("项目目录", check_projects(claude_dir.clone())),
+        with_timeout("settings.json", check_settings(claude_dir.clone())),
+        with_timeout("CCR 配置目录", check_ccr_dir(ccr_dir)),
+        with_timeout("临时备份目录", check_temp_backup_dir()),
+        with_timeout("磁盘剩余空间", check_disk_space(claude_dir)),
+        with_timeout("Claude CLI", check_claude_cli()),
+    );
+
+    vec![claude, projects, settings, ccr, temp, disk_space, cli]
+}
+
+/// 为单项检查加上超时，超时记为失败
+async fn with_timeout<F>(name: &str, check: F) -> DiagnosticItem
+where
+    F: Future<Output = DiagnosticItem>,
+{
+    match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(item) => item,
+        Err(_) => DiagnosticItem::fail(
+            name,
+            format!("检查超时（超过 {} 秒）", CHECK_TIMEOUT.as_secs()),
+            "文件系统或命令响应过慢，请稍后重试",
+        ),
+    }
+}
+
+/// 检查 `~/.claude/` 是否存在且可读
+async fn check_claude_dir(claude_dir: Option<PathBuf>) -> DiagnosticItem {
+    const NAME: &str = "Claude 数据目录";
+    let Some(dir) = claude_dir else {
+        return DiagnosticItem::fail(NAME, "无法获取用户主目录", "请检查 HOME / USERPROFILE 环境变量");
+    };
+
+    if !dir.is_dir() {
+        return DiagnosticItem::fail(
+            NAME,
+            format!("{} 不存在", dir.display()),
+            "请先安装 Claude Code 并至少运行一次 `claude`",
+        );
+    }
+    match tokio::fs::read_dir(&dir).await {
+        Ok(_) => DiagnosticItem::pass(NAME, dir.display().to_string()),
+        Err(e) => DiagnosticItem::fail(
+            NAME,
+            format!("{} 无法读取: {}", dir.display(), e),
+            "请检查该目录的读取权限",
+        ),
+    }
+}
+
+/// 统计 `~/.claude/projects/` 下的项目目录数
+async fn check_projects(claude_dir: Option<PathBuf>) -> DiagnosticItem {
+    const NAME: &str = "项目目录";
+    let Some(dir) = claude_dir.map(|d| d.join("projects")) else {
+        return DiagnosticItem::fail(NAME, "无法获取用户主目录", "请检查 HOME / USERPROFILE 环境变量");
+    };
+
+    let count = match count_project_dirs(&dir).await {
+        Ok(count) => count,
+        Err(e) => {
+            return DiagnosticItem::fail(
+                NAME,
+                format!("{} 无法读取: {}", dir.display(), e),
+                "在任意项目目录中运行一次 `claude` 即会生成会话记录",
+            );
+        }
+    };
+
+    if count == 0 {
+        DiagnosticItem::fail(
+            NAME,
+            format!("{} 下没有项目", dir.display()),
+            "在任意项目目录中运行一次 `claude` 即会生成会话记录",
+        )
+    } else {
+        DiagnosticItem::pass(NAME, format!("共 {} 个项目", count))
+    }
+}
+
+/// 统计目录下的子目录数（`projects/` 下每个子目录对应一个项目）
+async fn count_project_dirs(dir: &Path) -> std::io::Result<usize> {
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    let mut count = 0usize;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if entry.file_type().await.map(|t| t.is_dir()).unwrap_or(false) {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// 检查 `~/.claude/settings.json` 是否可解析（不存在视为通过）
+async fn check_settings(claude_dir: Option<PathBuf>) -> DiagnosticItem {
+    const NAME: &str = "settings.json";
+    let Some(settings_path) = claude_dir.map(|d| d.join("settings.json")) else {
+        return DiagnosticItem::fail(NAME, "无法获取用户主目录", "请检查 HOME / USERPROFILE 环境变量");
+    };
+
+    if !settings_path.exists() {
+        return DiagnosticItem::pass(NAME, "未创建，使用默认设置");
+    }
+    let content = match tokio::fs::read_to_string(&settings_path).await {
+        Ok(content) => content,
+        Err(e) => {
+            return DiagnosticItem::fail(
+                NAME,
+                format!("读取失败: {}", e),
+                "请检查该文件的读取权限",
+            );
+        }
+    };
+    match jsonc::parse_lenient::<serde_json::Value>(&content) {
+        Ok((_, false)) => DiagnosticItem::pass(NAME, settings_path.display().to_string()),
+        Ok((_, true)) => DiagnosticItem::pass(
+            NAME,
+            format!("{}（含注释或尾逗号，按 JSONC 解析）", settings_path.display()),
+        ),
+        Err(e) => DiagnosticItem::fail(
+            NAME,
+            format!("JSON 解析失败: {}", e),
+            "请修正 settings.json 的语法错误，或从备份恢复",
+        ),
+    }
+}
+
+/// 检查 `~/.mo/CCR/` 是否可写
+async fn check_ccr_dir(ccr_dir: Option<PathBuf>) -> DiagnosticItem {
+    const NAME: &str = "CCR 配置目录";
+    let Some(dir) = ccr_dir else {
+        return DiagnosticItem::fail(NAME, "无法获取用户主目录", "请检查 HOME / USERPROFILE 环境变量");
+    };
+
+    match probe_writable(&dir).await {
+        Ok(()) => DiagnosticItem::pass(NAME, dir.display().to_string()),
+        Err(e) => DiagnosticItem::fail(
+            NAME,
+            format!("{} 不可写: {}", dir.display(), e),
+            "请检查该目录的写入权限，否则设置与备份无法保存",
+        ),
+    }
+}
+
+/// 检查 TEMP 下的临时备份目录是否可写
+async fn check_temp_backup_dir() -> DiagnosticItem {
+    const NAME: &str = "临时备份目录";
+    let dir = file_guard::temp_backup_dir();
+
+    match probe_writable(&dir).await {
+        Ok(()) => DiagnosticItem::pass(NAME, dir.display().to_string()),
+        Err(e) => DiagnosticItem::fail(
+            NAME,
+            format!("{} 不可写: {}", dir.display(), e),
+            "请检查 TEMP 目录的写入权限，否则编辑会话前无法创建临时备份",
+        ),
+    }
+}
+
+/// 检查数据目录所在磁盘的剩余空间
+async fn check_disk_space(claude_dir: Option<PathBuf>) -> DiagnosticItem {
+    const NAME: &str = "磁盘剩余空间";
+    // 数据目录不存在时退回到主目录所在磁盘
+    let target = claude_dir
+        .filter(|d| d.exists())
+        .or_else(platform::home_dir)
+        .unwrap_or_else(std::env::temp_dir);
+
+    // statvfs 在挂起的网络盘上可能阻塞，放到阻塞线程池以便超时生效
+    let free = tokio::task::spawn_blocking(move || disk::available_space(&target))
+        .await
+        .ok()
+        .flatten();
+
+    match free {
+        Some(free) if free < MIN_FREE_BYTES => DiagnosticItem::fail(
+            NAME,
+            format!("剩余 {}", disk::format_bytes(free)),
+            "磁盘空间不足，备份与会话写入可能失败，请清理磁盘",
+        ),
+        Some(free) => DiagnosticItem::pass(NAME, format!("剩余 {}", disk::format_bytes(free))),
+        None => DiagnosticItem::fail(NAME, "无法获取磁盘剩余空间", "可忽略此项，或手动确认磁盘空间充足"),
+    }
+}
+
+/// 检查 `claude` CLI 是否可用
+async fn check_claude_cli() -> DiagnosticItem {
+    const NAME: &str = "Claude CLI";
+    let Some(program) = path::which("claude") else {
+        return DiagnosticItem::fail(
+            NAME,
+            "PATH 中未找到 claude",
+            "请安装 Claude Code：npm install -g @anthropic-ai/claude-code（一键 Resume 依赖此命令）",
+        );
+    };
+
+    match run_command(&program, &["--version"]).await {
+        Some(version) => DiagnosticItem::pass(NAME, format!("{}（{}）", version, program.display())),
+        None => DiagnosticItem::fail(
+            NAME,
+            format!("{} 无法执行", program.display()),
+            "请尝试重新安装 Claude Code",
+        ),
+    }
+}
+
+/// 确保目录存在，并写入、删除一个探测文件验证可写
+async fn probe_writable(dir: &Path) -> std::io::Result<()> {
+    tokio::fs::create_dir_all(dir).await?;
+    let probe = dir.join(format!(".ccr-write-test-{}", std::process::id()));
+    tokio::fs::write(&probe, b"").await?;
+    tokio::fs::remove_file(&probe).await
+}
+
+/// 执行外部命令并返回 stdout（已 trim）
+///
+/// 超时由调用方的 `with_timeout` 控制，`kill_on_drop` 保证超时后子进程被终止。
+///
+/// # 返回值
+/// 命令成功且输出非空时返回 stdout，否则返回 None
+async fn run_command(program: &Path, args: &[&str]) -> Option<String> {
+    let mut cmd = tokio::process::Command::new(program);
+    cmd.args(args).kill_on_drop(true);
+
+    // Windows 上不弹出控制台窗口（CREATE_NO_WINDOW）
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x0800_0000);
+
+    let out = cmd.output().await.ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (!stdout.is_empty()).then_some(stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_probe_writable_creates_dir() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path().join("data");
+        assert!(probe_writable(&dir).await.is_ok());
+        assert!(dir.is_dir());
+        // 探测文件已删除
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_count_project_dirs_ignores_files() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("-home-user-app")).unwrap();
+        std::fs::write(dir.join("stray.txt"), "x").unwrap();
+        assert_eq!(count_project_dirs(dir).await.unwrap(), 1);
+        assert!(count_project_dirs(&dir.join("missing")).await.is_err());
+    }
+}
@@ -0,0 +1,102 @@
+//! # 会话草稿箱服务
+//!
+//! 浏览历史会话时，用户可以为会话记下「下次 resume 要补充的内容」。
+//! 草稿持久化在 `~/.mo/CCR/drafts.json`（会话路径 → 草稿文本），绝不写入会话 JSONL。
+//!
+//! 一键 Resume 时可选择把草稿作为 `claude --resume <id> "<draft>"` 的首条输入，
+//! 终端启动成功后草稿自动清除。
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use crate::services::session_lock;
+use crate::utils::path;
+
+/// 草稿文件名（位于 `~/.mo/CCR/`）
+const DRAFTS_FILE: &str = "drafts.json";
+
+/// 串行化草稿文件的读-改-写，避免并发保存时互相覆盖
+static WRITE_LOCK: LazyLock<tokio::sync::Mutex<()>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+/// 读取全部草稿
+///
+/// # 返回值
+/// 归一化会话路径 → 草稿文本；文件不存在时返回空映射
+///
+/// # 错误
+/// 文件存在但读取或解析失败时返回错误
+pub async fn load_all() -> Result<BTreeMap<String, String>, String> {
+    let file_path = drafts_path()?;
+    if !file_path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let content = tokio::fs::read_to_string(&file_path)
+        .await
+        .map_err(|e| format!("读取草稿文件失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析草稿文件失败: {}", e))
+}
+
+/// 读取单个会话的草稿
+///
+/// # 错误
+/// 草稿文件读取或解析失败时返回错误
+pub async fn get(session_file_path: &str) -> Result<Option<String>, String> {
+    Ok(load_all().await?.remove(&session_lock::normalize(session_file_path)))
+}
+
+/// 保存会话草稿；去除首尾空白后为空时等同于删除
+///
+/// # 返回值
+/// 保存后该会话是否有草稿
+///
+/// # 错误
+/// 草稿文件读取、解析或写入失败时返回错误
+pub async fn save(session_file_path: &str, text: &str) -> Result<bool, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        delete(session_file_path).await?;
+        return Ok(false);
+    }
+
+    let _guard = WRITE_LOCK.lock().await;
+    let mut drafts = load_all().await?;
+    drafts.insert(session_lock::normalize(session_file_path), text.to_string());
+    write_all(&drafts).await?;
+    Ok(true)
+}
+
+/// 删除会话草稿（不存在时视为成功）
+///
+/// # 错误
+/// 草稿文件读取、解析或写入失败时返回错误
+pub async fn delete(session_file_path: &str) -> Result<(), String> {
+    let _guard = WRITE_LOCK.lock().await;
+    let mut drafts = load_all().await?;
+    if drafts.remove(&session_lock::normalize(session_file_path)).is_none() {
+        return Ok(());
+    }
+    write_all(&drafts).await
+}
+
+/// 写回草稿文件
+async fn write_all(drafts: &BTreeMap<String, String>) -> Result<(), String> {
+    let file_path = drafts_path()?;
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("创建 CCR 配置目录失败: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(drafts)
+        .map_err(|e| format!("序列化草稿失败: {}", e))?;
+    tokio::fs::write(&file_path, content)
+        .await
+        .map_err(|e| format!("写入草稿文件失败: {}", e))
+}
+
+/// 草稿文件路径：`~/.mo/CCR/drafts.json`
+fn drafts_path() -> Result<PathBuf, String> {
+    Ok(path::get_ccr_config_path()?.join(DRAFTS_FILE))
+}
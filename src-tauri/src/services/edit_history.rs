@@ -0,0 +1,224 @@
+//! # 消息编辑历史服务
+//!
+//! 在 CCR 中编辑消息会覆盖原文。为了能找回之前的措辞，每次编辑写回前把被修改消息的
+//! 旧版本（整条原始 Value）追加到 `~/.mo/CCR/edit-history/<session_id>.jsonl`。
+//!
+//! ## 文件格式
+//! 每行一条 `EditHistoryRecord`：`{ "versionId", "uuid", "savedAt", "message" }`。
+//! 历史文件与会话 JSONL 完全分离，绝不写入 `~/.claude/`。
+//!
+//! ## 容量
+//! 每个会话最多保留 `MAX_HISTORY_ENTRIES` 条记录，超出后滚动丢弃最早的记录。
+
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::utils::path;
+
+/// 历史目录名（位于 `~/.mo/CCR/`）
+const EDIT_HISTORY_DIR: &str = "edit-history";
+
+/// 每个会话最多保留的历史记录条数
+const MAX_HISTORY_ENTRIES: usize = 500;
+
+/// 文本摘要的最大字符数
+const SUMMARY_MAX_CHARS: usize = 120;
+
+/// 串行化历史文件的读-改-写，避免并发编辑时互相覆盖
+static WRITE_LOCK: LazyLock<tokio::sync::Mutex<()>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+/// 历史文件中的一行记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EditHistoryRecord {
+    /// 版本 ID（v4 UUID），供回滚时定位
+    version_id: String,
+    /// 被编辑消息的 uuid
+    uuid: String,
+    /// 保存时间（UTC，ISO 8601）
+    saved_at: String,
+    /// 编辑前的完整原始消息
+    message: Value,
+}
+
+/// 单条历史版本
+///
+/// 由 `get_message_edit_history` command 返回，对应前端 TypeScript 接口 `MessageEditVersion`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageEditVersion {
+    /// 版本 ID
+    pub version_id: String,
+    /// 保存时间（UTC，ISO 8601），即该版本被覆盖的时刻
+    pub saved_at: String,
+    /// 文本摘要（首段文本，最多 120 字符）
+    pub summary: String,
+    /// 完整的原始消息
+    pub message: Value,
+}
+
+/// 记录消息被编辑前的版本
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件路径（用于确定历史文件名）
+/// - `message` - 编辑前的完整原始消息（必须带 `uuid`）
+///
+/// # 错误
+/// 消息缺少 uuid，或历史文件读写失败时返回错误
+pub async fn record(session_file_path: &str, message: &Value) -> Result<(), String> {
+    let uuid = message
+        .get("uuid")
+        .and_then(|v| v.as_str())
+        .ok_or("消息缺少 uuid，无法记录编辑历史")?
+        .to_string();
+    let file_path = history_path(session_file_path)?;
+
+    let _guard = WRITE_LOCK.lock().await;
+    let mut records = read_records(&file_path).await?;
+    records.push(EditHistoryRecord {
+        version_id: uuid::Uuid::new_v4().to_string(),
+        uuid,
+        saved_at: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        message: message.clone(),
+    });
+    // 超出上限时滚动丢弃最早的记录
+    let overflow = records.len().saturating_sub(MAX_HISTORY_ENTRIES);
+    records.drain(..overflow);
+
+    write_records(&file_path, &records).await
+}
+
+/// 获取某条消息的历史版本列表
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件路径
+/// - `message_uuid` - 消息 uuid
+///
+/// # 返回值
+/// 历史版本列表（最新在前）；没有历史时返回空列表
+///
+/// # 错误
+/// 历史文件读取或解析失败时返回错误
+pub async fn list(session_file_path: &str, message_uuid: &str) -> Result<Vec<MessageEditVersion>, String> {
+    let records = read_records(&history_path(session_file_path)?).await?;
+    Ok(records
+        .into_iter()
+        .rev()
+        .filter(|r| r.uuid == message_uuid)
+        .map(|r| MessageEditVersion {
+            summary: summarize(&r.message),
+            version_id: r.version_id,
+            saved_at: r.saved_at,
+            message: r.message,
+        })
+        .collect())
+}
+
+/// 按版本 ID 查找某条消息的历史版本原文
+///
+/// # 返回值
+/// 历史版本的完整原始消息；找不到时返回 None
+///
+/// # 错误
+/// 历史文件读取或解析失败时返回错误
+pub async fn find_version(
+    session_file_path: &str,
+    message_uuid: &str,
+    version_id: &str,
+) -> Result<Option<Value>, String> {
+    let records = read_records(&history_path(session_file_path)?).await?;
+    Ok(records
+        .into_iter()
+        .find(|r| r.uuid == message_uuid && r.version_id == version_id)
+        .map(|r| r.message))
+}
+
+/// 历史文件路径：`~/.mo/CCR/edit-history/<session_id>.jsonl`
+fn history_path(session_file_path: &str) -> Result<PathBuf, String> {
+    let session_id = Path::new(session_file_path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| format!("无效的会话文件路径: {}", session_file_path))?;
+    Ok(path::get_ccr_config_path()?
+        .join(EDIT_HISTORY_DIR)
+        .join(format!("{}.jsonl", session_id)))
+}
+
+/// 读取历史文件（不存在时返回空列表，无法解析的行跳过）
+async fn read_records(file_path: &Path) -> Result<Vec<EditHistoryRecord>, String> {
+    if !file_path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = tokio::fs::read_to_string(file_path)
+        .await
+        .map_err(|e| format!("读取编辑历史失败: {}", e))?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// 整体写回历史文件
+async fn write_records(file_path: &Path, records: &[EditHistoryRecord]) -> Result<(), String> {
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("创建编辑历史目录失败: {}", e))?;
+    }
+    let mut content = String::new();
+    for record in records {
+        let line = serde_json::to_string(record).map_err(|e| format!("序列化编辑历史失败: {}", e))?;
+        content.push_str(&line);
+        content.push('\n');
+    }
+    tokio::fs::write(file_path, content)
+        .await
+        .map_err(|e| format!("写入编辑历史失败: {}", e))
+}
+
+/// 提取消息的文本摘要：字符串 content 或首个含文本的内容块
+fn summarize(message: &Value) -> String {
+    let content = message.get("message").and_then(|m| m.get("content"));
+    let text = match content {
+        Some(Value::String(s)) => s.as_str(),
+        Some(Value::Array(blocks)) => blocks
+            .iter()
+            .find_map(|b| {
+                b.get("text")
+                    .or_else(|| b.get("thinking"))
+                    .and_then(|t| t.as_str())
+            })
+            .unwrap_or(""),
+        _ => "",
+    };
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() > SUMMARY_MAX_CHARS {
+        format!("{}…", flat.chars().take(SUMMARY_MAX_CHARS).collect::<String>())
+    } else {
+        flat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_summarize() {
+        let msg = json!({ "message": { "content": "  第一行\n第二行  " } });
+        assert_eq!(summarize(&msg), "第一行 第二行");
+
+        let msg = json!({ "message": { "content": [
+            { "type": "tool_use", "id": "t1", "input": {} },
+            { "type": "text", "text": "a".repeat(200) },
+        ] } });
+        assert_eq!(summarize(&msg).chars().count(), SUMMARY_MAX_CHARS + 1);
+    }
+}
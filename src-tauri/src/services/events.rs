@@ -0,0 +1,53 @@
+//! # 跨窗口事件广播
+//!
+//! 多个窗口共享同一份 `AppCache`，但各窗口的前端状态相互独立：
+//! 一个窗口修改了会话文件后，其他打开同一会话的窗口需要收到通知重新加载。
+//!
+//! 写入入口统一在 `file_guard`，它不持有 `AppHandle`，因此在应用启动时把
+//! `AppHandle` 注册到本模块，由 `file_guard` 在写入 / 删除成功后调用 `emit_session_updated`。
+//! 未注册（如单元测试）时广播静默跳过。
+
+use std::sync::OnceLock;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// Claude 数据文件被修改或删除后广播的事件名
+pub const SESSION_UPDATED_EVENT: &str = "session-updated";
+
+/// 应用启动时注册的 AppHandle
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+/// `session-updated` 事件载荷
+///
+/// 对应前端 TypeScript 接口 `SessionUpdatedEvent`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionUpdated {
+    /// 被修改的文件绝对路径
+    pub file_path: String,
+    /// 文件是否已被删除
+    pub deleted: bool,
+}
+
+/// 注册 AppHandle（在 `setup` 钩子中调用一次，重复调用被忽略）
+pub fn init(handle: AppHandle) {
+    let _ = APP_HANDLE.set(handle);
+}
+
+/// 向所有窗口广播文件已修改
+///
+/// # 参数
+/// - `file_path` - 被修改的文件绝对路径
+/// - `deleted` - 文件是否已被删除
+pub fn emit_session_updated(file_path: &str, deleted: bool) {
+    if let Some(handle) = APP_HANDLE.get() {
+        let _ = handle.emit(
+            SESSION_UPDATED_EVENT,
+            SessionUpdated {
+                file_path: file_path.to_string(),
+                deleted,
+            },
+        );
+    }
+}
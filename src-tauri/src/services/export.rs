@@ -2,63 +2,224 @@
 //!
 //! 将原始 `Vec<serde_json::Value>` 消息导出为 Markdown 或 JSON 格式的字符串。
 //! 从前端 `claudeData.ts` 的 `exportAsMarkdown`/`exportAsJson` 逻辑平移而来。
+//! 另提供项目清单（每个项目的会话数、大小、最近活跃时间）的 CSV / JSON 导出。
 //!
 //! ## 导出策略
-//! - **Markdown**：仅导出 user 和 assistant 类型的消息，提取文本内容
+//! - **Markdown**：逐条消息先经 `classifier` 分类再渲染，提取文本内容（见下文「按分类渲染」）
 //! - **JSON**：保留所有消息的原始完整结构，美化输出
+//! - **项目清单 CSV**：RFC 4180 转义，时间为显示时区（见 `utils::time`）
+//!
+//! ## 时间显示
+//! `ExportOptions::time_style` 控制消息时间的显示方式：原始 ISO、显示时区时间，
+//! 或以导出时刻为基准的相对时间（「3 天前」，文案跟随 `locale`）。
+//!
+//! ## 按搜索结果导出
+//! `ExportFilter` 描述「只导出搜索命中的消息」：命令层复用会话内搜索得到命中的 DisplayMessage，
+//! 映射回原始消息 uuid（tool_result 拆分出的多条 DisplayMessage 共享同一个 uuid），
+//! 再由 `select_messages` 按文件顺序筛出原始消息交给 Markdown / JSON 导出。
+//!
+//! ## 按分类渲染（Markdown）
+//! - 普通 user / assistant 消息：`## 用户 / 助手 (时间)` + 文本
+//! - 斜杠命令：不输出原始 `<command-name>` 标签，渲染为「🔸 执行命令：/compact」
+//! - 压缩摘要：引用块，首行标注「上下文压缩摘要」
+//! - 系统消息（CLI 注入、附件、system 条目）：默认跳过，`include_types` 含 `"system"` 时
+//!   以 `## 系统（子类型）` 标题渲染
+//!
+//! `include_types` 使用与 DisplayMessage 相同的显示类型名，斜杠命令属于 `"user"`。
+//!
+//! ## 去噪
+//! `exclude_noise`（默认开启）在 `include_types` 选中的类型中再剔除噪音消息
+//! （压缩摘要、计划以外的系统注入，见 `classifier::is_noise`），两者取交集：
+//! 导出压缩摘要或系统注入需同时关闭 `exclude_noise` 并在 `include_types` 中选中对应类型。
+//!
+//! ## 单条消息渲染
+//! `render_message` 负责单条消息的 Markdown / 纯文本 / JSON 渲染，
+//! 既被 `to_markdown` 逐条复用，也供「复制这条消息」直接调用。
+
+use std::collections::HashSet;
 
+use serde::Deserialize;
 use serde_json::Value;
 
+use crate::models::project::{Project, ProjectOverviewRow};
+use crate::services::classifier::{self, Classification};
+use crate::utils::time::{self, Locale};
+
+/// 消息时间的显示方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeStyle {
+    /// 原始 ISO 8601 字符串（默认，与旧版导出一致）
+    #[default]
+    Iso,
+    /// 显示时区（`app-config.json` 的 `displayTimezone`，默认本地时区）`YYYY-MM-DD HH:MM:SS`
+    Local,
+    /// 相对导出时刻的时间（「x 分钟前 / x 天前」，超过 30 天显示日期）
+    Relative,
+}
+
+/// 未指定 `include_types` 时 Markdown 导出的显示类型（即跳过压缩摘要与 system）
+const DEFAULT_INCLUDE_TYPES: &[&str] = &["user", "assistant"];
+
+/// Markdown 导出支持的全部显示类型
+const ALL_INCLUDE_TYPES: &[&str] = &["user", "assistant", "compact_summary", "system"];
+
+/// 导出与复制选项
+///
+/// 前端未传或字段缺失时使用默认值（ISO 时间、中文文案、跳过系统消息、去噪）。
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportOptions {
+    /// 消息时间的显示方式
+    pub time_style: TimeStyle,
+    /// 相对时间文案语言
+    pub locale: Locale,
+    /// Markdown 导出包含的显示类型（"user" | "assistant" | "compact_summary" | "system"），
+    /// 未传时为 `DEFAULT_INCLUDE_TYPES`
+    pub include_types: Option<Vec<String>>,
+    /// Markdown 导出是否剔除噪音消息（默认 true，与 `include_types` 取交集）
+    pub exclude_noise: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            time_style: TimeStyle::default(),
+            locale: Locale::default(),
+            include_types: None,
+            exclude_noise: true,
+        }
+    }
+}
+
+impl ExportOptions {
+    /// 完整导出：包含全部显示类型且不剔除噪音（归档等导出后会删除原文件的场景）
+    pub fn complete() -> Self {
+        ExportOptions {
+            include_types: Some(ALL_INCLUDE_TYPES.iter().map(|t| t.to_string()).collect()),
+            exclude_noise: false,
+            ..Default::default()
+        }
+    }
+
+    /// 按 `time_style` 格式化消息时间戳
+    ///
+    /// # 参数
+    /// - `timestamp` - 消息的 ISO 8601 时间戳
+    /// - `now_millis` - 相对时间的基准（导出时刻，Unix 毫秒）
+    ///
+    /// # 返回值
+    /// 格式化后的时间；无法解析的时间戳原样返回
+    pub fn format_time(&self, timestamp: &str, now_millis: i64) -> String {
+        let formatted = match self.time_style {
+            TimeStyle::Iso => None,
+            TimeStyle::Local => {
+                time::parse_iso8601_millis(timestamp).and_then(time::format_display_datetime)
+            }
+            TimeStyle::Relative => time::parse_iso8601_millis(timestamp)
+                .map(|millis| time::humanize(millis, now_millis, self.locale)),
+        };
+        formatted.unwrap_or_else(|| timestamp.to_string())
+    }
+
+    /// Markdown 导出是否包含该分类的消息（显示类型在 `include_types` 中，且未被去噪剔除）
+    fn includes_message(&self, classification: &Classification, display_type: &str) -> bool {
+        let plan_source_path = match classification {
+            Classification::System { plan_source_path, .. } => plan_source_path.as_deref(),
+            _ => None,
+        };
+        self.includes(display_type) && !(self.exclude_noise && classifier::is_noise(display_type, plan_source_path))
+    }
+
+    /// Markdown 导出是否包含该显示类型
+    fn includes(&self, display_type: &str) -> bool {
+        match &self.include_types {
+            Some(types) => types.iter().any(|t| t == display_type),
+            None => DEFAULT_INCLUDE_TYPES.contains(&display_type),
+        }
+    }
+}
+
+/// 按搜索结果导出的过滤条件
+///
+/// 搜索语义与 `search_session` 相同（大小写、正则、`tool:` 等字段限定前缀）。
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportFilter {
+    /// 搜索词
+    pub query: String,
+    /// 是否大小写敏感
+    pub case_sensitive: bool,
+    /// 是否按正则表达式匹配
+    pub use_regex: bool,
+    /// 是否同时导出每条命中消息前后各一条消息作为上下文
+    pub include_context: bool,
+}
+
+/// 按 uuid 集合筛选原始消息（保持文件顺序，没有 uuid 的元数据行不保留）
+///
+/// # 参数
+/// - `messages` - 原始消息 Value 列表
+/// - `uuids` - 需要保留的消息 uuid
+pub fn select_messages(messages: &[Value], uuids: &HashSet<String>) -> Vec<Value> {
+    messages
+        .iter()
+        .filter(|m| m.get("uuid").and_then(|v| v.as_str()).is_some_and(|u| uuids.contains(u)))
+        .cloned()
+        .collect()
+}
+
+/// 当前时刻的 Unix 毫秒时间戳（相对时间的基准）
+pub fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
 /// 将消息列表导出为 Markdown 格式字符串
 ///
 /// 生成结构化的 Markdown 文档，包含会话标题和每条消息的角色、时间戳和内容。
-/// 仅导出 user 和 assistant 类型的消息，忽略其他类型（如 file-history-snapshot）。
+/// 每条消息先经 `classifier` 分类，按分类渲染（见模块文档）；
+/// 元数据条目（如 file-history-snapshot）与 `include_types` 之外的消息被忽略。
 ///
 /// # 参数
 /// - `messages` - 原始消息 Value 列表
 /// - `session_name` - 会话名称，用作文档标题
+/// - `options` - 导出选项（消息时间的显示方式、包含的显示类型）
 ///
 /// # 返回值
 /// Markdown 格式的字符串
-pub fn to_markdown(messages: &[Value], session_name: &str) -> String {
+pub fn to_markdown(messages: &[Value], session_name: &str, options: &ExportOptions) -> String {
     let mut lines: Vec<String> = Vec::new();
 
     // 文档标题
     lines.push(format!("# {}", session_name));
     lines.push(String::new());
 
-    // 导出时间：使用 SystemTime 计算 UTC 时间，避免依赖 chrono
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
+    // 导出时间：同时作为相对时间的基准
+    let now = now_millis();
     // 简单格式化为 Unix 时间戳（前端可进一步格式化）
-    lines.push(format!("导出时间: (UTC epoch: {})", now));
+    lines.push(format!("导出时间: (UTC epoch: {})", now / 1000));
     lines.push(String::new());
     lines.push("---".into());
     lines.push(String::new());
 
     for msg in messages {
-        let msg_type = msg.get("type").and_then(|v| v.as_str()).unwrap_or("");
-        // 仅导出用户和助手消息
-        if msg_type != "user" && msg_type != "assistant" {
+        let classification = classifier::classify(msg);
+        let Some(display_type) = classified_display_type(&classification) else {
+            continue;
+        };
+        if !options.includes_message(&classification, display_type) {
             continue;
         }
 
-        let role = if msg_type == "user" { "用户" } else { "助手" };
         let time = msg
             .get("timestamp")
             .and_then(|v| v.as_str())
-            .unwrap_or("未知时间");
-
-        lines.push(format!("## {} ({})", role, time));
-        lines.push(String::new());
-
-        // 提取消息文本内容
-        let text = extract_message_text(msg);
-        if !text.is_empty() {
-            lines.push(text);
-        }
+            .map(|ts| options.format_time(ts, now))
+            .unwrap_or_else(|| "未知时间".to_string());
+        lines.push(render_classified(msg, &classification, &time));
         lines.push(String::new());
         lines.push("---".into());
         lines.push(String::new());
@@ -67,6 +228,50 @@ pub fn to_markdown(messages: &[Value], session_name: &str) -> String {
     lines.join("\n")
 }
 
+/// 分类对应的显示类型（与 `transformer` 生成的 DisplayMessage 一致），Skip 返回 None
+fn classified_display_type(classification: &Classification) -> Option<&'static str> {
+    match classification {
+        Classification::Skip => None,
+        Classification::Assistant => Some("assistant"),
+        Classification::User | Classification::SlashCommand(_) => Some("user"),
+        Classification::CompactSummary => Some("compact_summary"),
+        Classification::Attachment | Classification::SystemEntry | Classification::System { .. } => Some("system"),
+    }
+}
+
+/// 按分类把一条原始消息渲染为 Markdown（不含末尾分隔线）
+///
+/// 整会话导出仅保留文本内容，工具调用与结果不展开。
+fn render_classified(msg: &Value, classification: &Classification, time: &str) -> String {
+    let blocks = content_blocks(msg.get("message").and_then(|m| m.get("content")));
+    match classification {
+        Classification::SlashCommand(command) => format!("## 用户 ({})\n\n🔸 执行命令：{}", time, command),
+        Classification::CompactSummary => {
+            let summary = render_blocks(&blocks, false, true);
+            format!("## {} ({})\n\n> **上下文压缩摘要**\n>\n{}", role_label("compact_summary"), time, blockquote(&summary))
+        }
+        Classification::Attachment => render_message("系统（附件）", time, &blocks, MessageFormat::Markdown, false),
+        Classification::System { label, .. } if label != "系统" => {
+            render_message(&format!("系统（{}）", label), time, &blocks, MessageFormat::Markdown, false)
+        }
+        Classification::SystemEntry | Classification::System { .. } => {
+            render_message(role_label("system"), time, &blocks, MessageFormat::Markdown, false)
+        }
+        Classification::Assistant => render_message(role_label("assistant"), time, &blocks, MessageFormat::Markdown, false),
+        Classification::User | Classification::Skip => {
+            render_message(role_label("user"), time, &blocks, MessageFormat::Markdown, false)
+        }
+    }
+}
+
+/// 把文本的每一行加上 `> ` 前缀（空行为 `>`），渲染为 Markdown 引用块
+fn blockquote(text: &str) -> String {
+    text.lines()
+        .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// 将消息列表导出为 JSON 格式字符串
 ///
 /// 直接将原始消息数组序列化为美化的 JSON 字符串（2 空格缩进），保留所有字段。
@@ -80,30 +285,376 @@ pub fn to_json(messages: &[Value]) -> String {
     serde_json::to_string_pretty(messages).unwrap_or_else(|_| "[]".to_string())
 }
 
-/// 从消息 Value 中提取纯文本内容
-///
-/// 处理 `message.content` 的两种格式：
-/// - 字符串：直接返回
-/// - 数组：提取所有 `type === "text"` 块的 text 字段，用换行符拼接
+/// 单条消息的渲染格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// `## 角色 (时间)` 标题 + Markdown 正文
+    Markdown,
+    /// 仅正文的纯文本
+    Plain,
+    /// 内容块数组的美化 JSON
+    Json,
+}
+
+impl MessageFormat {
+    /// 从前端传入的格式字符串解析（"markdown" / "plain" / "json"）
+    ///
+    /// # 错误
+    /// 不支持的格式返回错误
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format {
+            "markdown" => Ok(Self::Markdown),
+            "plain" => Ok(Self::Plain),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("不支持的格式: {}", format)),
+        }
+    }
+}
+
+/// 渲染单条消息
 ///
 /// # 参数
-/// - `msg` - 原始消息 Value
+/// - `role` - 角色显示名（如 "用户"、"助手"）
+/// - `time` - 时间戳文本
+/// - `blocks` - 消息内容块
+/// - `format` - 渲染格式
+/// - `include_tools` - 是否展开 tool_use / tool_result（含嵌套内容）与图片占位；
+///   为 false 时仅渲染 text 块
 ///
 /// # 返回值
-/// 消息的纯文本内容
-fn extract_message_text(msg: &Value) -> String {
-    let content = msg.get("message").and_then(|m| m.get("content"));
+/// 渲染后的字符串（Markdown 格式不含末尾分隔线）
+pub fn render_message(
+    role: &str,
+    time: &str,
+    blocks: &[Value],
+    format: MessageFormat,
+    include_tools: bool,
+) -> String {
+    match format {
+        MessageFormat::Json => {
+            serde_json::to_string_pretty(blocks).unwrap_or_else(|_| "[]".to_string())
+        }
+        MessageFormat::Plain => render_blocks(blocks, include_tools, false),
+        MessageFormat::Markdown => {
+            let mut out = format!("## {} ({})\n", role, time);
+            let body = render_blocks(blocks, include_tools, true);
+            if !body.is_empty() {
+                out.push('\n');
+                out.push_str(&body);
+            }
+            out
+        }
+    }
+}
 
+/// DisplayMessage 的 `display_type` 对应的角色显示名
+pub fn role_label(display_type: &str) -> &'static str {
+    match display_type {
+        "user" => "用户",
+        "assistant" => "助手",
+        "tool_result" => "工具结果",
+        "compact_summary" => "压缩摘要",
+        "system" => "系统",
+        _ => "消息",
+    }
+}
+
+/// 将 `message.content` 统一为内容块数组
+///
+/// 字符串格式转换为单个 text 块；数组格式原样克隆；其他情况返回空数组。
+fn content_blocks(content: Option<&Value>) -> Vec<Value> {
+    match content {
+        Some(Value::String(s)) => vec![serde_json::json!({ "type": "text", "text": s })],
+        Some(Value::Array(arr)) => arr.clone(),
+        _ => Vec::new(),
+    }
+}
+
+/// 渲染内容块正文，块之间以换行符拼接
+///
+/// - `text` 块：原样输出
+/// - `tool_use` / `server_tool_use`：工具名 + 格式化的 input JSON
+/// - `tool_result` / `*_tool_result`：结果文本（字符串或嵌套数组中的 text 块，嵌套图片以占位符表示）
+/// - `image` / `document`：占位符
+/// - 其余块（thinking 等）：忽略
+fn render_blocks(blocks: &[Value], include_tools: bool, markdown: bool) -> String {
+    let mut parts: Vec<String> = Vec::new();
+
+    for block in blocks {
+        let block_type = block.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        match block_type {
+            "text" => {
+                if let Some(text) = block.get("text").and_then(|v| v.as_str()) {
+                    parts.push(text.to_string());
+                }
+            }
+            _ if !include_tools => {}
+            "tool_use" | "server_tool_use" => {
+                let name = block.get("name").and_then(|v| v.as_str()).unwrap_or("未知工具");
+                let input = block
+                    .get("input")
+                    .map(|v| serde_json::to_string_pretty(v).unwrap_or_default())
+                    .unwrap_or_default();
+                if markdown {
+                    parts.push(format!("**工具调用: {}**\n\n{}", name, fenced(&input, "json")));
+                } else {
+                    parts.push(format!("[工具调用: {}]\n{}", name, input));
+                }
+            }
+            t if t.ends_with("tool_result") => {
+                let is_error = block.get("is_error").and_then(|v| v.as_bool()) == Some(true);
+                let title = if is_error { "工具结果（错误）" } else { "工具结果" };
+                let text = tool_result_text(block.get("content"));
+                if markdown {
+                    parts.push(format!("**{}**\n\n{}", title, fenced(&text, "")));
+                } else {
+                    parts.push(format!("[{}]\n{}", title, text));
+                }
+            }
+            "image" => parts.push("[image]".to_string()),
+            "document" => parts.push("[document]".to_string()),
+            _ => {}
+        }
+    }
+
+    parts.join("\n")
+}
+
+/// 提取 tool_result 的 content 文本（字符串或嵌套内容块数组）
+pub(crate) fn tool_result_text(content: Option<&Value>) -> String {
     match content {
         Some(Value::String(s)) => s.clone(),
-        Some(Value::Array(arr)) => {
-            let texts: Vec<&str> = arr
+        Some(Value::Array(arr)) => arr
+            .iter()
+            .filter_map(|b| match b.get("type").and_then(|v| v.as_str()) {
+                Some("text") => b.get("text").and_then(|v| v.as_str()).map(str::to_string),
+                Some("image") => Some("[image]".to_string()),
+                Some("document") => Some("[document]".to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Some(other) if !other.is_null() => other.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// 用代码围栏包裹文本，围栏长度大于文本中最长的连续反引号，避免提前闭合
+fn fenced(text: &str, lang: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}{}\n{}\n{}", fence, lang, text, fence)
+}
+
+/// 汇总项目列表为清单行
+///
+/// # 参数
+/// - `projects` - 扫描得到的项目列表
+///
+/// # 返回值
+/// 与输入顺序一致的清单行
+pub fn projects_overview(projects: &[Project]) -> Vec<ProjectOverviewRow> {
+    projects
+        .iter()
+        .map(|project| {
+            // ISO 8601 字符串可直接按字典序比较
+            let last_active = project.sessions.iter().map(|s| s.timestamp.as_str()).max();
+            let first_created = project
+                .sessions
                 .iter()
-                .filter(|b| b.get("type").and_then(|v| v.as_str()) == Some("text"))
-                .filter_map(|b| b.get("text").and_then(|v| v.as_str()))
-                .collect();
-            texts.join("\n")
+                .filter_map(|s| s.created_at.as_deref())
+                .min();
+
+            ProjectOverviewRow {
+                name: project.name.clone(),
+                path: project.path.clone(),
+                session_count: project.sessions.len(),
+                message_count: project.sessions.iter().map(|s| s.message_count as u64).sum(),
+                total_size: project.sessions.iter().filter_map(|s| s.file_size).sum(),
+                last_active: last_active.map(to_display_time),
+                first_created: first_created.map(to_display_time),
+            }
+        })
+        .collect()
+}
+
+/// 将项目清单导出为 CSV 字符串
+///
+/// 首行为表头；含逗号、双引号或换行的字段用双引号包裹，内部双引号转义为两个双引号。
+/// 开头带 UTF-8 BOM，保证 Excel 直接打开时中文路径不乱码。
+pub fn projects_overview_to_csv(rows: &[ProjectOverviewRow]) -> String {
+    let mut out = String::from("\u{feff}");
+    out.push_str("name,path,sessionCount,messageCount,totalSize,lastActive,firstCreated\r\n");
+
+    for row in rows {
+        let fields = [
+            csv_field(&row.name),
+            csv_field(&row.path),
+            row.session_count.to_string(),
+            row.message_count.to_string(),
+            row.total_size.to_string(),
+            csv_field(row.last_active.as_deref().unwrap_or("")),
+            csv_field(row.first_created.as_deref().unwrap_or("")),
+        ];
+        out.push_str(&fields.join(","));
+        out.push_str("\r\n");
+    }
+
+    out
+}
+
+/// 将项目清单导出为美化的 JSON 字符串
+pub fn projects_overview_to_json(rows: &[ProjectOverviewRow]) -> String {
+    serde_json::to_string_pretty(rows).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// 按 RFC 4180 转义单个 CSV 字段
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 将 ISO 8601（UTC）时间转换为显示时区的 `YYYY-MM-DD HH:MM:SS ±HH:MM` 格式
+///
+/// 无法解析时原样返回。
+fn to_display_time(iso: &str) -> String {
+    time::parse_iso8601_millis(iso)
+        .and_then(time::format_display_datetime_with_offset)
+        .unwrap_or_else(|| iso.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// 渲染单条消息（ISO 时间）
+    fn render(msg: &Value) -> String {
+        let time = msg.get("timestamp").and_then(|v| v.as_str()).unwrap_or_default();
+        render_classified(msg, &classifier::classify(msg), time)
+    }
+
+    #[test]
+    fn test_render_classified_snapshots() {
+        let ts = "2025-01-01T00:00:00Z";
+        let cases = [
+            (
+                json!({"type": "user", "timestamp": ts, "message": {"role": "user", "content": "你好"}}),
+                "## 用户 (2025-01-01T00:00:00Z)\n\n你好",
+            ),
+            (
+                json!({"type": "assistant", "timestamp": ts, "message": {"role": "assistant", "content": [
+                    {"type": "text", "text": "好的"},
+                    {"type": "tool_use", "name": "Bash", "input": {}},
+                ]}}),
+                "## 助手 (2025-01-01T00:00:00Z)\n\n好的",
+            ),
+            (
+                json!({"type": "user", "timestamp": ts, "message": {"role": "user",
+                    "content": "<command-name>/compact</command-name>\n<command-message>compact</command-message>\n<command-args>保留接口</command-args>"}}),
+                "## 用户 (2025-01-01T00:00:00Z)\n\n🔸 执行命令：/compact",
+            ),
+            (
+                json!({"type": "user", "timestamp": ts, "isCompactSummary": true,
+                    "message": {"role": "user", "content": "第一行\n\n第二行"}}),
+                "## 压缩摘要 (2025-01-01T00:00:00Z)\n\n> **上下文压缩摘要**\n>\n> 第一行\n>\n> 第二行",
+            ),
+            (
+                json!({"type": "user", "timestamp": ts, "isMeta": true,
+                    "message": {"role": "user", "content": "Base directory for this skill: /s"}}),
+                "## 系统（技能） (2025-01-01T00:00:00Z)\n\nBase directory for this skill: /s",
+            ),
+            (
+                json!({"type": "user", "timestamp": ts,
+                    "message": {"role": "user", "content": "<system-reminder>注意</system-reminder>"}}),
+                "## 系统 (2025-01-01T00:00:00Z)\n\n<system-reminder>注意</system-reminder>",
+            ),
+            (
+                json!({"type": "system", "timestamp": ts, "message": {"content": "Conversation compacted"}}),
+                "## 系统 (2025-01-01T00:00:00Z)\n\nConversation compacted",
+            ),
+            (
+                json!({"type": "attachment", "timestamp": ts}),
+                "## 系统（附件） (2025-01-01T00:00:00Z)\n",
+            ),
+        ];
+        for (msg, expected) in cases {
+            assert_eq!(render(&msg), expected, "{}", msg);
         }
-        _ => String::new(),
+    }
+
+    #[test]
+    fn test_to_markdown_skips_system_by_default() {
+        let messages = vec![
+            json!({"type": "user", "timestamp": "t1", "message": {"role": "user", "content": "问题"}}),
+            json!({"type": "user", "timestamp": "t2", "isMeta": true, "message": {"role": "user", "content": "注入"}}),
+            json!({"type": "file-history-snapshot", "timestamp": "t3"}),
+        ];
+        let markdown = to_markdown(&messages, "会话", &ExportOptions::default());
+        assert!(markdown.contains("## 用户 (t1)"));
+        assert!(!markdown.contains("注入"));
+
+        let options = ExportOptions { include_types: Some(vec!["system".into()]), exclude_noise: false, ..Default::default() };
+        let markdown = to_markdown(&messages, "会话", &options);
+        assert!(markdown.contains("## 系统 (t2)\n\n注入"));
+        assert!(!markdown.contains("问题"));
+
+        // 完整导出：对话与系统注入都保留
+        let markdown = to_markdown(&messages, "会话", &ExportOptions::complete());
+        assert!(markdown.contains("## 用户 (t1)") && markdown.contains("注入"));
+    }
+
+    #[test]
+    fn test_projects_overview_to_csv_escaping() {
+        let row = |name: &str, path: &str| ProjectOverviewRow {
+            name: name.to_string(),
+            path: path.to_string(),
+            session_count: 2,
+            message_count: 10,
+            total_size: 2048,
+            last_active: Some("2025-01-01 08:00:00 +08:00".to_string()),
+            first_created: None,
+        };
+        let csv = projects_overview_to_csv(&[
+            row("plain", "/work/plain"),
+            row("a,b", "/work/带 \"引号\" 的项目"),
+            row("multi", "第一行\n第二行\r\n第三行"),
+        ]);
+
+        let mut lines = csv.strip_prefix('\u{feff}').unwrap().split("\r\n");
+        assert_eq!(lines.next(), Some("name,path,sessionCount,messageCount,totalSize,lastActive,firstCreated"));
+        assert_eq!(lines.next(), Some("plain,/work/plain,2,10,2048,2025-01-01 08:00:00 +08:00,"));
+        assert_eq!(lines.next(), Some("\"a,b\",\"/work/带 \"\"引号\"\" 的项目\",2,10,2048,2025-01-01 08:00:00 +08:00,"));
+        // 字段内的换行原样保留在引号内，只有记录之间使用 CRLF
+        assert!(csv.contains("multi,\"第一行\n第二行\r\n第三行\",2,10"));
+        assert!(csv.ends_with("+08:00,\r\n"));
+    }
+
+    #[test]
+    fn test_to_markdown_exclude_noise() {
+        let messages = vec![
+            json!({"type": "user", "timestamp": "t1", "message": {"role": "user", "content": "问题"}}),
+            json!({"type": "user", "timestamp": "t2", "isCompactSummary": true, "message": {"role": "user", "content": "摘要"}}),
+            json!({"type": "user", "timestamp": "t3", "isMeta": true, "message": {"role": "user", "content": "注入"}}),
+        ];
+        let all_types = Some(vec!["user".into(), "compact_summary".into(), "system".into()]);
+
+        // 默认去噪：即使 include_types 选中，压缩摘要与系统注入也被剔除
+        let options = ExportOptions { include_types: all_types.clone(), ..Default::default() };
+        let markdown = to_markdown(&messages, "会话", &options);
+        assert!(markdown.contains("问题"));
+        assert!(!markdown.contains("摘要") && !markdown.contains("注入"));
+
+        // 关闭去噪后按 include_types 原样导出
+        let options = ExportOptions { include_types: all_types, exclude_noise: false, ..Default::default() };
+        let markdown = to_markdown(&messages, "会话", &options);
+        assert!(markdown.contains("摘要") && markdown.contains("注入"));
     }
 }
@@ -12,37 +12,107 @@
 //! 作为持久化的历史快照。
 //!
 //! ## 路径安全验证
-//! 所有写入/删除操作前验证目标路径是否在 `~/.claude/` 目录下，
-//! 防止意外修改非 Claude 数据文件。
+//! 所有写入/删除操作前验证目标路径是否在 `~/.claude/` 或用户登记的额外数据源目录
+//! （见 `data_sources`）下，防止意外修改非 Claude 数据文件。
+//!
+//! ## 会话锁定
+//! 写入 / 删除前检查目标会话是否被用户锁定为只读（见 `session_lock`），
+//! 锁定时直接返回 `SessionLocked` 错误，任何 `force` 参数都无法绕过。
+//!
+//! ## 原子写入
+//! 写入内容先落到同目录的 `<原文件名>.tmp`，刷盘后再重命名覆盖原文件，
+//! 写入中途崩溃时原文件保持完整。崩溃残留的 `.tmp` 由 `recovery` 在启动时检测。
+//!
+//! ## 磁盘空间预检
+//! 写入前检查目标分区剩余空间至少为写入内容的 `WRITE_SPACE_FACTOR` 倍，
+//! 不足时直接拒绝，避免备份成功后写入半途失败导致文件被截断。
+//!
+//! ## 恢复预检
+//! 从备份恢复前先检查备份内容本身是否可解析（备份可能恰好保存了损坏状态）。
+//! 问题行比例超过 `RESTORE_INVALID_RATIO_THRESHOLD` 或没有任何有效内容时，
+//! 调用方必须显式传入 `force` 才会写回。
+//!
+//! ## 跨窗口通知
+//! 写入 / 删除成功后通过 `events::emit_session_updated` 向所有窗口广播 `session-updated`。
+//!
+//! ## 审计
+//! 写入 / 删除结束后（无论成败）通过 `audit::record` 追加一条审计记录，审计失败不影响主操作。
 //!
 //! ## 使用方式
-//! 项目中所有对 Claude 数据文件的修改必须通过以下两个入口函数：
+//! 项目中所有对 Claude 数据文件的修改必须通过以下入口函数：
 //! - `safe_write_file()` — 安全写入文件
 //! - `safe_delete_file()` — 安全删除文件
+//! - `safe_restore_backup()` — 预检后从备份恢复文件（写回仍经过 `safe_write_file`）
 
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
 
 use crate::services::cache::AppCache;
-use crate::utils::path;
+use crate::services::audit::{self, AuditAction, AuditEntry};
+use crate::services::{data_sources, events, parser, session_lock};
+use crate::utils::{disk, filename, path, platform};
+
+/// 写入前要求的剩余空间倍数（备份 + 写入）
+pub const WRITE_SPACE_FACTOR: u64 = 2;
+
+/// 原子写入临时文件的后缀（追加在完整原文件名之后）
+pub const ATOMIC_TMP_SUFFIX: &str = ".tmp";
+
+/// 临时备份目录名（位于系统 TEMP 目录下）
+pub const TEMP_BACKUP_DIR_NAME: &str = "ccr-backups";
+
+/// 正在进行原子写入的临时文件，残留检测时排除
+static IN_FLIGHT_TMP: LazyLock<Mutex<HashSet<PathBuf>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// 恢复预检的问题行比例阈值
+///
+/// 备份中无法解析的行占比超过此值时，恢复需要调用方显式传入 `force`。
+pub const RESTORE_INVALID_RATIO_THRESHOLD: f64 = 0.1;
+
+/// 恢复预检结果中记录的问题行号上限
+const MAX_INVALID_LINE_NUMBERS: usize = 20;
 
 /// 备份配置（从 `~/.mo/CCR/backup-config.json` 加载）
 ///
-/// 控制主动备份（.ccbak）的启用状态。
+/// 控制主动备份（.ccbak）的启用状态、TEMP 下临时资源（图片、导出残留）的保留天数，
+/// 以及审计日志（见 `audit`）的保留天数。
 /// 临时备份始终启用，不受此配置影响。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BackupConfig {
     /// 是否启用主动备份（在原文件同目录创建 .ccbak 文件）
     pub auto_backup_enabled: bool,
+    /// 临时资源保留天数，超龄文件在应用退出时清理（0 表示不自动清理）
+    ///
+    /// 旧版配置文件没有此字段，反序列化时使用默认值。
+    #[serde(default = "default_temp_retention_days")]
+    pub temp_retention_days: u32,
+    /// 审计日志保留天数，超龄记录在应用退出时清理（0 表示永久保留）
+    #[serde(default = "default_audit_retention_days")]
+    pub audit_retention_days: u32,
+}
+
+/// 临时资源默认保留天数
+fn default_temp_retention_days() -> u32 {
+    7
+}
+
+/// 审计日志默认保留天数
+fn default_audit_retention_days() -> u32 {
+    90
 }
 
 impl Default for BackupConfig {
     fn default() -> Self {
         Self {
             auto_backup_enabled: false,
+            temp_retention_days: default_temp_retention_days(),
+            audit_retention_days: default_audit_retention_days(),
         }
     }
 }
@@ -63,16 +133,51 @@ pub struct TempBackupEntry {
     pub operation: String,
 }
 
+/// 备份文件恢复前的预检结果
+///
+/// JSONL 文件按行检查（空行不计入）；其他文件（如 `settings.json`）整体作为一行检查。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupCheck {
+    /// 参与检查的非空行总数
+    pub total_lines: usize,
+    /// 可解析为 JSON 对象的行数
+    pub valid_lines: usize,
+    /// 无法解析或不是 JSON 对象的行数
+    pub invalid_lines: usize,
+    /// 问题行占比（0.0 ~ 1.0，无内容时为 0.0）
+    pub invalid_ratio: f64,
+    /// 问题行的行号（从 1 开始，最多 `MAX_INVALID_LINE_NUMBERS` 条）
+    pub invalid_line_numbers: Vec<usize>,
+    /// 是否需要 `force` 才能恢复（问题比例超过阈值或没有任何有效内容）
+    pub requires_force: bool,
+}
+
+/// 备份恢复操作的结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreResult {
+    /// 恢复前对备份内容的预检结果
+    pub check: BackupCheck,
+    /// 是否已写回原文件（预检未通过且未传 `force` 时为 false）
+    pub restored: bool,
+    /// 写回前为当前文件创建的临时备份路径（使恢复本身也可撤销）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp_backup_path: Option<String>,
+}
+
 // ============ 公开入口函数 ============
 
 /// 安全写入文件（统一入口）
 ///
 /// 所有对 Claude 数据目录下文件的修改必须通过此函数。
 /// 执行流程：
-/// 1. 验证路径在 `~/.claude/` 目录下
-/// 2. 如果原文件存在，创建临时备份到系统 TEMP 目录（强制）
-/// 3. 如果启用主动备份且原文件存在，创建 `.ccbak` 文件（可选）
-/// 4. 执行实际写入
+/// 1. 验证路径在 `~/.claude/` 目录下，且目标会话未被锁定（`session_lock`）
+/// 2. 检查目标分区剩余空间至少为写入内容的 `WRITE_SPACE_FACTOR` 倍（备份 + 写入）
+/// 3. 如果原文件存在，创建临时备份到系统 TEMP 目录（强制）
+/// 4. 如果启用主动备份且原文件存在，创建 `.ccbak` 文件（可选）
+/// 5. 原子写入：写同目录 `.tmp` 文件后重命名覆盖原文件
+/// 6. 追加审计记录（失败时同样记录）
 ///
 /// # 参数
 /// - `file_path` - 目标文件的绝对路径
@@ -80,21 +185,43 @@ pub struct TempBackupEntry {
 /// - `operation` - 操作描述（用于备份记录，如 "delete_message"）
 /// - `cache` - AppCache 引用，用于注册临时备份记录
 ///
+/// # 返回值
+/// 原文件存在时返回本次临时备份的路径；新建文件（无需备份）时返回 None
+///
 /// # 错误
-/// 路径验证失败、备份创建失败或写入失败时返回错误
+/// 路径验证失败、会话已锁定（`SessionLocked`）、磁盘空间不足、备份创建失败或写入失败时返回错误
 pub async fn safe_write_file(
     file_path: &str,
     content: &[u8],
     operation: &str,
     cache: &AppCache,
-) -> Result<(), String> {
-    // 1. 路径安全验证
+) -> Result<Option<String>, String> {
+    let result = write_guarded(file_path, content, operation, cache).await;
+    let backup_path = result.as_ref().ok().cloned().flatten();
+    let entry = AuditEntry::new(operation, AuditAction::Write, file_path, Some(content.len() as u64), backup_path, &result);
+    audit::record(entry, cache).await;
+    result
+}
+
+/// `safe_write_file` 的实际写入流程（不含审计）
+async fn write_guarded(
+    file_path: &str,
+    content: &[u8],
+    operation: &str,
+    cache: &AppCache,
+) -> Result<Option<String>, String> {
+    // 1. 路径安全验证 + 锁定检查
     validate_claude_path(file_path)?;
+    session_lock::ensure_unlocked(file_path).await?;
 
-    // 2. 如果原文件存在，执行备份
+    // 2. 磁盘空间预检：避免备份成功后写入半途失败导致文件被截断
+    disk::ensure_free_space(Path::new(file_path), content.len() as u64 * WRITE_SPACE_FACTOR).await?;
+
+    // 3. 如果原文件存在，执行备份
+    let mut temp_backup_path = None;
     if Path::new(file_path).exists() {
         // 临时备份（强制）
-        create_temp_backup(file_path, operation, cache).await?;
+        temp_backup_path = Some(create_temp_backup(file_path, operation, cache).await?);
 
         // 主动备份（可选，根据配置决定）
         let config = read_backup_config_internal().await;
@@ -103,15 +230,18 @@ pub async fn safe_write_file(
         }
     }
 
-    // 3. 执行实际写入
-    tokio::fs::write(file_path, content)
-        .await
-        .map_err(|e| format!("写入文件失败: {}", e))
+    // 4. 原子写入：先写 `.tmp` 再重命名覆盖
+    write_atomic(file_path, content).await?;
+
+    // 5. 通知所有窗口（其他打开同一会话的窗口据此重新加载）
+    events::emit_session_updated(file_path, false);
+
+    Ok(temp_backup_path)
 }
 
 /// 安全删除文件（统一入口）
 ///
-/// 删除前同样执行完整的备份流程。
+/// 删除前同样执行完整的备份流程，结束后追加审计记录。
 ///
 /// # 参数
 /// - `file_path` - 要删除的文件的绝对路径
@@ -119,19 +249,32 @@ pub async fn safe_write_file(
 /// - `cache` - AppCache 引用，用于注册临时备份记录
 ///
 /// # 错误
-/// 路径验证失败、备份创建失败或删除失败时返回错误
+/// 路径验证失败、会话已锁定（`SessionLocked`）、备份创建失败或删除失败时返回错误
 pub async fn safe_delete_file(
     file_path: &str,
     operation: &str,
     cache: &AppCache,
 ) -> Result<(), String> {
-    // 1. 路径安全验证
+    let result = delete_guarded(file_path, operation, cache).await;
+    let backup_path = result.as_ref().ok().cloned().flatten();
+    audit::record(AuditEntry::new(operation, AuditAction::Delete, file_path, None, backup_path, &result), cache).await;
+    result.map(|_| ())
+}
+
+/// `safe_delete_file` 的实际删除流程（不含审计）
+///
+/// # 返回值
+/// 删除前创建的临时备份路径（文件不存在时为 None）
+async fn delete_guarded(file_path: &str, operation: &str, cache: &AppCache) -> Result<Option<String>, String> {
+    // 1. 路径安全验证 + 锁定检查
     validate_claude_path(file_path)?;
+    session_lock::ensure_unlocked(file_path).await?;
 
     // 2. 如果文件存在，执行备份
+    let mut temp_backup_path = None;
     if Path::new(file_path).exists() {
         // 临时备份（强制）
-        create_temp_backup(file_path, operation, cache).await?;
+        temp_backup_path = Some(create_temp_backup(file_path, operation, cache).await?);
 
         // 主动备份（可选）
         let config = read_backup_config_internal().await;
@@ -143,38 +286,280 @@ pub async fn safe_delete_file(
     // 3. 执行实际删除
     tokio::fs::remove_file(file_path)
         .await
-        .map_err(|e| format!("删除文件失败: {}", e))
+        .map_err(|e| format!("删除文件失败: {}", e))?;
+
+    events::emit_session_updated(file_path, true);
+    Ok(temp_backup_path)
+}
+
+/// 预检后从备份恢复文件（统一入口）
+///
+/// 先用 `check_backup_content` 检查备份内容，预检要求 `force` 而调用方未传入时
+/// 不做任何写入，仅返回检查结果供前端展示；否则通过 `safe_write_file` 写回原路径，
+/// 当前文件同样会先被备份。调用方负责持有会话写锁并在恢复后使缓存失效。
+///
+/// # 参数
+/// - `backup_path` - 备份文件的绝对路径
+/// - `original_path` - 要恢复到的原始文件绝对路径
+/// - `operation` - 操作描述（用于备份记录，如 "restore_temp_backup"）
+/// - `force` - 预检未通过时是否仍然恢复
+/// - `cache` - AppCache 引用，用于注册临时备份记录
+///
+/// # 返回值
+/// 预检结果与是否已写回
+///
+/// # 错误
+/// 备份文件读取失败、不是有效 UTF-8 文本，或写回失败时返回错误
+pub async fn safe_restore_backup(
+    backup_path: &str,
+    original_path: &str,
+    operation: &str,
+    force: bool,
+    cache: &AppCache,
+) -> Result<RestoreResult, String> {
+    let content = tokio::fs::read_to_string(backup_path)
+        .await
+        .map_err(|e| format!("读取备份文件失败: {}", e))?;
+
+    let is_jsonl = original_path.ends_with(".jsonl");
+    let check = check_backup_content(&content, is_jsonl);
+    if check.requires_force && !force {
+        return Ok(RestoreResult {
+            check,
+            restored: false,
+            temp_backup_path: None,
+        });
+    }
+
+    let temp_backup_path =
+        safe_write_file(original_path, content.as_bytes(), operation, cache).await?;
+
+    Ok(RestoreResult {
+        check,
+        restored: true,
+        temp_backup_path,
+    })
+}
+
+/// 检查备份内容是否可解析
+///
+/// JSONL 内容逐行使用 `parser::parse_line` 判定（与读取会话时的规则一致，
+/// 以原始文本保留的行即为问题行）；其他内容整体解析为一个 JSON 值。
+///
+/// # 参数
+/// - `content` - 备份文件的文本内容
+/// - `is_jsonl` - 是否按 JSONL 逐行检查
+pub fn check_backup_content(content: &str, is_jsonl: bool) -> BackupCheck {
+    let mut total_lines = 0;
+    let mut invalid_line_numbers = Vec::new();
+    let mut invalid_lines = 0;
+
+    if is_jsonl {
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            total_lines += 1;
+            if parser::raw_line(&parser::parse_line(line)).is_some() {
+                invalid_lines += 1;
+                if invalid_line_numbers.len() < MAX_INVALID_LINE_NUMBERS {
+                    invalid_line_numbers.push(index + 1);
+                }
+            }
+        }
+    } else if !content.trim().is_empty() {
+        total_lines = 1;
+        if serde_json::from_str::<serde_json::Value>(content).is_err() {
+            invalid_lines = 1;
+            invalid_line_numbers.push(1);
+        }
+    }
+
+    let valid_lines = total_lines - invalid_lines;
+    let invalid_ratio = if total_lines == 0 {
+        0.0
+    } else {
+        invalid_lines as f64 / total_lines as f64
+    };
+
+    BackupCheck {
+        total_lines,
+        valid_lines,
+        invalid_lines,
+        invalid_ratio,
+        invalid_line_numbers,
+        requires_force: valid_lines == 0 || invalid_ratio > RESTORE_INVALID_RATIO_THRESHOLD,
+    }
+}
+
+/// 从主动备份路径推导原始文件路径
+///
+/// 主动备份命名为 `<原始文件路径>.ccbak<timestamp>`，去掉该后缀即为原始路径。
+///
+/// # 返回值
+/// 路径不符合主动备份命名规则时返回 None
+pub fn auto_backup_original_path(backup_path: &str) -> Option<&str> {
+    let index = backup_path.rfind(".ccbak")?;
+    let suffix = &backup_path[index + ".ccbak".len()..];
+    if suffix.is_empty() || !suffix.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(&backup_path[..index])
+}
+
+/// 临时备份目录：`%TEMP%/ccr-backups/`（移动端为应用缓存目录下的 `ccr-backups/`）
+pub fn temp_backup_dir() -> PathBuf {
+    platform::temp_dir().join(TEMP_BACKUP_DIR_NAME)
+}
+
+/// 判断 `.tmp` 文件是否为本进程正在进行的原子写入
+///
+/// 残留检测据此排除写入中的文件，避免把正常写入误报为崩溃残留。
+pub fn is_write_in_flight(tmp_path: &Path) -> bool {
+    IN_FLIGHT_TMP
+        .lock()
+        .map(|set| set.contains(tmp_path))
+        .unwrap_or(false)
 }
 
 // ============ 内部辅助函数 ============
 
-/// 验证路径是否在 Claude 数据目录（`~/.claude/`）下
+/// 原子写入期间登记 `.tmp` 路径，离开作用域（含任务被取消）时自动注销
+struct InFlightGuard(PathBuf);
+
+impl InFlightGuard {
+    fn new(tmp_path: PathBuf) -> Self {
+        if let Ok(mut set) = IN_FLIGHT_TMP.lock() {
+            set.insert(tmp_path.clone());
+        }
+        Self(tmp_path)
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if let Ok(mut set) = IN_FLIGHT_TMP.lock() {
+            set.remove(&self.0);
+        }
+    }
+}
+
+/// 原子写入文件：写入 `<file_path>.tmp` 并刷盘后重命名覆盖目标文件
+///
+/// 写入或重命名失败时删除 `.tmp`；进程在此期间崩溃则 `.tmp` 残留，由 `recovery` 检测。
+///
+/// # 错误
+/// 临时文件写入、刷盘或重命名失败时返回错误
+pub(crate) async fn write_atomic(file_path: &str, content: &[u8]) -> Result<(), String> {
+    let tmp_path = PathBuf::from(format!("{}{}", file_path, ATOMIC_TMP_SUFFIX));
+    let _guard = InFlightGuard::new(tmp_path.clone());
+
+    let result = async {
+        let mut file = tokio::fs::File::create(&tmp_path)
+            .await
+            .map_err(|e| format!("创建临时文件失败: {}", e))?;
+        file.write_all(content)
+            .await
+            .map_err(|e| format!("写入文件失败: {}", e))?;
+        file.sync_all()
+            .await
+            .map_err(|e| format!("写入文件失败: {}", e))?;
+        drop(file);
+        tokio::fs::rename(&tmp_path, file_path)
+            .await
+            .map_err(|e| format!("替换原文件失败: {}", e))
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+    }
+    result
+}
+
+/// 验证路径是否在 Claude 数据目录（`~/.claude/` 或已登记的额外数据源）下
 ///
 /// 使用 `std::fs::canonicalize` 解析符号链接和 `..` 等路径组件，
-/// 确保最终路径确实位于 Claude 数据目录内，防止路径遍历攻击。
+/// 确保最终路径确实位于某个数据目录内，防止路径遍历攻击。
 ///
 /// # 错误
-/// 路径不在 `~/.claude/` 下时返回安全检查失败错误
+/// 路径不在任何数据目录下时返回安全检查失败错误
 pub(crate) fn validate_claude_path(file_path: &str) -> Result<(), String> {
-    let claude_path = path::get_claude_data_path()?;
+    let roots = data_sources::source_roots()?;
 
     // canonicalize 解析符号链接和相对路径组件
-    let canonical = std::fs::canonicalize(file_path)
-        .map_err(|e| format!("路径解析失败: {}", e))?;
-    let claude_canonical = std::fs::canonicalize(&claude_path)
+    // 新建文件时文件本身尚不存在，改为解析其所在目录
+    let target = Path::new(file_path);
+    let canonical = match (std::fs::canonicalize(target), target.parent(), target.file_name()) {
+        (Ok(canonical), _, _) => canonical,
+        (Err(_), Some(parent), Some(name)) if !target.exists() => std::fs::canonicalize(parent)
+            .map_err(|e| format!("路径解析失败: {}", e))?
+            .join(name),
+        (Err(e), _, _) => return Err(format!("路径解析失败: {}", e)),
+    };
+
+    // 默认数据源必须可解析；额外数据源目录暂不可用（如 WSL 未启动）时跳过
+    let claude_canonical = std::fs::canonicalize(&roots[0])
         .map_err(|e| format!("Claude 数据路径解析失败: {}", e))?;
+    let allowed = canonical.starts_with(&claude_canonical)
+        || roots[1..]
+            .iter()
+            .filter_map(|root| std::fs::canonicalize(root).ok())
+            .any(|root| canonical.starts_with(root));
 
-    if !canonical.starts_with(&claude_canonical) {
+    if !allowed {
         return Err(format!(
-            "安全检查失败：路径 {} 不在 Claude 数据目录 {} 下",
+            "安全检查失败：路径 {} 不在 Claude 数据目录 {} 或已登记的数据源下",
             file_path,
-            claude_path.display()
+            roots[0].display()
         ));
     }
 
     Ok(())
 }
 
+/// 路径（允许尚不存在）是否位于 Claude 数据目录或已登记的数据源内
+///
+/// 用于拒绝把导出、归档等输出写进数据目录。与 `validate_claude_path` 不同，
+/// 输出路径常含多级尚未创建的目录，因此经 `canonicalize_nearest` 解析；
+/// 无法解析的数据源（目录不存在）里也不会有需要保护的数据，直接跳过。
+///
+/// # 错误
+/// 路径的任何祖先都无法解析时返回错误
+pub(crate) fn is_within_data_sources(file_path: &str) -> Result<bool, String> {
+    let target = canonicalize_nearest(Path::new(file_path))?;
+    Ok(data_sources::source_roots()?
+        .iter()
+        .filter_map(|root| std::fs::canonicalize(root).ok())
+        .any(|root| target.starts_with(root)))
+}
+
+/// 规范化路径：自身不存在时规范化最近的已存在祖先，再拼回其余组件
+///
+/// 其余组件位于尚不存在的目录中，其中的 `..` 按字面回退一级。
+///
+/// # 错误
+/// 路径的任何祖先都无法解析时返回错误
+pub(crate) fn canonicalize_nearest(target: &Path) -> Result<PathBuf, String> {
+    let components: Vec<Component> = target.components().collect();
+    for existing in (1..=components.len()).rev() {
+        let Ok(mut resolved) = std::fs::canonicalize(components[..existing].iter().collect::<PathBuf>()) else {
+            continue;
+        };
+        for component in &components[existing..] {
+            match component {
+                Component::ParentDir => {
+                    resolved.pop();
+                }
+                Component::Normal(name) => resolved.push(name),
+                _ => {}
+            }
+        }
+        return Ok(resolved);
+    }
+    Err(format!("路径解析失败: {}", target.display()))
+}
+
 /// 获取当前 Unix 时间戳（秒）
 fn unix_timestamp() -> u64 {
     SystemTime::now()
@@ -200,12 +585,15 @@ fn formatted_timestamp() -> String {
 /// 使用完整原始文件名（含完整会话 UUID），避免不同会话截断后碰巧重名。
 ///
 /// 备份完成后将记录注册到 AppCache 的临时备份注册表中。
+///
+/// # 返回值
+/// 临时备份文件的绝对路径
 async fn create_temp_backup(
     file_path: &str,
     operation: &str,
     cache: &AppCache,
-) -> Result<(), String> {
-    let temp_dir = std::env::temp_dir().join("ccr-backups");
+) -> Result<String, String> {
+    let temp_dir = temp_backup_dir();
 
     // 确保临时备份目录存在
     if !temp_dir.exists() {
@@ -221,24 +609,28 @@ async fn create_temp_backup(
         .unwrap_or("unknown");
 
     let timestamp = unix_timestamp();
-    let backup_name = format!("{}_{}.bak", file_name, timestamp);
+    let backup_name = filename::sanitize_filename(
+        &format!("{}_{}.bak", file_name, timestamp),
+        filename::MAX_FILENAME_BYTES,
+    );
     let backup_path = temp_dir.join(&backup_name);
 
-    // 复制原文件到临时备份位置
-    tokio::fs::copy(file_path, &backup_path)
+    // 复制原文件到临时备份位置（TEMP 目录较深时可能超过 Windows 260 字符限制）
+    tokio::fs::copy(filename::to_long_path(file_path), filename::to_long_path(&backup_path))
         .await
         .map_err(|e| format!("创建临时备份失败: {}", e))?;
 
     // 注册到 AppCache 的临时备份注册表
+    let temp_path = backup_path.to_string_lossy().to_string();
     let entry = TempBackupEntry {
         original_path: file_path.to_string(),
-        temp_path: backup_path.to_string_lossy().to_string(),
+        temp_path: temp_path.clone(),
         created_at: format!("{}", timestamp),
         operation: operation.to_string(),
     };
     cache.register_temp_backup(entry);
 
-    Ok(())
+    Ok(temp_path)
 }
 
 /// 创建主动备份（.ccbak 文件，与原文件同目录）
@@ -276,3 +668,53 @@ pub(crate) async fn read_backup_config_internal() -> BackupConfig {
         Err(_) => BackupConfig::default(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_backup_content_jsonl() {
+        let content = "{\"type\":\"user\"}\n\n{\"type\":\"assistant\"}\n{\"type\":\"us\n";
+        let check = check_backup_content(content, true);
+        assert_eq!(check.total_lines, 3);
+        assert_eq!(check.valid_lines, 2);
+        assert_eq!(check.invalid_line_numbers, vec![4]);
+        assert!(check.requires_force);
+
+        let healthy = check_backup_content("{\"a\":1}\n{\"b\":2}\n", true);
+        assert_eq!(healthy.invalid_lines, 0);
+        assert!(!healthy.requires_force);
+
+        // 空备份没有任何有效内容，同样需要 force
+        assert!(check_backup_content("", true).requires_force);
+        assert!(check_backup_content("{\"model\":", false).requires_force);
+    }
+
+    #[test]
+    fn test_output_path_inside_data_dir_is_detected() {
+        let claude_dir = path::get_claude_data_path().unwrap();
+        std::fs::create_dir_all(&claude_dir).unwrap();
+        let claude_canonical = std::fs::canonicalize(&claude_dir).unwrap();
+
+        // 多级尚不存在的目录：解析到最近的已存在祖先后仍能识别
+        let nested = claude_dir.join("archive").join("2025").join("out.zip");
+        assert_eq!(canonicalize_nearest(&nested).unwrap(), claude_canonical.join("archive/2025/out.zip"));
+        assert!(is_within_data_sources(&nested.to_string_lossy()).unwrap());
+
+        // `..` 回退到数据目录之外
+        let escaped = claude_dir.join("missing").join("..").join("..").join("ccr-outside").join("out.zip");
+        assert!(!is_within_data_sources(&escaped.to_string_lossy()).unwrap());
+    }
+
+    #[test]
+    fn test_auto_backup_original_path() {
+        assert_eq!(
+            auto_backup_original_path("/a/b.jsonl.ccbak1740000000"),
+            Some("/a/b.jsonl")
+        );
+        assert_eq!(auto_backup_original_path("/a/b.jsonl"), None);
+        assert_eq!(auto_backup_original_path("/a/b.jsonl.ccbak"), None);
+        assert_eq!(auto_backup_original_path("/a/b.jsonl.ccbakx1"), None);
+    }
+}
@@ -0,0 +1,206 @@
+//! # 修复项共享工具函数
+//!
+//! Entry 档位修复反复用到的 JSON 操作：定位 `message.content` 数组、按条件过滤内容块、
+//! 收集与配对 tool_use / tool_result。各修复的 detect 与 execute 应共用这里的判定逻辑，
+//! 避免同一规则在多个修复中各写一份。
+//!
+//! 所有函数都只读写传入的消息，不访问文件系统。
+//!
+//! 部分函数暂时只有 strip_thinking 以外的后续修复项（孤立 tool_use 等）会用到，
+//! 标注 `allow(dead_code)` 保留。
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+use crate::models::message::SessionMessage;
+
+/// 获取消息的内容块类型（`block.type`）
+pub fn block_type(block: &Value) -> Option<&str> {
+    block.get("type").and_then(|v| v.as_str())
+}
+
+/// 获取消息的 `message.content` 数组
+///
+/// # 返回值
+/// content 不存在或不是数组（如纯字符串内容）时返回 None
+pub fn get_content_array(msg: &SessionMessage) -> Option<&Vec<Value>> {
+    msg.get("message")?.get("content")?.as_array()
+}
+
+/// 获取消息的 `message.content` 数组（可变）
+///
+/// # 返回值
+/// content 不存在或不是数组（如纯字符串内容）时返回 None
+pub fn get_content_array_mut(msg: &mut SessionMessage) -> Option<&mut Vec<Value>> {
+    msg.get_mut("message")?.get_mut("content")?.as_array_mut()
+}
+
+/// 按条件保留消息 `message.content` 中的内容块
+///
+/// # 参数
+/// - `msg` - 消息（可变引用）
+/// - `keep` - 返回 true 的内容块被保留
+///
+/// # 返回值
+/// 是否移除了至少一个内容块（即消息是否被修改）
+pub fn retain_blocks<F>(msg: &mut SessionMessage, mut keep: F) -> bool
+where
+    F: FnMut(&Value) -> bool,
+{
+    let Some(blocks) = get_content_array_mut(msg) else {
+        return false;
+    };
+    let original_len = blocks.len();
+    blocks.retain(|block| keep(block));
+    blocks.len() != original_len
+}
+
+/// 收集所有 `tool_use` 内容块的 id
+///
+/// 仅统计本地工具调用（`tool_use`），服务端工具（`server_tool_use`）的结果
+/// 与调用位于同一条 assistant 消息中，不参与配对。
+#[allow(dead_code)]
+pub fn collect_tool_use_ids(messages: &[SessionMessage]) -> HashSet<String> {
+    messages
+        .iter()
+        .filter_map(get_content_array)
+        .flatten()
+        .filter(|block| block_type(block) == Some("tool_use"))
+        .filter_map(|block| block.get("id").and_then(|v| v.as_str()))
+        .map(str::to_string)
+        .collect()
+}
+
+/// 单个工具调用在消息列表中的配对位置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ToolPair {
+    /// tool_use 块所在消息的索引（首次出现）
+    pub use_index: Option<usize>,
+    /// tool_result 块所在消息的索引（首次出现）
+    pub result_index: Option<usize>,
+}
+
+#[allow(dead_code)]
+impl ToolPair {
+    /// 是否只有一侧（孤立的 tool_use 或 tool_result）
+    pub fn is_orphan(&self) -> bool {
+        self.use_index.is_none() || self.result_index.is_none()
+    }
+}
+
+/// 按 tool_use_id 配对 tool_use 与 tool_result
+///
+/// # 参数
+/// - `messages` - 消息列表
+///
+/// # 返回值
+/// tool_use_id → 两侧所在消息的索引；缺失的一侧为 None
+pub fn pair_tool_results(messages: &[SessionMessage]) -> HashMap<String, ToolPair> {
+    let mut pairs: HashMap<String, ToolPair> = HashMap::new();
+
+    for (index, msg) in messages.iter().enumerate() {
+        let Some(blocks) = get_content_array(msg) else {
+            continue;
+        };
+        for block in blocks {
+            let (id, is_use) = match block_type(block) {
+                Some("tool_use") => (block.get("id"), true),
+                Some("tool_result") => (block.get("tool_use_id"), false),
+                _ => continue,
+            };
+            let Some(id) = id.and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let pair = pairs.entry(id.to_string()).or_default();
+            let slot = if is_use { &mut pair.use_index } else { &mut pair.result_index };
+            slot.get_or_insert(index);
+        }
+    }
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn assistant(blocks: Value) -> Value {
+        json!({ "type": "assistant", "message": { "role": "assistant", "content": blocks } })
+    }
+
+    fn user(blocks: Value) -> Value {
+        json!({ "type": "user", "message": { "role": "user", "content": blocks } })
+    }
+
+    #[test]
+    fn test_get_content_array() {
+        let mut msg = assistant(json!([{ "type": "text", "text": "hi" }]));
+        assert_eq!(get_content_array(&msg).map(Vec::len), Some(1));
+        get_content_array_mut(&mut msg).unwrap().clear();
+        assert_eq!(get_content_array(&msg).map(Vec::len), Some(0));
+
+        // 字符串内容、缺少 message 字段、非对象消息均返回 None
+        assert!(get_content_array(&user(json!("plain"))).is_none());
+        assert!(get_content_array(&json!({ "type": "summary" })).is_none());
+        assert!(get_content_array_mut(&mut Value::String("raw".into())).is_none());
+    }
+
+    #[test]
+    fn test_retain_blocks() {
+        let mut msg = assistant(json!([
+            { "type": "thinking", "thinking": "..." },
+            { "type": "text", "text": "a" },
+            { "type": "redacted_thinking", "data": "..." }
+        ]));
+
+        assert!(retain_blocks(&mut msg, |b| block_type(b) == Some("text")));
+        assert_eq!(
+            get_content_array(&msg).unwrap(),
+            &vec![json!({ "type": "text", "text": "a" })]
+        );
+
+        // 无块被移除时报告未修改
+        assert!(!retain_blocks(&mut msg, |_| true));
+        assert!(!retain_blocks(&mut user(json!("plain")), |_| false));
+    }
+
+    #[test]
+    fn test_collect_tool_use_ids() {
+        let messages = vec![
+            assistant(json!([
+                { "type": "tool_use", "id": "t1", "name": "Read", "input": {} },
+                { "type": "server_tool_use", "id": "s1", "name": "web_search", "input": {} }
+            ])),
+            user(json!([{ "type": "tool_result", "tool_use_id": "t1", "content": "ok" }])),
+            assistant(json!([{ "type": "tool_use", "id": "t2", "name": "Bash", "input": {} }])),
+        ];
+
+        let ids = collect_tool_use_ids(&messages);
+        assert_eq!(ids, HashSet::from(["t1".to_string(), "t2".to_string()]));
+    }
+
+    #[test]
+    fn test_pair_tool_results() {
+        let messages = vec![
+            assistant(json!([
+                { "type": "tool_use", "id": "t1", "name": "Read", "input": {} },
+                { "type": "tool_use", "id": "t2", "name": "Bash", "input": {} }
+            ])),
+            user(json!([{ "type": "tool_result", "tool_use_id": "t1", "content": "ok" }])),
+            user(json!([{ "type": "tool_result", "tool_use_id": "t3", "content": "stray" }])),
+            // 重复的结果只记录首次出现的位置
+            user(json!([{ "type": "tool_result", "tool_use_id": "t1", "content": "dup" }])),
+        ];
+
+        let pairs = pair_tool_results(&messages);
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(pairs["t1"], ToolPair { use_index: Some(0), result_index: Some(1) });
+        assert!(!pairs["t1"].is_orphan());
+        assert_eq!(pairs["t2"], ToolPair { use_index: Some(0), result_index: None });
+        assert!(pairs["t2"].is_orphan());
+        assert_eq!(pairs["t3"], ToolPair { use_index: None, result_index: Some(2) });
+        assert!(pairs["t3"].is_orphan());
+    }
+}
@@ -22,8 +22,12 @@
 //! 4. 在本文件中 `mod my_fix;` 引入模块
 //! 5. 在 `all_fixers()` 的返回数组中用对应的 `FixerExecutor` 变体注册
 //!
+//! Entry 档位修复操作 content 数组、配对 tool_use / tool_result 时，
+//! 优先使用 `helpers` 模块中的共享函数。
+//!
 //! 详细指南请参考 `docs/development/fixers-guide.md`。
 
+pub mod helpers;
 pub mod patch_anyrouter;
 pub mod patch_toolsearch;
 pub mod restore_toolsearch;
@@ -32,11 +36,13 @@ pub mod strip_thinking;
 
 use std::future::Future;
 use std::pin::Pin;
+use std::time::Instant;
 
 use serde::Serialize;
 use serde_json::Value;
 
 use crate::models::message::SessionMessage;
+use crate::services::audit;
 use crate::services::cache::AppCache;
 use crate::services::file_guard;
 use crate::services::parser;
@@ -141,6 +147,14 @@ pub struct FixResult {
     pub message: String,
     /// 受影响的消息行数（即被修改的 JSONL 行数）
     pub affected_lines: usize,
+    /// 本次修复创建的备份文件路径
+    ///
+    /// Entry / Content 档位由框架在写回时填充（file_guard 的临时备份路径）；
+    /// File / Full 档位由修复项自行填充，未创建备份时为 None。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_path: Option<String>,
+    /// 修复执行耗时（毫秒），由框架统一计时并填充，修复项返回 0 即可
+    pub elapsed_ms: u64,
 }
 
 // ============ 注册表类型定义 ============
@@ -303,60 +317,82 @@ pub async fn execute_by_id(
 
         // 根据档位构造 operation 标识（用于备份记录）
         let operation = format!("fixer_{}", fixer_id);
+        let started = Instant::now();
+
+        // 持有会话写锁直到修复完成，避免与界面上的编辑/删除交错覆盖
+        let _guard = cache.lock_session(session_file_path).await;
+
+        // 同一次修复涉及的所有写入 / 删除共享一个审计批次 ID
+        let mut result = audit::with_batch(async {
+            let result = match &fixer.executor {
+                // ---- Entry 档位：框架负责读写 ----
+                FixerExecutor::Entry(exec_fn) => {
+                    // 1. 框架读取所有消息
+                    let mut messages = parser::read_messages_for_write(session_file_path).await?;
+                    // 2. 修复逻辑在内存中操作消息列表
+                    let mut result = exec_fn(&mut messages, options).await?;
+                    // 3. 仅当有实际修改时，框架自动覆写（含双重备份），并带出临时备份路径
+                    if result.affected_lines > 0 {
+                        result.backup_path = parser::write_messages(
+                            session_file_path,
+                            &messages,
+                            &operation,
+                            cache,
+                        )
+                        .await?;
+                    }
+                    result
+                }
+
+                // ---- Content 档位：框架负责读写 ----
+                FixerExecutor::Content(exec_fn) => {
+                    // 1. 框架读取文件原始文本
+                    let content = tokio::fs::read_to_string(session_file_path)
+                        .await
+                        .map_err(|e| format!("读取文件内容失败: {}", e))?;
+                    // 2. 修复逻辑操作文本内容，返回新内容
+                    let (mut result, new_content) = exec_fn(&content, options).await?;
+                    // 3. 仅当有实际修改时，框架自动覆写，并带出临时备份路径
+                    if result.affected_lines > 0 {
+                        result.backup_path = file_guard::safe_write_file(
+                            session_file_path,
+                            new_content.as_bytes(),
+                            &operation,
+                            cache,
+                        )
+                        .await?;
+                    }
+                    result
+                }
 
-        return match &fixer.executor {
-            // ---- Entry 档位：框架负责读写 ----
-            FixerExecutor::Entry(exec_fn) => {
-                // 1. 框架读取所有消息
-                let mut messages = parser::read_messages(session_file_path).await?;
-                // 2. 修复逻辑在内存中操作消息列表
-                let result = exec_fn(&mut messages, options).await?;
-                // 3. 仅当有实际修改时，框架自动覆写（含双重备份）
-                if result.affected_lines > 0 {
-                    parser::write_messages(
-                        session_file_path,
-                        &messages,
-                        &operation,
-                        cache,
-                    )
-                    .await?;
+                // ---- File 档位：验证路径后交给修复自行操作 ----
+                FixerExecutor::File(exec_fn) => {
+                    // 框架预先验证路径在 ~/.claude/ 下
+                    file_guard::validate_claude_path(session_file_path)?;
+                    exec_fn(session_file_path, cache, options).await?
                 }
-                Ok(result)
-            }
-
-            // ---- Content 档位：框架负责读写 ----
-            FixerExecutor::Content(exec_fn) => {
-                // 1. 框架读取文件原始文本
-                let content = tokio::fs::read_to_string(session_file_path)
-                    .await
-                    .map_err(|e| format!("读取文件内容失败: {}", e))?;
-                // 2. 修复逻辑操作文本内容，返回新内容
-                let (result, new_content) = exec_fn(&content, options).await?;
-                // 3. 仅当有实际修改时，框架自动覆写
-                if result.affected_lines > 0 {
-                    file_guard::safe_write_file(
-                        session_file_path,
-                        new_content.as_bytes(),
-                        &operation,
-                        cache,
-                    )
-                    .await?;
+
+                // ---- Full 档位：完全权限，不做任何限制 ----
+                FixerExecutor::Full(exec_fn) => {
+                    exec_fn(session_file_path, cache, options).await?
                 }
-                Ok(result)
-            }
-
-            // ---- File 档位：验证路径后交给修复自行操作 ----
-            FixerExecutor::File(exec_fn) => {
-                // 框架预先验证路径在 ~/.claude/ 下
-                file_guard::validate_claude_path(session_file_path)?;
-                exec_fn(session_file_path, cache, options).await
-            }
-
-            // ---- Full 档位：完全权限，不做任何限制 ----
-            FixerExecutor::Full(exec_fn) => {
-                exec_fn(session_file_path, cache, options).await
-            }
-        };
+            };
+            Ok::<_, String>(result)
+        })
+        .await?;
+
+        // Entry 档位经 parser::write_messages 写回，已同步项目列表缓存；其余档位自行写文件，在此补上
+        if result.affected_lines > 0 && !matches!(fixer.executor, FixerExecutor::Entry(_)) {
+            cache.sync_session_file(session_file_path, None).await;
+        }
+        // 修复不压入撤销快照，旧快照已不能安全恢复
+        if result.affected_lines > 0 {
+            cache.clear_undo(session_file_path, "修复会话");
+        }
+
+        // 统一计时：覆盖修复项自身返回的 elapsed_ms
+        result.elapsed_ms = started.elapsed().as_millis() as u64;
+        return Ok(result);
     }
 
     Err(format!("未找到 ID 为 '{}' 的修复项", fixer_id))
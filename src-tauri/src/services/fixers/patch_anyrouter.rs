@@ -43,6 +43,7 @@ use serde_json::Value;
 
 use crate::services::cache::AppCache;
 use crate::services::fixers::{FixDefinition, FixLevel, FixResult};
+use crate::utils::platform;
 
 // ============ 常量 ============
 
@@ -164,6 +165,8 @@ async fn execute_inner() -> Result<FixResult, String> {
                 settings_path.display(),
             ),
             affected_lines: 0,
+            backup_path: None,
+            elapsed_ms: 0,
         });
     }
 
@@ -172,11 +175,13 @@ async fn execute_inner() -> Result<FixResult, String> {
         .map_err(|e| format!("序列化 JSON 失败: {}", e))?;
 
     // 第 5 步：创建备份（仅当原文件存在时）
+    let mut backup_path = None;
     if !original_content.is_empty() {
-        let backup_path = format!("{}{}", settings_path.display(), BACKUP_SUFFIX);
-        tokio::fs::write(&backup_path, &original_content)
+        let path = format!("{}{}", settings_path.display(), BACKUP_SUFFIX);
+        tokio::fs::write(&path, &original_content)
             .await
             .map_err(|e| format!("创建备份文件失败: {}", e))?;
+        backup_path = Some(path);
     }
 
     // 第 6 步：确保目录存在并写入新配置
@@ -209,12 +214,8 @@ async fn execute_inner() -> Result<FixResult, String> {
     ));
 
     // 如果原文件存在，附加备份路径信息
-    if !original_content.is_empty() {
-        report.push_str(&format!(
-            "\n备份文件: {}{}",
-            settings_path.display(),
-            BACKUP_SUFFIX,
-        ));
+    if let Some(ref path) = backup_path {
+        report.push_str(&format!("\n备份文件: {}", path));
     }
 
     Ok(FixResult {
@@ -222,12 +223,14 @@ async fn execute_inner() -> Result<FixResult, String> {
         message: report,
         // affected_lines 表示修改的配置项数量
         affected_lines: changes.len(),
+        backup_path,
+        elapsed_ms: 0,
     })
 }
 
 /// 定位 `~/.claude/settings.json` 文件路径
 ///
-/// 通过 `dirs::home_dir()` 获取用户主目录，
+/// 通过 `platform::home_dir()` 获取用户主目录，
 /// 拼接 `.claude/settings.json` 路径。
 ///
 /// # 返回值
@@ -236,7 +239,7 @@ async fn execute_inner() -> Result<FixResult, String> {
 /// # 错误
 /// 无法获取用户主目录时返回错误
 fn find_settings_path() -> Result<PathBuf, String> {
-    let home = dirs::home_dir()
+    let home = platform::home_dir()
         .ok_or_else(|| "无法获取用户主目录".to_string())?;
 
     Ok(home.join(CLAUDE_DIR_NAME).join(SETTINGS_FILENAME))
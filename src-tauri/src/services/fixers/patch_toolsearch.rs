@@ -50,6 +50,7 @@ use regex::bytes::Regex;
 
 use crate::services::cache::AppCache;
 use crate::services::fixers::{FixDefinition, FixLevel, FixResult};
+use crate::utils::{path, platform};
 
 // ============ 补丁定义常量 ============
 
@@ -98,6 +99,8 @@ struct PatchResult {
     success: bool,
     /// 结果消息
     message: String,
+    /// 本次补丁创建的备份文件路径（未写入补丁时为 None）
+    backup_path: Option<String>,
 }
 
 // ============ 公开接口 ============
@@ -186,6 +189,8 @@ async fn execute_inner() -> Result<FixResult, String> {
             )
             .to_string(),
             affected_lines: 0,
+            backup_path: None,
+            elapsed_ms: 0,
         });
     }
 
@@ -225,6 +230,9 @@ async fn execute_inner() -> Result<FixResult, String> {
         message: report,
         // affected_lines 用于表示成功补丁的安装数量
         affected_lines: success_count,
+        // 多个安装都创建了备份时返回第一个，其余见各安装的结果消息
+        backup_path: results.iter().find_map(|r| r.backup_path.clone()),
+        elapsed_ms: 0,
     })
 }
 
@@ -386,6 +394,7 @@ async fn apply_patch_to_installation(inst: &Installation) -> PatchResult {
                 description: desc,
                 success: false,
                 message: format!("读取文件失败: {}", e),
+                backup_path: None,
             };
         }
     };
@@ -398,6 +407,7 @@ async fn apply_patch_to_installation(inst: &Installation) -> PatchResult {
                 description: desc,
                 success: false,
                 message: format!("检查补丁状态失败: {}", e),
+                backup_path: None,
             };
         }
     };
@@ -409,6 +419,7 @@ async fn apply_patch_to_installation(inst: &Installation) -> PatchResult {
                 description: desc,
                 success: true,
                 message: "已经补丁过，跳过。".to_string(),
+                backup_path: None,
             };
         }
         "unknown" => {
@@ -417,6 +428,7 @@ async fn apply_patch_to_installation(inst: &Installation) -> PatchResult {
                 description: desc,
                 success: false,
                 message: "未找到目标字符串，可能版本不兼容。".to_string(),
+                backup_path: None,
             };
         }
         // "unpatched" — 继续执行补丁
@@ -431,6 +443,7 @@ async fn apply_patch_to_installation(inst: &Installation) -> PatchResult {
                 description: desc,
                 success: false,
                 message: format!("生成补丁字节失败: {}", e),
+                backup_path: None,
             };
         }
     };
@@ -440,6 +453,7 @@ async fn apply_patch_to_installation(inst: &Installation) -> PatchResult {
             description: desc,
             success: false,
             message: "未找到匹配项（状态检查与补丁不一致）".to_string(),
+            backup_path: None,
         };
     }
 
@@ -450,6 +464,7 @@ async fn apply_patch_to_installation(inst: &Installation) -> PatchResult {
             description: desc,
             success: false,
             message: format!("创建备份文件失败: {}", e),
+            backup_path: None,
         };
     }
 
@@ -464,11 +479,13 @@ async fn apply_patch_to_installation(inst: &Installation) -> PatchResult {
                 "补丁成功，共替换 {} 处。备份: {}",
                 count, backup_path,
             ),
+            backup_path: Some(backup_path),
         },
         Err(e) => PatchResult {
             description: desc,
             success: false,
             message: format!("写入补丁文件失败: {}", e),
+            backup_path: None,
         },
     }
 }
@@ -563,9 +580,9 @@ pub(super) async fn find_all_installations() -> Vec<Installation> {
 
 /// 获取用户主目录
 ///
-/// 封装 `dirs::home_dir()`，方便统一调用。
+/// 封装 `platform::home_dir()`，方便统一调用。
 fn home_dir() -> Option<PathBuf> {
-    dirs::home_dir()
+    platform::home_dir()
 }
 
 /// 在 npm/pnpm 包目录中搜索包含域名检查代码的 JS 文件
@@ -1142,7 +1159,7 @@ fn find_large_binary_sync(
 /// 命令成功时返回 stdout 输出（已 trim），失败或超时时返回 None
 async fn run_cmd_async(program: &str, args: &[&str]) -> Option<String> {
     // 先检查命令是否存在于 PATH 中
-    if which_sync(program).is_none() {
+    if path::which(program).is_none() {
         return None;
     }
 
@@ -1167,36 +1184,3 @@ async fn run_cmd_async(program: &str, args: &[&str]) -> Option<String> {
         _ => None,
     }
 }
-
-/// 同步检查程序是否存在于 PATH 中
-///
-/// 模拟 Python 的 `shutil.which()` 功能，
-/// 遍历 PATH 环境变量中的所有目录查找可执行文件。
-///
-/// # 参数
-/// - `program` — 程序名（如 "npm"、"pnpm"）
-fn which_sync(program: &str) -> Option<PathBuf> {
-    let path_var = std::env::var_os("PATH")?;
-
-    // Windows 上需要检查带扩展名的版本
-    let extensions: Vec<String> = if cfg!(target_os = "windows") {
-        std::env::var("PATHEXT")
-            .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string())
-            .split(';')
-            .map(|s| s.to_lowercase())
-            .collect()
-    } else {
-        vec![String::new()]
-    };
-
-    for dir in std::env::split_paths(&path_var) {
-        for ext in &extensions {
-            let candidate = dir.join(format!("{}{}", program, ext));
-            if candidate.is_file() {
-                return Some(candidate);
-            }
-        }
-    }
-
-    None
-}
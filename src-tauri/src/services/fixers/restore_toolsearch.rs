@@ -126,6 +126,8 @@ async fn execute_inner() -> Result<FixResult, String> {
             )
             .to_string(),
             affected_lines: 0,
+            backup_path: None,
+            elapsed_ms: 0,
         });
     }
 
@@ -152,6 +154,8 @@ async fn execute_inner() -> Result<FixResult, String> {
                 installations.len(),
             ),
             affected_lines: 0,
+            backup_path: None,
+            elapsed_ms: 0,
         });
     }
 
@@ -178,6 +182,8 @@ async fn execute_inner() -> Result<FixResult, String> {
         message: report,
         // affected_lines 用于表示成功恢复的安装数量
         affected_lines: success_count,
+        backup_path: None,
+        elapsed_ms: 0,
     })
 }
 
@@ -138,6 +138,8 @@ async fn execute_inner(
             success: true,
             message: "会话文件为空，无需修复".to_string(),
             affected_lines: 0,
+            backup_path: None,
+            elapsed_ms: 0,
         });
     }
 
@@ -197,6 +199,8 @@ async fn execute_inner(
             success: true,
             message: "未发现图片或文档内容块，无需修复".to_string(),
             affected_lines: 0,
+            backup_path: None,
+            elapsed_ms: 0,
         });
     }
 
@@ -215,6 +219,8 @@ async fn execute_inner(
                 total_images, keep_last
             ),
             affected_lines: 0,
+            backup_path: None,
+            elapsed_ms: 0,
         });
     } else {
         // keep_last == 0，全部清理
@@ -254,6 +260,8 @@ async fn execute_inner(
             success: true,
             message: "替换操作未产生实际变更".to_string(),
             affected_lines: 0,
+            backup_path: None,
+            elapsed_ms: 0,
         });
     }
 
@@ -270,6 +278,8 @@ async fn execute_inner(
             affected_count, stripped_count, kept_msg
         ),
         affected_lines: affected_count,
+        backup_path: None,
+        elapsed_ms: 0,
     })
 }
 
@@ -24,6 +24,7 @@ use std::future::Future;
 use std::pin::Pin;
 
 use crate::models::message::SessionMessage;
+use crate::services::fixers::helpers::{block_type, retain_blocks};
 use crate::services::fixers::{FixDefinition, FixLevel, FixResult};
 
 /// 返回该修复项的元数据定义
@@ -88,6 +89,8 @@ async fn execute_inner(
             success: true,
             message: "会话文件为空，无需修复".to_string(),
             affected_lines: 0,
+            backup_path: None,
+            elapsed_ms: 0,
         });
     }
 
@@ -95,10 +98,7 @@ async fn execute_inner(
     let mut affected_count = 0;
 
     for msg in messages.iter_mut() {
-        // SessionMessage 是 serde_json::Value
-        // 结构：{ "message": { "content": [ { "type": "thinking", ... }, ... ] }, ... }
-        let content_modified = remove_thinking_blocks(msg, &["message", "content"]);
-        if content_modified {
+        if retain_blocks(msg, |block| !is_thinking_block(block)) {
             affected_count += 1;
         }
     }
@@ -108,6 +108,8 @@ async fn execute_inner(
             success: true,
             message: "未发现 thinking 内容块，无需修复".to_string(),
             affected_lines: 0,
+            backup_path: None,
+            elapsed_ms: 0,
         });
     }
 
@@ -118,56 +120,12 @@ async fn execute_inner(
             affected_count
         ),
         affected_lines: affected_count,
+        backup_path: None,
+        elapsed_ms: 0,
     })
 }
 
-/// 从指定路径的 JSON 值中移除 thinking 类型的内容块
-///
-/// 沿着 `path` 指定的键路径深入 JSON 结构，找到 content 数组后，
-/// 过滤掉 `type` 为 `"thinking"` 或 `"redacted_thinking"` 的元素。
-///
-/// # 参数
-/// - `value` — 可变的 serde_json::Value 引用（整条消息）
-/// - `path` — 键路径数组（如 `["message", "content"]`）
-///
-/// # 返回值
-/// 如果确实移除了至少一个内容块，返回 true；否则返回 false
-fn remove_thinking_blocks(value: &mut serde_json::Value, path: &[&str]) -> bool {
-    // 沿路径深入到目标节点
-    let mut current = value as &mut serde_json::Value;
-    for &key in path {
-        match current.get_mut(key) {
-            Some(next) => current = next,
-            None => return false, // 路径不存在，跳过
-        }
-    }
-
-    // 目标节点必须是数组
-    let arr = match current.as_array() {
-        Some(arr) => arr,
-        None => return false,
-    };
-
-    // 检查是否存在 thinking 类型的内容块
-    let original_len = arr.len();
-    let filtered: Vec<serde_json::Value> = arr
-        .iter()
-        .filter(|item| {
-            // 保留 type 不是 thinking/redacted_thinking 的项
-            match item.get("type").and_then(|t| t.as_str()) {
-                Some("thinking") | Some("redacted_thinking") => false,
-                _ => true,
-            }
-        })
-        .cloned()
-        .collect();
-
-    // 如果长度没变化，说明没有 thinking 块
-    if filtered.len() == original_len {
-        return false;
-    }
-
-    // 用过滤后的数组替换原数组
-    *current = serde_json::Value::Array(filtered);
-    true
+/// 判断内容块是否为 thinking / redacted_thinking 块
+fn is_thinking_block(block: &serde_json::Value) -> bool {
+    matches!(block_type(block), Some("thinking") | Some("redacted_thinking"))
 }
@@ -0,0 +1,277 @@
+//! # 测试样本服务
+//!
+//! 为 classifier / transformer 的黄金样本回归测试提供支持：
+//! - `sanitize_session`：把真实会话脱敏成可提交的 fixture
+//! - 测试模块中的快照校验：对 `tests/fixtures/*.jsonl` 逐个执行 `classify` 和
+//!   `transform_session`，与同名 `.snap.json` 快照逐字比对
+//!
+//! ## 脱敏规则
+//! - 用户主目录（含 `~/.claude/projects/` 下的编码目录名）→ `/home/user`
+//! - UUID → 按首次出现顺序重写为 `00000000-0000-4000-8000-000000000001` 形式，
+//!   同一 UUID 在整个会话中映射一致，`parentUuid` 链保持完整
+//! - `toolu_` / `srvtoolu_` / `msg_` / `req_` 前缀的 API ID → `<前缀>_fixture0001` 形式
+//! - Anthropic API Key、Bearer token、邮箱地址 → 固定占位符
+//! - thinking 块的 `signature` → `REDACTED`
+//!
+//! 对话正文不做改写，提交前仍需人工检查内容。
+//!
+//! ## 更新快照
+//! 消息格式变化导致快照不一致时，确认新输出正确后执行：
+//! `UPDATE_GOLDEN=1 cargo test golden` 重新生成快照。
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+use crate::models::message::SessionMessage;
+
+/// UUID 匹配正则
+static UUID_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}").unwrap()
+});
+
+/// API 对象 ID 匹配正则（tool_use ID、消息 ID、请求 ID）
+static API_ID_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(srvtoolu|toolu|msg|req)_[A-Za-z0-9]{8,}\b").unwrap());
+
+/// 固定替换的敏感信息：(正则, 占位符)
+static SECRET_RES: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    vec![
+        (Regex::new(r"sk-ant-[A-Za-z0-9_\-]{8,}").unwrap(), "sk-ant-REDACTED"),
+        (Regex::new(r"(?i)\bbearer\s+[A-Za-z0-9._\-]{16,}").unwrap(), "Bearer REDACTED"),
+        (
+            Regex::new(r"[A-Za-z0-9._%+\-]+@[A-Za-z0-9.\-]+\.[A-Za-z]{2,}").unwrap(),
+            "user@example.com",
+        ),
+    ]
+});
+
+/// 脱敏后的主目录
+const FIXTURE_HOME: &str = "/home/user";
+
+/// 会话级脱敏上下文：保证同一 ID 在整个会话中映射一致
+struct Sanitizer {
+    /// 主目录的各种写法 → 替换文本（按长度降序，先替换更长的写法）
+    home_patterns: Vec<(String, &'static str)>,
+    /// 原始 UUID → 重写后的 UUID
+    uuids: HashMap<String, String>,
+    /// 原始 API ID → 重写后的 ID
+    api_ids: HashMap<String, String>,
+}
+
+impl Sanitizer {
+    fn new(home_dir: Option<&str>) -> Self {
+        let mut home_patterns = Vec::new();
+        if let Some(home) = home_dir.map(|h| h.trim_end_matches(['/', '\\'])).filter(|h| !h.is_empty()) {
+            home_patterns.push((home.to_string(), FIXTURE_HOME));
+            home_patterns.push((home.replace('\\', "/"), FIXTURE_HOME));
+            home_patterns.push((home.replace('\\', "\\\\"), FIXTURE_HOME));
+            // ~/.claude/projects/ 下的编码目录名：路径分隔符与冒号均编码为 '-'
+            home_patterns.push((home.replace(['/', '\\', ':'], "-"), "-home-user"));
+        }
+        home_patterns.sort_by_key(|(p, _)| std::cmp::Reverse(p.len()));
+        home_patterns.dedup_by(|a, b| a.0 == b.0);
+
+        Self {
+            home_patterns,
+            uuids: HashMap::new(),
+            api_ids: HashMap::new(),
+        }
+    }
+
+    /// 递归脱敏一个 JSON 值
+    fn scrub_value(&mut self, value: &mut Value) {
+        match value {
+            Value::String(s) => *s = self.scrub_str(s),
+            Value::Array(items) => items.iter_mut().for_each(|v| self.scrub_value(v)),
+            Value::Object(map) => {
+                for (key, v) in map.iter_mut() {
+                    if key == "signature" && v.is_string() {
+                        *v = Value::String("REDACTED".into());
+                    } else {
+                        self.scrub_value(v);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 脱敏单个字符串
+    fn scrub_str(&mut self, s: &str) -> String {
+        let mut text = s.to_string();
+        for (pattern, replacement) in &self.home_patterns {
+            if text.contains(pattern.as_str()) {
+                text = text.replace(pattern.as_str(), replacement);
+            }
+        }
+        for (re, replacement) in SECRET_RES.iter() {
+            text = re.replace_all(&text, *replacement).into_owned();
+        }
+
+        let uuids = &mut self.uuids;
+        text = UUID_RE
+            .replace_all(&text, |caps: &Captures| {
+                let next = uuids.len() + 1;
+                uuids
+                    .entry(caps[0].to_ascii_lowercase())
+                    .or_insert_with(|| format!("00000000-0000-4000-8000-{:012}", next))
+                    .clone()
+            })
+            .into_owned();
+
+        let api_ids = &mut self.api_ids;
+        API_ID_RE
+            .replace_all(&text, |caps: &Captures| {
+                let next = api_ids.len() + 1;
+                api_ids
+                    .entry(caps[0].to_string())
+                    .or_insert_with(|| format!("{}_fixture{:04}", &caps[1], next))
+                    .clone()
+            })
+            .into_owned()
+    }
+}
+
+/// 将会话消息脱敏为可提交的测试样本
+///
+/// 规则见模块文档。以原始文本保留的坏行同样会被脱敏。
+///
+/// # 参数
+/// - `messages` - 原始会话消息（`parser::read_messages` 的结果）
+/// - `home_dir` - 需要替换的用户主目录；None 时不做路径替换
+///
+/// # 返回值
+/// 脱敏后的消息列表，顺序与输入一致
+pub fn sanitize_session(messages: &[SessionMessage], home_dir: Option<&str>) -> Vec<SessionMessage> {
+    let mut sanitizer = Sanitizer::new(home_dir);
+    messages
+        .iter()
+        .map(|msg| {
+            let mut msg = msg.clone();
+            sanitizer.scrub_value(&mut msg);
+            msg
+        })
+        .collect()
+}
+
+/// 脱敏一段任意文本（诊断包、日志等），规则与 `sanitize_session` 相同
+///
+/// # 参数
+/// - `text` - 原始文本
+/// - `home_dir` - 需要替换的用户主目录；None 时不做路径替换
+pub fn sanitize_text(text: &str, home_dir: Option<&str>) -> String {
+    Sanitizer::new(home_dir).scrub_str(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{classifier, parser, transformer};
+    use serde_json::json;
+    use std::path::{Path, PathBuf};
+
+    /// 黄金样本目录
+    fn fixtures_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures")
+    }
+
+    /// 生成样本的快照文本：逐条分类结果 + 完整转换结果
+    fn snapshot(messages: &[SessionMessage]) -> String {
+        let classifications: Vec<String> = messages
+            .iter()
+            .map(|msg| format!("{:?}", classifier::classify(msg)))
+            .collect();
+        let (transformed, _, _) = transformer::transform_session(messages);
+        let value = json!({
+            "classifications": classifications,
+            "transformed": transformed,
+        });
+        serde_json::to_string_pretty(&value).unwrap() + "\n"
+    }
+
+    #[test]
+    fn test_golden_snapshots() {
+        let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+        let mut fixtures: Vec<PathBuf> = std::fs::read_dir(fixtures_dir())
+            .expect("缺少 tests/fixtures 目录")
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+            .collect();
+        fixtures.sort();
+        assert!(!fixtures.is_empty(), "tests/fixtures 下没有样本");
+
+        let mut failures = Vec::new();
+        for fixture in &fixtures {
+            let content = std::fs::read_to_string(fixture).unwrap();
+            let actual = snapshot(&parser::parse_jsonl(&content));
+            // 确定性：同一输入两次转换的输出必须逐字一致
+            assert_eq!(actual, snapshot(&parser::parse_jsonl(&content)), "{} 输出不确定", fixture.display());
+
+            let snap_path = fixture.with_extension("snap.json");
+            if update {
+                std::fs::write(&snap_path, &actual).unwrap();
+                continue;
+            }
+            let expected = std::fs::read_to_string(&snap_path).unwrap_or_default();
+            if expected != actual {
+                // 第一处不一致的行（一方是另一方的前缀时为较短一方的末尾）
+                let line = expected
+                    .lines()
+                    .zip(actual.lines())
+                    .take_while(|(e, a)| e == a)
+                    .count();
+                failures.push(format!(
+                    "{}: 第 {} 行起不一致\n  期望: {}\n  实际: {}",
+                    snap_path.display(),
+                    line + 1,
+                    expected.lines().nth(line).unwrap_or("<EOF>"),
+                    actual.lines().nth(line).unwrap_or("<EOF>"),
+                ));
+            }
+        }
+        assert!(
+            failures.is_empty(),
+            "快照不一致（确认新输出正确后用 UPDATE_GOLDEN=1 重新生成）:\n{}",
+            failures.join("\n")
+        );
+    }
+
+    #[test]
+    fn test_sanitize_session() {
+        let messages = vec![
+            json!({
+                "uuid": "7F3B2C1A-0000-4A1B-8C2D-123456789ABC",
+                "parentUuid": null,
+                "cwd": "/home/alice/repo",
+                "message": { "id": "msg_01AbCdEfGhIjKlMn", "content": [
+                    { "type": "thinking", "thinking": "…", "signature": "EqQBCkYIBxgC" },
+                    { "type": "text", "text": "key sk-ant-api03-abcdEFGH1234 mail alice@corp.io" },
+                ] },
+            }),
+            json!({
+                "uuid": "11111111-2222-4333-8444-555555555555",
+                "parentUuid": "7f3b2c1a-0000-4a1b-8c2d-123456789abc",
+                "message": { "content": "see /home/alice/.claude/projects/-home-alice-repo/x.jsonl" },
+            }),
+        ];
+        let out = sanitize_session(&messages, Some("/home/alice/"));
+
+        assert_eq!(out[0]["uuid"], "00000000-0000-4000-8000-000000000001");
+        assert_eq!(out[1]["parentUuid"], out[0]["uuid"]);
+        assert_eq!(out[1]["uuid"], "00000000-0000-4000-8000-000000000002");
+        assert_eq!(out[0]["cwd"], "/home/user/repo");
+        assert_eq!(out[0]["message"]["id"], "msg_fixture0001");
+        assert_eq!(out[0]["message"]["content"][0]["signature"], "REDACTED");
+        assert_eq!(
+            out[0]["message"]["content"][1]["text"],
+            "key sk-ant-REDACTED mail user@example.com"
+        );
+        assert_eq!(
+            out[1]["message"]["content"],
+            "see /home/user/.claude/projects/-home-user-repo/x.jsonl"
+        );
+    }
+}
@@ -0,0 +1,369 @@
+//! # 碎片项目检测与归并
+//!
+//! 在项目的子目录中误启动 Claude Code（如 `cd src && claude` 后立即退出）会在
+//! `~/.claude/projects/` 下生成一个几乎为空的独立项目。本模块识别这类「碎片项目」，
+//! 并支持把它的会话文件移回父项目目录。
+//!
+//! ## 判定规则（宁可漏判，不可误判）
+//! 项目同时满足以下条件才标记为父项目的碎片：
+//! 1. 至多 1 个会话，且该会话的 `cwd` 编码后恰好等于项目目录名（确认真实路径，
+//!    不依赖有歧义的 `decode_project_path`）
+//! 2. 存在另一个项目，其某个会话的 `cwd` 编码后等于该项目目录名，
+//!    且碎片路径是它的真子目录（有多个时取最深的一个）
+//! 3. 会话文件不超过 `MAX_FRAGMENT_FILE_BYTES`，且 user / assistant 消息少于
+//!    `MAX_FRAGMENT_MESSAGES` 条
+//!
+//! 条件 1、2 只用扫描得到的元数据判断；只有通过的极少数候选才读取文件统计消息数。
+//! 父项目必须在同一批扫描结果中，分页加载时跨页的碎片不会被标记。
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::models::project::Project;
+use crate::services::archive::Archived;
+use crate::services::cache::AppCache;
+use crate::services::{audit, drafts, file_guard, pins, scanner, session_lock, session_title};
+use crate::utils::path::encode_project_path;
+
+/// 碎片会话的 user / assistant 消息数上限（不含）
+const MAX_FRAGMENT_MESSAGES: usize = 3;
+
+/// 碎片会话文件的大小上限，超过时直接视为正常项目
+const MAX_FRAGMENT_FILE_BYTES: u64 = 65_536;
+
+/// 为碎片项目填充 `fragment_of`
+///
+/// # 参数
+/// - `projects` - 同一批扫描得到的项目列表
+pub async fn mark_fragments(projects: &mut [Project]) {
+    // 每个项目经 cwd 确认的真实路径（一个项目可能有多个大小写 / 分隔符不同的写法）
+    let mut roots: Vec<(PathBuf, String)> = Vec::new();
+    for project in projects.iter() {
+        for cwd in project.sessions.iter().filter_map(|s| s.cwd.as_deref()) {
+            if encode_project_path(cwd) == project.name
+                && !roots.iter().any(|(path, name)| name == &project.name && path == Path::new(cwd))
+            {
+                roots.push((PathBuf::from(cwd), project.name.clone()));
+            }
+        }
+    }
+    if roots.len() < 2 {
+        return;
+    }
+
+    let mut found = HashMap::new();
+    for project in projects.iter() {
+        let [session] = project.sessions.as_slice() else {
+            continue;
+        };
+        let Some(cwd) = session.cwd.as_deref() else {
+            continue;
+        };
+        if encode_project_path(cwd) != project.name
+            || session.file_size.is_none_or(|size| size > MAX_FRAGMENT_FILE_BYTES)
+        {
+            continue;
+        }
+
+        let Some(parent) = find_parent(Path::new(cwd), &project.name, &roots) else {
+            continue;
+        };
+        if count_messages(&session.file_path).await < MAX_FRAGMENT_MESSAGES {
+            found.insert(project.name.clone(), parent.to_string());
+        }
+    }
+
+    for project in projects.iter_mut() {
+        project.fragment_of = found.remove(&project.name);
+    }
+}
+
+/// 在已知项目路径中查找 `path` 最深的真祖先目录（排除项目自身）
+fn find_parent<'a>(path: &Path, own_name: &str, roots: &'a [(PathBuf, String)]) -> Option<&'a str> {
+    roots
+        .iter()
+        .filter(|(root, name)| name != own_name && path != root && path.starts_with(root))
+        .max_by_key(|(root, _)| root.components().count())
+        .map(|(_, name)| name.as_str())
+}
+
+/// 统计会话文件中的 user / assistant 消息数（达到上限即停止；读取失败时按上限处理）
+async fn count_messages(file_path: &str) -> usize {
+    let Ok(content) = tokio::fs::read_to_string(file_path).await else {
+        return MAX_FRAGMENT_MESSAGES;
+    };
+    content
+        .lines()
+        .filter(|line| {
+            serde_json::from_str::<serde_json::Value>(line).is_ok_and(|value| {
+                matches!(value.get("type").and_then(|t| t.as_str()), Some("user" | "assistant"))
+            })
+        })
+        .take(MAX_FRAGMENT_MESSAGES)
+        .count()
+}
+
+/// 校验项目目录名：不能为空、`.` / `..`，也不能包含路径分隔符
+pub(crate) fn validate_project_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(format!("无效的项目名称: {}", name));
+    }
+    Ok(())
+}
+
+/// 将碎片项目的全部内容移入父项目目录并删除碎片目录
+///
+/// 移动前重新扫描两个目录并按模块文档的规则复核，仅当 `fragment_name` 确实是
+/// `target_name` 的碎片时才执行。会话的草稿、CCR 别名与置顶状态随文件一起迁移。
+///
+/// # 参数
+/// - `projects_dir` - `~/.claude/projects/` 目录
+/// - `fragment_name` - 碎片项目目录名
+/// - `target_name` - 父项目目录名
+/// - `cache` - 内存缓存，移动后失效相关会话、把会话加入父项目并摘除碎片项目
+///
+/// # 返回值
+/// 移动的会话文件数
+///
+/// # 错误
+/// - 名称无效、目录不存在或复核不通过时返回错误
+/// - 父项目中已有同名文件、会话已锁定时返回错误（此时不移动任何文件）
+/// - 移动或删除目录失败时返回错误
+pub async fn merge_fragment(
+    projects_dir: &Path,
+    fragment_name: &str,
+    target_name: &str,
+    cache: &AppCache,
+) -> Result<usize, String> {
+    validate_project_name(fragment_name)?;
+    validate_project_name(target_name)?;
+    if fragment_name == target_name {
+        return Err("不能将项目合并到自身".to_string());
+    }
+    let fragment_dir = projects_dir.join(fragment_name);
+    let target_dir = projects_dir.join(target_name);
+    if !fragment_dir.is_dir() || !target_dir.is_dir() {
+        return Err(format!("项目目录不存在: {} / {}", fragment_name, target_name));
+    }
+
+    // 复核：前端的标记可能已过期（scan_project_dirs 会对这两个目录重新执行碎片判定）
+    let dirs = vec![
+        (fragment_name.to_string(), fragment_dir.clone()),
+        (target_name.to_string(), target_dir.clone()),
+    ];
+    let projects = scanner::scan_project_dirs(dirs, &HashMap::new(), scanner::ScanOptions::default(), &Archived::default()).await.projects;
+    let confirmed = projects
+        .iter()
+        .any(|p| p.name == fragment_name && p.fragment_of.as_deref() == Some(target_name));
+    if !confirmed {
+        return Err(format!("{} 不是 {} 的碎片项目，拒绝合并", fragment_name, target_name));
+    }
+
+    // 先检查全部条目，任何冲突或锁定都不移动
+    let mut entries = tokio::fs::read_dir(&fragment_dir)
+        .await
+        .map_err(|e| format!("读取项目目录失败: {}", e))?;
+    let mut moves = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| format!("读取项目目录失败: {}", e))?
+    {
+        let from = entry.path();
+        let to = target_dir.join(entry.file_name());
+        if tokio::fs::try_exists(&to).await.unwrap_or(true) {
+            return Err(format!("父项目中已存在同名文件: {}", to.display()));
+        }
+        let is_session = from.extension().and_then(|e| e.to_str()) == Some("jsonl");
+        if is_session {
+            session_lock::ensure_unlocked(&from.to_string_lossy()).await?;
+        }
+        moves.push((from, to, is_session));
+    }
+
+    let aliases = session_title::load_aliases().await.unwrap_or_default();
+    // 全部移动记入同一个审计批次
+    let moved_sessions = audit::with_batch(move_entries(moves, &aliases, cache)).await?;
+
+    tokio::fs::remove_dir(&fragment_dir)
+        .await
+        .map_err(|e| format!("删除碎片项目目录失败: {}", e))?;
+    cache.remove_project(&fragment_dir);
+    Ok(moved_sessions)
+}
+
+/// 逐个移动碎片项目中的条目
+///
+/// 会话文件经 `file_guard` 写入新位置后再删除原文件（原文件删除前备份，两步均记入审计）；
+/// 其他条目（会话的子 agent 目录等）在路径验证后直接重命名。
+///
+/// # 返回值
+/// 移动的会话文件数
+///
+/// # 错误
+/// 路径验证、写入、删除或重命名失败时返回错误（已移动的条目保持在新位置）
+async fn move_entries(
+    moves: Vec<(PathBuf, PathBuf, bool)>,
+    aliases: &BTreeMap<String, String>,
+    cache: &AppCache,
+) -> Result<usize, String> {
+    let mut moved_sessions = 0;
+    for (from, to, is_session) in moves {
+        let from_str = from.to_string_lossy().to_string();
+        let to_str = to.to_string_lossy().to_string();
+        if !is_session {
+            file_guard::validate_claude_path(&from_str)?;
+            file_guard::validate_claude_path(&to.parent().unwrap_or(&to).to_string_lossy())?;
+            tokio::fs::rename(&from, &to)
+                .await
+                .map_err(|e| format!("移动文件失败 {}: {}", from.display(), e))?;
+            continue;
+        }
+
+        let _guard = cache.lock_session(&from_str).await;
+        let content = tokio::fs::read(&from)
+            .await
+            .map_err(|e| format!("读取会话文件失败 {}: {}", from.display(), e))?;
+        file_guard::safe_write_file(&to_str, &content, "merge_fragment", cache).await?;
+        file_guard::safe_delete_file(&from_str, "merge_fragment", cache).await?;
+        moved_sessions += 1;
+
+        // 草稿、别名与置顶以会话路径为键，随文件迁移（失败只影响显示，不回滚移动）
+        if let Ok(Some(draft)) = drafts::get(&from_str).await
+            && drafts::save(&to_str, &draft).await.is_ok()
+        {
+            let _ = drafts::delete(&from_str).await;
+        }
+        if let Some(alias) = aliases.get(&session_lock::normalize(&from_str))
+            && session_title::set_alias(&to_str, alias).await.is_ok()
+        {
+            let _ = session_title::set_alias(&from_str, "").await;
+        }
+        let _ = pins::move_session(&from_str, &to_str).await;
+
+        cache.invalidate_session(&from_str);
+        cache.clear_undo(&from_str, "合并项目碎片");
+        cache.add_session_file(&to_str).await;
+    }
+    Ok(moved_sessions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::project::Session;
+
+    fn session(dir: &Path, id: &str, cwd: &str, lines: &[&str]) -> Session {
+        let file_path = dir.join(format!("{}.jsonl", id));
+        let content = lines.join("\n");
+        std::fs::write(&file_path, &content).unwrap();
+        Session {
+            id: id.to_string(),
+            name: None,
+            timestamp: String::new(),
+            message_count: 0,
+            file_path: file_path.to_string_lossy().to_string(),
+            summary: None,
+            first_prompt: None,
+            git_branch: None,
+            cwd: Some(cwd.to_string()),
+            tag: None,
+            created_at: None,
+            file_size: Some(content.len() as u64),
+            is_sidechain: false,
+            version: None,
+            locked: false,
+            has_draft: false,
+            updated_since_viewed: false,
+            alias: None,
+            pinned: false,
+            is_agent: false,
+            line_count: None,
+        }
+    }
+
+    fn project(cwd: &str, sessions: Vec<Session>) -> Project {
+        Project {
+            name: encode_project_path(cwd),
+            path: cwd.to_string(),
+            sessions,
+            fragment_of: None,
+            source: None,
+            pinned: false,
+            session_count: 0,
+            latest_timestamp: None,
+            archived: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mark_fragments_is_conservative() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        let user = r#"{"type":"user"}"#;
+        let assistant = r#"{"type":"assistant"}"#;
+
+        let mut projects = vec![
+            project("/w/app", vec![session(dir, "p", "/w/app", &[user, assistant, user, assistant])]),
+            // 子目录中只说了一句话：碎片
+            project("/w/app/src", vec![session(dir, "f", "/w/app/src", &[user, assistant])]),
+            // 子目录中消息足够多：正常项目
+            project("/w/app/docs", vec![session(dir, "d", "/w/app/docs", &[user, assistant, user])]),
+            // 不在任何已知项目之下
+            project("/w/other", vec![session(dir, "o", "/w/other", &[user])]),
+        ];
+        mark_fragments(&mut projects).await;
+
+        let app = encode_project_path("/w/app");
+        let marks: Vec<Option<&str>> = projects.iter().map(|p| p.fragment_of.as_deref()).collect();
+        assert_eq!(marks, vec![None, Some(app.as_str()), None, None]);
+    }
+
+    #[tokio::test]
+    async fn test_merge_fragment_moves_sessions_through_file_guard() {
+        let projects_dir = crate::utils::platform::test_home().join(".claude").join("projects");
+        let line = |cwd: &str, t: &str| {
+            format!(r#"{{"type":"{}","uuid":"{}-{}","cwd":"{}","timestamp":"2025-01-01T00:00:00.000Z","message":{{"role":"{}","content":"x"}}}}"#, t, cwd, t, cwd, t)
+        };
+        let (parent_cwd, fragment_cwd) = ("/merge-test/app", "/merge-test/app/src");
+        let (parent_name, fragment_name) = (encode_project_path(parent_cwd), encode_project_path(fragment_cwd));
+        let parent_dir = projects_dir.join(&parent_name);
+        let fragment_dir = projects_dir.join(&fragment_name);
+        std::fs::create_dir_all(&parent_dir).unwrap();
+        std::fs::create_dir_all(fragment_dir.join("f").join("subagents")).unwrap();
+        let parent_lines = [line(parent_cwd, "user"), line(parent_cwd, "assistant"), line(parent_cwd, "user")];
+        std::fs::write(parent_dir.join("p.jsonl"), parent_lines.join("\n")).unwrap();
+        let fragment_content = [line(fragment_cwd, "user"), line(fragment_cwd, "assistant")].join("\n");
+        std::fs::write(fragment_dir.join("f.jsonl"), &fragment_content).unwrap();
+        std::fs::write(fragment_dir.join("f").join("subagents").join("agent-1.jsonl"), "{}").unwrap();
+
+        let cache = AppCache::new();
+        let moved = merge_fragment(&projects_dir, &fragment_name, &parent_name, &cache).await.unwrap();
+
+        assert_eq!(moved, 1);
+        assert_eq!(std::fs::read_to_string(parent_dir.join("f.jsonl")).unwrap(), fragment_content);
+        assert!(parent_dir.join("f").join("subagents").join("agent-1.jsonl").exists());
+        assert!(!fragment_dir.exists());
+        // 合并到自身或非碎片项目被拒绝
+        assert!(merge_fragment(&projects_dir, &parent_name, &parent_name, &cache).await.is_err());
+
+        std::fs::remove_dir_all(&parent_dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_parent_prefers_deepest_and_validates_name() {
+        let roots = vec![
+            (PathBuf::from("/w"), "-w".to_string()),
+            (PathBuf::from("/w/app"), "-w-app".to_string()),
+            (PathBuf::from("/w/app-x"), "-w-app-x".to_string()),
+        ];
+        assert_eq!(find_parent(Path::new("/w/app/src"), "-w-app-src", &roots), Some("-w-app"));
+        // 只按路径组件匹配，`/w/app-x` 不是 `/w/app` 的子目录
+        assert_eq!(find_parent(Path::new("/w/app-x"), "-w-app-x", &roots), Some("-w"));
+        assert_eq!(find_parent(Path::new("/w"), "-w", &roots), None);
+
+        assert!(validate_project_name("-w-app").is_ok());
+        assert!(validate_project_name("..").is_err());
+        assert!(validate_project_name("a/b").is_err());
+    }
+}
@@ -0,0 +1,656 @@
+//! # 全局搜索服务
+//!
+//! 在所有项目的所有会话中搜索字面量或正则表达式，返回带上下文片段的命中列表。
+//! 匹配对象与会话内搜索相同（`search_texts` / `original_texts`）：已在会话缓存中且未被修改的
+//! 会话直接复用缓存的搜索文本，其余会话读取后经 `transformer` 转换。
+//!
+//! ## 并发与取消
+//! 每个会话文件一个 `JoinSet` 任务，以 `FILE_CONCURRENCY` 限制同时读取的文件数。
+//! 搜索在以下情况提前结束，返回已收集到的命中并标记 `cancelled`：
+//! - 前端以 `search_id` 调用 `cancel_search`
+//! - 超过 `timeout_ms`（默认 `DEFAULT_TIMEOUT_MS`）
+//!
+//! ## 过滤器（`GlobalSearchFilter`，可叠加）
+//! - **项目**：遍历项目目录时直接跳过未选中的项目
+//! - **时间范围**：先用会话文件 mtime 粗筛（mtime 早于 `after` 的会话整体跳过），
+//!   再按消息 timestamp 精筛
+//! - **消息类型**：按 classifier 分类后的 `display_type` 过滤命中
+//! - **噪音过滤**：默认排除 `system` 类型（system-reminder 等 CLI 注入文本、附件、system 条目），
+//!   `include_system` 为 true 时保留
+//!
+//! 任意过滤器组合的结果都是无过滤结果的子集。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+use memchr::memmem::Finder;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::models::display::DisplayMessage;
+use crate::services::cache::AppCache;
+use crate::services::{parser, scanner, transformer};
+use crate::utils::time;
+
+/// 单次全局搜索返回的命中数量上限
+pub const MAX_GLOBAL_SEARCH_HITS: usize = 500;
+
+/// 未指定 `timeout_ms` 时的搜索超时（毫秒）
+pub const DEFAULT_TIMEOUT_MS: u64 = 60_000;
+
+/// 同时读取的会话文件数上限
+const FILE_CONCURRENCY: usize = 8;
+
+/// 命中片段在匹配位置前后保留的字符数
+const SNIPPET_CONTEXT_CHARS: usize = 40;
+
+/// 噪音过滤排除的显示类型
+const NOISE_DISPLAY_TYPE: &str = "system";
+
+/// 进行中的搜索：`search_id` → 取消标记
+static ACTIVE_SEARCHES: LazyLock<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// 全局搜索过滤器
+///
+/// 对应前端 TypeScript 接口 `GlobalSearchFilter`，所有字段均可省略。
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalSearchFilter {
+    /// 只搜索这些项目（`Project.name`，即编码后的目录名）
+    #[serde(default)]
+    pub projects: Option<Vec<String>>,
+    /// 只保留该时间之后（含）的消息（ISO 8601）
+    #[serde(default)]
+    pub after: Option<String>,
+    /// 只保留该时间之前（含）的消息（ISO 8601）
+    #[serde(default)]
+    pub before: Option<String>,
+    /// 只保留这些显示类型的消息（"user" | "assistant" | "tool_result" | "compact_summary" | "system"）
+    #[serde(default)]
+    pub only_types: Option<Vec<String>>,
+    /// 是否保留 system 类型的命中（默认 false，即开启噪音过滤）
+    #[serde(default)]
+    pub include_system: bool,
+}
+
+/// 全局搜索选项
+///
+/// 对应前端 TypeScript 接口 `GlobalSearchOptions`，所有字段均可省略。
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalSearchOptions {
+    /// 是否把查询词作为正则表达式
+    #[serde(default)]
+    pub use_regex: bool,
+    /// 搜索 ID，传入后可通过 `cancel_search` 取消本次搜索
+    #[serde(default)]
+    pub search_id: Option<String>,
+    /// 超时（毫秒），未传时为 `DEFAULT_TIMEOUT_MS`
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+/// 解析后的过滤器（时间已转换为 Unix 毫秒）
+struct CompiledFilter {
+    after: Option<i64>,
+    before: Option<i64>,
+    only_types: Option<Vec<String>>,
+    include_system: bool,
+}
+
+impl CompiledFilter {
+    /// 解析时间参数
+    ///
+    /// # 错误
+    /// `after` / `before` 不是合法的 ISO 8601 时间时返回错误
+    fn new(filter: &GlobalSearchFilter) -> Result<Self, String> {
+        let parse = |value: &Option<String>| -> Result<Option<i64>, String> {
+            value
+                .as_deref()
+                .map(|s| time::parse_iso8601_millis(s).ok_or_else(|| format!("无效的时间: {}", s)))
+                .transpose()
+        };
+        Ok(Self {
+            after: parse(&filter.after)?,
+            before: parse(&filter.before)?,
+            only_types: filter.only_types.clone(),
+            include_system: filter.include_system,
+        })
+    }
+
+    /// 判断一条显示消息是否通过类型与时间过滤
+    fn accepts(&self, message: &DisplayMessage) -> bool {
+        if !self.include_system && message.display_type == NOISE_DISPLAY_TYPE {
+            return false;
+        }
+        if let Some(types) = &self.only_types
+            && !types.contains(&message.display_type)
+        {
+            return false;
+        }
+        if self.after.is_none() && self.before.is_none() {
+            return true;
+        }
+        // 设置了时间范围时，时间戳无法解析的消息不计入
+        let Some(millis) = time::parse_iso8601_millis(&message.timestamp) else {
+            return false;
+        };
+        self.after.is_none_or(|after| millis >= after) && self.before.is_none_or(|before| millis <= before)
+    }
+}
+
+/// 编译后的查询
+///
+/// 与 `AppCache::search_in_cache` 的模式选择一致：字面量大小写不敏感时在 `search_texts` 上匹配，
+/// 其余情况在 `original_texts` 上匹配。
+enum Matcher {
+    /// 字面量（`case_sensitive` 为 false 时 needle 已小写化）
+    Literal { finder: Box<Finder<'static>>, case_sensitive: bool },
+    /// 正则表达式（大小写不敏感时已加 `(?i)`）
+    Regex(Regex),
+}
+
+/// 单条消息内的匹配
+struct TextMatch<'t> {
+    /// 截取片段所用的文本
+    source: &'t str,
+    /// 首个匹配在 `source` 中的字节位置
+    position: usize,
+    /// 首个匹配的字节长度
+    len: usize,
+    /// 消息内的匹配次数（不重叠）
+    count: usize,
+}
+
+impl Matcher {
+    /// 编译查询词
+    ///
+    /// # 错误
+    /// 正则表达式无效时返回错误
+    fn new(query: &str, case_sensitive: bool, use_regex: bool) -> Result<Self, String> {
+        if use_regex {
+            let pattern = if case_sensitive { query.to_string() } else { format!("(?i){}", query) };
+            return Regex::new(&pattern)
+                .map(Matcher::Regex)
+                .map_err(|e| format!("无效正则表达式: {}", e));
+        }
+        let needle = if case_sensitive { query.to_string() } else { query.to_lowercase() };
+        Ok(Matcher::Literal { finder: Box::new(Finder::new(needle.as_bytes()).into_owned()), case_sensitive })
+    }
+
+    /// 在一条消息的搜索文本上匹配
+    fn find<'t>(&self, search_text: &'t str, original_text: &'t str) -> Option<TextMatch<'t>> {
+        match self {
+            Matcher::Literal { finder, case_sensitive } => {
+                let haystack = if *case_sensitive { original_text } else { search_text };
+                let position = finder.find(haystack.as_bytes())?;
+                // 小写化未改变字节长度时从原文截取片段，否则退回小写文本
+                let source = if original_text.len() == haystack.len() && original_text.is_char_boundary(position) {
+                    original_text
+                } else {
+                    haystack
+                };
+                let count = finder.find_iter(haystack.as_bytes()).count();
+                Some(TextMatch { source, position, len: finder.needle().len(), count })
+            }
+            Matcher::Regex(re) => {
+                let first = re.find(original_text)?;
+                let count = re.find_iter(original_text).count();
+                Some(TextMatch { source: original_text, position: first.start(), len: first.len(), count })
+            }
+        }
+    }
+}
+
+/// 单条全局搜索命中
+///
+/// 对应前端 TypeScript 接口 `GlobalSearchHit`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalSearchHit {
+    /// 项目名称（编码后的目录名）
+    pub project_name: String,
+    /// 会话 JSONL 文件的绝对路径
+    pub session_file_path: String,
+    /// 命中消息的原始 uuid
+    pub uuid: String,
+    /// 命中消息的 display_id（打开会话后据此定位）
+    pub display_id: String,
+    /// 命中消息的显示类型
+    pub display_type: String,
+    /// 命中消息的 ISO 8601 时间戳
+    pub timestamp: String,
+    /// 匹配位置附近的文本片段
+    pub snippet: String,
+    /// 该消息内的匹配次数
+    pub match_count: usize,
+}
+
+/// 全局搜索结果
+///
+/// 由 `search_all_sessions` command 返回，对应前端 TypeScript 接口 `GlobalSearchResult`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalSearchResult {
+    /// 命中列表，按匹配次数降序（相同时按时间倒序），最多 `MAX_GLOBAL_SEARCH_HITS` 条
+    pub hits: Vec<GlobalSearchHit>,
+    /// 实际搜索的会话数（被项目 / mtime 过滤跳过的不计）
+    pub sessions_searched: usize,
+    /// 命中数是否超过上限被截断
+    pub truncated: bool,
+    /// 是否因取消或超时提前结束（此时结果只覆盖部分会话）
+    pub cancelled: bool,
+}
+
+impl GlobalSearchResult {
+    /// 空结果
+    fn empty() -> Self {
+        Self { hits: Vec::new(), sessions_searched: 0, truncated: false, cancelled: false }
+    }
+}
+
+/// 进行中搜索的登记，drop 时自动注销
+struct SearchRegistration {
+    /// 搜索 ID（未传时不登记）
+    id: Option<String>,
+    /// 取消标记
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SearchRegistration {
+    /// 登记一次搜索；同 ID 的旧搜索被视为取消
+    fn new(id: Option<String>) -> Self {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        if let Some(id) = &id
+            && let Ok(mut active) = ACTIVE_SEARCHES.lock()
+            && let Some(previous) = active.insert(id.clone(), Arc::clone(&cancelled))
+        {
+            previous.store(true, Ordering::Relaxed);
+        }
+        Self { id, cancelled }
+    }
+}
+
+impl Drop for SearchRegistration {
+    fn drop(&mut self) {
+        if let Some(id) = &self.id
+            && let Ok(mut active) = ACTIVE_SEARCHES.lock()
+            && active.get(id).is_some_and(|flag| Arc::ptr_eq(flag, &self.cancelled))
+        {
+            active.remove(id);
+        }
+    }
+}
+
+/// 取消进行中的全局搜索
+///
+/// # 参数
+/// - `search_id` - 发起搜索时传入的 `GlobalSearchOptions::search_id`
+///
+/// # 返回值
+/// 找到对应的进行中搜索时返回 true（搜索已结束或 ID 不存在时返回 false）
+pub fn cancel_search(search_id: &str) -> bool {
+    let Ok(active) = ACTIVE_SEARCHES.lock() else {
+        return false;
+    };
+    active.get(search_id).inspect(|flag| flag.store(true, Ordering::Relaxed)).is_some()
+}
+
+/// 在所有会话中搜索
+///
+/// # 参数
+/// - `cache` - 应用缓存（已缓存且未修改的会话直接复用搜索文本）
+/// - `claude_path` - Claude 数据目录
+/// - `query` - 查询词（trim 后为空时直接返回空结果）
+/// - `case_sensitive` - 是否大小写敏感
+/// - `filter` - 过滤器
+/// - `options` - 正则 / 取消 / 超时选项
+///
+/// # 返回值
+/// 按匹配次数降序的命中列表；被取消或超时时返回已收集到的部分结果
+///
+/// # 错误
+/// 时间参数或正则表达式无效、项目目录无法读取，或后台搜索任务异常终止时返回错误
+pub async fn search_all_sessions(
+    cache: &AppCache,
+    claude_path: &str,
+    query: &str,
+    case_sensitive: bool,
+    filter: &GlobalSearchFilter,
+    options: &GlobalSearchOptions,
+) -> Result<GlobalSearchResult, String> {
+    // 提前校验时间参数与正则，避免扫描完才报错
+    let compiled = Arc::new(CompiledFilter::new(filter)?);
+    if query.trim().is_empty() {
+        return Ok(GlobalSearchResult::empty());
+    }
+    let matcher = Arc::new(Matcher::new(query, case_sensitive, options.use_regex)?);
+
+    let registration = SearchRegistration::new(options.search_id.clone());
+    let timeout = Duration::from_millis(options.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+    let deadline = tokio::time::Instant::now() + timeout;
+    let is_stopped = || registration.cancelled.load(Ordering::Relaxed) || tokio::time::Instant::now() >= deadline;
+
+    let mut result = GlobalSearchResult::empty();
+    let semaphore = Arc::new(Semaphore::new(FILE_CONCURRENCY));
+    let mut join_set = JoinSet::new();
+    'projects: for (project_name, dir) in scanner::list_project_dirs(claude_path).await? {
+        if filter.projects.as_ref().is_some_and(|p| !p.contains(&project_name)) {
+            continue;
+        }
+        for file_path in list_session_files(&dir, compiled.after).await {
+            if is_stopped() {
+                result.cancelled = true;
+                break 'projects;
+            }
+            let file_path = file_path.to_string_lossy().to_string();
+            let cached = cache.with_cached_texts(&file_path, |dm, search_texts, original_texts| {
+                collect_hits(&project_name, &file_path, dm, search_texts, original_texts, &matcher, &compiled)
+            });
+            if let Some(hits) = cached {
+                result.hits.extend(hits);
+                result.sessions_searched += 1;
+                continue;
+            }
+
+            let project_name = project_name.clone();
+            let (semaphore, matcher, compiled) = (Arc::clone(&semaphore), Arc::clone(&matcher), Arc::clone(&compiled));
+            let cancelled = Arc::clone(&registration.cancelled);
+            join_set.spawn(async move {
+                let _permit = semaphore
+                    .acquire_owned()
+                    .await
+                    .map_err(|e| format!("获取搜索并发许可失败: {}", e))?;
+                if cancelled.load(Ordering::Relaxed) {
+                    return Ok(None);
+                }
+                search_file(project_name, file_path, matcher, compiled).await
+            });
+        }
+    }
+
+    loop {
+        if registration.cancelled.load(Ordering::Relaxed) {
+            result.cancelled = true;
+            break;
+        }
+        match tokio::time::timeout_at(deadline, join_set.join_next()).await {
+            Err(_) => {
+                result.cancelled = true;
+                break;
+            }
+            Ok(None) => break,
+            Ok(Some(Ok(Ok(Some(hits))))) => {
+                result.hits.extend(hits);
+                result.sessions_searched += 1;
+            }
+            Ok(Some(Ok(Ok(None)))) => {}
+            Ok(Some(Ok(Err(e)))) => return Err(e),
+            Ok(Some(Err(e))) => log::warn!("全局搜索任务失败: {}", e),
+        }
+    }
+    join_set.abort_all();
+
+    result.hits.sort_by(|a, b| {
+        b.match_count
+            .cmp(&a.match_count)
+            .then_with(|| b.timestamp.cmp(&a.timestamp))
+            .then_with(|| a.session_file_path.cmp(&b.session_file_path))
+    });
+    result.truncated = result.hits.len() > MAX_GLOBAL_SEARCH_HITS;
+    result.hits.truncate(MAX_GLOBAL_SEARCH_HITS);
+    Ok(result)
+}
+
+/// 读取并搜索单个会话文件
+///
+/// transform 为 CPU 密集型（内部使用 rayon），与匹配一起放到阻塞线程池执行。
+///
+/// # 返回值
+/// 文件无法读取时返回 None（不计入已搜索的会话数）
+///
+/// # 错误
+/// 阻塞任务异常终止时返回错误
+async fn search_file(
+    project_name: String,
+    file_path: String,
+    matcher: Arc<Matcher>,
+    filter: Arc<CompiledFilter>,
+) -> Result<Option<Vec<GlobalSearchHit>>, String> {
+    let Ok(messages) = parser::read_messages(&file_path).await else {
+        return Ok(None);
+    };
+    tokio::task::spawn_blocking(move || {
+        let (session, search_texts, original_texts) = transformer::transform_session(&messages);
+        Some(collect_hits(
+            &project_name,
+            &file_path,
+            &session.display_messages,
+            &search_texts,
+            &original_texts,
+            &matcher,
+            &filter,
+        ))
+    })
+    .await
+    .map_err(|e| format!("搜索会话失败: {}", e))
+}
+
+/// 把一个会话中的匹配转换为命中列表
+fn collect_hits(
+    project_name: &str,
+    file_path: &str,
+    dm: &[DisplayMessage],
+    search_texts: &[String],
+    original_texts: &[String],
+    matcher: &Matcher,
+    filter: &CompiledFilter,
+) -> Vec<GlobalSearchHit> {
+    find_matches(dm, search_texts, original_texts, matcher, filter)
+        .into_iter()
+        .map(|(index, snippet, match_count)| GlobalSearchHit {
+            project_name: project_name.to_string(),
+            session_file_path: file_path.to_string(),
+            uuid: dm[index].source_uuid.clone(),
+            display_id: dm[index].display_id.clone(),
+            display_type: dm[index].display_type.clone(),
+            timestamp: dm[index].timestamp.clone(),
+            snippet,
+            match_count,
+        })
+        .collect()
+}
+
+/// 列出项目目录下的会话文件，mtime 早于 `after` 的会话整体跳过
+///
+/// 会话只会追加写入，mtime 不早于其中任何一条消息的时间，因此粗筛不会漏掉命中。
+async fn list_session_files(dir: &Path, after: Option<i64>) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return files;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata().await else { continue };
+        if !metadata.is_file() {
+            continue;
+        }
+        if let Some(after) = after {
+            let mtime = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as i64);
+            if mtime.is_some_and(|mtime| mtime < after) {
+                continue;
+            }
+        }
+        files.push(path);
+    }
+    files
+}
+
+/// 在一个会话的搜索文本中查找命中
+///
+/// # 返回值
+/// `(display_messages 下标, 文本片段, 匹配次数)` 列表，按消息顺序
+fn find_matches(
+    dm: &[DisplayMessage],
+    search_texts: &[String],
+    original_texts: &[String],
+    matcher: &Matcher,
+    filter: &CompiledFilter,
+) -> Vec<(usize, String, usize)> {
+    let mut matches = Vec::new();
+    for (index, message) in dm.iter().enumerate() {
+        let Some(found) = matcher.find(&search_texts[index], &original_texts[index]) else {
+            continue;
+        };
+        if !filter.accepts(message) {
+            continue;
+        }
+        matches.push((index, snippet(found.source, found.position, found.len), found.count));
+    }
+    matches
+}
+
+/// 截取匹配位置前后各 `SNIPPET_CONTEXT_CHARS` 个字符，换行替换为空格
+pub(crate) fn snippet(text: &str, position: usize, len: usize) -> String {
+    let start = text[..position]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT_CHARS - 1)
+        .map_or(0, |(i, _)| i);
+    let match_end = (position + len).min(text.len());
+    let end = text
+        .get(match_end..)
+        .and_then(|rest| rest.char_indices().nth(SNIPPET_CONTEXT_CHARS).map(|(i, _)| match_end + i))
+        .unwrap_or(text.len());
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    out.push_str(&text[start..end].replace(['\n', '\r'], " "));
+    if end < text.len() {
+        out.push('…');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_filters_are_subsets() {
+        let messages = vec![
+            json!({"type": "user", "uuid": "u1", "timestamp": "2025-01-01T00:00:00.000Z",
+                "message": {"role": "user", "content": "deploy the backup job"}}),
+            json!({"type": "assistant", "uuid": "a1", "parentUuid": "u1", "timestamp": "2025-02-01T00:00:00.000Z",
+                "message": {"role": "assistant", "content": [{"type": "text", "text": "Backup job deployed"}]}}),
+            json!({"type": "user", "uuid": "s1", "parentUuid": "a1", "timestamp": "2025-03-01T00:00:00.000Z", "isMeta": true,
+                "message": {"role": "user", "content": "<system-reminder>backup reminder</system-reminder>"}}),
+            json!({"type": "user", "uuid": "u2", "parentUuid": "s1", "timestamp": "2025-04-01T00:00:00.000Z",
+                "message": {"role": "user", "content": "check the BACKUP again"}}),
+        ];
+        let (session, search_texts, original_texts) = transformer::transform_session(&messages);
+        let dm = &session.display_messages;
+        let matcher = Matcher::new("backup", false, false).unwrap();
+        let run = |filter: &GlobalSearchFilter| -> Vec<String> {
+            let compiled = CompiledFilter::new(filter).unwrap();
+            find_matches(dm, &search_texts, &original_texts, &matcher, &compiled)
+                .into_iter()
+                .map(|(i, _, _)| dm[i].display_id.clone())
+                .collect()
+        };
+
+        let unfiltered = run(&GlobalSearchFilter { include_system: true, ..Default::default() });
+        assert_eq!(unfiltered, vec!["u1", "a1", "s1", "u2"]);
+
+        let filters = [
+            GlobalSearchFilter::default(),
+            GlobalSearchFilter { only_types: Some(vec!["user".into()]), include_system: true, ..Default::default() },
+            GlobalSearchFilter { after: Some("2025-02-01T00:00:00Z".into()), include_system: true, ..Default::default() },
+            GlobalSearchFilter { before: Some("2025-03-15T00:00:00Z".into()), include_system: true, ..Default::default() },
+            GlobalSearchFilter {
+                only_types: Some(vec!["user".into(), "system".into()]),
+                after: Some("2025-02-01T00:00:00Z".into()),
+                before: Some("2025-03-15T00:00:00Z".into()),
+                include_system: false,
+                ..Default::default()
+            },
+        ];
+        let results: Vec<Vec<String>> = filters.iter().map(run).collect();
+        for result in &results {
+            assert!(result.iter().all(|id| unfiltered.contains(id)));
+        }
+        // 默认开启噪音过滤
+        assert_eq!(results[0], vec!["u1", "a1", "u2"]);
+        assert_eq!(results[1], vec!["u1", "u2"]);
+        assert_eq!(results[2], vec!["a1", "s1", "u2"]);
+        assert_eq!(results[3], vec!["u1", "a1", "s1"]);
+        // 叠加后的结果同时是各单项过滤结果的子集
+        assert!(results[4].is_empty());
+        let stacked = run(&GlobalSearchFilter {
+            only_types: Some(vec!["user".into()]),
+            after: Some("2025-02-01T00:00:00Z".into()),
+            include_system: true,
+            ..Default::default()
+        });
+        assert_eq!(stacked, vec!["u2"]);
+        assert!(stacked.iter().all(|id| results[1].contains(id) && results[2].contains(id)));
+
+        assert!(CompiledFilter::new(&GlobalSearchFilter { after: Some("yesterday".into()), ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn test_regex_match_counts_and_cancel() {
+        let messages = vec![
+            json!({"type": "user", "uuid": "u1", "timestamp": "2025-01-01T00:00:00.000Z",
+                "message": {"role": "user", "content": "error E101, then Error E202"}}),
+            json!({"type": "user", "uuid": "u2", "parentUuid": "u1", "timestamp": "2025-01-02T00:00:00.000Z",
+                "message": {"role": "user", "content": "no codes here"}}),
+        ];
+        let (session, search_texts, original_texts) = transformer::transform_session(&messages);
+        let dm = &session.display_messages;
+        let compiled = CompiledFilter::new(&GlobalSearchFilter::default()).unwrap();
+
+        let matcher = Matcher::new(r"error e\d+", false, true).unwrap();
+        let hits = collect_hits("p", "/p/s.jsonl", dm, &search_texts, &original_texts, &matcher, &compiled);
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].uuid.as_str(), hits[0].match_count), ("u1", 2));
+        assert!(hits[0].snippet.contains("error E101"));
+
+        let matcher = Matcher::new("Error", true, false).unwrap();
+        let hits = collect_hits("p", "/p/s.jsonl", dm, &search_texts, &original_texts, &matcher, &compiled);
+        assert_eq!(hits[0].match_count, 1);
+        assert!(Matcher::new("(", false, true).is_err());
+
+        let registration = SearchRegistration::new(Some("s1".into()));
+        assert!(cancel_search("s1"));
+        assert!(registration.cancelled.load(Ordering::Relaxed));
+        drop(registration);
+        assert!(!cancel_search("s1"));
+    }
+
+    #[test]
+    fn test_snippet() {
+        let text = format!("{}needle{}", "a".repeat(50), "\nb".repeat(30));
+        let s = snippet(&text, 50, 6);
+        assert!(s.starts_with('…') && s.ends_with('…'));
+        assert!(s.contains("needle"));
+        assert!(!s.contains('\n'));
+        assert_eq!(snippet("short needle", 6, 6), "short needle");
+    }
+}
@@ -0,0 +1,175 @@
+//! # 会话关键词提取服务
+//!
+//! 基于缓存中的小写化搜索文本做简单的词频（TF）统计，为会话生成几个关键词标签。
+//! 纯规则实现，不依赖分词库：
+//! - **英文 / 代码**：按空白与符号切分（保留 `_`，使 `file_guard` 这类标识符成为一个词），
+//!   丢弃过短、纯数字和停用词
+//! - **中文**：连续汉字按 2-gram 切分，丢弃停用词以及含虚词（的、了、是……）的片段
+//!
+//! 两份停用词表内置在本模块中。
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+/// 会话缓存中保存的关键词数量上限（`top_n` 超过时截断到此值）
+pub const MAX_KEYWORDS: usize = 100;
+
+/// 英文词的最小长度（字节）
+const MIN_ASCII_WORD_LEN: usize = 3;
+
+/// 英文词的最大长度（字节），更长的多为哈希、base64 等无意义片段
+const MAX_ASCII_WORD_LEN: usize = 40;
+
+/// 英文停用词
+const EN_STOPWORDS: &[&str] = &[
+    "about", "above", "after", "again", "all", "also", "and", "any", "are", "because", "been",
+    "before", "being", "below", "between", "both", "but", "can", "could", "did", "does", "doing",
+    "down", "during", "each", "else", "few", "for", "from", "further", "had", "has", "have",
+    "having", "her", "here", "hers", "him", "his", "how", "into", "its", "itself", "just", "let",
+    "like", "more", "most", "must", "need", "not", "now", "off", "once", "only", "other", "our",
+    "out", "over", "own", "same", "she", "should", "some", "such", "than", "that", "the", "their",
+    "them", "then", "there", "these", "they", "this", "those", "through", "too", "under", "until",
+    "use", "used", "using", "very", "was", "were", "what", "when", "where", "which", "while", "who",
+    "why", "will", "with", "would", "yes", "you", "your", "yours", "don", "doesn", "didn", "isn",
+    "aren", "wasn", "won", "ok", "okay", "please", "sure", "thanks", "true", "false", "null",
+    "none", "http", "https", "www", "com",
+];
+
+/// 中文停用词（2 字词）
+const ZH_STOPWORDS: &[&str] = &[
+    "我们", "你们", "他们", "它们", "这个", "那个", "这些", "那些", "一个", "一下", "一些",
+    "可以", "如果", "因为", "所以", "但是", "然后", "还是", "或者", "而且", "并且", "已经",
+    "现在", "需要", "应该", "可能", "没有", "什么", "怎么", "这样", "那样", "其中",
+    "以及", "进行", "通过", "使用", "问题", "时候", "地方", "之后", "之前", "以下", "以上",
+    "当前", "所有", "每个", "直接", "只是", "就是", "还有", "自己", "目前", "一样", "好的",
+];
+
+/// 虚词：含有这些字的 2-gram 基本是跨词拼接出的片段，整体丢弃
+const ZH_STOP_CHARS: &str = "的了是在和也就都而及与着或不我你他她它这那有个吗呢吧啊把被给从对让将还又很";
+
+/// 单个关键词
+///
+/// 由 `get_session_keywords` command 返回，对应前端 TypeScript 接口 `SessionKeyword`。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionKeyword {
+    /// 关键词（小写）
+    pub word: String,
+    /// 在会话中出现的次数
+    pub count: usize,
+}
+
+/// 从搜索文本中提取词频最高的关键词
+///
+/// # 参数
+/// - `texts` - 小写化的搜索文本（`AppCache` 中的 `search_texts`）
+/// - `top_n` - 返回的关键词数量（超过 `MAX_KEYWORDS` 时截断）
+///
+/// # 返回值
+/// 按词频降序排列的关键词，词频相同时按字典序
+pub fn extract_keywords(texts: &[String], top_n: usize) -> Vec<SessionKeyword> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for text in texts {
+        tokenize(text, |token| *counts.entry(token.to_string()).or_default() += 1);
+    }
+
+    let mut keywords: Vec<SessionKeyword> = counts
+        .into_iter()
+        .map(|(word, count)| SessionKeyword { word, count })
+        .collect();
+    keywords.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+    keywords.truncate(top_n.min(MAX_KEYWORDS));
+    keywords
+}
+
+/// 切分文本，对每个保留下来的词调用 `emit`
+fn tokenize(text: &str, mut emit: impl FnMut(&str)) {
+    let mut word_start: Option<usize> = None;
+    let mut cjk_run: Vec<char> = Vec::new();
+
+    for (index, c) in text.char_indices() {
+        let is_word_char = c.is_ascii_alphanumeric() || c == '_';
+        let cjk = is_cjk(c);
+
+        if !is_word_char {
+            if let Some(start) = word_start.take() {
+                emit_ascii_word(&text[start..index], &mut emit);
+            }
+        } else if word_start.is_none() {
+            word_start = Some(index);
+        }
+
+        if cjk {
+            cjk_run.push(c);
+        } else if !cjk_run.is_empty() {
+            emit_cjk_bigrams(&cjk_run, &mut emit);
+            cjk_run.clear();
+        }
+    }
+    if let Some(start) = word_start {
+        emit_ascii_word(&text[start..], &mut emit);
+    }
+    emit_cjk_bigrams(&cjk_run, &mut emit);
+}
+
+/// 过滤并输出一个英文 / 代码词
+fn emit_ascii_word(word: &str, emit: &mut impl FnMut(&str)) {
+    let word = word.trim_matches('_');
+    if word.len() < MIN_ASCII_WORD_LEN
+        || word.len() > MAX_ASCII_WORD_LEN
+        || word.bytes().all(|b| b.is_ascii_digit() || b == b'_')
+        || EN_STOPWORDS.contains(&word)
+    {
+        return;
+    }
+    emit(word);
+}
+
+/// 把一段连续汉字按 2-gram 输出
+fn emit_cjk_bigrams(run: &[char], emit: &mut impl FnMut(&str)) {
+    let mut bigram = String::with_capacity(6);
+    for pair in run.windows(2) {
+        if pair.iter().any(|c| ZH_STOP_CHARS.contains(*c)) {
+            continue;
+        }
+        bigram.clear();
+        bigram.extend(pair);
+        if !ZH_STOPWORDS.contains(&bigram.as_str()) {
+            emit(&bigram);
+        }
+    }
+}
+
+/// 是否为 CJK 统一汉字（基本区与扩展 A 区）
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_keywords() {
+        let texts = vec![
+            "please fix the file_guard backup in file_guard.rs".to_string(),
+            "the backup of 会话文件 failed, 会话文件的备份失败 (error 404)".to_string(),
+            "we need to run cargo test for file_guard".to_string(),
+        ];
+        let keywords = extract_keywords(&texts, 5);
+        let words: Vec<&str> = keywords.iter().map(|k| k.word.as_str()).collect();
+
+        assert_eq!(keywords[0], SessionKeyword { word: "file_guard".to_string(), count: 3 });
+        assert!(words.contains(&"backup"));
+        assert!(words.contains(&"会话"));
+        assert!(words.contains(&"文件"));
+        // 停用词、纯数字、过短词与含虚词的 2-gram 被过滤
+        let all: Vec<String> = extract_keywords(&texts, MAX_KEYWORDS).into_iter().map(|k| k.word).collect();
+        for dropped in ["the", "please", "404", "we", "to", "件的", "的备"] {
+            assert!(!all.iter().any(|w| w == dropped), "{} 应被过滤", dropped);
+        }
+        assert!(all.iter().any(|w| w == "备份"));
+        assert_eq!(keywords.len(), 5);
+    }
+}
@@ -0,0 +1,155 @@
+//! # 会话最近查看时间
+//!
+//! 记录每个会话最近一次在 CCR 中打开的时间，用于在侧边栏标记「查看后又有新消息」的会话：
+//! 会话文件 mtime 晚于最近查看时间时 `Session::updated_since_viewed` 为 true。
+//! 从未在 CCR 中打开过的会话没有记录，不做标记。
+//!
+//! 记录持久化在 `~/.mo/CCR/last-viewed.json`（归一化路径 → Unix 毫秒）：
+//!
+//! ```json
+//! { "<会话文件绝对路径>": 1735689600000 }
+//! ```
+//!
+//! 只有前端查看会话时才刷新记录（`record_session_opened`）：打开会话时，以及正在查看的会话
+//! 收到 `session-updated` 事件（含本窗口的编辑、删除消息）时。写入会话本身不刷新记录，
+//! 批量修复、合并等写到未打开的会话时仍会正常标记。删除会话时清除记录。
+//!
+//! 记录文件经 `file_guard::write_atomic` 原子替换；每次写回时顺带剔除文件已不存在的会话，
+//! 被外部删除或移动的会话不会在记录中无限累积。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::SystemTime;
+
+use crate::services::{file_guard, session_lock};
+use crate::utils::path;
+
+/// 最近查看时间文件名（位于 `~/.mo/CCR/`）
+const LAST_VIEWED_FILE: &str = "last-viewed.json";
+
+/// 串行化记录文件的读-改-写，避免并发打开会话时互相覆盖
+static WRITE_LOCK: LazyLock<tokio::sync::Mutex<()>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+/// 归一化会话路径 → 最近查看时间（Unix 毫秒）
+pub type LastViewed = HashMap<String, i64>;
+
+/// 会话在最近一次查看后是否又被修改
+///
+/// # 参数
+/// - `viewed` - 最近查看时间记录
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `mtime_millis` - 会话文件的修改时间（Unix 毫秒，未知时为 None）
+pub fn is_updated(viewed: &LastViewed, session_file_path: &str, mtime_millis: Option<i64>) -> bool {
+    let Some(mtime) = mtime_millis else {
+        return false;
+    };
+    viewed
+        .get(&session_lock::normalize(session_file_path))
+        .is_some_and(|&last| mtime > last)
+}
+
+/// 文件的修改时间（Unix 毫秒，无法读取时为 None）
+pub fn file_mtime_millis(file_path: &str) -> Option<i64> {
+    let modified = std::fs::metadata(file_path).and_then(|m| m.modified()).ok()?;
+    let millis = modified.duration_since(SystemTime::UNIX_EPOCH).ok()?.as_millis();
+    i64::try_from(millis).ok()
+}
+
+/// 读取全部最近查看时间（文件不存在时返回空表）
+///
+/// # 错误
+/// 文件存在但读取或解析失败时返回错误
+pub async fn load_all() -> Result<LastViewed, String> {
+    let file_path = last_viewed_path()?;
+    if !file_path.exists() {
+        return Ok(LastViewed::new());
+    }
+    let content = tokio::fs::read_to_string(&file_path)
+        .await
+        .map_err(|e| format!("读取会话查看记录失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析会话查看记录失败: {}", e))
+}
+
+/// 把会话的最近查看时间记为当前时间
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+///
+/// # 错误
+/// 记录文件读取、解析或写入失败时返回错误
+pub async fn mark_viewed(session_file_path: &str) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp_millis();
+    update(|viewed| {
+        viewed.insert(session_lock::normalize(session_file_path), now);
+        true
+    })
+    .await
+}
+
+/// 清除会话的查看记录（会话删除时调用，没有记录时无操作）
+///
+/// # 错误
+/// 记录文件读取、解析或写入失败时返回错误
+pub async fn remove(session_file_path: &str) -> Result<(), String> {
+    update(|viewed| viewed.remove(&session_lock::normalize(session_file_path)).is_some()).await
+}
+
+/// 在写锁内读-改-写记录文件；`modify` 返回 false 表示没有变化，不写回（写回时剔除已不存在的会话）
+async fn update(modify: impl FnOnce(&mut LastViewed) -> bool) -> Result<(), String> {
+    let _guard = WRITE_LOCK.lock().await;
+    let mut viewed = load_all().await?;
+    if !modify(&mut viewed) {
+        return Ok(());
+    }
+
+    let file_path = last_viewed_path()?;
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("创建 CCR 配置目录失败: {}", e))?;
+    }
+    viewed.retain(|session_path, _| Path::new(session_path).exists());
+    let content = serde_json::to_string_pretty(&viewed)
+        .map_err(|e| format!("序列化会话查看记录失败: {}", e))?;
+    file_guard::write_atomic(&file_path.to_string_lossy(), content.as_bytes())
+        .await
+        .map_err(|e| format!("写入会话查看记录失败: {}", e))
+}
+
+/// 记录文件路径：`~/.mo/CCR/last-viewed.json`
+fn last_viewed_path() -> Result<PathBuf, String> {
+    Ok(path::get_ccr_config_path()?.join(LAST_VIEWED_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_updated() {
+        let viewed = LastViewed::from([(session_lock::normalize("/p/a.jsonl"), 1_000)]);
+        assert!(is_updated(&viewed, "/p/a.jsonl", Some(1_001)));
+        assert!(!is_updated(&viewed, "/p/a.jsonl", Some(1_000)));
+        assert!(!is_updated(&viewed, "/p/a.jsonl", None));
+        // 从未查看过的会话不标记
+        assert!(!is_updated(&viewed, "/p/b.jsonl", Some(9_999)));
+    }
+
+    #[tokio::test]
+    async fn test_update_prunes_missing_sessions() {
+        let dir = crate::utils::platform::test_home().join("last-viewed-prune");
+        std::fs::create_dir_all(&dir).unwrap();
+        let existing = dir.join("a.jsonl");
+        std::fs::write(&existing, "{}\n").unwrap();
+        let missing = dir.join("gone.jsonl");
+
+        mark_viewed(&missing.to_string_lossy()).await.unwrap();
+        mark_viewed(&existing.to_string_lossy()).await.unwrap();
+        let viewed = load_all().await.unwrap();
+        assert!(viewed.contains_key(&session_lock::normalize(&existing.to_string_lossy())));
+        assert!(!viewed.contains_key(&session_lock::normalize(&missing.to_string_lossy())));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+//! # 全局资源限制
+//!
+//! 批量修复、导出等重 I/O 操作都会「读全文件 + 备份复制 + 写全文件」，
+//! 并发过多时磁盘被打满，整个系统都会卡顿。`AppLimits` 作为 Tauri managed state，
+//! 提供一个全局共享的 I/O 并发信号量，所有重 I/O 的 command 在执行前先获取许可。
+//!
+//! ## 配置
+//! 并发数从 `~/.mo/CCR/limits.json` 的 `ioConcurrency` 读取（默认 `DEFAULT_IO_CONCURRENCY`），
+//! 限制在 `1..=MAX_IO_CONCURRENCY` 范围内，应用启动时加载，修改后重启生效。
+//!
+//! 同一文件的 `cacheMemoryMb` 配置会话缓存的内存上限（默认 `DEFAULT_CACHE_MEMORY_MB`），
+//! 由 `AppCache::with_memory_limit` 使用，见 `services::cache`。
+//!
+//! `oversizedTextKb` 配置单个文本块的折叠阈值（默认 `DEFAULT_OVERSIZED_TEXT_KB`），
+//! 超过该大小的 text 块在展示层替换为占位，见 `services::transformer`。
+//!
+//! ## 当前受限的操作
+//! - `execute_fixer`：一键修复
+//! - `export_session`：会话导出
+//! - `search_all_sessions`：全局搜索
+//! - `retrospect_export_zip`：项目回溯 ZIP 导出
+//! - `scan_projects`：统计消息数时同时读取的文件数（同一并发数，扫描内独立计数）
+//! - `get_project_stats`：项目级统计
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::utils::path;
+
+/// 默认的 I/O 并发数
+pub const DEFAULT_IO_CONCURRENCY: usize = 4;
+
+/// 默认的会话缓存内存上限（MB）
+pub const DEFAULT_CACHE_MEMORY_MB: u64 = 1024;
+
+/// 默认的超长文本块折叠阈值（KB）
+pub const DEFAULT_OVERSIZED_TEXT_KB: usize = 256;
+
+/// I/O 并发数上限（防止配置写错导致信号量形同虚设）
+const MAX_IO_CONCURRENCY: usize = 64;
+
+/// 配置文件名（位于 `~/.mo/CCR/`）
+const LIMITS_CONFIG_FILE: &str = "limits.json";
+
+/// 资源限制配置（`~/.mo/CCR/limits.json`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitsConfig {
+    /// 重 I/O 操作的最大并发数
+    #[serde(default = "default_io_concurrency")]
+    pub io_concurrency: usize,
+    /// 会话缓存的内存上限（MB）
+    #[serde(default = "default_cache_memory_mb")]
+    pub cache_memory_mb: u64,
+    /// 超长文本块折叠阈值（KB）
+    #[serde(default = "default_oversized_text_kb")]
+    pub oversized_text_kb: usize,
+}
+
+/// 默认 I/O 并发数（serde default）
+fn default_io_concurrency() -> usize {
+    DEFAULT_IO_CONCURRENCY
+}
+
+/// 默认会话缓存内存上限（serde default）
+fn default_cache_memory_mb() -> u64 {
+    DEFAULT_CACHE_MEMORY_MB
+}
+
+/// 默认超长文本块折叠阈值（serde default）
+fn default_oversized_text_kb() -> usize {
+    DEFAULT_OVERSIZED_TEXT_KB
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            io_concurrency: DEFAULT_IO_CONCURRENCY,
+            cache_memory_mb: DEFAULT_CACHE_MEMORY_MB,
+            oversized_text_kb: DEFAULT_OVERSIZED_TEXT_KB,
+        }
+    }
+}
+
+impl LimitsConfig {
+    /// 读取 `~/.mo/CCR/limits.json`（文件缺失或无法解析时使用默认值）
+    pub fn load() -> Self {
+        path::get_ccr_config_path()
+            .ok()
+            .and_then(|dir| std::fs::read_to_string(dir.join(LIMITS_CONFIG_FILE)).ok())
+            .and_then(|content| serde_json::from_str::<LimitsConfig>(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// 会话缓存内存上限（字节，至少 1 MB）
+    pub fn cache_memory_bytes(&self) -> usize {
+        usize::try_from(self.cache_memory_mb.max(1).saturating_mul(1024 * 1024)).unwrap_or(usize::MAX)
+    }
+
+    /// 超长文本块折叠阈值（字节，至少 1 KB）
+    pub fn oversized_text_bytes(&self) -> usize {
+        self.oversized_text_kb.max(1).saturating_mul(1024)
+    }
+}
+
+/// 全局资源限制（Tauri managed state）
+pub struct AppLimits {
+    /// 重 I/O 操作共享的信号量
+    io: Semaphore,
+    /// 信号量的许可数
+    io_concurrency: usize,
+}
+
+impl AppLimits {
+    /// 按 `~/.mo/CCR/limits.json` 创建（文件缺失或无法解析时使用默认值）
+    ///
+    /// 在应用启动时同步调用一次。
+    pub fn new() -> Self {
+        Self::with_io_concurrency(LimitsConfig::load().io_concurrency)
+    }
+
+    /// 以指定的 I/O 并发数创建（超出范围时截断到 `1..=MAX_IO_CONCURRENCY`）
+    pub fn with_io_concurrency(io_concurrency: usize) -> Self {
+        let io_concurrency = io_concurrency.clamp(1, MAX_IO_CONCURRENCY);
+        Self {
+            io: Semaphore::new(io_concurrency),
+            io_concurrency,
+        }
+    }
+
+    /// 配置的 I/O 并发数
+    ///
+    /// 供需要自行持有信号量的批量任务（如扫描时统计消息数）沿用同一限制。
+    pub fn io_concurrency(&self) -> usize {
+        self.io_concurrency
+    }
+
+    /// 获取一个 I/O 许可，许可在返回值被 drop 时归还
+    ///
+    /// 许可数用尽时异步等待，不阻塞 tokio 工作线程。
+    pub async fn acquire_io(&self) -> SemaphorePermit<'_> {
+        // 信号量从不 close，acquire 不会失败
+        self.io.acquire().await.expect("I/O 信号量已关闭")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn test_io_concurrency_is_enforced() {
+        let limits = Arc::new(AppLimits::with_io_concurrency(2));
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let started = Instant::now();
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let (limits, running, peak) = (limits.clone(), running.clone(), peak.clone());
+                tokio::spawn(async move {
+                    let _permit = limits.acquire_io().await;
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        // 并发数生效：峰值恰为 2，8 个任务至少分 4 轮执行
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert!(started.elapsed() >= Duration::from_millis(80));
+    }
+
+    #[test]
+    fn test_io_concurrency_is_clamped() {
+        assert_eq!(AppLimits::with_io_concurrency(0).io.available_permits(), 1);
+        assert_eq!(AppLimits::with_io_concurrency(1000).io.available_permits(), MAX_IO_CONCURRENCY);
+    }
+}
@@ -0,0 +1,155 @@
+//! # 会话链接提取服务
+//!
+//! 从会话的所有文本内容（text / thinking 块以及 tool_result 的输出）中提取 http/https URL，
+//! 按首次出现的顺序去重，并记录首次出现的消息与上下文片段，供前端「本会话引用的链接」面板展示。
+//!
+//! ## 尾部字符处理
+//! 正则只排除空白、引号、尖括号和全角标点，匹配结果再经 `trim_url` 修剪：
+//! 句末标点被去掉，`)` / `]` 仅在 URL 内部括号不配对时去掉。
+//! 这样 Markdown 链接 `[文档](https://a.com/x)` 的尾括号不会被吞进 URL，
+//! 而 `https://en.wikipedia.org/wiki/Rust_(language)` 这类自带括号的 URL 保持完整。
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::models::display::TransformedSession;
+use crate::services::export;
+
+/// URL 匹配正则（预编译，线程安全）
+///
+/// 匹配 `http://` 或 `https://` 开头、直到空白或分隔字符为止的连续文本。
+static RE_URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"https?://[^\s<>"'`，。、；：！？（）《》「」【】]+"#).unwrap()
+});
+
+/// 上下文片段在 URL 前后各保留的字符数
+const CONTEXT_CHARS: usize = 40;
+
+/// 会话中引用的单个链接
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLink {
+    /// 链接 URL
+    pub url: String,
+    /// 首次出现的消息 display_id
+    pub display_id: String,
+    /// 首次出现处的上下文片段（URL 前后各约 40 个字符，换行折叠为空格）
+    pub context: String,
+    /// 在整个会话中出现的次数
+    pub occurrences: usize,
+}
+
+/// 提取会话中的所有链接
+///
+/// # 参数
+/// - `session` - 已转换的会话数据
+///
+/// # 返回值
+/// 按首次出现顺序排列的去重链接列表
+pub fn extract_links(session: &TransformedSession) -> Vec<SessionLink> {
+    let mut links: Vec<SessionLink> = Vec::new();
+    // URL → 在 links 中的下标
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for msg in &session.display_messages {
+        for block in &msg.content {
+            let Some(text) = block_text(block) else {
+                continue;
+            };
+            for m in RE_URL.find_iter(&text) {
+                let url = trim_url(m.as_str());
+                if url.len() <= "https://".len() {
+                    continue;
+                }
+                if let Some(&i) = index.get(url) {
+                    links[i].occurrences += 1;
+                    continue;
+                }
+                index.insert(url.to_string(), links.len());
+                links.push(SessionLink {
+                    url: url.to_string(),
+                    display_id: msg.display_id.clone(),
+                    context: context_snippet(&text, m.start(), m.start() + url.len()),
+                    occurrences: 1,
+                });
+            }
+        }
+    }
+
+    links
+}
+
+/// 取内容块中可能包含链接的文本
+fn block_text(block: &Value) -> Option<String> {
+    let block_type = block.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    match block_type {
+        "text" => block.get("text").and_then(|v| v.as_str()).map(str::to_string),
+        "thinking" => block.get("thinking").and_then(|v| v.as_str()).map(str::to_string),
+        t if t.ends_with("tool_result") => Some(export::tool_result_text(block.get("content"))),
+        _ => None,
+    }
+}
+
+/// 修剪 URL 尾部不属于链接的字符
+///
+/// 反复去掉句末标点与 Markdown 强调符，以及不配对的 `)` / `]`。
+fn trim_url(url: &str) -> &str {
+    let mut end = url.len();
+    loop {
+        let trimmed = &url[..end];
+        let Some(last) = trimmed.chars().last() else {
+            break;
+        };
+        let strip = match last {
+            '.' | ',' | ';' | ':' | '!' | '?' | '*' | '_' | '~' => true,
+            ')' => trimmed.matches('(').count() < trimmed.matches(')').count(),
+            ']' => trimmed.matches('[').count() < trimmed.matches(']').count(),
+            _ => false,
+        };
+        if !strip {
+            break;
+        }
+        end -= last.len_utf8();
+    }
+    &url[..end]
+}
+
+/// 截取 `[start, end)` 前后各 `CONTEXT_CHARS` 个字符作为上下文片段
+fn context_snippet(text: &str, start: usize, end: usize) -> String {
+    let before: String = {
+        let chars: Vec<char> = text[..start].chars().rev().take(CONTEXT_CHARS).collect();
+        chars.into_iter().rev().collect()
+    };
+    let after: String = text[end..].chars().take(CONTEXT_CHARS).collect();
+    format!("{}{}{}", before, &text[start..end], after)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trim_url() {
+        assert_eq!(trim_url("https://a.com/x)"), "https://a.com/x");
+        assert_eq!(trim_url("https://a.com/x)."), "https://a.com/x");
+        assert_eq!(
+            trim_url("https://en.wikipedia.org/wiki/Rust_(language)"),
+            "https://en.wikipedia.org/wiki/Rust_(language)"
+        );
+        assert_eq!(trim_url("https://a.com/**"), "https://a.com/");
+    }
+
+    #[test]
+    fn test_markdown_link_match() {
+        let text = "见 [文档](https://docs.rs/regex/latest/regex/)，以及 <https://a.com>。";
+        let urls: Vec<&str> = RE_URL.find_iter(text).map(|m| trim_url(m.as_str())).collect();
+        assert_eq!(urls, vec!["https://docs.rs/regex/latest/regex/", "https://a.com"]);
+    }
+}
@@ -4,25 +4,107 @@
 //! - `scanner` - 文件系统扫描，支持并行 I/O
 //! - `parser` - JSONL 文件的高性能解析和写入
 //! - `cache` - 内存缓存管理（项目列表缓存和会话消息 LRU 缓存）
+//! - `cache_check` - 缓存一致性自检：会话缓存与项目列表缓存逐项对比磁盘状态（仅诊断用）
+//! - `appearance` - 外观配置：settings.json 的 statusLine / outputStyle 与自定义输出样式文件
+//! - `archive` - 项目归档：归档列表持久化，默认扫描结果中隐藏归档项目（不动原始文件）
+//! - `audit` - 敏感操作审计：file_guard 的写入 / 删除追加到 `audit.jsonl`，支持按路径分页查询与保留清理
+//! - `backups` - 备份浏览：汇总临时备份与 `.ccbak` 主动备份，支持按会话过滤
+//! - `benchmark` - 端到端基准测试：扫描、最大会话读取与搜索的分阶段计时（P50 / P95）
+//! - `branches` - 会话分叉：基于 parentUuid 构建消息树，枚举分叉点与分支路径
+//! - `data_sources` - 多数据源：登记额外的 Claude 数据目录，分别或合并扫描
+//! - `command_trace` - 命令级请求日志：主要 command 的耗时与结果写入环形缓冲，慢命令写 warn 日志
+//! - `compat` - 版本兼容性统计：会话与数据目录中 Claude Code version 的分布
+//! - `drafts` - 会话草稿箱：为会话保存下次 resume 时要补充的内容
+//! - `diagnostic_bundle` - 反馈诊断包：收集版本、自检、缓存统计、命令耗时、日志尾部与会话结构统计，脱敏后打包为 ZIP
+//! - `diagnostics` - 启动自检：数据目录、读写权限、磁盘空间与 CLI 可用性
+//! - `code_blocks` - 代码块提取：解析 assistant 文本中的围栏代码块（容错嵌套与未闭合），按内容去重并批量保存
 //! - `classifier` - 消息分类器：将原始消息分类为 user/assistant/system 等类型
 //! - `transformer` - 消息转换器：将原始消息转换为前端可渲染的 DisplayMessage
+//! - `project_filter` - 项目树关键词过滤：按项目路径、会话 ID、会话名称大小写不敏感匹配
+//! - `project_meta` - 项目元数据：比对解码路径与会话 cwd，持久化用户确认的真实路径覆盖
+//! - `project_stats` - 项目级统计：并行汇总项目下全部会话的数量、行数、字节数与 token 用量
+//! - `project_summary` - 项目摘要：读取项目目录下 CLAUDE.md / README.md 的开头部分
 //! - `export` - 会话导出服务：Markdown/JSON 格式导出
+//! - `global_search` - 全局搜索：跨所有会话搜索，支持项目 / 时间范围 / 消息类型过滤
+//! - `events` - 跨窗口事件广播：文件写入后通知所有窗口
+//! - `limits` - 全局资源限制：重 I/O 操作共享的并发信号量（managed state）
+//! - `keywords` - 关键词提取：基于搜索文本的分词与词频统计
+//! - `last_viewed` - 会话最近查看时间：标记「查看后又有新消息」的会话
+//! - `links` - 链接提取：收集会话文本中引用的 http/https URL
+//! - `edit_history` - 消息编辑历史：编辑前的旧版本持久化与回滚
+//! - `fixture` - 测试样本：会话脱敏与 classifier / transformer 黄金快照回归测试
 //! - `file_guard` - 文件写入守卫：统一文件修改入口 + 双重备份机制
+//! - `recovery` - 异常退出恢复：检测原子写入残留的 `.tmp` 文件并按用户选择处理
+//! - `path_set_store` - 路径集合存储：置顶 / 归档等归一化路径列表的读取、锁内修改与原子写入
+//! - `pins` - 置顶：项目与会话的置顶列表持久化，自动清理已删除项的记录
+//! - `share` - 导出后分享：写入 TEMP 导出目录并按平台在文件管理器中定位文件
+//! - `session_archive` - 归档导出并删除会话：导出 Markdown / JSON 到归档目录，校验后再删除原文件
+//! - `session_cleanup` - 删除会话后的清理：别名、查看记录、阅读状态、草稿与缓存
+//! - `session_lock` - 会话只读锁定：锁定列表持久化与写入前检查
+//! - `session_merge` - 会话合并：把源会话的消息按时间戳或追加并入目标会话，修正 uuid 冲突与 parentUuid 链
+//! - `session_title` - 会话重命名：CCR 别名持久化与可选的原生标题（custom-title / summary）写回
+//! - `tool_diff` - 工具结果对比：Read / Write / Edit 结果中的文件快照与磁盘当前内容的行级 diff
+//! - `trimmed_copy` - 会话副本：截取主线最后 N 条消息并补齐工具配对，或完整复制，另存为新会话
+//! - `temp_cleanup` - 临时资源清理：按保留策略清理 TEMP 下超龄的图片与导出残留
+//! - `fragments` - 碎片项目：识别子目录中误启动产生的空项目，并归并回父项目
+//! - `view_state` - 会话阅读状态：滚动位置、展开块与过滤器的 JSON 快照，按会话保存并限制 64KB
+//! - `watcher` - 数据目录监听：notify 监控 projects 目录变化，防抖后失效缓存并通知前端
 //! - `fixers` - 一键修复框架：可扩展的会话修复注册表和执行引擎
 //! - `skill` - Skills 扫描与解析服务：发现、读取和解析 Claude Code Skills
 //! - `pet` - 宠物系统服务：读取、清除宠物数据，确定性骨架生成
 //! - `plugin` - Plugins 管理服务：扫描已安装插件、启用/禁用、marketplace 列表
 
+pub mod appearance;
+pub mod archive;
+pub mod audit;
+pub mod backups;
+pub mod benchmark;
+pub mod branches;
 pub mod cache;
+pub mod cache_check;
 pub mod classifier;
+pub mod code_blocks;
+pub mod command_trace;
+pub mod compat;
+pub mod data_sources;
+pub mod diagnostic_bundle;
+pub mod diagnostics;
+pub mod drafts;
+pub mod edit_history;
+pub mod events;
 pub mod export;
 pub mod file_guard;
+pub mod fixture;
 pub mod fixers;
+pub mod fragments;
+pub mod global_search;
+pub mod keywords;
+pub mod last_viewed;
+pub mod limits;
+pub mod links;
 pub mod parser;
+pub mod path_set_store;
 pub mod pet;
+pub mod pins;
 pub mod plugin;
+pub mod project_filter;
+pub mod project_meta;
+pub mod project_stats;
+pub mod project_summary;
 pub mod proxy;
+pub mod recovery;
 pub mod retrospect;
 pub mod scanner;
+pub mod session_archive;
+pub mod session_cleanup;
+pub mod session_lock;
+pub mod session_merge;
+pub mod session_title;
+pub mod share;
 pub mod skill;
+pub mod temp_cleanup;
+pub mod tool_diff;
 pub mod transformer;
+pub mod trimmed_copy;
+pub mod view_state;
+pub mod watcher;
@@ -6,23 +6,98 @@
 //! ## 性能优化策略
 //! - 使用 `tokio::fs::read` 一次性读取文件到字节缓冲区（避免中间 UTF-8 转换开销）
 //! - 使用 `serde_json::from_str` 逐行解析，比 JS 的 `JSON.parse` 快 3-10 倍
-//! - 解析失败的行静默跳过，与前端容错策略一致
+//!
+//! ## 非 UTF-8 文件
+//! 被第三方工具转存为 UTF-16 / GBK 的会话在读取时自动探测并转码（见 `utils::encoding`），
+//! `read_messages_decoded` 额外返回原始编码，供前端提示用户用 `convert_session_encoding` 转存。
+//!
+//! ## 读取失败的错误码
+//! 常见的读取失败按 `ReadFailure` 归类，错误字符串以错误码开头并在新行附带自助修复建议
+//! （`<错误码>: <描述>\n建议: <建议>`），前端据此向用户说明原因，而不是显示空白会话：
+//! - `CloudPlaceholder`：OneDrive 等云盘的占位文件（Windows 文件属性 `FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS`）
+//! - `PermissionDenied`：没有读取权限或文件被独占
+//! - `TooLarge`：超过 `MAX_SESSION_FILE_BYTES`，或读入内存时内存不足
+//! - `NotUtf8`：编码无法识别（可识别的 UTF-16 / GBK 会自动转码，不属于此类）
+//!
+//! ## 无损往返（round-trip）
+//! 无法解析的行（截断数据、NDJSON 注释行等）以及不是 JSON 对象的行，
+//! 以 `Value::String(原始行文本)` 的形式原样保留在消息列表中，
+//! 写回时逐字输出；未知 `type` 的消息作为普通对象保留，由 classifier 归为 Skip。
+//! 配合 serde_json 的 `preserve_order` feature（保持键的插入顺序），
+//! 「读取 → 不修改 → 写回」只会产生行尾（CRLF → LF）与空行的归一化差异。
 //!
 //! ## 写入安全
 //! `write_messages` 通过 `file_guard::safe_write_file` 执行写入，
 //! 自动进行路径验证和双重备份（临时备份 + 可选主动备份）。
 
+use std::io;
 use std::path::Path;
 
+use serde::Serialize;
+use serde_json::Value;
+
 use crate::models::message::SessionMessage;
 use crate::services::cache::AppCache;
 use crate::services::file_guard;
+use crate::utils::{disk, encoding};
+
+/// 可读取的会话文件大小上限（会话整体读入内存后解析）
+pub const MAX_SESSION_FILE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// 错误字符串中自助修复建议的前缀（位于描述之后的新行）
+pub const SUGGESTION_PREFIX: &str = "建议: ";
+
+/// 读取会话文件的常见失败场景
+///
+/// 错误字符串格式为 `<错误码>: <描述>\n建议: <建议>`，由 `error` 生成。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadFailure {
+    /// 云盘占位文件，内容尚未下载到本机
+    CloudPlaceholder,
+    /// 没有读取权限
+    PermissionDenied,
+    /// 文件过大
+    TooLarge,
+    /// 编码无法识别
+    NotUtf8,
+    /// 文件不是 UTF-8，修改前需要先转存
+    NeedsUtf8,
+}
+
+impl ReadFailure {
+    /// 错误码（错误字符串前缀）
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::CloudPlaceholder => "CloudPlaceholder",
+            Self::PermissionDenied => "PermissionDenied",
+            Self::TooLarge => "TooLarge",
+            Self::NotUtf8 => "NotUtf8",
+            Self::NeedsUtf8 => "NeedsUtf8",
+        }
+    }
+
+    /// 面向用户的自助修复建议
+    pub fn suggestion(self) -> &'static str {
+        match self {
+            Self::CloudPlaceholder => "请在资源管理器中将该文件设置为「始终保留在此设备上」，下载完成后重新打开",
+            Self::PermissionDenied => "请检查当前用户对该文件的读取权限，并确认没有其他程序独占打开该文件",
+            Self::TooLarge => "会话文件过大，请在 Claude Code 中开启新会话继续工作，或用外部工具拆分该文件",
+            Self::NotUtf8 => "文件编码无法识别，可能已损坏；可从备份中恢复该会话",
+            Self::NeedsUtf8 => "请先将该会话「转存为 UTF-8」（原文件会先备份），再进行修改",
+        }
+    }
+
+    /// 生成带错误码与建议的错误字符串
+    fn error(self, detail: &str) -> String {
+        format!("{}: {}\n{}{}", self.code(), detail, SUGGESTION_PREFIX, self.suggestion())
+    }
+}
 
 /// 读取并解析 JSONL 会话文件中的所有消息
 ///
 /// 从指定的 `.jsonl` 文件中逐行解析消息数据。对于解析失败的行
-/// （如文件末尾的不完整行、或被截断的数据），采用静默跳过策略，
-/// 确保已成功解析的消息仍然可以正常返回。
+/// （如文件末尾的不完整行、或被截断的数据），以原始文本保留（见 `parse_line`），
+/// 确保写回时不会丢失数据。非 UTF-8 文件按 `read_messages_decoded` 的规则转码后解析。
 ///
 /// # 参数
 /// - `file_path` - 会话 JSONL 文件的绝对路径
@@ -31,43 +106,216 @@ use crate::services::file_guard;
 /// 返回按文件顺序排列的 SessionMessage 数组；文件不存在时返回空数组
 ///
 /// # 错误
-/// 文件存在但无法读取时返回错误
+/// 文件存在但无法读取，或编码无法识别时返回错误
 pub async fn read_messages(file_path: &str) -> Result<Vec<SessionMessage>, String> {
+    Ok(read_messages_decoded(file_path).await?.0)
+}
+
+/// 读取会话文件用于修改（读-改-写）
+///
+/// 与 `read_messages` 相同，但文件不是 UTF-8 时拒绝：写回会把文件悄悄转存为 UTF-8，
+/// 且转码结果可能有误（见 `encoding` 模块），需要用户先经 `convert_session_encoding` 显式转存。
+///
+/// # 参数
+/// - `file_path` - 会话 JSONL 文件的绝对路径
+///
+/// # 错误
+/// 同 `read_messages`；文件不是 UTF-8 时返回 `NeedsUtf8` 错误
+pub async fn read_messages_for_write(file_path: &str) -> Result<Vec<SessionMessage>, String> {
+    match read_messages_decoded(file_path).await? {
+        (messages, None) => Ok(messages),
+        (_, Some(from)) => Err(ReadFailure::NeedsUtf8.error(&format!("会话文件编码为 {}，修改后会被转存为 UTF-8", from))),
+    }
+}
+
+/// 读取并解析 JSONL 会话文件，同时返回文件的原始编码
+///
+/// 文件不是合法 UTF-8 时，用 `encoding::decode_bytes` 探测 BOM / UTF-16 / GBK 并转码，
+/// 转码只发生在内存中，文件本身由 `convert_session_encoding` 显式转存。
+///
+/// # 参数
+/// - `file_path` - 会话 JSONL 文件的绝对路径
+///
+/// # 返回值
+/// `(消息列表, 原始编码)`；文件本身是 UTF-8 或不存在时原始编码为 None
+///
+/// # 错误
+/// 文件存在但无法读取，或编码无法识别时返回错误（含十六进制片段诊断）；
+/// 常见场景带 `ReadFailure` 错误码与建议
+pub async fn read_messages_decoded(file_path: &str) -> Result<(Vec<SessionMessage>, Option<&'static str>), String> {
     let path = Path::new(file_path);
 
     // 文件不存在时返回空数组，与前端行为保持一致
     if !path.exists() {
-        return Ok(vec![]);
+        return Ok((vec![], None));
+    }
+
+    let size = tokio::fs::metadata(path).await.map_err(|e| read_error(path, &e))?.len();
+    if size > MAX_SESSION_FILE_BYTES {
+        let detail = format!(
+            "会话文件大小 {} 超过上限 {}",
+            disk::format_bytes(size),
+            disk::format_bytes(MAX_SESSION_FILE_BYTES)
+        );
+        return Err(ReadFailure::TooLarge.error(&detail));
     }
 
     // 一次性读取整个文件到内存（对于典型的会话文件大小，这是最高效的方式）
-    let content = tokio::fs::read_to_string(path)
-        .await
-        .map_err(|e| format!("读取会话文件失败: {}", e))?;
+    let bytes = tokio::fs::read(path).await.map_err(|e| read_error(path, &e))?;
+    let decoded = encoding::decode_bytes(bytes)
+        .map_err(|e| ReadFailure::NotUtf8.error(&format!("读取会话文件失败: {}", e)))?;
+    if let Some(from) = decoded.reencoded_from {
+        log::warn!("会话文件不是 UTF-8，已按 {} 转码读取: {}", from, file_path);
+    }
+
+    Ok((parse_jsonl(&decoded.text), decoded.reencoded_from))
+}
+
+/// 把读取会话文件时的 I/O 错误映射为带错误码的错误
+///
+/// 云盘占位文件优先识别（其读取失败可能表现为各种 I/O 错误）；
+/// 无法归类的错误保留原始描述、不带错误码。
+fn read_error(path: &Path, error: &io::Error) -> String {
+    let detail = format!("读取会话文件失败: {}", error);
+    let failure = if is_cloud_placeholder(path) {
+        Some(ReadFailure::CloudPlaceholder)
+    } else {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => Some(ReadFailure::PermissionDenied),
+            io::ErrorKind::OutOfMemory => Some(ReadFailure::TooLarge),
+            _ => None,
+        }
+    };
+    match failure {
+        Some(failure) => failure.error(&detail),
+        None => detail,
+    }
+}
+
+/// 文件是否为云盘占位文件（内容需在访问时从云端下载）
+#[cfg(windows)]
+fn is_cloud_placeholder(path: &Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    std::fs::metadata(path).is_ok_and(|metadata| has_recall_attribute(metadata.file_attributes()))
+}
+
+/// 非 Windows 平台没有可靠的占位文件标记
+#[cfg(not(windows))]
+fn is_cloud_placeholder(_path: &Path) -> bool {
+    false
+}
+
+/// 文件属性是否带 `FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS`（OneDrive「仅在线可用」文件）
+#[cfg(windows)]
+fn has_recall_attribute(attributes: u32) -> bool {
+    use windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS;
+
+    attributes & FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS != 0
+}
 
-    // 逐行解析 JSONL，解析失败的行静默跳过
-    let messages: Vec<SessionMessage> = content
+/// 将 JSONL 文本解析为消息列表
+///
+/// 空行被忽略，其余每行对应一个元素（见 `parse_line`）。
+pub fn parse_jsonl(content: &str) -> Vec<SessionMessage> {
+    content
         .lines()
         .filter(|line| !line.trim().is_empty())
-        .filter_map(|line| serde_json::from_str(line).ok())
-        .collect();
+        .map(parse_line)
+        .collect()
+}
 
+/// 严格解析外部输入的 JSONL 片段（剪贴板粘贴、拖拽导入）
+///
+/// 与 `parse_jsonl` 不同，坏行不会以原始文本保留，而是汇总为错误返回，
+/// 避免把无法识别的内容写进会话文件。空行被忽略。
+///
+/// # 错误
+/// 任一非空行不是 JSON 对象时返回错误，列出所有问题行的行号（从 1 开始）
+pub fn parse_jsonl_strict(content: &str) -> Result<Vec<SessionMessage>, String> {
+    let mut messages = Vec::new();
+    let mut bad_lines = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Value>(line.trim()) {
+            Ok(value) if value.is_object() => messages.push(value),
+            Ok(_) => bad_lines.push(format!("第 {} 行: 不是 JSON 对象", index + 1)),
+            Err(e) => bad_lines.push(format!("第 {} 行: {}", index + 1, e)),
+        }
+    }
+
+    if !bad_lines.is_empty() {
+        return Err(format!("JSONL 解析失败:\n{}", bad_lines.join("\n")));
+    }
     Ok(messages)
 }
 
+/// 解析单行 JSONL
+///
+/// 可解析为 JSON 对象的行返回该对象；其余行（解析失败、注释行、
+/// 非对象的 JSON 值）返回 `Value::String(原始行文本)`，
+/// 由 `serialize_line` 在写回时原样输出。
+pub fn parse_line(line: &str) -> SessionMessage {
+    match serde_json::from_str::<Value>(line) {
+        Ok(value) if value.is_object() => value,
+        _ => Value::String(line.to_string()),
+    }
+}
+
+/// 若消息是 `parse_line` 保留的原始行，返回其文本
+pub fn raw_line(msg: &SessionMessage) -> Option<&str> {
+    msg.as_str()
+}
+
+/// 将单条消息序列化为一行 JSONL 文本（原始行原样返回）
+///
+/// # 错误
+/// 序列化失败时返回错误
+pub fn serialize_line(msg: &SessionMessage) -> Result<String, String> {
+    match raw_line(msg) {
+        Some(raw) => Ok(raw.to_string()),
+        None => serde_json::to_string(msg).map_err(|e| format!("序列化消息失败: {}", e)),
+    }
+}
+
+/// 将消息列表序列化为 JSONL 文本（每行末尾带换行符）
+///
+/// # 错误
+/// 任一消息序列化失败时返回错误
+pub fn serialize_jsonl(messages: &[SessionMessage]) -> Result<String, String> {
+    // 预分配足够的缓冲区容量，减少重新分配次数
+    let mut content = String::with_capacity(messages.len() * 256);
+
+    for msg in messages {
+        content.push_str(&serialize_line(msg)?);
+        content.push('\n');
+    }
+
+    Ok(content)
+}
+
 /// 将消息列表序列化为 JSONL 格式并安全写入文件
 ///
-/// 每条消息序列化为单行 JSON，行之间用换行符分隔，末尾加换行符。
+/// 每条消息序列化为单行 JSON（原始行原样输出），行之间用换行符分隔，末尾加换行符。
 /// 通过 `file_guard::safe_write_file` 执行写入，自动进行：
 /// - 路径安全验证（确保在 `~/.claude/` 下）
 /// - 临时备份（强制，写入 TEMP 目录）
 /// - 主动备份（可选，写入同目录 `.ccbak` 文件）
 ///
+/// 写入成功后按新的 mtime、大小、行数与消息数定点更新项目列表缓存中的该会话。
+/// 最近查看时间不在这里刷新：写入的会话未必正被查看，由前端打开或重新加载会话时记录。
+///
 /// # 参数
 /// - `file_path` - 会话 JSONL 文件的绝对路径
 /// - `messages` - 要写入的完整消息列表
 /// - `operation` - 操作描述（用于备份记录，如 "delete_message"）
-/// - `cache` - AppCache 引用，用于注册临时备份记录
+/// - `cache` - AppCache 引用，用于注册临时备份记录与更新项目列表缓存
+///
+/// # 返回值
+/// 本次写入前创建的临时备份路径（原文件不存在时为 None）
 ///
 /// # 错误
 /// 序列化失败、路径验证失败、备份失败或文件写入失败时返回错误
@@ -76,17 +324,238 @@ pub async fn write_messages(
     messages: &[SessionMessage],
     operation: &str,
     cache: &AppCache,
-) -> Result<(), String> {
-    // 预分配足够的缓冲区容量，减少重新分配次数
-    let mut content = String::with_capacity(messages.len() * 256);
+) -> Result<Option<String>, String> {
+    let content = serialize_jsonl(messages)?;
 
-    for msg in messages {
-        let line = serde_json::to_string(msg)
-            .map_err(|e| format!("序列化消息失败: {}", e))?;
-        content.push_str(&line);
-        content.push('\n');
+    // 通过 file_guard 安全写入（含路径验证 + 双重备份）
+    let backup = file_guard::safe_write_file(file_path, content.as_bytes(), operation, cache).await?;
+    cache.sync_session_file(file_path, Some(messages)).await;
+    Ok(backup)
+}
+
+/// 往返校验中单行差异的记录上限
+const MAX_ROUNDTRIP_MISMATCHES: usize = 20;
+
+/// 往返校验中不一致的单行
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoundtripMismatch {
+    /// 原文件中的行号（从 1 开始）
+    pub line_number: usize,
+    /// 原始行文本
+    pub original: String,
+    /// 解析后重新序列化得到的文本
+    pub roundtrip: String,
+}
+
+/// 会话文件往返校验报告
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoundtripReport {
+    /// 是否无损（忽略行尾与空行差异后逐字节一致）
+    pub lossless: bool,
+    /// 非空行总数
+    pub total_lines: usize,
+    /// 解析为 JSON 对象的行数
+    pub object_lines: usize,
+    /// 以原始文本保留的行数（坏行、注释行、非对象 JSON）
+    pub raw_lines: usize,
+    /// 不一致的行总数
+    pub mismatch_count: usize,
+    /// 不一致行的明细（最多 `MAX_ROUNDTRIP_MISMATCHES` 条）
+    pub mismatches: Vec<RoundtripMismatch>,
+}
+
+/// 校验会话文件的「读取 → 写回」往返是否无损
+///
+/// 使用与 `read_messages` / `write_messages` 相同的解析和序列化逻辑，
+/// 在内存中逐行比对，不写入任何文件。
+///
+/// # 参数
+/// - `file_path` - 会话 JSONL 文件的绝对路径
+///
+/// # 错误
+/// 文件无法读取或序列化失败时返回错误
+pub async fn verify_roundtrip(file_path: &str) -> Result<RoundtripReport, String> {
+    let content = tokio::fs::read_to_string(file_path)
+        .await
+        .map_err(|e| format!("读取会话文件失败: {}", e))?;
+
+    roundtrip_report(&content)
+}
+
+/// 对 JSONL 文本执行往返比对，生成校验报告
+fn roundtrip_report(content: &str) -> Result<RoundtripReport, String> {
+    let mut report = RoundtripReport {
+        lossless: true,
+        total_lines: 0,
+        object_lines: 0,
+        raw_lines: 0,
+        mismatch_count: 0,
+        mismatches: Vec::new(),
+    };
+
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        report.total_lines += 1;
+
+        let msg = parse_line(line);
+        if raw_line(&msg).is_some() {
+            report.raw_lines += 1;
+        } else {
+            report.object_lines += 1;
+        }
+
+        let roundtrip = serialize_line(&msg)?;
+        if roundtrip != line {
+            report.mismatch_count += 1;
+            if report.mismatches.len() < MAX_ROUNDTRIP_MISMATCHES {
+                report.mismatches.push(RoundtripMismatch {
+                    line_number: index + 1,
+                    original: line.to_string(),
+                    roundtrip,
+                });
+            }
+        }
     }
 
-    // 通过 file_guard 安全写入（含路径验证 + 双重备份）
-    file_guard::safe_write_file(file_path, content.as_bytes(), operation, cache).await
+    report.lossless = report.mismatch_count == 0;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::classifier::{classify, Classification};
+
+    /// 忽略行尾差异与空行后的规范化文本
+    fn normalize(content: &str) -> String {
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| format!("{}\n", line))
+            .collect()
+    }
+
+    /// 贴近真实 Claude Code 会话的样本：键顺序非字母序、含中文与转义字符、
+    /// 未知 type、注释行、截断行与非对象 JSON 行
+    const SAMPLE: &str = concat!(
+        r#"{"type":"queue-operation","operation":"dequeue","timestamp":"2026-01-01T00:00:00.000Z","sessionId":"s1"}"#, "\r\n",
+        r#"{"parentUuid":null,"isSidechain":false,"userType":"external","cwd":"D:\\work\\repo","sessionId":"s1","version":"2.1.3","type":"user","message":{"role":"user","content":"你好\n\"quoted\" \u001b[0m"},"uuid":"u1","timestamp":"2026-01-01T00:00:01.000Z"}"#, "\r\n",
+        "// NDJSON comment line\r\n",
+        "\r\n",
+        r#"{"type":"future-entry-kind","zeta":1,"alpha":[1.5,-2,{"b":true,"a":null}],"uuid":"x1"}"#, "\n",
+        "[1,2,3]\n",
+        "\"just a string\"\n",
+        r#"{"parentUuid":"u1","type":"assistant","message":{"id":"m1","role":"assistant","content":[{"type":"text","text":"ok"}]},"uuid":"a1","timestamp":"2026-01-01T00:00:02.000Z"}"#, "\n",
+        r#"{"parentUuid":"a1","type":"user","message":{"role":"user","content":"trunc"#, "\n",
+    );
+
+    #[test]
+    fn test_roundtrip_is_lossless() {
+        let messages = parse_jsonl(SAMPLE);
+        let written = serialize_jsonl(&messages).unwrap();
+        assert_eq!(written, normalize(SAMPLE));
+    }
+
+    #[test]
+    fn test_roundtrip_report_counts() {
+        let report = roundtrip_report(SAMPLE).unwrap();
+        assert!(report.lossless, "{:?}", report.mismatches);
+        assert_eq!(report.total_lines, 8);
+        assert_eq!(report.object_lines, 4);
+        assert_eq!(report.raw_lines, 4);
+    }
+
+    #[test]
+    fn test_raw_lines_preserved_verbatim() {
+        let messages = parse_jsonl(SAMPLE);
+        let raws: Vec<&str> = messages.iter().filter_map(raw_line).collect();
+        assert_eq!(
+            raws,
+            vec![
+                "// NDJSON comment line",
+                "[1,2,3]",
+                "\"just a string\"",
+                r#"{"parentUuid":"a1","type":"user","message":{"role":"user","content":"trunc"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_and_raw_lines_are_skipped() {
+        let messages = parse_jsonl(SAMPLE);
+        for index in [2, 3, 4, 5, 7] {
+            assert!(matches!(classify(&messages[index]), Classification::Skip));
+        }
+    }
+
+    #[test]
+    fn test_parse_jsonl_strict_reports_line_numbers() {
+        let ok = parse_jsonl_strict("{\"uuid\":\"a\"}\r\n\n  {\"uuid\":\"b\"}  \n").unwrap();
+        assert_eq!(ok.len(), 2);
+
+        let err = parse_jsonl_strict("{\"uuid\":\"a\"}\n[1,2]\n\n{\"uuid\":").unwrap_err();
+        assert!(err.contains("第 2 行"));
+        assert!(err.contains("第 4 行"));
+        assert!(!err.contains("第 1 行"));
+    }
+
+    #[test]
+    fn test_read_error_codes() {
+        let path = Path::new("/nonexistent/session.jsonl");
+        let error = read_error(path, &io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(error.starts_with("PermissionDenied: 读取会话文件失败"), "{}", error);
+        assert!(error.ends_with(&format!("\n{}{}", SUGGESTION_PREFIX, ReadFailure::PermissionDenied.suggestion())));
+        assert!(read_error(path, &io::Error::from(io::ErrorKind::OutOfMemory)).starts_with("TooLarge: "));
+        // 无法归类的错误不带错误码
+        let error = read_error(path, &io::Error::from(io::ErrorKind::Interrupted));
+        assert!(error.starts_with("读取会话文件失败") && !error.contains(SUGGESTION_PREFIX));
+    }
+
+    #[tokio::test]
+    async fn test_read_messages_not_utf8() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("session.jsonl");
+        std::fs::write(&file, [0x80, 0xFF, 0x80]).unwrap();
+        let error = read_messages(&file.to_string_lossy()).await.unwrap_err();
+        assert!(error.starts_with("NotUtf8: "), "{}", error);
+        assert!(error.contains(SUGGESTION_PREFIX));
+    }
+
+    #[tokio::test]
+    async fn test_read_messages_for_write_rejects_reencoded() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("session.jsonl");
+        let line = "{\"type\":\"user\",\"message\":{\"content\":\"你好\"}}\n";
+        std::fs::write(&file, line.encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<u8>>()).unwrap();
+        let path = file.to_string_lossy().to_string();
+        // 读取照常转码，修改前拒绝
+        assert_eq!(read_messages(&path).await.unwrap().len(), 1);
+        let error = read_messages_for_write(&path).await.unwrap_err();
+        assert!(error.starts_with("NeedsUtf8: ") && error.contains("UTF-16LE"), "{}", error);
+
+        std::fs::write(&file, line).unwrap();
+        assert_eq!(read_messages_for_write(&path).await.unwrap().len(), 1);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_recall_attribute_marks_cloud_placeholder() {
+        use windows_sys::Win32::Storage::FileSystem::{FILE_ATTRIBUTE_ARCHIVE, FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS};
+
+        assert!(has_recall_attribute(FILE_ATTRIBUTE_ARCHIVE | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS));
+        assert!(!has_recall_attribute(FILE_ATTRIBUTE_ARCHIVE));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_no_cloud_placeholder_off_windows() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("session.jsonl");
+        std::fs::write(&file, "{}\n").unwrap();
+        assert!(!is_cloud_placeholder(&file));
+    }
 }
@@ -0,0 +1,186 @@
+//! # 路径集合存储
+//!
+//! 置顶（`pins`）、归档（`archive`）等 CCR 侧视图状态都是「若干组归一化路径」，
+//! 以 JSON 持久化在 `~/.mo/CCR/` 下。本模块提供它们共用的读写逻辑：
+//! - **读取**：文件不存在时返回空集合；`load_active` 只在返回结果中剔除失效记录，不写文件
+//! - **修改**：`update` 在模块级互斥锁内完成读-改-写，内容有变化时才写回
+//! - **写入**：写回前移除失效记录，经 `file_guard::write_atomic` 原子替换，并发读取不会读到半截文件
+//!
+//! ## 失效记录
+//! 记录的路径所在的上级目录仍存在、但路径本身已不存在时，视为项目 / 会话已被删除。
+//! 上级目录也不存在（如额外数据源暂不可用）时保留记录。
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::services::file_guard;
+use crate::utils::path;
+
+/// 由若干组归一化路径组成的持久化状态
+pub trait PathSets: Serialize + DeserializeOwned + Default + Clone + PartialEq {
+    /// 全部路径集合（用于统一清理失效记录）
+    fn sets_mut(&mut self) -> Vec<&mut BTreeSet<String>>;
+
+    /// 移除已删除项目 / 会话的记录（见模块文档「失效记录」）
+    fn retain_active(&mut self) {
+        for set in self.sets_mut() {
+            set.retain(|p| !is_stale(Path::new(p)));
+        }
+    }
+}
+
+/// 一个路径集合文件：文件名、错误信息中的名称与串行化读-改-写的锁
+pub struct PathSetStore {
+    /// 文件名（位于 `~/.mo/CCR/`）
+    file_name: &'static str,
+    /// 错误信息中的名称，如「置顶列表」
+    label: &'static str,
+    /// 串行化读-改-写，避免并发修改时互相覆盖
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl PathSetStore {
+    /// 创建存储（用于 `static`）
+    pub const fn new(file_name: &'static str, label: &'static str) -> Self {
+        Self {
+            file_name,
+            label,
+            lock: tokio::sync::Mutex::const_new(()),
+        }
+    }
+
+    /// 读取并剔除失效记录（只读，不写回文件）
+    ///
+    /// # 错误
+    /// 文件存在但读取、解析失败时返回错误
+    pub async fn load_active<T: PathSets>(&self) -> Result<T, String> {
+        let mut value: T = self.load().await?;
+        value.retain_active();
+        Ok(value)
+    }
+
+    /// 在锁内读取、修改并写回（内容未变化时不写文件）
+    ///
+    /// # 参数
+    /// - `modify` - 修改函数，其返回值原样返回
+    ///
+    /// # 错误
+    /// 读取、解析或写入失败时返回错误
+    pub async fn update<T: PathSets, R>(&self, modify: impl FnOnce(&mut T) -> R) -> Result<R, String> {
+        let _guard = self.lock.lock().await;
+        let mut value: T = self.load().await?;
+        let before = value.clone();
+        let result = modify(&mut value);
+        if value != before {
+            self.write(&mut value).await?;
+        }
+        Ok(result)
+    }
+
+    /// 读取原始内容（文件不存在时返回空集合）
+    async fn load<T: PathSets>(&self) -> Result<T, String> {
+        let file_path = self.path()?;
+        if !file_path.exists() {
+            return Ok(T::default());
+        }
+        let content = tokio::fs::read_to_string(&file_path)
+            .await
+            .map_err(|e| format!("读取{}失败: {}", self.label, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("解析{}失败: {}", self.label, e))
+    }
+
+    /// 移除失效记录后原子写入
+    async fn write<T: PathSets>(&self, value: &mut T) -> Result<(), String> {
+        value.retain_active();
+        let file_path = self.path()?;
+        if let Some(parent) = file_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("创建 CCR 配置目录失败: {}", e))?;
+        }
+        let content = serde_json::to_string_pretty(value)
+            .map_err(|e| format!("序列化{}失败: {}", self.label, e))?;
+        file_guard::write_atomic(&file_path.to_string_lossy(), content.as_bytes())
+            .await
+            .map_err(|e| format!("写入{}失败: {}", self.label, e))
+    }
+
+    /// 文件路径：`~/.mo/CCR/<file_name>`
+    fn path(&self) -> Result<PathBuf, String> {
+        Ok(path::get_ccr_config_path()?.join(self.file_name))
+    }
+}
+
+/// 路径已不存在，且其上级目录仍存在（确认是被删除而非暂不可访问）
+fn is_stale(path: &Path) -> bool {
+    !path.exists() && path.parent().is_some_and(Path::is_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    struct Paths {
+        #[serde(default)]
+        paths: BTreeSet<String>,
+    }
+
+    impl PathSets for Paths {
+        fn sets_mut(&mut self) -> Vec<&mut BTreeSet<String>> {
+            vec![&mut self.paths]
+        }
+    }
+
+    #[test]
+    fn test_is_stale_keeps_unreachable_sources() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        let session = dir.join("s.jsonl");
+        std::fs::write(&session, "").unwrap();
+
+        assert!(!is_stale(&session));
+        // 上级目录存在、文件已删除：失效
+        assert!(is_stale(&dir.join("deleted.jsonl")));
+        // 上级目录也不存在（数据源暂不可用）：保留
+        assert!(!is_stale(&dir.join("offline").join("s.jsonl")));
+    }
+
+    #[tokio::test]
+    async fn test_load_is_read_only_and_update_prunes() {
+        static STORE: PathSetStore = PathSetStore::new("path-sets-test.json", "测试列表");
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        let live = dir.join("live.jsonl");
+        std::fs::write(&live, "").unwrap();
+        let live = live.to_string_lossy().to_string();
+        let deleted = dir.join("deleted.jsonl").to_string_lossy().to_string();
+
+        let stored = Paths { paths: BTreeSet::from([live.clone(), deleted.clone()]) };
+        let content = serde_json::to_string_pretty(&stored).unwrap();
+        let file_path = STORE.path().unwrap();
+        std::fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        std::fs::write(&file_path, &content).unwrap();
+
+        let active: Paths = STORE.load_active().await.unwrap();
+        assert_eq!(active.paths, BTreeSet::from([live.clone()]));
+        // 读取不改动文件，失效记录留到下一次写入时清理
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), content);
+
+        // 内容未变化时不写文件
+        let contains = STORE.update(|paths: &mut Paths| paths.paths.contains(&live)).await.unwrap();
+        assert!(contains);
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), content);
+
+        let added = STORE.update(|paths: &mut Paths| paths.paths.insert("/elsewhere/x".to_string())).await.unwrap();
+        assert!(added);
+        let stored: Paths = STORE.load().await.unwrap();
+        assert_eq!(stored.paths, BTreeSet::from([live, "/elsewhere/x".to_string()]));
+
+        std::fs::remove_file(&file_path).unwrap();
+    }
+}
@@ -17,6 +17,7 @@ use crate::models::pet::{
     Companion, CompanionBones, PetActionResult, StoredCompanion,
     EYES, HATS, RARITY_STARS, RARITY_WEIGHTS, SALT, SPECIES, STAT_NAMES,
 };
+use crate::utils::platform;
 
 // ==================== 路径工具 ====================
 
@@ -28,7 +29,7 @@ use crate::models::pet::{
 ///
 /// 对应 Claude Code 源码 `src/utils/env.ts` 中的 `getGlobalClaudeFile`。
 fn get_global_claude_file() -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or_else(|| "无法获取用户主目录".to_string())?;
+    let home = platform::home_dir().ok_or_else(|| "无法获取用户主目录".to_string())?;
 
     // 优先检查旧版配置路径
     let legacy_path = home.join(".claude").join(".config.json");
@@ -383,16 +384,10 @@ pub async fn clear_companion() -> Result<PetActionResult, String> {
     //   `jsonStringify(filteredConfig, null, 2)` — 即 2 空格缩进的 pretty print。
     // `serde_json::to_string_pretty` 默认也是 2 空格缩进，格式与 Claude Code 一致。
     //
-    // ## 已知限制：key 顺序
+    // ## key 顺序
     //
-    // serde_json::Value 内部使用 BTreeMap（字母序排列），而 JavaScript 的
-    // JSON.stringify 保持对象的插入顺序。因此写回后 key 的排列顺序可能与原文件不同。
-    // 这不影响功能正确性（JSON 规范不依赖 key 顺序），但用户手动查看文件时可能注意到
-    // key 顺序变化。此行为与 Claude Code 自身的写入行为也存在类似的顺序不稳定性
-    // （config.ts 中的 `filterBy` 会过滤掉默认值字段，也会改变 key 集合）。
-    //
-    // 如果未来需要严格保持 key 顺序，可以切换为 `serde_json::Map`（基于 BTreeMap）
-    // 配合 `preserve_order` feature（基于 IndexMap）来保持解析时的插入顺序。
+    // serde_json 启用了 `preserve_order` feature（基于 IndexMap），
+    // 解析时的键插入顺序会被保留，写回后 key 的排列顺序与原文件一致。
     let updated = serde_json::to_string_pretty(&config)
         .map_err(|e| format!("序列化配置失败: {}", e))?;
 
@@ -0,0 +1,150 @@
+//! # 项目与会话置顶
+//!
+//! 用户可以把常用的项目和会话置顶，扫描结果中置顶项排在最前。
+//! 置顶列表持久化在 `~/.mo/CCR/pins.json`：
+//!
+//! ```json
+//! { "projects": ["<项目目录绝对路径>"], "sessions": ["<会话文件绝对路径>"] }
+//! ```
+//!
+//! 路径经 `session_lock::normalize` 归一化。项目以目录绝对路径而非目录名为键，
+//! 不同数据源中的同名项目互不影响。
+//!
+//! ## 失效记录清理
+//! 读写由 `path_set_store` 完成：读取（`load_active`）只在返回结果中剔除已删除项目 / 会话的记录、
+//! 不写文件；下一次修改置顶列表时才把它们从文件中移除。
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::services::path_set_store::{PathSetStore, PathSets};
+use crate::services::session_lock;
+
+/// 置顶列表文件（位于 `~/.mo/CCR/`）
+static STORE: PathSetStore = PathSetStore::new("pins.json", "置顶列表");
+
+/// 置顶列表
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pins {
+    /// 置顶项目的目录路径（归一化）
+    #[serde(default)]
+    pub projects: BTreeSet<String>,
+    /// 置顶会话的文件路径（归一化）
+    #[serde(default)]
+    pub sessions: BTreeSet<String>,
+}
+
+impl Pins {
+    /// 项目目录是否已置顶
+    pub fn is_project_pinned(&self, project_dir: &Path) -> bool {
+        self.projects.contains(&session_lock::normalize(&project_dir.to_string_lossy()))
+    }
+
+    /// 会话是否已置顶
+    pub fn is_session_pinned(&self, session_file_path: &str) -> bool {
+        self.sessions.contains(&session_lock::normalize(session_file_path))
+    }
+}
+
+impl PathSets for Pins {
+    fn sets_mut(&mut self) -> Vec<&mut BTreeSet<String>> {
+        vec![&mut self.projects, &mut self.sessions]
+    }
+}
+
+/// 读取置顶列表，剔除已删除项目 / 会话的记录（只读，不写回文件）
+///
+/// # 错误
+/// 文件存在但读取、解析失败时返回错误
+pub async fn load_active() -> Result<Pins, String> {
+    STORE.load_active().await
+}
+
+/// 切换项目的置顶状态
+///
+/// # 参数
+/// - `project_dir` - 项目目录的绝对路径
+///
+/// # 返回值
+/// 切换后是否置顶
+///
+/// # 错误
+/// 置顶列表读取、解析或写入失败时返回错误
+pub async fn toggle_project(project_dir: &Path) -> Result<bool, String> {
+    let key = session_lock::normalize(&project_dir.to_string_lossy());
+    toggle(|pins| &mut pins.projects, key).await
+}
+
+/// 切换会话的置顶状态
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+///
+/// # 返回值
+/// 切换后是否置顶
+///
+/// # 错误
+/// 置顶列表读取、解析或写入失败时返回错误
+pub async fn toggle_session(session_file_path: &str) -> Result<bool, String> {
+    toggle(|pins| &mut pins.sessions, session_lock::normalize(session_file_path)).await
+}
+
+/// 会话文件移动后迁移其置顶记录（未置顶时无操作）
+///
+/// # 错误
+/// 置顶列表读取、解析或写入失败时返回错误
+pub async fn move_session(from: &str, to: &str) -> Result<(), String> {
+    let (from, to) = (session_lock::normalize(from), session_lock::normalize(to));
+    STORE
+        .update(|pins: &mut Pins| {
+            if pins.sessions.remove(&from) {
+                pins.sessions.insert(to);
+            }
+        })
+        .await
+}
+
+/// 在 `select` 选出的集合中切换 `key`
+async fn toggle(select: impl FnOnce(&mut Pins) -> &mut BTreeSet<String>, key: String) -> Result<bool, String> {
+    STORE
+        .update(|pins: &mut Pins| {
+            let set = select(pins);
+            if set.remove(&key) {
+                false
+            } else {
+                set.insert(key);
+                true
+            }
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_toggle_and_move_session() {
+        let dir = crate::utils::platform::test_home().join("pins-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b) = (dir.join("a.jsonl"), dir.join("b.jsonl"));
+        std::fs::write(&a, "").unwrap();
+        std::fs::write(&b, "").unwrap();
+        let (a, b) = (a.to_string_lossy().to_string(), b.to_string_lossy().to_string());
+
+        assert!(toggle_session(&a).await.unwrap());
+        assert!(load_active().await.unwrap().is_session_pinned(&a));
+        move_session(&a, &b).await.unwrap();
+        let pins = load_active().await.unwrap();
+        assert!(!pins.is_session_pinned(&a) && pins.is_session_pinned(&b));
+        assert!(!toggle_session(&b).await.unwrap());
+        assert!(!load_active().await.unwrap().is_session_pinned(&b));
+        assert!(toggle_project(&dir).await.unwrap());
+        assert!(load_active().await.unwrap().is_project_pinned(&dir));
+        assert!(!toggle_project(&dir).await.unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
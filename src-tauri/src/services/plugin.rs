@@ -23,6 +23,7 @@ use crate::models::plugin::{
     InstalledPluginsFile, KnownMarketplace, MarketplaceInfo,
     PluginActionResult, PluginInfo, PluginManifest, PluginScope,
 };
+use crate::utils::{jsonc, platform};
 
 /// 获取 plugins 根目录路径
 ///
@@ -30,7 +31,7 @@ use crate::models::plugin::{
 /// `pluginDirectories.ts` → `getPluginsDirectory()`
 fn get_plugins_directory() -> Result<PathBuf, String> {
     // 获取用户主目录下的 .claude 目录
-    let home = dirs::home_dir().ok_or("无法获取用户主目录")?;
+    let home = platform::home_dir().ok_or("无法获取用户主目录")?;
     Ok(home.join(".claude").join("plugins"))
 }
 
@@ -38,7 +39,7 @@ fn get_plugins_directory() -> Result<PathBuf, String> {
 ///
 /// 即 `~/.claude/`
 fn get_claude_dir() -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or("无法获取用户主目录")?;
+    let home = platform::home_dir().ok_or("无法获取用户主目录")?;
     Ok(home.join(".claude"))
 }
 
@@ -136,7 +137,8 @@ async fn load_enabled_plugins() -> Result<std::collections::HashMap<String, bool
         .await
         .map_err(|e| format!("读取 settings.json 失败: {}", e))?;
 
-    let settings: serde_json::Value = serde_json::from_str(&content)
+    // 只读场景，含注释的 settings.json 也按 JSONC 宽容解析
+    let (settings, _): (serde_json::Value, bool) = jsonc::parse_lenient(&content)
         .map_err(|e| format!("解析 settings.json 失败: {}", e))?;
 
     let enabled_plugins = settings.get("enabledPlugins")
@@ -0,0 +1,113 @@
+//! # 项目树关键词过滤
+//!
+//! 侧边栏过滤需要匹配会话名称，而会话名称（customTitle / aiTitle / lastPrompt、摘要、首条消息）
+//! 只在后端扫描时低成本可得。本模块在已扫描的项目树上做大小写不敏感的子串匹配：
+//! - 项目路径命中：保留整个项目及其全部会话
+//! - 否则保留会话 ID 或会话名称命中的会话；没有命中会话的项目被移除
+//!
+//! 路径分隔符统一为 `/` 后再比较，Windows 路径可用 `G:/work` 或 `G:\work` 任一写法过滤。
+
+use crate::models::project::{Project, Session};
+use crate::services::scanner;
+
+/// 按关键词过滤项目树
+///
+/// # 参数
+/// - `projects` - 项目列表（含会话）
+/// - `query` - 过滤关键词；去掉首尾空白后为空时原样返回
+///
+/// # 返回值
+/// 过滤后的项目列表（保持原顺序），`session_count` 与 `latest_timestamp` 按保留的会话重算
+pub fn filter_projects(projects: Vec<Project>, query: &str) -> Vec<Project> {
+    let query = normalize(query.trim());
+    if query.is_empty() {
+        return projects;
+    }
+
+    projects
+        .into_iter()
+        .filter_map(|mut project| {
+            if normalize(&project.path).contains(&query) {
+                return Some(project);
+            }
+            project.sessions.retain(|session| session_matches(session, &query));
+            if project.sessions.is_empty() {
+                return None;
+            }
+            scanner::refresh_project_totals(&mut project);
+            Some(project)
+        })
+        .collect()
+}
+
+/// 会话 ID 或任一名称字段（别名、标题、摘要、首条消息）是否包含关键词
+fn session_matches(session: &Session, query: &str) -> bool {
+    std::iter::once(Some(&session.id))
+        .chain([&session.alias, &session.name, &session.summary, &session.first_prompt].map(Option::as_ref))
+        .flatten()
+        .any(|text| normalize(text).contains(query))
+}
+
+/// 小写化并把 `\` 统一为 `/`
+fn normalize(text: &str) -> String {
+    text.to_lowercase().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(path: &str, sessions: Vec<Session>) -> Project {
+        serde_json::from_value(serde_json::json!({
+            "name": path.replace(['/', '\\', ':'], "-"),
+            "path": path,
+            "sessions": sessions,
+            "sessionCount": sessions.len(),
+            "latestTimestamp": sessions.iter().map(|s| s.timestamp.clone()).max(),
+        }))
+        .unwrap()
+    }
+
+    fn session(id: &str, name: Option<&str>, timestamp: &str) -> Session {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": name,
+            "timestamp": timestamp,
+            "messageCount": 0,
+            "filePath": format!("/p/{}.jsonl", id),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_filter_projects_by_path_id_and_name() {
+        let projects = vec![
+            project("G:\\Work\\网站重构", vec![session("a1", None, "2025-01-01T00:00:00Z"), session("a2", None, "2025-01-02T00:00:00Z")]),
+            project("/home/u/api", vec![
+                session("b1", Some("修复登录 Bug"), "2025-01-01T00:00:00Z"),
+                session("b2", Some("Add tests"), "2025-01-03T00:00:00Z"),
+            ]),
+        ];
+
+        assert_eq!(filter_projects(projects.clone(), "  ").len(), 2);
+
+        // 路径命中保留全部会话，且分隔符写法不影响匹配
+        for query in ["g:/work/网站", "G:\\WORK", "网站重构"] {
+            let filtered = filter_projects(projects.clone(), query);
+            assert_eq!(filtered.len(), 1, "{}", query);
+            assert_eq!(filtered[0].sessions.len(), 2);
+        }
+
+        // 会话名称（中文与大小写）命中只保留命中的会话
+        let filtered = filter_projects(projects.clone(), "登录 bug");
+        assert_eq!(filtered.len(), 1);
+        assert_eq!((filtered[0].sessions[0].id.as_str(), filtered[0].session_count), ("b1", 1));
+        // 最新会话时间随保留的会话重算，而不是沿用被过滤掉的 b2
+        assert_eq!(filtered[0].latest_timestamp.as_deref(), Some("2025-01-01T00:00:00Z"));
+
+        let filtered = filter_projects(projects.clone(), "B2");
+        assert_eq!(filtered[0].sessions[0].id, "b2");
+
+        assert!(filter_projects(projects, "不存在").is_empty());
+    }
+}
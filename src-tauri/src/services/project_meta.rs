@@ -0,0 +1,226 @@
+//! # 项目元数据：真实路径校正
+//!
+//! 项目目录名的编码有歧义（见 `path::resolve_project_path`），项目被移动过时会话头部的 cwd
+//! 也可能与当前位置不符，Resume、「在资源管理器打开」等依赖 `Project.path` 的功能因此失效。
+//! 本模块提供两项能力：
+//! - `resolve_real_path`：读取项目最新会话中最后一条带 `cwd` 的消息，与解码结果比对
+//! - 路径覆盖：用户确认后写入 `~/.mo/CCR/projects-meta.json`，扫描时优先于 cwd 推断与解码
+//!
+//! ```json
+//! { "pathOverrides": { "<项目目录名>": "<真实路径>" } }
+//! ```
+//!
+//! 覆盖以项目目录名为键：目录名由真实路径编码而来，不同数据源中的同名项目指向同一路径。
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncBufReadExt;
+
+use crate::services::scanner::AGENT_SESSION_PREFIX;
+use crate::utils::path;
+
+/// 项目元数据文件名（位于 `~/.mo/CCR/`）
+const PROJECTS_META_FILE: &str = "projects-meta.json";
+
+/// 串行化项目元数据的读-改-写
+static WRITE_LOCK: LazyLock<tokio::sync::Mutex<()>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+/// 项目元数据
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectsMeta {
+    /// 项目目录名 → 用户指定的真实路径
+    #[serde(default)]
+    pub path_overrides: BTreeMap<String, String>,
+}
+
+/// 项目真实路径的校正结果
+///
+/// 对应前端 TypeScript 接口 `ProjectPathResolution`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectPathResolution {
+    /// 目录名的解码结果
+    pub decoded_path: String,
+    /// 最新会话中最后一条带 cwd 的消息记录的工作目录（没有会话或都不带 cwd 时为 None）
+    pub cwd_path: Option<String>,
+    /// 当前生效的用户覆盖路径
+    pub override_path: Option<String>,
+    /// cwd 与解码结果是否一致（没有 cwd 时视为一致）
+    pub consistent: bool,
+}
+
+/// 比对项目目录名的解码结果与最新会话记录的 cwd
+///
+/// # 参数
+/// - `project_dir` - 项目目录的绝对路径（`<数据目录>/projects/<目录名>`）
+/// - `project_name` - 项目目录名
+///
+/// # 返回值
+/// 解码路径、cwd 路径、当前覆盖路径与两者是否一致
+///
+/// # 错误
+/// 读取项目目录、会话文件或项目元数据失败时返回错误
+pub async fn resolve_real_path(project_dir: &Path, project_name: &str) -> Result<ProjectPathResolution, String> {
+    let decoded_path = path::decode_project_path(project_name);
+    let cwd_path = match latest_session(project_dir).await? {
+        Some(session) => last_cwd(&session).await?,
+        None => None,
+    };
+    let override_path = load_meta().await?.path_overrides.remove(project_name);
+    Ok(ProjectPathResolution {
+        consistent: cwd_path.as_ref().is_none_or(|cwd| cwd == &decoded_path),
+        decoded_path,
+        cwd_path,
+        override_path,
+    })
+}
+
+/// 读取全部路径覆盖（文件不存在时为空）
+///
+/// # 错误
+/// 文件存在但读取或解析失败时返回错误
+pub async fn load_overrides() -> Result<BTreeMap<String, String>, String> {
+    Ok(load_meta().await?.path_overrides)
+}
+
+/// 设置或清除项目的路径覆盖
+///
+/// # 参数
+/// - `project_name` - 项目目录名
+/// - `real_path` - 真实路径；为空字符串时清除覆盖
+///
+/// # 返回值
+/// 覆盖是否发生了变化
+///
+/// # 错误
+/// 项目元数据读取、解析或写入失败时返回错误
+pub async fn set_override(project_name: &str, real_path: &str) -> Result<bool, String> {
+    let _guard = WRITE_LOCK.lock().await;
+    let mut meta = load_meta().await?;
+    let changed = if real_path.is_empty() {
+        meta.path_overrides.remove(project_name).is_some()
+    } else {
+        meta.path_overrides.insert(project_name.to_string(), real_path.to_string()).as_deref() != Some(real_path)
+    };
+    if changed {
+        write_meta(&meta).await?;
+    }
+    Ok(changed)
+}
+
+/// 项目目录下最近修改的会话文件（不含子 agent 会话）
+async fn latest_session(project_dir: &Path) -> Result<Option<PathBuf>, String> {
+    let mut entries = tokio::fs::read_dir(project_dir)
+        .await
+        .map_err(|e| format!("读取项目目录失败: {}", e))?;
+    let mut latest: Option<(std::time::SystemTime, PathBuf)> = None;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.ends_with(".jsonl") || name.starts_with(AGENT_SESSION_PREFIX) {
+            continue;
+        }
+        let Ok(modified) = entry.metadata().await.and_then(|m| m.modified()) else {
+            continue;
+        };
+        if latest.as_ref().is_none_or(|(time, _)| modified > *time) {
+            latest = Some((modified, entry.path()));
+        }
+    }
+    Ok(latest.map(|(_, path)| path))
+}
+
+/// 会话文件中最后一条带 cwd 的消息记录的工作目录
+///
+/// 逐行读取，只解析包含 `"cwd"` 的行，避免把整个会话载入内存。
+async fn last_cwd(session_file: &Path) -> Result<Option<String>, String> {
+    let file = tokio::fs::File::open(session_file)
+        .await
+        .map_err(|e| format!("读取会话文件失败: {}", e))?;
+    let mut lines = tokio::io::BufReader::new(file).lines();
+    let mut cwd = None;
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| format!("读取会话文件失败: {}", e))?
+    {
+        if !line.contains("\"cwd\"") {
+            continue;
+        }
+        if let Some(value) = serde_json::from_str::<serde_json::Value>(&line)
+            .ok()
+            .and_then(|v| v.get("cwd")?.as_str().map(str::to_string))
+        {
+            cwd = Some(value);
+        }
+    }
+    Ok(cwd)
+}
+
+/// 读取项目元数据（文件不存在时返回默认值）
+async fn load_meta() -> Result<ProjectsMeta, String> {
+    let file_path = meta_path()?;
+    if !file_path.exists() {
+        return Ok(ProjectsMeta::default());
+    }
+    let content = tokio::fs::read_to_string(&file_path)
+        .await
+        .map_err(|e| format!("读取项目元数据失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析项目元数据失败: {}", e))
+}
+
+/// 写入项目元数据
+async fn write_meta(meta: &ProjectsMeta) -> Result<(), String> {
+    let file_path = meta_path()?;
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("创建 CCR 配置目录失败: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(meta)
+        .map_err(|e| format!("序列化项目元数据失败: {}", e))?;
+    tokio::fs::write(&file_path, content)
+        .await
+        .map_err(|e| format!("写入项目元数据失败: {}", e))
+}
+
+/// 项目元数据文件路径：`~/.mo/CCR/projects-meta.json`
+fn meta_path() -> Result<PathBuf, String> {
+    Ok(path::get_ccr_config_path()?.join(PROJECTS_META_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_last_cwd_of_latest_session() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        std::fs::write(dir.join("old.jsonl"), "{\"type\":\"user\",\"cwd\":\"/old\"}\n").unwrap();
+        std::fs::write(
+            dir.join("new.jsonl"),
+            concat!(
+                "{\"type\":\"user\",\"cwd\":\"/w/my-app\"}\n",
+                "{\"type\":\"assistant\",\"cwd\":\"/w/my-app/src\"}\n",
+                "{\"type\":\"summary\",\"summary\":\"s\"}\n",
+            ),
+        )
+        .unwrap();
+        std::fs::write(dir.join("agent-1.jsonl"), "{\"cwd\":\"/agent\"}\n").unwrap();
+        // 显式设置 mtime，不依赖写入先后的时间差
+        let base = std::time::SystemTime::now();
+        for (name, age) in [("old.jsonl", 60), ("new.jsonl", 0)] {
+            let file = std::fs::File::options().write(true).open(dir.join(name)).unwrap();
+            file.set_modified(base - std::time::Duration::from_secs(age)).unwrap();
+        }
+
+        let latest = latest_session(dir).await.unwrap().unwrap();
+        assert_eq!(latest, dir.join("new.jsonl"));
+        assert_eq!(last_cwd(&latest).await.unwrap().as_deref(), Some("/w/my-app/src"));
+    }
+}
@@ -0,0 +1,171 @@
+//! # 项目级统计
+//!
+//! 为项目概览面板汇总单个项目下全部会话的规模：会话数、JSONL 总行数、文件总字节数，
+//! 累加所有 assistant 消息 usage 得到的 `TokenStats`，以及按天的消息数。
+//!
+//! 按天统计以显示时区切分（见 `utils::time`），显示时区变更后统计缓存随之失效。
+//!
+//! 每个会话文件都要完整读取并解析，超大项目可能有上千个会话，因此使用 `JoinSet`
+//! 并行处理，同时以 `FILE_CONCURRENCY` 限制同时读取的文件数，避免一次把所有文件载入内存。
+//! 统计结果由 `AppCache` 缓存 `PROJECT_STATS_TTL_SECS` 秒（见 `services::cache`）。
+//!
+//! 子 agent 会话（`agent-` 前缀）不计入，与项目会话列表保持一致。
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::models::display::TokenStats;
+use crate::models::message::SessionMessage;
+use crate::services::{parser, scanner};
+use crate::utils::time::{self, DisplayTimezone};
+
+/// 同时读取的会话文件数上限
+const FILE_CONCURRENCY: usize = 8;
+
+/// 项目统计结果
+///
+/// 对应前端 TypeScript 接口 `ProjectStats`。
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStats {
+    /// 会话数量
+    pub session_count: usize,
+    /// JSONL 总行数（不含空行）
+    pub line_count: usize,
+    /// 会话文件总字节数
+    pub total_bytes: u64,
+    /// 全部 assistant 消息 usage 的累加（`tool_calls` 等按会话统计的字段不填）
+    pub token_stats: TokenStats,
+    /// 每天的 user / assistant 消息数（`YYYY-MM-DD` → 条数，按日期升序，显示时区切分）
+    pub daily_messages: BTreeMap<String, usize>,
+}
+
+/// 单个会话文件的统计
+#[derive(Debug, Default)]
+struct FileStats {
+    /// 非空行数
+    line_count: usize,
+    /// 文件字节数
+    bytes: u64,
+    /// 该会话的 usage 累加
+    token_stats: TokenStats,
+    /// 该会话每天的 user / assistant 消息数
+    daily_messages: BTreeMap<String, usize>,
+}
+
+/// 汇总项目目录下全部会话文件的统计
+///
+/// # 参数
+/// - `project_dir` - 项目目录的绝对路径
+///
+/// # 返回值
+/// 会话数、总行数、总字节数与 token 汇总；读取失败的单个文件跳过并写 warn 日志
+///
+/// # 错误
+/// 项目目录无法读取时返回错误
+pub async fn collect_project_stats(project_dir: &Path) -> Result<ProjectStats, String> {
+    let files = scanner::list_session_files(project_dir, false).await?;
+    // 整个统计使用同一时区，避免中途切换设置导致切分口径不一致
+    let tz = time::display_timezone();
+    let semaphore = Arc::new(Semaphore::new(FILE_CONCURRENCY));
+    let mut join_set = JoinSet::new();
+    for file in files {
+        let semaphore = Arc::clone(&semaphore);
+        join_set.spawn(async move {
+            let result = match semaphore.acquire_owned().await {
+                Ok(_permit) => file_stats(&file, tz).await,
+                Err(e) => Err(format!("获取统计并发许可失败: {}", e)),
+            };
+            (file, result)
+        });
+    }
+
+    let mut stats = ProjectStats::default();
+    while let Some(joined) = join_set.join_next().await {
+        match joined {
+            Ok((_, Ok(file))) => {
+                stats.session_count += 1;
+                stats.line_count += file.line_count;
+                stats.total_bytes += file.bytes;
+                stats.token_stats.merge(&file.token_stats);
+                for (day, count) in file.daily_messages {
+                    *stats.daily_messages.entry(day).or_insert(0) += count;
+                }
+            }
+            Ok((file, Err(e))) => log::warn!("统计会话文件失败 {}: {}", file.display(), e),
+            Err(e) => log::warn!("项目统计任务失败: {}", e),
+        }
+    }
+    Ok(stats)
+}
+
+/// 读取单个会话文件并统计
+async fn file_stats(file: &Path, tz: DisplayTimezone) -> Result<FileStats, String> {
+    let bytes = tokio::fs::metadata(file)
+        .await
+        .map_err(|e| format!("读取会话文件信息失败: {}", e))?
+        .len();
+    let messages = parser::read_messages(&file.to_string_lossy()).await?;
+    Ok(FileStats {
+        line_count: messages.len(),
+        bytes,
+        token_stats: accumulate_usage(&messages),
+        daily_messages: count_daily_messages(&messages, tz),
+    })
+}
+
+/// 按天统计 user / assistant 消息数（时间戳缺失或无法解析的消息不计入）
+fn count_daily_messages(messages: &[SessionMessage], tz: DisplayTimezone) -> BTreeMap<String, usize> {
+    let millis = messages
+        .iter()
+        .filter(|msg| matches!(msg.get("type").and_then(|v| v.as_str()), Some("user" | "assistant")))
+        .filter_map(|msg| msg.get("timestamp").and_then(|v| v.as_str()))
+        .filter_map(time::parse_iso8601_millis);
+    time::count_by_day(millis, tz)
+}
+
+/// 累加所有 assistant 消息的 `message.usage`
+fn accumulate_usage(messages: &[SessionMessage]) -> TokenStats {
+    let mut stats = TokenStats::default();
+    for msg in messages {
+        if msg.get("type").and_then(|v| v.as_str()) == Some("assistant") {
+            stats.accumulate(&msg.get("message").and_then(|m| m.get("usage")).cloned());
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_collect_project_stats() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        let session = concat!(
+            "{\"type\":\"user\",\"message\":{\"content\":\"hi\"}}\n",
+            "{\"type\":\"assistant\",\"message\":{\"usage\":{\"input_tokens\":10,\"output_tokens\":5}}}\n",
+            "\n",
+            "{\"type\":\"assistant\",\"message\":{\"usage\":{\"input_tokens\":1,\"cache_read_input_tokens\":7,",
+            "\"server_tool_use\":{\"web_search_requests\":2}}}}\n",
+        );
+        std::fs::write(dir.join("a.jsonl"), session).unwrap();
+        let other = "{\"type\":\"assistant\",\"message\":{\"usage\":{\"output_tokens\":3}}}\n";
+        std::fs::write(dir.join("b.jsonl"), other).unwrap();
+        std::fs::write(dir.join("agent-1.jsonl"), "{\"type\":\"assistant\",\"message\":{\"usage\":{\"output_tokens\":100}}}\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "x").unwrap();
+
+        let stats = collect_project_stats(dir).await.unwrap();
+        assert_eq!((stats.session_count, stats.line_count), (2, 4));
+        assert_eq!(stats.total_bytes, (session.len() + other.len()) as u64);
+        let tokens = &stats.token_stats;
+        assert_eq!((tokens.input_tokens, tokens.output_tokens, tokens.cache_read_input_tokens), (11, 8, 7));
+        assert_eq!(tokens.web_search_requests, 2);
+    }
+}
@@ -0,0 +1,110 @@
+//! # 项目摘要服务
+//!
+//! 读取项目目录下的 `CLAUDE.md` 或 `README.md` 开头部分，供项目详情快速回忆「这是干嘛的」。
+//!
+//! ## 只读访问
+//! 项目目录位于 `~/.claude/` 之外，这里只做读取，不经过 file_guard。为防止误读大文件或二进制文件：
+//! - 最多读取 `MAX_SUMMARY_BYTES` 字节、`MAX_SUMMARY_LINES` 行
+//! - 含 NUL 字节或不是有效 UTF-8 的内容直接拒绝
+//!
+//! 结果由 `AppCache` 按源文件 mtime 缓存，避免反复读取。
+
+use std::path::{Path, PathBuf};
+
+use tokio::io::AsyncReadExt;
+
+use crate::models::project::ProjectSummary;
+
+/// 摘要来源文件，按优先级排列
+const SUMMARY_CANDIDATES: &[&str] = &["CLAUDE.md", "README.md", "readme.md"];
+
+/// 最多读取的字节数（64 KB）
+const MAX_SUMMARY_BYTES: u64 = 64 * 1024;
+
+/// 最多返回的行数
+const MAX_SUMMARY_LINES: usize = 80;
+
+/// 查找项目的摘要来源文件
+///
+/// # 返回值
+/// 第一个存在的候选文件路径；都不存在时返回 None
+pub fn find_summary_file(project_path: &str) -> Option<PathBuf> {
+    SUMMARY_CANDIDATES
+        .iter()
+        .map(|name| Path::new(project_path).join(name))
+        .find(|p| p.is_file())
+}
+
+/// 读取摘要文件的开头部分
+///
+/// # 参数
+/// - `file_path` - 摘要来源文件（由 `find_summary_file` 给出）
+///
+/// # 返回值
+/// 前 `MAX_SUMMARY_LINES` 行文本（不超过 `MAX_SUMMARY_BYTES` 字节）
+///
+/// # 错误
+/// 文件读取失败，或内容为二进制 / 非 UTF-8 时返回错误
+pub async fn read_summary(file_path: &Path) -> Result<ProjectSummary, String> {
+    let file = tokio::fs::File::open(file_path)
+        .await
+        .map_err(|e| format!("打开项目摘要文件失败: {}", e))?;
+    let file_size = file
+        .metadata()
+        .await
+        .map_err(|e| format!("读取项目摘要文件信息失败: {}", e))?
+        .len();
+
+    let mut bytes = Vec::new();
+    file.take(MAX_SUMMARY_BYTES)
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|e| format!("读取项目摘要文件失败: {}", e))?;
+
+    let text = decode_text(&bytes)
+        .ok_or_else(|| format!("项目摘要文件不是 UTF-8 文本: {}", file_path.display()))?;
+    let mut lines = text.lines();
+    let head: Vec<&str> = lines.by_ref().take(MAX_SUMMARY_LINES).collect();
+    let truncated = file_size > MAX_SUMMARY_BYTES || lines.next().is_some();
+
+    Ok(ProjectSummary {
+        file_path: file_path.to_string_lossy().to_string(),
+        text: head.join("\n"),
+        truncated,
+    })
+}
+
+/// 将读取到的字节解码为文本
+///
+/// 按字节上限截断可能切断末尾的多字节字符，此时丢弃不完整的尾部；
+/// 其他位置的非法序列或 NUL 字节视为二进制内容。
+///
+/// # 返回值
+/// 有效的 UTF-8 文本；二进制或非 UTF-8 内容返回 None
+fn decode_text(bytes: &[u8]) -> Option<&str> {
+    if bytes.contains(&0) {
+        return None;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some(text),
+        // error_len() 为 None 表示错误仅由末尾不完整的字符引起
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&bytes[..e.valid_up_to()]).ok(),
+        Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(decode_text("# 项目".as_bytes()), Some("# 项目"));
+        // 末尾被截断的多字节字符被丢弃
+        let bytes = "说明".as_bytes();
+        assert_eq!(decode_text(&bytes[..bytes.len() - 1]), Some("说"));
+        // 二进制与非法 UTF-8
+        assert_eq!(decode_text(b"PNG\0data"), None);
+        assert_eq!(decode_text(&[0xff, 0xfe, b'a', b'b']), None);
+    }
+}
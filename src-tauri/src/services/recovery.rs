@@ -0,0 +1,306 @@
+//! # 异常退出恢复服务
+//!
+//! `file_guard` 以「写 `.tmp` → 重命名」的方式原子写入会话文件。应用在写入过程中崩溃时，
+//! 会话目录下会残留 `<session>.jsonl.tmp`。本模块在启动时扫描 `~/.claude/projects/`
+//! 下的此类残留，比对同名正式文件的修改时间与内容完整性，交由用户决定如何处理。
+//!
+//! ## 排除写入中的文件
+//! - 本进程正在写入的 `.tmp`（`file_guard::is_write_in_flight`）
+//! - 修改时间在 `MIN_TMP_AGE` 之内的 `.tmp`（可能是另一个 CCR 实例正在写入）
+//!
+//! ## 处理方式
+//! - `Discard`：删除 `.tmp`（经过 `safe_delete_file`，删除前备份）
+//! - `Overwrite`：用 `.tmp` 覆盖正式文件（经过 `safe_write_file`，覆盖前备份正式文件）
+//! - `KeepBoth`：把 `.tmp` 的内容写入新的会话文件（同样经过 `safe_write_file`），正式文件保持不变
+//!
+//! 三种处理均在正式文件的会话写锁（`AppCache::lock_session`）内进行。
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::services::cache::AppCache;
+use crate::services::file_guard::{self, ATOMIC_TMP_SUFFIX, BackupCheck};
+use crate::services::scanner::system_time_to_iso8601;
+use crate::utils::path;
+
+/// 残留文件的最小存在时间，更新的 `.tmp` 视为正在写入
+const MIN_TMP_AGE: Duration = Duration::from_secs(10);
+
+/// 扫描的最大目录深度（`projects/<项目>/<会话>/subagents/`）
+const MAX_SCAN_DEPTH: usize = 3;
+
+/// 残留 `.tmp` 的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecoveryAction {
+    /// 丢弃 `.tmp`
+    Discard,
+    /// 用 `.tmp` 覆盖正式文件
+    Overwrite,
+    /// 保留两者，`.tmp` 另存为新会话
+    KeepBoth,
+}
+
+/// 一个待处理的写入残留
+///
+/// 由 `get_recovery_candidates` command 返回，对应前端 TypeScript 接口 `RecoveryCandidate`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryCandidate {
+    /// 残留 `.tmp` 文件的绝对路径
+    pub tmp_path: String,
+    /// 同名正式文件的绝对路径（去掉 `.tmp` 后缀）
+    pub target_path: String,
+    /// 正式文件是否存在
+    pub target_exists: bool,
+    /// `.tmp` 文件大小（字节）
+    pub tmp_size: u64,
+    /// 正式文件大小（字节，不存在时为 None）
+    pub target_size: Option<u64>,
+    /// `.tmp` 修改时间（ISO 8601）
+    pub tmp_modified: String,
+    /// 正式文件修改时间（ISO 8601，不存在时为 None）
+    pub target_modified: Option<String>,
+    /// `.tmp` 是否比正式文件更新（正式文件不存在时为 true）
+    pub tmp_newer: bool,
+    /// `.tmp` 内容完整性检查
+    pub tmp_check: BackupCheck,
+    /// 正式文件内容完整性检查（不存在时为 None）
+    pub target_check: Option<BackupCheck>,
+    /// 建议的处理方式
+    pub suggested_action: RecoveryAction,
+}
+
+/// 扫描 `~/.claude/projects/` 下的写入残留
+///
+/// # 返回值
+/// 残留列表（按路径排序）；projects 目录不存在时返回空列表
+///
+/// # 错误
+/// 无法确定 Claude 数据目录时返回错误
+pub async fn scan_candidates() -> Result<Vec<RecoveryCandidate>, String> {
+    let projects_dir = path::get_claude_data_path()?.join("projects");
+    tokio::task::spawn_blocking(move || {
+        let mut tmp_files = Vec::new();
+        collect_tmp_files(&projects_dir, 0, &mut tmp_files);
+        tmp_files.sort();
+        tmp_files
+            .iter()
+            .filter_map(|tmp| inspect(tmp, SystemTime::now()))
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("扫描写入残留失败: {}", e))
+}
+
+/// 处理一个写入残留
+///
+/// # 参数
+/// - `tmp_path` - 残留 `.tmp` 文件的绝对路径
+/// - `action` - 处理方式
+/// - `cache` - AppCache 引用（会话写锁与临时备份登记）
+///
+/// # 返回值
+/// 处理后保留下来的会话文件路径：`Overwrite` 为正式文件，`KeepBoth` 为新会话文件，`Discard` 为 None
+///
+/// # 错误
+/// 路径不是有效的写入残留、文件正在写入、正式文件被锁定或文件操作失败时返回错误
+pub async fn resolve(
+    tmp_path: &str,
+    action: RecoveryAction,
+    cache: &AppCache,
+) -> Result<Option<String>, String> {
+    let target_path = tmp_path
+        .strip_suffix(ATOMIC_TMP_SUFFIX)
+        .filter(|t| t.ends_with(".jsonl"))
+        .ok_or_else(|| format!("不是会话写入残留: {}", tmp_path))?;
+    file_guard::validate_claude_path(tmp_path)?;
+    if file_guard::is_write_in_flight(Path::new(tmp_path)) {
+        return Err("该文件正在写入中，请稍后再试".to_string());
+    }
+
+    // 与其他写操作共用正式文件的会话写锁，避免处理残留时正式文件被并发改写
+    let _guard = cache.lock_session(target_path).await;
+    match action {
+        RecoveryAction::Discard => {
+            // 经过 safe_delete_file：删除前备份残留文件，误删后仍可从临时备份找回
+            file_guard::safe_delete_file(tmp_path, "discard_tmp", cache).await?;
+            Ok(None)
+        }
+        RecoveryAction::Overwrite => {
+            // 经过 safe_write_file：检查锁定并备份当前正式文件
+            write_from_tmp(tmp_path, target_path, "recover_tmp", cache).await?;
+            Ok(Some(target_path.to_string()))
+        }
+        RecoveryAction::KeepBoth => {
+            let new_path = Path::new(target_path)
+                .with_file_name(format!("{}.jsonl", uuid::Uuid::new_v4()))
+                .to_string_lossy()
+                .to_string();
+            write_from_tmp(tmp_path, &new_path, "recover_tmp_keep_both", cache).await?;
+            Ok(Some(new_path))
+        }
+    }
+}
+
+/// 把残留文件的内容经 `safe_write_file` 写入 `dest_path`，成功后删除残留文件
+///
+/// # 错误
+/// 残留文件读取失败、目标会话被锁定或写入、删除失败时返回错误
+async fn write_from_tmp(tmp_path: &str, dest_path: &str, operation: &str, cache: &AppCache) -> Result<(), String> {
+    let content = tokio::fs::read(tmp_path)
+        .await
+        .map_err(|e| format!("读取残留文件失败: {}", e))?;
+    file_guard::safe_write_file(dest_path, &content, operation, cache).await?;
+    // 覆盖正式文件时原子写入使用的正是同名 `.tmp`，写入完成后它已被重命名，不存在即视为已清理
+    match tokio::fs::remove_file(tmp_path).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("删除残留文件失败: {}", e)),
+    }
+}
+
+/// 递归收集目录下的 `*.jsonl.tmp` 文件（不可读的目录静默跳过）
+fn collect_tmp_files(dir: &Path, depth: usize, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if depth < MAX_SCAN_DEPTH {
+                collect_tmp_files(&path, depth + 1, out);
+            }
+        } else if file_type.is_file()
+            && path
+                .to_str()
+                .and_then(|p| p.strip_suffix(ATOMIC_TMP_SUFFIX))
+                .is_some_and(|target| target.ends_with(".jsonl"))
+        {
+            out.push(path);
+        }
+    }
+}
+
+/// 比对残留与正式文件，生成候选项
+///
+/// 写入中或无法读取的残留返回 None。
+fn inspect(tmp_path: &Path, now: SystemTime) -> Option<RecoveryCandidate> {
+    if file_guard::is_write_in_flight(tmp_path) {
+        return None;
+    }
+    let tmp_meta = std::fs::metadata(tmp_path).ok()?;
+    let tmp_mtime = tmp_meta.modified().ok()?;
+    if now.duration_since(tmp_mtime).unwrap_or_default() < MIN_TMP_AGE {
+        return None;
+    }
+
+    let tmp_str = tmp_path.to_str()?;
+    let target_path = PathBuf::from(tmp_str.strip_suffix(ATOMIC_TMP_SUFFIX)?);
+    let tmp_check = check_file(tmp_path)?;
+
+    let target_meta = std::fs::metadata(&target_path).ok();
+    let target_mtime = target_meta.as_ref().and_then(|m| m.modified().ok());
+    let target_check = target_meta.as_ref().and_then(|_| check_file(&target_path));
+    let tmp_newer = target_mtime.is_none_or(|t| tmp_mtime > t);
+
+    Some(RecoveryCandidate {
+        tmp_path: tmp_str.to_string(),
+        target_path: target_path.to_string_lossy().to_string(),
+        target_exists: target_meta.is_some(),
+        tmp_size: tmp_meta.len(),
+        target_size: target_meta.as_ref().map(|m| m.len()),
+        tmp_modified: system_time_to_iso8601(tmp_mtime),
+        target_modified: target_mtime.map(system_time_to_iso8601),
+        tmp_newer,
+        suggested_action: suggest(&tmp_check, target_check.as_ref(), tmp_newer),
+        tmp_check,
+        target_check,
+    })
+}
+
+/// 按 JSONL 规则检查文件内容完整性
+fn check_file(file_path: &Path) -> Option<BackupCheck> {
+    let content = std::fs::read_to_string(file_path).ok()?;
+    Some(file_guard::check_backup_content(&content, true))
+}
+
+/// 根据完整性与新旧程度给出建议
+///
+/// - `.tmp` 完整且比正式文件新（或正式文件缺失 / 损坏）→ 覆盖
+/// - `.tmp` 完整但比正式文件旧 → 保留两者，交由用户比较
+/// - `.tmp` 不完整（写入中途崩溃的典型表现）→ 丢弃
+fn suggest(tmp: &BackupCheck, target: Option<&BackupCheck>, tmp_newer: bool) -> RecoveryAction {
+    if tmp.invalid_lines > 0 || tmp.valid_lines == 0 {
+        return RecoveryAction::Discard;
+    }
+    match target {
+        None => RecoveryAction::Overwrite,
+        Some(t) if t.requires_force || tmp_newer => RecoveryAction::Overwrite,
+        Some(_) => RecoveryAction::KeepBoth,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest() {
+        let complete = file_guard::check_backup_content("{\"a\":1}\n{\"b\":2}\n", true);
+        let truncated = file_guard::check_backup_content("{\"a\":1}\n{\"b\":", true);
+
+        assert_eq!(suggest(&truncated, Some(&complete), true), RecoveryAction::Discard);
+        assert_eq!(suggest(&complete, None, true), RecoveryAction::Overwrite);
+        assert_eq!(suggest(&complete, Some(&complete), true), RecoveryAction::Overwrite);
+        assert_eq!(suggest(&complete, Some(&complete), false), RecoveryAction::KeepBoth);
+        // 正式文件损坏时即使 .tmp 更旧也建议覆盖
+        let broken = file_guard::check_backup_content("{\"a\":", true);
+        assert_eq!(suggest(&complete, Some(&broken), false), RecoveryAction::Overwrite);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_actions() {
+        let dir = crate::utils::platform::test_home()
+            .join(".claude")
+            .join("projects")
+            .join("recovery-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("s.jsonl");
+        let tmp = dir.join(format!("s.jsonl{}", ATOMIC_TMP_SUFFIX));
+        let tmp_path = tmp.to_string_lossy().to_string();
+        let cache = AppCache::new();
+
+        for action in [RecoveryAction::Discard, RecoveryAction::Overwrite, RecoveryAction::KeepBoth] {
+            std::fs::write(&target, "{\"old\":1}\n").unwrap();
+            std::fs::write(&tmp, "{\"new\":1}\n").unwrap();
+
+            let kept = resolve(&tmp_path, action, &cache).await.unwrap();
+            assert!(!tmp.exists(), "{:?}", action);
+            let target_content = std::fs::read_to_string(&target).unwrap();
+            match action {
+                RecoveryAction::Discard => {
+                    assert_eq!(kept, None);
+                    assert_eq!(target_content, "{\"old\":1}\n");
+                }
+                RecoveryAction::Overwrite => {
+                    assert_eq!(kept.as_deref(), Some(target.to_string_lossy().as_ref()));
+                    assert_eq!(target_content, "{\"new\":1}\n");
+                }
+                RecoveryAction::KeepBoth => {
+                    let new_path = kept.unwrap();
+                    assert_ne!(new_path, target.to_string_lossy());
+                    assert_eq!(std::fs::read_to_string(&new_path).unwrap(), "{\"new\":1}\n");
+                    assert_eq!(target_content, "{\"old\":1}\n");
+                }
+            }
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
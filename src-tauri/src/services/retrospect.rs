@@ -61,6 +61,7 @@ static RE_MKDIR: LazyLock<Regex> = LazyLock::new(|| {
 use crate::models::retrospect::{
     FileOpRecord, FileOpSummary, FileOperation, FileTreeNode, RetrospectTimeline,
 };
+use crate::utils::filename;
 
 // ============================================================================
 // 常量定义
@@ -846,7 +847,7 @@ fn write_zip_file(save_path: &str, files: &[(String, String)]) -> Result<(), Str
     let result = write_zip_file_inner(save_path, files);
     if result.is_err() {
         // 尽力清理残留的不完整 ZIP 文件，忽略清理错误
-        let _ = std::fs::remove_file(save_path);
+        let _ = std::fs::remove_file(filename::to_long_path(save_path));
     }
     result
 }
@@ -862,7 +863,7 @@ fn write_zip_file_inner(save_path: &str, files: &[(String, String)]) -> Result<(
     use zip::ZipWriter;
 
     // 创建输出文件
-    let file = std::fs::File::create(save_path)
+    let file = std::fs::File::create(filename::to_long_path(save_path))
         .map_err(|e| format!("创建 ZIP 文件失败: {}", e))?;
 
     let mut zip = ZipWriter::new(file);
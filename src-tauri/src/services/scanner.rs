@@ -13,17 +13,55 @@
 //! ## v0.4.0 升级：head+tail 轻量读取
 //! 参考 Claude Code 源码 `listSessionsImpl.ts` 的 `readSessionLite` 策略：
 //! - 对每个 JSONL 文件读取 **前 64KB** (head) 和 **后 64KB** (tail)
-//! - 从 head 提取: `cwd`, `gitBranch`, `timestamp`(创建时间), `isSidechain`, `firstPrompt`
-//! - 从 tail 提取: `customTitle`, `aiTitle`, `lastPrompt`, `summary`, `tag`, `gitBranch`
+//! - 从 head 提取: `cwd`, `gitBranch`, `version`, `timestamp`(创建时间), `isSidechain`, `firstPrompt`
+//! - 从 tail 提取: `customTitle`, `aiTitle`, `lastPrompt`, `summary`, `tag`, `gitBranch`, `version`
 //! - 标题优先级: `customTitle` > `aiTitle` > `lastPrompt`
+//!
+//! ## 增量扫描
+//! 每个项目目录扫描后生成 `ProjectSnapshot`（目录 mtime + 各会话文件的 mtime / 大小 / 元数据），
+//! 由 `AppCache` 保存。下次扫描时：
+//! - 目录 mtime 未变：会话文件集合未变，沿用上次的文件列表，不再 read_dir
+//! - 目录 mtime 变化或新增目录：重新枚举会话文件
+//! - 两种情况下都会 stat 每个会话文件：追加写入只改文件本身、不改目录 mtime
+//!   （各平台皆然，Windows 上连新建子文件也可能不更新目录 mtime），
+//!   因此只有 mtime 与大小都未变的会话才复用上次的元数据，其余重新 head+tail 读取
+//! - 沿用的文件列表中有文件已不存在时，回退为重新枚举该目录
+//!
+//! 项目很多但没有变化时，一次扫描的开销约等于对所有目录和会话文件各 stat 一次。
+//!
+//! ## 消息数统计
+//! `ScanOptions::count_lines` 为 true 时逐行读取每个会话文件，统计消息记录数与行数（见 `count_messages`），
+//! 不做 JSON 解析。同时读取的文件数受 `io_concurrency`（来自 `AppLimits`）限制；
+//! 统计结果随快照缓存，文件 mtime / 大小未变时直接沿用，编辑或删除消息后自然重新统计。
+//!
+//! ## 扫描进度
+//! `scan_project_dirs_with_progress` 每完成一个项目目录回调一次进度，回调经 `ProgressThrottle`
+//! 节流：距上次上报满 `PROGRESS_EVERY_PROJECTS` 个项目或 `PROGRESS_INTERVAL` 时间才上报，
+//! 最后一个项目完成时总会上报。
+//!
+//! ## 不可读目录
+//! 单个项目目录不可读（权限不足、符号链接损坏等）不会让整次扫描失败：该目录以空项目返回，
+//! 同时生成一条 `ScanWarning`（目录路径 + 原因）随结果返回，前端据此提示有多少项目无法读取。
+//! 指向目录 / 文件的符号链接按其目标处理。
 
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
-use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use serde::Deserialize;
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt};
+use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
 
-use crate::models::project::{Project, Session};
-use crate::utils::path::decode_project_path;
+use crate::models::project::{Project, ProjectCounts, ScanProgress, ScanResult, ScanWarning, ScanWarningKind, Session};
+use crate::services::archive::{self, Archived};
+use crate::services::classifier::{self, Classification};
+use crate::services::{drafts, fragments, last_viewed, pins, project_meta, session_lock, session_title};
+use crate::utils::path::resolve_project_path;
+use crate::utils::time;
 
 /// head+tail 轻量读取的缓冲区大小（64KB）
 ///
@@ -37,6 +75,146 @@ const LITE_READ_BYTES: u64 = 65_536;
 /// 避免将超长的首条用户消息完整存入内存和传输给前端。
 const MAX_FIRST_PROMPT_LENGTH: usize = 200;
 
+/// 没有任何标题时由首条用户消息生成的会话名称的最大长度（字符数）
+const MAX_PROMPT_NAME_LENGTH: usize = 50;
+
+/// head 区域末尾被截断的行最多补读到的长度（字节）
+///
+/// 首条用户消息很长（如粘贴了大段日志）时整行会超出 head 区域而无法解析，
+/// 补读该行以提取首条消息；超过此长度仍未结束的行放弃。
+const MAX_HEAD_LINE_BYTES: usize = 1024 * 1024;
+
+/// 文件系统 mtime 的最粗精度（FAT 为 2 秒）
+///
+/// 目录 mtime 与扫描时刻相距不足此值时，扫描之后同一时间粒度内的新建文件无法通过 mtime 察觉，
+/// 此时不沿用文件列表。
+const MTIME_GRANULARITY: Duration = Duration::from_secs(2);
+
+/// 统计消息数时的读缓冲区大小
+const MESSAGE_COUNT_BUFFER_BYTES: usize = 256 * 1024;
+
+/// 扫描进度的上报间隔（项目数）
+const PROGRESS_EVERY_PROJECTS: usize = 50;
+
+/// 扫描进度的上报间隔（时间）
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 消息记录行的特征：顶层 `uuid` 字段（Claude Code 写入紧凑 JSON）
+///
+/// user / assistant / system 等消息记录都带 uuid；summary、custom-title、file-history-snapshot
+/// 等元数据行没有（summary 的是 `leafUuid`），字符串内容中的引号会被转义，不会误匹配。
+const MESSAGE_RECORD_MARKER: &[u8] = b"\"uuid\":\"";
+
+/// 扫描选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScanOptions {
+    /// 是否逐行读取每个会话文件统计消息数（填充 `Session.message_count` 与 `Session.line_count`）
+    pub count_lines: bool,
+    /// 统计消息数时同时读取的文件数上限（取自 `AppLimits::io_concurrency`，至少为 1）
+    pub io_concurrency: usize,
+    /// 是否把 `agent-` 前缀的子 agent 会话文件也作为会话返回
+    pub include_agents: bool,
+    /// 是否列出已归档的项目（只影响项目目录的筛选，见 `services::archive`）
+    pub include_archived: bool,
+}
+
+/// 子 agent 会话文件名前缀（`agent-<id>.jsonl`），默认不作为独立会话列出
+pub const AGENT_SESSION_PREFIX: &str = "agent-";
+
+/// 会话列表的排序键
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionSortBy {
+    /// 文件修改时间（`Session.timestamp`）
+    #[default]
+    Time,
+    /// 文件大小（`Session.file_size`）
+    Size,
+    /// 消息数（`Session.line_count`，未统计时取 `message_count`）
+    Count,
+    /// 显示名称（别名 > 标题 > 摘要 > 首条消息，大小写不敏感）
+    Name,
+}
+
+/// 排序方向
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortOrder {
+    /// 升序
+    Asc,
+    /// 降序
+    #[default]
+    Desc,
+}
+
+/// `scan_project_dirs` 的扫描结果
+#[derive(Debug, Default)]
+pub struct ScannedDirs {
+    /// 项目列表，与输入顺序一致；无法读取的目录以空项目返回
+    pub projects: Vec<Project>,
+    /// 本次扫描的快照（目录名 → 快照），无法读取的目录不产生快照
+    pub snapshots: HashMap<String, ProjectSnapshot>,
+    /// 无法读取的项目目录
+    pub warnings: Vec<ScanWarning>,
+}
+
+/// 项目目录的扫描快照，供下次扫描增量复用（见模块文档「增量扫描」）
+#[derive(Debug, Clone)]
+pub struct ProjectSnapshot {
+    /// 扫描时项目目录的 mtime
+    dir_mtime: SystemTime,
+    /// 开始扫描的时刻
+    scanned_at: SystemTime,
+    /// 下次扫描是否可以按目录 mtime 沿用文件列表（强制刷新时置为 false）
+    listing_trusted: bool,
+    /// 文件列表是否包含 `agent-` 前缀的子 agent 会话
+    includes_agents: bool,
+    /// 扫描时的会话文件
+    files: Vec<SessionFileSnapshot>,
+}
+
+/// 单个会话文件的扫描快照
+#[derive(Debug, Clone)]
+struct SessionFileSnapshot {
+    /// 会话文件路径
+    path: PathBuf,
+    /// 扫描时的文件 mtime
+    mtime: SystemTime,
+    /// 扫描时的文件大小
+    size: u64,
+    /// 轻量读取的结果（读取失败为 None，文件未变化时同样不再重试）
+    session: Option<Session>,
+    /// `session.message_count` 是否已统计（未要求统计时保持 0）
+    counted: bool,
+}
+
+impl ProjectSnapshot {
+    /// 快照中的会话（按时间戳降序）
+    fn sessions(&self) -> Vec<Session> {
+        let mut sessions: Vec<Session> = self.files.iter().filter_map(|f| f.session.clone()).collect();
+        sessions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        sessions
+    }
+
+    /// 不再沿用本快照的文件列表，下次扫描重新枚举目录（会话元数据仍按文件 mtime / 大小复用）
+    ///
+    /// 用于强制刷新：部分文件系统（如 Windows 上的某些场景）新建子文件后目录 mtime 不更新。
+    pub fn distrust_listing(&mut self) {
+        self.listing_trusted = false;
+    }
+
+    /// 目录 mtime 为 `dir_mtime`、本次是否包含子 agent 会话为 `include_agents` 时能否沿用本快照的文件列表
+    fn listing_still_valid(&self, dir_mtime: SystemTime, include_agents: bool) -> bool {
+        self.listing_trusted
+            && self.includes_agents == include_agents
+            && self.dir_mtime == dir_mtime
+            && self
+                .scanned_at
+                .duration_since(self.dir_mtime)
+                .is_ok_and(|elapsed| elapsed >= MTIME_GRANULARITY)
+    }
+}
+
 /// 并行扫描所有项目及其会话
 ///
 /// 扫描 `~/.claude/projects/` 目录下的所有子目录，每个子目录代表一个项目。
@@ -52,67 +230,268 @@ const MAX_FIRST_PROMPT_LENGTH: usize = 200;
 /// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
 ///
 /// # 返回值
-/// 返回按最新会话时间倒序排列的 Project 数组
+/// 按最新会话时间倒序排列的项目，以及无法读取的项目目录
+///
+/// # 错误
+/// 如果 projects 目录不可读，返回错误信息
+pub async fn scan_all_projects(claude_path: &str) -> Result<ScanResult, String> {
+    let (project_dirs, mut warnings) = list_project_dirs_with_warnings(claude_path).await?;
+    let archived = archive::load_active().await.unwrap_or_default();
+    let mut scanned = scan_project_dirs(project_dirs, &HashMap::new(), ScanOptions::default(), &archived).await;
+    sort_by_latest_session(&mut scanned.projects);
+    warnings.append(&mut scanned.warnings);
+    Ok(ScanResult {
+        projects: scanned.projects,
+        warnings,
+    })
+}
+
+/// 列出 `~/.claude/projects/` 下的所有项目目录，按目录 mtime 倒序排列
+///
+/// 仅执行一次 read_dir + 每个条目一次 stat，不读取任何会话文件，
+/// 用于分页扫描时先确定项目总数和页内范围。
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
+///
+/// # 返回值
+/// `(目录名, 完整路径)` 列表，最近修改的目录排在前面；projects 目录不存在时返回空列表
+///
+/// # 错误
+/// 如果 projects 目录不可读，返回错误信息
+pub async fn list_project_dirs(claude_path: &str) -> Result<Vec<(String, PathBuf)>, String> {
+    let (project_dirs, warnings) = list_project_dirs_with_warnings(claude_path).await?;
+    for warning in warnings {
+        log::warn!("跳过无法读取的项目目录 {}: {}", warning.path, warning.message);
+    }
+    Ok(project_dirs)
+}
+
+/// 列出项目目录，同时返回无法判断类型的条目（如损坏的符号链接）
+///
+/// 规则同 `list_project_dirs`；指向目录的符号链接按目录处理。
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
+///
+/// # 返回值
+/// `(目录列表, 跳过的条目)`
 ///
 /// # 错误
 /// 如果 projects 目录不可读，返回错误信息
-pub async fn scan_all_projects(claude_path: &str) -> Result<Vec<Project>, String> {
+pub async fn list_project_dirs_with_warnings(
+    claude_path: &str,
+) -> Result<(Vec<(String, PathBuf)>, Vec<ScanWarning>), String> {
     let projects_path = Path::new(claude_path).join("projects");
 
     // 如果 projects 目录不存在，说明没有任何项目数据
     if !projects_path.exists() {
-        return Ok(vec![]);
+        return Ok((vec![], vec![]));
     }
 
-    // 第一步：读取 projects 目录下的所有条目
     let mut dir = tokio::fs::read_dir(&projects_path)
         .await
         .map_err(|e| format!("读取项目目录失败: {}", e))?;
 
-    // 收集所有子目录的名称和完整路径
+    // 收集所有子目录的名称、完整路径和修改时间
     let mut project_dirs = Vec::new();
+    let mut warnings = Vec::new();
     while let Some(entry) = dir
         .next_entry()
         .await
         .map_err(|e| format!("遍历项目目录条目失败: {}", e))?
     {
-        // 检查是否为目录（跳过文件）
-        let file_type = entry
-            .file_type()
-            .await
-            .map_err(|e| format!("获取条目文件类型失败: {}", e))?;
+        // 检查是否为目录（跳过文件）；符号链接按目标判断，目标不可访问时记为警告
+        let is_dir = match entry.file_type().await {
+            Ok(file_type) if !file_type.is_symlink() => file_type.is_dir(),
+            _ => match tokio::fs::metadata(entry.path()).await {
+                Ok(metadata) => metadata.is_dir(),
+                Err(e) => {
+                    warnings.push(scan_warning(&entry.path(), &e).await);
+                    continue;
+                }
+            },
+        };
 
-        if file_type.is_dir() {
+        if is_dir {
             let dir_name = entry.file_name().to_string_lossy().to_string();
-            let dir_path = entry.path();
-            project_dirs.push((dir_name, dir_path));
+            let mtime = entry
+                .metadata()
+                .await
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .unwrap_or(std::time::UNIX_EPOCH);
+            project_dirs.push((dir_name, entry.path(), mtime));
         }
     }
 
-    // 第二步：使用 JoinSet 并行扫描所有项目目录的会话文件
-    let mut join_set = JoinSet::new();
+    // 按目录 mtime 倒序；mtime 相同时按名称排序，保证分页结果稳定
+    project_dirs.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
 
-    for (dir_name, dir_path) in project_dirs {
+    let project_dirs = project_dirs
+        .into_iter()
+        .map(|(name, path, _)| (name, path))
+        .collect();
+    Ok((project_dirs, warnings))
+}
+
+/// 统计项目目录与会话文件数量
+///
+/// 项目目录与会话文件的判定规则与扫描一致：先经 `list_project_dirs` 列出项目目录并排除已归档的项目
+/// （见 `services::archive`），再按 `list_session_files` 的规则枚举会话文件，
+/// 指向文件的符号链接按目标计入，损坏的链接不计入。不读取任何会话内容。
+/// 各项目目录并行统计，单个目录不可读时按 0 计。
+///
+/// # 参数
+/// - `claude_path` - Claude 数据目录路径（`~/.claude/`）
+///
+/// # 错误
+/// projects 目录存在但不可读时返回错误
+pub async fn count_projects(claude_path: &str) -> Result<ProjectCounts, String> {
+    let mut project_dirs = list_project_dirs(claude_path).await?;
+    archive::load_active().await.unwrap_or_default().retain_unarchived(&mut project_dirs);
+
+    let mut counts = ProjectCounts {
+        projects: project_dirs.len(),
+        ..ProjectCounts::default()
+    };
+    let mut join_set = JoinSet::new();
+    for (_, dir) in project_dirs {
         join_set.spawn(async move {
-            // 解码编码后的目录名为原始文件系统路径
-            let project_path = decode_project_path(&dir_name);
+            let files = read_session_files(&dir, true).await.unwrap_or_default();
+            let agents = files
+                .iter()
+                .filter(|f| f.file_name().is_some_and(|n| n.to_string_lossy().starts_with(AGENT_SESSION_PREFIX)))
+                .count();
+            (files.len() - agents, agents)
+        });
+    }
 
-            // 扫描项目目录下的所有会话文件
-            let sessions = scan_project_sessions(&dir_path).await.unwrap_or_default();
+    while let Some(result) = join_set.join_next().await {
+        if let Ok((sessions, agent_sessions)) = result {
+            counts.sessions += sessions;
+            counts.agent_sessions += agent_sessions;
+        }
+    }
+    Ok(counts)
+}
 
-            Project {
-                name: dir_name,
-                path: project_path,
-                sessions,
-            }
+/// 并行扫描给定的项目目录及其会话
+///
+/// 有上次扫描快照的目录按增量方式扫描，只重新读取变化的会话文件。
+/// 扫描后标记置顶项目与会话（见 `services::pins`），并在本批次内标记碎片项目（见 `services::fragments`）。
+///
+/// # 参数
+/// - `project_dirs` - `(目录名, 完整路径)` 列表
+/// - `snapshots` - 上次扫描的快照（目录名 → 快照），可以为空
+/// - `options` - 扫描选项（是否包含子 agent 会话、是否统计消息数）
+/// - `archived` - 本次扫描读取的归档列表，用于标记项目的归档状态（见 `services::archive`）
+///
+/// # 返回值
+/// 项目列表（与输入顺序一致）、本次扫描的快照与无法读取的目录（见 `ScannedDirs`）
+pub async fn scan_project_dirs(
+    project_dirs: Vec<(String, PathBuf)>,
+    snapshots: &HashMap<String, ProjectSnapshot>,
+    options: ScanOptions,
+    archived: &Archived,
+) -> ScannedDirs {
+    scan_project_dirs_with_progress(project_dirs, snapshots, options, archived, |_| {}).await
+}
+
+/// 并行扫描给定的项目目录及其会话，并节流上报扫描进度
+///
+/// 扫描逻辑与 `scan_project_dirs` 相同，上报规则见模块文档「扫描进度」。
+///
+/// # 参数
+/// - `project_dirs` - `(目录名, 完整路径)` 列表
+/// - `snapshots` - 上次扫描的快照（目录名 → 快照），可以为空
+/// - `options` - 扫描选项
+/// - `archived` - 本次扫描读取的归档列表
+/// - `on_progress` - 进度回调（在扫描任务中同步调用，应尽快返回）
+///
+/// # 返回值
+/// 同 `scan_project_dirs`
+pub async fn scan_project_dirs_with_progress(
+    project_dirs: Vec<(String, PathBuf)>,
+    snapshots: &HashMap<String, ProjectSnapshot>,
+    options: ScanOptions,
+    archived: &Archived,
+    mut on_progress: impl FnMut(ScanProgress),
+) -> ScannedDirs {
+    // 统计消息数需要完整读取文件：整次扫描共享一个信号量，限制同时读取的文件数
+    let count_permits = options
+        .count_lines
+        .then(|| Arc::new(Semaphore::new(options.io_concurrency.max(1))));
+
+    // 置顶列表（读取失败仅影响排序，同时清理已删除项目 / 会话的记录）
+    let pins = pins::load_active().await.unwrap_or_default();
+    // 用户确认过的真实路径（读取失败时回退到 cwd 推断与解码）
+    let path_overrides = project_meta::load_overrides().await.unwrap_or_default();
+
+    // 使用 JoinSet 并行扫描所有项目目录的会话文件
+    let mut join_set = JoinSet::new();
+    let total = project_dirs.len();
+    let mut throttle = ProgressThrottle::new();
+
+    for (index, (dir_name, dir_path)) in project_dirs.into_iter().enumerate() {
+        let previous = snapshots.get(&dir_name).cloned();
+        let count_permits = count_permits.clone();
+        let pinned = pins.is_project_pinned(&dir_path);
+        let archived = archived.is_archived(&dir_path);
+        let path_override = path_overrides.get(&dir_name).cloned();
+        join_set.spawn(async move {
+            // 扫描项目目录下的所有会话文件（增量复用未变化的会话）
+            let (snapshot, warning) = match scan_project_sessions(&dir_path, previous, options, count_permits).await {
+                Ok(snapshot) => (Some(snapshot), None),
+                Err(warning) => (None, Some(warning)),
+            };
+            let sessions = snapshot.as_ref().map(ProjectSnapshot::sessions).unwrap_or_default();
+
+            // 用户覆盖优先；否则目录名的编码有歧义，优先采用会话头部记录的 cwd（随快照缓存，无需额外读取文件）
+            let project_path = path_override.unwrap_or_else(|| {
+                resolve_project_path(&dir_name, sessions.iter().filter_map(|s| s.cwd.as_deref()))
+            });
+
+            (
+                index,
+                Project {
+                    name: dir_name,
+                    path: project_path,
+                    sessions,
+                    fragment_of: None,
+                    source: None,
+                    pinned,
+                    session_count: 0,
+                    latest_timestamp: None,
+                    archived,
+                },
+                snapshot,
+                warning,
+            )
         });
     }
 
-    // 第三步：收集所有并行任务的结果
+    // 收集所有并行任务的结果
     let mut projects = Vec::new();
+    let mut fresh_snapshots = HashMap::new();
+    let mut warnings = Vec::new();
     while let Some(result) = join_set.join_next().await {
+        let done = total - join_set.len();
         match result {
-            Ok(project) => projects.push(project),
+            Ok((index, project, snapshot, warning)) => {
+                if throttle.should_report(done, total) {
+                    on_progress(ScanProgress {
+                        done,
+                        total,
+                        current_project: project.name.clone(),
+                    });
+                }
+                if let Some(snapshot) = snapshot {
+                    fresh_snapshots.insert(project.name.clone(), snapshot);
+                }
+                warnings.extend(warning);
+                projects.push((index, project));
+            }
             Err(e) => {
                 // 单个项目扫描失败不影响其他项目，仅记录日志
                 log::warn!("扫描项目任务失败: {}", e);
@@ -120,81 +499,408 @@ pub async fn scan_all_projects(claude_path: &str) -> Result<Vec<Project>, String
         }
     }
 
-    // 第四步：按每个项目中最新会话的时间戳降序排列
-    projects.sort_by(|a, b| {
-        let a_latest = a.sessions.first().map(|s| s.timestamp.as_str()).unwrap_or("");
-        let b_latest = b.sessions.first().map(|s| s.timestamp.as_str()).unwrap_or("");
-        b_latest.cmp(a_latest)
-    });
+    // 恢复输入顺序
+    projects.sort_by_key(|(index, _)| *index);
+    let mut projects: Vec<Project> = projects.into_iter().map(|(_, project)| project).collect();
+
+    // 标记锁定的会话（锁定列表读取失败时仅影响图标显示，写入拦截仍由 file_guard 保证）
+    let locked = session_lock::load_locked().await.unwrap_or_default();
+    if !locked.is_empty() {
+        for session in projects.iter_mut().flat_map(|p| p.sessions.iter_mut()) {
+            session.locked = locked.contains(&session_lock::normalize(&session.file_path));
+        }
+    }
+
+    // 标记有草稿的会话（同上，读取失败仅影响图标显示）
+    let drafts = drafts::load_all().await.unwrap_or_default();
+    if !drafts.is_empty() {
+        for session in projects.iter_mut().flat_map(|p| p.sessions.iter_mut()) {
+            session.has_draft = drafts.contains_key(&session_lock::normalize(&session.file_path));
+        }
+    }
+
+    // 标记查看后又有新消息的会话（同上，读取失败仅影响图标显示）
+    let viewed = last_viewed::load_all().await.unwrap_or_default();
+    if !viewed.is_empty() {
+        for session in projects.iter_mut().flat_map(|p| p.sessions.iter_mut()) {
+            let mtime = time::parse_iso8601_millis(&session.timestamp);
+            session.updated_since_viewed = last_viewed::is_updated(&viewed, &session.file_path, mtime);
+        }
+    }
+
+    // 填充 CCR 会话别名（同上，读取失败仅影响显示名称）
+    let aliases = session_title::load_aliases().await.unwrap_or_default();
+    if !aliases.is_empty() {
+        for session in projects.iter_mut().flat_map(|p| p.sessions.iter_mut()) {
+            session.alias = aliases.get(&session_lock::normalize(&session.file_path)).cloned();
+        }
+    }
+
+    // 标记置顶会话并排到项目内最前
+    if !pins.sessions.is_empty() {
+        for project in projects.iter_mut() {
+            for session in project.sessions.iter_mut() {
+                session.pinned = pins.is_session_pinned(&session.file_path);
+            }
+            sort_sessions(&mut project.sessions);
+        }
+    }
+
+    // 会话数量与最新时间：只返回项目索引时前端依赖这两个字段
+    for project in projects.iter_mut() {
+        refresh_project_totals(project);
+    }
+
+    // 标记本批次内的碎片项目（子目录中误启动产生的空项目）
+    fragments::mark_fragments(&mut projects).await;
+
+    // 警告按路径排序，与并行任务的完成顺序无关
+    warnings.sort_by(|a: &ScanWarning, b: &ScanWarning| a.path.cmp(&b.path));
+    ScannedDirs {
+        projects,
+        snapshots: fresh_snapshots,
+        warnings,
+    }
+}
+
+/// 扫描进度上报的节流器
+struct ProgressThrottle {
+    /// 上次上报时的已完成数
+    last_done: usize,
+    /// 上次上报（或开始扫描）的时间
+    last_at: Instant,
+}
+
+impl ProgressThrottle {
+    fn new() -> Self {
+        Self {
+            last_done: 0,
+            last_at: Instant::now(),
+        }
+    }
+
+    /// 本次是否应上报；返回 true 时同时记为已上报
+    fn should_report(&mut self, done: usize, total: usize) -> bool {
+        self.should_report_at(done, total, Instant::now())
+    }
+
+    /// 以 `now` 为当前时间判断是否应上报（时间由调用方给出，便于测试）
+    fn should_report_at(&mut self, done: usize, total: usize, now: Instant) -> bool {
+        let due = done == total
+            || done - self.last_done >= PROGRESS_EVERY_PROJECTS
+            || now.saturating_duration_since(self.last_at) >= PROGRESS_INTERVAL;
+        if due {
+            self.last_done = done;
+            self.last_at = now;
+        }
+        due
+    }
+}
 
-    Ok(projects)
+/// 按会话列表重算项目的会话数量与最新会话时间
+///
+/// 扫描结束时与缓存定点更新会话后都经过这里，保证两处口径一致。
+pub fn refresh_project_totals(project: &mut Project) {
+    project.session_count = project.sessions.len();
+    project.latest_timestamp = project.sessions.iter().map(|s| s.timestamp.clone()).max();
 }
 
-/// 扫描指定项目目录下的所有会话文件
+/// 排列项目：置顶项目在前，同组内按最新会话的时间戳降序
+pub fn sort_by_latest_session(projects: &mut [Project]) {
+    fn latest(project: &Project) -> &str {
+        project.latest_timestamp.as_deref().unwrap_or("")
+    }
+    projects.sort_by(|a, b| b.pinned.cmp(&a.pinned).then_with(|| latest(b).cmp(latest(a))));
+}
+
+/// 排列会话：置顶会话在前，同组内按时间戳降序
+pub fn sort_sessions(sessions: &mut [Session]) {
+    sort_sessions_by(sessions, SessionSortBy::Time, SortOrder::Desc);
+}
+
+/// 会话排序键的取值（同一次排序中所有会话的取值类型相同）
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum SortValue {
+    Text(String),
+    Number(u64),
+}
+
+/// 按指定键排列会话
 ///
-/// 遍历项目目录中的 `.jsonl` 文件，排除 `agent-` 前缀的子 agent 会话文件。
-/// 对每个文件获取 metadata 并执行 head+tail 轻量读取提取元数据。
+/// 置顶会话始终在前。同组内缺少排序键的会话（大小 / 消息数未统计、没有任何名称）排在末尾，
+/// 不受排序方向影响；键相同或都缺失时依次按时间戳降序、会话 ID 升序，保证顺序稳定。
+/// 排序键每个会话只计算一次（`sort_by_cached_key`），按名称排序时不会在比较中反复小写化。
 ///
-/// # 过滤规则
-/// - 必须是文件（非目录）
-/// - 必须以 `.jsonl` 结尾
-/// - 排除 `agent-` 前缀的文件（子 agent 会话不应作为独立会话展示）
+/// # 参数
+/// - `sessions` - 会话列表
+/// - `sort_by` - 排序键
+/// - `order` - 排序方向
+pub fn sort_sessions_by(sessions: &mut [Session], sort_by: SessionSortBy, order: SortOrder) {
+    fn count(session: &Session) -> Option<u64> {
+        session.line_count.or((session.message_count > 0).then_some(u64::from(session.message_count)))
+    }
+    fn name(session: &Session) -> Option<String> {
+        [&session.alias, &session.name, &session.summary, &session.first_prompt]
+            .into_iter()
+            .flatten()
+            .find(|text| !text.trim().is_empty())
+            .map(|text| text.trim().to_lowercase())
+    }
+
+    sessions.sort_by_cached_key(|session| {
+        let value = match sort_by {
+            SessionSortBy::Time => Some(SortValue::Text(session.timestamp.clone())),
+            SessionSortBy::Size => session.file_size.map(SortValue::Number),
+            SessionSortBy::Count => count(session).map(SortValue::Number),
+            SessionSortBy::Name => name(session).map(SortValue::Text),
+        };
+        (
+            Reverse(session.pinned),
+            directed_key(value, order),
+            Reverse(session.timestamp.clone()),
+            session.id.clone(),
+        )
+    });
+}
+
+/// 按方向排列的排序键：缺失的键总是排在后面，升序取原值、降序取 `Reverse`
+fn directed_key(value: Option<SortValue>, order: SortOrder) -> (bool, Option<SortValue>, Option<Reverse<SortValue>>) {
+    match (value, order) {
+        (None, _) => (true, None, None),
+        (Some(value), SortOrder::Asc) => (false, Some(value), None),
+        (Some(value), SortOrder::Desc) => (false, None, Some(Reverse(value))),
+    }
+}
+
+/// 扫描指定项目目录下的所有会话文件（增量）
+///
+/// 有上次的快照时按模块文档「增量扫描」中的规则复用未变化的会话，
+/// 只对新增或 mtime / 大小变化的文件执行 head+tail 轻量读取。
 ///
 /// # 参数
 /// - `project_dir` - 项目在 `~/.claude/projects/` 下的完整目录路径
+/// - `previous` - 该目录上次扫描的快照
+/// - `options` - 扫描选项（使用其中的 `include_agents`）
+/// - `count_permits` - 需要统计消息数时为限制并发读取的信号量；快照中未统计过的会话只补充统计，
+///   不重新轻量读取
 ///
 /// # 返回值
-/// 返回按时间戳降序排列的 Session 数组
-async fn scan_project_sessions(project_dir: &Path) -> Result<Vec<Session>, String> {
-    let mut dir = tokio::fs::read_dir(project_dir)
+/// 本次扫描的快照
+///
+/// # 错误
+/// 项目目录无法读取时返回对应的扫描警告
+async fn scan_project_sessions(
+    project_dir: &Path,
+    previous: Option<ProjectSnapshot>,
+    options: ScanOptions,
+    count_permits: Option<Arc<Semaphore>>,
+) -> Result<ProjectSnapshot, ScanWarning> {
+    let include_agents = options.include_agents;
+    let scanned_at = SystemTime::now();
+    let dir_mtime = match tokio::fs::metadata(project_dir).await.and_then(|m| m.modified()) {
+        Ok(mtime) => mtime,
+        Err(e) => return Err(scan_warning(project_dir, &e).await),
+    };
+
+    // 目录 mtime 未变：会话文件集合未变，沿用上次的文件列表
+    let reuse_listing = previous
+        .as_ref()
+        .is_some_and(|p| p.listing_still_valid(dir_mtime, include_agents));
+    let mut stats = if reuse_listing {
+        let paths = previous.iter().flat_map(|p| p.files.iter().map(|f| f.path.clone())).collect();
+        stat_session_files(paths).await
+    } else {
+        stat_session_files(session_files_or_warning(project_dir, include_agents).await?).await
+    };
+    if reuse_listing && stats.iter().any(|(_, stat)| stat.is_none()) {
+        // 沿用的列表中有文件已不存在，回退为重新枚举
+        stats = stat_session_files(session_files_or_warning(project_dir, include_agents).await?).await;
+    }
+
+    // mtime 与大小均未变的会话直接复用，其余并行重新轻量读取
+    let previous_files: HashMap<PathBuf, SessionFileSnapshot> = previous
+        .map(|p| p.files.into_iter().map(|f| (f.path.clone(), f)).collect())
+        .unwrap_or_default();
+    let mut files = Vec::with_capacity(stats.len());
+    let mut join_set = JoinSet::new();
+
+    for (path, stat) in stats {
+        let Some((mtime, size)) = stat else { continue };
+        let unchanged = previous_files
+            .get(&path)
+            .filter(|prev| prev.mtime == mtime && prev.size == size)
+            .cloned();
+        let count_permits = count_permits.clone();
+        match unchanged {
+            Some(prev) if count_permits.is_none() || prev.counted => files.push(prev),
+            // 文件未变化，只缺消息数：沿用元数据，补充统计
+            Some(mut prev) => {
+                join_set.spawn(async move {
+                    fill_message_count(&mut prev, count_permits).await;
+                    prev
+                });
+            }
+            None => {
+                join_set.spawn(async move {
+                    let file_name = path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let session = scan_single_session(file_name, path.clone()).await;
+                    let mut file = SessionFileSnapshot { path, mtime, size, session, counted: false };
+                    fill_message_count(&mut file, count_permits).await;
+                    file
+                });
+            }
+        }
+    }
+
+    // 收集结果
+    while let Some(result) = join_set.join_next().await {
+        if let Ok(file) = result {
+            files.push(file);
+        }
+    }
+
+    Ok(ProjectSnapshot {
+        dir_mtime,
+        scanned_at,
+        listing_trusted: true,
+        includes_agents: include_agents,
+        files,
+    })
+}
+
+/// 枚举项目目录下的会话文件
+///
+/// # 过滤规则
+/// - 必须是文件（非目录）；符号链接按目标判断，目标不存在（损坏的链接）时跳过
+/// - 必须以 `.jsonl` 结尾
+/// - 默认排除 `agent-` 前缀的文件（子 agent 会话），`include_agents` 为 true 时保留
+///
+/// # 错误
+/// 目录无法读取时返回错误
+pub async fn list_session_files(project_dir: &Path, include_agents: bool) -> Result<Vec<PathBuf>, String> {
+    read_session_files(project_dir, include_agents)
         .await
-        .map_err(|e| format!("读取项目会话目录失败: {}", e))?;
+        .map_err(|e| format!("读取项目会话目录失败: {}", e))
+}
+
+/// 枚举会话文件，目录无法读取时转为扫描警告
+async fn session_files_or_warning(project_dir: &Path, include_agents: bool) -> Result<Vec<PathBuf>, ScanWarning> {
+    match read_session_files(project_dir, include_agents).await {
+        Ok(files) => Ok(files),
+        Err(e) => Err(scan_warning(project_dir, &e).await),
+    }
+}
+
+/// `list_session_files` 的实际枚举逻辑（保留原始 I/O 错误以便归类）
+async fn read_session_files(project_dir: &Path, include_agents: bool) -> std::io::Result<Vec<PathBuf>> {
+    let mut dir = tokio::fs::read_dir(project_dir).await?;
 
     // 收集所有符合条件的 .jsonl 文件路径
     let mut session_files = Vec::new();
-    while let Some(entry) = dir
-        .next_entry()
-        .await
-        .map_err(|e| format!("遍历会话文件条目失败: {}", e))?
-    {
+    while let Some(entry) = dir.next_entry().await? {
         let file_name = entry.file_name().to_string_lossy().to_string();
 
-        // 过滤条件：.jsonl 文件，非 agent- 前缀
-        if file_name.ends_with(".jsonl") && !file_name.starts_with("agent-") {
-            let file_type = entry.file_type().await.unwrap_or_else(|_| {
-                // 在极端情况下（如符号链接损坏），默认当作普通文件处理
-                std::fs::metadata(entry.path())
-                    .map(|m| m.file_type())
-                    .unwrap_or_else(|_| std::fs::metadata(".").unwrap().file_type())
-            });
+        // 过滤条件：.jsonl 文件，未要求包含子 agent 会话时排除 agent- 前缀
+        if file_name.ends_with(".jsonl") && (include_agents || !file_name.starts_with(AGENT_SESSION_PREFIX)) {
+            let is_file = match entry.file_type().await {
+                Ok(file_type) if !file_type.is_symlink() => file_type.is_file(),
+                _ => tokio::fs::metadata(entry.path()).await.is_ok_and(|m| m.is_file()),
+            };
 
-            if file_type.is_file() {
-                session_files.push((file_name, entry.path()));
+            if is_file {
+                session_files.push(entry.path());
             }
         }
     }
 
-    // 并行获取所有会话文件的元数据 + 轻量读取
-    let mut join_set = JoinSet::new();
+    Ok(session_files)
+}
 
-    for (file_name, file_path) in session_files {
+/// 把目录的 I/O 错误归类为扫描警告
+///
+/// 权限不足直接归类；路径本身是符号链接且目标不可访问时为损坏的链接；其余归为无法读取。
+async fn scan_warning(path: &Path, error: &std::io::Error) -> ScanWarning {
+    let kind = if error.kind() == std::io::ErrorKind::PermissionDenied {
+        ScanWarningKind::PermissionDenied
+    } else if tokio::fs::symlink_metadata(path).await.is_ok_and(|m| m.file_type().is_symlink())
+        && tokio::fs::metadata(path).await.is_err()
+    {
+        ScanWarningKind::BrokenSymlink
+    } else {
+        ScanWarningKind::Unreadable
+    };
+    ScanWarning {
+        path: path.to_string_lossy().to_string(),
+        kind,
+        message: error.to_string(),
+    }
+}
+
+/// 统计消息数与行数并填入快照中的会话（`count_permits` 为 None 或读取失败时不变）
+async fn fill_message_count(file: &mut SessionFileSnapshot, count_permits: Option<Arc<Semaphore>>) {
+    let (Some(permits), Some(session)) = (count_permits, file.session.as_mut()) else {
+        return;
+    };
+    // 信号量从不 close，acquire 不会失败
+    let Ok(_permit) = permits.acquire().await else { return };
+    if let Some((lines, messages)) = count_messages(&file.path).await {
+        session.message_count = u32::try_from(messages).unwrap_or(u32::MAX);
+        session.line_count = Some(lines);
+        file.counted = true;
+    }
+}
+
+/// 统计会话文件的行数与消息记录数（含 `MESSAGE_RECORD_MARKER` 的行）
+///
+/// 逐行按字节查找，不解析 JSON；summary 等元数据行只计入行数。最后一行没有换行符时也计入。
+///
+/// # 返回值
+/// `(行数, 消息记录数)`；读取失败返回 None
+async fn count_messages(path: &Path) -> Option<(u64, u64)> {
+    let file = tokio::fs::File::open(path).await.ok()?;
+    let mut reader = tokio::io::BufReader::with_capacity(MESSAGE_COUNT_BUFFER_BYTES, file);
+    let finder = memchr::memmem::Finder::new(MESSAGE_RECORD_MARKER);
+    let mut line = Vec::new();
+    let (mut lines, mut messages) = (0u64, 0u64);
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).await.ok()? == 0 {
+            break;
+        }
+        lines += 1;
+        if finder.find(&line).is_some() {
+            messages += 1;
+        }
+    }
+    Some((lines, messages))
+}
+
+/// 并行 stat 一批会话文件
+///
+/// # 返回值
+/// `(路径, Some((mtime, 大小)))`；文件不存在或无法 stat 时为 None
+async fn stat_session_files(paths: Vec<PathBuf>) -> Vec<(PathBuf, Option<(SystemTime, u64)>)> {
+    let mut join_set = JoinSet::new();
+    for path in paths {
         join_set.spawn(async move {
-            scan_single_session(file_name, file_path).await
+            let stat = tokio::fs::metadata(&path)
+                .await
+                .ok()
+                .and_then(|m| Some((m.modified().ok()?, m.len())));
+            (path, stat)
         });
     }
 
-    // 收集结果
-    let mut sessions = Vec::new();
+    let mut stats = Vec::new();
     while let Some(result) = join_set.join_next().await {
-        if let Ok(Some(session)) = result {
-            sessions.push(session);
+        if let Ok(stat) = result {
+            stats.push(stat);
         }
     }
-
-    // 按时间戳降序排列（最新修改的会话排在前面）
-    sessions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-
-    Ok(sessions)
+    stats
 }
 
 /// 扫描单个会话文件：获取元数据 + head+tail 轻量读取
@@ -208,7 +914,7 @@ async fn scan_project_sessions(project_dir: &Path) -> Result<Vec<Session>, Strin
 ///
 /// # 返回值
 /// 成功时返回填充了元数据的 `Session`，失败时返回 `None`
-async fn scan_single_session(file_name: String, file_path: PathBuf) -> Option<Session> {
+pub(crate) async fn scan_single_session(file_name: String, file_path: PathBuf) -> Option<Session> {
     // 获取文件元数据以读取最后修改时间和文件大小
     let metadata = tokio::fs::metadata(&file_path).await.ok()?;
     let mtime = metadata.modified().ok()?;
@@ -216,6 +922,7 @@ async fn scan_single_session(file_name: String, file_path: PathBuf) -> Option<Se
 
     // 从文件名中提取会话 ID（去掉 .jsonl 扩展名）
     let session_id = file_name.trim_end_matches(".jsonl").to_string();
+    let is_agent = file_name.starts_with(AGENT_SESSION_PREFIX);
 
     // 将系统时间转换为 ISO 8601 格式字符串
     let timestamp = system_time_to_iso8601(mtime);
@@ -224,10 +931,11 @@ async fn scan_single_session(file_name: String, file_path: PathBuf) -> Option<Se
     let lite_meta = read_session_lite(&file_path, file_size).await.unwrap_or_default();
 
     // 按照 Claude Code 源码的标题优先级确定 name 字段：
-    // customTitle > aiTitle > lastPrompt
+    // customTitle > aiTitle > lastPrompt，都没有时取首条用户消息的前 50 个字符
     let name = lite_meta.custom_title
         .or(lite_meta.ai_title)
-        .or(lite_meta.last_prompt);
+        .or(lite_meta.last_prompt)
+        .or_else(|| lite_meta.first_prompt.as_deref().map(prompt_name));
 
     Some(Session {
         id: session_id,
@@ -243,9 +951,24 @@ async fn scan_single_session(file_name: String, file_path: PathBuf) -> Option<Se
         created_at: lite_meta.created_at,
         file_size: Some(file_size),
         is_sidechain: lite_meta.is_sidechain,
+        version: lite_meta.version,
+        // 锁定状态由 scan_project_dirs 统一标记
+        locked: false,
+        has_draft: false,
+        updated_since_viewed: false,
+        alias: None,
+        pinned: false,
+        is_agent,
+        line_count: None,
     })
 }
 
+/// 由首条用户消息生成会话名称：合并空白为单个空格后截取前 `MAX_PROMPT_NAME_LENGTH` 个字符
+fn prompt_name(prompt: &str) -> String {
+    let single_line = prompt.split_whitespace().collect::<Vec<_>>().join(" ");
+    single_line.chars().take(MAX_PROMPT_NAME_LENGTH).collect()
+}
+
 /// 轻量读取提取的会话元数据
 ///
 /// 存储从 JSONL 文件 head+tail 区域中解析出的所有可用元数据字段。
@@ -260,7 +983,7 @@ struct LiteMetadata {
     last_prompt: Option<String>,
     /// 会话摘要（从尾部 `summary` 条目提取）
     summary: Option<String>,
-    /// 首条用户消息文本（从头部第一条 user 消息提取）
+    /// 首条用户消息文本（从头部第一条普通 user 消息提取）
     first_prompt: Option<String>,
     /// Git 分支名（优先取尾部，回退头部）
     git_branch: Option<String>,
@@ -272,6 +995,8 @@ struct LiteMetadata {
     created_at: Option<String>,
     /// 是否为侧链会话（从头部第一条消息的 isSidechain 字段判断）
     is_sidechain: bool,
+    /// Claude Code 版本（取尾部最后一条带 version 字段的消息）
+    version: Option<String>,
 }
 
 /// 对 JSONL 文件执行 head+tail 轻量读取，提取会话元数据
@@ -280,7 +1005,10 @@ struct LiteMetadata {
 /// - 读取文件的前 64KB (head) 和后 64KB (tail)
 /// - 将读取到的字节按行分割后逐行解析 JSON
 /// - 从 head 行提取：cwd, gitBranch, timestamp, isSidechain, firstPrompt
-/// - 从 tail 行提取：customTitle, aiTitle, lastPrompt, summary, tag, gitBranch
+/// - 从 tail 行提取：customTitle, aiTitle, lastPrompt, summary, tag, gitBranch, version
+///
+/// gitBranch 与 version 以 tail 为准（反映会话结束时的状态）；tail 中没有时
+/// （如尾部全是 summary 等元数据行）回退到 head 中第一条带该字段的消息。
 ///
 /// 对于小于 128KB 的文件，head 和 tail 可能有重叠，通过 tail 行的去重处理避免重复解析。
 ///
@@ -313,8 +1041,9 @@ async fn read_session_lite(file_path: &Path, file_size: u64) -> Result<LiteMetad
     let head_str = String::from_utf8_lossy(&head_buf);
     let head_lines: Vec<&str> = head_str.lines().collect();
 
-    // 用于标记 head 中的 gitBranch（tail 优先级更高，如果 tail 也有则覆盖）
+    // head 中的 gitBranch / version（tail 优先级更高，tail 没有时作为回退）
     let mut head_git_branch: Option<String> = None;
+    let mut head_version: Option<String> = None;
 
     // 解析 head 行
     for line in &head_lines {
@@ -324,10 +1053,18 @@ async fn read_session_lite(file_path: &Path, file_size: u64) -> Result<LiteMetad
         }
         // 尝试解析为 JSON 对象
         if let Ok(obj) = serde_json::from_str::<serde_json::Value>(line) {
-            parse_head_line(&obj, &mut meta, &mut head_git_branch);
+            parse_head_line(&obj, &mut meta, &mut head_git_branch, &mut head_version);
         }
     }
 
+    // head 区域末尾的行被截断且尚未找到首条用户消息时，补读该行（文件指针正位于 head 末尾）
+    if meta.first_prompt.is_none()
+        && file_size > LITE_READ_BYTES
+        && let Some(obj) = read_truncated_head_line(&mut file, &head_buf).await
+    {
+        extract_first_prompt(&obj, &mut meta);
+    }
+
     // ---- 读取 tail 区域（后 64KB）----
     // 仅当文件大于 head 读取范围时才需要单独读取 tail
     if file_size > LITE_READ_BYTES {
@@ -374,10 +1111,13 @@ async fn read_session_lite(file_path: &Path, file_size: u64) -> Result<LiteMetad
         }
     }
 
-    // 如果 tail 中没有找到 gitBranch，使用 head 中的值作为回退
+    // 如果 tail 中没有找到 gitBranch / version，使用 head 中的值作为回退
     if meta.git_branch.is_none() {
         meta.git_branch = head_git_branch;
     }
+    if meta.version.is_none() {
+        meta.version = head_version;
+    }
 
     Ok(meta)
 }
@@ -388,17 +1128,20 @@ async fn read_session_lite(file_path: &Path, file_size: u64) -> Result<LiteMetad
 /// - 第一条消息的 `timestamp` → `created_at`
 /// - 第一条消息的 `cwd` → `cwd`
 /// - 第一条消息的 `gitBranch` → `git_branch`（低优先级，tail 可覆盖）
+/// - 第一条消息的 `version` → `version`（低优先级，tail 可覆盖）
 /// - 第一条消息的 `isSidechain` → `is_sidechain`
-/// - 第一条 `type: "user"` 消息的文本 → `first_prompt`
+/// - 第一条普通 user 消息的文本 → `first_prompt`（见 `extract_first_prompt`）
 ///
 /// # 参数
 /// - `obj` - 解析后的 JSON 对象
 /// - `meta` - 当前累积的元数据（就地修改）
 /// - `head_git_branch` - head 区域发现的 gitBranch（低优先级缓存）
+/// - `head_version` - head 区域发现的 Claude Code 版本（低优先级缓存）
 fn parse_head_line(
     obj: &serde_json::Value,
     meta: &mut LiteMetadata,
     head_git_branch: &mut Option<String>,
+    head_version: &mut Option<String>,
 ) {
     let entry_type = obj.get("type").and_then(|v| v.as_str()).unwrap_or("");
 
@@ -423,6 +1166,13 @@ fn parse_head_line(
         }
     }
 
+    // 提取 Claude Code 版本（head 的值为低优先级，tail 可覆盖）
+    if head_version.is_none()
+        && let Some(version) = obj.get("version").and_then(|v| v.as_str())
+    {
+        *head_version = Some(version.to_string());
+    }
+
     // 检测侧链标记（仅检查第一条消息）
     if !meta.is_sidechain {
         if let Some(true) = obj.get("isSidechain").and_then(|v| v.as_bool()) {
@@ -430,17 +1180,54 @@ fn parse_head_line(
         }
     }
 
-    // 提取首条用户消息文本（仅第一条 user 消息有效）
-    if meta.first_prompt.is_none() && entry_type == "user" {
-        meta.first_prompt = extract_message_text(obj);
-        // 截取到最大长度
-        if let Some(ref mut text) = meta.first_prompt {
-            if text.chars().count() > MAX_FIRST_PROMPT_LENGTH {
-                let truncated: String = text.chars().take(MAX_FIRST_PROMPT_LENGTH).collect();
-                *text = truncated;
-            }
+    // 提取首条用户消息文本（仅第一条普通 user 消息有效）
+    if entry_type == "user" {
+        extract_first_prompt(obj, meta);
+    }
+}
+
+/// 尚未找到首条用户消息时，尝试从该行提取
+///
+/// 复用 `classifier` 的判断，只接受普通用户消息：跳过 isMeta、斜杠命令、
+/// 系统注入与压缩摘要；只含 tool_result 的消息没有文本，同样跳过。
+/// 结果截取到 `MAX_FIRST_PROMPT_LENGTH` 个字符。
+fn extract_first_prompt(obj: &serde_json::Value, meta: &mut LiteMetadata) {
+    if meta.first_prompt.is_some() || !matches!(classifier::classify(obj), Classification::User) {
+        return;
+    }
+    meta.first_prompt = extract_message_text(obj).map(|text| text.chars().take(MAX_FIRST_PROMPT_LENGTH).collect());
+}
+
+/// 补读 head 区域末尾被截断的行并解析
+///
+/// # 参数
+/// - `file` - 文件指针位于 head 区域末尾的会话文件
+/// - `head_buf` - 已读取的 head 区域
+///
+/// # 返回值
+/// 补全后的 JSON 对象；head 恰好以换行结束、读取失败、行超过 `MAX_HEAD_LINE_BYTES` 或解析失败时返回 None
+async fn read_truncated_head_line(file: &mut tokio::fs::File, head_buf: &[u8]) -> Option<serde_json::Value> {
+    let start = head_buf.iter().rposition(|&b| b == b'\n').map_or(0, |pos| pos + 1);
+    let mut line = head_buf[start..].to_vec();
+    if line.is_empty() {
+        return None;
+    }
+    let mut chunk = vec![0u8; LITE_READ_BYTES as usize];
+    loop {
+        let n = file.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        if let Some(end) = memchr::memchr(b'\n', &chunk[..n]) {
+            line.extend_from_slice(&chunk[..end]);
+            break;
+        }
+        line.extend_from_slice(&chunk[..n]);
+        if line.len() > MAX_HEAD_LINE_BYTES {
+            return None;
         }
     }
+    serde_json::from_slice(&line).ok()
 }
 
 /// 从 tail 区域的单行 JSON 中提取元数据条目
@@ -452,6 +1239,7 @@ fn parse_head_line(
 /// - `type: "summary"` → `summary`
 /// - `type: "tag"` → `tag`
 /// - 任何消息的 `gitBranch` 字段 → `git_branch`（tail 优先级最高）
+/// - 任何消息的 `version` 字段 → `version`（保留最后一个）
 ///
 /// 注意：这些条目可能存在多次（每次都会覆盖之前的值），保留最后一次的值。
 ///
@@ -500,6 +1288,11 @@ fn parse_tail_line(obj: &serde_json::Value, meta: &mut LiteMetadata) {
     if let Some(branch) = obj.get("gitBranch").and_then(|v| v.as_str()) {
         meta.git_branch = Some(branch.to_string());
     }
+
+    // 同理保留最后一条消息的 Claude Code 版本
+    if let Some(version) = obj.get("version").and_then(|v| v.as_str()) {
+        meta.version = Some(version.to_string());
+    }
 }
 
 /// 从消息对象中提取纯文本内容
@@ -560,7 +1353,7 @@ fn extract_message_text(obj: &serde_json::Value) -> Option<String> {
 ///
 /// # 返回值
 /// ISO 8601 格式的时间字符串；如果转换失败返回当前 Unix 时间戳字符串
-fn system_time_to_iso8601(time: std::time::SystemTime) -> String {
+pub(crate) fn system_time_to_iso8601(time: std::time::SystemTime) -> String {
     // 计算自 Unix epoch 以来的毫秒数
     match time.duration_since(std::time::UNIX_EPOCH) {
         Ok(duration) => {
@@ -615,3 +1408,219 @@ fn days_to_date(days_since_epoch: u64) -> (u64, u64, u64) {
 
     (y, m, d)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_session_name_from_first_user_prompt() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        // 系统注入与只含 tool_result 的消息被跳过；首条普通消息超出 head 区域，需补读被截断的行
+        let lines = [
+            json!({"type": "user", "isMeta": true, "message": {"role": "user", "content": "注入"}}),
+            json!({"type": "user", "message": {"role": "user", "content": [{"type": "tool_result", "tool_use_id": "t", "content": "r"}]}}),
+            json!({"type": "user", "message": {"role": "user", "content": format!("请帮我\n分析这段日志 {}", "x".repeat(100_000))}}),
+        ];
+        let content: String = lines.iter().map(|l| format!("{}\n", l)).collect();
+        let path = dir.join("a.jsonl");
+        std::fs::write(&path, &content).unwrap();
+
+        let session = scan_single_session("a.jsonl".into(), path.clone()).await.unwrap();
+        assert!(session.first_prompt.as_deref().unwrap().starts_with("请帮我\n分析这段日志 xxx"));
+        let name = session.name.unwrap();
+        assert!(name.starts_with("请帮我 分析这段日志 xxx"));
+        assert_eq!(name.chars().count(), MAX_PROMPT_NAME_LENGTH);
+
+        // custom-title 优先于首条消息
+        std::fs::write(&path, content + "{\"type\":\"custom-title\",\"customTitle\":\"标题\"}\n").unwrap();
+        let session = scan_single_session("a.jsonl".into(), path).await.unwrap();
+        assert_eq!(session.name.as_deref(), Some("标题"));
+    }
+
+    #[test]
+    fn test_progress_throttle() {
+        let mut throttle = ProgressThrottle::new();
+        let start = throttle.last_at;
+        let reported: Vec<usize> = (1..=120).filter(|&done| throttle.should_report_at(done, 120, start)).collect();
+        // 时间不前进时按项目数每 50 个上报一次，最后一个总会上报
+        assert_eq!(reported, vec![50, 100, 120]);
+
+        // 距上次上报满 PROGRESS_INTERVAL 时即使项目数不足也上报，随后重新计时
+        let later = start + PROGRESS_INTERVAL;
+        assert!(throttle.should_report_at(121, 200, later));
+        assert!(!throttle.should_report_at(122, 200, later + PROGRESS_INTERVAL / 2));
+        assert!(throttle.should_report_at(123, 200, later + PROGRESS_INTERVAL));
+    }
+
+    #[test]
+    fn test_sort_sessions_by_keys_with_fallback() {
+        let session = |id: &str, timestamp: &str, size: Option<u64>, name: Option<&str>| -> Session {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "name": name,
+                "timestamp": timestamp,
+                "messageCount": 0,
+                "filePath": format!("/p/{}.jsonl", id),
+                "fileSize": size,
+            }))
+            .unwrap()
+        };
+        let mut sessions = vec![
+            session("a", "2025-01-01", Some(300), Some("beta")),
+            session("b", "2025-01-03", None, None),
+            session("c", "2025-01-02", Some(100), Some("Alpha")),
+            session("d", "2025-01-04", None, Some("gamma")),
+        ];
+        let ids = |sessions: &[Session]| sessions.iter().map(|s| s.id.clone()).collect::<Vec<_>>();
+
+        sort_sessions_by(&mut sessions, SessionSortBy::Size, SortOrder::Asc);
+        // 未统计大小的会话在末尾，按时间戳降序
+        assert_eq!(ids(&sessions), ["c", "a", "d", "b"]);
+        sort_sessions_by(&mut sessions, SessionSortBy::Size, SortOrder::Desc);
+        assert_eq!(ids(&sessions), ["a", "c", "d", "b"]);
+        sort_sessions_by(&mut sessions, SessionSortBy::Name, SortOrder::Asc);
+        assert_eq!(ids(&sessions), ["c", "a", "d", "b"]);
+        sort_sessions_by(&mut sessions, SessionSortBy::Count, SortOrder::Desc);
+        assert_eq!(ids(&sessions), ["d", "b", "c", "a"]);
+
+        sessions[3].pinned = true;
+        sort_sessions(&mut sessions);
+        assert_eq!(ids(&sessions), ["a", "d", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_session_lite_falls_back_to_head_metadata() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("session.jsonl");
+        let head = "{\"type\":\"user\",\"cwd\":\"/w/app\",\"gitBranch\":\"feat/x\",\"version\":\"2.0.1\",\"message\":{\"content\":\"hi\"}}\n";
+        // tail 只有不带 gitBranch / version 的 summary 行
+        let summary = "{\"type\":\"summary\",\"summary\":\"s\"}\n";
+        let content = head.to_string() + &summary.repeat(LITE_READ_BYTES as usize / summary.len() + 10);
+        std::fs::write(&file, &content).unwrap();
+
+        let meta = read_session_lite(&file, content.len() as u64).await.unwrap();
+        assert_eq!(meta.cwd.as_deref(), Some("/w/app"));
+        assert_eq!(meta.git_branch.as_deref(), Some("feat/x"));
+        assert_eq!(meta.version.as_deref(), Some("2.0.1"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_broken_symlinks_become_warnings() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        let projects = root.join("projects");
+        std::fs::create_dir_all(projects.join("ok")).unwrap();
+        std::fs::write(projects.join("ok").join("a.jsonl"), "{}\n").unwrap();
+        std::os::unix::fs::symlink(root.join("missing.jsonl"), projects.join("ok").join("b.jsonl")).unwrap();
+        std::os::unix::fs::symlink(projects.join("ok"), projects.join("linked")).unwrap();
+        std::os::unix::fs::symlink(root.join("missing"), projects.join("broken")).unwrap();
+
+        let (dirs, warnings) = list_project_dirs_with_warnings(root.to_str().unwrap()).await.unwrap();
+        let mut names: Vec<&str> = dirs.iter().map(|(name, _)| name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["linked", "ok"]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, ScanWarningKind::BrokenSymlink);
+        assert!(warnings[0].path.ends_with("broken"));
+
+        let files = list_session_files(&projects.join("ok"), false).await.unwrap();
+        assert_eq!(files, vec![projects.join("ok").join("a.jsonl")]);
+
+        let warning = scan_project_sessions(&projects.join("broken"), None, ScanOptions::default(), None).await.unwrap_err();
+        assert_eq!(warning.kind, ScanWarningKind::BrokenSymlink);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_scan_picks_up_changes() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        let title = |t: &str| format!("{{\"type\":\"custom-title\",\"customTitle\":\"{}\",\"sessionId\":\"a\"}}\n", t);
+        std::fs::write(dir.join("a.jsonl"), title("first")).unwrap();
+        std::fs::write(dir.join("b.jsonl"), title("other")).unwrap();
+        std::fs::write(dir.join("agent-x.jsonl"), title("agent")).unwrap();
+
+        let snapshot = scan_project_sessions(dir, None, ScanOptions::default(), None).await.unwrap();
+        let names = |s: &ProjectSnapshot| {
+            let mut names: Vec<String> = s.sessions().into_iter().filter_map(|s| s.name).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(&snapshot), vec!["first", "other"]);
+
+        // 追加写入（大小变化）、新增与删除文件都能被察觉
+        let mut appended = title("first");
+        appended.push_str(&title("renamed"));
+        std::fs::write(dir.join("a.jsonl"), appended).unwrap();
+        std::fs::write(dir.join("c.jsonl"), title("new")).unwrap();
+        std::fs::remove_file(dir.join("b.jsonl")).unwrap();
+
+        let mut previous = snapshot.clone();
+        previous.distrust_listing();
+        let rescanned = scan_project_sessions(dir, Some(previous), ScanOptions::default(), None).await.unwrap();
+        assert_eq!(names(&rescanned), vec!["new", "renamed"]);
+
+        // 未变化的文件直接复用快照中的元数据
+        let unchanged = scan_project_sessions(dir, Some(rescanned.clone()), ScanOptions::default(), None).await.unwrap();
+        assert_eq!(names(&unchanged), vec!["new", "renamed"]);
+
+        // 要求统计消息数时，未变化的会话补充统计（元数据行不计入）
+        let message = "{\"type\":\"user\",\"uuid\":\"u1\",\"message\":{\"role\":\"user\",\"content\":\"hi\"}}\n";
+        std::fs::write(dir.join("c.jsonl"), format!("{}{}{}", title("new"), message, message)).unwrap();
+        let counted = scan_project_sessions(dir, Some(unchanged), ScanOptions::default(), Some(Arc::new(Semaphore::new(1)))).await.unwrap();
+        let count = |name: &str| counted.sessions().into_iter().find(|s| s.name.as_deref() == Some(name)).unwrap().message_count;
+        assert_eq!((count("new"), count("renamed")), (2, 0));
+
+        // 要求包含子 agent 会话时不沿用排除了它们的文件列表
+        let options = ScanOptions { include_agents: true, ..ScanOptions::default() };
+        let with_agents = scan_project_sessions(dir, Some(counted), options, None).await.unwrap();
+        assert_eq!(names(&with_agents), vec!["agent", "new", "renamed"]);
+        assert!(with_agents.sessions().iter().any(|s| s.is_agent && s.id == "agent-x"));
+
+        // 统计消息数时同时填充行数，新出现的子 agent 会话也补充统计（"renamed" 会话有两行）
+        let permits = Some(Arc::new(Semaphore::new(1)));
+        let counted = scan_project_sessions(dir, Some(with_agents), options, permits).await.unwrap();
+        let renamed = counted.sessions().into_iter().find(|s| s.name.as_deref() == Some("renamed")).unwrap();
+        assert_eq!(renamed.line_count, Some(2));
+        assert!(counted.sessions().iter().all(|s| s.line_count.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_count_projects() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        let projects = root.join("projects");
+        for (project, files) in [("-w-a", vec!["1.jsonl", "2.jsonl", "agent-x.jsonl"]), ("-w-b", vec!["3.jsonl", "notes.txt"])] {
+            std::fs::create_dir_all(projects.join(project)).unwrap();
+            for file in files {
+                std::fs::write(projects.join(project).join(file), "").unwrap();
+            }
+        }
+        // 会话子目录（子 agent 等）与 projects 下的散落文件不计入
+        std::fs::create_dir_all(projects.join("-w-a").join("1")).unwrap();
+        std::fs::write(projects.join("stray.jsonl"), "").unwrap();
+
+        let counts = count_projects(&root.to_string_lossy()).await.unwrap();
+        assert_eq!(counts, ProjectCounts { projects: 2, sessions: 3, agent_sessions: 1 });
+
+        // 指向文件的符号链接按目标计入，损坏的链接不计入
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(projects.join("-w-b").join("3.jsonl"), projects.join("-w-b").join("4.jsonl")).unwrap();
+            std::os::unix::fs::symlink(root.join("missing.jsonl"), projects.join("-w-b").join("5.jsonl")).unwrap();
+            let counts = count_projects(&root.to_string_lossy()).await.unwrap();
+            assert_eq!(counts, ProjectCounts { projects: 2, sessions: 4, agent_sessions: 1 });
+        }
+
+        // 已归档的项目不计入
+        archive::set_archived(&projects.join("-w-a"), true).await.unwrap();
+        let counts = count_projects(&root.to_string_lossy()).await.unwrap();
+        archive::set_archived(&projects.join("-w-a"), false).await.unwrap();
+        assert_eq!(counts.projects, 1);
+        assert_eq!(counts.agent_sessions, 0);
+        assert_eq!(count_projects(&root.join("missing").to_string_lossy()).await.unwrap(), ProjectCounts::default());
+    }
+}
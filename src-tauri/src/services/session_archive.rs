@@ -0,0 +1,188 @@
+//! # 归档导出并删除会话
+//!
+//! 清理旧会话的标准动作是「先导出一份存档，再删除原文件」。本模块把三步合成一个操作：
+//! 1. 按请求的格式（Markdown / JSON）把会话导出到归档目录，
+//!    文件名为 `<最后活跃日期>_<预览摘要>.<扩展名>`，与已有文件重名时追加序号；
+//!    归档是删除前的最后一份副本，Markdown 包含全部显示类型且不剔除噪音（`ExportOptions::complete`）
+//! 2. 逐个校验导出文件存在且非空
+//! 3. 经 `file_guard::safe_delete_file` 删除原文件（强制临时备份，可在应用关闭前恢复）
+//!
+//! 任何一步失败都立即中止，原会话不会被删除；已写出的导出文件保留在归档目录中。
+//! 每次调用只处理一个会话并持有该会话的写锁，批量清理时逐个调用即为各自独立的事务。
+
+use std::path::{Path, PathBuf};
+
+use crate::models::project::Session;
+use crate::services::cache::AppCache;
+use crate::services::export::{self, ExportOptions};
+use crate::services::{file_guard, parser, scanner, session_cleanup};
+use crate::utils::{filename, time};
+
+/// 文件名中预览摘要的最大字符数
+const PREVIEW_MAX_CHARS: usize = 40;
+
+/// 归档支持的导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveFormat {
+    Markdown,
+    Json,
+}
+
+impl ArchiveFormat {
+    /// 从前端传入的格式字符串解析（"markdown" / "json"）
+    fn parse(format: &str) -> Result<Self, String> {
+        match format {
+            "markdown" => Ok(Self::Markdown),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("不支持的导出格式: {}", format)),
+        }
+    }
+
+    /// 导出文件扩展名
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// 导出会话到归档目录，校验通过后删除原文件
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径（已由调用方校验）
+/// - `archive_dir` - 归档目录（不存在时自动创建，已由调用方校验不在 Claude 数据目录内）
+/// - `formats` - 导出格式列表（"markdown" / "json"，重复项忽略）
+/// - `cache` - AppCache 引用，用于会话写锁、删除备份与缓存同步
+///
+/// # 返回值
+/// 导出文件的绝对路径列表（与 `formats` 去重后的顺序一致）
+///
+/// # 错误
+/// 格式列表为空或含不支持的格式、会话读取失败、导出文件写入失败或校验不通过、
+/// 删除失败时返回错误；除最后一种情况外原会话均保持不变
+pub async fn archive_and_delete(
+    session_file_path: &str,
+    archive_dir: &str,
+    formats: &[String],
+    cache: &AppCache,
+) -> Result<Vec<String>, String> {
+    let mut parsed: Vec<ArchiveFormat> = Vec::new();
+    for format in formats {
+        let format = ArchiveFormat::parse(format)?;
+        if !parsed.contains(&format) {
+            parsed.push(format);
+        }
+    }
+    if parsed.is_empty() {
+        return Err("至少需要选择一种导出格式".to_string());
+    }
+
+    let _guard = cache.lock_session(session_file_path).await;
+    let messages = parser::read_messages(session_file_path).await?;
+    let session = session_info(session_file_path, cache).await?;
+    let title = session_title(&session);
+    let stem = file_stem(&session.timestamp, &title);
+
+    let archive_dir = Path::new(archive_dir);
+    tokio::fs::create_dir_all(filename::to_long_path(archive_dir))
+        .await
+        .map_err(|e| format!("创建归档目录失败: {}", e))?;
+
+    let mut exported: Vec<String> = Vec::new();
+    for format in parsed {
+        let content = match format {
+            ArchiveFormat::Markdown => export::to_markdown(&messages, &title, &ExportOptions::complete()),
+            ArchiveFormat::Json => export::to_json(&messages),
+        };
+        let target = unique_path(archive_dir, &stem, format.extension());
+        tokio::fs::write(filename::to_long_path(&target), content)
+            .await
+            .map_err(|e| format!("写入导出文件失败 {}: {}", target.display(), e))?;
+        exported.push(target.to_string_lossy().to_string());
+    }
+
+    // 删除前确认每个导出文件都已落盘且非空
+    for file in &exported {
+        let size = tokio::fs::metadata(filename::to_long_path(file))
+            .await
+            .map_err(|e| format!("导出文件校验失败 {}: {}", file, e))?
+            .len();
+        if size == 0 {
+            return Err(format!("导出文件为空，已中止删除: {}", file));
+        }
+    }
+
+    file_guard::safe_delete_file(session_file_path, "archive_and_delete_session", cache).await?;
+    session_cleanup::forget_session(session_file_path, cache).await;
+
+    Ok(exported)
+}
+
+/// 取会话元数据：优先项目列表缓存（含 CCR 别名），未命中时轻量读取文件
+async fn session_info(session_file_path: &str, cache: &AppCache) -> Result<Session, String> {
+    if let Some(session) = cache.get_cached_session(session_file_path) {
+        return Ok(session);
+    }
+    let path = PathBuf::from(session_file_path);
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or("无效的会话文件路径")?;
+    scanner::scan_single_session(file_name, path)
+        .await
+        .ok_or_else(|| "读取会话信息失败".to_string())
+}
+
+/// 会话标题：CCR 别名 > 原生标题 > 摘要 > 首条提问 > 会话 ID
+fn session_title(session: &Session) -> String {
+    [&session.alias, &session.name, &session.summary, &session.first_prompt]
+        .into_iter()
+        .flatten()
+        .map(|s| s.trim())
+        .find(|s| !s.is_empty())
+        .unwrap_or(&session.id)
+        .to_string()
+}
+
+/// 导出文件主名：`<最后活跃日期>_<预览摘要>`（日期按显示时区切分，无法解析时省略）
+fn file_stem(timestamp: &str, title: &str) -> String {
+    let preview: String = title.chars().take(PREVIEW_MAX_CHARS).collect();
+    let date = time::parse_iso8601_millis(timestamp).and_then(|millis| time::day_key(millis, time::display_timezone()));
+    let stem = match date {
+        Some(date) => format!("{}_{}", date, preview.trim()),
+        None => preview.trim().to_string(),
+    };
+    // 预留扩展名与重名序号的空间
+    filename::sanitize_filename(&stem, filename::MAX_FILENAME_BYTES - 16)
+}
+
+/// 在目录中选取不重名的文件路径：`<主名>.<扩展名>`，已存在时为 `<主名> (2).<扩展名>` 等
+fn unique_path(dir: &Path, stem: &str, extension: &str) -> PathBuf {
+    let mut candidate = dir.join(format!("{}.{}", stem, extension));
+    let mut n = 2;
+    while candidate.exists() {
+        candidate = dir.join(format!("{} ({}).{}", stem, n, extension));
+        n += 1;
+    }
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_stem_and_unique_path() {
+        let stem = file_stem("2025-01-02T12:00:00.000Z", "修复: 登录/注册 流程");
+        assert!(stem.starts_with("2025-01-0"));
+        assert!(stem.ends_with("_修复_ 登录_注册 流程"));
+        assert_eq!(file_stem("bad", &"长".repeat(100)), "长".repeat(PREVIEW_MAX_CHARS));
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        std::fs::write(dir.join("a.md"), "x").unwrap();
+        let next = unique_path(dir, "a", "md");
+        assert_eq!(next.file_name().unwrap(), "a (2).md");
+    }
+}
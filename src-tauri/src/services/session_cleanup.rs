@@ -0,0 +1,35 @@
+//! # 删除会话后的清理
+//!
+//! 会话文件被删除（`delete_session`、归档删除、合并后删除源会话）后，CCR 为它保存的附属数据
+//! 与缓存都要随之清除：CCR 别名、最近查看时间、阅读状态、草稿，以及会话缓存、撤销栈与项目列表缓存中的条目。
+//! 各删除路径统一调用 `forget_session`，不会因为某处漏掉一项而留下指向已删除会话的记录。
+
+use crate::services::cache::AppCache;
+use crate::services::{drafts, last_viewed, session_title, view_state};
+
+/// 清除已删除会话的附属数据与缓存
+///
+/// 在会话文件删除成功后调用（持有会话写锁）。会话已删除，单项清理失败只记录日志。
+///
+/// # 参数
+/// - `session_file_path` - 已删除的会话 JSONL 文件的绝对路径
+/// - `cache` - AppCache 引用，清除会话缓存并从项目列表缓存中移除该会话
+pub async fn forget_session(session_file_path: &str, cache: &AppCache) {
+    if let Err(e) = session_title::set_alias(session_file_path, "").await {
+        log::warn!("删除会话后清除别名失败: {}", e);
+    }
+    if let Err(e) = last_viewed::remove(session_file_path).await {
+        log::warn!("删除会话后清除查看记录失败: {}", e);
+    }
+    if let Err(e) = view_state::remove(session_file_path).await {
+        log::warn!("删除会话后清除阅读状态失败: {}", e);
+    }
+    if let Err(e) = drafts::delete(session_file_path).await {
+        log::warn!("删除会话后清除草稿失败: {}", e);
+    }
+
+    // 会话数与最新时间随项目列表缓存中的移除一并更新
+    cache.invalidate_session(session_file_path);
+    cache.clear_undo(session_file_path, "删除会话");
+    cache.sync_session_file(session_file_path, None).await;
+}
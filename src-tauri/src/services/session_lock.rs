@@ -0,0 +1,146 @@
+//! # 会话只读锁定服务
+//!
+//! 用户可以把重要的归档会话标记为「锁定」，防止误删误改。
+//! 锁定列表持久化在 `~/.mo/CCR/locked-sessions.json`（会话文件绝对路径数组）。
+//!
+//! ## 生效方式
+//! `file_guard::safe_write_file` / `safe_delete_file` 在路径验证后调用 `ensure_unlocked`，
+//! 因此所有经过 file_guard 的写入（编辑、删除、修复、恢复备份等）都会被拦截，
+//! 且不受任何 `force` 参数影响。解锁只能通过 `set_session_locked` 显式完成。
+//!
+//! ## 错误码
+//! 被拦截时返回以 `SESSION_LOCKED_ERROR` 开头的错误字符串，前端据此识别并提示解锁。
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use crate::services::file_guard;
+use crate::utils::path;
+
+/// 锁定列表文件名（位于 `~/.mo/CCR/`）
+const LOCKED_SESSIONS_FILE: &str = "locked-sessions.json";
+
+/// 会话被锁定时返回的错误码（错误字符串前缀）
+pub const SESSION_LOCKED_ERROR: &str = "SessionLocked";
+
+/// 串行化锁定列表的读-改-写，避免并发切换时互相覆盖
+static WRITE_LOCK: LazyLock<tokio::sync::Mutex<()>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+/// 读取锁定列表
+///
+/// # 返回值
+/// 已锁定会话的归一化路径集合；文件不存在时返回空集合
+///
+/// # 错误
+/// 文件存在但读取或解析失败时返回错误（调用方不应把损坏的列表当作「全部未锁定」）
+pub async fn load_locked() -> Result<BTreeSet<String>, String> {
+    let file_path = locked_sessions_path()?;
+    if !file_path.exists() {
+        return Ok(BTreeSet::new());
+    }
+
+    let content = tokio::fs::read_to_string(&file_path)
+        .await
+        .map_err(|e| format!("读取会话锁定列表失败: {}", e))?;
+    let paths: Vec<String> =
+        serde_json::from_str(&content).map_err(|e| format!("解析会话锁定列表失败: {}", e))?;
+    Ok(paths.iter().map(|p| normalize(p)).collect())
+}
+
+/// 写入前检查会话未被锁定
+///
+/// # 错误
+/// - 会话已锁定：返回 `SessionLocked: ...` 错误
+/// - 锁定列表损坏：返回读取错误（宁可拒绝写入也不绕过锁定）
+pub async fn ensure_unlocked(file_path: &str) -> Result<(), String> {
+    if load_locked().await?.contains(&normalize(file_path)) {
+        return Err(format!(
+            "{}: 会话已锁定为只读，请先解锁后再修改: {}",
+            SESSION_LOCKED_ERROR, file_path
+        ));
+    }
+    Ok(())
+}
+
+/// 锁定或解锁会话
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件的绝对路径
+/// - `locked` - true 锁定，false 解锁
+///
+/// # 错误
+/// 锁定列表读取、解析或写入失败时返回错误
+pub async fn set_locked(session_file_path: &str, locked: bool) -> Result<(), String> {
+    let _guard = WRITE_LOCK.lock().await;
+
+    let mut paths = load_locked().await?;
+    let key = normalize(session_file_path);
+    let changed = if locked { paths.insert(key) } else { paths.remove(&key) };
+    if !changed {
+        return Ok(());
+    }
+
+    let file_path = locked_sessions_path()?;
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("创建 CCR 配置目录失败: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(&paths)
+        .map_err(|e| format!("序列化会话锁定列表失败: {}", e))?;
+    // 原子写入：写入锁检查可能与本次写入并发读取该文件，不能读到半截内容
+    file_guard::write_atomic(&file_path.to_string_lossy(), content.as_bytes())
+        .await
+        .map_err(|e| format!("写入会话锁定列表失败: {}", e))
+}
+
+/// 归一化会话路径，作为锁定列表的比较键
+///
+/// 统一使用 `/` 分隔符；Windows 文件系统大小写不敏感，额外转为小写。
+pub fn normalize(file_path: &str) -> String {
+    let unified = file_path.replace('\\', "/");
+    if cfg!(target_os = "windows") {
+        unified.to_lowercase()
+    } else {
+        unified
+    }
+}
+
+/// 锁定列表文件路径：`~/.mo/CCR/locked-sessions.json`
+fn locked_sessions_path() -> Result<PathBuf, String> {
+    Ok(path::get_ccr_config_path()?.join(LOCKED_SESSIONS_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 并发锁定/解锁不同会话时，读-改-写经 `WRITE_LOCK` 串行化，不会互相覆盖
+    #[tokio::test]
+    async fn test_concurrent_set_locked_keeps_every_change() {
+        let paths: Vec<String> = (0..16).map(|i| format!("/lock-contention/s{}.jsonl", i)).collect();
+
+        let results = futures_util::future::join_all(paths.iter().map(|p| set_locked(p, true))).await;
+        assert!(results.into_iter().all(|r| r.is_ok()));
+        let locked = load_locked().await.unwrap();
+        assert!(paths.iter().all(|p| locked.contains(p)));
+        let err = ensure_unlocked(&paths[3]).await.unwrap_err();
+        assert!(err.starts_with(SESSION_LOCKED_ERROR));
+
+        // 一半解锁、一半重复锁定交错进行
+        let results = futures_util::future::join_all(
+            paths.iter().enumerate().map(|(i, p)| set_locked(p, i % 2 == 1)),
+        )
+        .await;
+        assert!(results.into_iter().all(|r| r.is_ok()));
+        let locked = load_locked().await.unwrap();
+        for (i, p) in paths.iter().enumerate() {
+            assert_eq!(locked.contains(p), i % 2 == 1, "{}", p);
+        }
+
+        futures_util::future::join_all(paths.iter().map(|p| set_locked(p, false))).await;
+        assert!(ensure_unlocked(&paths[3]).await.is_ok());
+    }
+}
@@ -0,0 +1,330 @@
+//! # 会话合并
+//!
+//! Claude Code 崩溃后重新启动，同一个任务常被拆成两个会话。本模块把源会话的消息并入目标会话，
+//! 写回目标文件（经 `file_guard` 备份），可选删除源会话。两个会话必须位于同一项目目录。
+//! 写回与删除记入同一个审计批次；删除源会话失败时把目标会话写回合并前的内容，
+//! 不会留下「目标已含源消息、源会话仍在」的重复状态。删除源会话时一并清除其别名、
+//! 查看记录、阅读状态与草稿。
+//!
+//! ## 合并位置（`MergePosition`）
+//! - `append`：源会话的全部消息追加到目标末尾
+//! - `timestamp`：按时间戳归并两个会话（各自内部保持文件顺序），没有时间戳的行跟随其前一行
+//!
+//! ## 消息修正
+//! - **uuid 冲突**：源消息的 uuid 已存在于目标会话时重新生成，源会话内指向它的
+//!   `parentUuid` / `logicalParentUuid` 一并改写
+//! - **parentUuid 链**：合并结果中每当消息来源在两个会话间切换，切换后的首条带 uuid 的消息
+//!   接到它之前的最后一条带 uuid 的消息上（`append` 时只有源会话首条接到目标末条；
+//!   `timestamp` 交错时两边的消息都会改接），使合并后的对话链是一条连续的链
+//! - **sessionId**：带有该字段的源消息改写为目标会话 ID（即目标文件名）
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::message::SessionMessage;
+use crate::services::cache::AppCache;
+use crate::services::{audit, file_guard, parser, session_cleanup};
+use crate::utils::time;
+
+/// 源消息插入目标会话的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergePosition {
+    /// 追加到目标末尾
+    Append,
+    /// 按时间戳归并
+    Timestamp,
+}
+
+/// 会话合并结果
+///
+/// 对应前端 TypeScript 接口 `MergeSessionsResult`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeSessionsResult {
+    /// 并入目标会话的消息条数
+    pub merged: usize,
+    /// 因 uuid 冲突而重新生成 uuid 的消息条数
+    pub renamed_uuids: usize,
+    /// 源会话是否已删除
+    pub source_deleted: bool,
+}
+
+/// 把源会话合并到目标会话
+///
+/// 同时持有两个会话的写锁（按路径顺序获取，避免交叉合并时死锁）。
+/// 写回目标与删除源会话在同一个审计批次中；删除失败时回滚目标会话。
+///
+/// # 参数
+/// - `source_path` - 源会话 JSONL 文件的绝对路径
+/// - `target_path` - 目标会话 JSONL 文件的绝对路径（合并结果写回此文件）
+/// - `position` - 源消息的插入位置
+/// - `delete_source` - 合并成功后是否删除源会话（经 `file_guard` 备份）
+/// - `cache` - AppCache 引用，用于会话写锁、备份注册与缓存更新
+///
+/// # 返回值
+/// 合并条数、重新生成 uuid 的条数与源会话是否已删除
+///
+/// # 错误
+/// 两个路径相同或不在同一项目目录、任一会话不存在或为空、读写失败时返回错误
+pub async fn merge_sessions(
+    source_path: &str,
+    target_path: &str,
+    position: MergePosition,
+    delete_source: bool,
+    cache: &AppCache,
+) -> Result<MergeSessionsResult, String> {
+    audit::with_batch(merge_sessions_batched(source_path, target_path, position, delete_source, cache)).await
+}
+
+/// `merge_sessions` 的实现，由外层放进同一个审计批次执行
+async fn merge_sessions_batched(
+    source_path: &str,
+    target_path: &str,
+    position: MergePosition,
+    delete_source: bool,
+    cache: &AppCache,
+) -> Result<MergeSessionsResult, String> {
+    let (source, target) = (Path::new(source_path), Path::new(target_path));
+    if source == target {
+        return Err("源会话与目标会话不能相同".to_string());
+    }
+    if source.parent() != target.parent() {
+        return Err("两个会话不属于同一项目目录，无法合并".to_string());
+    }
+
+    let (first, second) = if source_path < target_path { (source_path, target_path) } else { (target_path, source_path) };
+    let _first_guard = cache.lock_session(first).await;
+    let _second_guard = cache.lock_session(second).await;
+
+    let source_messages = parser::read_messages(source_path).await?;
+    if source_messages.is_empty() {
+        return Err("源会话不存在或没有消息".to_string());
+    }
+    let target_messages = parser::read_messages_for_write(target_path).await?;
+    if target_messages.is_empty() {
+        return Err("目标会话不存在或没有消息".to_string());
+    }
+
+    let session_id = target.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let merged_count = source_messages.len();
+    let original_target = delete_source.then(|| target_messages.clone());
+    let (merged, renamed_uuids) = merge_messages(target_messages, source_messages, position, session_id);
+    parser::write_messages(target_path, &merged, "merge_sessions", cache).await?;
+    cache.invalidate_session(target_path);
+    cache.clear_undo(target_path, "合并会话");
+
+    if let Some(original_target) = original_target {
+        if let Err(e) = file_guard::safe_delete_file(source_path, "merge_sessions", cache).await {
+            // 源会话仍在：把目标会话恢复为合并前的内容，避免两边各有一份源消息
+            let rollback = parser::write_messages(target_path, &original_target, "merge_sessions_rollback", cache).await;
+            cache.invalidate_session(target_path);
+            return Err(match rollback {
+                Ok(_) => format!("删除源会话失败，已撤回合并: {}", e),
+                Err(rollback_error) => format!(
+                    "删除源会话失败: {}；撤回合并也失败: {}（合并前的目标会话可从备份恢复）",
+                    e, rollback_error
+                ),
+            });
+        }
+        session_cleanup::forget_session(source_path, cache).await;
+    }
+
+    Ok(MergeSessionsResult {
+        merged: merged_count,
+        renamed_uuids,
+        source_deleted: delete_source,
+    })
+}
+
+/// 合并两个会话的消息列表（规则见模块文档）
+///
+/// # 参数
+/// - `target` - 目标会话的消息
+/// - `source` - 源会话的消息
+/// - `position` - 源消息的插入位置
+/// - `session_id` - 目标会话 ID，写入源消息的 `sessionId`
+///
+/// # 返回值
+/// `(合并后的消息列表, 重新生成 uuid 的条数)`
+pub fn merge_messages(
+    target: Vec<SessionMessage>,
+    mut source: Vec<SessionMessage>,
+    position: MergePosition,
+    session_id: &str,
+) -> (Vec<SessionMessage>, usize) {
+    let renamed = rename_conflicting_uuids(&target, &mut source);
+    for msg in source.iter_mut().filter_map(|m| m.as_object_mut()) {
+        if msg.contains_key("sessionId") {
+            msg.insert("sessionId".to_string(), Value::String(session_id.to_string()));
+        }
+    }
+
+    // 合并后每条消息是否来自源会话
+    let mut merged: Vec<(bool, SessionMessage)> = match position {
+        MergePosition::Append => target
+            .into_iter()
+            .map(|m| (false, m))
+            .chain(source.into_iter().map(|m| (true, m)))
+            .collect(),
+        MergePosition::Timestamp => merge_by_timestamp(target, source),
+    };
+    relink_at_switches(&mut merged);
+
+    (merged.into_iter().map(|(_, m)| m).collect(), renamed)
+}
+
+/// 在消息来源切换处改接 parentUuid，使合并结果是一条连续的对话链
+///
+/// 切换后的首条带 uuid 的消息接到它之前的最后一条带 uuid 的消息上；同一来源内部的链保持不变。
+///
+/// # 参数
+/// - `merged` - 合并结果，每项为 `(是否来自源会话, 消息)`
+fn relink_at_switches(merged: &mut [(bool, SessionMessage)]) {
+    let mut previous: Option<(bool, String)> = None;
+    for (from_source, msg) in merged.iter_mut() {
+        let Some(uuid) = uuid_of(msg).map(str::to_string) else {
+            continue;
+        };
+        if let Some((previous_from_source, previous_uuid)) = previous.replace((*from_source, uuid))
+            && previous_from_source != *from_source
+            && let Some(obj) = msg.as_object_mut()
+        {
+            obj.insert("parentUuid".to_string(), Value::String(previous_uuid));
+        }
+    }
+}
+
+/// 为与目标会话冲突的源消息重新生成 uuid，并改写源会话内对它们的引用
+///
+/// # 返回值
+/// 重新生成 uuid 的条数
+fn rename_conflicting_uuids(target: &[SessionMessage], source: &mut [SessionMessage]) -> usize {
+    let existing: HashSet<&str> = target.iter().filter_map(uuid_of).collect();
+    let mut renames: HashMap<String, String> = HashMap::new();
+    for msg in source.iter_mut() {
+        let Some(uuid) = uuid_of(msg).filter(|u| existing.contains(u)).map(str::to_string) else {
+            continue;
+        };
+        let new_uuid = uuid::Uuid::new_v4().to_string();
+        if let Some(obj) = msg.as_object_mut() {
+            obj.insert("uuid".to_string(), Value::String(new_uuid.clone()));
+        }
+        renames.insert(uuid, new_uuid);
+    }
+
+    if !renames.is_empty() {
+        for obj in source.iter_mut().filter_map(|m| m.as_object_mut()) {
+            for key in ["parentUuid", "logicalParentUuid"] {
+                if let Some(new_uuid) = obj.get(key).and_then(|v| v.as_str()).and_then(|p| renames.get(p)) {
+                    let new_uuid = Value::String(new_uuid.clone());
+                    obj.insert(key.to_string(), new_uuid);
+                }
+            }
+        }
+    }
+    renames.len()
+}
+
+/// 按时间戳归并两个消息列表（各自内部保持原顺序）
+///
+/// 没有可解析时间戳的行沿用其前一行的时间，因此总是紧跟前一行输出。
+fn merge_by_timestamp(target: Vec<SessionMessage>, source: Vec<SessionMessage>) -> Vec<(bool, SessionMessage)> {
+    let target = with_sort_keys(target);
+    let source = with_sort_keys(source);
+    let mut merged = Vec::with_capacity(target.len() + source.len());
+    let (mut target, mut source) = (target.into_iter().peekable(), source.into_iter().peekable());
+    loop {
+        let take_source = match (target.peek(), source.peek()) {
+            (Some((t, _)), Some((s, _))) => s < t,
+            (None, Some(_)) => true,
+            (Some(_), None) => false,
+            (None, None) => break,
+        };
+        let (from_source, (_, msg)) = if take_source {
+            (true, source.next().expect("已 peek"))
+        } else {
+            (false, target.next().expect("已 peek"))
+        };
+        merged.push((from_source, msg));
+    }
+    merged
+}
+
+/// 为每条消息计算归并用的时间（Unix 毫秒；无时间戳时沿用前一行，开头的行为最小值）
+fn with_sort_keys(messages: Vec<SessionMessage>) -> Vec<(i64, SessionMessage)> {
+    let mut last = i64::MIN;
+    messages
+        .into_iter()
+        .map(|msg| {
+            if let Some(millis) = msg.get("timestamp").and_then(|v| v.as_str()).and_then(time::parse_iso8601_millis) {
+                last = millis;
+            }
+            (last, msg)
+        })
+        .collect()
+}
+
+/// 消息的 uuid（没有或为空时为 None）
+fn uuid_of(msg: &SessionMessage) -> Option<&str> {
+    msg.get("uuid").and_then(|v| v.as_str()).filter(|u| !u.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn msg(uuid: &str, parent: Option<&str>, timestamp: &str) -> Value {
+        json!({"type": "user", "uuid": uuid, "parentUuid": parent, "sessionId": "old", "timestamp": timestamp})
+    }
+
+    fn uuids(messages: &[Value]) -> Vec<&str> {
+        messages.iter().filter_map(uuid_of).collect()
+    }
+
+    #[test]
+    fn test_append_links_chain_and_renames_conflicts() {
+        let target = vec![msg("t1", None, "2025-01-01T00:00:00Z"), msg("t2", Some("t1"), "2025-01-01T00:01:00Z")];
+        let source = vec![
+            msg("s1", None, "2025-01-02T00:00:00Z"),
+            msg("t2", Some("s1"), "2025-01-02T00:01:00Z"),
+            msg("s3", Some("t2"), "2025-01-02T00:02:00Z"),
+        ];
+        let (merged, renamed) = merge_messages(target, source, MergePosition::Append, "target-id");
+        assert_eq!(renamed, 1);
+        assert_eq!(merged.len(), 5);
+        assert_eq!(&uuids(&merged)[..3], ["t1", "t2", "s1"]);
+
+        // 首条源消息接到目标末条
+        assert_eq!(merged[2]["parentUuid"], "t2");
+        // 冲突的 uuid 重新生成，源会话内的引用随之改写
+        let renamed_uuid = merged[3]["uuid"].as_str().unwrap();
+        assert_ne!(renamed_uuid, "t2");
+        assert_eq!(merged[4]["parentUuid"], renamed_uuid);
+        assert!(merged[2..].iter().all(|m| m["sessionId"] == "target-id"));
+        assert_eq!(merged[0]["sessionId"], "old");
+    }
+
+    #[test]
+    fn test_timestamp_merge_interleaves() {
+        let target = vec![
+            msg("t1", None, "2025-01-01T00:00:00Z"),
+            json!({"type": "summary", "summary": "无时间戳"}),
+            msg("t2", Some("t1"), "2025-01-01T00:10:00Z"),
+        ];
+        let source = vec![msg("s1", None, "2025-01-01T00:05:00Z"), msg("s2", Some("s1"), "2025-01-01T00:20:00Z")];
+        let (merged, renamed) = merge_messages(target, source, MergePosition::Timestamp, "target-id");
+        assert_eq!(renamed, 0);
+        assert_eq!(uuids(&merged), ["t1", "s1", "t2", "s2"]);
+        assert_eq!(merged[1]["type"], "summary");
+        // 来源切换处改接，交错后仍是一条连续的链
+        assert_eq!(merged[0]["parentUuid"], Value::Null);
+        assert_eq!(merged[2]["parentUuid"], "t1");
+        assert_eq!(merged[3]["parentUuid"], "s1");
+        assert_eq!(merged[4]["parentUuid"], "t2");
+    }
+}
@@ -0,0 +1,287 @@
+//! # 会话重命名服务
+//!
+//! 会话重命名分两层：
+//! - **CCR 别名**：持久化在 `~/.mo/CCR/session-aliases.json`（会话路径 → 别名），
+//!   只有 CCR 自己认识，不修改会话 JSONL
+//! - **原生标题**（可选）：把标题写回会话 JSONL，让 Claude Code 的 `--resume` 选择列表也显示新名字
+//!
+//! ## 原生标题机制
+//! Claude Code 不同版本使用两种标题格式：
+//! - `custom-title` 行：`{"type":"custom-title","customTitle":"...","sessionId":"..."}`，
+//!   新版本 `/rename` 写入，读取时取文件尾部最后一条
+//! - `summary` 行：`{"type":"summary","summary":"...","leafUuid":"..."}`，
+//!   旧版本按 `leafUuid` 把标题挂在对话链的叶子消息上
+//!
+//! 写回前按会话中实际存在的格式选择机制（见 `detect_native_format`），
+//! 无法确定时拒绝写回，避免写入该版本 Claude Code 不认识的行。
+//!
+//! ## 显示优先级
+//! CCR 别名 > `custom-title` > `ai-title` > `last-prompt`。别名单独放在 `Session.alias`，
+//! 不覆盖扫描得到的 `Session.name`，因此即使会话中已有 Claude Code 写入的 `custom-title`，
+//! CCR 中仍显示别名；清除别名后恢复显示原生标题。
+//!
+//! 别名以会话文件路径为键：会话删除时由 `delete_session` 清除，归并碎片项目时随文件迁移。
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::models::message::SessionMessage;
+use crate::services::{branches, session_lock};
+use crate::utils::path;
+
+/// 别名文件名（位于 `~/.mo/CCR/`）
+const ALIASES_FILE: &str = "session-aliases.json";
+
+/// 表明会话由支持 `custom-title` 的新版本 Claude Code 写入的元数据条目类型
+const MODERN_METADATA_TYPES: [&str; 3] = ["ai-title", "last-prompt", "tag"];
+
+/// 串行化别名文件的读-改-写，避免并发保存时互相覆盖
+static WRITE_LOCK: LazyLock<tokio::sync::Mutex<()>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+/// 原生标题的写入格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NativeTitleFormat {
+    /// `type: "custom-title"` 行
+    CustomTitle,
+    /// `type: "summary"` 行（按 `leafUuid` 关联主线叶子）
+    Summary,
+}
+
+/// 会话重命名结果
+///
+/// 由 `rename_session` command 返回，对应前端 TypeScript 接口 `RenameSessionResult`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameSessionResult {
+    /// 保存后的 CCR 别名（标题为空时别名被清除，为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// 写回原生标题时使用的格式（未写回时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub native_format: Option<NativeTitleFormat>,
+}
+
+/// 读取全部会话别名
+///
+/// # 返回值
+/// 归一化会话路径 → 别名；文件不存在时返回空映射
+///
+/// # 错误
+/// 文件存在但读取或解析失败时返回错误
+pub async fn load_aliases() -> Result<BTreeMap<String, String>, String> {
+    let file_path = aliases_path()?;
+    if !file_path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let content = tokio::fs::read_to_string(&file_path)
+        .await
+        .map_err(|e| format!("读取会话别名文件失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析会话别名文件失败: {}", e))
+}
+
+/// 保存会话别名；去除首尾空白后为空时删除别名
+///
+/// # 返回值
+/// 保存后的别名（已删除时为 None）
+///
+/// # 错误
+/// 别名文件读取、解析或写入失败时返回错误
+pub async fn set_alias(session_file_path: &str, title: &str) -> Result<Option<String>, String> {
+    let title = title.trim();
+    let key = session_lock::normalize(session_file_path);
+
+    let _guard = WRITE_LOCK.lock().await;
+    let mut aliases = load_aliases().await?;
+    let changed = if title.is_empty() {
+        aliases.remove(&key).is_some()
+    } else {
+        aliases.insert(key, title.to_string()).as_deref() != Some(title)
+    };
+    if changed {
+        write_aliases(&aliases).await?;
+    }
+    Ok((!title.is_empty()).then(|| title.to_string()))
+}
+
+/// 按会话中实际存在的条目判断应使用的原生标题格式
+///
+/// - 存在 `custom-title` 行 → `CustomTitle`（它在 Claude Code 的标题优先级中最高）
+/// - 存在带 `leafUuid` 的 `summary` 行 → `Summary`
+/// - 两者都没有，但存在 `ai-title` / `last-prompt` / `tag` 等新版本元数据 → `CustomTitle`
+/// - 其余情况无法确定该会话的 Claude Code 版本认哪种格式
+///
+/// # 错误
+/// 无法确定格式时返回说明原因的错误
+pub fn detect_native_format(messages: &[SessionMessage]) -> Result<NativeTitleFormat, String> {
+    let entry_type = |m: &SessionMessage| m.get("type").and_then(|v| v.as_str()).map(String::from);
+
+    if messages.iter().any(|m| entry_type(m).as_deref() == Some("custom-title")) {
+        return Ok(NativeTitleFormat::CustomTitle);
+    }
+
+    let summaries: Vec<&SessionMessage> = messages
+        .iter()
+        .filter(|m| entry_type(m).as_deref() == Some("summary"))
+        .collect();
+    if !summaries.is_empty() {
+        if summaries.iter().any(|m| m.get("leafUuid").and_then(|v| v.as_str()).is_some()) {
+            return Ok(NativeTitleFormat::Summary);
+        }
+        return Err("会话中的 summary 行缺少 leafUuid，无法确定标题应关联到哪条消息，已拒绝写回".to_string());
+    }
+
+    if messages.iter().any(|m| {
+        entry_type(m).is_some_and(|t| MODERN_METADATA_TYPES.contains(&t.as_str()))
+    }) {
+        return Ok(NativeTitleFormat::CustomTitle);
+    }
+
+    Err("会话中既没有 custom-title 行也没有 summary 行，无法判断写入它的 Claude Code 版本支持哪种标题格式，已拒绝写回".to_string())
+}
+
+/// 把标题写入会话消息（就地修改，其余条目保持不变）
+///
+/// - `CustomTitle`：最后一条条目已经是 `custom-title` 时就地更新，否则在末尾追加一行
+///   （Claude Code 只读取文件尾部的元数据，追加保证新标题可见）
+/// - `Summary`：已有 `leafUuid` 指向主线叶子的 summary 行时就地更新，
+///   否则在最后一条 summary 行之后插入新行
+///
+/// # 参数
+/// - `messages` - 会话的全部原始条目
+/// - `title` - 新标题（调用方保证非空）
+/// - `session_id` - 会话 ID（`custom-title` 行的 `sessionId`）
+///
+/// # 返回值
+/// 实际使用的标题格式
+///
+/// # 错误
+/// 无法确定标题格式，或 `Summary` 格式下找不到主线叶子消息时返回错误
+pub fn apply_native_title(
+    messages: &mut Vec<SessionMessage>,
+    title: &str,
+    session_id: &str,
+) -> Result<NativeTitleFormat, String> {
+    let format = detect_native_format(messages)?;
+    let is_type = |m: &SessionMessage, t: &str| m.get("type").and_then(|v| v.as_str()) == Some(t);
+
+    match format {
+        NativeTitleFormat::CustomTitle => {
+            match messages.last_mut().filter(|m| is_type(m, "custom-title")) {
+                Some(last) => last["customTitle"] = Value::String(title.to_string()),
+                None => messages.push(json!({
+                    "type": "custom-title",
+                    "customTitle": title,
+                    "sessionId": session_id,
+                })),
+            }
+        }
+        NativeTitleFormat::Summary => {
+            let leaf = branches::main_leaf_uuid(messages)
+                .ok_or("会话中没有可关联标题的对话消息，已拒绝写回")?;
+            let existing = messages.iter().rposition(|m| {
+                is_type(m, "summary") && m.get("leafUuid").and_then(|v| v.as_str()) == Some(leaf.as_str())
+            });
+            match existing {
+                Some(idx) => messages[idx]["summary"] = Value::String(title.to_string()),
+                None => {
+                    let insert_at = messages
+                        .iter()
+                        .rposition(|m| is_type(m, "summary"))
+                        .map_or(0, |idx| idx + 1);
+                    messages.insert(insert_at, json!({
+                        "type": "summary",
+                        "summary": title,
+                        "leafUuid": leaf,
+                    }));
+                }
+            }
+        }
+    }
+    Ok(format)
+}
+
+/// 写回别名文件
+async fn write_aliases(aliases: &BTreeMap<String, String>) -> Result<(), String> {
+    let file_path = aliases_path()?;
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("创建 CCR 配置目录失败: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(aliases)
+        .map_err(|e| format!("序列化会话别名失败: {}", e))?;
+    tokio::fs::write(&file_path, content)
+        .await
+        .map_err(|e| format!("写入会话别名文件失败: {}", e))
+}
+
+/// 别名文件路径：`~/.mo/CCR/session-aliases.json`
+fn aliases_path() -> Result<PathBuf, String> {
+    Ok(path::get_ccr_config_path()?.join(ALIASES_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(uuid: &str, parent: Option<&str>, ts: &str) -> SessionMessage {
+        json!({ "type": "user", "uuid": uuid, "parentUuid": parent, "timestamp": ts })
+    }
+
+    #[test]
+    fn test_apply_native_title() {
+        // custom-title：末尾已是 custom-title 时就地更新，否则追加
+        let mut messages = vec![
+            msg("a", None, "2026-01-01T00:00:01Z"),
+            json!({ "type": "custom-title", "customTitle": "旧标题", "sessionId": "s1" }),
+        ];
+        assert_eq!(apply_native_title(&mut messages, "新标题", "s1"), Ok(NativeTitleFormat::CustomTitle));
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1]["customTitle"], "新标题");
+        messages.push(msg("b", Some("a"), "2026-01-01T00:00:02Z"));
+        apply_native_title(&mut messages, "再改", "s1").unwrap();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[3]["customTitle"], "再改");
+        assert_eq!(messages[3]["sessionId"], "s1");
+
+        // summary：更新指向主线叶子的行，其他分支的 summary 保持不变
+        let mut messages = vec![
+            json!({ "type": "summary", "summary": "旧分支", "leafUuid": "b" }),
+            msg("a", None, "2026-01-01T00:00:01Z"),
+            msg("b", Some("a"), "2026-01-01T00:00:02Z"),
+            msg("c", Some("a"), "2026-01-01T00:00:03Z"),
+        ];
+        assert_eq!(apply_native_title(&mut messages, "主线", "s1"), Ok(NativeTitleFormat::Summary));
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[0]["summary"], "旧分支");
+        assert_eq!(messages[1], json!({ "type": "summary", "summary": "主线", "leafUuid": "c" }));
+        // 再次写入时就地更新刚插入的行
+        apply_native_title(&mut messages, "主线 2", "s1").unwrap();
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[1]["summary"], "主线 2");
+    }
+
+    #[test]
+    fn test_detect_native_format_rejects_uncertain() {
+        // 只有对话消息：无法判断版本
+        let plain = vec![msg("a", None, "2026-01-01T00:00:01Z")];
+        assert!(detect_native_format(&plain).is_err());
+
+        // summary 缺少 leafUuid：无法关联
+        let mut broken = plain.clone();
+        broken.push(json!({ "type": "summary", "summary": "x" }));
+        assert!(detect_native_format(&broken).is_err());
+
+        // 新版本元数据存在时使用 custom-title
+        let mut modern = plain.clone();
+        modern.push(json!({ "type": "last-prompt", "lastPrompt": "hi" }));
+        assert_eq!(detect_native_format(&modern), Ok(NativeTitleFormat::CustomTitle));
+    }
+}
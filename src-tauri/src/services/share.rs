@@ -0,0 +1,215 @@
+//! # 导出后分享
+//!
+//! 把导出内容写到 TEMP 下的 `ccr-exports/` 目录，再交给系统把文件递到用户手边：
+//! - **macOS**：在 Finder 中显示并选中文件（`open -R`），用户可直接使用 Finder 的「共享」菜单
+//! - **Windows**：打开资源管理器并选中文件（`explorer /select,`），用户可使用「发送到」
+//! - **Linux**：用 `xdg-open` 打开文件所在目录
+//!
+//! 系统原生的分享面板（NSSharingServicePicker）必须依附于应用自己的窗口弹出，
+//! 外部进程无法调起，因此 macOS 同样退化为在 Finder 中定位文件。
+//!
+//! 平台命令由 `share_command` 构造、`launch` 执行，构造层不依赖当前平台，便于测试。
+//! 启动失败不视为错误：导出文件已经写好，返回其路径由前端提示用户手动处理。
+//! `ccr-exports/` 中的文件由 `temp_cleanup` 按保留策略清理。
+
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::thread::JoinHandle;
+
+use serde::Serialize;
+
+use crate::utils::{filename, platform};
+
+/// 导出文件所在的 TEMP 子目录（与 `temp_cleanup::TEMP_ARTIFACT_DIRS` 一致）
+const EXPORT_DIR_NAME: &str = "ccr-exports";
+
+/// 分享目标平台
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SharePlatform {
+    /// macOS：Finder 中显示
+    MacOs,
+    /// Windows：资源管理器中选中
+    Windows,
+    /// Linux：xdg-open 打开所在目录
+    Linux,
+}
+
+impl SharePlatform {
+    /// 当前编译目标平台（移动端等不支持的平台返回 None）
+    pub fn current() -> Option<Self> {
+        if cfg!(target_os = "macos") {
+            Some(Self::MacOs)
+        } else if cfg!(target_os = "windows") {
+            Some(Self::Windows)
+        } else if cfg!(target_os = "linux") {
+            Some(Self::Linux)
+        } else {
+            None
+        }
+    }
+}
+
+/// 待执行的平台命令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareCommand {
+    /// 可执行文件
+    pub program: &'static str,
+    /// 参数；Windows 下作为一整段原始命令行传入（explorer 不接受标准的参数转义）
+    pub args: Vec<String>,
+}
+
+/// 导出并分享的结果
+///
+/// 对应前端 TypeScript 接口 `ShareResult`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareResult {
+    /// 导出文件的绝对路径
+    pub file_path: String,
+    /// 是否已成功调起系统文件管理器
+    pub shared: bool,
+    /// 调起失败的原因（成功时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 把导出内容写入 TEMP 导出目录，并按平台调起文件管理器
+///
+/// # 参数
+/// - `file_name` - 导出文件名（会再次清理为安全文件名）
+/// - `content` - 导出内容
+///
+/// # 返回值
+/// 导出文件路径与分享是否成功
+///
+/// # 错误
+/// 创建导出目录或写入文件失败时返回错误（调起文件管理器失败不是错误）
+pub async fn write_and_share(file_name: &str, content: &str) -> Result<ShareResult, String> {
+    let dir = platform::temp_dir().join(EXPORT_DIR_NAME);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("创建导出目录失败: {}", e))?;
+    let file_path = unique_path(&dir, &filename::sanitize_filename(file_name, filename::MAX_FILENAME_BYTES));
+    tokio::fs::write(filename::to_long_path(&file_path), content)
+        .await
+        .map_err(|e| format!("写入导出文件失败: {}", e))?;
+
+    let result = match SharePlatform::current() {
+        Some(platform) => launch(&share_command(platform, &file_path)).map(|_| ()),
+        None => Err(platform::unsupported("调起文件管理器")),
+    };
+    if let Err(e) = &result {
+        log::warn!("调起系统分享失败: {}", e);
+    }
+    Ok(ShareResult {
+        file_path: file_path.to_string_lossy().to_string(),
+        shared: result.is_ok(),
+        error: result.err(),
+    })
+}
+
+/// 构造指定平台上定位文件的命令
+///
+/// # 参数
+/// - `platform` - 目标平台
+/// - `file_path` - 导出文件的绝对路径
+pub fn share_command(platform: SharePlatform, file_path: &Path) -> ShareCommand {
+    let path = file_path.to_string_lossy();
+    match platform {
+        SharePlatform::MacOs => ShareCommand {
+            program: "open",
+            args: vec!["-R".to_string(), path.to_string()],
+        },
+        SharePlatform::Windows => ShareCommand {
+            program: "explorer",
+            args: vec![format!("/select,\"{}\"", path)],
+        },
+        SharePlatform::Linux => ShareCommand {
+            program: "xdg-open",
+            args: vec![
+                file_path
+                    .parent()
+                    .map_or_else(|| path.to_string(), |dir| dir.to_string_lossy().to_string()),
+            ],
+        },
+    }
+}
+
+/// 启动平台命令，并在后台线程等待其退出
+///
+/// 这些命令拉起文件管理器后很快退出，等待只为回收子进程，避免在 Unix 上留下僵尸进程；
+/// 不检查退出码（explorer 成功选中文件时也返回 1）。
+///
+/// # 返回值
+/// 等待线程的句柄，调用方通常直接丢弃（线程照常运行）
+fn launch(command: &ShareCommand) -> Result<JoinHandle<std::io::Result<ExitStatus>>, String> {
+    let mut process = std::process::Command::new(command.program);
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        process.raw_arg(command.args.join(" "));
+    }
+    #[cfg(not(target_os = "windows"))]
+    process.args(&command.args);
+
+    let mut child = process
+        .spawn()
+        .map_err(|e| format!("启动 {} 失败: {}", command.program, e))?;
+    Ok(std::thread::spawn(move || child.wait()))
+}
+
+/// 目录中已有同名文件时在扩展名前追加 ` (2)`、` (3)`……
+fn unique_path(dir: &Path, file_name: &str) -> PathBuf {
+    let candidate = dir.join(file_name);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let path = Path::new(file_name);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (2..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|p| !p.exists())
+        .unwrap_or(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_command_per_platform() {
+        let file = Path::new("/tmp/ccr-exports/my session.md");
+        assert_eq!(
+            share_command(SharePlatform::MacOs, file),
+            ShareCommand { program: "open", args: vec!["-R".into(), "/tmp/ccr-exports/my session.md".into()] }
+        );
+        assert_eq!(
+            share_command(SharePlatform::Windows, file).args,
+            vec!["/select,\"/tmp/ccr-exports/my session.md\"".to_string()]
+        );
+        assert_eq!(
+            share_command(SharePlatform::Linux, file),
+            ShareCommand { program: "xdg-open", args: vec!["/tmp/ccr-exports".into()] }
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_launch_waits_for_child() {
+        let waiter = launch(&ShareCommand { program: "true", args: vec![] }).unwrap();
+        assert!(waiter.join().unwrap().unwrap().success());
+        assert!(launch(&ShareCommand { program: "ccr-missing-program", args: vec![] }).is_err());
+    }
+
+    #[test]
+    fn test_unique_path() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        assert_eq!(unique_path(dir, "a.md"), dir.join("a.md"));
+        std::fs::write(dir.join("a.md"), "").unwrap();
+        std::fs::write(dir.join("a (2).md"), "").unwrap();
+        assert_eq!(unique_path(dir, "a.md"), dir.join("a (3).md"));
+    }
+}
@@ -22,6 +22,7 @@ use std::path::{Path, PathBuf};
 use tokio::fs;
 
 use crate::models::skill::{SkillDetail, SkillFrontmatter, SkillInfo, SkillSource};
+use crate::utils::platform;
 
 /// Frontmatter 分隔符正则匹配的简化实现
 ///
@@ -31,7 +32,7 @@ use crate::models::skill::{SkillDetail, SkillFrontmatter, SkillInfo, SkillSource
 /// # 返回值
 /// - `Some((frontmatter_text, markdown_content))` - 成功解析出 frontmatter 和内容
 /// - `None` - 没有找到有效的 frontmatter
-fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
+pub(crate) fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
     // 必须以 "---" 开头（允许尾随空格）
     let content = content.trim_start_matches('\u{feff}'); // 去除 BOM
     if !content.starts_with("---") {
@@ -530,7 +531,7 @@ fn build_namespace(target_dir: &Path, base_dir: &Path) -> String {
 /// 对应源码 `envUtils.ts` 中的 `getClaudeConfigHomeDir()`。
 /// 返回 `~/.claude/` 路径。
 fn get_claude_config_home() -> PathBuf {
-    dirs::home_dir()
+    platform::home_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join(".claude")
 }
@@ -561,7 +562,7 @@ fn get_managed_file_path() -> PathBuf {
 /// # 参数
 /// - `project_path` - 项目根目录路径
 fn get_project_skills_dirs(project_path: &str) -> Vec<PathBuf> {
-    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    let home = platform::home_dir().unwrap_or_else(|| PathBuf::from("."));
     let mut dirs_list = Vec::new();
     let mut current = PathBuf::from(project_path);
 
@@ -591,7 +592,7 @@ fn get_project_skills_dirs(project_path: &str) -> Vec<PathBuf> {
 ///
 /// 与 `get_project_skills_dirs` 类似，但扫描 `.claude/commands/` 目录。
 fn get_project_commands_dirs(project_path: &str) -> Vec<PathBuf> {
-    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    let home = platform::home_dir().unwrap_or_else(|| PathBuf::from("."));
     let mut dirs_list = Vec::new();
     let mut current = PathBuf::from(project_path);
 
@@ -710,7 +711,7 @@ fn validate_skill_path(source_path: &str) -> Result<PathBuf, String> {
     let mut allowed_prefixes: Vec<PathBuf> = Vec::new();
 
     // 允许前缀 1：~/.claude/（用户级）
-    if let Some(home) = dirs::home_dir() {
+    if let Some(home) = platform::home_dir() {
         allowed_prefixes.push(home.join(".claude"));
     }
 
@@ -791,7 +792,7 @@ fn infer_source_from_path(path: &Path) -> SkillSource {
     // 统一路径分隔符为 /，便于跨平台匹配
     let normalized = path_str.replace('\\', "/");
 
-    let home = dirs::home_dir()
+    let home = platform::home_dir()
         .map(|h| h.to_string_lossy().replace('\\', "/"))
         .unwrap_or_default();
 
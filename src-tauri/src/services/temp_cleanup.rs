@@ -0,0 +1,182 @@
+//! # 临时资源清理服务
+//!
+//! CCR 运行期间会在系统 TEMP 目录下产生临时图片、导出中间文件等资源，
+//! 本模块按 `BackupConfig::temp_retention_days` 保留策略清理其中的超龄文件。
+//!
+//! ## 清理范围
+//! 仅清理 `TEMP_ARTIFACT_DIRS` 列出的目录；`ccr-backups`（临时备份）由备份策略单独管理，
+//! 不在此处清理，避免用户在下次启动前失去反悔的机会。
+//!
+//! ## 触发时机
+//! - 应用退出：`lib.rs` 在 `RunEvent::ExitRequested` 时调用 `cleanup_on_exit`，
+//!   清理在后台线程执行，最多等待 `EXIT_CLEANUP_TIMEOUT`，超时则直接退出；
+//!   同一线程中按 `audit_retention_days` 滚动清理审计日志
+//! - 手动：`cleanup_temp_artifacts` command 调用 `cleanup_expired`
+
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::Once;
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+
+use crate::services::{audit, file_guard};
+use crate::utils::platform;
+
+/// 需要按保留策略清理的 TEMP 子目录
+pub const TEMP_ARTIFACT_DIRS: &[&str] = &["ccr-images", "ccr-exports"];
+
+/// 退出时等待清理完成的最长时间
+const EXIT_CLEANUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 保证退出清理只执行一次（`ExitRequested` 可能被多次触发）
+static EXIT_CLEANUP: Once = Once::new();
+
+/// 临时资源清理结果
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupReport {
+    /// 删除的文件数
+    pub removed_files: usize,
+    /// 释放的空间（字节）
+    pub freed_bytes: u64,
+    /// 删除失败的文件数（被占用、权限不足等）
+    pub failed_files: usize,
+}
+
+/// 清理所有临时资源目录中修改时间早于 `max_age` 的文件
+///
+/// 同步执行（使用 `std::fs`），调用方应在后台线程或 `spawn_blocking` 中调用。
+/// 文件删除后变为空的子目录一并移除，目录本身保留。
+///
+/// # 参数
+/// - `max_age` - 文件最长保留时间
+///
+/// # 返回值
+/// 删除文件数、释放空间与失败数
+pub fn cleanup_expired(max_age: Duration) -> CleanupReport {
+    let mut report = CleanupReport::default();
+    let Some(cutoff) = SystemTime::now().checked_sub(max_age) else {
+        return report;
+    };
+
+    let temp_dir = platform::temp_dir();
+    for name in TEMP_ARTIFACT_DIRS {
+        let dir = temp_dir.join(name);
+        if dir.is_dir() {
+            cleanup_dir(&dir, cutoff, &mut report);
+        }
+    }
+
+    report
+}
+
+/// 应用退出时按配置的保留策略清理临时资源
+///
+/// 在后台线程中读取配置并执行清理，当前线程最多等待 `EXIT_CLEANUP_TIMEOUT`。
+/// 保留天数为 0 时不清理。随后清理超龄的审计记录。
+pub fn cleanup_on_exit() {
+    EXIT_CLEANUP.call_once(|| {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let config = tauri::async_runtime::block_on(file_guard::read_backup_config_internal());
+            if config.temp_retention_days > 0 {
+                let report = cleanup_expired(retention(config.temp_retention_days));
+                log::info!(
+                    "退出清理临时资源: 删除 {} 个文件，释放 {} 字节",
+                    report.removed_files,
+                    report.freed_bytes
+                );
+            }
+            match tauri::async_runtime::block_on(audit::cleanup_expired(config.audit_retention_days)) {
+                Ok(0) => {}
+                Ok(removed) => log::info!("退出清理审计日志: 移除 {} 条超龄记录", removed),
+                Err(e) => log::warn!("清理审计日志失败: {}", e),
+            }
+            let _ = tx.send(());
+        });
+
+        if rx.recv_timeout(EXIT_CLEANUP_TIMEOUT).is_err() {
+            log::warn!("退出清理临时资源超时，跳过剩余清理");
+        }
+    });
+}
+
+/// 将保留天数转换为时长
+pub fn retention(days: u32) -> Duration {
+    Duration::from_secs(u64::from(days) * 24 * 60 * 60)
+}
+
+/// 递归清理目录中早于 `cutoff` 的文件
+///
+/// # 返回值
+/// 清理后目录是否为空
+fn cleanup_dir(dir: &Path, cutoff: SystemTime, report: &mut CleanupReport) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+
+    let mut empty = true;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            empty = false;
+            continue;
+        };
+
+        if metadata.is_dir() {
+            if cleanup_dir(&path, cutoff, report) && std::fs::remove_dir(&path).is_ok() {
+                continue;
+            }
+            empty = false;
+            continue;
+        }
+
+        let expired = metadata.modified().map(|m| m < cutoff).unwrap_or(false);
+        if !expired {
+            empty = false;
+            continue;
+        }
+
+        match std::fs::remove_file(&path) {
+            Ok(()) => {
+                report.removed_files += 1;
+                report.freed_bytes += metadata.len();
+            }
+            Err(_) => {
+                report.failed_files += 1;
+                empty = false;
+            }
+        }
+    }
+
+    empty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cleanup_dir_removes_expired_files() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        let nested = root.join("nested");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join("a.png"), b"12345").unwrap();
+        std::fs::write(nested.join("b.md"), b"123").unwrap();
+
+        // 截止时间在过去：文件均未过期
+        let mut report = CleanupReport::default();
+        let past = SystemTime::now() - Duration::from_secs(3600);
+        assert!(!cleanup_dir(root, past, &mut report));
+        assert_eq!(report.removed_files, 0);
+
+        // 截止时间在未来：全部过期，空子目录一并移除
+        let future = SystemTime::now() + Duration::from_secs(3600);
+        assert!(cleanup_dir(root, future, &mut report));
+        assert_eq!(report.removed_files, 2);
+        assert_eq!(report.freed_bytes, 8);
+        assert!(!nested.exists());
+    }
+}
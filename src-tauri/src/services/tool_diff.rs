@@ -0,0 +1,400 @@
+//! # 工具结果与磁盘文件对比
+//!
+//! 会话中 Read / Write / Edit 的工具结果保存的是当时的文件内容。本模块从 tool_result 中
+//! 还原该快照，与磁盘上的当前内容做行级 diff（`similar` crate），回答「这个文件现在被改成什么样了」。
+//!
+//! ## 快照来源（按优先级）
+//! 1. `toolUseResult.file.content`：Read 的原始文本（不带行号），附带 `startLine` / `numLines` / `totalLines`
+//! 2. `toolUseResult.originalFile` + tool_use 的 `old_string` / `new_string`：Edit 后的完整内容
+//! 3. tool_use 的 `content`：Write 写入的完整内容
+//! 4. tool_result 文本：旧版本 Read 的 `     1→内容` 行号格式，去掉行号前缀
+//!
+//! Read 只读取了部分行时，只与磁盘文件的相同行范围比较。
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+use similar::{ChangeTag, TextDiff};
+
+use crate::models::display::{DisplayMessage, TransformedSession};
+
+/// 参与 diff 的文件大小上限（快照与磁盘文件分别检查）
+pub const MAX_DIFF_FILE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// 每个 hunk 前后保留的上下文行数
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// 判定二进制文件时检查的开头字节数
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+/// 对比结果状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiskDiffStatus {
+    /// 磁盘内容与快照一致
+    Identical,
+    /// 磁盘内容已变化，见 hunks
+    Changed,
+    /// 文件已不存在
+    Deleted,
+    /// 磁盘文件是二进制或不是 UTF-8 文本，无法做行级 diff
+    Binary,
+}
+
+/// diff 行类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffLineKind {
+    /// 上下文（两侧相同）
+    Context,
+    /// 快照之后新增的行
+    Added,
+    /// 快照中有、磁盘上已删除的行
+    Removed,
+}
+
+/// diff 中的一行
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    /// 行类型
+    pub kind: DiffLineKind,
+    /// 行内容（不含换行符）
+    pub text: String,
+}
+
+/// 一个 diff 片段（行号从 1 开始，已按快照起始行偏移）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    /// 快照侧起始行号
+    pub old_start: usize,
+    /// 快照侧行数
+    pub old_lines: usize,
+    /// 磁盘侧起始行号
+    pub new_start: usize,
+    /// 磁盘侧行数
+    pub new_lines: usize,
+    /// 片段内的行
+    pub lines: Vec<DiffLine>,
+}
+
+/// 行级统计
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffStats {
+    /// 新增行数
+    pub added: usize,
+    /// 删除行数
+    pub removed: usize,
+    /// 未变化行数
+    pub unchanged: usize,
+}
+
+/// 工具结果与磁盘文件的对比结果
+///
+/// 由 `diff_tool_result_with_disk` command 返回，对应前端 TypeScript 接口 `ToolResultDiff`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolResultDiff {
+    /// 文件的绝对路径
+    pub file_path: String,
+    /// 工具名称
+    pub tool_name: String,
+    /// 对比状态
+    pub status: DiskDiffStatus,
+    /// 快照是否只覆盖文件的一部分行（Read 指定了 offset / limit）
+    pub partial: bool,
+    /// 快照对应的起始行号（从 1 开始）
+    pub start_line: usize,
+    /// diff 片段（status 为 Changed 时非空）
+    pub hunks: Vec<DiffHunk>,
+    /// 行级统计
+    pub stats: DiffStats,
+}
+
+/// 从 tool_result 还原的文件快照
+#[derive(Debug, PartialEq)]
+struct FileSnapshot {
+    file_path: PathBuf,
+    content: String,
+    /// 快照起始行号（从 1 开始）
+    start_line: usize,
+    /// 快照只覆盖部分行时为 Some(行数)
+    partial_lines: Option<usize>,
+}
+
+/// 对比指定 tool_result 消息中的文件快照与磁盘当前内容
+///
+/// # 参数
+/// - `session` - 已转换的会话
+/// - `display_id` - tool_result 消息的 display_id
+///
+/// # 错误
+/// 找不到消息、消息不含文件快照、快照或磁盘文件超过 `MAX_DIFF_FILE_BYTES`、读取失败时返回错误
+pub async fn diff_with_disk(session: &TransformedSession, display_id: &str) -> Result<ToolResultDiff, String> {
+    let dm = session
+        .display_messages
+        .iter()
+        .find(|dm| dm.display_id == display_id)
+        .ok_or_else(|| format!("未找到消息: {}", display_id))?;
+    let (tool_name, input) = dm
+        .content
+        .iter()
+        .find_map(|block| block.get("tool_use_id").and_then(|v| v.as_str()))
+        .and_then(|id| session.tool_use_map.get(id))
+        .map(|info| (info.name.clone(), info.input.clone()))
+        .ok_or_else(|| "该消息不是可关联到工具调用的 tool_result".to_string())?;
+    let snapshot = extract_snapshot(dm, &input).ok_or_else(|| "该工具结果不包含文件内容快照".to_string())?;
+    if snapshot.content.len() as u64 > MAX_DIFF_FILE_BYTES {
+        return Err("文件快照超过 5MB，拒绝对比".to_string());
+    }
+
+    let mut result = ToolResultDiff {
+        file_path: snapshot.file_path.to_string_lossy().to_string(),
+        tool_name,
+        status: DiskDiffStatus::Deleted,
+        partial: snapshot.partial_lines.is_some(),
+        start_line: snapshot.start_line,
+        hunks: Vec::new(),
+        stats: DiffStats::default(),
+    };
+
+    let metadata = match tokio::fs::metadata(&snapshot.file_path).await {
+        Ok(metadata) if metadata.is_file() => metadata,
+        _ => return Ok(result),
+    };
+    if metadata.len() > MAX_DIFF_FILE_BYTES {
+        return Err(format!("文件超过 5MB，拒绝对比: {}", result.file_path));
+    }
+    let bytes = tokio::fs::read(&snapshot.file_path)
+        .await
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    let is_binary = bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0);
+    let Some(disk) = String::from_utf8(bytes).ok().filter(|_| !is_binary) else {
+        result.status = DiskDiffStatus::Binary;
+        return Ok(result);
+    };
+
+    let disk = match snapshot.partial_lines {
+        Some(count) => slice_lines(&disk, snapshot.start_line, count),
+        None => disk.as_str(),
+    };
+    let (hunks, stats) = line_diff(&snapshot.content, disk, snapshot.start_line);
+    result.status = if hunks.is_empty() {
+        DiskDiffStatus::Identical
+    } else {
+        DiskDiffStatus::Changed
+    };
+    result.hunks = hunks;
+    result.stats = stats;
+    Ok(result)
+}
+
+/// 从 tool_result 消息与对应的 tool_use 输入中还原文件快照
+fn extract_snapshot(dm: &DisplayMessage, input: &Value) -> Option<FileSnapshot> {
+    let result = dm.tool_use_result.as_ref();
+    let str_field = |value: Option<&Value>, key: &str| {
+        value.and_then(|v| v.get(key)).and_then(|v| v.as_str()).map(str::to_string)
+    };
+    let file_path = str_field(Some(input), "file_path")
+        .or_else(|| str_field(result.and_then(|r| r.get("file")), "filePath"))
+        .or_else(|| str_field(result, "filePath"))?;
+    let file_path = resolve_path(&file_path, dm.cwd.as_deref());
+
+    // 1. Read：toolUseResult.file
+    if let Some(file) = result.and_then(|r| r.get("file"))
+        && let Some(content) = file.get("content").and_then(|v| v.as_str())
+    {
+        let number = |key: &str| file.get(key).and_then(|v| v.as_u64()).map(|n| n as usize);
+        let start_line = number("startLine").unwrap_or(1).max(1);
+        let num_lines = number("numLines");
+        let partial = start_line > 1 || num_lines.zip(number("totalLines")).is_some_and(|(n, total)| n < total);
+        return Some(FileSnapshot {
+            file_path,
+            content: content.to_string(),
+            start_line,
+            partial_lines: partial.then(|| num_lines.unwrap_or_else(|| content.lines().count())),
+        });
+    }
+
+    // 2. Edit：编辑前的完整内容 + 替换
+    if let Some(original) = str_field(result, "originalFile")
+        && let (Some(old), Some(new)) = (str_field(Some(input), "old_string"), str_field(Some(input), "new_string"))
+    {
+        let replace_all = input.get("replace_all").and_then(|v| v.as_bool()).unwrap_or(false);
+        let content = if replace_all {
+            original.replace(&old, &new)
+        } else {
+            original.replacen(&old, &new, 1)
+        };
+        return Some(whole_file(file_path, content));
+    }
+
+    // 3. Write：写入的完整内容
+    if let Some(content) = str_field(Some(input), "content") {
+        return Some(whole_file(file_path, content));
+    }
+
+    // 4. 旧版本 Read：带行号前缀的 tool_result 文本
+    let text = dm.content.iter().find_map(|block| match block.get("content") {
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(Value::Array(items)) => items
+            .iter()
+            .find_map(|item| item.get("text").and_then(|v| v.as_str()).map(str::to_string)),
+        _ => None,
+    })?;
+    let (start_line, content) = strip_line_numbers(&text)?;
+    let line_count = content.lines().count();
+    Some(FileSnapshot {
+        file_path,
+        content,
+        start_line,
+        // 无法得知文件总行数，按部分快照处理，只比较相同行范围
+        partial_lines: Some(line_count),
+    })
+}
+
+/// 整个文件的快照
+fn whole_file(file_path: PathBuf, content: String) -> FileSnapshot {
+    FileSnapshot {
+        file_path,
+        content,
+        start_line: 1,
+        partial_lines: None,
+    }
+}
+
+/// 相对路径按消息的 cwd 解析
+fn resolve_path(file_path: &str, cwd: Option<&str>) -> PathBuf {
+    let path = Path::new(file_path);
+    match cwd {
+        Some(cwd) if path.is_relative() => Path::new(cwd).join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// 去掉 Read 输出的行号前缀（`     1→内容` 或 `     1\t内容`）
+///
+/// # 返回值
+/// `(起始行号, 去掉前缀后的内容)`；任一行不符合格式（不是 Read 输出）时返回 None
+fn strip_line_numbers(text: &str) -> Option<(usize, String)> {
+    let mut start_line = None;
+    let mut content = String::with_capacity(text.len());
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        let digits = trimmed.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return None;
+        }
+        let rest = &trimmed[digits..];
+        let rest = rest.strip_prefix('→').or_else(|| rest.strip_prefix('\t'))?;
+        start_line.get_or_insert_with(|| trimmed[..digits].parse().unwrap_or(1));
+        content.push_str(rest);
+        content.push('\n');
+    }
+    Some((start_line?, content))
+}
+
+/// 取文本中从 `start_line`（从 1 开始）起的 `count` 行（保留换行符）
+fn slice_lines(text: &str, start_line: usize, count: usize) -> &str {
+    let mut offsets = text.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some(start)
+    });
+    let begin = offsets.nth(start_line.saturating_sub(1)).unwrap_or(text.len());
+    let end = if count == 0 {
+        begin
+    } else {
+        offsets.nth(count - 1).unwrap_or(text.len())
+    };
+    &text[begin..end]
+}
+
+/// 行级 diff
+///
+/// # 参数
+/// - `old` / `new` - 快照内容与磁盘内容
+/// - `start_line` - 两侧内容在文件中的起始行号，用于换算 hunk 行号
+fn line_diff(old: &str, new: &str, start_line: usize) -> (Vec<DiffHunk>, DiffStats) {
+    let diff = TextDiff::from_lines(old, new);
+    let mut stats = DiffStats::default();
+    for op in diff.ops() {
+        for change in diff.iter_changes(op) {
+            match change.tag() {
+                ChangeTag::Equal => stats.unchanged += 1,
+                ChangeTag::Insert => stats.added += 1,
+                ChangeTag::Delete => stats.removed += 1,
+            }
+        }
+    }
+
+    let hunks = diff
+        .grouped_ops(DIFF_CONTEXT_LINES)
+        .into_iter()
+        .filter_map(|group| {
+            let first = group.first()?;
+            let last = group.last()?;
+            let lines = group
+                .iter()
+                .flat_map(|op| diff.iter_changes(op))
+                .map(|change| DiffLine {
+                    kind: match change.tag() {
+                        ChangeTag::Equal => DiffLineKind::Context,
+                        ChangeTag::Insert => DiffLineKind::Added,
+                        ChangeTag::Delete => DiffLineKind::Removed,
+                    },
+                    text: change.value().trim_end_matches(['\r', '\n']).to_string(),
+                })
+                .collect();
+            Some(DiffHunk {
+                old_start: first.old_range().start + start_line,
+                old_lines: last.old_range().end - first.old_range().start,
+                new_start: first.new_range().start + start_line,
+                new_lines: last.new_range().end - first.new_range().start,
+                lines,
+            })
+        })
+        .collect();
+    (hunks, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_line_numbers_and_slice() {
+        let text = "    10→fn main() {\n    11\t    println!(\"a→b\");\n    12→}";
+        assert_eq!(
+            strip_line_numbers(text),
+            Some((10, "fn main() {\n    println!(\"a→b\");\n}\n".to_string()))
+        );
+        assert_eq!(strip_line_numbers("plain output\n1→x"), None);
+
+        let file = "a\nb\nc\nd\n";
+        assert_eq!(slice_lines(file, 2, 2), "b\nc\n");
+        assert_eq!(slice_lines(file, 4, 5), "d\n");
+        assert_eq!(slice_lines(file, 9, 1), "");
+    }
+
+    #[test]
+    fn test_line_diff_hunks_and_stats() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n10\n11\n";
+        let (hunks, stats) = line_diff(old, new, 1);
+        assert_eq!((stats.added, stats.removed, stats.unchanged), (2, 1, 9));
+        assert_eq!(hunks.len(), 1);
+        let hunk = &hunks[0];
+        assert_eq!((hunk.old_start, hunk.new_start), (2, 2));
+        assert_eq!(hunk.lines[3].kind, DiffLineKind::Removed);
+        assert_eq!(hunk.lines[3].text, "5");
+        assert_eq!(hunk.lines.last().map(|l| l.kind), Some(DiffLineKind::Added));
+
+        let (hunks, stats) = line_diff("same\n", "same\n", 40);
+        assert!(hunks.is_empty());
+        assert_eq!(stats.unchanged, 1);
+    }
+}
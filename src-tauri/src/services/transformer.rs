@@ -10,6 +10,17 @@
 //!
 //! 消息保持原始时间顺序（旧→新），前端通过渐进式渲染实现视口优先加载。
 //!
+//! ## 分块产出
+//! `transform_session_chunked` 按 `chunk_size` 条原始消息分块执行并行 map 与顺序 reduce，
+//! 每累计 `chunk_size` 条 DisplayMessage 回调一次，供流式加载边转换边推送给前端。
+//! 分块不改变 reduce 顺序，最终结果与 `transform_session` 完全一致。
+//!
+//! ## 超长文本块
+//! 单个 text 块超过阈值（`limits.json` 的 `oversizedTextKb`，默认 256 KB）时，DisplayMessage 中
+//! 以占位块 `{ type: "oversized_text", preview, original_length, block_index }` 代替，
+//! 完整内容由 `read_full_block` 按需从文件读取；该块只有前 `OVERSIZED_SEARCH_PREFIX_BYTES`
+//! 字节进入搜索文本。仅影响展示层，原始消息与写回路径不受影响。
+//!
 //! ## 设计原则
 //! - 零注入：不修改原始 `serde_json::Value`
 //! - 完全分离：`DisplayMessage` 是独立 struct
@@ -18,14 +29,32 @@
 //!   - `original_texts`：原始大小写版本，用于大小写敏感搜索和正则搜索
 
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use rayon::prelude::*;
 use serde_json::Value;
 
 use crate::models::display::{
-    DisplayMessage, TokenStats, ToolUseInfo, TransformedSession,
+    CwdChange, DisplayMessage, StopReasonStat, TokenStats, ToolInvocation, ToolStat, ToolUseInfo,
+    TransformedSession,
 };
 use crate::services::classifier::{self, Classification};
+use crate::services::limits::DEFAULT_OVERSIZED_TEXT_KB;
+use crate::utils::time::parse_iso8601_millis;
+
+/// 超长文本块阈值（字节），启动时由 `set_oversized_text_threshold` 按 `limits.json` 设置
+static OVERSIZED_TEXT_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_OVERSIZED_TEXT_KB * 1024);
+
+/// 超长文本块进入搜索文本的前缀长度（字节）
+const OVERSIZED_SEARCH_PREFIX_BYTES: usize = 64 * 1024;
+
+/// 超长文本块占位中预览的字符数
+const OVERSIZED_PREVIEW_CHARS: usize = 500;
+
+/// 设置超长文本块阈值（字节，0 视为 1）
+pub fn set_oversized_text_threshold(bytes: usize) {
+    OVERSIZED_TEXT_BYTES.store(bytes.max(1), Ordering::Relaxed);
+}
 
 /// 单条消息的并行处理中间结果
 ///
@@ -41,6 +70,19 @@ struct PerMessageResult {
     usage: Option<Value>,
 }
 
+/// 分块转换时每次回调产出的一批结果
+pub struct TransformChunk<'a> {
+    /// 分块序号（从 0 开始，按产出顺序递增）
+    pub seq: u64,
+    /// 本批新增的 DisplayMessage（按时间顺序）
+    pub display_messages: &'a [DisplayMessage],
+    /// 自上一批以来新增的 tool_use 映射（tool_use 总在对应 tool_result 之前或同批产出）
+    pub tool_uses: HashMap<String, ToolUseInfo>,
+}
+
+/// 分块回调：`(每批 DisplayMessage 条数, 回调函数)`
+type ChunkSink<'f> = (usize, &'f mut dyn FnMut(TransformChunk<'_>));
+
 /// 转换入口：将原始消息列表转换为前端可渲染的 TransformedSession
 ///
 /// 返回 `(TransformedSession, Vec<String>, Vec<String>)` 三元组：
@@ -58,6 +100,35 @@ struct PerMessageResult {
 /// `(TransformedSession, Vec<String>, Vec<String>)` 三元组：
 /// `(session, lowercase_texts, original_texts)`
 pub fn transform_session(messages: &[Value]) -> (TransformedSession, Vec<String>, Vec<String>) {
+    transform_inner(messages, None)
+}
+
+/// 分块转换：与 `transform_session` 结果一致，但转换过程中每累计 `chunk_size` 条
+/// DisplayMessage 就调用一次 `on_chunk`，最后不足一批的剩余部分也会回调一次
+///
+/// 主链计算需要完整的消息列表，因此在第一批产出前完成；
+/// 其后每 `chunk_size` 条原始消息执行一轮 rayon 并行 map + 顺序 reduce。
+///
+/// # 参数
+/// - `messages` - 原始消息列表
+/// - `chunk_size` - 每批 DisplayMessage 条数（0 视为 1）
+/// - `on_chunk` - 每批产出时的回调
+///
+/// # 返回值
+/// 与 `transform_session` 相同的 `(session, lowercase_texts, original_texts)` 三元组
+pub fn transform_session_chunked(
+    messages: &[Value],
+    chunk_size: usize,
+    mut on_chunk: impl FnMut(TransformChunk<'_>),
+) -> (TransformedSession, Vec<String>, Vec<String>) {
+    transform_inner(messages, Some((chunk_size.max(1), &mut on_chunk)))
+}
+
+/// 转换实现：`sink` 为 None 时整体执行一轮 map + reduce，不产生任何分块回调
+fn transform_inner(
+    messages: &[Value],
+    mut sink: Option<ChunkSink<'_>>,
+) -> (TransformedSession, Vec<String>, Vec<String>) {
     // ---- 阶段 0：计算主链 UUID 集合 ----
     // Claude Code 的消息通过 parentUuid 构成对话树。当用户重试或分支时，
     // 会产生不在当前对话路径上的"遗弃"消息。
@@ -85,38 +156,99 @@ pub fn transform_session(messages: &[Value]) -> (TransformedSession, Vec<String>
         chain
     };
 
-    // ---- 阶段 1：并行 map，每条消息独立处理（分类 + tool_use 提取 + usage 提取）----
-    let per_msg: Vec<PerMessageResult> = messages
-        .par_iter()
-        .map(|msg| PerMessageResult {
-            classification: classifier::classify(msg),
-            tool_uses: extract_tool_uses(msg),
-            usage: extract_usage(msg),
-        })
-        .collect();
-
-    // ---- 阶段 2：顺序 reduce，保持消息顺序 ----
     let mut tool_use_map = HashMap::new();
     let mut token_stats = TokenStats::default();
     let mut display_messages = Vec::with_capacity(messages.len());
 
-    for (result, msg) in per_msg.into_iter().zip(messages.iter()) {
-        // 合并 tool_use_map
-        for (id, info) in result.tool_uses {
-            tool_use_map.insert(id, info);
+    // 分块状态：已回调的 DisplayMessage 条数、下一批序号、尚未随批次推送的 tool_use
+    let mut flushed = 0usize;
+    let mut seq = 0u64;
+    let mut pending_tool_uses: HashMap<String, ToolUseInfo> = HashMap::new();
+    // 被替换为占位的超长文本块：DisplayMessage 下标 → (content 位置, 搜索文本前缀)
+    let mut oversized_prefixes: HashMap<usize, Vec<(usize, String)>> = HashMap::new();
+
+    // 非分块模式整体作为一批，行为与分块前完全相同
+    let map_chunk_size = sink.as_ref().map_or(messages.len().max(1), |(size, _)| *size);
+    for chunk in messages.chunks(map_chunk_size) {
+        // ---- 阶段 1：并行 map，每条消息独立处理（分类 + tool_use 提取 + usage 提取）----
+        let per_msg: Vec<PerMessageResult> = chunk
+            .par_iter()
+            .map(|msg| PerMessageResult {
+                classification: classifier::classify(msg),
+                tool_uses: extract_tool_uses(msg),
+                usage: extract_usage(msg),
+            })
+            .collect();
+
+        // ---- 阶段 2：顺序 reduce，保持消息顺序 ----
+        for (result, msg) in per_msg.into_iter().zip(chunk.iter()) {
+            // 合并 tool_use_map
+            for (id, info) in result.tool_uses {
+                if sink.is_some() {
+                    pending_tool_uses.insert(id.clone(), info.clone());
+                }
+                tool_use_map.insert(id, info);
+            }
+            // 累加 token_stats
+            token_stats.accumulate(&result.usage);
+            // 构建 DisplayMessage（User 消息拆分 tool_result），超长文本块替换为占位
+            let built_from = display_messages.len();
+            build_display_messages(&mut display_messages, result.classification, msg, &main_chain_uuids);
+            for (index, dm) in display_messages.iter_mut().enumerate().skip(built_from) {
+                let prefixes = collapse_oversized_text(dm);
+                if !prefixes.is_empty() {
+                    oversized_prefixes.insert(index, prefixes);
+                }
+            }
+        }
+
+        // 累计满一批即回调（单条原始消息可能拆出多条 DisplayMessage，故用 while）
+        if let Some((size, on_chunk)) = sink.as_mut() {
+            while display_messages.len() - flushed >= *size {
+                on_chunk(TransformChunk {
+                    seq,
+                    display_messages: &display_messages[flushed..flushed + *size],
+                    tool_uses: std::mem::take(&mut pending_tool_uses),
+                });
+                flushed += *size;
+                seq += 1;
+            }
         }
-        // 累加 token_stats
-        token_stats.accumulate(&result.usage);
-        // 构建 DisplayMessage（User 消息拆分 tool_result）
-        build_display_messages(&mut display_messages, result.classification, msg, &main_chain_uuids);
     }
 
+    // 末尾不足一批的剩余部分
+    if let Some((_, on_chunk)) = sink.as_mut().filter(|_| flushed < display_messages.len()) {
+        on_chunk(TransformChunk {
+            seq,
+            display_messages: &display_messages[flushed..],
+            tool_uses: std::mem::take(&mut pending_tool_uses),
+        });
+    }
+
+    // 工具调用与失败计数
+    token_stats.tool_calls = tool_use_map.len() as u64;
+    token_stats.failed_tool_calls = display_messages.iter().filter(|dm| dm.is_error).count() as u64;
+
+    // ---- 阶段 2.5：检测相邻消息的工作目录切换 ----
+    let (cwd_changes, cwds) = collect_cwd_changes(&display_messages);
+
     // ---- 阶段 3：并行提取原始大小写搜索文本 ----
     // 先提取 original_texts（保留原始大小写），再从 original_texts 直接小写化生成
     // search_texts，避免两次遍历 content 块，提高性能
+    // 含超长文本占位的消息：占位块换回其前缀文本后再提取
     let original_texts: Vec<String> = display_messages
         .par_iter()
-        .map(|dm| extract_search_text_original(&dm.content))
+        .enumerate()
+        .map(|(index, dm)| match oversized_prefixes.get(&index) {
+            Some(prefixes) => {
+                let mut content = dm.content.clone();
+                for (pos, prefix) in prefixes {
+                    content[*pos] = serde_json::json!({ "type": "text", "text": prefix });
+                }
+                extract_search_text_original(&content)
+            }
+            None => extract_search_text_original(&dm.content),
+        })
         .collect();
 
     // ---- 阶段 4：从 original_texts 生成小写化版本 ----
@@ -131,12 +263,52 @@ pub fn transform_session(messages: &[Value]) -> (TransformedSession, Vec<String>
             display_messages,
             tool_use_map,
             token_stats,
+            cwd_changes,
+            cwds,
+            reencoded_from: None,
+            total_count: None,
         },
         search_texts,
         original_texts,
     )
 }
 
+/// 按消息顺序检测工作目录切换点，并收集去重后的工作目录列表
+///
+/// 仅比较带有 `cwd` 的消息：没有 cwd 字段的消息（如部分元数据条目）不会打断比较，
+/// 切换点记录在切换后第一条消息上。
+///
+/// # 参数
+/// - `display_messages` - 按时间顺序排列的 DisplayMessage 列表
+///
+/// # 返回值
+/// `(cwd_changes, cwds)`：切换点列表 + 按首次出现顺序去重的工作目录列表
+fn collect_cwd_changes(display_messages: &[DisplayMessage]) -> (Vec<CwdChange>, Vec<String>) {
+    let mut changes = Vec::new();
+    let mut cwds: Vec<String> = Vec::new();
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut last_cwd: Option<&str> = None;
+
+    for dm in display_messages {
+        let Some(cwd) = dm.cwd.as_deref() else {
+            continue;
+        };
+        if seen.insert(cwd) {
+            cwds.push(cwd.to_string());
+        }
+        if let Some(prev) = last_cwd.filter(|prev| *prev != cwd) {
+            changes.push(CwdChange {
+                display_id: dm.display_id.clone(),
+                from: prev.to_string(),
+                to: cwd.to_string(),
+            });
+        }
+        last_cwd = Some(cwd);
+    }
+
+    (changes, cwds)
+}
+
 /// 从 assistant 消息的 content 数组中提取所有 tool_use 和 server_tool_use 块的信息
 ///
 /// 遍历 `message.content` 数组，对每个 `type === "tool_use"` 或 `type === "server_tool_use"` 的块，
@@ -239,7 +411,7 @@ fn build_display_messages(
         .and_then(|v| v.as_str())
         .map(|s| s.to_string());
 
-    // 提取 assistant 专属字段（model, usage, toolUseResult, todos）
+    // 提取 assistant 专属字段（model, usage, stop_reason, id, toolUseResult, todos）
     let model = msg
         .get("message")
         .and_then(|m| m.get("model"))
@@ -249,6 +421,16 @@ fn build_display_messages(
         .get("message")
         .and_then(|m| m.get("usage"))
         .cloned();
+    let stop_reason = msg
+        .get("message")
+        .and_then(|m| m.get("stop_reason"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let message_id = msg
+        .get("message")
+        .and_then(|m| m.get("id"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
     let tool_use_result = msg.get("toolUseResult").cloned();
     let todos = msg
         .get("todos")
@@ -278,10 +460,13 @@ fn build_display_messages(
                 usage,
                 tool_use_result,
                 todos,
+                stop_reason,
+                message_id,
                 system_label: None,
                 plan_source_path: None,
                 cwd,
                 is_abandoned,
+                is_error: false,
             });
         }
 
@@ -301,10 +486,13 @@ fn build_display_messages(
                 usage: None,
                 tool_use_result: None,
                 todos: None,
+                stop_reason: None,
+                message_id: None,
                 system_label: Some("附件".into()),
                 plan_source_path: None,
                 cwd,
                 is_abandoned,
+                is_error: false,
             });
         }
 
@@ -324,10 +512,13 @@ fn build_display_messages(
                 usage: None,
                 tool_use_result: None,
                 todos: None,
+                stop_reason: None,
+                message_id: None,
                 system_label: Some("系统".into()),
                 plan_source_path: None,
                 cwd,
                 is_abandoned,
+                is_error: false,
             });
         }
 
@@ -347,10 +538,13 @@ fn build_display_messages(
                 usage: None,
                 tool_use_result: None,
                 todos: None,
+                stop_reason: None,
+                message_id: None,
                 system_label: None,
                 plan_source_path: None,
                 cwd,
                 is_abandoned,
+                is_error: false,
             });
         }
 
@@ -369,10 +563,13 @@ fn build_display_messages(
                 usage: None,
                 tool_use_result: None,
                 todos: None,
+                stop_reason: None,
+                message_id: None,
                 system_label: None,
                 plan_source_path: None,
                 cwd,
                 is_abandoned,
+                is_error: false,
             });
         }
 
@@ -394,10 +591,13 @@ fn build_display_messages(
                 usage: None,
                 tool_use_result: None,
                 todos: None,
+                stop_reason: None,
+                message_id: None,
                 system_label: Some(label),
                 plan_source_path,
                 cwd,
                 is_abandoned,
+                is_error: false,
             });
         }
 
@@ -433,6 +633,46 @@ fn content_to_blocks(content: Option<&Value>) -> (Vec<Value>, Vec<usize>) {
     }
 }
 
+/// 把 DisplayMessage 中超过阈值的 text 块替换为 `oversized_text` 占位块
+///
+/// 占位块字段：`preview`（前 `OVERSIZED_PREVIEW_CHARS` 个字符）、`original_length`（原文字节数）、
+/// `block_index`（在原始 `message.content` 中的索引，供 `read_full_block` 定位）。
+///
+/// # 返回值
+/// 被替换的块的 `(content 位置, 前 OVERSIZED_SEARCH_PREFIX_BYTES 字节的文本)`，供搜索文本使用
+fn collapse_oversized_text(dm: &mut DisplayMessage) -> Vec<(usize, String)> {
+    let threshold = OVERSIZED_TEXT_BYTES.load(Ordering::Relaxed);
+    let mut prefixes = Vec::new();
+    for (pos, block) in dm.content.iter_mut().enumerate() {
+        if block.get("type").and_then(|v| v.as_str()) != Some("text") {
+            continue;
+        }
+        let Some(text) = block.get("text").and_then(|v| v.as_str()).filter(|t| t.len() > threshold) else {
+            continue;
+        };
+        prefixes.push((pos, utf8_prefix(text, OVERSIZED_SEARCH_PREFIX_BYTES).to_string()));
+        *block = serde_json::json!({
+            "type": "oversized_text",
+            "preview": text.chars().take(OVERSIZED_PREVIEW_CHARS).collect::<String>(),
+            "original_length": text.len(),
+            "block_index": dm.block_index_map.get(pos).copied().unwrap_or(pos),
+        });
+    }
+    prefixes
+}
+
+/// 按 UTF-8 字符边界截取不超过 `max_bytes` 字节的前缀
+fn utf8_prefix(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+    let mut end = max_bytes;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
 /// 从 content 中提取纯文本（与 classifier 中的 extract_text 功能相同，但返回 String）
 ///
 /// # 参数
@@ -500,10 +740,13 @@ fn build_user_display_messages(
                 usage: None,
                 tool_use_result: None,
                 todos: None,
+                stop_reason: None,
+                message_id: None,
                 system_label: None,
                 plan_source_path: None,
                 cwd,
                 is_abandoned,
+                is_error: false,
             });
         }
 
@@ -537,15 +780,19 @@ fn build_user_display_messages(
                     usage: None,
                     tool_use_result: None,
                     todos: None,
+                    stop_reason: None,
+                    message_id: None,
                     system_label: None,
                     plan_source_path: None,
                     cwd: cwd.clone(),
                     is_abandoned,
+                    is_error: false,
                 });
             }
 
             // 生成工具结果消息（每个 tool_result 块一条独立消息）
             for (seq_idx, (block, original_index)) in tool_result_blocks.into_iter().enumerate() {
+                let is_error = block.get("is_error").and_then(|v| v.as_bool()) == Some(true);
                 out.push(DisplayMessage {
                     source_uuid: uuid.to_string(),
                     display_id: format!("{}-tool-{}", uuid, seq_idx),
@@ -558,10 +805,13 @@ fn build_user_display_messages(
                     usage: None,
                     tool_use_result: None,
                     todos: None,
+                    stop_reason: None,
+                    message_id: None,
                     system_label: None,
                     plan_source_path: None,
                     cwd: cwd.clone(),
                     is_abandoned,
+                    is_error,
                 });
             }
         }
@@ -636,3 +886,470 @@ fn extract_search_text_original(content: &[Value]) -> String {
     // 返回原始大小写文本（不做 to_lowercase()）
     buf
 }
+
+/// 元数据搜索字段
+///
+/// 查询以 `tool:` / `model:` / `cwd:` 开头时，只在对应字段上匹配（见 `AppCache::search_in_cache`）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchField {
+    /// 工具名（tool_use 块的 name；tool_result 消息取对应调用的工具名）
+    Tool,
+    /// 模型标识符（仅 assistant 消息）
+    Model,
+    /// 消息发送时的工作目录
+    Cwd,
+}
+
+impl SearchField {
+    /// 所有字段（用于查询前缀解析）
+    const ALL: [SearchField; 3] = [SearchField::Tool, SearchField::Model, SearchField::Cwd];
+
+    /// 元数据文本与查询语法中使用的前缀
+    pub fn prefix(self) -> &'static str {
+        match self {
+            SearchField::Tool => "tool:",
+            SearchField::Model => "model:",
+            SearchField::Cwd => "cwd:",
+        }
+    }
+
+    /// 解析字段限定查询，如 `tool:bash` → `(Tool, "bash")`
+    ///
+    /// 前缀不区分大小写，值两端空白被去除。
+    ///
+    /// # 返回值
+    /// 查询不以已知前缀开头时返回 None（按普通查询处理）
+    pub fn parse_query(query: &str) -> Option<(SearchField, &str)> {
+        let query = query.trim_start();
+        Self::ALL.into_iter().find_map(|field| {
+            let prefix = field.prefix();
+            let head = query.get(..prefix.len())?;
+            head.eq_ignore_ascii_case(prefix)
+                .then(|| (field, query[prefix.len()..].trim()))
+        })
+    }
+}
+
+/// 提取 DisplayMessage 的元数据搜索文本（原始大小写）
+///
+/// 每行一个带前缀的字段：`tool:<工具名>`、`model:<模型>`、`cwd:<路径>`。
+/// 与 `extract_search_text_original` 产出的正文分开缓存，普通查询不会匹配到元数据。
+///
+/// # 参数
+/// - `dm` - 显示消息
+/// - `tool_use_map` - tool_use_id → ToolUseInfo 映射，用于查询 tool_result 对应的工具名
+pub fn extract_search_metadata(
+    dm: &DisplayMessage,
+    tool_use_map: &HashMap<String, ToolUseInfo>,
+) -> String {
+    let mut buf = String::new();
+    let mut push = |field: SearchField, value: &str| {
+        buf.push_str(field.prefix());
+        buf.push_str(value);
+        buf.push('\n');
+    };
+
+    for block in &dm.content {
+        let block_type = block.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        let name = match block_type {
+            "tool_use" | "server_tool_use" => block.get("name").and_then(|v| v.as_str()),
+            "tool_result" => block
+                .get("tool_use_id")
+                .and_then(|v| v.as_str())
+                .and_then(|id| tool_use_map.get(id))
+                .map(|info| info.name.as_str()),
+            _ => None,
+        };
+        if let Some(name) = name {
+            push(SearchField::Tool, name);
+        }
+    }
+    if let Some(model) = &dm.model {
+        push(SearchField::Model, model);
+    }
+    if let Some(cwd) = &dm.cwd {
+        push(SearchField::Cwd, cwd);
+    }
+
+    buf
+}
+
+/// 在已转换的会话中定位指定 tool_use_id 的调用与结果配对
+///
+/// 先通过 `tool_use_map` 确认调用是否存在，再在 DisplayMessage 中查找：
+/// - tool_use 侧：`type` 为 `tool_use` / `server_tool_use` 且 `id` 匹配的内容块
+/// - tool_result 侧：`tool_use_id` 匹配的 `tool_result` / `*_tool_result` 内容块
+///   （本地工具结果位于拆分后的 tool_result 消息，服务端工具结果位于 assistant 消息）
+///
+/// # 参数
+/// - `session` - 已转换的会话
+/// - `tool_use_id` - 工具调用 ID
+///
+/// # 返回值
+/// 配对详情；两侧均找不到时返回 None
+pub fn find_tool_invocation(session: &TransformedSession, tool_use_id: &str) -> Option<ToolInvocation> {
+    let mut tool_use: Option<(&DisplayMessage, &Value)> = None;
+    let mut tool_result: Option<(&DisplayMessage, &Value)> = None;
+
+    let block_type = |block: &Value| block.get("type").and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let has_tool_use = session.tool_use_map.contains_key(tool_use_id);
+
+    for dm in &session.display_messages {
+        for block in &dm.content {
+            let t = block_type(block);
+            if has_tool_use
+                && tool_use.is_none()
+                && (t == "tool_use" || t == "server_tool_use")
+                && block.get("id").and_then(|v| v.as_str()) == Some(tool_use_id)
+            {
+                tool_use = Some((dm, block));
+            } else if tool_result.is_none()
+                && t.ends_with("tool_result")
+                && block.get("tool_use_id").and_then(|v| v.as_str()) == Some(tool_use_id)
+            {
+                tool_result = Some((dm, block));
+            }
+        }
+        if tool_use.is_some() && tool_result.is_some() {
+            break;
+        }
+    }
+
+    if tool_use.is_none() && tool_result.is_none() {
+        return None;
+    }
+
+    let elapsed_ms = match (tool_use, tool_result) {
+        (Some((use_dm, _)), Some((result_dm, _))) => parse_iso8601_millis(&result_dm.timestamp)
+            .zip(parse_iso8601_millis(&use_dm.timestamp))
+            .map(|(end, start)| end - start),
+        _ => None,
+    };
+    let missing = match (tool_use, tool_result) {
+        (None, _) => Some("tool_use".to_string()),
+        (_, None) => Some("tool_result".to_string()),
+        _ => None,
+    };
+
+    Some(ToolInvocation {
+        tool_use_id: tool_use_id.to_string(),
+        name: session.tool_use_map.get(tool_use_id).map(|info| info.name.clone()),
+        tool_use: tool_use.map(|(_, block)| block.clone()),
+        tool_result: tool_result.map(|(_, block)| block.clone()),
+        tool_use_uuid: tool_use.map(|(dm, _)| dm.source_uuid.clone()),
+        tool_result_uuid: tool_result.map(|(dm, _)| dm.source_uuid.clone()),
+        elapsed_ms,
+        missing,
+    })
+}
+
+/// 按工具名统计调用次数与失败次数
+///
+/// 调用次数来自 `tool_use_map`，失败次数来自带 `is_error` 的 tool_result 消息，
+/// 通过 `tool_use_id` 关联到工具名。
+///
+/// # 参数
+/// - `session` - 已转换的会话数据
+///
+/// # 返回值
+/// 按失败次数降序（其次按调用次数降序、名称升序）排列的工具统计
+pub fn tool_stats(session: &TransformedSession) -> Vec<ToolStat> {
+    // 工具名 → (调用次数, 失败次数)
+    let mut counts: HashMap<&str, (u64, u64)> = HashMap::new();
+    for info in session.tool_use_map.values() {
+        counts.entry(info.name.as_str()).or_default().0 += 1;
+    }
+
+    for dm in session.display_messages.iter().filter(|dm| dm.is_error) {
+        let name = dm
+            .content
+            .first()
+            .and_then(|block| block.get("tool_use_id"))
+            .and_then(|v| v.as_str())
+            .and_then(|id| session.tool_use_map.get(id))
+            .map(|info| info.name.as_str())
+            .unwrap_or("未知工具");
+        counts.entry(name).or_default().1 += 1;
+    }
+
+    let mut stats: Vec<ToolStat> = counts
+        .into_iter()
+        .map(|(name, (calls, failures))| ToolStat {
+            name: name.to_string(),
+            calls,
+            failures,
+            failure_rate: if calls == 0 { 0.0 } else { failures as f64 / calls as f64 },
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        b.failures
+            .cmp(&a.failures)
+            .then(b.calls.cmp(&a.calls))
+            .then(a.name.cmp(&b.name))
+    });
+    stats
+}
+
+/// 统计 assistant 消息的 stop_reason 分布
+///
+/// Claude Code 会把一次流式响应拆成多条共享 `message.id` 的记录，
+/// 同一 `message_id` 只计一次；缺少 `message_id` 的消息逐条计数。
+///
+/// # 参数
+/// - `session` - 已转换的会话数据
+///
+/// # 返回值
+/// 按次数降序（其次按名称升序）排列的停止原因统计
+pub fn stop_reason_stats(session: &TransformedSession) -> Vec<StopReasonStat> {
+    let mut seen_ids: HashSet<&str> = HashSet::new();
+    let mut counts: HashMap<&str, u64> = HashMap::new();
+
+    for dm in &session.display_messages {
+        let Some(reason) = dm.stop_reason.as_deref() else {
+            continue;
+        };
+        if dm.message_id.as_deref().is_some_and(|id| !seen_ids.insert(id)) {
+            continue;
+        }
+        *counts.entry(reason).or_default() += 1;
+    }
+
+    let mut stats: Vec<StopReasonStat> = counts
+        .into_iter()
+        .map(|(reason, count)| StopReasonStat {
+            stop_reason: reason.to_string(),
+            count,
+        })
+        .collect();
+    stats.sort_by(|a, b| b.count.cmp(&a.count).then(a.stop_reason.cmp(&b.stop_reason)));
+    stats
+}
+
+/// 统计显示消息条数与文本字数
+///
+/// 超长文本块已被替换为 `oversized_text` 占位，按占位中记录的原文长度（`original_length`，UTF-8 字节数，
+/// 中文等多字节文字会被高估）计入，不再重新读取原文。
+///
+/// # 参数
+/// - `session` - 已转换的会话数据
+/// - `exclude_noise` - 是否剔除噪音消息（见 `classifier::is_noise`）
+///
+/// # 返回值
+/// `(消息条数, text 块字符数)`
+pub fn message_counts(session: &TransformedSession, exclude_noise: bool) -> (usize, usize) {
+    session
+        .display_messages
+        .iter()
+        .filter(|dm| !(exclude_noise && classifier::is_noise(&dm.display_type, dm.plan_source_path.as_deref())))
+        .fold((0, 0), |(count, chars), dm| {
+            let text: usize = dm.content.iter().map(text_block_chars).sum();
+            (count + 1, chars + text)
+        })
+}
+
+/// 内容块计入字数的长度：text 块为字符数，超长文本占位为原文长度，其他块为 0
+fn text_block_chars(block: &Value) -> usize {
+    match block.get("type").and_then(|v| v.as_str()) {
+        Some("text") => block.get("text").and_then(|v| v.as_str()).map_or(0, |text| text.chars().count()),
+        Some("oversized_text") => block
+            .get("original_length")
+            .and_then(|v| v.as_u64())
+            .map_or(0, |len| len as usize),
+        _ => 0,
+    }
+}
+
+/// 收集失败工具调用相关的 display_id
+///
+/// 包含每条失败的 tool_result 消息，以及发起该调用的 assistant 消息（含对应 tool_use 块），
+/// 供前端「只看失败的调用」筛选使用。
+///
+/// # 参数
+/// - `session` - 已转换的会话数据
+///
+/// # 返回值
+/// 按消息顺序排列的 display_id 列表
+pub fn failed_tool_display_ids(session: &TransformedSession) -> Vec<String> {
+    let failed_ids: HashSet<&str> = session
+        .display_messages
+        .iter()
+        .filter(|dm| dm.is_error)
+        .filter_map(|dm| dm.content.first()?.get("tool_use_id")?.as_str())
+        .collect();
+
+    session
+        .display_messages
+        .iter()
+        .filter(|dm| {
+            dm.is_error
+                || (dm.display_type == "assistant"
+                    && dm.content.iter().any(|block| {
+                        block
+                            .get("id")
+                            .and_then(|v| v.as_str())
+                            .is_some_and(|id| failed_ids.contains(id))
+                    }))
+        })
+        .map(|dm| dm.display_id.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// 构造 n 轮 user → assistant(tool_use) → user(tool_result) 的消息链
+    fn sample_messages(rounds: usize) -> Vec<Value> {
+        let mut messages = Vec::new();
+        let mut parent: Option<String> = None;
+        for i in 0..rounds {
+            let ids = [format!("u{}", i), format!("a{}", i), format!("r{}", i)];
+            let bodies = [
+                json!({ "type": "user", "message": { "role": "user", "content": format!("问题 {}", i) } }),
+                json!({ "type": "assistant", "message": { "role": "assistant", "id": format!("msg_{}", i),
+                    "content": [{ "type": "tool_use", "id": format!("t{}", i), "name": "Bash", "input": { "command": "ls" } }] } }),
+                json!({ "type": "user", "message": { "role": "user",
+                    "content": [{ "type": "tool_result", "tool_use_id": format!("t{}", i), "content": "ok" }] } }),
+            ];
+            for (id, mut body) in ids.into_iter().zip(bodies) {
+                body["uuid"] = json!(id);
+                body["parentUuid"] = json!(parent);
+                body["timestamp"] = json!("2025-01-01T00:00:00.000Z");
+                parent = Some(id);
+                messages.push(body);
+            }
+        }
+        messages
+    }
+
+    #[test]
+    fn test_chunked_matches_full_transform() {
+        let messages = sample_messages(20);
+        let (full, full_lower, _) = transform_session(&messages);
+
+        let mut ids = Vec::new();
+        let mut seqs = Vec::new();
+        let mut tool_uses = 0;
+        let (chunked, chunked_lower, _) = transform_session_chunked(&messages, 7, |chunk| {
+            assert!(chunk.display_messages.len() <= 7);
+            seqs.push(chunk.seq);
+            tool_uses += chunk.tool_uses.len();
+            ids.extend(chunk.display_messages.iter().map(|dm| dm.display_id.clone()));
+        });
+
+        let full_ids: Vec<String> = full.display_messages.iter().map(|dm| dm.display_id.clone()).collect();
+        assert_eq!(ids, full_ids);
+        assert_eq!(seqs, (0..seqs.len() as u64).collect::<Vec<_>>());
+        assert_eq!(tool_uses, full.tool_use_map.len());
+        assert_eq!(chunked.display_messages.len(), full.display_messages.len());
+        assert_eq!(chunked.token_stats.tool_calls, full.token_stats.tool_calls);
+        assert_eq!(chunked_lower, full_lower);
+    }
+
+    #[test]
+    fn test_page_keeps_full_tool_map() {
+        let (full, _, _) = transform_session(&sample_messages(5));
+        let total = full.display_messages.len();
+        let ids = |s: &TransformedSession| s.display_messages.iter().map(|m| m.display_id.clone()).collect::<Vec<_>>();
+
+        let first = full.page(0, Some(4));
+        assert_eq!(ids(&first), ids(&full)[..4]);
+        assert_eq!((first.total_count, first.tool_use_map.len()), (Some(total), full.tool_use_map.len()));
+
+        let rest = full.page(4, None);
+        assert_eq!(ids(&rest), ids(&full)[4..]);
+        assert!(full.page(total + 1, Some(4)).display_messages.is_empty());
+        assert_eq!(full.total_count, None);
+    }
+
+    #[test]
+    fn test_chunked_first_chunk_before_completion() {
+        // 2 万条消息：第一批回调时转换远未完成
+        let messages = sample_messages(20_000 / 3);
+        let mut first_chunk_at: Option<usize> = None;
+        let mut produced = 0usize;
+        let (session, _, _) = transform_session_chunked(&messages, 500, |chunk| {
+            produced += chunk.display_messages.len();
+            first_chunk_at.get_or_insert(produced);
+        });
+        assert_eq!(first_chunk_at, Some(500));
+        assert_eq!(produced, session.display_messages.len());
+    }
+
+    #[test]
+    fn test_oversized_text_is_collapsed() {
+        let big = format!("{}尾", "a".repeat(300 * 1024));
+        let messages = vec![json!({
+            "type": "user", "uuid": "u0", "parentUuid": null, "timestamp": "2025-01-01T00:00:00.000Z",
+            "message": { "role": "user", "content": [{ "type": "text", "text": "开头" }, { "type": "text", "text": big }] }
+        })];
+        let (session, _, original_texts) = transform_session(&messages);
+
+        let block = &session.display_messages[0].content[1];
+        assert_eq!(block["type"], "oversized_text");
+        assert_eq!((block["original_length"].as_u64(), block["block_index"].as_u64()), (Some(big.len() as u64), Some(1)));
+        assert_eq!(block["preview"].as_str().map(|p| p.chars().count()), Some(OVERSIZED_PREVIEW_CHARS));
+        // 搜索文本只含前缀，原始消息不受影响
+        assert!(original_texts[0].contains("开头") && !original_texts[0].contains('尾'));
+        assert!(original_texts[0].len() < OVERSIZED_SEARCH_PREFIX_BYTES + 64);
+        assert_eq!(messages[0]["message"]["content"][1]["text"].as_str().map(str::len), Some(big.len()));
+    }
+
+    #[test]
+    fn test_message_counts_noise_and_oversized_text() {
+        let big = "字".repeat(200 * 1024);
+        let messages = vec![
+            json!({ "type": "user", "uuid": "u0", "parentUuid": null, "timestamp": "2025-01-01T00:00:00.000Z",
+                "message": { "role": "user", "content": "abc" } }),
+            json!({ "type": "user", "uuid": "u1", "parentUuid": "u0", "timestamp": "2025-01-01T00:00:00.000Z",
+                "message": { "role": "user", "content": [{ "type": "text", "text": big }] } }),
+            json!({ "type": "user", "uuid": "u2", "parentUuid": "u1", "timestamp": "2025-01-01T00:00:00.000Z",
+                "isCompactSummary": true, "message": { "role": "user", "content": "摘要" } }),
+        ];
+        let (session, _, _) = transform_session(&messages);
+        assert_eq!(session.display_messages[1].content[0]["type"], "oversized_text");
+
+        // 超长文本按原文长度计入，而不是预览长度
+        assert_eq!(message_counts(&session, true), (2, 3 + big.len()));
+        assert_eq!(message_counts(&session, false), (3, 3 + big.len() + 2));
+    }
+
+    #[test]
+    fn test_noise_is_keyed_on_plan_source() {
+        assert!(classifier::is_noise("compact_summary", None));
+        assert!(classifier::is_noise("system", None));
+        assert!(!classifier::is_noise("system", Some("/p/plan.jsonl")));
+        assert!(!classifier::is_noise("user", None));
+    }
+
+    #[test]
+    fn test_stop_reason_extracted_and_counted_once_per_message_id() {
+        let assistant = |uuid: &str, id: Option<&str>, reason: Option<&str>| {
+            json!({
+                "type": "assistant", "uuid": uuid, "parentUuid": null, "timestamp": "2025-01-01T00:00:00.000Z",
+                "message": { "role": "assistant", "id": id, "stop_reason": reason,
+                    "content": [{ "type": "text", "text": "回答" }] }
+            })
+        };
+        let messages = vec![
+            // 同一流式响应拆成两条记录，只计一次
+            assistant("a0", Some("msg_0"), Some("tool_use")),
+            assistant("a1", Some("msg_0"), Some("tool_use")),
+            assistant("a2", Some("msg_1"), Some("max_tokens")),
+            // 缺少 id 的消息逐条计数；缺少 stop_reason 的消息不计
+            assistant("a3", None, Some("end_turn")),
+            assistant("a4", None, Some("end_turn")),
+            assistant("a5", Some("msg_2"), None),
+        ];
+        let (session, _, _) = transform_session(&messages);
+
+        let first = &session.display_messages[0];
+        assert_eq!((first.stop_reason.as_deref(), first.message_id.as_deref()), (Some("tool_use"), Some("msg_0")));
+        assert_eq!(session.display_messages[5].stop_reason, None);
+
+        let stats = stop_reason_stats(&session);
+        let counts: Vec<(&str, u64)> = stats.iter().map(|s| (s.stop_reason.as_str(), s.count)).collect();
+        assert_eq!(counts, vec![("end_turn", 2), ("max_tokens", 1), ("tool_use", 1)]);
+    }
+}
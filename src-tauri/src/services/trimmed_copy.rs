@@ -0,0 +1,391 @@
+//! # 会话瘦身副本
+//!
+//! 长会话 resume 时上下文过大，用户往往只需要最近的几轮对话。本模块从会话主线
+//! （Claude Code resume 时实际接续的分支，见 `branches`）中截取最后 N 条消息，
+//! 写为同项目下的新 UUID 会话或导出到指定路径，原文件保持不变。
+//!
+//! ## 截取规则
+//! 直接截断可能让副本在 resume 时被 API 以 400 拒绝，因此起点按以下规则向前扩展，直到不再变化：
+//! - 窗口内的 tool_result 对应的 tool_use 位于窗口之前时，扩展到该 tool_use 所在消息
+//!   （配对判定复用 `fixers::helpers::pair_tool_results`）
+//! - 起点是同一条 assistant 回复（相同 `message.id`）拆分出的后续行时，扩展到该回复的第一行
+//! - 起点不是 user 消息时继续向前，保证首条发往 API 的消息为 user 角色
+//!
+//! 截取后首条消息的 `parentUuid` 置空作为新根，所有消息的 `sessionId` 改为新会话 ID。
+//! 主线之外的分支、侧链与 summary 等元数据行不写入副本。
+//!
+//! ## 完整副本
+//! `duplicate_session` 复制整个会话（含分支、侧链与坏行）为同项目下的新 UUID 会话，
+//! 只改写 `sessionId`，用于在副本上做实验性修剪而不破坏原文件。
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::models::message::SessionMessage;
+use crate::models::project::Session;
+use crate::services::cache::AppCache;
+use crate::services::fixers::helpers;
+use crate::services::{branches, file_guard, parser};
+use crate::utils::filename;
+
+/// 瘦身副本的生成结果
+///
+/// 对应前端 TypeScript 接口 `TrimmedCopyResult`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrimmedCopyResult {
+    /// 副本文件的绝对路径
+    pub file_path: String,
+    /// 副本的会话 ID（即文件名中的 UUID）
+    pub session_id: String,
+    /// 副本实际包含的消息条数（补齐配对后可能多于请求的条数）
+    pub message_count: usize,
+}
+
+/// 生成会话的瘦身副本
+///
+/// # 参数
+/// - `session_file_path` - 源会话 JSONL 文件的绝对路径（只读）
+/// - `keep_last_n` - 保留主线上最后多少条消息
+/// - `output_path` - 导出路径；为 None 时在源会话所在项目目录下生成 `<新UUID>.jsonl`
+/// - `cache` - AppCache 引用，写入项目目录时用于注册备份并把新会话加入项目列表缓存
+///
+/// # 返回值
+/// 副本路径、会话 ID 与实际包含的条数
+///
+/// # 错误
+/// `keep_last_n` 为 0、会话没有可保留的对话消息、导出路径位于 Claude 数据目录内、
+/// 读取或写入失败时返回错误
+pub async fn create_trimmed_copy(
+    session_file_path: &str,
+    keep_last_n: usize,
+    output_path: Option<&str>,
+    cache: &AppCache,
+) -> Result<TrimmedCopyResult, String> {
+    if keep_last_n == 0 {
+        return Err("保留条数必须大于 0".to_string());
+    }
+    // 导出路径用于数据目录之外；写回项目目录请省略该参数，走带备份的统一写入入口
+    if let Some(output) = output_path
+        && file_guard::is_within_data_sources(output)?
+    {
+        return Err("导出路径不能位于 Claude 数据目录内，如需在项目中生成副本请不指定导出路径".to_string());
+    }
+
+    let messages = parser::read_messages(session_file_path).await?;
+    let mut session_id = new_session_id();
+    let mut trimmed = trim_messages(&messages, keep_last_n, &session_id);
+    if trimmed.is_empty() {
+        return Err("会话中没有可保留的对话消息".to_string());
+    }
+
+    let file_path = match output_path {
+        Some(output) => {
+            tokio::fs::write(filename::to_long_path(output), parser::serialize_jsonl(&trimmed)?)
+                .await
+                .map_err(|e| format!("写入瘦身副本失败: {}", e))?;
+            output.to_string()
+        }
+        None => {
+            // 副本的会话 ID 以项目目录中实际占用到的文件名为准
+            let (claimed_id, target) = claim_session_file(project_dir(session_file_path)?, new_session_id).await?;
+            session_id = claimed_id;
+            rewrite_session_id(&mut trimmed, &session_id);
+            write_claimed(&target, &parser::serialize_jsonl(&trimmed)?, "create_trimmed_copy", cache).await?;
+            cache.add_session_file(&target).await;
+            target
+        }
+    };
+
+    Ok(TrimmedCopyResult {
+        file_path,
+        session_id,
+        message_count: trimmed.len(),
+    })
+}
+
+/// 生成新会话文件名时的最大尝试次数（UUID 与已有文件冲突时重新生成）
+const MAX_NAME_ATTEMPTS: usize = 8;
+
+/// 复制整个会话为同项目下的新 UUID 会话
+///
+/// 每行带有 `sessionId` 字段的消息改写为新会话 ID（否则 Claude Code resume 时会混淆两个会话），
+/// 其余内容原样保留。新文件名以 `create_new` 占用（见 `claim_session_file`），
+/// 再经 `file_guard` 写入，写入后加入项目列表缓存。
+///
+/// # 参数
+/// - `session_file_path` - 源会话 JSONL 文件的绝对路径（只读）
+/// - `cache` - AppCache 引用，用于注册写入记录并把新会话加入项目列表缓存
+///
+/// # 返回值
+/// 新会话的 `Session` 结构（与扫描结果一致）
+///
+/// # 错误
+/// 会话没有消息、多次生成的文件名均已存在、读取或写入失败时返回错误
+pub async fn duplicate_session(session_file_path: &str, cache: &AppCache) -> Result<Session, String> {
+    let mut messages = parser::read_messages(session_file_path).await?;
+    if messages.is_empty() {
+        return Err("会话不存在或没有消息".to_string());
+    }
+
+    let (session_id, target) = claim_session_file(project_dir(session_file_path)?, new_session_id).await?;
+    rewrite_session_id(&mut messages, &session_id);
+    write_claimed(&target, &parser::serialize_jsonl(&messages)?, "duplicate_session", cache).await?;
+
+    cache
+        .add_session_file(&target)
+        .await
+        .ok_or_else(|| format!("读取新会话元数据失败: {}", target))
+}
+
+/// 会话文件所在的项目目录
+fn project_dir(session_file_path: &str) -> Result<&Path, String> {
+    Path::new(session_file_path)
+        .parent()
+        .ok_or_else(|| format!("无法确定会话所在目录: {}", session_file_path))
+}
+
+/// 生成新的会话 ID（v4 UUID）
+fn new_session_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// 以 `create_new` 占用项目目录下新的 `<会话ID>.jsonl` 文件名
+///
+/// 同名文件已存在（含并发创建方刚占用的）时重新生成 ID，最多尝试 `MAX_NAME_ATTEMPTS` 次。
+/// 占用的是空文件，内容随后由 `write_claimed` 写入。
+///
+/// # 参数
+/// - `project_dir` - 项目目录
+/// - `new_id` - 会话 ID 生成器
+///
+/// # 返回值
+/// `(会话 ID, 文件路径)`
+///
+/// # 错误
+/// 多次生成的文件名均已存在或创建文件失败时返回错误
+async fn claim_session_file(project_dir: &Path, mut new_id: impl FnMut() -> String) -> Result<(String, String), String> {
+    for _ in 0..MAX_NAME_ATTEMPTS {
+        let session_id = new_id();
+        let target = project_dir.join(format!("{}.jsonl", session_id));
+        match tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(filename::to_long_path(&target))
+            .await
+        {
+            Ok(_) => return Ok((session_id, target.to_string_lossy().to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("创建新会话文件失败: {}", e)),
+        }
+    }
+    Err("生成新会话文件名失败：文件名多次冲突".to_string())
+}
+
+/// 经 `file_guard` 把内容写入已占用的新会话文件；写入失败时删除占用的空文件
+async fn write_claimed(target: &str, content: &str, operation: &str, cache: &AppCache) -> Result<(), String> {
+    let result = file_guard::safe_write_file(target, content.as_bytes(), operation, cache).await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(filename::to_long_path(target)).await;
+    }
+    result.map(|_| ())
+}
+
+/// 把带有 `sessionId` 字段的消息改写为指定会话 ID（没有该字段的行不补）
+fn rewrite_session_id(messages: &mut [SessionMessage], session_id: &str) {
+    for msg in messages.iter_mut().filter_map(|m| m.as_object_mut()) {
+        if msg.contains_key("sessionId") {
+            msg.insert("sessionId".to_string(), serde_json::Value::String(session_id.to_string()));
+        }
+    }
+}
+
+/// 截取主线上最后 `keep_last_n` 条消息，并按模块文档的规则补齐起点
+///
+/// # 参数
+/// - `messages` - 原始会话消息
+/// - `keep_last_n` - 保留的消息条数
+/// - `session_id` - 副本的会话 ID，写入每条消息的 `sessionId`
+///
+/// # 返回值
+/// 副本的消息列表；会话没有带 uuid 的对话消息时为空
+pub fn trim_messages(messages: &[SessionMessage], keep_last_n: usize, session_id: &str) -> Vec<SessionMessage> {
+    let Some(leaf) = branches::main_leaf_uuid(messages) else {
+        return Vec::new();
+    };
+    let Some(path) = branches::branch_path(messages, &leaf) else {
+        return Vec::new();
+    };
+
+    // 主线消息（文件顺序，重复 uuid 以首次出现为准，与建树规则一致）
+    let mut seen = HashSet::new();
+    let chain: Vec<SessionMessage> = messages
+        .iter()
+        .filter(|m| !m.get("isSidechain").and_then(|v| v.as_bool()).unwrap_or(false))
+        .filter(|m| {
+            m.get("uuid")
+                .and_then(|v| v.as_str())
+                .is_some_and(|u| path.contains(u) && seen.insert(u))
+        })
+        .cloned()
+        .collect();
+
+    let start = expand_start(&chain, chain.len().saturating_sub(keep_last_n));
+    let mut trimmed = chain[start..].to_vec();
+    if let Some(first) = trimmed.first_mut().and_then(|m| m.as_object_mut()) {
+        first.insert("parentUuid".to_string(), serde_json::Value::Null);
+        first.remove("logicalParentUuid");
+    }
+    for msg in trimmed.iter_mut().filter_map(|m| m.as_object_mut()) {
+        if msg.contains_key("sessionId") {
+            msg.insert("sessionId".to_string(), serde_json::Value::String(session_id.to_string()));
+        }
+    }
+    trimmed
+}
+
+/// 向前扩展截取起点，直到窗口内的工具调用配对完整、且起点是一条完整 user 消息
+fn expand_start(chain: &[SessionMessage], mut start: usize) -> usize {
+    let pairs = helpers::pair_tool_results(chain);
+    loop {
+        let previous = start;
+
+        for pair in pairs.values() {
+            if let (Some(use_index), Some(result_index)) = (pair.use_index, pair.result_index)
+                && result_index >= start
+                && use_index < start
+            {
+                start = use_index;
+            }
+        }
+        while start > 0 && assistant_message_id(&chain[start]).is_some_and(|id| assistant_message_id(&chain[start - 1]) == Some(id)) {
+            start -= 1;
+        }
+        while start > 0 && chain[start].get("type").and_then(|v| v.as_str()) != Some("user") {
+            start -= 1;
+        }
+
+        if start == previous {
+            return start;
+        }
+    }
+}
+
+/// assistant 消息的 `message.id`（同一回复拆分成的多行共享该 id）
+fn assistant_message_id(msg: &SessionMessage) -> Option<&str> {
+    if msg.get("type").and_then(|v| v.as_str()) != Some("assistant") {
+        return None;
+    }
+    msg.get("message")?.get("id")?.as_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn line(uuid: &str, parent: Option<&str>, kind: &str, content: Value) -> SessionMessage {
+        json!({
+            "type": kind,
+            "uuid": uuid,
+            "parentUuid": parent,
+            "sessionId": "old",
+            "timestamp": format!("2025-01-01T00:00:{}Z", uuid.trim_start_matches('m')),
+            "message": { "id": format!("msg-{}", uuid), "role": kind, "content": content }
+        })
+    }
+
+    fn uuids(messages: &[SessionMessage]) -> Vec<&str> {
+        messages.iter().filter_map(|m| m.get("uuid")?.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_claim_session_file_retries_on_collision() {
+        let dir = crate::utils::platform::test_home().join("trimmed-copy-claim");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("taken.jsonl"), "{}\n").unwrap();
+
+        let mut ids = ["taken", "fresh"].into_iter().map(str::to_string);
+        let (session_id, target) = claim_session_file(&dir, || ids.next().unwrap()).await.unwrap();
+        assert_eq!(session_id, "fresh");
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "");
+        // 已存在的文件不被覆盖
+        assert_eq!(std::fs::read_to_string(dir.join("taken.jsonl")).unwrap(), "{}\n");
+
+        assert!(claim_session_file(&dir, || "taken".to_string()).await.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_session_rewrites_session_id() {
+        let dir = crate::utils::path::get_claude_data_path().unwrap().join("projects").join("-trimmed-copy-duplicate");
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("source.jsonl");
+        let original = [
+            line("m10", None, "user", json!("first")),
+            line("m11", Some("m10"), "assistant", json!([{ "type": "text", "text": "ok" }])),
+            json!({ "type": "summary", "summary": "s", "leafUuid": "m11" }),
+        ];
+        let content = parser::serialize_jsonl(&original).unwrap();
+        std::fs::write(&source, &content).unwrap();
+
+        let session = duplicate_session(&source.to_string_lossy(), &AppCache::new()).await.unwrap();
+        let copy: Vec<Value> = std::fs::read_to_string(&session.file_path)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(copy.len(), 3);
+        assert!(copy[..2].iter().all(|m| m["sessionId"] == session.id.as_str()));
+        assert_eq!(uuids(&copy), vec!["m10", "m11"]);
+        // 没有 sessionId 的行不补字段，源文件不变
+        assert!(copy[2].get("sessionId").is_none());
+        assert_eq!(std::fs::read_to_string(&source).unwrap(), content);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_trim_messages_completes_tool_pairs() {
+        let mut split = line("m13", Some("m12"), "assistant", json!([{ "type": "tool_use", "id": "t1", "name": "Read", "input": {} }]));
+        split["message"]["id"] = json!("msg-m12");
+        let messages = vec![
+            line("m10", None, "user", json!("first")),
+            line("m11", Some("m10"), "assistant", json!([{ "type": "text", "text": "ok" }])),
+            line("m12", Some("m11"), "assistant", json!([{ "type": "thinking", "thinking": "…" }])),
+            split,
+            line("m14", Some("m13"), "user", json!([{ "type": "tool_result", "tool_use_id": "t1", "content": "x" }])),
+            line("m15", Some("m14"), "assistant", json!([{ "type": "text", "text": "done" }])),
+            json!({ "type": "summary", "summary": "s", "leafUuid": "m15" }),
+        ];
+
+        let trimmed = trim_messages(&messages, 1, "new");
+        // 截取到 m15 → tool_result 需要 m13 → 同一回复的 m12 → 向前找到 user 消息 m10
+        assert_eq!(uuids(&trimmed), vec!["m10", "m11", "m12", "m13", "m14", "m15"]);
+
+        let trimmed = trim_messages(&messages[..2], 1, "new");
+        assert_eq!(uuids(&trimmed), vec!["m10", "m11"]);
+        assert!(trimmed.iter().all(|m| m["sessionId"] == "new"));
+        assert!(trimmed[0]["parentUuid"].is_null());
+    }
+
+    #[test]
+    fn test_trim_messages_reroots_on_main_branch() {
+        let messages = vec![
+            line("m10", None, "user", json!("a")),
+            line("m11", Some("m10"), "assistant", json!([{ "type": "text", "text": "b" }])),
+            line("m12", Some("m11"), "user", json!("old question")),
+            // Esc 回退后重新提问，m13 所在分支为主线
+            line("m13", Some("m11"), "user", json!("new question")),
+            line("m14", Some("m13"), "assistant", json!([{ "type": "text", "text": "c" }])),
+        ];
+
+        let trimmed = trim_messages(&messages, 2, "new");
+        assert_eq!(uuids(&trimmed), vec!["m13", "m14"]);
+        assert!(trimmed[0]["parentUuid"].is_null());
+        assert_eq!(trimmed[1]["parentUuid"], "m13");
+
+        assert!(trim_messages(&[json!({ "type": "summary" })], 3, "new").is_empty());
+    }
+}
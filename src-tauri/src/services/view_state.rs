@@ -0,0 +1,146 @@
+//! # 会话阅读状态服务
+//!
+//! 几千条消息的会话每次打开都回到顶部或底部。前端把「读到哪、哪些块展开过、激活的过滤器」
+//! 序列化为 JSON 交给后端保存，下次打开时恢复。
+//!
+//! 状态保存在 `~/.mo/CCR/view-state/<key>.json`，绝不写入会话 JSONL。
+//! `<key>` 是归一化会话路径（`session_lock::normalize`）的 FNV-1a 64 位哈希：
+//! 不同项目或数据源中同名的会话文件（如精简副本、同步来的副本）各自保存，互不覆盖。
+//! 后端只校验是合法 JSON 并限制大小（`MAX_VIEW_STATE_BYTES`），不解析内容语义；
+//! 会话被删除、归档或合并进其他会话时随之清理。
+
+use std::path::PathBuf;
+
+use crate::services::{file_guard, session_lock};
+use crate::utils::path;
+
+/// 阅读状态目录名（位于 `~/.mo/CCR/`）
+const VIEW_STATE_DIR: &str = "view-state";
+
+/// 单个会话阅读状态的大小上限（字节）
+const MAX_VIEW_STATE_BYTES: usize = 64 * 1024;
+
+/// 读取会话的阅读状态
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件路径（用于确定状态文件名）
+///
+/// # 返回值
+/// 保存时的 JSON 原文；没有保存过时返回 None
+///
+/// # 错误
+/// 状态文件存在但读取失败时返回错误
+pub async fn get(session_file_path: &str) -> Result<Option<String>, String> {
+    let file_path = view_state_path(session_file_path)?;
+    if !file_path.exists() {
+        return Ok(None);
+    }
+    tokio::fs::read_to_string(&file_path)
+        .await
+        .map(Some)
+        .map_err(|e| format!("读取阅读状态失败: {}", e))
+}
+
+/// 保存会话的阅读状态（整体覆盖）
+///
+/// # 参数
+/// - `session_file_path` - 会话 JSONL 文件路径
+/// - `state_json` - 前端序列化的状态 JSON
+///
+/// # 错误
+/// 状态超过大小上限、不是合法 JSON，或写入失败时返回错误
+pub async fn save(session_file_path: &str, state_json: &str) -> Result<(), String> {
+    validate(state_json)?;
+    let file_path = view_state_path(session_file_path)?;
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("创建阅读状态目录失败: {}", e))?;
+    }
+    file_guard::write_atomic(&file_path.to_string_lossy(), state_json.as_bytes())
+        .await
+        .map_err(|e| format!("写入阅读状态失败: {}", e))
+}
+
+/// 删除会话的阅读状态（不存在时视为成功）
+///
+/// # 错误
+/// 状态文件存在但删除失败时返回错误
+pub async fn remove(session_file_path: &str) -> Result<(), String> {
+    let file_path = view_state_path(session_file_path)?;
+    match tokio::fs::remove_file(&file_path).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("删除阅读状态失败: {}", e)),
+    }
+}
+
+/// 校验状态的大小与 JSON 格式
+fn validate(state_json: &str) -> Result<(), String> {
+    if state_json.len() > MAX_VIEW_STATE_BYTES {
+        return Err(format!(
+            "阅读状态过大（{} 字节，上限 {} 字节）",
+            state_json.len(),
+            MAX_VIEW_STATE_BYTES
+        ));
+    }
+    serde_json::from_str::<serde_json::Value>(state_json)
+        .map(|_| ())
+        .map_err(|e| format!("阅读状态不是合法的 JSON: {}", e))
+}
+
+/// 状态文件路径：`~/.mo/CCR/view-state/<归一化路径的哈希>.json`
+fn view_state_path(session_file_path: &str) -> Result<PathBuf, String> {
+    let key = fnv1a_64(&session_lock::normalize(session_file_path));
+    Ok(path::get_ccr_config_path()?
+        .join(VIEW_STATE_DIR)
+        .join(format!("{:016x}.json", key)))
+}
+
+/// FNV-1a 64 位哈希（算法固定，跨版本、跨平台稳定，可用作持久化的文件名）
+fn fnv1a_64(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_view_state() {
+        assert!(validate(r#"{"anchor":"u1","expanded":["u2-0"]}"#).is_ok());
+        assert!(validate("{").unwrap_err().contains("JSON"));
+        let oversized = format!("\"{}\"", "x".repeat(MAX_VIEW_STATE_BYTES));
+        assert!(validate(&oversized).unwrap_err().contains("过大"));
+    }
+
+    #[test]
+    fn test_view_state_path_keyed_by_normalized_path() {
+        let a = view_state_path("/p/-w-app/s1.jsonl").unwrap();
+        // 同名会话位于不同项目时不共用状态
+        assert_ne!(a, view_state_path("/p/-w-other/s1.jsonl").unwrap());
+        // 分隔符差异归一化后是同一个会话
+        assert_eq!(a, view_state_path("\\p\\-w-app\\s1.jsonl").unwrap());
+        assert_eq!(fnv1a_64(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a_64("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[tokio::test]
+    async fn test_save_enforces_size_quota() {
+        let session = "/view-state-quota/s.jsonl";
+        // 恰好 64KB 的合法 JSON 可以保存
+        let exact = format!("\"{}\"", "x".repeat(MAX_VIEW_STATE_BYTES - 2));
+        assert_eq!(exact.len(), MAX_VIEW_STATE_BYTES);
+        save(session, &exact).await.unwrap();
+
+        // 超出 1 字节被拒绝，已保存的状态保持不变
+        let over = format!("\"{}\"", "x".repeat(MAX_VIEW_STATE_BYTES - 1));
+        assert!(save(session, &over).await.unwrap_err().contains("过大"));
+        assert_eq!(get(session).await.unwrap().as_deref(), Some(exact.as_str()));
+
+        remove(session).await.unwrap();
+        assert_eq!(get(session).await.unwrap(), None);
+    }
+}
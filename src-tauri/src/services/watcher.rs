@@ -0,0 +1,252 @@
+//! # Claude 数据目录监听
+//!
+//! Claude Code 运行期间会持续追加会话 JSONL。本模块用 `notify` 递归监听全部数据源的
+//! `projects/` 目录，文件变化时使相关缓存失效，并向前端广播 `claude-data-changed` 事件，
+//! 前端据此自动刷新，无需手动重新加载。
+//!
+//! ## 防抖
+//! Claude Code 在一次回复中会多次写入同一文件。事件先按路径合并，静默 `DEBOUNCE` 后统一处理；
+//! 持续写入时最迟每 `MAX_DEBOUNCE_WAIT` 处理一次，保证流式输出期间也能看到进展。
+//! 事件类型在处理时按文件是否仍存在判定（created / modified / removed），
+//! 因此「创建后立即删除」「原子写入的先删后建」等序列都能得到最终状态。
+//! 事件同时带上 `updated_since_viewed` 标记（见 `services::last_viewed`），前端据此更新未读圆点。
+//!
+//! ## 生命周期
+//! 监听由前端通过 `start_watching` / `stop_watching` 控制。`WatcherState` 持有 watcher，
+//! 停止时 drop watcher，事件通道随之关闭，后台任务处理完剩余事件后退出。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::services::cache::AppCache;
+use crate::services::data_sources;
+use crate::services::last_viewed::{self, LastViewed};
+
+/// 数据目录变化后广播的事件名
+pub const CLAUDE_DATA_CHANGED_EVENT: &str = "claude-data-changed";
+
+/// 防抖静默时间：最后一个事件之后等待这么久没有新事件才处理
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// 持续写入时的最长等待时间
+const MAX_DEBOUNCE_WAIT: Duration = Duration::from_secs(2);
+
+/// 文件变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DataChangeKind {
+    /// 新建
+    Created,
+    /// 内容被修改
+    Modified,
+    /// 已删除
+    Removed,
+}
+
+/// `claude-data-changed` 事件载荷
+///
+/// 对应前端 TypeScript 接口 `ClaudeDataChangedEvent`。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaudeDataChanged {
+    /// 变化的会话文件绝对路径
+    pub file_path: String,
+    /// 变化类型
+    pub kind: DataChangeKind,
+    /// 自上次在 CCR 中查看后是否又被修改（删除时为 false）
+    pub updated_since_viewed: bool,
+}
+
+/// 文件监听全局状态（Tauri managed state）
+pub struct WatcherState {
+    /// 正在运行的 watcher（None 表示未监听）
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl WatcherState {
+    /// 创建未启动的监听状态
+    pub fn new() -> Self {
+        Self {
+            watcher: Mutex::new(None),
+        }
+    }
+
+    /// 开始监听全部数据源的 `projects/` 目录（已在监听时直接返回）
+    ///
+    /// # 参数
+    /// - `app` - Tauri AppHandle，后台任务用它获取 `AppCache` 并广播事件
+    ///
+    /// # 返回值
+    /// 本次是否新启动了监听
+    ///
+    /// # 错误
+    /// 无法确定数据目录、没有可监听的 projects 目录，或创建 watcher 失败时返回错误
+    pub fn start(&self, app: AppHandle) -> Result<bool, String> {
+        let mut guard = self.watcher.lock().map_err(|_| "监听状态锁已损坏".to_string())?;
+        if guard.is_some() {
+            return Ok(false);
+        }
+
+        let dirs: Vec<PathBuf> = data_sources::source_roots()?
+            .into_iter()
+            .map(|root| root.join("projects"))
+            .filter(|dir| dir.is_dir())
+            .collect();
+        if dirs.is_empty() {
+            return Err("没有可监听的 projects 目录".to_string());
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+            Ok(event) => {
+                let _ = tx.send(event);
+            }
+            Err(e) => log::warn!("文件监听出错: {}", e),
+        })
+        .map_err(|e| format!("创建文件监听失败: {}", e))?;
+        for dir in &dirs {
+            watcher
+                .watch(dir, RecursiveMode::Recursive)
+                .map_err(|e| format!("监听目录失败 {}: {}", dir.display(), e))?;
+        }
+
+        tauri::async_runtime::spawn(debounce_loop(rx, app));
+        *guard = Some(watcher);
+        Ok(true)
+    }
+
+    /// 停止监听（未在监听时无操作）
+    ///
+    /// # 返回值
+    /// 本次是否停止了正在运行的监听
+    pub fn stop(&self) -> bool {
+        self.watcher
+            .lock()
+            .map(|mut guard| guard.take().is_some())
+            .unwrap_or(false)
+    }
+}
+
+impl Default for WatcherState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 防抖循环：收集一批事件后统一失效缓存并广播，通道关闭（停止监听）时退出
+async fn debounce_loop(mut rx: mpsc::UnboundedReceiver<notify::Event>, app: AppHandle) {
+    while let Some(event) = rx.recv().await {
+        let mut pending = HashMap::new();
+        record_event(&mut pending, event);
+
+        let deadline = Instant::now() + MAX_DEBOUNCE_WAIT;
+        let mut closed = false;
+        loop {
+            let wait_until = (Instant::now() + DEBOUNCE).min(deadline);
+            match tokio::time::timeout_at(wait_until, rx.recv()).await {
+                Ok(Some(event)) => record_event(&mut pending, event),
+                Ok(None) => {
+                    closed = true;
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+
+        let viewed = last_viewed::load_all().await.unwrap_or_default();
+        let changes = resolve_changes(pending, &viewed);
+        if !changes.is_empty() {
+            let cache = app.state::<AppCache>();
+            for change in &changes {
+                cache.invalidate_session(&change.file_path);
+                let _ = app.emit(CLAUDE_DATA_CHANGED_EVENT, change);
+            }
+            cache.invalidate_projects();
+        }
+        if closed {
+            break;
+        }
+    }
+}
+
+/// 把一个 notify 事件合并进待处理集合
+///
+/// 只关心会话 `.jsonl` 文件（原子写入的 `.tmp`、备份等忽略）；
+/// 值表示该路径在本批次中是否出现过创建事件。
+fn record_event(pending: &mut HashMap<PathBuf, bool>, event: notify::Event) {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+    let created = matches!(event.kind, EventKind::Create(_));
+    for path in event.paths {
+        if path.extension().and_then(|e| e.to_str()) == Some("jsonl") {
+            *pending.entry(path).or_insert(false) |= created;
+        }
+    }
+}
+
+/// 按文件当前是否存在确定每个路径的最终变化类型，并按最近查看时间计算未读标记
+fn resolve_changes(pending: HashMap<PathBuf, bool>, viewed: &LastViewed) -> Vec<ClaudeDataChanged> {
+    let mut changes: Vec<ClaudeDataChanged> = pending
+        .into_iter()
+        .map(|(path, created)| {
+            let kind = match (path.is_file(), created) {
+                (false, _) => DataChangeKind::Removed,
+                (true, true) => DataChangeKind::Created,
+                (true, false) => DataChangeKind::Modified,
+            };
+            let file_path = path.to_string_lossy().to_string();
+            let updated_since_viewed = kind != DataChangeKind::Removed
+                && last_viewed::is_updated(viewed, &file_path, last_viewed::file_mtime_millis(&file_path));
+            ClaudeDataChanged {
+                file_path,
+                kind,
+                updated_since_viewed,
+            }
+        })
+        .collect();
+    changes.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, ModifyKind, RemoveKind};
+
+    #[test]
+    fn test_record_and_resolve_changes() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        let created = dir.join("a.jsonl");
+        let modified = dir.join("b.jsonl");
+        let removed = dir.join("c.jsonl");
+        std::fs::write(&created, "").unwrap();
+        std::fs::write(&modified, "").unwrap();
+
+        let event = |kind, path: &PathBuf| notify::Event::new(kind).add_path(path.clone());
+        let mut pending = HashMap::new();
+        record_event(&mut pending, event(EventKind::Create(CreateKind::File), &created));
+        record_event(&mut pending, event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), &created));
+        record_event(&mut pending, event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), &modified));
+        record_event(&mut pending, event(EventKind::Create(CreateKind::File), &removed));
+        record_event(&mut pending, event(EventKind::Remove(RemoveKind::File), &removed));
+        // 非会话文件忽略
+        record_event(&mut pending, event(EventKind::Create(CreateKind::File), &dir.join("a.jsonl.tmp")));
+        assert_eq!(pending.len(), 3);
+
+        let kinds: Vec<DataChangeKind> = resolve_changes(pending, &LastViewed::new()).into_iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![DataChangeKind::Created, DataChangeKind::Modified, DataChangeKind::Removed]
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! # 应用配置文件
+//!
+//! `~/.mo/CCR/app-config.json` 是 CCR 的杂项配置，各字段由不同模块读取：
+//! - `enableBenchmark`（`services::benchmark`）：release 构建中开放基准测试
+//! - `wslPathStyle`（`utils::path`）：WSL 挂载路径的输出风格
+//! - `mobileClaudeDir`（`utils::path`）：移动端 Claude 数据目录（相对应用沙箱）
+//! - `displayTimezone`（`utils::time`）：时间显示与按天切分的时区
+//!
+//! 读取时各模块只反序列化自己关心的字段（`load`），文件不存在或解析失败时取默认值。
+//! 修改某个字段经 `set_field`：在模块级互斥锁内读-改-写，保留其他字段，
+//! 经 `file_guard::write_atomic` 原子替换，启动时的并发读取不会读到半截文件。
+
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+
+use crate::services::file_guard;
+use crate::utils::path;
+
+/// 应用配置文件名（位于 `~/.mo/CCR/`）
+pub const APP_CONFIG_FILE: &str = "app-config.json";
+
+/// 串行化读-改-写，避免同时修改不同字段时互相覆盖
+static WRITE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// 配置文件路径：`~/.mo/CCR/app-config.json`
+///
+/// # 错误
+/// 无法确定用户主目录时返回错误
+pub fn config_path() -> Result<PathBuf, String> {
+    Ok(path::get_ccr_config_path()?.join(APP_CONFIG_FILE))
+}
+
+/// 读取配置中调用方关心的部分（文件不存在或解析失败时为默认值）
+///
+/// 同步读取，供启动时的 `LazyLock` 初始化使用。
+pub fn load<T: DeserializeOwned + Default>() -> T {
+    config_path()
+        .ok()
+        .and_then(|file_path| std::fs::read_to_string(file_path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// 设置一个顶层字段（保留文件中的其他字段）
+///
+/// # 参数
+/// - `key` - 字段名（如 `displayTimezone`）
+/// - `value` - 字段值
+///
+/// # 错误
+/// 现有配置文件不是合法 JSON 对象（此时不修改文件）、目录创建或写入失败时返回错误
+pub async fn set_field(key: &str, value: serde_json::Value) -> Result<(), String> {
+    let file_path = config_path()?;
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("创建 CCR 配置目录失败: {}", e))?;
+    }
+    set_field_in(&file_path, key, value).await
+}
+
+/// `set_field` 的实现，配置文件路径由调用方给出
+async fn set_field_in(file_path: &Path, key: &str, value: serde_json::Value) -> Result<(), String> {
+    let _guard = WRITE_LOCK.lock().await;
+    let mut config = match tokio::fs::read_to_string(file_path).await {
+        Ok(content) => serde_json::from_str::<serde_json::Value>(&content)
+            .map_err(|e| format!("{} 不是合法的 JSON，未修改: {}", APP_CONFIG_FILE, e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => serde_json::json!({}),
+        Err(e) => return Err(format!("读取应用配置文件失败: {}", e)),
+    };
+    config
+        .as_object_mut()
+        .ok_or_else(|| format!("{} 不是 JSON 对象，未修改", APP_CONFIG_FILE))?
+        .insert(key.to_string(), value);
+
+    let content = serde_json::to_string_pretty(&config).map_err(|e| format!("序列化应用配置失败: {}", e))?;
+    file_guard::write_atomic(&file_path.to_string_lossy(), content.as_bytes())
+        .await
+        .map_err(|e| format!("写入应用配置文件失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_set_field_keeps_other_fields() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        let file_path = dir.join(APP_CONFIG_FILE);
+
+        // 文件不存在时新建
+        set_field_in(&file_path, "displayTimezone", serde_json::json!("utc")).await.unwrap();
+        std::fs::write(&file_path, r#"{ "enableBenchmark": true, "displayTimezone": "utc" }"#).unwrap();
+
+        set_field_in(&file_path, "displayTimezone", serde_json::json!("+08:00")).await.unwrap();
+        let config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&file_path).unwrap()).unwrap();
+        assert_eq!(config, serde_json::json!({ "enableBenchmark": true, "displayTimezone": "+08:00" }));
+
+        // 不是 JSON 对象时拒绝修改，原内容保留
+        std::fs::write(&file_path, "[1, 2]").unwrap();
+        assert!(set_field_in(&file_path, "displayTimezone", serde_json::json!("utc")).await.is_err());
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "[1, 2]");
+    }
+}
@@ -0,0 +1,134 @@
+//! # 磁盘空间工具函数
+//!
+//! 查询路径所在分区的剩余空间，供写入前的空间预检使用：
+//! - Unix（Linux / macOS）：`statvfs`
+//! - Windows：`GetDiskFreeSpaceExW`
+//!
+//! 查询失败（不支持的平台、路径无法访问等）时返回 None，调用方不应因此阻止写入。
+
+use std::path::Path;
+
+/// 获取路径所在分区对当前用户可用的剩余空间（字节）
+///
+/// 路径不存在时（如即将新建的文件）沿父目录向上查找第一个存在的祖先目录。
+///
+/// # 参数
+/// - `path` - 文件或目录路径
+///
+/// # 返回值
+/// 剩余空间字节数；查询失败时返回 None
+pub fn available_space(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|p| !p.as_os_str().is_empty() && p.exists())?;
+    query_available(existing)
+}
+
+/// 检查路径所在分区是否至少有 `required` 字节的剩余空间
+///
+/// `statvfs` / `GetDiskFreeSpaceExW` 在挂起的网络盘上可能长时间阻塞，查询放到阻塞线程池执行，
+/// 不占用异步运行时的工作线程。
+///
+/// # 参数
+/// - `path` - 即将写入的文件路径
+/// - `required` - 需要的空间（字节）
+///
+/// # 错误
+/// 剩余空间不足时返回包含所需空间与当前剩余空间的错误信息；
+/// 无法查询剩余空间时视为通过
+pub async fn ensure_free_space(path: &Path, required: u64) -> Result<(), String> {
+    let target = path.to_path_buf();
+    let free = tokio::task::spawn_blocking(move || available_space(&target))
+        .await
+        .ok()
+        .flatten();
+    match free {
+        Some(free) if free < required => Err(format!(
+            "磁盘空间不足: 需要至少 {}，当前剩余 {}",
+            format_bytes(required),
+            format_bytes(free)
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// 将字节数格式化为人类可读的大小
+pub fn format_bytes(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    const MB: f64 = KB * 1024.0;
+    const GB: f64 = MB * 1024.0;
+    let value = bytes as f64;
+    if value >= GB {
+        format!("{:.1} GB", value / GB)
+    } else if value >= MB {
+        format!("{:.1} MB", value / MB)
+    } else if value >= KB {
+        format!("{:.1} KB", value / KB)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+#[cfg(unix)]
+fn query_available(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: statvfs 为纯 C 结构体，全零是合法初始值
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path 为 NUL 结尾的有效字符串，stat 指向可写的 statvfs 结构
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // 字段位宽随平台不同（macOS 的 f_bavail 为 u32），统一转为 u64
+    #[allow(clippy::unnecessary_cast)]
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+fn query_available(path: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut free: u64 = 0;
+    // SAFETY: wide 为 NUL 结尾的 UTF-16 路径，不需要的输出参数传空指针
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut free,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    (ok != 0).then_some(free)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn query_available(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(300 * 1024 * 1024), "300.0 MB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024 / 2), "1.5 GB");
+    }
+
+    #[tokio::test]
+    async fn test_available_space_and_ensure() {
+        // 不存在的文件沿父目录查找
+        let missing = std::env::temp_dir().join("ccr-disk-test").join("not-exists.jsonl");
+        let free = available_space(&missing).expect("TEMP 分区剩余空间可查询");
+        assert!(free > 0);
+
+        assert!(ensure_free_space(&missing, 0).await.is_ok());
+        let err = ensure_free_space(&missing, u64::MAX).await.unwrap_err();
+        assert!(err.starts_with("磁盘空间不足"));
+    }
+}
@@ -0,0 +1,188 @@
+//! # 会话文件编码探测
+//!
+//! Claude Code 始终以 UTF-8 写入会话，但被第三方工具处理过的文件偶尔会变成 UTF-16 或 GBK，
+//! 直接按 UTF-8 读取会失败。`decode_bytes` 依次尝试：
+//! 1. **UTF-8**：合法即采用；唯一的例外是 0 字节分布呈 UTF-16 特征的内容（见第 3 步）——
+//!    ASCII 为主的 UTF-16 同样是合法 UTF-8，需要交给后面的探测
+//! 2. **BOM**：UTF-16LE / UTF-16BE 的字节序标记
+//! 3. **无 BOM 的 UTF-16**：JSONL 几乎全是 ASCII，UTF-16 编码后奇数（LE）或偶数（BE）位置上
+//!    大量是 0 字节，比例超过 `UTF16_ZERO_RATIO` 即判定
+//! 4. **GBK**：GBK 解码器实际是 GB18030，几乎能把任意字节序列「解码」成文字，
+//!    因此严格解码后还要通过 `decode_gbk` 的合理性校验，不满足即放弃
+//!
+//! 每一步都使用不做替换的严格解码，避免把无法识别的内容悄悄变成 `\u{FFFD}`。
+//! 都失败时返回带首个非法字节偏移与十六进制片段的诊断信息。
+//! 转码结果只用于读取：非 UTF-8 的会话在显式转存为 UTF-8 之前拒绝修改（见 `parser::read_messages_for_write`）。
+
+use encoding_rs::{Encoding, GBK, UTF_16BE, UTF_16LE};
+
+/// 判定为无 BOM UTF-16 所需的 0 字节比例（相对于对应奇偶位置的字节数）
+const UTF16_ZERO_RATIO: f64 = 0.4;
+
+/// 诊断信息中展示的十六进制片段长度（字节）
+const HEX_SNIPPET_BYTES: usize = 16;
+
+/// 解码结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedText {
+    /// 解码后的文本
+    pub text: String,
+    /// 原始编码名称；文件本身就是 UTF-8 时为 None
+    pub reencoded_from: Option<&'static str>,
+}
+
+/// 将会话文件内容解码为文本
+///
+/// # 参数
+/// - `bytes` - 文件的原始字节
+///
+/// # 返回值
+/// 解码后的文本与原始编码（UTF-8 时为 None）
+///
+/// # 错误
+/// 无法识别编码时返回错误，包含首个非法 UTF-8 字节的偏移与附近的十六进制片段
+pub fn decode_bytes(bytes: Vec<u8>) -> Result<DecodedText, String> {
+    // 合法 JSONL 不含 0 字节（JSON 会转义控制字符），绝大多数文件在这里直接返回
+    let bytes = match String::from_utf8(bytes) {
+        Ok(text) if memchr::memchr(0, text.as_bytes()).is_none() || guess_utf16(text.as_bytes()).is_none() => {
+            return Ok(DecodedText { text, reencoded_from: None });
+        }
+        Ok(text) => text.into_bytes(),
+        Err(e) => e.into_bytes(),
+    };
+
+    if let Some((encoding, bom_len)) = Encoding::for_bom(&bytes)
+        && encoding != encoding_rs::UTF_8
+        && let Some(decoded) = decode_strict(encoding, &bytes[bom_len..])
+    {
+        return Ok(decoded);
+    }
+    if let Some(encoding) = guess_utf16(&bytes)
+        && let Some(decoded) = decode_strict(encoding, &bytes)
+    {
+        return Ok(decoded);
+    }
+    if let Ok(text) = std::str::from_utf8(&bytes) {
+        return Ok(DecodedText { text: text.to_string(), reencoded_from: None });
+    }
+    if let Some(decoded) = decode_gbk(&bytes) {
+        return Ok(decoded);
+    }
+
+    Err(undecodable_message(&bytes))
+}
+
+/// 按 GBK 解码，并校验结果确实像 GBK 编码的 JSONL
+///
+/// 严格解码之外还要求：
+/// - 解码结果按 GBK 重新编码后与原字节完全一致，排除 GB18030 独有的四字节序列
+/// - 除最后一行（可能是写到一半的行）外，每个非空行都是合法 JSON；
+///   其他编码被错位解码时通常会吞掉引号、括号等 ASCII 字符
+fn decode_gbk(bytes: &[u8]) -> Option<DecodedText> {
+    let decoded = decode_strict(GBK, bytes)?;
+    let (encoded, _, unmappable) = GBK.encode(&decoded.text);
+    if unmappable || encoded.as_ref() != bytes {
+        return None;
+    }
+    let lines: Vec<&str> = decoded.text.lines().filter(|line| !line.trim().is_empty()).collect();
+    let complete = &lines[..lines.len().saturating_sub(1)];
+    complete
+        .iter()
+        .all(|line| serde_json::from_str::<serde::de::IgnoredAny>(line).is_ok())
+        .then_some(decoded)
+}
+
+/// 以指定编码严格解码（出现非法序列时返回 None）
+fn decode_strict(encoding: &'static Encoding, bytes: &[u8]) -> Option<DecodedText> {
+    let text = encoding.decode_without_bom_handling_and_without_replacement(bytes)?;
+    Some(DecodedText {
+        text: text.into_owned(),
+        reencoded_from: Some(encoding.name()),
+    })
+}
+
+/// 根据 0 字节的分布猜测无 BOM 的 UTF-16 字节序
+fn guess_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    if bytes.len() < 2 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let half = (bytes.len() / 2) as f64;
+    let zeros_at = |parity: usize| bytes.iter().skip(parity).step_by(2).filter(|&&b| b == 0).count() as f64 / half;
+    if zeros_at(1) >= UTF16_ZERO_RATIO {
+        Some(UTF_16LE)
+    } else if zeros_at(0) >= UTF16_ZERO_RATIO {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// 生成无法识别编码时的诊断信息
+fn undecodable_message(bytes: &[u8]) -> String {
+    let offset = std::str::from_utf8(bytes).err().map_or(0, |e| e.valid_up_to());
+    let start = offset.saturating_sub(HEX_SNIPPET_BYTES / 2);
+    let end = (start + HEX_SNIPPET_BYTES).min(bytes.len());
+    let hex: Vec<String> = bytes[start..end].iter().map(|b| format!("{:02X}", b)).collect();
+    format!(
+        "无法识别会话文件编码（不是 UTF-8、UTF-16 或 GBK）：第 {} 字节附近为 [{}]（自第 {} 字节起）",
+        offset,
+        hex.join(" "),
+        start
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_bytes_detects_encodings() {
+        let line = "{\"type\":\"user\",\"message\":{\"content\":\"你好\"}}\n";
+        assert_eq!(decode_bytes(line.as_bytes().to_vec()).unwrap().reencoded_from, None);
+
+        let utf16le: Vec<u8> = line.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let mut with_bom = vec![0xFF, 0xFE];
+        with_bom.extend(&utf16le);
+        for (bytes, expected) in [(with_bom, "UTF-16LE"), (utf16le, "UTF-16LE")] {
+            let decoded = decode_bytes(bytes).unwrap();
+            assert_eq!((decoded.text.as_str(), decoded.reencoded_from), (line, Some(expected)));
+        }
+        let utf16be: Vec<u8> = line.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(decode_bytes(utf16be).unwrap().reencoded_from, Some("UTF-16BE"));
+
+        let (gbk, _, _) = GBK.encode(line);
+        let decoded = decode_bytes(gbk.into_owned()).unwrap();
+        assert_eq!((decoded.text.as_str(), decoded.reencoded_from), (line, Some("GBK")));
+
+        // 含少量 0 字节、但不呈 UTF-16 分布的合法 UTF-8 仍按 UTF-8 读取
+        let with_nul = format!("{}{}", line, "{\"type\":\"user\",\"x\":\"a\u{0}b\"}\n");
+        let decoded = decode_bytes(with_nul.clone().into_bytes()).unwrap();
+        assert_eq!((decoded.text, decoded.reencoded_from), (with_nul, None));
+    }
+
+    #[test]
+    fn test_gbk_fallback_rejects_implausible_text() {
+        let gbk = |text: &str| GBK.encode(text).0.into_owned();
+
+        // 最后一行写到一半时仍接受
+        let mut truncated = gbk("{\"message\":\"你好\"}\n{\"message\":\"半");
+        assert_eq!(decode_bytes(truncated.clone()).unwrap().reencoded_from, Some("GBK"));
+        // 完整的行不是 JSON：不是 GBK 编码的会话
+        truncated.splice(0..0, gbk("你好 不是 JSON\n"));
+        assert!(decode_bytes(truncated).is_err());
+        // GB18030 独有的四字节序列
+        let mut four_byte = b"{\"message\":\"".to_vec();
+        four_byte.extend([0x81, 0x30, 0x81, 0x30]);
+        four_byte.extend(b"\"}\n");
+        assert!(decode_bytes(four_byte).is_err());
+    }
+
+    #[test]
+    fn test_decode_bytes_reports_hex_snippet() {
+        let mut bytes = b"{\"type\":\"user\"}\n".to_vec();
+        bytes.extend([0x80, 0xFF, 0x80]);
+        let error = decode_bytes(bytes).unwrap_err();
+        assert!(error.contains("第 16 字节附近"), "{}", error);
+        assert!(error.contains("80 FF 80"), "{}", error);
+    }
+}
@@ -0,0 +1,187 @@
+//! # 文件名工具函数
+//!
+//! 以用户内容（会话标题、预览文本等）生成文件名时，需要处理：
+//! - Windows 非法字符（`<>:"/\|?*` 与控制字符）以及结尾的点和空格
+//! - Windows 保留设备名（`CON`、`PRN`、`COM1` 等，带扩展名同样保留）
+//! - 文件名长度上限（多数文件系统为 255 字节），截断时不能切断 UTF-8 字符
+//! - Windows 260 字符路径上限（`to_long_path` 加 `\\?\` 前缀绕过）
+
+use std::path::{Path, PathBuf};
+
+/// 文件名的最大字节数（NTFS / ext4 / APFS 的共同下限）
+pub const MAX_FILENAME_BYTES: usize = 255;
+
+/// 截断时保留扩展名的最大字节数（更长的「扩展名」视为正文的一部分）
+const MAX_EXTENSION_BYTES: usize = 16;
+
+/// 清理后为空时使用的文件名
+const FALLBACK_NAME: &str = "untitled";
+
+/// Windows 保留设备名（不区分大小写）
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// 把任意文本转换为跨平台安全的文件名
+///
+/// - 非法字符与控制字符替换为 `_`，连续空白合并为一个空格
+/// - 去除首尾空白，以及结尾的 `.`（Windows 会静默丢弃）
+/// - 主名为 Windows 保留设备名时加 `_` 前缀
+/// - 按 UTF-8 字节截断到 `max_len`（不超过 `MAX_FILENAME_BYTES`），尽量保留扩展名
+///
+/// # 参数
+/// - `name` - 原始文件名（可含扩展名）
+/// - `max_len` - 最大字节数
+///
+/// # 返回值
+/// 清理后的文件名；清理后为空时返回 `untitled`
+pub fn sanitize_filename(name: &str, max_len: usize) -> String {
+    let max_len = max_len.clamp(1, MAX_FILENAME_BYTES);
+
+    let mut cleaned = String::with_capacity(name.len());
+    for c in name.chars() {
+        let c = match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_whitespace() => ' ',
+            c if c.is_control() => '_',
+            c => c,
+        };
+        // 合并连续空白
+        if c == ' ' && cleaned.ends_with(' ') {
+            continue;
+        }
+        cleaned.push(c);
+    }
+    let mut cleaned = trim_name(&cleaned).to_string();
+    if cleaned.is_empty() {
+        cleaned = FALLBACK_NAME.to_string();
+    }
+
+    let stem = cleaned.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem.trim_end())) {
+        cleaned.insert(0, '_');
+    }
+
+    if cleaned.len() <= max_len {
+        return cleaned;
+    }
+
+    // 超长：截断主名，保留较短的扩展名
+    let (stem, extension) = match cleaned.rfind('.') {
+        Some(idx) if idx > 0 && cleaned.len() - idx <= MAX_EXTENSION_BYTES.min(max_len / 2) => {
+            cleaned.split_at(idx)
+        }
+        _ => (cleaned.as_str(), ""),
+    };
+    let stem = trim_name(truncate_utf8(stem, max_len - extension.len()));
+    let stem = if stem.is_empty() { FALLBACK_NAME } else { stem };
+    format!("{}{}", stem, extension)
+}
+
+/// 把绝对路径转换为 Windows 扩展长度路径（`\\?\C:\...` / `\\?\UNC\server\share\...`）
+///
+/// 非 Windows 平台、相对路径或已带前缀的路径原样返回。
+///
+/// # 参数
+/// - `path` - 文件路径
+///
+/// # 返回值
+/// 可超过 260 字符限制的路径
+pub fn to_long_path(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    if !cfg!(target_os = "windows") {
+        return path.to_path_buf();
+    }
+    match path.to_str() {
+        Some(s) => PathBuf::from(windows_long_path(s)),
+        None => path.to_path_buf(),
+    }
+}
+
+/// `to_long_path` 的字符串实现（与平台无关，便于测试）
+fn windows_long_path(path: &str) -> String {
+    if path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return path.to_string();
+    }
+    // 扩展长度路径不做 `/` → `\` 转换，需要预先统一
+    let unified = path.replace('/', "\\");
+    if let Some(unc) = unified.strip_prefix(r"\\") {
+        return format!(r"\\?\UNC\{}", unc);
+    }
+    let bytes = unified.as_bytes();
+    let is_drive_absolute =
+        bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\';
+    if is_drive_absolute {
+        format!(r"\\?\{}", unified)
+    } else {
+        path.to_string()
+    }
+}
+
+/// 去除首尾空白与结尾的 `.`
+fn trim_name(name: &str) -> &str {
+    name.trim().trim_end_matches(['.', ' '])
+}
+
+/// 按字节截断到不超过 `max_bytes`，不切断 UTF-8 字符
+fn truncate_utf8(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+    let mut end = max_bytes;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_filename_characters_and_reserved_names() {
+        assert_eq!(sanitize_filename("修复: a/b\\c <d>?*|\"e\".md", 255), "修复_ a_b_c _d_____e_.md");
+        assert_eq!(sanitize_filename("  标题\n\t换行  ", 255), "标题 换行");
+        assert_eq!(sanitize_filename("结尾的点...", 255), "结尾的点");
+        assert_eq!(sanitize_filename("🎉 发布", 255), "🎉 发布");
+        assert_eq!(sanitize_filename("...", 255), "untitled");
+
+        // 保留名不区分大小写，带扩展名同样保留
+        assert_eq!(sanitize_filename("CON", 255), "_CON");
+        assert_eq!(sanitize_filename("nul.md", 255), "_nul.md");
+        assert_eq!(sanitize_filename("Com1.tar.gz", 255), "_Com1.tar.gz");
+        assert_eq!(sanitize_filename("lpt9 .txt", 255), "_lpt9 .txt");
+        assert_eq!(sanitize_filename("CONSOLE.md", 255), "CONSOLE.md");
+        assert_eq!(sanitize_filename("COM10", 255), "COM10");
+    }
+
+    #[test]
+    fn test_sanitize_filename_utf8_boundary() {
+        // 300 字节的中文主名：截断到 255 字节以内，保留扩展名，不切断字符
+        let name = format!("{}.md", "中".repeat(100));
+        let result = sanitize_filename(&name, MAX_FILENAME_BYTES);
+        assert!(result.len() <= MAX_FILENAME_BYTES);
+        assert!(result.ends_with(".md"));
+        assert_eq!(result, format!("{}.md", "中".repeat(84)));
+
+        // 4 字节表情恰好跨越边界时整体丢弃
+        let emoji = format!("a{}", "😀".repeat(70));
+        let result = sanitize_filename(&emoji, MAX_FILENAME_BYTES);
+        assert_eq!(result, format!("a{}", "😀".repeat(63)));
+        assert_eq!(result.len(), 253);
+
+        // max_len 超过上限时按上限截断
+        assert_eq!(sanitize_filename(&"x".repeat(400), 1000).len(), MAX_FILENAME_BYTES);
+    }
+
+    #[test]
+    fn test_windows_long_path() {
+        assert_eq!(windows_long_path(r"C:\Users\a\导出.md"), r"\\?\C:\Users\a\导出.md");
+        assert_eq!(windows_long_path("D:/exports/a.md"), r"\\?\D:\exports\a.md");
+        assert_eq!(windows_long_path(r"\\server\share\a.md"), r"\\?\UNC\server\share\a.md");
+        assert_eq!(windows_long_path(r"\\?\C:\already"), r"\\?\C:\already");
+        assert_eq!(windows_long_path(r"relative\a.md"), r"relative\a.md");
+    }
+}
@@ -0,0 +1,161 @@
+//! # JSONC 宽容解析
+//!
+//! 部分用户的 settings.json 复制自带注释的示例（JSONC），严格的 `serde_json` 解析会失败。
+//! 本模块在严格解析失败时退回容错路径：剥离 `//` 与 `/* */` 注释、去掉尾逗号后再解析。
+//! 预处理逐字符跟踪字符串字面量（含转义），字符串内的 `//`、`/*`、`,]` 等原样保留。
+//!
+//! 保存时只能输出标准 JSON，注释会丢失；写入前用 `ensure_no_comments_lost` 检查，
+//! 未经用户确认时返回以 `COMMENTS_LOST_ERROR` 开头的错误，前端据此弹出确认。
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+
+/// 覆盖宽容解析得到的文件会丢失注释时的错误前缀
+pub const COMMENTS_LOST_ERROR: &str = "CommentsWillBeLost";
+
+/// 剥离 JSONC 的注释与尾逗号，得到标准 JSON 文本
+///
+/// 注释替换为等长空白（保留换行），解析错误的行列号仍与原文件一致。
+pub fn strip_jsonc(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    // 最近一个之后只出现过空白 / 注释的逗号在 out 中的位置
+    let mut pending_comma: Option<usize> = None;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        out.push(escaped);
+                    }
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                // 行注释：吞掉到行尾（换行本身保留）
+                while let Some(&next) = chars.peek() {
+                    if next == '\n' {
+                        break;
+                    }
+                    chars.next();
+                    out.push(' ');
+                }
+                out.push(' ');
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                out.push_str("  ");
+                let mut prev = '\0';
+                for next in chars.by_ref() {
+                    out.push(if next == '\n' { '\n' } else { ' ' });
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            _ if c.is_whitespace() => out.push(c),
+            _ => {
+                if matches!(c, '}' | ']')
+                    && let Some(index) = pending_comma
+                {
+                    out.replace_range(index..index + 1, " ");
+                }
+                pending_comma = (c == ',').then_some(out.len());
+                in_string = c == '"';
+                out.push(c);
+            }
+        }
+    }
+    out
+}
+
+/// 先严格解析，失败时退回 JSONC 容错解析
+///
+/// # 返回值
+/// `(解析结果, 是否经过容错解析)`
+///
+/// # 错误
+/// 两种方式都失败时返回严格解析的错误信息（更贴近原文件）
+pub fn parse_lenient<T: DeserializeOwned>(content: &str) -> Result<(T, bool), String> {
+    match serde_json::from_str(content) {
+        Ok(value) => Ok((value, false)),
+        Err(strict_error) => serde_json::from_str(&strip_jsonc(content))
+            .map(|value| (value, true))
+            .map_err(|_| strict_error.to_string()),
+    }
+}
+
+/// 写入前检查：现有文件只能宽容解析（含注释或尾逗号）且未经确认时拒绝覆盖
+///
+/// 文件不存在、是标准 JSON，或连宽容解析也失败（已损坏，覆盖不会损失可用内容）时放行。
+///
+/// # 参数
+/// - `file_path` - 即将覆盖的文件
+/// - `discard_comments` - 用户是否已确认丢弃注释
+///
+/// # 错误
+/// 需要确认时返回 `CommentsWillBeLost: ...` 错误
+pub async fn ensure_no_comments_lost(file_path: &Path, discard_comments: bool) -> Result<(), String> {
+    if discard_comments {
+        return Ok(());
+    }
+    let Ok(content) = tokio::fs::read_to_string(file_path).await else {
+        return Ok(());
+    };
+    if matches!(parse_lenient::<serde_json::Value>(&content), Ok((_, true))) {
+        return Err(format!(
+            "{}: {} 含有注释或尾逗号，保存为标准 JSON 后这些内容将丢失，请确认后重试",
+            COMMENTS_LOST_ERROR,
+            file_path.display()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    #[test]
+    fn test_strip_jsonc_keeps_string_literals() {
+        let content = r#"{
+  // 模型
+  "model": "opus", /* 行内
+  块注释 */
+  "url": "https://example.com//a/*b*/",
+  "quote": "say \"hi\" // not a comment",
+  "list": [1, 2, "x,]",],
+}"#;
+        let (value, lenient) = parse_lenient::<Value>(content).unwrap();
+        assert!(lenient);
+        assert_eq!(
+            value,
+            json!({
+                "model": "opus",
+                "url": "https://example.com//a/*b*/",
+                "quote": "say \"hi\" // not a comment",
+                "list": [1, 2, "x,]"]
+            })
+        );
+        // 注释替换为空白，行数不变
+        assert_eq!(strip_jsonc(content).lines().count(), content.lines().count());
+    }
+
+    #[test]
+    fn test_parse_lenient_strict_and_invalid() {
+        assert_eq!(parse_lenient::<Value>(r#"{"a": 1}"#).unwrap(), (json!({"a": 1}), false));
+        assert!(parse_lenient::<Value>("{\"a\": }").is_err());
+        assert!(parse_lenient::<Value>("[1,,]").is_err());
+    }
+}
@@ -1,6 +1,20 @@
 //! # 工具函数模块
 //!
 //! 包含跨模块共享的通用工具函数：
+//! - `app_config` - 应用配置文件 `app-config.json` 的按字段读取与加锁原子写入
+//! - `disk` - 磁盘剩余空间查询与写入前的空间预检
+//! - `encoding` - 会话文件编码探测：UTF-8 失败时按 BOM / UTF-16 / GBK 转码
+//! - `filename` - 以用户内容生成文件名时的非法字符清理、长度截断与 Windows 长路径处理
+//! - `jsonc` - JSONC 宽容解析：剥离注释与尾逗号，覆盖前检查注释是否会丢失
 //! - `path` - 路径解码、主目录获取等文件路径相关工具
+//! - `platform` - 平台能力分层：移动端的主目录 / 临时目录替代与不支持功能的错误码
+//! - `time` - ISO 8601 时间戳解析
 
+pub mod app_config;
+pub mod disk;
+pub mod encoding;
+pub mod filename;
+pub mod jsonc;
 pub mod path;
+pub mod platform;
+pub mod time;
@@ -2,18 +2,75 @@
 //!
 //! 提供与文件路径相关的工具函数，包括：
 //! - 获取 Claude Code 数据目录路径（`~/.claude/`）
-//! - 解码编码后的项目目录名为原始文件系统路径
+//! - 解码编码后的项目目录名为原始文件系统路径（或由会话 cwd 确定真实路径），以及反向的路径编码
+//! - 识别 WSL 中 Claude Code 的 `/mnt/<盘符>/...` 项目，按配置输出原生或 `\\wsl$\` 路径
 //! - 获取 CCR 自身配置目录路径（`~/.mo/CCR/`）
+//! - 在 PATH 中查找可执行文件
 
+#[cfg(any(mobile, test))]
+use std::path::{Component, Path};
 use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{app_config, platform};
+
+/// WSL 挂载路径的输出风格，启动时从 `app-config.json` 读取一次
+static WSL_PATH_STYLE: LazyLock<WslPathStyle> = LazyLock::new(load_wsl_path_style);
+
+/// 移动端 Claude 数据目录（相对应用沙箱），启动时从 `app-config.json` 读取一次
+#[cfg(mobile)]
+static MOBILE_CLAUDE_DIR: LazyLock<Option<String>> =
+    LazyLock::new(|| app_config::load::<AppConfig>().mobile_claude_dir);
+
+/// 移动端 Claude 数据目录的默认值（相对应用沙箱）
+const DEFAULT_CLAUDE_DIR: &str = ".claude";
+
+/// WSL 中 Claude Code 项目（目录名形如 `-mnt-d-projects-foo`）的解码输出风格
+///
+/// 在 `~/.mo/CCR/app-config.json` 中配置，修改后重启生效：
+/// ```json
+/// { "wslPathStyle": { "style": "unc", "distro": "Ubuntu" } }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "style", rename_all = "camelCase")]
+pub enum WslPathStyle {
+    /// Linux 原生路径 `/mnt/d/projects/foo`（默认）
+    #[default]
+    Native,
+    /// Windows 可直接访问的 UNC 路径 `\\wsl$\<distro>\mnt\d\projects\foo`
+    Unc {
+        /// WSL 发行版名称（如 `Ubuntu`）
+        distro: String,
+    },
+}
+
+/// 应用配置中与路径解码相关的部分
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppConfig {
+    /// WSL 挂载路径的输出风格
+    #[serde(default)]
+    wsl_path_style: WslPathStyle,
+    /// 移动端 Claude 数据目录（相对应用沙箱的子路径，默认 `.claude`）
+    #[cfg_attr(not(mobile), allow(dead_code))]
+    #[serde(default)]
+    mobile_claude_dir: Option<String>,
+}
 
 /// 获取 Claude Code 数据目录的绝对路径
 ///
 /// Claude Code 将所有用户数据存储在用户主目录下的 `.claude` 文件夹中。
-/// 使用 `dirs` crate 获取跨平台的主目录路径。
+/// 主目录由 `platform::home_dir` 获取：桌面端为用户主目录，移动端为应用沙箱数据目录。
+/// 移动端的子目录可在 `app-config.json` 中以 `mobileClaudeDir` 配置（修改后重启生效），
+/// 用于对接同步工具放置数据的位置：
+/// ```json
+/// { "mobileClaudeDir": "Sync/claude" }
+/// ```
 ///
 /// # 返回值
-/// 返回 `~/.claude/` 目录的绝对路径。
+/// 返回 `~/.claude/` 目录（移动端为 `<沙箱>/<mobileClaudeDir>`）的绝对路径。
 ///
 /// # 错误
 /// 如果无法确定用户主目录（极端情况，如无 HOME 环境变量），返回错误信息。
@@ -22,8 +79,39 @@ use std::path::PathBuf;
 /// - Windows: `C:\Users\username\.claude`
 /// - Linux/macOS: `/home/username/.claude`
 pub fn get_claude_data_path() -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or_else(|| "无法获取用户主目录".to_string())?;
-    Ok(home.join(".claude"))
+    let home = platform::home_dir().ok_or_else(|| "无法获取用户主目录".to_string())?;
+    #[cfg(mobile)]
+    let dir = sandbox_claude_dir(&home, MOBILE_CLAUDE_DIR.as_deref());
+    #[cfg(not(mobile))]
+    let dir = home.join(DEFAULT_CLAUDE_DIR);
+    Ok(dir)
+}
+
+/// 拼接沙箱内的 Claude 数据目录
+///
+/// 配置值必须是不含 `..` 的相对路径，保证目录留在沙箱内；
+/// 未配置、为空或不合法时回退到 `.claude`（不合法时记录警告）。
+///
+/// # 参数
+/// - `home` - 应用沙箱数据目录
+/// - `configured` - 配置的子路径（`mobileClaudeDir`）
+#[cfg(any(mobile, test))]
+fn sandbox_claude_dir(home: &Path, configured: Option<&str>) -> PathBuf {
+    let relative = match configured.map(str::trim).filter(|dir| !dir.is_empty()) {
+        Some(dir)
+            if Path::new(dir)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) =>
+        {
+            dir
+        }
+        Some(dir) => {
+            log::warn!("mobileClaudeDir 必须是沙箱内的相对路径，已忽略: {}", dir);
+            DEFAULT_CLAUDE_DIR
+        }
+        None => DEFAULT_CLAUDE_DIR,
+    };
+    home.join(relative)
 }
 
 /// 获取 CCR 自身配置目录的绝对路径
@@ -37,12 +125,51 @@ pub fn get_claude_data_path() -> Result<PathBuf, String> {
 /// # 错误
 /// 如果无法确定用户主目录，返回错误信息。
 pub fn get_ccr_config_path() -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or_else(|| "无法获取用户主目录".to_string())?;
+    let home = platform::home_dir().ok_or_else(|| "无法获取用户主目录".to_string())?;
     Ok(home.join(".mo").join("CCR"))
 }
 
+/// 同步检查程序是否存在于 PATH 中
+///
+/// 模拟 Python 的 `shutil.which()` 功能，
+/// 遍历 PATH 环境变量中的所有目录查找可执行文件。
+///
+/// # 参数
+/// - `program` — 程序名（如 "npm"、"pnpm"、"claude"）
+///
+/// # 返回值
+/// 找到时返回可执行文件的绝对路径
+pub fn which(program: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH")?;
+
+    // Windows 上需要检查带扩展名的版本
+    let extensions: Vec<String> = if cfg!(target_os = "windows") {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string())
+            .split(';')
+            .map(|s| s.to_lowercase())
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+
+    for dir in std::env::split_paths(&path_var) {
+        for ext in &extensions {
+            let candidate = dir.join(format!("{}{}", program, ext));
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+    }
+
+    None
+}
+
 /// 将编码的项目目录名解码为原始文件系统路径
 ///
+/// 以 `-mnt-<盘符>` 开头的目录名来自 WSL 中的 Claude Code，按 `app-config.json` 中的
+/// `wslPathStyle` 输出（见 `decode_project_path_with`）；其余目录名按下述规则解码。
+///
 /// Claude Code 在 `~/.claude/projects/` 目录下使用编码后的路径作为子目录名，
 /// 将路径分隔符和驱动器号替换为短横线，以适应文件系统命名限制。
 ///
@@ -71,6 +198,26 @@ pub fn get_ccr_config_path() -> Result<PathBuf, String> {
 /// assert_eq!(decoded, r"G:\ClaudeProjects\Test");
 /// ```
 pub fn decode_project_path(encoded_name: &str) -> String {
+    decode_project_path_with(encoded_name, &WSL_PATH_STYLE)
+}
+
+/// 按指定的 WSL 路径风格解码项目目录名
+///
+/// # 参数
+/// - `encoded_name` - 编码后的项目目录名
+/// - `wsl_style` - `-mnt-<盘符>` 目录名的输出风格
+///
+/// # 返回值
+/// WSL 挂载目录名解码为 `/mnt/d/...` 或 `\\wsl$\<distro>\mnt\d\...`；
+/// 其余目录名解码为本机路径
+pub fn decode_project_path_with(encoded_name: &str, wsl_style: &WslPathStyle) -> String {
+    if let Some(native) = decode_wsl_mount(encoded_name) {
+        return match wsl_style {
+            WslPathStyle::Native => native,
+            WslPathStyle::Unc { distro } => wsl_unc_path(&native, distro),
+        };
+    }
+
     // 使用与前端 TypeScript 相同的解码逻辑
     let separator = std::path::MAIN_SEPARATOR.to_string();
 
@@ -97,10 +244,116 @@ pub fn decode_project_path(encoded_name: &str) -> String {
     }
 }
 
+/// 识别 WSL 挂载目录名（`-mnt-<盘符>` 后接 `-` 或结尾）并解码为 Linux 原生路径
+///
+/// Windows 上按 `MAIN_SEPARATOR` 解码会得到 `\mnt\d\...`，既不是 Windows 路径也不是 Linux 路径，
+/// 因此这类目录名始终以 `/` 为分隔符解码。
+fn decode_wsl_mount(encoded_name: &str) -> Option<String> {
+    let rest = encoded_name.strip_prefix("-mnt-")?;
+    let mut chars = rest.chars();
+    if !chars.next()?.is_ascii_alphabetic() || !matches!(chars.next(), None | Some('-')) {
+        return None;
+    }
+    Some(encoded_name.replace("--", "\x00").replace(['-', '\x00'], "/"))
+}
+
+/// 把 WSL 内的 Linux 路径转换为 Windows 可访问的 UNC 路径
+///
+/// # 参数
+/// - `linux_path` - 以 `/` 开头的 Linux 绝对路径
+/// - `distro` - WSL 发行版名称
+///
+/// # 返回值
+/// `\\wsl$\<distro>\...` 形式的路径
+pub fn wsl_unc_path(linux_path: &str, distro: &str) -> String {
+    format!(r"\\wsl$\{}{}", distro, linux_path.replace('/', "\\"))
+}
+
+/// 识别需要在 WSL 中打开的路径
+///
+/// 支持 `/mnt/<盘符>[/...]` 原生路径，以及 `\\wsl$\<distro>\...`、`\\wsl.localhost\<distro>\...` UNC 路径。
+///
+/// # 参数
+/// - `path` - 项目路径
+///
+/// # 返回值
+/// `(发行版, Linux 路径)`：原生路径的发行版为 None（使用默认发行版）；不是 WSL 路径时返回 None
+pub fn wsl_linux_path(path: &str) -> Option<(Option<String>, String)> {
+    if let Some(rest) = path.strip_prefix("/mnt/") {
+        let mut chars = rest.chars();
+        return (chars.next()?.is_ascii_alphabetic() && matches!(chars.next(), None | Some('/')))
+            .then(|| (None, path.to_string()));
+    }
+
+    let lower = path.to_ascii_lowercase();
+    let prefix_len = [r"\\wsl$\", r"\\wsl.localhost\"]
+        .into_iter()
+        .find(|prefix| lower.starts_with(prefix))?
+        .len();
+    let rest = &path[prefix_len..];
+    let (distro, linux) = rest.split_once('\\').unwrap_or((rest, ""));
+    if distro.is_empty() {
+        return None;
+    }
+    Some((Some(distro.to_string()), format!("/{}", linux.replace('\\', "/"))))
+}
+
+/// 从 `app-config.json` 读取 WSL 路径风格（文件不存在或解析失败时为原生路径）
+fn load_wsl_path_style() -> WslPathStyle {
+    app_config::load::<AppConfig>().wsl_path_style
+}
+
+/// 将文件系统路径编码为项目目录名（与 Claude Code 的编码规则一致）
+///
+/// Claude Code 把路径中所有非 ASCII 字母数字的字符（分隔符、盘符冒号、`.`、`_`、空格、
+/// 非 ASCII 字符等）都替换为 `-`。该编码不可逆（`decode_project_path` 只能近似还原），
+/// 需要判断某个 cwd 是否属于某个项目目录时，应比较编码结果而不是解码结果。
+///
+/// # 参数
+/// - `path` - 原始文件系统路径（如 `G:\ClaudeProjects\Test`）
+///
+/// # 返回值
+/// 编码后的目录名（如 "G--ClaudeProjects-Test"）
+pub fn encode_project_path(path: &str) -> String {
+    path.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// 由会话记录的工作目录确定项目的真实路径
+///
+/// 编码中 `-` 同时代表分隔符、`_`、`.`、空格、连字符本身和非 ASCII 字符，`decode_project_path`
+/// 无法区分（如 `D--my-app-frontend` 会被解码成 `D:\my\app\frontend`）。会话 JSONL 的消息带有
+/// 启动时的 `cwd`，编码后等于目录名的 cwd 就是权威路径；都不匹配（会话中途切换过目录、
+/// 没有会话）时回退到解码结果。
+///
+/// # 参数
+/// - `encoded_name` - 编码后的项目目录名
+/// - `cwds` - 该项目各会话记录的工作目录
+///
+/// # 返回值
+/// 第一个编码后等于目录名的 cwd；没有时为 `decode_project_path` 的结果
+pub fn resolve_project_path<'a>(encoded_name: &str, cwds: impl IntoIterator<Item = &'a str>) -> String {
+    cwds.into_iter()
+        .find(|cwd| encode_project_path(cwd) == encoded_name)
+        .map_or_else(|| decode_project_path(encoded_name), str::to_string)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sandbox_claude_dir() {
+        let home = Path::new("/sandbox");
+        assert_eq!(sandbox_claude_dir(home, None), home.join(".claude"));
+        assert_eq!(sandbox_claude_dir(home, Some("  ")), home.join(".claude"));
+        assert_eq!(sandbox_claude_dir(home, Some("Sync/claude")), home.join("Sync/claude"));
+        // 逃出沙箱的配置被忽略
+        assert_eq!(sandbox_claude_dir(home, Some("../claude")), home.join(".claude"));
+        assert_eq!(sandbox_claude_dir(home, Some("/data/claude")), home.join(".claude"));
+    }
+
     #[test]
     fn test_decode_project_path_windows() {
         // Windows 风格路径解码测试
@@ -116,4 +369,59 @@ mod tests {
         let expected = format!("home{sep}user{sep}projects{sep}myapp");
         assert_eq!(decoded, expected);
     }
+
+    #[test]
+    fn test_encode_project_path() {
+        assert_eq!(encode_project_path(r"G:\ClaudeProjects\Test"), "G--ClaudeProjects-Test");
+        assert_eq!(encode_project_path("/home/u/my_app.v2"), "-home-u-my-app-v2");
+        assert_eq!(encode_project_path("/home/u/项目"), "-home-u---");
+    }
+
+    #[test]
+    fn test_resolve_project_path_prefers_matching_cwd() {
+        // 连字符、下划线、中文目录名都无法从编码还原，以 cwd 为准
+        for cwd in [r"D:\my-app\frontend", "/home/u/my_app.v2", "/home/u/项目/前端"] {
+            let name = encode_project_path(cwd);
+            assert_eq!(resolve_project_path(&name, [cwd]), cwd);
+        }
+
+        // 中途 cd 到子目录的会话不能作为项目路径，跳过后取下一个匹配的
+        let name = encode_project_path("/w/my-app");
+        assert_eq!(resolve_project_path(&name, ["/w/my-app/src", "/w/my-app"]), "/w/my-app");
+
+        // 没有匹配的 cwd 时回退到解码结果
+        assert_eq!(resolve_project_path(&name, ["/w/other"]), decode_project_path(&name));
+        assert_eq!(resolve_project_path(&name, []), decode_project_path(&name));
+    }
+
+    #[test]
+    fn test_decode_wsl_mount_project_path() {
+        let name = "-mnt-d-projects-foo";
+        assert_eq!(decode_project_path_with(name, &WslPathStyle::Native), "/mnt/d/projects/foo");
+        let unc = WslPathStyle::Unc { distro: "Ubuntu".to_string() };
+        assert_eq!(decode_project_path_with(name, &unc), r"\\wsl$\Ubuntu\mnt\d\projects\foo");
+        assert_eq!(decode_project_path_with("-mnt-c", &unc), r"\\wsl$\Ubuntu\mnt\c");
+
+        // 不是 `-mnt-<盘符>` 前缀的目录名不受 WSL 风格影响
+        for name in ["-mnt-data-foo", "-home-u-app", "G--ClaudeProjects-Test"] {
+            assert_eq!(decode_project_path_with(name, &unc), decode_project_path_with(name, &WslPathStyle::Native));
+        }
+    }
+
+    #[test]
+    fn test_wsl_linux_path() {
+        assert_eq!(wsl_linux_path("/mnt/d/projects/foo"), Some((None, "/mnt/d/projects/foo".to_string())));
+        assert_eq!(
+            wsl_linux_path(r"\\wsl$\Ubuntu\mnt\d\projects\foo"),
+            Some((Some("Ubuntu".to_string()), "/mnt/d/projects/foo".to_string()))
+        );
+        assert_eq!(
+            wsl_linux_path(r"\\WSL.localhost\Debian\home\u"),
+            Some((Some("Debian".to_string()), "/home/u".to_string()))
+        );
+
+        for native in [r"D:\projects\foo", "/mnt/data/foo", "/home/u/app", r"\\server\share"] {
+            assert_eq!(wsl_linux_path(native), None, "{}", native);
+        }
+    }
 }
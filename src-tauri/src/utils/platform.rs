@@ -0,0 +1,111 @@
+//! # 平台能力分层
+//!
+//! 桌面端与移动端（Android / iOS，编译期 `cfg(mobile)`）共用同一套 command，但移动端：
+//! - **没有用户主目录**：`home_dir` 返回应用沙箱内的数据目录（`setup` 中由 `init_mobile_dirs` 注入），
+//!   Claude 数据目录默认位于 `<沙箱>/.claude/`（可由 `app-config.json` 的 `mobileClaudeDir` 改为沙箱内其他子目录），
+//!   用于查看从桌面端同步过来的数据；其他位置的数据仍可通过数据源（`services::data_sources`）添加
+//! - **没有可用的系统 TEMP**：`temp_dir` 返回应用缓存目录，临时备份、导出文件等随之迁移
+//! - **不能启动外部程序**：终端、文件管理器、独立窗口等 command 经 `require_desktop` 返回以 `UNSUPPORTED_ERROR`
+//!   开头的错误，前端据此隐藏或提示
+//!
+//! 桌面端编译时 `home_dir` / `temp_dir` 与 `dirs::home_dir` / `std::env::temp_dir` 完全一致；
+//! 单元测试中 `home_dir` 固定为进程专属的临时目录，测试不会读写真实的 `~/.claude/` 与 `~/.mo/`。
+
+use std::path::PathBuf;
+#[cfg(any(mobile, test))]
+use std::sync::OnceLock;
+
+/// 当前平台不支持该功能时返回的错误码（错误字符串前缀）
+pub const UNSUPPORTED_ERROR: &str = "Unsupported";
+
+/// 移动端的应用沙箱目录：`(数据目录, 缓存目录)`
+#[cfg(mobile)]
+static MOBILE_DIRS: OnceLock<(PathBuf, PathBuf)> = OnceLock::new();
+
+/// 注入移动端的应用沙箱目录（仅首次调用生效）
+///
+/// # 参数
+/// - `data_dir` - 应用数据目录（`PathResolver::app_data_dir`），作为移动端的「主目录」
+/// - `cache_dir` - 应用缓存目录（`PathResolver::app_cache_dir`），代替系统 TEMP
+#[cfg(mobile)]
+pub fn init_mobile_dirs(data_dir: PathBuf, cache_dir: PathBuf) {
+    let _ = MOBILE_DIRS.set((data_dir, cache_dir));
+}
+
+/// 用户主目录；移动端为应用数据目录（尚未注入时为 None）
+pub fn home_dir() -> Option<PathBuf> {
+    #[cfg(mobile)]
+    let home = MOBILE_DIRS.get().map(|(data_dir, _)| data_dir.clone());
+    #[cfg(all(not(mobile), not(test)))]
+    let home = dirs::home_dir();
+    #[cfg(all(not(mobile), test))]
+    let home = Some(test_home());
+    home
+}
+
+/// 单元测试使用的主目录（见 `test_home`）
+#[cfg(test)]
+static TEST_HOME: OnceLock<tempfile::TempDir> = OnceLock::new();
+
+/// 单元测试使用的主目录：进程内共享的临时目录，首次访问时创建，进程退出时删除
+#[cfg(test)]
+pub fn test_home() -> PathBuf {
+    TEST_HOME
+        .get_or_init(|| {
+            let home = tempfile::Builder::new().prefix("ccr-test-home-").tempdir().expect("创建测试主目录失败");
+            // 静态变量不会析构，TempDir 自身的清理不会执行；测试进程以 `process::exit` 结束，由 atexit 回调删除
+            // SAFETY: `remove_test_home` 是无参数、不 unwind 的 extern "C" 函数
+            unsafe { atexit(remove_test_home) };
+            home
+        })
+        .path()
+        .to_path_buf()
+}
+
+/// 进程退出时删除测试主目录（审计日志、编辑历史、锁、草稿等测试产物随之清理）
+#[cfg(test)]
+extern "C" fn remove_test_home() {
+    if let Some(home) = TEST_HOME.get() {
+        let _ = std::fs::remove_dir_all(home.path());
+    }
+}
+
+#[cfg(test)]
+unsafe extern "C" {
+    /// C 运行时的进程退出回调注册（Unix 与 Windows CRT 均提供）
+    fn atexit(callback: extern "C" fn()) -> std::ffi::c_int;
+}
+
+/// 临时文件目录；移动端为应用缓存目录（尚未注入时回退到 `std::env::temp_dir`）
+pub fn temp_dir() -> PathBuf {
+    #[cfg(mobile)]
+    let temp = MOBILE_DIRS
+        .get()
+        .map_or_else(std::env::temp_dir, |(_, cache_dir)| cache_dir.clone());
+    #[cfg(not(mobile))]
+    let temp = std::env::temp_dir();
+    temp
+}
+
+/// 生成「当前平台不支持」的错误
+///
+/// # 参数
+/// - `feature` - 功能名称（如 "打开终端"）
+pub fn unsupported(feature: &str) -> String {
+    format!("{}: 当前平台不支持{}", UNSUPPORTED_ERROR, feature)
+}
+
+/// 仅桌面端可用的功能在入口处调用：移动端返回 `unsupported(feature)` 错误
+///
+/// # 参数
+/// - `feature` - 功能名称（如 "打开终端"）
+///
+/// # 错误
+/// 移动端编译时返回以 `UNSUPPORTED_ERROR` 开头的错误
+pub fn require_desktop(feature: &str) -> Result<(), String> {
+    if cfg!(mobile) {
+        Err(unsupported(feature))
+    } else {
+        Ok(())
+    }
+}
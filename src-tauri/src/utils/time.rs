@@ -0,0 +1,425 @@
+//! # 时间工具函数
+//!
+//! 提供 Claude Code JSONL 中 ISO 8601 时间戳的解析工具。
+//! 解析与 `scanner` 中的格式化函数一致，不依赖时间库，使用标准库手动计算。
+//!
+//! 另提供导出 / 复制时的时间显示：按显示时区格式化（chrono）
+//! 与「x 分钟前 / x 天前」的相对时间（`humanize`，中英文两套文案）。
+//!
+//! ## 显示时区
+//! 会话 timestamp 均为 UTC。格式化与按天切分（`day_key` / `count_by_day`）统一使用
+//! `~/.mo/CCR/app-config.json` 中的 `displayTimezone`：`"local"`（默认，系统时区）、
+//! `"utc"` 或固定偏移（如 `"+08:00"`、`"-05:00"`），跨时区讨论「某天的会话」时各处切分边界一致。
+//! 启动时读取一次，设置页修改后经 `set_display_timezone` 立即生效。
+
+use std::collections::BTreeMap;
+use std::sync::{LazyLock, RwLock};
+
+use chrono::{DateTime, FixedOffset, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::app_config;
+
+/// 当前显示时区，启动时从 `app-config.json` 读取
+static DISPLAY_TIMEZONE: LazyLock<RwLock<DisplayTimezone>> =
+    LazyLock::new(|| RwLock::new(load_display_timezone()));
+
+/// 显示时区：时间格式化与按天切分的统一基准
+///
+/// 序列化为字符串：`"local"` / `"utc"` / `"±HH:MM"`。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum DisplayTimezone {
+    /// 系统本地时区（默认）
+    #[default]
+    Local,
+    /// UTC
+    Utc,
+    /// 固定偏移（分钟，东正西负）
+    Offset(i32),
+}
+
+impl DisplayTimezone {
+    /// 解析配置字符串：`local` / `utc`（不区分大小写）或 `±HH:MM` / `±HHMM` / `±HH`
+    ///
+    /// # 错误
+    /// 格式不合法或偏移超出 ±14 小时时返回错误
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("local") {
+            return Ok(Self::Local);
+        }
+        if s.eq_ignore_ascii_case("utc") || s == "Z" {
+            return Ok(Self::Utc);
+        }
+        let invalid = || format!("无效的时区: {}（应为 local、utc 或 ±HH:MM）", s);
+        let (sign, rest) = match s.as_bytes().first() {
+            Some(b'+') => (1, &s[1..]),
+            Some(b'-') => (-1, &s[1..]),
+            _ => return Err(invalid()),
+        };
+        let digits = rest.replace(':', "");
+        if !matches!(digits.len(), 2 | 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let hours: i32 = digits[..2].parse().map_err(|_| invalid())?;
+        let minutes: i32 = digits.get(2..).filter(|m| !m.is_empty()).map_or(Ok(0), str::parse).map_err(|_| invalid())?;
+        if hours > 14 || minutes > 59 || hours * 60 + minutes > 14 * 60 {
+            return Err(invalid());
+        }
+        Ok(Self::Offset(sign * (hours * 60 + minutes)))
+    }
+
+    /// 毫秒时间戳 → 该时区的时间
+    fn datetime(self, millis: i64) -> Option<DateTime<FixedOffset>> {
+        let utc = DateTime::from_timestamp_millis(millis)?;
+        match self {
+            Self::Local => Some(utc.with_timezone(&Local).fixed_offset()),
+            Self::Utc => Some(utc.fixed_offset()),
+            Self::Offset(minutes) => FixedOffset::east_opt(minutes * 60).map(|tz| utc.with_timezone(&tz)),
+        }
+    }
+}
+
+impl TryFrom<String> for DisplayTimezone {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        Self::parse(&s)
+    }
+}
+
+impl From<DisplayTimezone> for String {
+    fn from(tz: DisplayTimezone) -> String {
+        match tz {
+            DisplayTimezone::Local => "local".to_string(),
+            DisplayTimezone::Utc => "utc".to_string(),
+            DisplayTimezone::Offset(minutes) => {
+                let sign = if minutes < 0 { '-' } else { '+' };
+                format!("{}{:02}:{:02}", sign, minutes.abs() / 60, minutes.abs() % 60)
+            }
+        }
+    }
+}
+
+/// 应用配置中与显示时区相关的部分
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppConfig {
+    /// 显示时区（缺失或无法解析时为本地时区）
+    #[serde(default)]
+    display_timezone: Option<String>,
+}
+
+/// 从 `app-config.json` 读取显示时区（文件不存在或解析失败时为本地时区）
+fn load_display_timezone() -> DisplayTimezone {
+    app_config::load::<AppConfig>()
+        .display_timezone
+        .and_then(|tz| DisplayTimezone::parse(&tz).ok())
+        .unwrap_or_default()
+}
+
+/// 当前显示时区
+pub fn display_timezone() -> DisplayTimezone {
+    DISPLAY_TIMEZONE.read().map(|tz| *tz).unwrap_or_default()
+}
+
+/// 切换显示时区（仅内存，持久化由调用方写入 `app-config.json`）
+pub fn set_display_timezone(tz: DisplayTimezone) {
+    if let Ok(mut current) = DISPLAY_TIMEZONE.write() {
+        *current = tz;
+    }
+}
+
+/// 相对时间文案的语言
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    /// 中文：「3 天前」
+    #[default]
+    Zh,
+    /// 英文："3 days ago"
+    En,
+}
+
+/// 相对时间的最大跨度（天），超过后显示具体日期
+const RELATIVE_MAX_DAYS: i64 = 30;
+
+/// 将 ISO 8601 时间字符串解析为 Unix 毫秒时间戳
+///
+/// 支持 Claude Code 写入的 `YYYY-MM-DDTHH:MM:SS.sssZ` 格式，
+/// 以及省略小数秒、带 `±HH:MM` 时区偏移的变体。
+///
+/// # 参数
+/// - `s` - ISO 8601 时间字符串
+///
+/// # 返回值
+/// 解析成功返回毫秒时间戳（UTC）；格式不合法时返回 None
+pub fn parse_iso8601_millis(s: &str) -> Option<i64> {
+    let bytes = s.as_bytes();
+    if bytes.len() < 19
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+    if bytes[10] != b'T' && bytes[10] != b' ' {
+        return None;
+    }
+
+    let num = |range: std::ops::Range<usize>| -> Option<i64> {
+        let part = s.get(range)?;
+        if part.bytes().all(|b| b.is_ascii_digit()) {
+            part.parse().ok()
+        } else {
+            None
+        }
+    };
+    let year = num(0..4)?;
+    let month = num(5..7)?;
+    let day = num(8..10)?;
+    let hour = num(11..13)?;
+    let minute = num(14..16)?;
+    let second = num(17..19)?;
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    // 小数秒：取前 3 位作为毫秒，不足 3 位右侧补零
+    let mut rest = &s[19..];
+    let mut millis = 0;
+    if let Some(frac) = rest.strip_prefix('.') {
+        let digits = frac.bytes().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 {
+            return None;
+        }
+        let ms_part: String = frac[..digits].chars().chain("00".chars()).take(3).collect();
+        millis = ms_part.parse().ok()?;
+        rest = &frac[digits..];
+    }
+
+    // 时区：Z / 空（按 UTC）/ ±HH:MM
+    let offset_minutes = match rest {
+        "" | "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let tz = rest[1..].replace(':', "");
+            if tz.len() != 4 || !tz.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            sign * (tz[..2].parse::<i64>().ok()? * 60 + tz[2..].parse::<i64>().ok()?)
+        }
+    };
+
+    let days = days_from_civil(year, month, day);
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second - offset_minutes * 60;
+    Some(secs * 1000 + millis)
+}
+
+/// 将毫秒时间戳格式化为显示时区的 `YYYY-MM-DD HH:MM:SS`
+///
+/// # 返回值
+/// 时间戳超出可表示范围时返回 None
+pub fn format_display_datetime(millis: i64) -> Option<String> {
+    display_timezone().datetime(millis).map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// 将毫秒时间戳格式化为带偏移的 `YYYY-MM-DD HH:MM:SS ±HH:MM`（显示时区）
+///
+/// # 返回值
+/// 时间戳超出可表示范围时返回 None
+pub fn format_display_datetime_with_offset(millis: i64) -> Option<String> {
+    display_timezone().datetime(millis).map(|dt| dt.format("%Y-%m-%d %H:%M:%S %:z").to_string())
+}
+
+/// 时间戳在指定时区下所属的日期 `YYYY-MM-DD`（按天聚合的切分键）
+///
+/// # 返回值
+/// 时间戳超出可表示范围时返回 None
+pub fn day_key(millis: i64, tz: DisplayTimezone) -> Option<String> {
+    tz.datetime(millis).map(|dt| dt.format("%Y-%m-%d").to_string())
+}
+
+/// 按天计数：以指定时区的日期切分，返回按日期升序的 `日期 → 条数`
+///
+/// # 参数
+/// - `millis` - 毫秒时间戳序列
+/// - `tz` - 切分基准时区（通常为 `display_timezone()`）
+pub fn count_by_day(millis: impl IntoIterator<Item = i64>, tz: DisplayTimezone) -> BTreeMap<String, usize> {
+    let mut days = BTreeMap::new();
+    for key in millis.into_iter().filter_map(|ms| day_key(ms, tz)) {
+        *days.entry(key).or_insert(0) += 1;
+    }
+    days
+}
+
+/// 将时间戳转换为相对于 `now` 的人类可读时间
+///
+/// 阈值切换：
+/// - 1 分钟内（含轻微的时钟偏差）：刚刚 / just now
+/// - 1 小时内：x 分钟前 / x minutes ago
+/// - 1 天内：x 小时前 / x hours ago
+/// - `RELATIVE_MAX_DAYS` 天内：x 天前 / x days ago
+/// - 更早或在未来：显示时区的具体日期 `YYYY-MM-DD`
+///
+/// # 参数
+/// - `ts_millis` - 目标时间（Unix 毫秒）
+/// - `now_millis` - 基准时间（Unix 毫秒），导出时取导出时刻
+/// - `locale` - 文案语言
+pub fn humanize(ts_millis: i64, now_millis: i64, locale: Locale) -> String {
+    let diff_secs = (now_millis - ts_millis) / 1000;
+    if diff_secs.abs() < 60 {
+        return match locale {
+            Locale::Zh => "刚刚".to_string(),
+            Locale::En => "just now".to_string(),
+        };
+    }
+
+    let minutes = diff_secs / 60;
+    let hours = minutes / 60;
+    let days = hours / 24;
+    if diff_secs < 0 || days >= RELATIVE_MAX_DAYS {
+        return day_key(ts_millis, display_timezone()).unwrap_or_default();
+    }
+
+    let (count, zh_unit, en_unit) = if minutes < 60 {
+        (minutes, "分钟", "minute")
+    } else if hours < 24 {
+        (hours, "小时", "hour")
+    } else {
+        (days, "天", "day")
+    };
+
+    match locale {
+        Locale::Zh => format!("{} {}前", count, zh_unit),
+        Locale::En => format!("{} {}{} ago", count, en_unit, if count == 1 { "" } else { "s" }),
+    }
+}
+
+/// 将公历日期 (年, 月, 日) 转换为自 1970-01-01 以来的天数
+///
+/// `scanner::days_to_date` 的逆运算，同样基于 Howard Hinnant 的算法：
+/// http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400; // [0, 399]
+    let mp = if month > 2 { month - 3 } else { month + 9 }; // [0, 11]
+    let doy = (153 * mp + 2) / 5 + day - 1; // [0, 365]
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy; // [0, 146096]
+    era * 146097 + doe - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_iso8601_millis_utc() {
+        assert_eq!(parse_iso8601_millis("1970-01-01T00:00:00.000Z"), Some(0));
+        assert_eq!(
+            parse_iso8601_millis("2026-01-01T00:00:01.250Z"),
+            Some(1_767_225_601_250)
+        );
+        assert_eq!(
+            parse_iso8601_millis("2024-02-29T12:00:00Z"),
+            Some(1_709_208_000_000)
+        );
+    }
+
+    #[test]
+    fn test_parse_iso8601_millis_offset() {
+        assert_eq!(
+            parse_iso8601_millis("2026-01-01T08:00:00+08:00"),
+            parse_iso8601_millis("2026-01-01T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_humanize_thresholds() {
+        const MIN: i64 = 60_000;
+        const HOUR: i64 = 60 * MIN;
+        const DAY: i64 = 24 * HOUR;
+        let now = 1_767_225_600_000;
+
+        assert_eq!(humanize(now - 10_000, now, Locale::Zh), "刚刚");
+        assert_eq!(humanize(now - 5 * MIN, now, Locale::Zh), "5 分钟前");
+        assert_eq!(humanize(now - 3 * HOUR, now, Locale::Zh), "3 小时前");
+        assert_eq!(humanize(now - 3 * DAY, now, Locale::Zh), "3 天前");
+
+        assert_eq!(humanize(now - 10_000, now, Locale::En), "just now");
+        assert_eq!(humanize(now - MIN, now, Locale::En), "1 minute ago");
+        assert_eq!(humanize(now - 2 * HOUR, now, Locale::En), "2 hours ago");
+        assert_eq!(humanize(now - DAY, now, Locale::En), "1 day ago");
+
+        // 超过阈值或在未来：具体日期
+        let old = humanize(now - 40 * DAY, now, Locale::En);
+        assert_eq!(old.len(), 10);
+        assert_eq!(&old[4..5], "-");
+        assert_eq!(humanize(now + DAY, now, Locale::Zh).len(), 10);
+    }
+
+    #[test]
+    fn test_display_timezone_parse_roundtrip() {
+        assert_eq!(DisplayTimezone::parse("local"), Ok(DisplayTimezone::Local));
+        assert_eq!(DisplayTimezone::parse("UTC"), Ok(DisplayTimezone::Utc));
+        assert_eq!(DisplayTimezone::parse("+08:00"), Ok(DisplayTimezone::Offset(480)));
+        assert_eq!(DisplayTimezone::parse("-0530"), Ok(DisplayTimezone::Offset(-330)));
+        assert_eq!(DisplayTimezone::parse("-05"), Ok(DisplayTimezone::Offset(-300)));
+        assert!(DisplayTimezone::parse("+15:00").is_err());
+        assert!(DisplayTimezone::parse("Asia/Shanghai").is_err());
+        assert_eq!(String::from(DisplayTimezone::Offset(-330)), "-05:30");
+        let json = serde_json::to_string(&DisplayTimezone::Offset(480)).unwrap();
+        assert_eq!(serde_json::from_str::<DisplayTimezone>(&json).unwrap(), DisplayTimezone::Offset(480));
+    }
+
+    #[test]
+    fn test_count_by_day_across_timezones() {
+        // 2026-03-04T20:00Z：UTC+8 已是 3 月 5 日凌晨，UTC-5 仍是 3 月 4 日下午
+        // 2026-03-05T03:00Z：UTC+8 为 3 月 5 日上午，UTC-5 为 3 月 4 日晚上
+        // 2026-03-05T16:30Z：UTC+8 已是 3 月 6 日，UTC-5 为 3 月 5 日上午
+        let samples = [
+            "2026-03-04T20:00:00.000Z",
+            "2026-03-05T03:00:00.000Z",
+            "2026-03-05T16:30:00.000Z",
+        ]
+        .map(|s| parse_iso8601_millis(s).unwrap());
+        let days = |tz| count_by_day(samples, tz).into_iter().collect::<Vec<_>>();
+        let day = |d: &str, n| (d.to_string(), n);
+
+        assert_eq!(
+            days(DisplayTimezone::Offset(480)),
+            vec![day("2026-03-05", 2), day("2026-03-06", 1)]
+        );
+        assert_eq!(
+            days(DisplayTimezone::Offset(-300)),
+            vec![day("2026-03-04", 2), day("2026-03-05", 1)]
+        );
+        assert_eq!(
+            days(DisplayTimezone::Utc),
+            vec![day("2026-03-04", 1), day("2026-03-05", 2)]
+        );
+        // 切分边界恰在当地零点
+        let midnight = parse_iso8601_millis("2026-03-04T16:00:00.000Z").unwrap();
+        assert_eq!(day_key(midnight, DisplayTimezone::Offset(480)).as_deref(), Some("2026-03-05"));
+        assert_eq!(day_key(midnight - 1, DisplayTimezone::Offset(480)).as_deref(), Some("2026-03-04"));
+    }
+
+    #[test]
+    fn test_parse_iso8601_millis_invalid() {
+        assert_eq!(parse_iso8601_millis(""), None);
+        assert_eq!(parse_iso8601_millis("2026-13-01T00:00:00Z"), None);
+        assert_eq!(parse_iso8601_millis("not a timestamp at all"), None);
+    }
+}
@@ -11,29 +11,44 @@
  * App
  *  ├── Sidebar          - 左侧边栏（项目列表、会话列表、环境配置切换器）
  *  ├── ChatView         - 主内容区（消息列表、消息操作）
- *  └── SettingsPanel    - 设置面板（浮层，条件渲染）
+ *  ├── SettingsPanel    - 设置面板（浮层，条件渲染）
+ *  └── RecoveryPrompt   - 异常退出恢复提示（启动时检测到写入残留才显示）
  */
 
 import { useState, useEffect, useCallback, useRef } from 'react';
 import { AnimatePresence } from 'motion/react';
 import { Sidebar, ChatView, SettingsPanel, ProxyPanel } from './components';
 import { RetrospectView } from './components/retrospect/RetrospectView';
-import type { Project, Session, ClaudeSettings, EnvSwitcherConfig, EnvProfile, TransformedSession } from './types/claude';
+import { RecoveryPrompt } from './components/RecoveryPrompt';
+import type { Project, Session, ClaudeSettings, EnvSwitcherConfig, EnvProfile, TransformedSession, TimeStyle, RecoveryCandidate } from './types/claude';
 import {
   getClaudeDataPath,
+  getDisplayTimezone,
+  setFormattingTimezone,
   getProjects,
   readSettings,
   saveSettings,
   readSessionMessages,
+  readSessionMessagesStreamed,
   deleteMessage,
   deleteMessages,
   editMessageContent,
   deleteSession,
+  setSessionLocked,
   exportSession,
+  suggestExportFileName,
+  detectExportLocale,
   readEnvSwitcherConfig,
   saveEnvSwitcherConfig,
+  isCommentsLostError,
+  parseSessionReadError,
+  recordSessionOpened,
   applyEnvProfile,
   saveCurrentAsProfile,
+  getRecoveryCandidates,
+  openSessionWindow,
+  getSessionWindowTarget,
+  onSessionUpdated,
 } from './utils/claudeData';
 
 /** 侧边栏自动折叠阈值（像素）：拖动宽度低于此值后松开鼠标，侧边栏自动折叠 */
@@ -42,6 +57,8 @@ const SIDEBAR_COLLAPSE_THRESHOLD = 160;
 const SIDEBAR_MIN_WIDTH = 220;
 /** 侧边栏默认宽度（像素）：初始宽度，折叠后重新展开时恢复到此值 */
 const SIDEBAR_DEFAULT_WIDTH = 320;
+/** 流式加载会话时每批推送的消息条数：首批尽快出现，批次过小会增加事件与渲染开销 */
+const SESSION_STREAM_CHUNK_SIZE = 500;
 
 /**
  * 应用根组件
@@ -97,8 +114,14 @@ function App() {
   const [showProxyPanel, setShowProxyPanel] = useState(false);
   /** 项目回溯视图可见性：为 true 时显示 RetrospectView 替代 ChatView */
   const [showRetrospect, setShowRetrospect] = useState(false);
+  /** 上次异常退出留下的写入残留：非空时显示 RecoveryPrompt */
+  const [recoveryCandidates, setRecoveryCandidates] = useState<RecoveryCandidate[]>([]);
   /** 使用 ref 追踪拖动状态，避免全局事件监听器中的闭包陈旧问题 */
   const isResizingRef = useRef(false);
+  /** 正在流式加载的会话路径，切换会话后丢弃旧会话的进度回调 */
+  const loadingSessionPathRef = useRef<string | null>(null);
+  /** 会话窗口是否已打开过启动目标会话（之后切换会话不再跳回） */
+  const sessionWindowOpenedRef = useRef(false);
 
   /**
    * 开始拖动调整侧边栏宽度。
@@ -192,6 +215,10 @@ function App() {
         // 第一步：获取 Claude 数据目录路径
         const path = await getClaudeDataPath();
         setClaudeDataPath(path);
+        // 显示时区只影响时间格式化，读取失败时保持跟随系统
+        getDisplayTimezone()
+          .then(setFormattingTimezone)
+          .catch((err) => console.error('加载显示时区失败:', err));
 
         // 第二步：并行加载设置、项目列表和环境配置，减少总加载时间
         const [loadedSettings, loadedProjects, loadedEnvConfig] = await Promise.all([
@@ -200,9 +227,12 @@ function App() {
           readEnvSwitcherConfig(path),
         ]);
 
-        setSettings(loadedSettings);
+        if (loadedSettings.wasLenient || loadedEnvConfig.wasLenient) {
+          console.warn('配置文件含注释或尾逗号，已按 JSONC 解析；保存时注释将丢失');
+        }
+        setSettings(loadedSettings.value);
         setProjects(loadedProjects);
-        setEnvConfig(loadedEnvConfig);
+        setEnvConfig(loadedEnvConfig.value);
         setError(null);
       } catch (err) {
         console.error('初始化失败:', err);
@@ -215,11 +245,27 @@ function App() {
     init();
   }, []);
 
+  /**
+   * 异常退出恢复检测副作用
+   *
+   * 启动时检查会话目录下是否残留上次写入未完成的 `.tmp` 文件，有则弹出 RecoveryPrompt。
+   * 独立于初始化流程，检测失败不影响正常使用。仅主窗口检测，会话窗口不重复弹出。
+   *
+   * 触发条件：仅在组件首次挂载时执行一次（依赖数组为空）
+   */
+  useEffect(() => {
+    if (getSessionWindowTarget()) return;
+    getRecoveryCandidates()
+      .then(setRecoveryCandidates)
+      .catch((err) => console.error('检测写入残留失败:', err));
+  }, []);
+
   /**
    * 处理会话选择事件
    *
-   * 当用户在侧边栏点击某个会话时触发，加载该会话的所有消息并更新状态。
-   * 如果加载失败，清空消息列表并在控制台输出错误信息。
+   * 当用户在侧边栏点击某个会话时触发，流式加载该会话的消息：
+   * 每收到一批消息即更新状态，大会话无需等待完整转换即可看到首屏内容。
+   * 如果加载失败，清空消息列表并在控制台输出错误信息；可识别的读取失败（云盘占位、权限等）弹窗给出处理建议。
    *
    * @param session - 用户选择的会话对象
    */
@@ -228,12 +274,95 @@ function App() {
     // 切换会话时清空选择模式和已选消息，防止残留状态跨会话
     setSelectedMessages(new Set());
     setSelectionMode(false);
+    loadingSessionPathRef.current = sess.filePath;
+    const isCurrent = () => loadingSessionPathRef.current === sess.filePath;
     try {
-      const transformed = await readSessionMessages(sess.filePath);
-      setSession(transformed);
+      const transformed = await readSessionMessagesStreamed(
+        sess.filePath,
+        SESSION_STREAM_CHUNK_SIZE,
+        (partial) => { if (isCurrent()) setSession(partial); }
+      );
+      if (isCurrent()) setSession(transformed);
+      // 记录查看时间并就地清除未读圆点（记录失败只影响圆点显示）
+      recordSessionOpened(sess.filePath).catch((e) => console.warn('记录会话查看时间失败:', e));
+      setProjects((prev) =>
+        prev.map((project) => ({
+          ...project,
+          sessions: project.sessions.map((s) =>
+            s.filePath === sess.filePath ? { ...s, updatedSinceViewed: false } : s
+          ),
+        }))
+      );
     } catch (err) {
       console.error('加载消息失败:', err);
+      if (!isCurrent()) return;
       setSession(null);
+      // 云盘占位、权限不足等常见失败向用户说明原因与处理办法，避免只看到空白会话
+      const readError = parseSessionReadError(err);
+      if (readError) window.alert(`${readError.message}\n\n${readError.suggestion}`);
+    }
+  }, []);
+
+  /**
+   * 会话窗口启动副作用
+   *
+   * 由 `open_session_window` 创建的窗口在项目列表加载完成后直接打开目标会话（仅执行一次）。
+   *
+   * 触发条件：projects 变化时检查，打开后不再重复
+   */
+  useEffect(() => {
+    const target = getSessionWindowTarget();
+    if (!target || sessionWindowOpenedRef.current) return;
+    const project = projects.find((p) => p.sessions.some((s) => s.filePath === target));
+    const targetSession = project?.sessions.find((s) => s.filePath === target);
+    if (!project || !targetSession) return;
+    sessionWindowOpenedRef.current = true;
+    setCurrentProject(project);
+    handleSelectSession(targetSession);
+  }, [projects, handleSelectSession]);
+
+  /**
+   * 跨窗口同步副作用
+   *
+   * 其他窗口修改了当前会话文件时重新加载消息；当前会话被删除时清空视图。
+   * 写操作总是由获得焦点的窗口发起，它已用命令返回值更新过自身状态，因此只重新加载未聚焦的窗口。
+   * 正在查看的会话被修改（含本窗口的编辑）时刷新最近查看时间，避免它被标记为「查看后又有新消息」。
+   *
+   * 触发条件：currentSession 变化时重新注册监听
+   */
+  useEffect(() => {
+    if (!currentSession) return;
+    const filePath = currentSession.filePath;
+    const unlisten = onSessionUpdated((event) => {
+      if (event.filePath !== filePath) return;
+      if (!event.deleted) {
+        recordSessionOpened(filePath).catch((e) => console.warn('记录会话查看时间失败:', e));
+      }
+      if (document.hasFocus()) return;
+      if (event.deleted) {
+        setCurrentSession(null);
+        setSession(null);
+        return;
+      }
+      readSessionMessages(filePath)
+        .then(setSession)
+        .catch((err) => console.error('同步会话失败:', err));
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [currentSession]);
+
+  /**
+   * 在独立窗口中打开会话（用于左右对照查看两个会话）
+   *
+   * @param sess - 要打开的会话
+   */
+  const handleOpenSessionWindow = useCallback(async (sess: Session) => {
+    try {
+      await openSessionWindow(sess.filePath);
+    } catch (err) {
+      console.error('打开会话窗口失败:', err);
     }
   }, []);
 
@@ -465,6 +594,76 @@ function App() {
     [claudeDataPath, currentSession]
   );
 
+  /**
+   * 处理写入残留后刷新数据
+   *
+   * 残留可能被另存为新会话或覆盖了正式文件，重新加载项目列表并刷新当前会话。
+   */
+  const handleRecoveryResolved = useCallback(async () => {
+    try {
+      setProjects(await getProjects(claudeDataPath));
+    } catch (err) {
+      console.error('刷新项目列表失败:', err);
+    }
+    handleRefresh();
+  }, [claudeDataPath, handleRefresh]);
+
+  /**
+   * 同步当前会话的草稿标记
+   *
+   * 草稿由 SessionDraftModal / 一键 Resume 在后端更新，这里仅就地更新项目列表与当前会话中的 `hasDraft`。
+   *
+   * @param hasDraft - 当前会话是否仍有草稿
+   */
+  const handleDraftChange = useCallback(
+    (hasDraft: boolean) => {
+      if (!currentSession) return;
+      const filePath = currentSession.filePath;
+      setProjects((prev) =>
+        prev.map((project) => ({
+          ...project,
+          sessions: project.sessions.map((s) =>
+            s.filePath === filePath ? { ...s, hasDraft } : s
+          ),
+        }))
+      );
+      setCurrentSession((prev) =>
+        prev?.filePath === filePath ? { ...prev, hasDraft } : prev
+      );
+    },
+    [currentSession]
+  );
+
+  /**
+   * 切换会话的只读锁定状态
+   *
+   * 调用后端更新锁定列表后，就地更新项目列表与当前会话中的 `locked` 标记，无需重新扫描。
+   *
+   * @param target - 要切换锁定状态的会话
+   */
+  const handleToggleSessionLock = useCallback(
+    async (target: Session) => {
+      const locked = !target.locked;
+      try {
+        await setSessionLocked(target.filePath, locked);
+        setProjects((prev) =>
+          prev.map((project) => ({
+            ...project,
+            sessions: project.sessions.map((s) =>
+              s.filePath === target.filePath ? { ...s, locked } : s
+            ),
+          }))
+        );
+        setCurrentSession((prev) =>
+          prev?.filePath === target.filePath ? { ...prev, locked } : prev
+        );
+      } catch (err) {
+        console.error('切换会话锁定状态失败:', err);
+      }
+    },
+    []
+  );
+
   /**
    * 处理会话导出事件
    *
@@ -473,9 +672,10 @@ function App() {
    * 然后通过 @tauri-apps/plugin-fs 的 writeTextFile() 写入文件。
    *
    * @param format - 导出格式：'markdown' 或 'json'
+   * @param timeStyle - 消息时间的显示方式（相对时间以导出时刻为基准）
    */
   const handleExport = useCallback(
-    async (format: 'markdown' | 'json') => {
+    async (format: 'markdown' | 'json', timeStyle: TimeStyle) => {
       if (!currentSession || !session) return;
       try {
         const { save } = await import('@tauri-apps/plugin-dialog');
@@ -485,11 +685,14 @@ function App() {
         const extension = format === 'markdown' ? 'md' : 'json';
 
         // 通过 Rust 后端导出
-        const content = await exportSession(currentSession.filePath, sessionName, format);
+        const content = await exportSession(currentSession.filePath, sessionName, format, {
+          timeStyle,
+          locale: detectExportLocale(),
+        });
 
-        // 弹出系统文件保存对话框
+        // 弹出系统文件保存对话框（默认文件名经后端清理非法字符与超长内容）
         const filePath = await save({
-          defaultPath: `${sessionName}.${extension}`,
+          defaultPath: await suggestExportFileName(sessionName, extension),
           filters: [
             {
               name: format === 'markdown' ? 'Markdown' : 'JSON',
@@ -520,7 +723,15 @@ function App() {
   const handleSaveSettings = useCallback(
     async (newSettings: ClaudeSettings) => {
       try {
-        await saveSettings(claudeDataPath, newSettings);
+        try {
+          await saveSettings(claudeDataPath, newSettings);
+        } catch (err) {
+          // settings.json 含注释：确认后以标准 JSON 覆盖
+          if (!isCommentsLostError(err) || !window.confirm('settings.json 中的注释与尾逗号将在保存后丢失，是否继续？')) {
+            throw err;
+          }
+          await saveSettings(claudeDataPath, newSettings, true);
+        }
         setSettings(newSettings);
       } catch (err) {
         console.error('保存设置失败:', err);
@@ -572,7 +783,7 @@ function App() {
       try {
         const profile = await saveCurrentAsProfile(claudeDataPath, name);
         // 重新加载配置以确保数据一致性
-        const updatedConfig = await readEnvSwitcherConfig(claudeDataPath);
+        const { value: updatedConfig } = await readEnvSwitcherConfig(claudeDataPath);
         setEnvConfig(updatedConfig);
         console.log('保存配置成功:', profile.name);
       } catch (err) {
@@ -723,6 +934,8 @@ function App() {
             onSelectProject={setCurrentProject}
             onSelectSession={handleSidebarSelectSession}
             onDeleteSession={handleDeleteSession}
+            onToggleSessionLock={handleToggleSessionLock}
+            onOpenSessionWindow={handleOpenSessionWindow}
             onOpenSettings={() => setShowSettings(true)}
             onSwitchEnvProfile={handleSwitchEnvProfile}
             onSaveEnvProfile={handleSaveEnvProfile}
@@ -780,6 +993,7 @@ function App() {
           onNavigateToSession={handleNavigateToSession}
           onOpenProxyPanel={() => setShowProxyPanel(true)}
           onOpenRetrospect={currentProject ? () => setShowRetrospect(true) : undefined}
+          onDraftChange={handleDraftChange}
         />
       )}
 
@@ -822,6 +1036,17 @@ function App() {
           />
         )}
       </AnimatePresence>
+
+      {/* 异常退出恢复提示（浮层）：启动时检测到写入残留才显示，关闭后残留保留到下次启动 */}
+      <AnimatePresence>
+        {recoveryCandidates.length > 0 && (
+          <RecoveryPrompt
+            candidates={recoveryCandidates}
+            onClose={() => setRecoveryCandidates([])}
+            onResolved={handleRecoveryResolved}
+          />
+        )}
+      </AnimatePresence>
     </div>
   );
 }
@@ -20,10 +20,10 @@ import {
   ChevronRight, ChevronDown, ChevronUp, X, CheckSquare, Square, Filter,
   Download, FileText, FileJson, RefreshCw, ArrowLeft, Plus,
   Copy, Edit2, Trash2, Bot, User, Lightbulb, Wrench, Archive, Terminal, ExternalLink, Search, Globe, History,
-  ClipboardCopy, Sparkles, Zap, Package
+  ClipboardCopy, Sparkles, Zap, Package, Link2, XCircle, AlertTriangle, Lock, NotebookPen
 } from 'lucide-react';
-import type { Session, Project, DisplayMessage, TransformedSession, ToolUseInfo, SearchHighlight } from '../types/claude';
-import { formatTimestamp, searchSession, openResumeTerminal, buildResumeCommand, insertMessage } from '../utils/claudeData';
+import type { Session, Project, DisplayMessage, TransformedSession, ToolUseInfo, SearchHighlight, TimeStyle, InsertPosition } from '../types/claude';
+import { formatTimestamp, searchSession, openResumeTerminal, buildResumeCommand, insertMessage, insertRawMessage, formatMessage, getFailedToolCalls } from '../utils/claudeData';
 import { parseJsonlPath } from '../utils/messageTransform';
 import { MessageBlockList } from './MessageBlockList';
 import { MessageContentRenderer } from './MessageContentRenderer';
@@ -31,6 +31,12 @@ import { useProgressiveRender } from '../hooks/useProgressiveRender';
 import { useCollapsible } from '../hooks/useCollapsible';
 import { NavSearchBar, type SearchRequest, type NavSearchBarHandle } from './NavSearchBar';
 import { QuickFixModal } from './QuickFixModal';
+import { SessionLinksModal } from './SessionLinksModal';
+import { SessionDraftModal } from './SessionDraftModal';
+import { MessageEditHistoryModal } from './MessageEditHistoryModal';
+import { StartupDiagnostics } from './StartupDiagnostics';
+import { OnboardingGuide } from './OnboardingGuide';
+import { ProjectSummaryCard } from './ProjectSummaryCard';
 import { PetManager } from './PetManager';
 import { SkillsManager } from './SkillsManager';
 import { PluginsManager } from './PluginsManager';
@@ -60,8 +66,8 @@ interface ChatViewProps {
   onDeleteMessage: (uuid: string) => void;
   /** 刷新当前会话数据的回调函数 */
   onRefresh: () => void;
-  /** 导出会话的回调函数，接收导出格式 */
-  onExport: (format: 'markdown' | 'json') => void;
+  /** 导出会话的回调函数，接收导出格式与消息时间显示方式 */
+  onExport: (format: 'markdown' | 'json', timeStyle: TimeStyle) => void;
   /** 多选模式是否开启 */
   selectionMode: boolean;
   /** 当前已选中的消息 UUID 集合 */
@@ -80,7 +86,7 @@ interface ChatViewProps {
   sidebarCollapsed: boolean;
   /** 展开侧边栏的回调 */
   onExpandSidebar: () => void;
-  /** 所有项目列表，用于计划消息跳转时查找目标会话 */
+  /** 所有项目列表：为空时空状态页展示启动自检 */
   projects: Project[];
   /** 导航回退目标：跳转到引用会话后，用于显示悬浮"返回"按钮 */
   navBackTarget: { project: Project; session: Session } | null;
@@ -92,6 +98,8 @@ interface ChatViewProps {
   onOpenProxyPanel?: () => void;
   /** 打开项目回溯视图的回调 */
   onOpenRetrospect?: () => void;
+  /** 会话草稿保存、删除或随 Resume 发送后的回调，参数为是否仍有草稿 */
+  onDraftChange: (hasDraft: boolean) => void;
 }
 
 /** 展开/收起动画的过渡参数 */
@@ -392,7 +400,20 @@ function copyToClipboard(text: string) {
 }
 
 /**
- * 获取 DisplayMessage 的文本内容，用于复制到剪贴板。
+ * 复制单条消息：由 Rust 后端 `format_message` 按纯文本格式渲染（与导出共用渲染逻辑，
+ * 完整展开工具调用与嵌套 tool_result），后端失败时回退到前端提取的文本。
+ */
+async function copyMessage(sessionFilePath: string, msg: DisplayMessage) {
+  try {
+    copyToClipboard(await formatMessage(sessionFilePath, msg.displayId, 'plain'));
+  } catch (err) {
+    console.error('格式化消息失败:', err);
+    copyToClipboard(getDisplayText(msg));
+  }
+}
+
+/**
+ * 获取 DisplayMessage 的文本内容，用于复制到剪贴板的回退路径。
  * 直接从 content 块中提取文本，不依赖 rawMessage。
  */
 function getDisplayText(msg: DisplayMessage): string {
@@ -426,6 +447,8 @@ interface MessageItemProps {
   isRendered: boolean;
   /** 项目根目录路径 */
   projectPath: string;
+  /** 当前会话 JSONL 文件路径（复制消息时交给后端格式化） */
+  sessionFilePath: string;
   /** 工具调用映射表 */
   toolUseMap: Record<string, ToolUseInfo>;
   /** 搜索高亮选项（仅匹配消息传入，非匹配传 undefined） */
@@ -446,6 +469,8 @@ interface MessageItemProps {
   onDeleteMessage: (uuid: string) => void;
   /** 开始编辑消息的回调 */
   onStartEdit: (msg: DisplayMessage) => void;
+  /** 查看消息编辑历史的回调，接收消息的 sourceUuid */
+  onShowEditHistory: (uuid: string) => void;
   /** 保存编辑的回调 */
   onSaveEdit: () => void;
   /** 取消编辑的回调 */
@@ -468,6 +493,7 @@ function messageItemAreEqual(prev: MessageItemProps, next: MessageItemProps): bo
     && prev.index === next.index
     && prev.isRendered === next.isRendered
     && prev.projectPath === next.projectPath
+    && prev.sessionFilePath === next.sessionFilePath
     && prev.toolUseMap === next.toolUseMap
     && prev.searchHighlight === next.searchHighlight
     && prev.searchAutoExpand === next.searchAutoExpand
@@ -490,6 +516,7 @@ const MessageItem = memo(function MessageItem({
   index,
   isRendered,
   projectPath,
+  sessionFilePath,
   toolUseMap,
   searchHighlight,
   searchAutoExpand,
@@ -500,6 +527,7 @@ const MessageItem = memo(function MessageItem({
   onToggleSelect,
   onDeleteMessage,
   onStartEdit,
+  onShowEditHistory,
   onSaveEdit,
   onCancelEdit,
   onEditBlockChange,
@@ -544,9 +572,11 @@ const MessageItem = memo(function MessageItem({
           className={`rounded-xl p-4 message-bubble ${
             msg.displayType === 'user'
               ? 'bg-primary/5 border border-primary/10'
-              : msg.displayType === 'tool_result'
-                ? 'bg-emerald-500/5 border border-emerald-500/10'
-                : 'bg-muted/50 border border-border'
+              : msg.isError
+                ? 'bg-red-500/5 border border-red-500/20'
+                : msg.displayType === 'tool_result'
+                  ? 'bg-emerald-500/5 border border-emerald-500/10'
+                  : 'bg-muted/50 border border-border'
           } ${isSelected ? 'ring-2 ring-primary' : ''}`}
           onClick={selectionMode ? () => onToggleSelect(msg.sourceUuid) : undefined}
           style={selectionMode ? { cursor: 'pointer' } : undefined}
@@ -573,9 +603,11 @@ const MessageItem = memo(function MessageItem({
                 className={`inline-flex items-center gap-1 px-2.5 py-0.5 rounded-full text-xs font-medium ${
                   msg.displayType === 'user'
                     ? 'bg-primary text-primary-foreground'
-                    : msg.displayType === 'tool_result'
-                      ? 'bg-emerald-600 text-white'
-                      : 'bg-secondary text-secondary-foreground'
+                    : msg.isError
+                      ? 'bg-red-600 text-white'
+                      : msg.displayType === 'tool_result'
+                        ? 'bg-emerald-600 text-white'
+                        : 'bg-secondary text-secondary-foreground'
                 }`}
               >
                 {msg.displayType === 'user' ? (
@@ -587,9 +619,11 @@ const MessageItem = memo(function MessageItem({
                 )}
                 {msg.displayType === 'user'
                   ? '用户'
-                  : msg.displayType === 'tool_result'
-                    ? '工具结果'
-                    : '助手'}
+                  : msg.isError
+                    ? '工具失败'
+                    : msg.displayType === 'tool_result'
+                      ? '工具结果'
+                      : '助手'}
               </span>
               {/* 遗弃标签：不在主链上的消息 */}
               {msg.isAbandoned && (
@@ -610,7 +644,7 @@ const MessageItem = memo(function MessageItem({
             {!selectionMode && (
               <div className="flex items-center gap-1 opacity-0 group-hover:opacity-100 transition-opacity">
                 <button
-                  onClick={() => copyToClipboard(getDisplayText(msg))}
+                  onClick={() => copyMessage(sessionFilePath, msg)}
                   className="p-1.5 rounded hover:bg-accent transition-all hover:scale-110 active:scale-90"
                   title="复制"
                 >
@@ -625,6 +659,15 @@ const MessageItem = memo(function MessageItem({
                   <Edit2 className="w-4 h-4" />
                 </button>
                 )}
+                {msg.editable && (
+                <button
+                  onClick={() => onShowEditHistory(msg.sourceUuid)}
+                  className="p-1.5 rounded hover:bg-accent transition-all hover:scale-110 active:scale-90"
+                  title="编辑历史"
+                >
+                  <History className="w-4 h-4" />
+                </button>
+                )}
                 <button
                   onClick={() => onDeleteMessage(msg.sourceUuid)}
                   className="p-1.5 rounded hover:bg-destructive/10 text-destructive transition-all hover:scale-110 active:scale-90"
@@ -636,6 +679,14 @@ const MessageItem = memo(function MessageItem({
             )}
           </div>
 
+          {/* 截断提示：回复因 max_tokens 被截断，内容不完整 */}
+          {msg.stopReason === 'max_tokens' && (
+            <div className="flex items-center gap-1.5 mb-2 px-2 py-1 rounded-md text-xs bg-amber-100 text-amber-800 dark:bg-amber-900/30 dark:text-amber-300">
+              <AlertTriangle className="w-3.5 h-3.5 shrink-0" />
+              回复被截断（达到 max_tokens 上限），内容可能不完整
+            </div>
+          )}
+
           {/* 消息内容 */}
           {isEditing ? (
             <div className="space-y-2">
@@ -715,6 +766,8 @@ const MessageItem = memo(function MessageItem({
                         block={block}
                         projectPath={projectPath}
                         toolUseMap={toolUseMap}
+                        sessionFilePath={sessionFilePath}
+                        sourceUuid={msg.sourceUuid}
                       />
                     ))}
                 </div>
@@ -742,6 +795,8 @@ const MessageItem = memo(function MessageItem({
                 toolUseMap={toolUseMap}
                 searchHighlight={searchHighlight}
                 searchAutoExpand={searchAutoExpand}
+                sessionFilePath={sessionFilePath}
+                sourceUuid={msg.sourceUuid}
               />
             </div>
           )}
@@ -786,7 +841,8 @@ const FILTER_CONFIG: { type: FilterableType; icon: typeof User; label: string }[
  * - 多选模式：复选框选择、全选/取消全选、批量删除
  * - 显示每条消息的 Token 使用量和模型信息
  *
- * 当没有选中会话时，显示一个引导用户选择会话的空状态界面。
+ * 当没有选中会话时，显示一个引导用户选择会话的空状态界面；
+ * 未扫描到任何项目时在空状态页附带启动自检结果。
  *
  * @param props - 组件属性
  * @returns JSX 元素
@@ -824,13 +880,14 @@ export function ChatView({
   onToggleSelectionMode,
   sidebarCollapsed,
   onExpandSidebar,
-  // projects 保留在接口中但组件内不再直接使用（跳转按钮改为延迟解析路径）
-  projects: _projects,
+  // projects 仅用于空状态页判断是否需要展示启动自检（跳转按钮改为延迟解析路径）
+  projects,
   navBackTarget,
   onNavigateBack,
   onNavigateToSession,
   onOpenProxyPanel,
   onOpenRetrospect,
+  onDraftChange,
 }: ChatViewProps) {
   /** 当前正在编辑的消息 displayId，为 null 表示没有消息处于编辑状态 */
   const [editingId, setEditingId] = useState<string | null>(null);
@@ -851,12 +908,24 @@ export function ChatView({
   const [activeFilters, setActiveFilters] = useState<Set<FilterableType>>(new Set(ALL_FILTERS));
   /** 控制过滤器下拉菜单的显示/隐藏状态 */
   const [showFilterDropdown, setShowFilterDropdown] = useState(false);
+  /** 是否只看失败的工具调用 */
+  const [onlyFailedTools, setOnlyFailedTools] = useState(false);
+  /** 后端返回的失败工具调用 displayId 集合（null 表示未启用该筛选） */
+  const [failedToolIds, setFailedToolIds] = useState<Set<string> | null>(null);
   /** 控制导出下拉菜单的显示/隐藏状态 */
   const [showExportDropdown, setShowExportDropdown] = useState(false);
+  /** 导出时消息时间的显示方式（ISO / 本地时间 / 相对时间） */
+  const [exportTimeStyle, setExportTimeStyle] = useState<TimeStyle>('iso');
   /** 控制实用工具下拉菜单的显示/隐藏状态 */
   const [showToolsDropdown, setShowToolsDropdown] = useState(false);
   /** 控制一键修复弹窗的显示/隐藏状态 */
   const [showQuickFix, setShowQuickFix] = useState(false);
+  /** 控制会话引用链接弹窗的显示/隐藏状态 */
+  const [showLinks, setShowLinks] = useState(false);
+  /** 会话草稿弹窗可见性 */
+  const [showDraft, setShowDraft] = useState(false);
+  /** 正在查看编辑历史的消息 UUID，为 null 时不显示编辑历史弹窗 */
+  const [editHistoryUuid, setEditHistoryUuid] = useState<string | null>(null);
   /** 控制宠物管理弹窗的显示/隐藏状态 */
   const [showPetManager, setShowPetManager] = useState(false);
   /** 控制 Skills 管理面板的显示/隐藏状态 */
@@ -952,8 +1021,8 @@ export function ChatView({
     }
     const timer = setTimeout(async () => {
       try {
-        const ids = await searchSession(session.filePath, filterSearchQuery);
-        setFilterSearchResults(new Set(ids));
+        const hits = await searchSession(session.filePath, filterSearchQuery);
+        setFilterSearchResults(new Set(hits.map(hit => hit.displayId)));
       } catch (err) {
         console.error('搜索失败:', err);
         setFilterSearchResults(null);
@@ -963,7 +1032,24 @@ export function ChatView({
   }, [filterSearchQuery, session]);
 
   /**
-   * 组合筛选：类型多选 + 后端搜索结果交叉
+   * 失败调用筛选：启用时向后端查询失败的 tool_result 及发起调用的消息，
+   * 会话内容变化（编辑、删除、刷新）后重新查询
+   */
+  useEffect(() => {
+    if (!onlyFailedTools || !session) {
+      setFailedToolIds(null);
+      return;
+    }
+    getFailedToolCalls(session.filePath)
+      .then(ids => setFailedToolIds(new Set(ids)))
+      .catch(err => {
+        console.error('筛选失败调用失败:', err);
+        setFailedToolIds(null);
+      });
+  }, [onlyFailedTools, session, transformedSession]);
+
+  /**
+   * 组合筛选：类型多选 + 后端搜索结果交叉 + 失败调用筛选
    *
    * displayMessages 保持原始时间顺序（旧→新），前端通过 useProgressiveRender 实现视口优先加载。
    */
@@ -973,9 +1059,11 @@ export function ChatView({
       if (!activeFilters.has(msg.displayType as FilterableType)) return false;
       // 内容筛选搜索结果
       if (filterSearchResults !== null && !filterSearchResults.has(msg.displayId)) return false;
+      // 失败调用筛选结果
+      if (failedToolIds !== null && !failedToolIds.has(msg.displayId)) return false;
       return true;
     });
-  }, [displayMessages, activeFilters, filterSearchResults]);
+  }, [displayMessages, activeFilters, filterSearchResults, failedToolIds]);
 
   /** 过滤前的总显示消息数，用于显示 "N/M" 计数 */
   const totalMessages = displayMessages.length;
@@ -1108,13 +1196,13 @@ export function ChatView({
     const requestId = ++searchRequestIdRef.current;
 
     try {
-      const ids = await searchSession(session.filePath, query, {
+      const hits = await searchSession(session.filePath, query, {
         caseSensitive,
         useRegex,
       });
       // 异步返回后检查是否已被更新的请求取代
       if (requestId !== searchRequestIdRef.current) return;
-      const resultSet = new Set(ids);
+      const resultSet = new Set(hits.map(hit => hit.displayId));
       // 原子性同时更新结果集 + 高亮选项（React 18 自动批处理）
       setNavSearchResultSet(resultSet);
       setSearchHighlight(
@@ -1411,35 +1499,32 @@ export function ChatView({
     if (!session || !insertType || insertSaving || insertingAfterUuid === null) return;
     setInsertSaving(true);
     try {
-      const uuid = crypto.randomUUID();
-      const timestamp = new Date().toISOString();
-      const afterUuid = insertingAfterUuid ?? '';
-      const baseMessage: Record<string, unknown> = {
-        type: insertType, uuid,
-        parentUuid: afterUuid || null,
-        isSidechain: false,
-        sessionId: session.id,
-        timestamp,
-      };
-      switch (insertType) {
-        case 'user':
-          baseMessage.message = { role: 'user', content: insertContent };
-          break;
-        case 'assistant':
-          baseMessage.message = { role: 'assistant', content: [{ type: 'text', text: insertContent }] };
-          break;
-        case 'custom-title':
-          baseMessage.title = insertContent;
-          break;
-        case 'tag':
-          baseMessage.value = insertContent;
-          break;
-        case 'file-history-snapshot':
-        case 'queue-operation':
-          try { Object.assign(baseMessage, JSON.parse(insertContent)); } catch { baseMessage.content = insertContent; }
-          break;
+      // DropZone 上报空字符串表示会话开头
+      const position: InsertPosition = insertingAfterUuid ? { kind: 'after', uuid: insertingAfterUuid } : { kind: 'start' };
+      if (insertType === 'user' || insertType === 'assistant') {
+        // 对话消息由后端按 Claude Code 的 JSONL 结构构造
+        await insertMessage(session.filePath, position, insertType, insertContent);
+      } else {
+        const baseMessage: Record<string, unknown> = {
+          type: insertType, uuid: crypto.randomUUID(),
+          isSidechain: false,
+          sessionId: session.id,
+          timestamp: new Date().toISOString(),
+        };
+        switch (insertType) {
+          case 'custom-title':
+            baseMessage.title = insertContent;
+            break;
+          case 'tag':
+            baseMessage.value = insertContent;
+            break;
+          case 'file-history-snapshot':
+          case 'queue-operation':
+            try { Object.assign(baseMessage, JSON.parse(insertContent)); } catch { baseMessage.content = insertContent; }
+            break;
+        }
+        await insertRawMessage(session.filePath, position, baseMessage);
       }
-      await insertMessage(session.filePath, afterUuid, baseMessage);
       onRefresh();
     } catch (err) {
       console.error('插入消息失败:', err);
@@ -1558,15 +1643,30 @@ export function ChatView({
                 d="M8 12h.01M12 12h.01M16 12h.01M21 12c0 4.418-4.03 8-9 8a9.863 9.863 0 01-4.255-.949L3 20l1.395-3.72C3.512 15.042 3 13.574 3 12c0-4.418 4.03-8 9-8s9 3.582 9 8z"
               />
             </motion.svg>
-            <p className="text-lg gradient-text">选择一个会话来查看聊天记录</p>
+            <p className="text-lg gradient-text">
+              {projects.length === 0 ? '没有找到任何 Claude Code 会话' : '选择一个会话来查看聊天记录'}
+            </p>
+            {/* 未扫描到项目时展示引导页与环境自检，帮助排查安装、路径与权限问题 */}
+            {projects.length === 0 && (
+              <div className="mt-6 space-y-4">
+                <OnboardingGuide />
+                <StartupDiagnostics />
+              </div>
+            )}
+            {/* 已选中项目时展示项目说明（CLAUDE.md / README.md 开头部分） */}
+            {projects.length > 0 && projectPath && (
+              <div className="mt-6">
+                <ProjectSummaryCard projectPath={projectPath} />
+              </div>
+            )}
           </motion.div>
         </div>
       </div>
     );
   }
 
-  /** 筛选器是否处于非全选状态（类型不全选 或 内容筛选搜索有关键词） */
-  const isFiltered = activeFilters.size !== ALL_FILTERS.length || filterSearchQuery.trim() !== '';
+  /** 筛选器是否处于非全选状态（类型不全选 或 内容筛选搜索有关键词 或 只看失败调用） */
+  const isFiltered = activeFilters.size !== ALL_FILTERS.length || filterSearchQuery.trim() !== '' || onlyFailedTools;
 
   return (
     <div className="flex-1 flex flex-col bg-background min-w-0">
@@ -1585,8 +1685,17 @@ export function ChatView({
             </motion.button>
           )}
           <div className="min-w-[8rem]">
-            <h2 className="text-lg font-semibold text-foreground truncate">
-              会话: {session.name || session.id.substring(0, 8)}
+            <h2 className="text-lg font-semibold text-foreground truncate flex items-center gap-2">
+              会话: {session.alias || session.name || session.id.substring(0, 8)}
+              {/* 只读锁定标记：锁定的会话拒绝一切写入，需在侧边栏解锁 */}
+              {session.locked && (
+                <span
+                  className="inline-flex items-center gap-1 px-2 py-0.5 rounded-full text-xs font-medium bg-amber-100 text-amber-800 dark:bg-amber-900/30 dark:text-amber-300 shrink-0"
+                  title="该会话已锁定为只读，编辑、删除、修复都会被拒绝；可在侧边栏解锁"
+                >
+                  <Lock className="w-3 h-3" /> 只读
+                </span>
+              )}
             </h2>
             <p className="text-sm text-muted-foreground break-words">
               {formatTimestamp(session.timestamp)} ·{' '}
@@ -1601,6 +1710,12 @@ export function ChatView({
                   {tokenStats.cacheCreationInputTokens > 0 && ` · 缓存创建: ${tokenStats.cacheCreationInputTokens.toLocaleString()}`}
                 </span>
               )}
+              {/* 工具调用失败计数 */}
+              {tokenStats && tokenStats.failedToolCalls > 0 && (
+                <span className="ml-2 text-red-500">
+                  · 失败调用: {tokenStats.failedToolCalls}/{tokenStats.toolCalls}
+                </span>
+              )}
             </p>
           </div>
         </div>
@@ -1657,7 +1772,9 @@ export function ChatView({
                       setShowToolsDropdown(false);
                       if (!session || !projectPath) return;
                       try {
-                        await openResumeTerminal(projectPath, session.id);
+                        // 有草稿时作为首条输入发送，后端在终端启动后清除草稿
+                        await openResumeTerminal(projectPath, session.id, session.hasDraft ? session.filePath : undefined);
+                        if (session.hasDraft) onDraftChange(false);
                       } catch (err) {
                         console.error('一键 Resume 失败:', err);
                       }
@@ -1665,7 +1782,7 @@ export function ChatView({
                     className="w-full flex items-center gap-2 px-3 py-2 text-sm hover:bg-accent/50 transition-colors"
                   >
                     <Terminal className="w-4 h-4" />
-                    <span>一键 Resume</span>
+                    <span>{session?.hasDraft ? '一键 Resume（附草稿）' : '一键 Resume'}</span>
                   </button>
                   {/* 复制 Resume 指令：将命令字符串复制到剪贴板 */}
                   <button
@@ -1673,7 +1790,7 @@ export function ChatView({
                       setShowToolsDropdown(false);
                       if (!session) return;
                       try {
-                        const cmd = await buildResumeCommand(session.id);
+                        const cmd = await buildResumeCommand(session.id, session.hasDraft ? session.filePath : undefined);
                         await navigator.clipboard.writeText(cmd);
                         setResumeCopyToast('已复制');
                         setTimeout(() => setResumeCopyToast(''), 2000);
@@ -1688,6 +1805,18 @@ export function ChatView({
                     <ClipboardCopy className="w-4 h-4" />
                     <span>复制 Resume 指令</span>
                   </button>
+                  {/* 会话草稿：记录下次 resume 时要补充的内容 */}
+                  <button
+                    onClick={() => {
+                      setShowToolsDropdown(false);
+                      setShowDraft(true);
+                    }}
+                    className="w-full flex items-center gap-2 px-3 py-2 text-sm hover:bg-accent/50 transition-colors"
+                  >
+                    <NotebookPen className="w-4 h-4" />
+                    <span>会话草稿</span>
+                    {session?.hasDraft && <span className="ml-auto w-1.5 h-1.5 rounded-full bg-primary" />}
+                  </button>
                   {/* 一键修复：打开修复弹窗 */}
                   <button
                     onClick={() => {
@@ -1699,6 +1828,17 @@ export function ChatView({
                     <Wrench className="w-4 h-4" />
                     <span>一键修复</span>
                   </button>
+                  {/* 引用的链接：打开会话链接清单弹窗 */}
+                  <button
+                    onClick={() => {
+                      setShowToolsDropdown(false);
+                      setShowLinks(true);
+                    }}
+                    className="w-full flex items-center gap-2 px-3 py-2 text-sm hover:bg-accent/50 transition-colors"
+                  >
+                    <Link2 className="w-4 h-4" />
+                    <span>引用的链接</span>
+                  </button>
                   {/* 中转抓包：打开代理面板 */}
                   <button
                     onClick={() => {
@@ -1923,6 +2063,19 @@ export function ChatView({
                       <span className="flex-1 text-left">{label}</span>
                     </button>
                   ))}
+                  {/* 只看失败的工具调用 */}
+                  <button
+                    onClick={() => setOnlyFailedTools(!onlyFailedTools)}
+                    className="w-full flex items-center gap-2 px-3 py-2 text-sm transition-colors hover:bg-accent/50 border-t border-border/50"
+                  >
+                    {onlyFailedTools ? (
+                      <CheckSquare className="w-4 h-4 text-primary" />
+                    ) : (
+                      <Square className="w-4 h-4" />
+                    )}
+                    <XCircle className="w-4 h-4 text-red-500" />
+                    <span className="flex-1 text-left">只看失败的调用</span>
+                  </button>
                 </motion.div>
               )}
             </AnimatePresence>
@@ -1946,17 +2099,31 @@ export function ChatView({
                   animate={{ opacity: 1, y: 0, scale: 1 }}
                   exit={{ opacity: 0, y: -8, scale: 0.95 }}
                   transition={{ duration: 0.15 }}
-                  className="absolute right-0 top-full mt-1 w-44 bg-popover border border-border rounded-lg shadow-lg z-50 overflow-hidden"
+                  className="absolute right-0 top-full mt-1 w-52 bg-popover border border-border rounded-lg shadow-lg z-50 overflow-hidden"
                 >
+                  {/* 消息时间显示方式（仅影响 Markdown，相对时间以导出时刻为基准） */}
+                  <div className="flex gap-1 px-2 py-2 border-b border-border">
+                    {([['iso', 'ISO'], ['local', '本地时间'], ['relative', '相对时间']] as const).map(([style, label]) => (
+                      <button
+                        key={style}
+                        onClick={() => setExportTimeStyle(style)}
+                        className={`flex-1 px-1.5 py-1 text-xs rounded transition-colors ${
+                          exportTimeStyle === style ? 'bg-primary text-primary-foreground' : 'hover:bg-accent/50 text-muted-foreground'
+                        }`}
+                      >
+                        {label}
+                      </button>
+                    ))}
+                  </div>
                   <button
-                    onClick={() => { onExport('markdown'); setShowExportDropdown(false); }}
+                    onClick={() => { onExport('markdown', exportTimeStyle); setShowExportDropdown(false); }}
                     className="w-full flex items-center gap-2 px-3 py-2 text-sm hover:bg-accent/50 transition-colors"
                   >
                     <FileText className="w-4 h-4" />
                     <span>Markdown</span>
                   </button>
                   <button
-                    onClick={() => { onExport('json'); setShowExportDropdown(false); }}
+                    onClick={() => { onExport('json', exportTimeStyle); setShowExportDropdown(false); }}
                     className="w-full flex items-center gap-2 px-3 py-2 text-sm hover:bg-accent/50 transition-colors"
                   >
                     <FileJson className="w-4 h-4" />
@@ -2190,6 +2357,7 @@ export function ChatView({
                     index={index}
                     isRendered={isRendered(index)}
                     projectPath={projectPath}
+                    sessionFilePath={session.filePath}
                     toolUseMap={toolUseMap}
                     searchHighlight={navSearchResultSet.has(msg.displayId) ? searchHighlight : undefined}
                     searchAutoExpand={searchAutoExpandId === msg.displayId}
@@ -2200,6 +2368,7 @@ export function ChatView({
                     onToggleSelect={onToggleSelect}
                     onDeleteMessage={onDeleteMessage}
                     onStartEdit={handleStartEdit}
+                    onShowEditHistory={setEditHistoryUuid}
                     onSaveEdit={handleSaveEdit}
                     onCancelEdit={handleCancelEdit}
                     onEditBlockChange={setEditBlocks}
@@ -2316,12 +2485,12 @@ export function ChatView({
               className="inline-flex items-center gap-1.5 px-4 py-2 rounded-full
                          bg-primary text-primary-foreground shadow-lg hover:bg-primary/90
                          transition-colors text-sm font-medium"
-              title={`返回: ${navBackTarget.session.name || navBackTarget.session.id.substring(0, 8)}`}
+              title={`返回: ${navBackTarget.session.alias || navBackTarget.session.name || navBackTarget.session.id.substring(0, 8)}`}
               whileHover={{ scale: 1.05 }}
               whileTap={{ scale: 0.95 }}
             >
               <ArrowLeft className="w-4 h-4" />
-              返回: {navBackTarget.session.name || navBackTarget.session.id.substring(0, 8)}
+              返回: {navBackTarget.session.alias || navBackTarget.session.name || navBackTarget.session.id.substring(0, 8)}
             </motion.button>
           </motion.div>
         )}
@@ -2338,6 +2507,39 @@ export function ChatView({
         )}
       </AnimatePresence>
 
+      {/* 会话引用链接弹窗 */}
+      <AnimatePresence>
+        {showLinks && session && (
+          <SessionLinksModal
+            sessionFilePath={session.filePath}
+            onClose={() => setShowLinks(false)}
+          />
+        )}
+      </AnimatePresence>
+
+      {/* 消息编辑历史弹窗 */}
+      <AnimatePresence>
+        {editHistoryUuid && session && (
+          <MessageEditHistoryModal
+            sessionFilePath={session.filePath}
+            messageUuid={editHistoryUuid}
+            onClose={() => setEditHistoryUuid(null)}
+            onReverted={onRefresh}
+          />
+        )}
+      </AnimatePresence>
+
+      {/* 会话草稿弹窗 */}
+      <AnimatePresence>
+        {showDraft && session && (
+          <SessionDraftModal
+            sessionFilePath={session.filePath}
+            onClose={() => setShowDraft(false)}
+            onDraftChange={onDraftChange}
+          />
+        )}
+      </AnimatePresence>
+
       {/* 宠物管理弹窗 */}
       <AnimatePresence>
         {showPetManager && (
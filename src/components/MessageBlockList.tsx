@@ -43,6 +43,10 @@ interface MessageBlockListProps {
    * false/undefined 时不干预。
    */
   searchAutoExpand?: boolean;
+  /** 当前会话 JSONL 文件路径（超长文本块按需读取全文时使用） */
+  sessionFilePath?: string;
+  /** 内容块所属消息的 UUID（超长文本块按需读取全文时使用） */
+  sourceUuid?: string;
 }
 
 /**
@@ -57,7 +61,7 @@ interface MessageBlockListProps {
  * @param props - 包含待渲染的内容块数组和上下文信息
  * @returns 渲染后的 JSX 元素
  */
-export const MessageBlockList = memo(function MessageBlockList({ content, projectPath, toolUseMap, searchHighlight, searchAutoExpand }: MessageBlockListProps) {
+export const MessageBlockList = memo(function MessageBlockList({ content, projectPath, toolUseMap, searchHighlight, searchAutoExpand, sessionFilePath, sourceUuid }: MessageBlockListProps) {
   if (content.length === 0) {
     return (
       <div className="text-xs text-muted-foreground italic">
@@ -76,6 +80,8 @@ export const MessageBlockList = memo(function MessageBlockList({ content, projec
           toolUseMap={toolUseMap}
           searchHighlight={searchHighlight}
           searchAutoExpand={searchAutoExpand}
+          sessionFilePath={sessionFilePath}
+          sourceUuid={sourceUuid}
         />
       ))}
     </div>
@@ -8,6 +8,7 @@
  * - tool_result：工具结果块 → 委托给 ToolResultRenderer（折叠 + 打开文件位置）
  * - thinking：AI 思考过程块（默认折叠，受 useCollapsible 控制）
  * - image：图片内容块（通过 Base64 data URI 渲染）
+ * - oversized_text：超长文本块占位（显示预览，按需从后端读取全文）
  *
  * 性能优化：
  * - 使用 React.memo 包裹，props 不变时跳过重渲染
@@ -18,13 +19,14 @@
  * - 搜索跳转时自动展开折叠内容，离开时自动收起（手动展开的不受影响）
  */
 
-import { memo } from 'react';
-import { Lightbulb, ChevronRight, ChevronDown } from 'lucide-react';
+import { memo, useState } from 'react';
+import { Lightbulb, ChevronRight, ChevronDown, FileText } from 'lucide-react';
 import type { MessageContent, ToolUseInfo, SearchHighlight } from '../types/claude';
 import { ToolUseRenderer } from './ToolUseRenderer';
 import { ToolResultRenderer } from './ToolResultRenderer';
 import { MarkdownRenderer } from './MarkdownRenderer';
 import { useCollapsible } from '../hooks/useCollapsible';
+import { readFullBlock } from '../utils/claudeData';
 
 /**
  * MessageContentRenderer 组件的属性接口
@@ -48,6 +50,10 @@ interface MessageContentRendererProps {
    * false/undefined 时不干预。
    */
   searchAutoExpand?: boolean;
+  /** 当前会话 JSONL 文件路径（oversized_text 读取全文时使用，缺省时不提供加载按钮） */
+  sessionFilePath?: string;
+  /** 内容块所属消息的 UUID（oversized_text 读取全文时使用） */
+  sourceUuid?: string;
 }
 
 /**
@@ -89,6 +95,70 @@ function ThinkingBlock({ content, searchHighlight, searchAutoExpand }: {
   );
 }
 
+/**
+ * OversizedTextBlock - 超长文本块占位
+ *
+ * 后端把超过阈值的 text 块（常见于 base64 或超长单行）替换为只含预览的占位，
+ * 避免整块进入 Markdown 渲染。用户点击后才读取全文，并以纯文本显示。
+ *
+ * @param block - oversized_text 占位块
+ * @param sessionFilePath - 会话文件路径（缺省时只显示预览）
+ * @param sourceUuid - 所属消息的 UUID
+ */
+function OversizedTextBlock({ block, sessionFilePath, sourceUuid }: {
+  block: MessageContent;
+  sessionFilePath?: string;
+  sourceUuid?: string;
+}) {
+  const [fullText, setFullText] = useState<string | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const canLoad = Boolean(sessionFilePath && sourceUuid) && block.block_index !== undefined;
+
+  const handleLoad = async () => {
+    if (!sessionFilePath || !sourceUuid || block.block_index === undefined) return;
+    setLoading(true);
+    setError(null);
+    try {
+      setFullText(await readFullBlock(sessionFilePath, sourceUuid, block.block_index));
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  return (
+    <div className="content-block animate-scale-in">
+      <div className="flex items-center gap-2 text-xs text-muted-foreground mb-1">
+        <FileText className="w-3.5 h-3.5 shrink-0" />
+        <span>内容过长（{(block.original_length ?? 0).toLocaleString()} 字节），仅显示预览</span>
+        {canLoad && fullText === null && (
+          <button
+            onClick={handleLoad}
+            disabled={loading}
+            className="px-2 py-0.5 rounded bg-secondary text-secondary-foreground hover:bg-secondary/80 transition-colors disabled:opacity-50"
+          >
+            {loading ? '加载中...' : '加载全文'}
+          </button>
+        )}
+        {fullText !== null && (
+          <button
+            onClick={() => setFullText(null)}
+            className="px-2 py-0.5 rounded bg-secondary text-secondary-foreground hover:bg-secondary/80 transition-colors"
+          >
+            收起
+          </button>
+        )}
+      </div>
+      {error && <div className="text-xs text-destructive mb-1">读取失败：{error}</div>}
+      <pre className="whitespace-pre-wrap break-all text-xs max-h-96 overflow-auto">
+        {fullText ?? `${block.preview ?? ''}…`}
+      </pre>
+    </div>
+  );
+}
+
 /**
  * MessageContentRenderer - 单个消息内容块的渲染组件（React.memo 优化）
  *
@@ -98,6 +168,7 @@ function ThinkingBlock({ content, searchHighlight, searchAutoExpand }: {
  * - tool_result: 委托给 ToolResultRenderer（折叠 + 打开文件位置）
  * - thinking: 受控折叠 + 斜体淡色（通过 ThinkingBlock + useCollapsible）
  * - image: 内联图片展示
+ * - oversized_text: 预览 + 按需加载全文（通过 OversizedTextBlock）
  *
  * 所有入场动画使用 CSS @keyframes（animate-msg-in / animate-scale-in），
  * 不引入 framer-motion，由浏览器合成线程执行，不阻塞主线程。
@@ -105,7 +176,7 @@ function ThinkingBlock({ content, searchHighlight, searchAutoExpand }: {
  * @param props - 包含待渲染的内容块对象
  * @returns 渲染后的 JSX 元素
  */
-export const MessageContentRenderer = memo(function MessageContentRenderer({ block, projectPath, toolUseMap, searchHighlight, searchAutoExpand }: MessageContentRendererProps) {
+export const MessageContentRenderer = memo(function MessageContentRenderer({ block, projectPath, toolUseMap, searchHighlight, searchAutoExpand, sessionFilePath, sourceUuid }: MessageContentRendererProps) {
   switch (block.type) {
     /* ====== 文本内容块（Markdown 渲染） ====== */
     case 'text':
@@ -170,6 +241,10 @@ export const MessageContentRenderer = memo(function MessageContentRenderer({ blo
         </div>
       );
 
+    /* ====== 超长文本块占位（预览 + 按需加载全文） ====== */
+    case 'oversized_text':
+      return <OversizedTextBlock block={block} sessionFilePath={sessionFilePath} sourceUuid={sourceUuid} />;
+
     /* ====== 未知类型的降级处理 ====== */
     default:
       return (
@@ -0,0 +1,159 @@
+/**
+ * @file MessageEditHistoryModal.tsx - 消息编辑历史弹窗组件
+ * @description
+ * 列出某条消息被 CCR 编辑前的历史版本（由 Rust 后端 `get_message_edit_history` 提供），
+ * 可展开查看完整原文，并通过 `revert_message_to_version` 用历史版本覆盖当前消息。
+ * UI 模式复用 QuickFixModal 的模态框样式。
+ */
+
+import { useState, useEffect } from 'react';
+import { motion } from 'motion/react';
+import { X, History, Loader2, RotateCcw, ChevronRight, ChevronDown } from 'lucide-react';
+import type { MessageEditVersion } from '../types/claude';
+import { getMessageEditHistory, revertMessageToVersion, formatTimestamp } from '../utils/claudeData';
+
+/**
+ * MessageEditHistoryModal 组件的属性接口
+ */
+interface MessageEditHistoryModalProps {
+  /** 当前会话 JSONL 文件的绝对路径 */
+  sessionFilePath: string;
+  /** 目标消息的 UUID */
+  messageUuid: string;
+  /** 关闭弹窗的回调函数 */
+  onClose: () => void;
+  /** 回滚成功后的回调函数（用于刷新会话数据） */
+  onReverted: () => void;
+}
+
+/**
+ * 消息编辑历史弹窗组件
+ *
+ * 挂载时加载历史版本列表（最新在前）；回滚成功后关闭弹窗并刷新会话。
+ */
+export function MessageEditHistoryModal({ sessionFilePath, messageUuid, onClose, onReverted }: MessageEditHistoryModalProps) {
+  /** 历史版本列表 */
+  const [versions, setVersions] = useState<MessageEditVersion[]>([]);
+  /** 加载历史列表时的加载状态 */
+  const [loading, setLoading] = useState(true);
+  /** 当前展开查看原文的版本 ID */
+  const [expandedId, setExpandedId] = useState<string | null>(null);
+  /** 正在回滚的版本 ID，防止重复提交 */
+  const [revertingId, setRevertingId] = useState<string | null>(null);
+
+  /** 组件挂载时加载历史版本 */
+  useEffect(() => {
+    getMessageEditHistory(sessionFilePath, messageUuid)
+      .then((data) => {
+        setVersions(data);
+        setLoading(false);
+      })
+      .catch((err) => {
+        console.error('读取消息编辑历史失败:', err);
+        setLoading(false);
+      });
+  }, [sessionFilePath, messageUuid]);
+
+  /** 用指定历史版本覆盖当前消息 */
+  const handleRevert = async (versionId: string) => {
+    setRevertingId(versionId);
+    try {
+      await revertMessageToVersion(sessionFilePath, messageUuid, versionId);
+      onReverted();
+      onClose();
+    } catch (err) {
+      console.error('恢复历史版本失败:', err);
+    } finally {
+      setRevertingId(null);
+    }
+  };
+
+  return (
+    /* 模态遮罩层：点击遮罩关闭弹窗 */
+    <motion.div
+      className="fixed inset-0 bg-black/50 backdrop-blur-sm flex items-center justify-center z-50"
+      initial={{ opacity: 0 }}
+      animate={{ opacity: 1 }}
+      exit={{ opacity: 0 }}
+      onClick={onClose}
+    >
+      {/* 弹窗主体：阻止点击冒泡 */}
+      <motion.div
+        className="bg-card rounded-xl shadow-xl w-[640px] h-[70vh] flex flex-col border border-border overflow-hidden"
+        initial={{ scale: 0.95, y: 20, opacity: 0 }}
+        animate={{ scale: 1, y: 0, opacity: 1 }}
+        exit={{ scale: 0.95, y: 20, opacity: 0 }}
+        onClick={(e) => e.stopPropagation()}
+      >
+        {/* 头部：标题 + 关闭按钮 */}
+        <div className="p-4 border-b border-border flex items-center justify-between bg-card shrink-0">
+          <div className="flex items-center gap-2">
+            <History className="w-5 h-5 text-primary" />
+            <h2 className="text-lg font-semibold text-foreground">编辑历史</h2>
+            {!loading && (
+              <span className="text-xs text-muted-foreground">{versions.length} 个版本</span>
+            )}
+          </div>
+          <motion.button
+            onClick={onClose}
+            className="p-2 rounded-lg hover:bg-accent transition-colors"
+            whileHover={{ scale: 1.1 }}
+            whileTap={{ scale: 0.9 }}
+          >
+            <X className="w-5 h-5" />
+          </motion.button>
+        </div>
+
+        {/* 版本列表 */}
+        <div className="flex-1 overflow-y-auto p-3 custom-scrollbar">
+          {loading ? (
+            <div className="flex items-center justify-center py-12 text-muted-foreground">
+              <Loader2 className="w-5 h-5 animate-spin mr-2" />
+              <span className="text-sm">读取编辑历史...</span>
+            </div>
+          ) : versions.length === 0 ? (
+            <div className="text-center py-12 text-muted-foreground text-sm">
+              这条消息还没有在 CCR 中被编辑过
+            </div>
+          ) : (
+            <div className="space-y-2">
+              {versions.map((version) => {
+                const expanded = expandedId === version.versionId;
+                return (
+                  <div key={version.versionId} className="rounded-lg border border-border bg-muted/30">
+                    <div className="flex items-center gap-2 px-3 py-2">
+                      <button
+                        onClick={() => setExpandedId(expanded ? null : version.versionId)}
+                        className="flex-1 min-w-0 flex items-center gap-2 text-left"
+                      >
+                        {expanded ? <ChevronDown className="w-4 h-4 shrink-0" /> : <ChevronRight className="w-4 h-4 shrink-0" />}
+                        <span className="text-xs text-muted-foreground shrink-0">{formatTimestamp(version.savedAt)}</span>
+                        <span className="text-sm text-foreground truncate">{version.summary || '（无文本内容）'}</span>
+                      </button>
+                      <button
+                        onClick={() => handleRevert(version.versionId)}
+                        disabled={revertingId !== null}
+                        className="flex items-center gap-1 px-2 py-1 rounded text-xs hover:bg-accent transition-colors disabled:opacity-50 shrink-0"
+                        title="用此版本覆盖当前消息"
+                      >
+                        {revertingId === version.versionId
+                          ? <Loader2 className="w-3.5 h-3.5 animate-spin" />
+                          : <RotateCcw className="w-3.5 h-3.5" />}
+                        恢复
+                      </button>
+                    </div>
+                    {expanded && (
+                      <pre className="px-3 pb-3 text-xs text-muted-foreground whitespace-pre-wrap break-all max-h-80 overflow-y-auto custom-scrollbar">
+                        {JSON.stringify(version.message, null, 2)}
+                      </pre>
+                    )}
+                  </div>
+                );
+              })}
+            </div>
+          )}
+        </div>
+      </motion.div>
+    </motion.div>
+  );
+}
@@ -216,6 +216,7 @@ export const NavSearchBar = forwardRef<NavSearchBarHandle, NavSearchBarProps>(
               }
             }}
             placeholder="搜索消息..."
+            title="支持字段限定：tool:bash、model:opus、cwd:src"
             className={`w-full pl-8 pr-[4.25rem] py-1.5 rounded-lg bg-secondary text-foreground border focus:outline-none focus:border-ring text-sm ${
               regexError ? 'border-destructive' : 'border-border'
             }`}
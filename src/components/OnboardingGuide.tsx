@@ -0,0 +1,80 @@
+/**
+ * @file OnboardingGuide.tsx - 首次使用引导页
+ * @description
+ * 在空状态页（未扫描到任何项目）根据 Rust 后端 `get_onboarding_state` 的结果展示引导：
+ * - 未安装 Claude Code（`~/.claude/` 不存在）：提示安装并运行一次 `claude`
+ * - 已安装但还没有项目：提示在项目目录中开始第一个会话
+ * 已有项目时不渲染（列表为空可能只是筛选导致）。
+ */
+
+import { useState, useEffect } from 'react';
+import { motion } from 'motion/react';
+import { Download, Rocket, Terminal } from 'lucide-react';
+import type { OnboardingState } from '../types/claude';
+import { getOnboardingState } from '../utils/claudeData';
+
+/** 行内命令样式 */
+const CODE_CLASS = 'px-1.5 py-0.5 rounded bg-muted text-xs font-mono';
+
+/**
+ * 首次使用引导页
+ *
+ * 挂载时查询一次引导状态。
+ */
+export function OnboardingGuide() {
+  /** 引导状态（查询完成前为 null） */
+  const [state, setState] = useState<OnboardingState | null>(null);
+
+  useEffect(() => {
+    getOnboardingState()
+      .then(setState)
+      .catch((err) => console.error('获取引导状态失败:', err));
+  }, []);
+
+  if (!state || state.status === 'ok') return null;
+
+  const notInstalled = state.status === 'claudeNotInstalled';
+  const Icon = notInstalled ? Download : Rocket;
+
+  return (
+    <motion.div
+      initial={{ opacity: 0, y: 20 }}
+      animate={{ opacity: 1, y: 0 }}
+      className="w-full max-w-xl mx-auto bg-card border border-border rounded-xl p-4 text-left"
+    >
+      <div className="flex items-center gap-2 mb-3">
+        <Icon className="w-5 h-5 text-primary" />
+        <span className="font-medium text-foreground">
+          {notInstalled ? '安装 Claude Code' : '开始第一个会话'}
+        </span>
+      </div>
+
+      <ol className="space-y-2 text-sm text-foreground list-decimal list-inside">
+        {/* 未在 PATH 中找到 claude 时先提示安装 */}
+        {!state.cliPath && (
+          <li>
+            安装 Claude Code：<code className={CODE_CLASS}>npm install -g @anthropic-ai/claude-code</code>
+          </li>
+        )}
+        <li>
+          在任意项目目录中运行 <code className={CODE_CLASS}>claude</code> 并完成一次对话
+        </li>
+        <li>回到 CCR 刷新项目列表，会话记录即会出现在左侧</li>
+      </ol>
+
+      <div className="mt-3 space-y-1 text-xs text-muted-foreground">
+        {state.cliPath && (
+          <p className="flex items-center gap-1 truncate" title={state.cliPath}>
+            <Terminal className="w-3.5 h-3.5 shrink-0" />
+            已找到 claude CLI：{state.cliPath}
+          </p>
+        )}
+        {state.claudeDir && (
+          <p className="truncate" title={state.claudeDir}>
+            {notInstalled ? '数据目录尚未创建：' : '数据目录：'}{state.claudeDir}
+          </p>
+        )}
+      </div>
+    </motion.div>
+  );
+}
@@ -0,0 +1,74 @@
+/**
+ * @file ProjectSummaryCard.tsx - 项目摘要卡片
+ * @description
+ * 在选中项目但未打开会话时，展示项目目录下 CLAUDE.md（优先）或 README.md 的开头部分，
+ * 帮助用户快速回忆项目用途。内容由 Rust 后端 `get_project_summary` 只读获取并缓存。
+ */
+
+import { useState, useEffect } from 'react';
+import { BookOpen, Loader2 } from 'lucide-react';
+import type { ProjectSummary } from '../types/claude';
+import { getProjectSummary } from '../utils/claudeData';
+import { MarkdownRenderer } from './MarkdownRenderer';
+
+/**
+ * ProjectSummaryCard 组件的属性接口
+ */
+interface ProjectSummaryCardProps {
+  /** 项目的真实文件系统路径（已解码） */
+  projectPath: string;
+}
+
+/**
+ * 项目摘要卡片
+ *
+ * 项目路径变化时重新加载；没有摘要文件或读取失败时不渲染任何内容。
+ */
+export function ProjectSummaryCard({ projectPath }: ProjectSummaryCardProps) {
+  /** 摘要内容，null 表示没有摘要文件 */
+  const [summary, setSummary] = useState<ProjectSummary | null>(null);
+  /** 加载中 */
+  const [loading, setLoading] = useState(true);
+
+  useEffect(() => {
+    // 快速切换项目时丢弃过期的返回结果
+    let cancelled = false;
+    setLoading(true);
+    getProjectSummary(projectPath)
+      .then((data) => { if (!cancelled) setSummary(data); })
+      .catch((err) => {
+        console.error('读取项目摘要失败:', err);
+        if (!cancelled) setSummary(null);
+      })
+      .finally(() => { if (!cancelled) setLoading(false); });
+    return () => { cancelled = true; };
+  }, [projectPath]);
+
+  if (loading) {
+    return (
+      <div className="flex items-center justify-center gap-2 text-sm text-muted-foreground">
+        <Loader2 className="w-4 h-4 animate-spin" />
+        <span>读取项目说明...</span>
+      </div>
+    );
+  }
+  if (!summary) return null;
+
+  const fileName = summary.filePath.split(/[\\/]/).pop();
+
+  return (
+    <div className="w-[560px] max-w-full text-left bg-card border border-border rounded-xl overflow-hidden">
+      <div className="px-4 py-2 border-b border-border flex items-center gap-2 text-sm text-foreground">
+        <BookOpen className="w-4 h-4 text-primary" />
+        <span className="font-medium">{fileName}</span>
+        <span className="truncate text-xs text-muted-foreground" title={summary.filePath}>{summary.filePath}</span>
+      </div>
+      <div className="max-h-[50vh] overflow-y-auto p-4 custom-scrollbar">
+        <MarkdownRenderer content={summary.text} className="text-sm" />
+        {summary.truncated && (
+          <p className="mt-2 text-xs text-muted-foreground">……仅显示开头部分</p>
+        )}
+      </div>
+    </div>
+  );
+}
@@ -13,7 +13,7 @@ import { useState, useEffect, useMemo } from 'react';
 import { motion, AnimatePresence } from 'motion/react';
 import { X, Search, ArrowLeft, Wrench, CheckCircle, XCircle, Loader2, ChevronRight, List, FileText, HardDrive, ShieldAlert } from 'lucide-react';
 import type { FixDefinition, FixResult, FixLevel, FixOptionDef } from '../types/claude';
-import { listFixers, executeFixer } from '../utils/claudeData';
+import { listFixers, executeFixer, openInExplorer } from '../utils/claudeData';
 
 /**
  * 档位标注配置
@@ -155,6 +155,7 @@ export function QuickFixModal({
         success: false,
         message: `执行修复失败: ${err}`,
         affectedLines: 0,
+        elapsedMs: 0,
       });
     } finally {
       setExecuting(false);
@@ -423,8 +424,18 @@ export function QuickFixModal({
                       {result.success && result.affectedLines > 0 && (
                         <p className="text-xs ml-6 mt-1 opacity-75">
                           受影响的消息行数: {result.affectedLines}
+                          {' · '}耗时 {result.elapsedMs} ms
                         </p>
                       )}
+                      {result.backupPath && (
+                        <button
+                          onClick={() => openInExplorer(result.backupPath!)}
+                          className="text-xs ml-6 mt-1 opacity-75 hover:opacity-100 underline break-all text-left"
+                          title="在文件管理器中打开"
+                        >
+                          已备份到 {result.backupPath}
+                        </button>
+                      )}
                     </motion.div>
                   )}
                 </div>
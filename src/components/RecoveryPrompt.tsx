@@ -0,0 +1,163 @@
+/**
+ * @file RecoveryPrompt.tsx - 异常退出恢复提示弹窗
+ * @description
+ * 应用启动时检测到会话目录下残留的 `xxx.jsonl.tmp`（上次写入过程中崩溃）时弹出，
+ * 逐个展示残留与正式文件的修改时间、大小和完整性比对结果，
+ * 由用户选择丢弃残留、用残留覆盖正式文件，或保留两者（残留另存为新会话）。
+ * 数据由 Rust 后端 `get_recovery_candidates` / `resolve_recovery` 提供。
+ */
+
+import { useState } from 'react';
+import { motion } from 'motion/react';
+import { X, LifeBuoy, Loader2, Trash2, FileInput, Copy } from 'lucide-react';
+import type { RecoveryCandidate, RecoveryAction, BackupCheck } from '../types/claude';
+import { resolveRecovery, formatTimestamp } from '../utils/claudeData';
+
+/**
+ * RecoveryPrompt 组件的属性接口
+ */
+interface RecoveryPromptProps {
+  /** 启动时检测到的写入残留 */
+  candidates: RecoveryCandidate[];
+  /** 关闭弹窗的回调函数（稍后处理，残留保留到下次启动） */
+  onClose: () => void;
+  /** 任一残留处理完成后的回调函数（用于刷新项目列表） */
+  onResolved: () => void;
+}
+
+/** 各处理方式的按钮文案与图标 */
+const ACTIONS: { action: RecoveryAction; label: string; icon: typeof Trash2 }[] = [
+  { action: 'discard', label: '丢弃残留', icon: Trash2 },
+  { action: 'overwrite', label: '用残留覆盖', icon: FileInput },
+  { action: 'keepBoth', label: '另存为新会话', icon: Copy },
+];
+
+/** 完整性检查结果的简短描述 */
+function describeCheck(check: BackupCheck | null): string {
+  if (!check) return '不存在';
+  if (check.invalidLines === 0) return `${check.validLines} 行，完整`;
+  return `${check.validLines} 行有效，${check.invalidLines} 行损坏`;
+}
+
+/** 字节数转 KB 文本 */
+function formatSize(bytes: number | null): string {
+  return bytes === null ? '-' : `${(bytes / 1024).toFixed(1)} KB`;
+}
+
+/**
+ * 异常退出恢复提示弹窗
+ *
+ * 已处理的残留从列表移除；全部处理完毕后自动关闭。
+ */
+export function RecoveryPrompt({ candidates, onClose, onResolved }: RecoveryPromptProps) {
+  /** 尚未处理的残留 */
+  const [pending, setPending] = useState(candidates);
+  /** 正在处理的残留路径，防止重复提交 */
+  const [resolvingPath, setResolvingPath] = useState<string | null>(null);
+
+  /** 处理一个残留 */
+  const handleResolve = async (candidate: RecoveryCandidate, action: RecoveryAction) => {
+    setResolvingPath(candidate.tmpPath);
+    try {
+      await resolveRecovery(candidate.tmpPath, action);
+      const rest = pending.filter((c) => c.tmpPath !== candidate.tmpPath);
+      setPending(rest);
+      onResolved();
+      if (rest.length === 0) onClose();
+    } catch (err) {
+      console.error('处理写入残留失败:', err);
+    } finally {
+      setResolvingPath(null);
+    }
+  };
+
+  return (
+    /* 模态遮罩层：点击遮罩关闭弹窗 */
+    <motion.div
+      className="fixed inset-0 bg-black/50 backdrop-blur-sm flex items-center justify-center z-50"
+      initial={{ opacity: 0 }}
+      animate={{ opacity: 1 }}
+      exit={{ opacity: 0 }}
+      onClick={onClose}
+    >
+      {/* 弹窗主体：阻止点击冒泡 */}
+      <motion.div
+        className="bg-card rounded-xl shadow-xl w-[640px] max-h-[80vh] flex flex-col border border-border overflow-hidden"
+        initial={{ scale: 0.95, y: 20, opacity: 0 }}
+        animate={{ scale: 1, y: 0, opacity: 1 }}
+        exit={{ scale: 0.95, y: 20, opacity: 0 }}
+        onClick={(e) => e.stopPropagation()}
+      >
+        {/* 头部：标题 + 关闭按钮 */}
+        <div className="p-4 border-b border-border flex items-center justify-between bg-card shrink-0">
+          <div className="flex items-center gap-2">
+            <LifeBuoy className="w-5 h-5 text-primary" />
+            <h2 className="text-lg font-semibold text-foreground">检测到上次可能未完成的写入</h2>
+          </div>
+          <motion.button
+            onClick={onClose}
+            className="p-2 rounded-lg hover:bg-accent transition-colors"
+            whileHover={{ scale: 1.1 }}
+            whileTap={{ scale: 0.9 }}
+            title="稍后处理"
+          >
+            <X className="w-5 h-5" />
+          </motion.button>
+        </div>
+
+        <p className="px-4 pt-3 text-xs text-muted-foreground">
+          应用在写入会话时意外退出，留下了以下临时文件。覆盖前会自动备份当前会话文件。
+        </p>
+
+        {/* 残留列表 */}
+        <div className="flex-1 overflow-y-auto p-4 space-y-3 custom-scrollbar">
+          {pending.map((candidate) => (
+            <div key={candidate.tmpPath} className="rounded-lg border border-border bg-muted/30 p-3 text-sm">
+              <div className="truncate text-foreground font-medium" title={candidate.targetPath}>
+                {candidate.targetPath.split(/[\\/]/).pop()}
+              </div>
+              <div className="truncate text-xs text-muted-foreground mb-2" title={candidate.targetPath}>
+                {candidate.targetPath}
+              </div>
+              <table className="w-full text-xs text-muted-foreground mb-3">
+                <tbody>
+                  <tr>
+                    <td className="pr-2 text-foreground">残留 .tmp</td>
+                    <td className="pr-2">{formatTimestamp(candidate.tmpModified)}</td>
+                    <td className="pr-2">{formatSize(candidate.tmpSize)}</td>
+                    <td>{describeCheck(candidate.tmpCheck)}</td>
+                  </tr>
+                  <tr>
+                    <td className="pr-2 text-foreground">正式文件</td>
+                    <td className="pr-2">{candidate.targetModified ? formatTimestamp(candidate.targetModified) : '-'}</td>
+                    <td className="pr-2">{formatSize(candidate.targetSize)}</td>
+                    <td>{describeCheck(candidate.targetCheck)}</td>
+                  </tr>
+                </tbody>
+              </table>
+              <div className="flex items-center justify-end gap-2">
+                {resolvingPath === candidate.tmpPath && <Loader2 className="w-4 h-4 animate-spin text-muted-foreground" />}
+                {ACTIONS.map(({ action, label, icon: Icon }) => (
+                  <button
+                    key={action}
+                    onClick={() => handleResolve(candidate, action)}
+                    disabled={resolvingPath !== null}
+                    className={`flex items-center gap-1 px-2 py-1 rounded text-xs transition-colors disabled:opacity-50 ${
+                      candidate.suggestedAction === action
+                        ? 'bg-primary text-primary-foreground hover:bg-primary/90'
+                        : 'hover:bg-accent'
+                    }`}
+                    title={candidate.suggestedAction === action ? '建议的处理方式' : undefined}
+                  >
+                    <Icon className="w-3.5 h-3.5" />
+                    {label}
+                  </button>
+                ))}
+              </div>
+            </div>
+          ))}
+        </div>
+      </motion.div>
+    </motion.div>
+  );
+}
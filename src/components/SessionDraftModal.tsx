@@ -0,0 +1,156 @@
+/**
+ * @file SessionDraftModal.tsx - 会话草稿弹窗组件
+ * @description
+ * 为当前会话记录「下次 resume 要补充的内容」。草稿由 Rust 后端保存在
+ * `~/.mo/CCR/drafts.json`，不写入会话 JSONL；一键 Resume 时作为首条输入发送并自动清除。
+ * UI 模式复用 QuickFixModal 的模态框样式。
+ */
+
+import { useState, useEffect } from 'react';
+import { motion } from 'motion/react';
+import { X, NotebookPen, Loader2, Trash2, Save } from 'lucide-react';
+import { getSessionDraft, saveSessionDraft, deleteSessionDraft } from '../utils/claudeData';
+
+/**
+ * SessionDraftModal 组件的属性接口
+ */
+interface SessionDraftModalProps {
+  /** 当前会话 JSONL 文件的绝对路径 */
+  sessionFilePath: string;
+  /** 关闭弹窗的回调函数 */
+  onClose: () => void;
+  /** 草稿保存或删除后的回调函数，参数为保存后是否仍有草稿 */
+  onDraftChange: (hasDraft: boolean) => void;
+}
+
+/**
+ * 会话草稿弹窗组件
+ *
+ * 挂载时从 Rust 后端加载已有草稿；保存空白内容等同于删除草稿。
+ */
+export function SessionDraftModal({ sessionFilePath, onClose, onDraftChange }: SessionDraftModalProps) {
+  /** 草稿文本 */
+  const [text, setText] = useState('');
+  /** 加载已有草稿时的加载状态 */
+  const [loading, setLoading] = useState(true);
+  /** 保存 / 删除进行中，防止重复提交 */
+  const [saving, setSaving] = useState(false);
+
+  /** 组件挂载时加载已有草稿 */
+  useEffect(() => {
+    getSessionDraft(sessionFilePath)
+      .then((draft) => {
+        setText(draft ?? '');
+        setLoading(false);
+      })
+      .catch((err) => {
+        console.error('读取会话草稿失败:', err);
+        setLoading(false);
+      });
+  }, [sessionFilePath]);
+
+  /** 保存草稿（空白内容由后端视为删除） */
+  const handleSave = async () => {
+    setSaving(true);
+    try {
+      await saveSessionDraft(sessionFilePath, text);
+      onDraftChange(text.trim().length > 0);
+      onClose();
+    } catch (err) {
+      console.error('保存会话草稿失败:', err);
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  /** 删除草稿 */
+  const handleDelete = async () => {
+    setSaving(true);
+    try {
+      await deleteSessionDraft(sessionFilePath);
+      onDraftChange(false);
+      onClose();
+    } catch (err) {
+      console.error('删除会话草稿失败:', err);
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    /* 模态遮罩层：点击遮罩关闭弹窗 */
+    <motion.div
+      className="fixed inset-0 bg-black/50 backdrop-blur-sm flex items-center justify-center z-50"
+      initial={{ opacity: 0 }}
+      animate={{ opacity: 1 }}
+      exit={{ opacity: 0 }}
+      onClick={onClose}
+    >
+      {/* 弹窗主体：阻止点击冒泡 */}
+      <motion.div
+        className="bg-card rounded-xl shadow-xl w-[520px] flex flex-col border border-border overflow-hidden"
+        initial={{ scale: 0.95, y: 20, opacity: 0 }}
+        animate={{ scale: 1, y: 0, opacity: 1 }}
+        exit={{ scale: 0.95, y: 20, opacity: 0 }}
+        onClick={(e) => e.stopPropagation()}
+      >
+        {/* 头部：标题 + 关闭按钮 */}
+        <div className="p-4 border-b border-border flex items-center justify-between bg-card shrink-0">
+          <div className="flex items-center gap-2">
+            <NotebookPen className="w-5 h-5 text-primary" />
+            <h2 className="text-lg font-semibold text-foreground">会话草稿</h2>
+          </div>
+          <motion.button
+            onClick={onClose}
+            className="p-2 rounded-lg hover:bg-accent transition-colors"
+            whileHover={{ scale: 1.1 }}
+            whileTap={{ scale: 0.9 }}
+          >
+            <X className="w-5 h-5" />
+          </motion.button>
+        </div>
+
+        {/* 草稿编辑区 */}
+        <div className="p-4 flex flex-col gap-2">
+          {loading ? (
+            <div className="flex items-center justify-center py-12 text-muted-foreground">
+              <Loader2 className="w-5 h-5 animate-spin mr-2" />
+              <span className="text-sm">读取草稿...</span>
+            </div>
+          ) : (
+            <textarea
+              value={text}
+              onChange={(e) => setText(e.target.value)}
+              placeholder="下次 resume 时要补充的问题..."
+              className="w-full h-40 p-3 rounded-lg bg-muted text-foreground border border-border focus:outline-none focus:ring-2 focus:ring-ring text-sm resize-none custom-scrollbar"
+              autoFocus
+            />
+          )}
+          <p className="text-xs text-muted-foreground">
+            草稿仅保存在 CCR 配置中，不会写入会话文件；一键 Resume 时作为首条输入发送并自动清除。
+          </p>
+        </div>
+
+        {/* 底部操作 */}
+        <div className="p-4 border-t border-border flex items-center justify-end gap-2">
+          <button
+            onClick={handleDelete}
+            disabled={loading || saving}
+            className="flex items-center gap-1.5 px-3 py-1.5 rounded-lg text-sm text-destructive hover:bg-destructive/10 transition-colors disabled:opacity-50"
+          >
+            <Trash2 className="w-4 h-4" />
+            删除草稿
+          </button>
+          <button
+            onClick={handleSave}
+            disabled={loading || saving}
+            className="flex items-center gap-1.5 px-3 py-1.5 rounded-lg text-sm bg-primary text-primary-foreground hover:bg-primary/90 transition-colors disabled:opacity-50"
+          >
+            <Save className="w-4 h-4" />
+            保存
+          </button>
+        </div>
+      </motion.div>
+    </motion.div>
+  );
+}
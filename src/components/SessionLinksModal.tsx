@@ -0,0 +1,155 @@
+/**
+ * @file SessionLinksModal.tsx - 会话引用链接弹窗组件
+ * @description
+ * 展示当前会话中引用过的所有 http/https 链接（由 Rust 后端 `extract_links` 提取并去重），
+ * 每项附带首次出现处的上下文片段与出现次数，点击通过 opener 插件在系统浏览器中打开。
+ * UI 模式复用 QuickFixModal 的模态框样式。
+ */
+
+import { useState, useEffect, useMemo } from 'react';
+import { motion } from 'motion/react';
+import { X, Search, Link2, Loader2, ExternalLink } from 'lucide-react';
+import type { SessionLink } from '../types/claude';
+import { extractLinks, openUrl } from '../utils/claudeData';
+
+/**
+ * SessionLinksModal 组件的属性接口
+ */
+interface SessionLinksModalProps {
+  /** 当前会话 JSONL 文件的绝对路径 */
+  sessionFilePath: string;
+  /** 关闭弹窗的回调函数 */
+  onClose: () => void;
+}
+
+/**
+ * 会话引用链接弹窗组件
+ *
+ * 挂载时从 Rust 后端加载链接列表，支持按 URL 与上下文过滤。
+ */
+export function SessionLinksModal({ sessionFilePath, onClose }: SessionLinksModalProps) {
+  /** 会话中的链接列表（按首次出现顺序） */
+  const [links, setLinks] = useState<SessionLink[]>([]);
+  /** 加载链接列表时的加载状态 */
+  const [loading, setLoading] = useState(true);
+  /** 过滤关键词 */
+  const [searchQuery, setSearchQuery] = useState('');
+
+  /** 组件挂载时从 Rust 后端提取链接 */
+  useEffect(() => {
+    extractLinks(sessionFilePath)
+      .then((data) => {
+        setLinks(data);
+        setLoading(false);
+      })
+      .catch((err) => {
+        console.error('提取会话链接失败:', err);
+        setLoading(false);
+      });
+  }, [sessionFilePath]);
+
+  /** 按关键词过滤（大小写不敏感，匹配 URL 与上下文） */
+  const filteredLinks = useMemo(() => {
+    if (!searchQuery.trim()) return links;
+
+    const query = searchQuery.toLowerCase();
+    return links.filter((link) =>
+      link.url.toLowerCase().includes(query) || link.context.toLowerCase().includes(query)
+    );
+  }, [links, searchQuery]);
+
+  return (
+    /* 模态遮罩层：点击遮罩关闭弹窗 */
+    <motion.div
+      className="fixed inset-0 bg-black/50 backdrop-blur-sm flex items-center justify-center z-50"
+      initial={{ opacity: 0 }}
+      animate={{ opacity: 1 }}
+      exit={{ opacity: 0 }}
+      onClick={onClose}
+    >
+      {/* 弹窗主体：阻止点击冒泡 */}
+      <motion.div
+        className="bg-card rounded-xl shadow-xl w-[600px] h-[70vh] flex flex-col border border-border overflow-hidden"
+        initial={{ scale: 0.95, y: 20, opacity: 0 }}
+        animate={{ scale: 1, y: 0, opacity: 1 }}
+        exit={{ scale: 0.95, y: 20, opacity: 0 }}
+        onClick={(e) => e.stopPropagation()}
+      >
+        {/* 头部：标题 + 关闭按钮 */}
+        <div className="p-4 border-b border-border flex items-center justify-between bg-card shrink-0">
+          <div className="flex items-center gap-2">
+            <Link2 className="w-5 h-5 text-primary" />
+            <h2 className="text-lg font-semibold text-foreground">本会话引用的链接</h2>
+            {!loading && (
+              <span className="text-xs text-muted-foreground">{links.length} 个</span>
+            )}
+          </div>
+          <motion.button
+            onClick={onClose}
+            className="p-2 rounded-lg hover:bg-accent transition-colors"
+            whileHover={{ scale: 1.1 }}
+            whileTap={{ scale: 0.9 }}
+          >
+            <X className="w-5 h-5" />
+          </motion.button>
+        </div>
+
+        {/* 搜索框 */}
+        <div className="p-3 border-b border-border shrink-0">
+          <div className="relative">
+            <Search className="absolute left-3 top-1/2 -translate-y-1/2 w-4 h-4 text-muted-foreground" />
+            <input
+              type="text"
+              value={searchQuery}
+              onChange={(e) => setSearchQuery(e.target.value)}
+              placeholder="过滤链接（URL、上下文）..."
+              className="w-full pl-9 pr-3 py-2 rounded-lg bg-muted text-foreground border border-border focus:outline-none focus:ring-2 focus:ring-ring text-sm"
+              autoFocus
+            />
+          </div>
+        </div>
+
+        {/* 链接列表 */}
+        <div className="flex-1 overflow-y-auto p-3 custom-scrollbar">
+          {loading ? (
+            <div className="flex items-center justify-center py-12 text-muted-foreground">
+              <Loader2 className="w-5 h-5 animate-spin mr-2" />
+              <span className="text-sm">提取链接...</span>
+            </div>
+          ) : filteredLinks.length === 0 ? (
+            <div className="text-center py-12 text-muted-foreground">
+              <Link2 className="w-8 h-8 mx-auto mb-3 opacity-50" />
+              <p className="text-sm">
+                {searchQuery ? '未找到匹配的链接' : '本会话没有引用任何链接'}
+              </p>
+            </div>
+          ) : (
+            <div className="space-y-2">
+              {filteredLinks.map((link) => (
+                <button
+                  key={link.url}
+                  onClick={() => openUrl(link.url).catch((err) => console.error('打开链接失败:', err))}
+                  className="w-full flex items-start gap-3 p-3 rounded-lg bg-muted/50 hover:bg-accent/50 transition-colors text-left group"
+                  title={link.url}
+                >
+                  <ExternalLink className="w-4 h-4 text-primary shrink-0 mt-0.5" />
+                  <div className="flex-1 min-w-0">
+                    <p className="text-sm font-medium text-primary break-all">
+                      {link.url}
+                    </p>
+                    <p className="text-xs text-muted-foreground line-clamp-2 mt-1">
+                      {link.context}
+                    </p>
+                  </div>
+                  {link.occurrences > 1 && (
+                    <span className="text-xs text-muted-foreground shrink-0">×{link.occurrences}</span>
+                  )}
+                </button>
+              ))}
+            </div>
+          )}
+        </div>
+      </motion.div>
+    </motion.div>
+  );
+}
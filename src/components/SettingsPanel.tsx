@@ -2,7 +2,7 @@ import { useState, useEffect } from 'react';
 import { motion, AnimatePresence } from 'motion/react';
 import { X, Palette, Bot, Shield, Info, Eye, EyeOff, Plus, Trash2, Github, Sun, SunMoon, Moon, Wrench, CheckSquare, Square } from 'lucide-react';
 import type { ClaudeSettings, EnvProfile, ResumeConfig, BackupConfig } from '../types/claude';
-import { readResumeConfig, saveResumeConfig, readBackupConfig, saveBackupConfig } from '../utils/claudeData';
+import { readResumeConfig, saveResumeConfig, readBackupConfig, saveBackupConfig, cleanupTempArtifacts, getDisplayTimezone, saveDisplayTimezone, setFormattingTimezone } from '../utils/claudeData';
 
 /**
  * 设置面板组件的属性接口
@@ -50,8 +50,14 @@ export function SettingsPanel({
   const [showApiKey, setShowApiKey] = useState(false);
   /** 一键 Resume 配置（独立于 Claude Code settings，存储在 CCR 配置目录） */
   const [resumeConfig, setResumeConfig] = useState<ResumeConfig>({ flags: [], customArgs: '' });
-  /** 备份配置（控制主动备份的启用状态） */
-  const [backupConfig, setBackupConfig] = useState<BackupConfig>({ autoBackupEnabled: false });
+  /** 备份配置（控制主动备份的启用状态与临时资源保留天数） */
+  const [backupConfig, setBackupConfig] = useState<BackupConfig>({ autoBackupEnabled: false, tempRetentionDays: 7, auditRetentionDays: 90 });
+  /** 显示时区输入框内容（'local' / 'utc' / ±HH:MM） */
+  const [timezoneInput, setTimezoneInput] = useState('local');
+  /** 显示时区保存失败的错误提示（空字符串表示不显示） */
+  const [timezoneError, setTimezoneError] = useState('');
+  /** 手动清理临时资源的结果提示（空字符串表示不显示） */
+  const [cleanupMessage, setCleanupMessage] = useState('');
 
   /**
    * 可勾选的常用 Claude CLI flag 列表
@@ -87,8 +93,25 @@ export function SettingsPanel({
     readBackupConfig()
       .then(setBackupConfig)
       .catch((err) => console.error('加载备份配置失败:', err));
+    getDisplayTimezone()
+      .then(setTimezoneInput)
+      .catch((err) => console.error('加载显示时区失败:', err));
   }, []);
 
+  /**
+   * 保存显示时区：后端校验并规范化后同步到前端时间格式化
+   */
+  const handleSaveTimezone = async () => {
+    try {
+      const saved = await saveDisplayTimezone(timezoneInput.trim());
+      setTimezoneInput(saved);
+      setFormattingTimezone(saved);
+      setTimezoneError('');
+    } catch (err) {
+      setTimezoneError(String(err));
+    }
+  };
+
   /**
    * 更新指定环境变量的值
    * @param key - 环境变量名称
@@ -296,6 +319,24 @@ export function SettingsPanel({
                     {claudeDataPath}
                   </div>
                 </div>
+
+                {/* 显示时区：影响时间显示、导出、归档文件名与按天统计 */}
+                <div>
+                  <label className="block text-sm font-medium text-foreground mb-2">显示时区</label>
+                  <input
+                    type="text"
+                    value={timezoneInput}
+                    onChange={(e) => setTimezoneInput(e.target.value)}
+                    onBlur={handleSaveTimezone}
+                    onKeyDown={(e) => e.key === 'Enter' && handleSaveTimezone()}
+                    placeholder="local、utc 或 +08:00"
+                    className="w-full px-3 py-2 rounded-lg bg-muted text-foreground border border-border focus:outline-none focus:ring-2 focus:ring-ring font-mono text-sm"
+                  />
+                  <p className="mt-1 text-xs text-muted-foreground">
+                    local 跟随系统时区，utc 为协调世界时，也可填写固定偏移如 +08:00、-05:00
+                  </p>
+                  {timezoneError && <p className="mt-1 text-xs text-destructive">{timezoneError}</p>}
+                </div>
               </motion.div>
             )}
 
@@ -487,6 +528,72 @@ export function SettingsPanel({
                       </p>
                     </div>
                   </button>
+
+                  {/* 临时资源保留天数 + 手动清理 */}
+                  <div className="flex items-center gap-3 px-3 py-2">
+                    <div className="flex-1 min-w-0">
+                      <span className="text-sm text-foreground">临时资源保留天数</span>
+                      <p className="text-xs text-muted-foreground">
+                        退出时清理超龄的临时图片与导出残留（0 表示不自动清理，临时备份不受影响）
+                      </p>
+                    </div>
+                    <input
+                      type="number"
+                      min={0}
+                      value={backupConfig.tempRetentionDays}
+                      onChange={(e) => {
+                        const days = Math.max(0, Math.floor(Number(e.target.value) || 0));
+                        const newConfig = { ...backupConfig, tempRetentionDays: days };
+                        setBackupConfig(newConfig);
+                        saveBackupConfig(newConfig).catch(err =>
+                          console.error('保存备份配置失败:', err)
+                        );
+                      }}
+                      className="w-16 px-2 py-1 rounded-lg bg-muted text-foreground border border-border focus:outline-none focus:ring-2 focus:ring-ring text-sm"
+                    />
+                    <button
+                      onClick={async () => {
+                        try {
+                          const report = await cleanupTempArtifacts();
+                          setCleanupMessage(`已清理 ${report.removedFiles} 个文件，释放 ${(report.freedBytes / 1024 / 1024).toFixed(1)} MB`);
+                        } catch (err) {
+                          console.error('清理临时资源失败:', err);
+                          setCleanupMessage('清理失败');
+                        }
+                        setTimeout(() => setCleanupMessage(''), 3000);
+                      }}
+                      className="px-3 py-1 rounded-lg text-sm hover:bg-accent transition-colors border border-border shrink-0"
+                    >
+                      立即清理
+                    </button>
+                  </div>
+                  {cleanupMessage && (
+                    <p className="text-xs text-muted-foreground px-3">{cleanupMessage}</p>
+                  )}
+
+                  {/* 审计日志保留天数 */}
+                  <div className="flex items-center gap-3 px-3 py-2">
+                    <div className="flex-1 min-w-0">
+                      <span className="text-sm text-foreground">审计日志保留天数</span>
+                      <p className="text-xs text-muted-foreground">
+                        退出时清理超龄的写入 / 删除审计记录（0 表示永久保留）
+                      </p>
+                    </div>
+                    <input
+                      type="number"
+                      min={0}
+                      value={backupConfig.auditRetentionDays}
+                      onChange={(e) => {
+                        const days = Math.max(0, Math.floor(Number(e.target.value) || 0));
+                        const newConfig = { ...backupConfig, auditRetentionDays: days };
+                        setBackupConfig(newConfig);
+                        saveBackupConfig(newConfig).catch(err =>
+                          console.error('保存备份配置失败:', err)
+                        );
+                      }}
+                      className="w-16 px-2 py-1 rounded-lg bg-muted text-foreground border border-border focus:outline-none focus:ring-2 focus:ring-ring text-sm"
+                    />
+                  </div>
                 </div>
               </motion.div>
             )}
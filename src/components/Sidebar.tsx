@@ -7,7 +7,7 @@
 
 import { useState } from 'react';
 import { motion, AnimatePresence } from 'motion/react';
-import { Settings, ChevronLeft, Search, ChevronRight, Trash2 } from 'lucide-react';
+import { Settings, ChevronLeft, Search, ChevronRight, Trash2, Lock, Unlock, NotebookPen, ExternalLink } from 'lucide-react';
 import type { Project, Session, EnvProfile, EnvSwitcherConfig } from '../types/claude';
 import { formatTimestamp } from '../utils/claudeData';
 import { EnvSwitcher } from './EnvSwitcher';
@@ -34,6 +34,10 @@ interface SidebarProps {
   onSelectSession: (session: Session) => void;
   /** 删除会话时触发的回调，接收会话文件路径 */
   onDeleteSession: (sessionFilePath: string) => void;
+  /** 切换会话只读锁定状态的回调 */
+  onToggleSessionLock: (session: Session) => void;
+  /** 在独立窗口中打开会话的回调 */
+  onOpenSessionWindow: (session: Session) => void;
   /** 打开设置面板的回调 */
   onOpenSettings: () => void;
   /** 切换环境配置时触发的回调 */
@@ -71,6 +75,8 @@ export function Sidebar({
   onSelectProject,
   onSelectSession,
   onDeleteSession,
+  onToggleSessionLock,
+  onOpenSessionWindow,
   onOpenSettings,
   onSwitchEnvProfile,
   onSaveEnvProfile,
@@ -254,13 +260,53 @@ export function Sidebar({
                         onClick={() => onSelectSession(session)}
                       >
                         {/* 会话名称：超长时显示水平滚动条 */}
-                        <div className="text-sm text-foreground whitespace-nowrap overflow-x-auto pr-6 custom-scrollbar">
-                          {session.name || session.id.substring(0, 8)}
+                        <div className="text-sm text-foreground whitespace-nowrap overflow-x-auto pr-20 custom-scrollbar flex items-center gap-1">
+                          {session.locked && (
+                            <span title="已锁定为只读"><Lock className="w-3 h-3 shrink-0 text-amber-500" /></span>
+                          )}
+                          {session.hasDraft && (
+                            <span title="有待发送的草稿"><NotebookPen className="w-3 h-3 shrink-0 text-primary" /></span>
+                          )}
+                          {session.alias || session.name || session.id.substring(0, 8)}
+                          {session.updatedSinceViewed && (
+                            <span title="查看后有新消息" className="w-1.5 h-1.5 shrink-0 rounded-full bg-primary" />
+                          )}
                         </div>
                         <div className="text-xs text-muted-foreground whitespace-nowrap">
                           {formatTimestamp(session.timestamp)}
                         </div>
-                        {/* 删除按钮：使用 motion.button 添加悬停/点击效果，hover 时显示，阻止事件冒泡防止触发会话选择 */}
+                        {/* 新窗口打开按钮：hover 时显示，用于左右对照查看两个会话 */}
+                        <motion.button
+                          onClick={(e) => {
+                            e.stopPropagation();
+                            onOpenSessionWindow(session);
+                          }}
+                          className={`absolute top-1/2 -translate-y-1/2 p-1 rounded opacity-0 group-hover:opacity-100 hover:bg-accent text-muted-foreground transition-all ${
+                            session.locked ? 'right-8' : 'right-14'
+                          }`}
+                          title="在新窗口中打开"
+                          whileHover={{ scale: 1.1 }}
+                          whileTap={{ scale: 0.9 }}
+                        >
+                          <ExternalLink className="w-3.5 h-3.5" />
+                        </motion.button>
+                        {/* 锁定切换按钮：hover 时显示，锁定后该会话拒绝一切写入 */}
+                        <motion.button
+                          onClick={(e) => {
+                            e.stopPropagation();
+                            onToggleSessionLock(session);
+                          }}
+                          className={`absolute top-1/2 -translate-y-1/2 p-1 rounded opacity-0 group-hover:opacity-100 hover:bg-accent text-muted-foreground transition-all ${
+                            session.locked ? 'right-2' : 'right-8'
+                          }`}
+                          title={session.locked ? '解锁会话' : '锁定会话（只读）'}
+                          whileHover={{ scale: 1.1 }}
+                          whileTap={{ scale: 0.9 }}
+                        >
+                          {session.locked ? <Unlock className="w-3.5 h-3.5" /> : <Lock className="w-3.5 h-3.5" />}
+                        </motion.button>
+                        {/* 删除按钮：使用 motion.button 添加悬停/点击效果，hover 时显示，阻止事件冒泡防止触发会话选择；锁定的会话不显示 */}
+                        {!session.locked && (
                         <motion.button
                           onClick={(e) => {
                             e.stopPropagation();
@@ -273,6 +319,7 @@ export function Sidebar({
                         >
                           <Trash2 className="w-3.5 h-3.5" />
                         </motion.button>
+                        )}
                       </motion.div>
                     ))}
                   </motion.div>
@@ -0,0 +1,92 @@
+/**
+ * @file StartupDiagnostics.tsx - 启动自检结果面板
+ * @description
+ * 在空状态页（未扫描到任何项目）展示 Rust 后端 `run_startup_diagnostics` 的逐项检查结果，
+ * 帮助新用户判断是未安装 Claude Code、还是路径 / 权限 / 磁盘空间问题，并给出修复建议。
+ */
+
+import { useState, useEffect, useCallback } from 'react';
+import { motion } from 'motion/react';
+import { CheckCircle2, XCircle, Loader2, RefreshCw, Stethoscope } from 'lucide-react';
+import type { DiagnosticItem } from '../types/claude';
+import { runStartupDiagnostics } from '../utils/claudeData';
+
+/**
+ * 启动自检结果面板
+ *
+ * 挂载时执行一次自检，可手动重新检查。
+ */
+export function StartupDiagnostics() {
+  /** 检查结果列表（按后端固定顺序） */
+  const [items, setItems] = useState<DiagnosticItem[]>([]);
+  /** 自检执行中 */
+  const [loading, setLoading] = useState(true);
+
+  /** 执行自检 */
+  const runDiagnostics = useCallback(() => {
+    setLoading(true);
+    runStartupDiagnostics()
+      .then(setItems)
+      .catch((err) => console.error('启动自检失败:', err))
+      .finally(() => setLoading(false));
+  }, []);
+
+  useEffect(() => {
+    runDiagnostics();
+  }, [runDiagnostics]);
+
+  const failedCount = items.filter((item) => !item.ok).length;
+
+  return (
+    <motion.div
+      initial={{ opacity: 0, y: 20 }}
+      animate={{ opacity: 1, y: 0 }}
+      className="w-full max-w-xl mx-auto bg-card border border-border rounded-xl p-4 text-left"
+    >
+      {/* 标题栏：结论摘要 + 重新检查 */}
+      <div className="flex items-center justify-between mb-3">
+        <div className="flex items-center gap-2">
+          <Stethoscope className="w-5 h-5 text-primary" />
+          <span className="font-medium text-foreground">环境自检</span>
+          {!loading && (
+            <span className={`text-xs ${failedCount > 0 ? 'text-red-500' : 'text-muted-foreground'}`}>
+              {failedCount > 0 ? `${failedCount} 项异常` : '全部正常'}
+            </span>
+          )}
+        </div>
+        <button
+          onClick={runDiagnostics}
+          disabled={loading}
+          className="p-1.5 rounded-lg hover:bg-accent transition-colors text-muted-foreground disabled:opacity-50"
+          title="重新检查"
+        >
+          {loading ? <Loader2 className="w-4 h-4 animate-spin" /> : <RefreshCw className="w-4 h-4" />}
+        </button>
+      </div>
+
+      {/* 检查项列表 */}
+      <div className="space-y-2">
+        {items.map((item) => (
+          <div key={item.name} className="flex items-start gap-2 text-sm">
+            {item.ok ? (
+              <CheckCircle2 className="w-4 h-4 mt-0.5 shrink-0 text-green-500" />
+            ) : (
+              <XCircle className="w-4 h-4 mt-0.5 shrink-0 text-red-500" />
+            )}
+            <div className="min-w-0">
+              <div className="flex items-baseline gap-2">
+                <span className="text-foreground shrink-0">{item.name}</span>
+                <span className="text-xs text-muted-foreground truncate" title={item.detail}>
+                  {item.detail}
+                </span>
+              </div>
+              {item.suggestion && (
+                <p className="text-xs text-amber-600 dark:text-amber-400 mt-0.5">{item.suggestion}</p>
+              )}
+            </div>
+          </div>
+        ))}
+      </div>
+    </motion.div>
+  );
+}
@@ -9,7 +9,7 @@
  * - 展开/收起带平滑高度动画
  * - 收起时自动滚动定位，避免用户丢失上下文
  * - Read/Write/Edit 工具结果带"打开文件位置"按钮
- * - 文件不存在时按钮禁用
+ * - 文件不存在时按钮禁用（存在性查询经 checkFileExistsBatched 合并为批量请求）
  * - 错误结果用红色样式高亮
  */
 
@@ -21,7 +21,7 @@ import {
 } from 'lucide-react';
 import type { MessageContent, ToolUseInfo, SearchHighlight } from '../types/claude';
 import { formatToolArgs } from '../utils/toolFormatter';
-import { checkFileExists, openInExplorer } from '../utils/claudeData';
+import { checkFileExistsBatched, openInExplorer } from '../utils/claudeData';
 import { useCollapsible } from '../hooks/useCollapsible';
 import { HighlightedText } from './HighlightedText';
 
@@ -129,13 +129,13 @@ export function ToolResultRenderer({
     return lines.slice(COLLAPSE_LINE_THRESHOLD).join('\n');
   }, [shouldCollapse, lines]);
 
-  // 检查关联文件是否存在
+  // 检查关联文件是否存在：组件在消息可见时才挂载，同一时间窗口内的查询合并为一次批量 IPC
   useEffect(() => {
     if (!filePath) {
       setFileExists(null);
       return;
     }
-    checkFileExists(filePath).then(setFileExists);
+    checkFileExistsBatched(filePath).then(setFileExists);
   }, [filePath]);
 
   /** 处理"打开文件位置"按钮点击 */
@@ -34,6 +34,48 @@ export interface ClaudeSettings {
   apiKey?: string;
 }
 
+/**
+ * 状态栏配置（settings.json 的 `statusLine` 字段）
+ *
+ * 对应 Rust 后端 `services::appearance::StatusLineConfig` 结构体。
+ */
+export interface StatusLineConfig {
+  /** 状态栏类型，目前只有 "command" */
+  type: string;
+  /** 生成状态栏内容的命令 */
+  command: string;
+  /** 状态栏左右内边距（字符数） */
+  padding?: number;
+}
+
+/**
+ * 自定义输出样式文件（`~/.claude/output-styles/*.md`）
+ *
+ * 对应 Rust 后端 `services::appearance::OutputStyleFile` 结构体。
+ */
+export interface OutputStyleFile {
+  /** 样式名（frontmatter 的 name，缺失时取文件名） */
+  name: string;
+  /** 样式描述 */
+  description: string | null;
+  /** 样式文件的绝对路径 */
+  filePath: string;
+}
+
+/**
+ * 外观配置：状态栏、当前输出样式与自定义样式列表
+ *
+ * 对应 Rust 后端 `services::appearance::AppearanceConfig` 结构体。
+ */
+export interface AppearanceConfig {
+  /** 状态栏配置（未配置时为 null） */
+  statusLine: StatusLineConfig | null;
+  /** 当前输出样式名（未设置时为 "default"） */
+  outputStyle: string;
+  /** 自定义样式列表，按名称排序 */
+  outputStyles: OutputStyleFile[];
+}
+
 /**
  * 环境配置组接口
  *
@@ -67,6 +109,20 @@ export interface EnvSwitcherConfig {
   activeProfileId: string | null;
 }
 
+/**
+ * 宽容读取的配置文件接口
+ *
+ * 严格 JSON 解析失败时后端会退回 JSONC 容错解析（剥离注释与尾逗号），此时 `wasLenient` 为 true；
+ * 保存这类文件会输出标准 JSON，注释将丢失，需要用户确认。
+ * 对应 Rust 后端 `models::settings::LenientParsed` 结构体。
+ */
+export interface LenientParsed<T> {
+  /** 解析得到的配置内容 */
+  value: T;
+  /** 是否经过 JSONC 容错解析（文件含注释或尾逗号） */
+  wasLenient: boolean;
+}
+
 /**
  * 历史记录条目接口
  *
@@ -252,8 +308,10 @@ export interface MessageContent {
    * - 'server_tool_use'：服务端工具调用块（v0.4.0 新增），如 web_search、web_fetch 等
    * - 'web_search_tool_result'：网页搜索结果块（v0.4.0 新增），包含搜索结果内容
    * - 'citation'：引用块（v0.4.0 新增），包含引用来源信息
+   * - 'oversized_text'：超长文本块的占位（仅出现在 DisplayMessage 中，由后端 transformer 生成），
+   *   全文通过 `readFullBlock` 按需读取
    */
-  type: 'text' | 'tool_use' | 'tool_result' | 'image' | 'thinking' | 'redacted_thinking' | 'server_tool_use' | 'web_search_tool_result' | 'citation';
+  type: 'text' | 'tool_use' | 'tool_result' | 'image' | 'thinking' | 'redacted_thinking' | 'server_tool_use' | 'web_search_tool_result' | 'citation' | 'oversized_text';
   /** 文本内容：当 type 为 'text' 时，存储实际的文本字符串 */
   text?: string;
   /**
@@ -292,6 +350,12 @@ export interface MessageContent {
    * 为 true 表示工具执行失败，UI 应以红色错误样式渲染此结果块。
    */
   is_error?: boolean;
+  /** 预览文本：当 type 为 'oversized_text' 时，原文的前若干个字符 */
+  preview?: string;
+  /** 原文长度（字节）：当 type 为 'oversized_text' 时有效 */
+  original_length?: number;
+  /** 原始 `message.content` 中的索引：当 type 为 'oversized_text' 时有效，传给 `readFullBlock` */
+  block_index?: number;
 }
 
 /**
@@ -356,10 +420,181 @@ export interface Project {
   name: string;
   /** 项目路径：解码还原后的完整文件系统路径 */
   path: string;
-  /** 会话列表：该项目下的所有聊天会话，按时间倒序排列 */
+  /** 会话列表：该项目下的所有聊天会话，置顶会话在前，其余按时间倒序排列 */
   sessions: Session[];
+  /** 碎片项目的父项目名称：子目录中误启动 Claude Code 产生的空项目，可归并回父项目 */
+  fragmentOf?: string;
+  /** 所属数据源 ID；默认数据源（~/.claude/）的项目省略该字段 */
+  source?: string;
+  /** 是否被用户置顶（保存在 ~/.mo/CCR/pins.json），置顶项目排在最前 */
+  pinned: boolean;
+  /** 会话数量；以 `indexOnly` 扫描时 `sessions` 为空，以此显示数量 */
+  sessionCount: number;
+  /** 最新会话的时间戳（ISO 8601），没有会话时省略 */
+  latestTimestamp?: string;
+  /** 是否已归档（保存在 ~/.mo/CCR/archived.json），归档项目默认不出现在扫描结果中 */
+  archived: boolean;
+}
+
+/**
+ * Claude 数据源接口
+ *
+ * 默认数据源 ~/.claude/ 的 ID 固定为 "default"；额外数据源登记在 ~/.mo/CCR/data-sources.json。
+ * 对应 Rust 后端 `services::data_sources::DataSource` 结构体。
+ */
+export interface DataSource {
+  /** 数据源 ID */
+  id: string;
+  /** 显示名称 */
+  name: string;
+  /** Claude 数据目录的绝对路径 */
+  path: string;
+}
+
+/**
+ * 项目分页扫描结果接口
+ *
+ * 项目目录按 mtime 倒序分页，页内项目再按最新会话时间倒序排列。
+ * 对应 Rust 后端 `models::project::ProjectPage` 结构体。
+ */
+export interface ProjectPage {
+  /** 本页的项目列表 */
+  projects: Project[];
+  /** projects 目录下的项目目录总数 */
+  totalProjects: number;
+  /** 本页在全部项目目录中的起始偏移 */
+  offset: number;
+  /** 是否还有未加载的项目 */
+  hasMore: boolean;
+  /** 本次实际扫描时无法读取的项目目录（命中缓存或没有时省略） */
+  warnings?: ScanWarning[];
 }
 
+/**
+ * 扫描时跳过某个目录的原因
+ *
+ * 对应 Rust 后端 `models::project::ScanWarningKind` 枚举。
+ */
+export type ScanWarningKind = 'permissionDenied' | 'brokenSymlink' | 'unreadable';
+
+/**
+ * 扫描时跳过的目录
+ *
+ * 对应 Rust 后端 `models::project::ScanWarning` 结构体。
+ */
+export interface ScanWarning {
+  /** 目录的绝对路径 */
+  path: string;
+  /** 跳过原因 */
+  kind: ScanWarningKind;
+  /** 原始错误信息 */
+  message: string;
+}
+
+/**
+ * 项目真实路径的校正结果
+ *
+ * 对应 Rust 后端 `services::project_meta::ProjectPathResolution` 结构体。
+ */
+export interface ProjectPathResolution {
+  /** 目录名的解码结果 */
+  decodedPath: string;
+  /** 最新会话中最后一条带 cwd 的消息记录的工作目录 */
+  cwdPath: string | null;
+  /** 当前生效的用户覆盖路径 */
+  overridePath: string | null;
+  /** cwd 与解码结果是否一致（没有 cwd 时视为一致） */
+  consistent: boolean;
+}
+
+/**
+ * 会话列表的排序键
+ *
+ * 对应 Rust 后端 `services::scanner::SessionSortBy` 枚举。
+ * - `time`：文件修改时间
+ * - `size`：文件大小
+ * - `count`：消息数（未统计时视为缺失）
+ * - `name`：显示名称（别名 > 标题 > 摘要 > 首条消息）
+ */
+export type SessionSortBy = 'time' | 'size' | 'count' | 'name';
+
+/**
+ * 排序方向
+ *
+ * 对应 Rust 后端 `services::scanner::SortOrder` 枚举。
+ */
+export type SortOrder = 'asc' | 'desc';
+
+/**
+ * 单个项目的会话分页结果
+ *
+ * 对应 Rust 后端 `models::project::SessionPage` 结构体。
+ */
+export interface SessionPage {
+  /** 本页的会话列表（置顶在前，其余按时间倒序） */
+  sessions: Session[];
+  /** 项目下的会话总数 */
+  totalSessions: number;
+  /** 本页在全部会话中的起始偏移 */
+  offset: number;
+  /** 是否还有未加载的会话 */
+  hasMore: boolean;
+}
+
+/**
+ * 项目与会话计数（侧边栏角标）
+ *
+ * 对应 Rust 后端 `models::project::ProjectCounts` 结构体。
+ */
+export interface ProjectCounts {
+  /** projects 目录下的项目目录数（不含已归档项目） */
+  projects: number;
+  /** 会话文件数（不含子 agent 会话） */
+  sessions: number;
+  /** agent- 前缀的子 agent 会话文件数 */
+  agentSessions: number;
+}
+
+/**
+ * 项目摘要：项目目录下 CLAUDE.md / README.md 的开头部分
+ *
+ * 对应 Rust 后端 `models::project::ProjectSummary` 结构体。
+ */
+export interface ProjectSummary {
+  /** 摘要来源文件的绝对路径 */
+  filePath: string;
+  /** 文件开头的文本（行数与字节数均有上限） */
+  text: string;
+  /** 文件内容是否超出上限被截断 */
+  truncated: boolean;
+}
+
+/**
+ * 项目级统计：项目下全部会话的规模与 token 用量
+ *
+ * 对应 Rust 后端 `project_stats::ProjectStats` 结构体。
+ */
+export interface ProjectStats {
+  /** 会话数量（不含子 agent 会话） */
+  sessionCount: number;
+  /** JSONL 总行数（不含空行） */
+  lineCount: number;
+  /** 会话文件总字节数 */
+  totalBytes: number;
+  /** 全部 assistant 消息 usage 的累加（toolCalls / failedToolCalls 不统计，恒为 0） */
+  tokenStats: TokenStats;
+  /** 每天的 user / assistant 消息数（`YYYY-MM-DD` → 条数，按显示时区切分） */
+  dailyMessages: Record<string, number>;
+}
+
+/**
+ * 显示时区
+ *
+ * 对应 Rust 后端 `utils::time::DisplayTimezone`（序列化为字符串）：
+ * `'local'` 跟随系统、`'utc'`，或固定偏移 `'+08:00'` / `'-05:00'`。
+ */
+export type DisplayTimezone = string;
+
 /**
  * 会话接口
  *
@@ -377,11 +612,11 @@ export interface Project {
 export interface Session {
   /** 会话 ID：对应 JSONL 文件名（不含扩展名），通常是 UUID 格式 */
   id: string;
-  /** 会话名称：优先级 customTitle > aiTitle > lastPrompt（可选） */
+  /** 会话名称：优先级 customTitle > aiTitle > lastPrompt > 首条用户消息前 50 字符（可选） */
   name?: string;
   /** 会话时间戳：基于 JSONL 文件的最后修改时间 */
   timestamp: Date;
-  /** 消息数量：该会话中包含的消息条数（初始加载时可能为 0，需要读取文件后更新） */
+  /** 消息数量：扫描时要求统计（includeMessageCount）才填充为消息记录数，否则为 0 */
   messageCount: number;
   /** 文件路径：JSONL 文件的完整绝对路径，用于后续读取会话内容 */
   filePath: string;
@@ -401,6 +636,141 @@ export interface Session {
   fileSize?: number;
   /** 是否为侧链会话：子 agent 或分支对话 */
   isSidechain: boolean;
+  /** 写入该会话的 Claude Code 版本：JSONL 尾部最后一条带 version 字段的消息 */
+  version?: string;
+  /** 是否被用户锁定为只读：锁定后后端拒绝一切写入（错误以 "SessionLocked" 开头） */
+  locked: boolean;
+  /** 是否有待发送的草稿（保存在 ~/.mo/CCR/drafts.json，不写入会话 JSONL） */
+  hasDraft: boolean;
+  /** 自上次在 CCR 中查看后文件是否又被修改（记录在 ~/.mo/CCR/last-viewed.json），侧边栏显示未读圆点 */
+  updatedSinceViewed: boolean;
+  /** CCR 会话别名（保存在 ~/.mo/CCR/session-aliases.json），显示时优先于 name */
+  alias?: string;
+  /** 是否被用户置顶（保存在 ~/.mo/CCR/pins.json），置顶会话在项目内排在最前 */
+  pinned: boolean;
+  /** 是否为子 agent 会话（agent-*.jsonl），仅在扫描时指定 includeAgentSessions 才会出现 */
+  isAgent: boolean;
+  /** 文件行数：仅在扫描时指定 includeMessageCount 才返回 */
+  lineCount?: number;
+}
+
+/**
+ * 写回 Claude Code 原生标题时使用的格式
+ *
+ * 对应 Rust 后端 `services::session_title::NativeTitleFormat` 枚举：
+ * - customTitle: 插入 / 更新 `type: "custom-title"` 行
+ * - summary: 插入 / 更新 `leafUuid` 指向主线叶子的 `type: "summary"` 行
+ */
+export type NativeTitleFormat = 'customTitle' | 'summary';
+
+/**
+ * 会话重命名结果
+ *
+ * 对应 Rust 后端 `services::session_title::RenameSessionResult` 结构体。
+ */
+export interface RenameSessionResult {
+  /** 保存后的 CCR 别名（标题为空时别名被清除） */
+  alias?: string;
+  /** 写回原生标题时使用的格式（未写回时缺省） */
+  nativeFormat?: NativeTitleFormat;
+}
+
+/**
+ * 单个 version 值在会话中出现的条数
+ *
+ * 对应 Rust 后端 `services::compat::VersionCount` 结构体。
+ */
+export interface VersionCount {
+  /** Claude Code 版本号 */
+  version: string;
+  /** 带该版本号的消息条数 */
+  count: number;
+}
+
+/**
+ * 会话分叉点：拥有多个子消息的节点
+ *
+ * 对应 Rust 后端 `services::branches::ForkPoint` 结构体。
+ */
+export interface ForkPoint {
+  /** 分叉节点的 UUID */
+  uuid: string;
+  /** 子消息 UUID（按文件顺序） */
+  childUuids: string[];
+}
+
+/**
+ * 一条从根到叶子的会话分支
+ *
+ * 对应 Rust 后端 `services::branches::SessionBranch` 结构体。
+ */
+export interface SessionBranch {
+  /** 叶子消息 UUID，作为分支标识（传给 readSessionMessages 的 branchLeaf） */
+  leafUuid: string;
+  /** 从根到叶子的消息 UUID 序列 */
+  uuids: string[];
+  /** 路径上的消息条数 */
+  length: number;
+  /** 叶子消息的时间戳 */
+  lastTimestamp?: string;
+  /** 分支标题（来自 summary 条目的 leafUuid） */
+  title?: string;
+  /** 是否为当前 Claude Code 实际采用的主线 */
+  isMain: boolean;
+}
+
+/**
+ * 会话的分叉结构
+ *
+ * 对应 Rust 后端 `services::branches::SessionBranches` 结构体。
+ */
+export interface SessionBranches {
+  /** 所有分叉点（按文件顺序） */
+  forkPoints: ForkPoint[];
+  /** 所有分支（主线在前，其余按叶子时间倒序） */
+  branches: SessionBranch[];
+}
+
+/**
+ * 单个会话的版本统计
+ *
+ * 对应 Rust 后端 `services::compat::SessionVersionStats` 结构体。
+ */
+export interface SessionVersionStats {
+  /** 出现过的版本及条数（新版本在前） */
+  versions: VersionCount[];
+  /** 没有 version 字段的条目数 */
+  unversioned: number;
+}
+
+/**
+ * 单个版本在整个数据目录中的会话分布
+ *
+ * 对应 Rust 后端 `services::compat::VersionDistribution` 结构体。
+ */
+export interface VersionDistribution {
+  /** Claude Code 版本号 */
+  version: string;
+  /** 末条消息为该版本的会话数 */
+  sessionCount: number;
+  /** 涉及的项目数 */
+  projectCount: number;
+  /** 这些会话中最新的文件修改时间（ISO 8601） */
+  lastActive: string;
+}
+
+/**
+ * 数据目录的版本兼容性报告
+ *
+ * 对应 Rust 后端 `services::compat::DataCompatReport` 结构体。
+ */
+export interface DataCompatReport {
+  /** 会话总数 */
+  totalSessions: number;
+  /** 无法确定版本的会话数 */
+  unknownVersionSessions: number;
+  /** 各版本的会话分布（新版本在前） */
+  versions: VersionDistribution[];
 }
 
 /**
@@ -508,6 +878,10 @@ export interface DisplayMessage {
   toolUseResult: ToolUseResult | null;
   /** 待办事项列表（仅包含 todos 的消息） */
   todos: Todo[] | null;
+  /** 停止原因（仅 assistant 消息，如 'end_turn'、'tool_use'、'max_tokens'） */
+  stopReason?: string;
+  /** API 响应消息 ID（仅 assistant 消息，流式拆分的多条记录共享同一 ID） */
+  messageId?: string;
   // ---- system 专属字段 ----
   /**
    * 系统消息子类型标签（仅 displayType === 'system' 时使用）。
@@ -527,6 +901,8 @@ export interface DisplayMessage {
   /** 是否为遗弃消息（不在主链上）
    *  主链 = 从 JSONL 最后一条消息沿 parentUuid 回溯到根的路径 */
   isAbandoned: boolean;
+  /** 是否为失败的工具结果（tool_result 块带 is_error: true） */
+  isError: boolean;
 }
 
 /**
@@ -542,6 +918,319 @@ export interface ToolUseInfo {
   input: Record<string, unknown>;
 }
 
+/**
+ * 全局搜索过滤器（所有字段均可省略，可叠加）
+ *
+ * 对应 Rust 后端 `services::global_search::GlobalSearchFilter` 结构体。
+ */
+export interface GlobalSearchFilter {
+  /** 只搜索这些项目（`Project.name`） */
+  projects?: string[];
+  /** 只保留该时间之后（含）的消息（ISO 8601） */
+  after?: string;
+  /** 只保留该时间之前（含）的消息（ISO 8601） */
+  before?: string;
+  /** 只保留这些显示类型的消息 */
+  onlyTypes?: DisplayMessage['displayType'][];
+  /** 是否保留 system 类型的命中（默认 false，即过滤 system-reminder 等噪音） */
+  includeSystem?: boolean;
+}
+
+/**
+ * 全局搜索选项（所有字段均可省略）
+ *
+ * 对应 Rust 后端 `services::global_search::GlobalSearchOptions` 结构体。
+ */
+export interface GlobalSearchOptions {
+  /** 是否把查询词作为正则表达式 */
+  useRegex?: boolean;
+  /** 搜索 ID，传入后可通过 `cancelGlobalSearch` 取消 */
+  searchId?: string;
+  /** 超时（毫秒），默认 60000 */
+  timeoutMs?: number;
+}
+
+/**
+ * 会话内搜索的一个匹配
+ *
+ * 对应 Rust 后端 `services::cache::SearchSnippet` 结构体。
+ */
+export interface SearchSnippet {
+  /** 匹配在该消息搜索文本中的起始字节位置（UTF-8） */
+  start: number;
+  /** 匹配在该消息搜索文本中的结束字节位置（不含） */
+  end: number;
+  /** 匹配位置前后各 40 个字符的片段 */
+  preview: string;
+}
+
+/**
+ * 会话内搜索的一条命中消息
+ *
+ * 对应 Rust 后端 `services::cache::SearchHit` 结构体，由 `searchSession` 返回。
+ */
+export interface SearchHit {
+  /** 命中消息的 displayId */
+  displayId: string;
+  /** 该消息内的匹配次数 */
+  matchCount: number;
+  /** 前 5 个匹配的位置与片段 */
+  snippets: SearchSnippet[];
+}
+
+/**
+ * 全局搜索的单条命中
+ *
+ * 对应 Rust 后端 `services::global_search::GlobalSearchHit` 结构体。
+ */
+export interface GlobalSearchHit {
+  /** 项目名称（编码后的目录名） */
+  projectName: string;
+  /** 会话 JSONL 文件的绝对路径 */
+  sessionFilePath: string;
+  /** 命中消息的原始 uuid */
+  uuid: string;
+  /** 命中消息的 displayId */
+  displayId: string;
+  /** 命中消息的显示类型 */
+  displayType: DisplayMessage['displayType'];
+  /** 命中消息的时间戳 */
+  timestamp: string;
+  /** 匹配位置附近的文本片段 */
+  snippet: string;
+  /** 该消息内的匹配次数 */
+  matchCount: number;
+}
+
+/**
+ * 全局搜索结果
+ *
+ * 对应 Rust 后端 `services::global_search::GlobalSearchResult` 结构体。
+ */
+export interface GlobalSearchResult {
+  /** 按匹配次数降序的命中列表（最多 500 条） */
+  hits: GlobalSearchHit[];
+  /** 实际搜索的会话数 */
+  sessionsSearched: number;
+  /** 命中数是否超过上限被截断 */
+  truncated: boolean;
+  /** 是否因取消或超时提前结束（此时只覆盖部分会话） */
+  cancelled: boolean;
+}
+
+/**
+ * 会话主题关键词
+ *
+ * 对应 Rust 后端 `services::keywords::SessionKeyword` 结构体。
+ */
+export interface SessionKeyword {
+  /** 关键词（小写；中文为 2 字片段） */
+  word: string;
+  /** 在会话中出现的次数 */
+  count: number;
+}
+
+/**
+ * 会话中引用的单个链接
+ *
+ * 对应 Rust 后端 `services::links::SessionLink` 结构体。
+ */
+export interface SessionLink {
+  /** 链接 URL */
+  url: string;
+  /** 首次出现的消息 displayId */
+  displayId: string;
+  /** 首次出现处的上下文片段 */
+  context: string;
+  /** 在整个会话中出现的次数 */
+  occurrences: number;
+}
+
+/**
+ * 会话中的单个代码块
+ *
+ * 对应 Rust 后端 `services::code_blocks::CodeBlock` 结构体。
+ */
+export interface CodeBlock {
+  /** 内容哈希（16 位十六进制），同时作为保存时的块 ID */
+  id: string;
+  /** 围栏信息串中的语言标记 */
+  language?: string;
+  /** 代码内容（不含围栏行） */
+  content: string;
+  /** 行数 */
+  lineCount: number;
+  /** 首次出现的消息 displayId */
+  displayId: string;
+  /** 在整个会话中出现的次数 */
+  occurrences: number;
+  /** 围栏是否正常闭合（未闭合时内容延续到该文本块末尾） */
+  closed: boolean;
+}
+
+/**
+ * 向会话追加 JSONL 片段的结果
+ *
+ * 对应 Rust 后端 `models::display::AppendLinesResult` 结构体。
+ */
+export interface AppendLinesResult {
+  /** 新增的消息条数 */
+  appended: number;
+  /** 追加后重新转换的会话数据 */
+  session: TransformedSession;
+}
+
+/**
+ * 撤销操作的结果
+ *
+ * 对应 Rust 后端 `models::display::UndoResult` 结构体。
+ */
+export interface UndoResult {
+  /** 被撤销的操作描述（如「删除 3 条消息」） */
+  undone: string;
+  /** 剩余可撤销操作的描述（最近的在前） */
+  remaining: string[];
+  /** 撤销后重新转换的会话数据 */
+  session: TransformedSession;
+}
+
+/**
+ * 跨会话复制 / 移动消息的结果
+ *
+ * 对应 Rust 后端 `models::display::CopyMessagesResult` 结构体。
+ */
+export interface CopyMessagesResult {
+  /** 复制到目标会话的消息条数 */
+  copied: number;
+  /** 副本的新 uuid（按插入顺序） */
+  newUuids: string[];
+}
+
+/**
+ * 单次工具调用的输入输出配对详情
+ *
+ * 对应 Rust 后端 `models::display::ToolInvocation` 结构体。
+ */
+export interface ToolInvocation {
+  /** 工具调用 ID */
+  toolUseId: string;
+  /** 工具名称（tool_use 缺失时缺省） */
+  name?: string;
+  /** 完整的 tool_use 内容块 */
+  toolUse?: MessageContent;
+  /** 完整的 tool_result 内容块 */
+  toolResult?: MessageContent;
+  /** tool_use 所在消息的 uuid */
+  toolUseUuid?: string;
+  /** tool_result 所在消息的 uuid */
+  toolResultUuid?: string;
+  /** tool_result 与 tool_use 的时间差（毫秒） */
+  elapsedMs?: number;
+  /** 缺失的一侧；配对完整时缺省 */
+  missing?: 'tool_use' | 'tool_result';
+}
+
+/**
+ * 工具结果与磁盘文件的对比状态
+ *
+ * - `identical`：磁盘内容与快照一致
+ * - `changed`：磁盘内容已变化
+ * - `deleted`：文件已不存在
+ * - `binary`：磁盘文件是二进制或非 UTF-8 文本
+ */
+export type DiskDiffStatus = 'identical' | 'changed' | 'deleted' | 'binary';
+
+/** diff 中的一行 */
+export interface DiffLine {
+  /** 行类型：上下文 / 新增 / 删除 */
+  kind: 'context' | 'added' | 'removed';
+  /** 行内容（不含换行符） */
+  text: string;
+}
+
+/** 一个 diff 片段（行号从 1 开始） */
+export interface DiffHunk {
+  /** 快照侧起始行号 */
+  oldStart: number;
+  /** 快照侧行数 */
+  oldLines: number;
+  /** 磁盘侧起始行号 */
+  newStart: number;
+  /** 磁盘侧行数 */
+  newLines: number;
+  /** 片段内的行 */
+  lines: DiffLine[];
+}
+
+/**
+ * 工具结果中的文件快照与磁盘当前内容的对比结果
+ *
+ * 对应 Rust 后端 `services::tool_diff::ToolResultDiff` 结构体。
+ */
+export interface ToolResultDiff {
+  /** 文件的绝对路径 */
+  filePath: string;
+  /** 工具名称 */
+  toolName: string;
+  /** 对比状态 */
+  status: DiskDiffStatus;
+  /** 快照是否只覆盖文件的一部分行 */
+  partial: boolean;
+  /** 快照对应的起始行号 */
+  startLine: number;
+  /** diff 片段（status 为 changed 时非空） */
+  hunks: DiffHunk[];
+  /** 行级统计 */
+  stats: { added: number; removed: number; unchanged: number };
+}
+
+/**
+ * 会话瘦身副本的生成结果
+ *
+ * 对应 Rust 后端 `services::trimmed_copy::TrimmedCopyResult` 结构体。
+ */
+export interface TrimmedCopyResult {
+  /** 副本文件的绝对路径 */
+  filePath: string;
+  /** 副本的会话 ID（即文件名中的 UUID） */
+  sessionId: string;
+  /** 副本实际包含的消息条数（补齐配对后可能多于请求的条数） */
+  messageCount: number;
+}
+
+/**
+ * 插入消息的位置
+ *
+ * 对应 Rust 后端 `commands::messages::InsertPosition` 枚举。
+ *
+ * - `start`：会话开头，新消息成为根消息
+ * - `after`：指定 UUID 的消息之后
+ * - `end`：会话末尾，接在最后一条带 uuid 的消息之后
+ */
+export type InsertPosition = { kind: 'start' } | { kind: 'after'; uuid: string } | { kind: 'end' };
+
+/**
+ * 会话合并时源消息的插入位置
+ *
+ * - `timestamp`：按时间戳与目标会话的消息归并
+ * - `append`：追加到目标会话末尾
+ */
+export type MergePosition = 'timestamp' | 'append';
+
+/**
+ * 会话合并结果
+ *
+ * 对应 Rust 后端 `services::session_merge::MergeSessionsResult` 结构体。
+ */
+export interface MergeSessionsResult {
+  /** 并入目标会话的消息条数 */
+  merged: number;
+  /** 因 uuid 冲突而重新生成 uuid 的消息条数 */
+  renamedUuids: number;
+  /** 源会话是否已删除 */
+  sourceDeleted: boolean;
+}
+
 /**
  * Token 统计汇总接口
  *
@@ -565,6 +1254,121 @@ export interface TokenStats {
   webSearchRequests: number;
   /** 服务端网页获取请求总次数（来自 usage.server_tool_use.web_fetch_requests） */
   webFetchRequests: number;
+  /** 工具调用总次数 */
+  toolCalls: number;
+  /** 失败的工具调用次数（带 is_error 的 tool_result 数量） */
+  failedToolCalls: number;
+}
+
+/**
+ * 单个工具的调用与失败统计
+ *
+ * 对应 Rust 后端 `models::display::ToolStat` 结构体。
+ */
+export interface ToolStat {
+  /** 工具名称 */
+  name: string;
+  /** 调用次数 */
+  calls: number;
+  /** 失败次数 */
+  failures: number;
+  /** 失败率（0 ~ 1） */
+  failureRate: number;
+}
+
+/**
+ * 单个 stop_reason 的出现次数
+ *
+ * 对应 Rust 后端 `models::display::StopReasonStat` 结构体。
+ */
+export interface StopReasonStat {
+  /** 停止原因 */
+  stopReason: string;
+  /** 出现次数（同一 messageId 只计一次） */
+  count: number;
+}
+
+/**
+ * 会话统计
+ *
+ * 对应 Rust 后端 `models::display::SessionStats` 结构体。
+ */
+export interface SessionStats {
+  /** Token 与工具调用汇总 */
+  tokenStats: TokenStats;
+  /** 按工具名的调用与失败统计（按失败次数降序） */
+  tools: ToolStat[];
+  /** 停止原因分布（按次数降序） */
+  stopReasons: StopReasonStat[];
+  /** 显示消息条数（去噪时不含压缩摘要与计划以外的系统消息） */
+  messageCount: number;
+  /** text 块字符数（统计范围同 messageCount） */
+  textChars: number;
+}
+
+/**
+ * 消息的一个历史版本（被 CCR 编辑前的原文）
+ *
+ * 对应 Rust 后端 `services::edit_history::MessageEditVersion` 结构体。
+ */
+export interface MessageEditVersion {
+  /** 版本 ID，回滚时使用 */
+  versionId: string;
+  /** 保存时间（UTC，ISO 8601），即该版本被覆盖的时刻 */
+  savedAt: string;
+  /** 文本摘要（首段文本，最多 120 字符） */
+  summary: string;
+  /** 完整的原始消息 */
+  message: Record<string, unknown>;
+}
+
+/** 导出 / 复制时消息时间的显示方式：原始 ISO、本地时间、相对时间（x 天前） */
+export type TimeStyle = 'iso' | 'local' | 'relative';
+
+/**
+ * 导出并分享的结果
+ *
+ * 对应 Rust 后端 `services::share::ShareResult` 结构体。
+ */
+export interface ShareResult {
+  /** 导出文件的绝对路径（位于 TEMP 下的 ccr-exports 目录） */
+  filePath: string;
+  /** 是否已成功调起系统文件管理器 */
+  shared: boolean;
+  /** 调起失败的原因（移动端以 "Unsupported" 开头） */
+  error?: string;
+}
+
+/**
+ * 导出与复制选项
+ *
+ * 对应 Rust 后端 `services::export::ExportOptions` 结构体，字段均可省略（默认 ISO、中文、跳过系统消息）。
+ */
+export interface ExportOptions {
+  /** 消息时间的显示方式 */
+  timeStyle?: TimeStyle;
+  /** 相对时间文案语言 */
+  locale?: 'zh' | 'en';
+  /** Markdown 导出包含的显示类型（默认 user / assistant） */
+  includeTypes?: DisplayMessage['displayType'][];
+  /** 是否剔除压缩摘要与计划以外的系统注入（默认 true，与 includeTypes 取交集） */
+  excludeNoise?: boolean;
+}
+
+/**
+ * 按搜索结果导出的过滤条件
+ *
+ * 对应 Rust 后端 `services::export::ExportFilter` 结构体，搜索语义与 `searchSession` 相同。
+ */
+export interface ExportFilter {
+  /** 搜索词 */
+  query: string;
+  /** 是否大小写敏感 */
+  caseSensitive?: boolean;
+  /** 是否按正则表达式匹配 */
+  useRegex?: boolean;
+  /** 是否同时导出每条命中消息前后各一条消息作为上下文 */
+  includeContext?: boolean;
 }
 
 /**
@@ -582,6 +1386,137 @@ export interface TransformedSession {
   toolUseMap: Record<string, ToolUseInfo>;
   /** Token 统计汇总 */
   tokenStats: TokenStats;
+  /** 工作目录切换点列表（按消息顺序） */
+  cwdChanges: CwdChange[];
+  /** 会话涉及的全部工作目录（按首次出现顺序去重） */
+  cwds: string[];
+  /** 会话文件不是 UTF-8 时的原始编码（如 "UTF-16LE"、"GBK"），可用 convertSessionEncoding 转存 */
+  reencodedFrom?: string;
+  /** 分页读取时的 displayMessages 总条数（未分页时省略） */
+  totalCount?: number;
+}
+
+/**
+ * 消息写操作的回执
+ *
+ * deleteMessage / deleteMessages / editMessageContent 以回执模式调用时返回，
+ * 只含局部更新所需的差异（可用 `applyOperationReceipt` 合并到当前会话）。
+ *
+ * 对应 Rust 后端 `models::display::OperationReceipt` 结构体。
+ */
+export interface OperationReceipt {
+  /** 写入后不再存在的 displayId */
+  removedDisplayIds: string[];
+  /** 新增或内容有变化的显示消息（最新在前） */
+  updatedMessages: DisplayMessage[];
+  /** 写入后的 Token 统计汇总 */
+  tokenStats: TokenStats;
+}
+
+/**
+ * 工作目录切换点
+ *
+ * 会话中途切换工作目录时，标记切换后的第一条消息，前端在其前插入分隔条。
+ *
+ * 对应 Rust 后端 `models::display::CwdChange` 结构体。
+ */
+export interface CwdChange {
+  /** 切换后第一条消息的 displayId */
+  displayId: string;
+  /** 切换前的工作目录 */
+  from: string;
+  /** 切换后的工作目录 */
+  to: string;
+}
+
+/**
+ * 流式加载会话的首个响应
+ *
+ * 对应 Rust 后端 `models::display::SessionStreamStart` 结构体。
+ */
+export interface SessionStreamStart {
+  /** 本次加载的流 ID，事件载荷中携带相同 ID */
+  streamId: number;
+  /** 原始消息条数，供估算加载进度（缓存命中时为 DisplayMessage 条数） */
+  totalRaw: number;
+  /** 缓存命中时的完整会话（此时不会推送事件） */
+  cached?: TransformedSession;
+  /** 会话文件不是 UTF-8 时的原始编码（缓存命中时见 cached.reencodedFrom） */
+  reencodedFrom?: string;
+}
+
+/**
+ * `session-chunk` 事件载荷：一批按时间顺序排列的 DisplayMessage
+ *
+ * 对应 Rust 后端 `models::display::SessionChunk` 结构体。
+ */
+export interface SessionChunk {
+  /** 所属流 ID */
+  streamId: number;
+  /** 分块序号（从 0 开始） */
+  seq: number;
+  /** 本批 DisplayMessage */
+  displayMessages: DisplayMessage[];
+  /** 本批新增的 tool_use 映射 */
+  toolUseMap: Record<string, ToolUseInfo>;
+}
+
+/**
+ * `session-updated` 事件载荷：某个窗口经后端修改或删除了 Claude 数据文件
+ *
+ * 对应 Rust 后端 `services::events::SessionUpdated` 结构体。
+ */
+export interface SessionUpdatedEvent {
+  /** 被修改的文件绝对路径 */
+  filePath: string;
+  /** 文件是否已被删除 */
+  deleted: boolean;
+}
+
+/**
+ * `scan-progress` 事件载荷：项目扫描进度（缓存未命中时节流推送）
+ *
+ * 对应 Rust 后端 `models::project::ScanProgress` 结构体。
+ */
+export interface ScanProgress {
+  /** 已扫描完成的项目数 */
+  done: number;
+  /** 本次需要扫描的项目总数 */
+  total: number;
+  /** 最近完成的项目目录名 */
+  currentProject: string;
+}
+
+/**
+ * `claude-data-changed` 事件载荷：数据目录监听发现会话文件变化
+ *
+ * 对应 Rust 后端 `services::watcher::ClaudeDataChanged` 结构体。
+ */
+export interface ClaudeDataChangedEvent {
+  /** 变化的会话文件绝对路径 */
+  filePath: string;
+  /** 变化类型 */
+  kind: 'created' | 'modified' | 'removed';
+  /** 自上次在 CCR 中查看后是否又被修改（删除时为 false） */
+  updatedSinceViewed: boolean;
+}
+
+/**
+ * `session-stream-done` 事件载荷：转换完成后的会话级汇总
+ *
+ * 对应 Rust 后端 `models::display::SessionStreamDone` 结构体。
+ */
+export interface SessionStreamDone {
+  /** 所属流 ID */
+  streamId: number;
+  /** 已推送的分块总数 */
+  chunks: number;
+  /** Token 统计汇总 */
+  tokenStats: TokenStats;
+  /** 工作目录切换点列表 */
+  cwdChanges: CwdChange[];
+  /** 会话涉及的全部工作目录 */
+  cwds: string[];
 }
 
 /**
@@ -637,6 +1572,355 @@ export interface ResumeConfig {
 export interface BackupConfig {
   /** 是否启用主动备份（在原文件同目录创建 .ccbak 文件） */
   autoBackupEnabled: boolean;
+  /** 临时资源保留天数，超龄文件在应用退出时清理（0 表示不自动清理） */
+  tempRetentionDays: number;
+  /** 审计日志保留天数，超龄记录在应用退出时清理（0 表示永久保留） */
+  auditRetentionDays: number;
+}
+
+/**
+ * 临时资源清理结果
+ *
+ * 对应 Rust 后端 `services::temp_cleanup::CleanupReport` 结构体。
+ */
+export interface CleanupReport {
+  /** 删除的文件数 */
+  removedFiles: number;
+  /** 释放的空间（字节） */
+  freedBytes: number;
+  /** 删除失败的文件数 */
+  failedFiles: number;
+}
+
+/**
+ * 启动自检的单个检查项结果
+ *
+ * 对应 Rust 后端 `services::diagnostics::DiagnosticItem` 结构体。
+ */
+export interface DiagnosticItem {
+  /** 检查项名称 */
+  name: string;
+  /** 是否通过 */
+  ok: boolean;
+  /** 检查结果详情（路径、数量、错误信息等） */
+  detail: string;
+  /** 修复建议（通过时不存在） */
+  suggestion?: string;
+}
+
+/**
+ * 引导状态
+ *
+ * 对应 Rust 后端 `services::diagnostics::OnboardingStatus` 枚举：
+ * - 'claudeNotInstalled'：`~/.claude/` 不存在（未安装或从未运行过 Claude Code）
+ * - 'noProjects'：数据目录存在，但 `projects/` 下没有项目
+ * - 'ok'：已有项目
+ */
+export type OnboardingStatus = 'claudeNotInstalled' | 'noProjects' | 'ok';
+
+/**
+ * 引导状态检查结果
+ *
+ * 对应 Rust 后端 `services::diagnostics::OnboardingState` 结构体。
+ */
+export interface OnboardingState {
+  /** 数据目录与项目状态 */
+  status: OnboardingStatus;
+  /** Claude 数据目录路径（无法获取主目录时为空字符串） */
+  claudeDir: string;
+  /** `projects/` 下的项目目录数 */
+  projectCount: number;
+  /** PATH 中 claude CLI 的路径（未找到时为 null） */
+  cliPath: string | null;
+}
+
+/**
+ * 反馈诊断包中单个条目的收集结果
+ *
+ * 对应 Rust 后端 `services::diagnostic_bundle::DiagnosticBundleItem` 结构体。
+ */
+export interface DiagnosticBundleItem {
+  /** ZIP 内的文件名（如 `diagnostics.json`、`sessions/1.json`） */
+  name: string;
+  /** 是否收集成功并写入 ZIP */
+  included: boolean;
+  /** 收集失败的原因（成功时不存在） */
+  error?: string;
+}
+
+/**
+ * 反馈诊断包清单（同时写入 ZIP 的 `manifest.json`）
+ *
+ * 对应 Rust 后端 `services::diagnostic_bundle::DiagnosticBundleManifest` 结构体。
+ */
+export interface DiagnosticBundleManifest {
+  /** 生成时间（RFC 3339，UTC） */
+  createdAt: string;
+  /** CCR 版本 */
+  appVersion: string;
+  /** 各条目的收集结果 */
+  items: DiagnosticBundleItem[];
+}
+
+/**
+ * 单次命令调用记录
+ *
+ * 对应 Rust 后端 `services::command_trace::CommandTraceEntry` 结构体。
+ */
+export interface CommandTraceEntry {
+  /** command 名称 */
+  command: string;
+  /** 参数摘要（路径已脱敏为文件名） */
+  args: string;
+  /** 开始时间（RFC 3339，UTC） */
+  startedAt: string;
+  /** 耗时（毫秒） */
+  durationMs: number;
+  /** 是否成功返回 */
+  ok: boolean;
+}
+
+/**
+ * 会话缓存的占用统计
+ *
+ * 对应 Rust 后端 `services::cache::CacheStats` 结构体。
+ */
+export interface CacheStats {
+  /** 当前缓存的会话数 */
+  entries: number;
+  /** 当前估算占用（字节） */
+  totalBytes: number;
+  /** 撤销栈快照的估算占用（字节，计入内存上限） */
+  undoBytes: number;
+  /** 内存上限（字节） */
+  maxBytes: number;
+  /** 累计淘汰次数（含按条数与按内存淘汰） */
+  evictions: number;
+  /** 其中因超过内存上限而淘汰的次数 */
+  memoryEvictions: number;
+  /** 审计日志写入失败次数 */
+  auditFailures: number;
+}
+
+/**
+ * 缓存不一致的类别
+ *
+ * 对应 Rust 后端 `services::cache_check::CacheInconsistencyKind` 枚举。
+ */
+export type CacheInconsistencyKind =
+  | 'sessionFileMissing'
+  | 'sessionStale'
+  | 'sessionContentMismatch'
+  | 'projectSessionMissing'
+  | 'projectSessionUnlisted'
+  | 'projectSessionStale';
+
+/**
+ * 缓存与磁盘状态的一处不一致
+ *
+ * 对应 Rust 后端 `services::cache_check::CacheInconsistency` 结构体。
+ */
+export interface CacheInconsistency {
+  /** 不一致的类别 */
+  kind: CacheInconsistencyKind;
+  /** 会话文件的绝对路径 */
+  filePath: string;
+  /** 差异说明 */
+  detail: string;
+}
+
+/**
+ * 缓存一致性自检报告
+ *
+ * 对应 Rust 后端 `services::cache_check::CacheConsistencyReport` 结构体。
+ */
+export interface CacheConsistencyReport {
+  /** 检查的会话缓存条目数 */
+  checkedSessions: number;
+  /** 检查的项目数（项目列表缓存中已加载的项目） */
+  checkedProjects: number;
+  /** 发现的不一致 */
+  issues: CacheInconsistency[];
+  /** 移除的会话缓存条目数（未要求失效时为 0） */
+  invalidatedSessions: number;
+  /** 是否因不一致使项目列表缓存失效 */
+  invalidatedProjects: boolean;
+}
+
+/**
+ * 临时备份注册表中的单条记录
+ *
+ * 对应 Rust 后端 `services::file_guard::TempBackupEntry` 结构体。
+ */
+export interface TempBackupEntry {
+  /** 被备份的原始文件绝对路径 */
+  originalPath: string;
+  /** 临时备份文件的绝对路径 */
+  tempPath: string;
+  /** 备份创建时间 */
+  createdAt: string;
+  /** 触发备份的操作描述 */
+  operation: string;
+}
+
+/**
+ * 备份来源：`temp` 为 TEMP 下的临时备份，`auto` 为原文件同目录的 `.ccbak` 主动备份
+ */
+export type BackupSource = 'temp' | 'auto';
+
+/**
+ * 备份管理页中的单条备份
+ *
+ * 对应 Rust 后端 `services::backups::BackupItem` 结构体。
+ */
+export interface BackupItem {
+  /** 备份文件的绝对路径 */
+  backupPath: string;
+  /** 原始文件的绝对路径（此前运行留下的临时备份无法确定时为 null） */
+  originalPath: string | null;
+  /** 原始文件名 */
+  originalFileName: string;
+  /** 备份文件大小（字节） */
+  size: number;
+  /** 备份创建时间（ISO 8601） */
+  createdAt: string;
+  /** 备份来源 */
+  source: BackupSource;
+  /** 触发备份的操作（仅本次运行期间的临时备份有） */
+  operation: string | null;
+}
+
+/**
+ * 审计记录的动作类型
+ */
+export type AuditAction = 'write' | 'delete';
+
+/**
+ * 单条审计记录
+ *
+ * 对应 Rust 后端 `services::audit::AuditEntry` 结构体。
+ */
+export interface AuditEntry {
+  /** 记录时间（RFC 3339，UTC） */
+  timestamp: string;
+  /** 操作描述（如 "delete_message"） */
+  operation: string;
+  /** 动作类型 */
+  action: AuditAction;
+  /** 目标文件的绝对路径 */
+  targetPath: string;
+  /** 写入内容的字节数（删除时缺省） */
+  contentSize?: number;
+  /** 本次操作前创建的临时备份路径 */
+  backupPath?: string;
+  /** 操作是否成功 */
+  success: boolean;
+  /** 失败原因 */
+  error?: string;
+  /** 所属批次 ID（修复项等批量操作） */
+  batchId?: string;
+}
+
+/**
+ * 审计日志查询条件
+ *
+ * 对应 Rust 后端 `services::audit::AuditFilter` 结构体。
+ */
+export interface AuditFilter {
+  /** 目标路径包含的子串（大小写不敏感，`\` 与 `/` 视为相同） */
+  path?: string;
+  /** 只返回指定批次的记录 */
+  batchId?: string;
+  /** 跳过的记录数（按时间倒序） */
+  offset?: number;
+}
+
+/**
+ * 审计日志的一页查询结果
+ *
+ * 对应 Rust 后端 `services::audit::AuditLogPage` 结构体。
+ */
+export interface AuditLogPage {
+  /** 本页记录（最新的在前） */
+  entries: AuditEntry[];
+  /** 符合条件的记录总数 */
+  total: number;
+  /** 是否还有更早的记录 */
+  hasMore: boolean;
+}
+
+/**
+ * 备份文件恢复前的预检结果
+ *
+ * 对应 Rust 后端 `services::file_guard::BackupCheck` 结构体。
+ */
+export interface BackupCheck {
+  /** 参与检查的非空行总数 */
+  totalLines: number;
+  /** 可解析为 JSON 对象的行数 */
+  validLines: number;
+  /** 无法解析或不是 JSON 对象的行数 */
+  invalidLines: number;
+  /** 问题行占比（0 ~ 1） */
+  invalidRatio: number;
+  /** 问题行的行号（从 1 开始，最多 20 条） */
+  invalidLineNumbers: number[];
+  /** 是否需要 force 才能恢复 */
+  requiresForce: boolean;
+}
+
+/**
+ * 备份恢复操作的结果
+ *
+ * 对应 Rust 后端 `services::file_guard::RestoreResult` 结构体。
+ */
+export interface RestoreResult {
+  /** 恢复前对备份内容的预检结果 */
+  check: BackupCheck;
+  /** 是否已写回原文件（预检未通过且未传 force 时为 false） */
+  restored: boolean;
+  /** 写回前为当前文件创建的临时备份路径 */
+  tempBackupPath?: string;
+}
+
+/**
+ * 上次异常退出留下的写入残留的处理方式
+ *
+ * 对应 Rust 后端 `services::recovery::RecoveryAction` 枚举：
+ * - discard: 丢弃 .tmp
+ * - overwrite: 用 .tmp 覆盖正式文件（覆盖前备份正式文件）
+ * - keepBoth: 保留两者，.tmp 另存为新会话
+ */
+export type RecoveryAction = 'discard' | 'overwrite' | 'keepBoth';
+
+/**
+ * 一个待处理的写入残留（会话目录下的 `xxx.jsonl.tmp`）
+ *
+ * 对应 Rust 后端 `services::recovery::RecoveryCandidate` 结构体。
+ */
+export interface RecoveryCandidate {
+  /** 残留 .tmp 文件的绝对路径 */
+  tmpPath: string;
+  /** 同名正式文件的绝对路径 */
+  targetPath: string;
+  /** 正式文件是否存在 */
+  targetExists: boolean;
+  /** .tmp 文件大小（字节） */
+  tmpSize: number;
+  /** 正式文件大小（字节，不存在时为 null） */
+  targetSize: number | null;
+  /** .tmp 修改时间（ISO 8601） */
+  tmpModified: string;
+  /** 正式文件修改时间（ISO 8601，不存在时为 null） */
+  targetModified: string | null;
+  /** .tmp 是否比正式文件更新 */
+  tmpNewer: boolean;
+  /** .tmp 内容完整性检查 */
+  tmpCheck: BackupCheck;
+  /** 正式文件内容完整性检查（不存在时为 null） */
+  targetCheck: BackupCheck | null;
+  /** 建议的处理方式 */
+  suggestedAction: RecoveryAction;
 }
 
 /**
@@ -720,6 +2004,44 @@ export interface FixResult {
   message: string;
   /** 受影响的消息行数 */
   affectedLines: number;
+  /** 本次修复创建的备份文件路径（未创建备份时缺省） */
+  backupPath?: string;
+  /** 修复执行耗时（毫秒） */
+  elapsedMs: number;
+}
+
+/**
+ * 往返校验中不一致的单行
+ *
+ * 对应 Rust 后端 `services::parser::RoundtripMismatch` 结构体。
+ */
+export interface RoundtripMismatch {
+  /** 原文件中的行号（从 1 开始） */
+  lineNumber: number;
+  /** 原始行文本 */
+  original: string;
+  /** 解析后重新序列化得到的文本 */
+  roundtrip: string;
+}
+
+/**
+ * 会话文件往返校验报告
+ *
+ * 对应 Rust 后端 `services::parser::RoundtripReport` 结构体。
+ */
+export interface RoundtripReport {
+  /** 是否无损（忽略行尾与空行差异后逐字节一致） */
+  lossless: boolean;
+  /** 非空行总数 */
+  totalLines: number;
+  /** 解析为 JSON 对象的行数 */
+  objectLines: number;
+  /** 以原始文本保留的行数（坏行、注释行、非对象 JSON） */
+  rawLines: number;
+  /** 不一致的行总数 */
+  mismatchCount: number;
+  /** 不一致行的明细（最多 20 条） */
+  mismatches: RoundtripMismatch[];
 }
 
 // =============================================================================
@@ -46,7 +46,9 @@
  */
 
 import { invoke } from '@tauri-apps/api/core';
-import type { ClaudeSettings, Project, HistoryEntry, EnvSwitcherConfig, EnvProfile, TransformedSession, ResumeConfig, BackupConfig, FixDefinition, FixResult, ProxyStatus, ProxyMode, InterceptAction, InterceptResponseAction, ProxyRecord, ProxyRecordDetail } from '../types/claude';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
+import type { ClaudeSettings, DataSource, AppearanceConfig, StatusLineConfig, Project, Session, ProjectPage, SessionPage, SessionSortBy, SortOrder, ProjectCounts, ProjectSummary, ProjectStats, ProjectPathResolution, MessageEditVersion, HistoryEntry, EnvSwitcherConfig, EnvProfile, LenientParsed, TransformedSession, ResumeConfig, BackupConfig, TempBackupEntry, BackupItem, AuditFilter, AuditLogPage, RestoreResult, CleanupReport, DiagnosticItem, DiagnosticBundleManifest, CommandTraceEntry, CacheStats, CacheConsistencyReport, RecoveryCandidate, RecoveryAction, RenameSessionResult, UndoResult, CopyMessagesResult, SessionUpdatedEvent, ClaudeDataChangedEvent, ScanProgress, FixDefinition, FixResult, ProxyStatus, ProxyMode, InterceptAction, InterceptResponseAction, ProxyRecord, ProxyRecordDetail, RoundtripReport, ToolInvocation, ToolResultDiff, TrimmedCopyResult, InsertPosition, MergePosition, MergeSessionsResult, AppendLinesResult, SessionLink, CodeBlock, SessionKeyword, GlobalSearchFilter, GlobalSearchOptions, GlobalSearchResult, SessionStats, SessionVersionStats, SessionBranches, DataCompatReport, ExportOptions, ExportFilter, ShareResult, DisplayMessage, ToolUseInfo, TokenStats, SessionStreamStart, SessionChunk, SessionStreamDone, SearchHit, OnboardingState, DisplayTimezone, OperationReceipt } from '../types/claude';
 
 // ============ 路径工具函数 ============
 
@@ -62,6 +64,44 @@ export async function getClaudeDataPath(): Promise<string> {
   return invoke<string>('get_claude_data_path');
 }
 
+/**
+ * 列出全部 Claude 数据源（第一项始终是默认的 ~/.claude/）
+ *
+ * @returns 数据源列表
+ */
+export async function listDataSources(): Promise<DataSource[]> {
+  return invoke<DataSource[]>('list_data_sources');
+}
+
+/**
+ * 保存额外数据源（整体替换，不含默认数据源）
+ *
+ * @param sources - 额外数据源列表；ID 不能为 "default" / "all"，目录必须已存在
+ * @returns 保存后的全部数据源
+ */
+export async function saveDataSources(sources: DataSource[]): Promise<DataSource[]> {
+  return invoke<DataSource[]>('save_data_sources', { sources });
+}
+
+/**
+ * 读取显示时区设置
+ *
+ * @returns `'local'`、`'utc'` 或固定偏移（如 `'+08:00'`）
+ */
+export async function getDisplayTimezone(): Promise<DisplayTimezone> {
+  return invoke<DisplayTimezone>('get_display_timezone');
+}
+
+/**
+ * 保存显示时区设置（影响导出、归档文件名与按天统计）
+ *
+ * @param timezone - `'local'`、`'utc'` 或 `±HH:MM` 偏移
+ * @returns 规范化后的时区
+ */
+export async function saveDisplayTimezone(timezone: DisplayTimezone): Promise<DisplayTimezone> {
+  return invoke<DisplayTimezone>('save_display_timezone', { timezone });
+}
+
 // ============ 环境配置管理 ============
 
 /**
@@ -69,12 +109,13 @@ export async function getClaudeDataPath(): Promise<string> {
  *
  * 通过 Rust 后端读取 ~/.mo/CCR/env-profiles.json。
  * 如果配置文件不存在（首次使用），返回空的默认配置。
+ * 文件含注释或尾逗号时按 JSONC 容错解析，`wasLenient` 为 true。
  *
  * @param claudePath - Claude 数据路径（保留参数，保持 API 一致性）
- * @returns 返回包含所有配置组和激活 ID 的 EnvSwitcherConfig 对象
+ * @returns 返回包含所有配置组和激活 ID 的 EnvSwitcherConfig 对象及是否经过容错解析
  */
-export async function readEnvSwitcherConfig(claudePath: string): Promise<EnvSwitcherConfig> {
-  return invoke<EnvSwitcherConfig>('read_env_config', { claudePath });
+export async function readEnvSwitcherConfig(claudePath: string): Promise<LenientParsed<EnvSwitcherConfig>> {
+  return invoke<LenientParsed<EnvSwitcherConfig>>('read_env_config', { claudePath });
 }
 
 /**
@@ -84,9 +125,43 @@ export async function readEnvSwitcherConfig(claudePath: string): Promise<EnvSwit
  *
  * @param claudePath - Claude 数据路径（保留参数，保持 API 一致性）
  * @param config - 要保存的完整环境切换器配置对象
+ * @param discardComments - 现有文件含注释时是否确认丢弃；未确认时后端返回 "CommentsWillBeLost" 错误
+ */
+export async function saveEnvSwitcherConfig(
+  claudePath: string,
+  config: EnvSwitcherConfig,
+  discardComments?: boolean
+): Promise<void> {
+  return invoke<void>('save_env_config', { claudePath, config, discardComments });
+}
+
+/**
+ * 判断错误是否为「保存会丢失配置文件中的注释」
+ *
+ * 前端捕获后应向用户确认，确认后以 `discardComments = true` 重试保存。
+ *
+ * @param err - 保存配置时捕获的错误
+ */
+export function isCommentsLostError(err: unknown): boolean {
+  return String(err).startsWith('CommentsWillBeLost');
+}
+
+/** 读取会话失败的错误码（对应 Rust 后端 `parser::ReadFailure`；`NeedsUtf8` 由修改非 UTF-8 会话时返回） */
+export type SessionReadErrorCode = 'CloudPlaceholder' | 'PermissionDenied' | 'TooLarge' | 'NotUtf8' | 'NeedsUtf8';
+
+/**
+ * 解析读取会话失败的错误
+ *
+ * 后端错误格式为 `<错误码>: <描述>\n建议: <建议>`，不是这些错误码时返回 null。
+ *
+ * @param err - 读取会话时捕获的错误
  */
-export async function saveEnvSwitcherConfig(claudePath: string, config: EnvSwitcherConfig): Promise<void> {
-  return invoke<void>('save_env_config', { claudePath, config });
+export function parseSessionReadError(
+  err: unknown
+): { code: SessionReadErrorCode; message: string; suggestion: string } | null {
+  const match = /^(CloudPlaceholder|PermissionDenied|TooLarge|NotUtf8|NeedsUtf8): ([\s\S]*)\n建议: (.*)$/.exec(String(err));
+  if (!match) return null;
+  return { code: match[1] as SessionReadErrorCode, message: match[2], suggestion: match[3] };
 }
 
 /**
@@ -136,7 +211,7 @@ export async function applyEnvProfile(
   claudePath: string,
   profile: EnvProfile
 ): Promise<ClaudeSettings> {
-  const settings = await readSettings(claudePath);
+  const { value: settings } = await readSettings(claudePath);
   const updatedSettings = {
     ...settings,
     env: { ...profile.env },
@@ -159,11 +234,11 @@ export async function saveCurrentAsProfile(
   claudePath: string,
   name: string
 ): Promise<EnvProfile> {
-  const settings = await readSettings(claudePath);
+  const { value: settings } = await readSettings(claudePath);
   // 如果当前设置中没有 env 字段，使用空对象作为默认值
   const profile = createEnvProfile(name, settings.env || {});
 
-  const config = await readEnvSwitcherConfig(claudePath);
+  const { value: config } = await readEnvSwitcherConfig(claudePath);
   config.profiles.push(profile);
   // 将新创建的配置组设置为当前激活的配置
   config.activeProfileId = profile.id;
@@ -178,13 +253,13 @@ export async function saveCurrentAsProfile(
  * 读取 Claude Code 设置文件
  *
  * 通过 Rust 后端从 ~/.claude/settings.json 加载用户设置。
- * 如果文件不存在，返回空对象 {}。
+ * 如果文件不存在，返回空对象 {}。文件含注释或尾逗号时按 JSONC 容错解析，`wasLenient` 为 true。
  *
  * @param claudePath - Claude 数据目录路径（~/.claude/）
- * @returns 返回解析后的 ClaudeSettings 对象；文件不存在时返回空对象 {}
+ * @returns 返回解析后的 ClaudeSettings 对象（文件不存在时为空对象 {}）及是否经过容错解析
  */
-export async function readSettings(claudePath: string): Promise<ClaudeSettings> {
-  return invoke<ClaudeSettings>('read_settings', { claudePath });
+export async function readSettings(claudePath: string): Promise<LenientParsed<ClaudeSettings>> {
+  return invoke<LenientParsed<ClaudeSettings>>('read_settings', { claudePath });
 }
 
 /**
@@ -194,9 +269,63 @@ export async function readSettings(claudePath: string): Promise<ClaudeSettings>
  *
  * @param claudePath - Claude 数据目录路径（~/.claude/）
  * @param settings - 要保存的完整设置对象
+ * @param discardComments - 现有文件含注释时是否确认丢弃；未确认时后端返回 "CommentsWillBeLost" 错误
+ */
+export async function saveSettings(
+  claudePath: string,
+  settings: ClaudeSettings,
+  discardComments?: boolean
+): Promise<void> {
+  return invoke<void>('save_settings', { claudePath, settings, discardComments });
+}
+
+/**
+ * 读取外观配置（statusLine / outputStyle 与自定义输出样式列表）
+ *
+ * 字段缺失时返回默认值。
+ *
+ * @param claudePath - Claude 数据目录路径（~/.claude/）
+ * @returns 外观配置
+ */
+export async function getAppearanceConfig(claudePath: string): Promise<AppearanceConfig> {
+  return invoke<AppearanceConfig>('get_appearance_config', { claudePath });
+}
+
+/**
+ * 保存外观配置（只改动 settings.json 的 statusLine / outputStyle 字段）
+ *
+ * @param claudePath - Claude 数据目录路径（~/.claude/）
+ * @param statusLine - 状态栏配置，null 表示移除
+ * @param outputStyle - 输出样式名，null 或 "default" 表示移除
+ * @param discardComments - settings.json 含注释时是否确认丢弃；未确认时后端返回 "CommentsWillBeLost" 错误
  */
-export async function saveSettings(claudePath: string, settings: ClaudeSettings): Promise<void> {
-  return invoke<void>('save_settings', { claudePath, settings });
+export async function saveAppearanceConfig(
+  claudePath: string,
+  statusLine: StatusLineConfig | null,
+  outputStyle: string | null,
+  discardComments?: boolean,
+): Promise<void> {
+  return invoke<void>('save_appearance_config', { claudePath, statusLine, outputStyle, discardComments });
+}
+
+/**
+ * 读取自定义输出样式文件内容
+ *
+ * @param path - 样式文件的绝对路径（~/.claude/output-styles/ 下）
+ * @returns 完整文件内容（含 frontmatter）
+ */
+export async function readOutputStyle(path: string): Promise<string> {
+  return invoke<string>('read_output_style', { path });
+}
+
+/**
+ * 保存（或新建）自定义输出样式文件
+ *
+ * @param path - 样式文件的绝对路径（~/.claude/output-styles/ 下的 .md 文件）
+ * @param content - 完整文件内容（含 frontmatter）
+ */
+export async function saveOutputStyle(path: string, content: string): Promise<void> {
+  return invoke<void>('save_output_style', { path, content });
 }
 
 // ============ 历史记录 ============
@@ -232,270 +361,1479 @@ export async function readHistory(claudePath: string): Promise<HistoryEntry[]> {
  * @returns 返回按最新会话时间倒序排列的 Project 数组
  */
 export async function getProjects(claudePath: string): Promise<Project[]> {
-  // Rust 后端返回的 Session.timestamp 是 ISO 8601 字符串
-  // 需要将其转换为 Date 对象以保持前端类型兼容
-  const projects = await invoke<Project[]>('scan_projects', { claudePath });
+  const page = await getProjectsPage(claudePath);
+  if (page.warnings?.length) {
+    console.warn(`有 ${page.warnings.length} 个项目无法读取:`, page.warnings);
+  }
+  return page.projects;
+}
+
+/**
+ * 分页扫描项目
+ *
+ * 项目目录按 mtime 倒序分页；已加载的页由后端缓存，
+ * 「加载更多」时以 `offset = 当前已加载数量` 继续请求。
+ *
+ * @param claudePath - Claude 数据目录路径（~/.claude/）
+ * @param options.maxProjects - 本页最多项目数；缺省时扫描全部
+ * @param options.offset - 起始偏移；缺省为 0
+ * @param options.forceRefresh - 跳过缓存重新扫描
+ * @param options.sourceId - 数据源 ID，"all" 表示合并全部数据源；缺省时只扫描 claudePath
+ * @param options.includeMessageCount - 逐行读取会话文件统计 messageCount 与 lineCount（较慢，文件未变化时后端沿用上次统计）
+ * @param options.includeAgentSessions - 同时列出 agent- 前缀的子 agent 会话（`Session.isAgent`）
+ * @param options.includeArchived - 同时列出已归档的项目（`Project.archived`）
+ * @param options.indexOnly - 只返回项目索引（`sessions` 为空，附 `sessionCount` / `latestTimestamp`），
+ *   会话列表改由 `getProjectSessionsPage` 按需加载
+ * @param options.sortBy - 项目内会话的排序键；缺省按时间
+ * @param options.order - 排序方向；缺省为降序
+ * @returns 返回 ProjectPage，页内项目按最新会话时间倒序排列；实际扫描时无法读取的目录在 `warnings` 中
+ */
+export async function getProjectsPage(
+  claudePath: string,
+  options: {
+    maxProjects?: number;
+    offset?: number;
+    forceRefresh?: boolean;
+    sourceId?: string;
+    includeAgentSessions?: boolean;
+    includeMessageCount?: boolean;
+    indexOnly?: boolean;
+    includeArchived?: boolean;
+    sortBy?: SessionSortBy;
+    order?: SortOrder;
+  } = {}
+): Promise<ProjectPage> {
+  const page = await invoke<ProjectPage>('scan_projects', { claudePath, ...options });
 
   // 将 Rust 返回的 ISO 8601 时间字符串转换为 Date 对象
-  for (const project of projects) {
+  for (const project of page.projects) {
     for (const session of project.sessions) {
       session.timestamp = new Date(session.timestamp as unknown as string);
     }
   }
 
-  return projects;
+  return page;
 }
 
-// ============ 消息操作 ============
-
 /**
- * 读取指定会话的所有消息并返回转换后的 TransformedSession
+ * 监听项目扫描进度
  *
- * 通过 Rust 后端高性能解析 JSONL 文件，分类、转换后返回可直接渲染的数据。
- * Rust 后端利用 rayon 并行 map + memchr SIMD 搜索，性能远超前端 JS。
+ * `getProjectsPage` 缓存未命中、实际扫描时，后端每 50 个项目或 200ms 推送一次 `scan-progress` 事件，
+ * 最后一个项目完成时总会推送；缓存命中时不会收到事件。
  *
- * @param sessionFilePath - 会话 JSONL 文件的绝对路径
- * @returns 返回 TransformedSession，包含倒序的 displayMessages、toolUseMap 和 tokenStats
+ * @param handler - 事件回调
+ * @returns 取消监听的函数
  */
-export async function readSessionMessages(sessionFilePath: string): Promise<TransformedSession> {
-  return invoke<TransformedSession>('read_session_messages', { sessionFilePath });
+export async function onScanProgress(handler: (event: ScanProgress) => void): Promise<UnlistenFn> {
+  return listen<ScanProgress>('scan-progress', (e) => handler(e.payload));
 }
 
 /**
- * 删除指定的单条消息
- *
- * 通过 Rust 后端在单次 IPC 调用中完成：读取文件 → 过滤消息 → 写入文件 → 重新 transform。
+ * 按需分页加载单个项目的会话列表
+ *
+ * 配合 `getProjectsPage(claudePath, { indexOnly: true })` 使用，展开项目时再加载其会话。
+ *
+ * @param projectName - 项目目录名（`Project.name`）
+ * @param options.sourceId - 项目所属数据源 ID（`Project.source`）；缺省为默认数据源
+ * @param options.offset - 起始偏移；缺省为 0
+ * @param options.limit - 本页最多会话数；缺省时返回全部
+ * @param options.forceRefresh - 跳过缓存重新扫描该项目
+ * @param options.includeAgentSessions - 同时列出 agent- 前缀的子 agent 会话
+ * @param options.sortBy - 排序键；缺省按时间（缺少排序键的会话排在末尾）
+ * @param options.order - 排序方向；缺省为降序
+ * @returns 返回 SessionPage，会话置顶在前、其余按排序键排列（先排序后分页）
+ */
+export async function getProjectSessionsPage(
+  projectName: string,
+  options: {
+    sourceId?: string;
+    offset?: number;
+    limit?: number;
+    forceRefresh?: boolean;
+    includeAgentSessions?: boolean;
+    sortBy?: SessionSortBy;
+    order?: SortOrder;
+  } = {}
+): Promise<SessionPage> {
+  const page = await invoke<SessionPage>('scan_project_sessions', { projectName, ...options });
+  for (const session of page.sessions) {
+    session.timestamp = new Date(session.timestamp as unknown as string);
+  }
+  return page;
+}
+
+/**
+ * 按关键词过滤项目树（后端匹配项目路径、会话 ID 与会话名称，大小写不敏感）
  *
- * @param sessionFilePath - 会话 JSONL 文件的绝对路径
- * @param messageUuid - 要删除的消息的 UUID
- * @returns 返回删除后重新转换的 TransformedSession
+ * @param claudePath - Claude 数据目录路径（~/.claude/）
+ * @param query - 过滤关键词；为空时返回完整列表
+ * @returns 过滤后的项目列表，项目只包含命中的会话（项目路径命中时包含全部会话）
  */
-export async function deleteMessage(sessionFilePath: string, messageUuid: string): Promise<TransformedSession> {
-  return invoke<TransformedSession>('delete_message', { sessionFilePath, messageUuid });
+export async function filterProjects(claudePath: string, query: string): Promise<Project[]> {
+  const projects = await invoke<Project[]>('filter_projects', { claudePath, query });
+  for (const project of projects) {
+    for (const session of project.sessions) {
+      session.timestamp = new Date(session.timestamp as unknown as string);
+    }
+  }
+  return projects;
 }
 
 /**
- * 批量删除多条消息
+ * 统计项目与会话数量（只读目录条目，不读取会话内容）
  *
- * 通过 Rust 后端在单次 IPC 调用中完成批量删除 → 重新 transform。
- * 前端传入的 Set<string> 会被转换为 string[] 进行 IPC 传输。
+ * 比 `getProjectsPage` 轻得多，适合侧边栏角标和冷启动时先行显示总数。
  *
- * @param sessionFilePath - 会话 JSONL 文件的绝对路径
- * @param messageUuids - 要删除的消息 UUID 集合（Set<string>）
- * @returns 返回删除后重新转换的 TransformedSession
+ * @param claudePath - Claude 数据目录路径（~/.claude/）
+ * @returns 项目目录数、会话文件数与子 agent 会话文件数
  */
-export async function deleteMessages(sessionFilePath: string, messageUuids: Set<string>): Promise<TransformedSession> {
-  // Set<string> 无法直接通过 Tauri IPC 传输，需转换为数组
-  return invoke<TransformedSession>('delete_messages', {
-    sessionFilePath,
-    messageUuids: Array.from(messageUuids),
-  });
+export async function getCounts(claudePath: string): Promise<ProjectCounts> {
+  return invoke<ProjectCounts>('get_counts', { claudePath });
 }
 
 /**
- * 单个内容块的编辑数据
+ * 读取项目摘要：项目目录下 CLAUDE.md（优先）或 README.md 的开头部分
  *
- * 描述对消息 content 数组中某个内容块的文本修改：
- * - index：内容块在数组中的位置
- * - text：用户编辑后的新文本
+ * 后端只读访问，最多读取 64KB，二进制或非 UTF-8 内容会被拒绝；结果按文件 mtime 缓存。
+ *
+ * @param projectPath - 项目的真实文件系统路径（已解码）
+ * @returns 摘要文本与来源文件路径；两个文件都不存在时为 null
  */
-export interface BlockEdit {
-  /** 内容块在 message.content 数组中的索引位置 */
-  index: number;
-  /** 用户编辑后的新文本内容 */
-  text: string;
+export async function getProjectSummary(projectPath: string): Promise<ProjectSummary | null> {
+  return invoke<ProjectSummary | null>('get_project_summary', { projectPath });
 }
 
 /**
- * 按内容块索引编辑指定消息
+ * 汇总项目下全部会话的统计（结果在后端缓存 5 分钟）
  *
- * 通过 Rust 后端在单次 IPC 调用中完成：读取文件 → 按块索引修改 → 写入文件 → 重新 transform。
- * 每个内容块的 type 和其他元数据保持不变，仅更新对应的文本字段。
+ * @param projectName - 项目目录名
+ * @param sourceId - 项目所属数据源（即 `Project.source`）；缺省为默认数据源
+ * @param forceRefresh - 为 true 时忽略缓存重新统计
+ * @returns 会话数、总行数、总字节数与 token 汇总
+ */
+export async function getProjectStats(projectName: string, sourceId?: string, forceRefresh?: boolean): Promise<ProjectStats> {
+  return invoke<ProjectStats>('get_project_stats', { projectName, sourceId, forceRefresh });
+}
+
+/**
+ * 汇总数据目录中各 Claude Code 版本的会话分布
  *
- * @param sessionFilePath - 会话 JSONL 文件的绝对路径
- * @param messageUuid - 要编辑的消息的 UUID
- * @param blockEdits - 按块索引的编辑列表
- * @returns 返回更新后重新转换的 TransformedSession
+ * @param claudePath - Claude 数据目录路径（~/.claude/）
+ * @returns 数据兼容性报告（新版本在前）
  */
-export async function editMessageContent(
-  sessionFilePath: string,
-  messageUuid: string,
-  blockEdits: BlockEdit[]
-): Promise<TransformedSession> {
-  return invoke<TransformedSession>('edit_message_content', {
-    sessionFilePath,
-    messageUuid,
-    blockEdits,
-  });
+export async function getDataCompatReport(claudePath: string): Promise<DataCompatReport> {
+  return invoke<DataCompatReport>('get_data_compat_report', { claudePath });
 }
 
+// ============ 消息操作 ============
+
 /**
- * 在指定位置插入一条新消息
+ * 读取指定会话的所有消息并返回转换后的 TransformedSession
+ *
+ * 通过 Rust 后端高性能解析 JSONL 文件，分类、转换后返回可直接渲染的数据。
+ * Rust 后端利用 rayon 并行 map + memchr SIMD 搜索，性能远超前端 JS。
  *
- * 通过 Rust 后端在单次 IPC 调用中完成：读取文件 → 定位插入位置 → 插入 → 写入文件 → 重新 transform。
- * 新消息由前端构造完整的 SessionMessage JSON 对象，Rust 后端仅负责定位和持久化。
+ * 传入 `page` 时只返回 displayMessages 的一个窗口（toolUseMap、tokenStats 仍为全量），
+ * 并附带 `totalCount`。displayMessages 为倒序，`offset = 0` 是最新的一页；
+ * 滚动到顶部时以 `offset + 已加载条数` 请求更早的消息。
  *
  * @param sessionFilePath - 会话 JSONL 文件的绝对路径
- * @param afterUuid - 插入到此 UUID 消息之后（空字符串表示插入到最前方）
- * @param newMessage - 前端构造好的完整 SessionMessage JSON 对象
- * @returns 返回插入后重新转换的 TransformedSession
+ * @param branchLeaf - 分支叶子消息 UUID（来自 getSessionBranches），传入时只返回该分支路径上的消息
+ * @param page.offset - displayMessages 的起始偏移；缺省为 0
+ * @param page.limit - 本页最多条数；缺省时取到末尾
+ * @returns 返回 TransformedSession，包含倒序的 displayMessages、toolUseMap 和 tokenStats
  */
-export async function insertMessage(
+export async function readSessionMessages(
   sessionFilePath: string,
-  afterUuid: string,
-  newMessage: Record<string, unknown>,
+  branchLeaf?: string,
+  page?: { offset?: number; limit?: number }
 ): Promise<TransformedSession> {
-  return invoke<TransformedSession>('insert_message', {
+  return invoke<TransformedSession>('read_session_messages', {
     sessionFilePath,
-    afterUuid,
-    newMessage,
+    branchLeaf: branchLeaf ?? null,
+    offset: page?.offset ?? null,
+    limit: page?.limit ?? null,
   });
 }
 
-// ============ 搜索功能 ============
-
 /**
- * 搜索选项接口
+ * 枚举会话的分叉点与所有分支
  *
- * 与 Rust 后端 `search_session` 命令的 `case_sensitive` / `use_regex` 参数对应。
+ * Claude Code 中按 Esc 回退重新提问会让对话分叉。Rust 后端基于 parentUuid 构建消息树，
+ * 返回分叉点、每条根 → 叶子路径的 UUID 序列，并标注当前实际采用的主线。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @returns 分叉点与分支列表（主线在前）
  */
-export interface SearchOptions {
-  /** 是否大小写敏感（默认 false） */
-  caseSensitive?: boolean;
-  /** 是否启用正则表达式模式（默认 false） */
-  useRegex?: boolean;
+export async function getSessionBranches(sessionFilePath: string): Promise<SessionBranches> {
+  return invoke<SessionBranches>('get_session_branches', { sessionFilePath });
 }
 
 /**
- * 在 Rust 后端搜索会话消息
+ * 读取被折叠的超长文本块全文
  *
- * 支持三种模式：
- * - 默认：大小写不敏感的 memchr SIMD 子串搜索（最快）
- * - caseSensitive：大小写敏感的 memchr SIMD 子串搜索
- * - useRegex：通过 Rust regex crate 正则匹配（大小写由 caseSensitive 控制）
+ * 超过阈值（limits.json 的 oversizedTextKb）的 text 块在 DisplayMessage 中被替换为
+ * `oversized_text` 占位，用户展开时再通过本函数从文件读取完整内容。
  *
  * @param sessionFilePath - 会话 JSONL 文件的绝对路径
- * @param query - 搜索查询词
- * @param options - 搜索选项（可选，默认不敏感+非正则）
- * @returns 返回匹配的 display_id 字符串数组
+ * @param messageUuid - 消息的 UUID（DisplayMessage 的 sourceUuid）
+ * @param blockIndex - 占位块的 block_index
+ * @returns 文本块的完整内容
  */
-export async function searchSession(
-  sessionFilePath: string,
-  query: string,
-  options?: SearchOptions,
-): Promise<string[]> {
-  return invoke<string[]>('search_session', {
-    sessionFilePath,
-    query,
-    caseSensitive: options?.caseSensitive ?? false,
-    useRegex: options?.useRegex ?? false,
-  });
+export async function readFullBlock(sessionFilePath: string, messageUuid: string, blockIndex: number): Promise<string> {
+  return invoke<string>('read_full_block', { sessionFilePath, messageUuid, blockIndex });
 }
 
-// ============ 导出功能 ============
+/** 流式加载完成前的 Token 统计占位（汇总数据在 `session-stream-done` 事件中给出） */
+const EMPTY_TOKEN_STATS: TokenStats = {
+  inputTokens: 0,
+  outputTokens: 0,
+  cacheCreationInputTokens: 0,
+  cacheReadInputTokens: 0,
+  webSearchRequests: 0,
+  webFetchRequests: 0,
+  toolCalls: 0,
+  failedToolCalls: 0,
+};
 
 /**
- * 导出会话为指定格式
+ * 流式读取会话：转换完成前即可分批渲染已产出的消息
  *
- * 通过 Rust 后端从文件直接读取原始消息并导出。
- * Markdown 格式仅导出 user/assistant 消息的文本内容。
- * JSON 格式保留所有消息的完整结构。
+ * Rust 后端解析完成后立即返回，随后每转换 `chunkSize` 条消息推送一次 `session-chunk` 事件，
+ * 结束时推送 `session-stream-done`。事件可能早于 invoke 返回值到达，因此先注册监听，
+ * 在拿到 streamId 之前缓冲所有事件；分块按 `seq` 拼接，保证顺序。
+ * 缓存命中时直接返回完整会话，不触发 `onProgress`。
  *
  * @param sessionFilePath - 会话 JSONL 文件的绝对路径
- * @param sessionName - 会话名称（用于 Markdown 标题）
- * @param format - 导出格式："markdown" 或 "json"
- * @returns 返回导出的字符串内容
+ * @param chunkSize - 每批消息条数
+ * @param onProgress - 每拼接上新的分块时以当前累计结果回调（tokenStats 等汇总数据在完成前为零值）
+ * @returns 全部分块与汇总数据合并后的完整 TransformedSession
  */
-export async function exportSession(
+export async function readSessionMessagesStreamed(
   sessionFilePath: string,
-  sessionName: string,
-  format: 'markdown' | 'json'
-): Promise<string> {
-  return invoke<string>('export_session', { sessionFilePath, sessionName, format });
-}
+  chunkSize: number,
+  onProgress: (partial: TransformedSession) => void
+): Promise<TransformedSession> {
+  let streamId: number | null = null;
+  const earlyChunks: SessionChunk[] = [];
+  const earlyDones: SessionStreamDone[] = [];
+
+  const pending = new Map<number, SessionChunk>();
+  let nextSeq = 0;
+  let done: SessionStreamDone | null = null;
+  const displayMessages: DisplayMessage[] = [];
+  const toolUseMap: Record<string, ToolUseInfo> = {};
+
+  let finish: (session: TransformedSession) => void = () => {};
+  const finished = new Promise<TransformedSession>((resolve) => { finish = resolve; });
+
+  const tryFinish = () => {
+    if (done && nextSeq >= done.chunks) {
+      finish({ displayMessages, toolUseMap, tokenStats: done.tokenStats, cwdChanges: done.cwdChanges, cwds: done.cwds });
+    }
+  };
 
-// ============ 格式化工具 ============
+  const handleChunk = (chunk: SessionChunk) => {
+    if (streamId === null) {
+      earlyChunks.push(chunk);
+      return;
+    }
+    if (chunk.streamId !== streamId) return;
+    pending.set(chunk.seq, chunk);
+
+    let advanced = false;
+    for (let next = pending.get(nextSeq); next; next = pending.get(nextSeq)) {
+      pending.delete(nextSeq);
+      nextSeq++;
+      displayMessages.push(...next.displayMessages);
+      Object.assign(toolUseMap, next.toolUseMap);
+      advanced = true;
+    }
+    if (advanced && !done) {
+      onProgress({ displayMessages: [...displayMessages], toolUseMap: { ...toolUseMap }, tokenStats: EMPTY_TOKEN_STATS, cwdChanges: [], cwds: [] });
+    }
+    tryFinish();
+  };
+
+  const handleDone = (payload: SessionStreamDone) => {
+    if (streamId === null) {
+      earlyDones.push(payload);
+      return;
+    }
+    if (payload.streamId !== streamId) return;
+    done = payload;
+    tryFinish();
+  };
+
+  // 后端只向发起读取的窗口推送，监听也限定在当前窗口
+  const currentWindow = getCurrentWebviewWindow();
+  const unlistenChunk = await currentWindow.listen<SessionChunk>('session-chunk', (e) => handleChunk(e.payload));
+  const unlistenDone = await currentWindow.listen<SessionStreamDone>('session-stream-done', (e) => handleDone(e.payload));
+  try {
+    const start = await invoke<SessionStreamStart>('read_session_messages_streamed', { sessionFilePath, chunkSize });
+    if (start.cached) return start.cached;
+
+    streamId = start.streamId;
+    earlyChunks.forEach(handleChunk);
+    earlyDones.forEach(handleDone);
+    return await finished;
+  } finally {
+    unlistenChunk();
+    unlistenDone();
+  }
+}
 
 /**
- * 删除指定的会话文件
- *
- * 通过 Rust 后端从文件系统中永久移除会话的 JSONL 文件。
- * 同时清除相关的内存缓存。
+ * 统计会话中出现过的 Claude Code 版本及各自条数
  *
- * @param sessionFilePath - 要删除的会话 JSONL 文件的绝对路径
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @returns 版本统计（新版本在前）
  */
-export async function deleteSession(sessionFilePath: string): Promise<void> {
-  return invoke<void>('delete_session', { sessionFilePath });
+export async function getSessionVersions(sessionFilePath: string): Promise<SessionVersionStats> {
+  return invoke<SessionVersionStats>('get_session_versions', { sessionFilePath });
 }
 
-// ============ 文件系统辅助 ============
-
 /**
- * 检查指定路径的文件是否存在
- *
- * 通过 Rust 后端检查文件系统中文件是否存在。
- * 用于在渲染工具结果的"打开文件位置"按钮前判断按钮是否应该可用。
+ * 获取会话统计：Token / 工具调用汇总、按工具名的失败率、stop_reason 分布与消息数 / 字数
  *
- * @param filePath - 要检查的文件的绝对路径
- * @returns 文件存在返回 true，否则返回 false
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param excludeNoise - 消息数与字数是否剔除压缩摘要与计划以外的系统注入（默认 true，Token 统计不受影响）
+ * @returns 会话统计
  */
-export async function checkFileExists(filePath: string): Promise<boolean> {
-  return invoke<boolean>('check_file_exists', { filePath });
+export async function getSessionStats(sessionFilePath: string, excludeNoise = true): Promise<SessionStats> {
+  return invoke<SessionStats>('get_session_stats', { sessionFilePath, excludeNoise });
 }
 
-// ============ 实用工具函数 ============
-
 /**
- * 读取一键 Resume 配置
+ * 筛选会话中失败的工具调用
  *
- * 从 `~/.mo/CCR/resume-config.json` 加载用户配置的 Resume 参数。
- * 配置文件不存在时返回默认空配置（空 flags + 空 customArgs）。
+ * 返回失败的 tool_result 消息及发起对应调用的 assistant 消息的 displayId，
+ * 供「只看失败的调用」筛选使用。
  *
- * @returns ResumeConfig 对象
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @returns 按消息顺序排列的 displayId 列表
  */
-export async function readResumeConfig(): Promise<ResumeConfig> {
-  return invoke<ResumeConfig>('read_resume_config');
+export async function getFailedToolCalls(sessionFilePath: string): Promise<string[]> {
+  return invoke<string[]>('get_failed_tool_calls', { sessionFilePath });
 }
 
 /**
- * 保存一键 Resume 配置
+ * 提取会话中引用的所有 http/https 链接
  *
- * 将 ResumeConfig 序列化为 JSON 并写入 `~/.mo/CCR/resume-config.json`。
+ * 由 Rust 后端扫描 text、thinking 与 tool_result 内容，按首次出现顺序去重。
  *
- * @param config - 要保存的 ResumeConfig 对象
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @returns 链接列表，每项含首次出现的 displayId、上下文片段与出现次数
  */
-export async function saveResumeConfig(config: ResumeConfig): Promise<void> {
-  return invoke<void>('save_resume_config', { config });
+export async function extractLinks(sessionFilePath: string): Promise<SessionLink[]> {
+  return invoke<SessionLink[]>('extract_links', { sessionFilePath });
 }
 
 /**
- * 打开系统终端执行 claude --resume 命令
+ * 提取会话中 assistant 回复里的围栏代码块
  *
- * 在 Rust 后端通过 `std::process::Command` 打开系统终端，
- * 自动 cd 到项目目录并执行 `claude --resume <sessionId> <flags> <customArgs>`。
+ * 由 Rust 后端解析 assistant 文本，容错嵌套反引号与未闭合围栏，按内容哈希去重。
  *
- * @param projectPath - 项目的真实文件系统路径（已解码）
- * @param sessionId - 会话 UUID
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @returns 代码块列表，每项含语言标记、行数、首次出现的 displayId 与内容哈希
  */
-export async function openResumeTerminal(projectPath: string, sessionId: string): Promise<void> {
-  return invoke<void>('open_resume_terminal', { projectPath, sessionId });
+export async function extractCodeBlocks(sessionFilePath: string): Promise<CodeBlock[]> {
+  return invoke<CodeBlock[]>('extract_code_blocks', { sessionFilePath });
 }
 
 /**
- * 构建 resume 命令字符串（不执行）
+ * 把选中的代码块保存为 `snippet_<n>.<ext>` 文件
  *
- * 调用 Rust 后端构建完整的 `claude --resume <sessionId> <flags> <customArgs>` 命令，
- * 返回字符串供前端复制到剪贴板。
+ * 扩展名由语言标记决定，不覆盖目标目录中已有的文件。
  *
- * @param sessionId - 会话 UUID
- * @returns 完整的 resume 命令字符串
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param blockIds - 要保存的代码块 ID（`CodeBlock.id`），按此顺序编号
+ * @param targetDir - 目标目录
+ * @returns 写入的文件路径列表
  */
-export async function buildResumeCommand(sessionId: string): Promise<string> {
-  return invoke<string>('build_resume_command', { sessionId });
+export async function saveCodeBlocks(sessionFilePath: string, blockIds: string[], targetDir: string): Promise<string[]> {
+  return invoke<string[]>('save_code_blocks', { sessionFilePath, blockIds, targetDir });
 }
 
-// ============ 备份配置 ============
-
+/**
+ * 提取会话的主题关键词
+ *
+ * 由 Rust 后端对搜索文本做简单分词（英文按符号切分、中文 2-gram）、去停用词后按词频排序，
+ * 结果随会话缓存保存。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param topN - 返回的关键词数量（最多 100 个）
+ * @returns 按词频降序排列的关键词
+ */
+export async function getSessionKeywords(sessionFilePath: string, topN: number): Promise<SessionKeyword[]> {
+  return invoke<SessionKeyword[]>('get_session_keywords', { sessionFilePath, topN });
+}
+
+/**
+ * 查询某个 tool_use 的完整输入与输出配对详情
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param toolUseId - 工具调用 ID
+ * @returns 配对详情；仅找到一侧时 `missing` 说明缺失的一侧
+ */
+export async function getToolInvocation(
+  sessionFilePath: string,
+  toolUseId: string
+): Promise<ToolInvocation> {
+  return invoke<ToolInvocation>('get_tool_invocation', { sessionFilePath, toolUseId });
+}
+
+/**
+ * 对比工具结果中的文件内容与磁盘上的当前文件
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param displayId - tool_result 消息的 displayId
+ * @returns diff 片段与统计；文件已删除或为二进制时通过 `status` 标明
+ */
+export async function diffToolResultWithDisk(
+  sessionFilePath: string,
+  displayId: string
+): Promise<ToolResultDiff> {
+  return invoke<ToolResultDiff>('diff_tool_result_with_disk', { sessionFilePath, displayId });
+}
+
+/**
+ * 生成只保留最近 N 条消息的会话瘦身副本（原文件不变）
+ *
+ * 被截断的 tool_use 配对与 assistant 回复会自动补齐，实际条数可能多于 `keepLastN`。
+ *
+ * @param sessionFilePath - 源会话 JSONL 文件的绝对路径
+ * @param keepLastN - 保留主线上最后多少条消息
+ * @param outputPath - 可选的导出路径（须在 Claude 数据目录之外）；省略时在同项目下生成新 UUID 会话
+ * @returns 副本路径、会话 ID 与实际包含的条数
+ */
+export async function createTrimmedCopy(
+  sessionFilePath: string,
+  keepLastN: number,
+  outputPath?: string
+): Promise<TrimmedCopyResult> {
+  return invoke<TrimmedCopyResult>('create_trimmed_copy', { sessionFilePath, keepLastN, outputPath });
+}
+
+/**
+ * 复制整个会话为同项目下的新会话（原文件不变）
+ *
+ * 新会话使用新的 UUID 文件名，每行的 sessionId 随之改写，可放心在副本上做实验性修剪。
+ *
+ * @param sessionFilePath - 源会话 JSONL 文件的绝对路径
+ * @returns 新会话的 Session 结构（timestamp 已转换为 Date）
+ */
+export async function duplicateSession(sessionFilePath: string): Promise<Session> {
+  const session = await invoke<Session>('duplicate_session', { sessionFilePath });
+  session.timestamp = new Date(session.timestamp as unknown as string);
+  return session;
+}
+
+/**
+ * 把源会话合并到目标会话（两者须位于同一项目目录）
+ *
+ * 冲突的 uuid 会重新生成，源会话的首条消息接到目标会话中位于它之前的最后一条消息上。
+ * 目标文件写回前经 file_guard 备份。
+ *
+ * @param sourcePath - 源会话 JSONL 文件的绝对路径
+ * @param targetPath - 目标会话 JSONL 文件的绝对路径
+ * @param position - 插入位置：按时间戳归并或追加到末尾
+ * @param deleteSource - 合并成功后是否删除源会话
+ * @returns 并入条数、重新生成 uuid 的条数与源会话是否已删除
+ */
+export async function mergeSessions(
+  sourcePath: string,
+  targetPath: string,
+  position: MergePosition,
+  deleteSource: boolean
+): Promise<MergeSessionsResult> {
+  return invoke<MergeSessionsResult>('merge_sessions', { sourcePath, targetPath, position, deleteSource });
+}
+
+/**
+ * 删除指定的单条消息
+ *
+ * 通过 Rust 后端在单次 IPC 调用中完成：读取文件 → 过滤消息 → 写入文件 → 重新 transform。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param messageUuid - 要删除的消息的 UUID
+ * @returns 返回删除后重新转换的 TransformedSession
+ */
+export async function deleteMessage(sessionFilePath: string, messageUuid: string): Promise<TransformedSession> {
+  return invoke<TransformedSession>('delete_message', { sessionFilePath, messageUuid });
+}
+
+/**
+ * 批量删除多条消息
+ *
+ * 通过 Rust 后端在单次 IPC 调用中完成批量删除 → 重新 transform。
+ * 前端传入的 Set<string> 会被转换为 string[] 进行 IPC 传输。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param messageUuids - 要删除的消息 UUID 集合（Set<string>）
+ * @returns 返回删除后重新转换的 TransformedSession
+ */
+export async function deleteMessages(sessionFilePath: string, messageUuids: Set<string>): Promise<TransformedSession> {
+  // Set<string> 无法直接通过 Tauri IPC 传输，需转换为数组
+  return invoke<TransformedSession>('delete_messages', {
+    sessionFilePath,
+    messageUuids: Array.from(messageUuids),
+  });
+}
+
+/**
+ * 删除单条消息（回执模式）
+ *
+ * 与 `deleteMessage` 相同，但只返回差异回执，避免大会话回传完整的 TransformedSession。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param messageUuid - 要删除的消息的 UUID
+ * @returns 被移除的 displayId、有变化的显示消息与新的 Token 统计
+ */
+export async function deleteMessageReceipt(sessionFilePath: string, messageUuid: string): Promise<OperationReceipt> {
+  return invoke<OperationReceipt>('delete_message', { sessionFilePath, messageUuid, receipt: true });
+}
+
+/**
+ * 批量删除多条消息（回执模式）
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param messageUuids - 要删除的消息 UUID 集合（Set<string>）
+ * @returns 差异回执
+ */
+export async function deleteMessagesReceipt(sessionFilePath: string, messageUuids: Set<string>): Promise<OperationReceipt> {
+  return invoke<OperationReceipt>('delete_messages', {
+    sessionFilePath,
+    messageUuids: Array.from(messageUuids),
+    receipt: true,
+  });
+}
+
+/**
+ * 把操作回执合并到当前会话（局部更新）
+ *
+ * 移除 `removedDisplayIds`，用 `updatedMessages` 替换同 displayId 的消息，并更新 Token 统计。
+ * 回执中含当前会话没有的 displayId 时无法确定插入位置，返回 null，调用方应重新读取会话。
+ *
+ * @param session - 当前会话
+ * @param receipt - 写操作返回的回执
+ * @returns 合并后的新会话对象；无法局部合并时返回 null
+ */
+export function applyOperationReceipt(session: TransformedSession, receipt: OperationReceipt): TransformedSession | null {
+  const removed = new Set(receipt.removedDisplayIds);
+  const updated = new Map(receipt.updatedMessages.map((msg) => [msg.displayId, msg]));
+  const displayMessages = session.displayMessages
+    .filter((msg) => !removed.has(msg.displayId))
+    .map((msg) => {
+      const next = updated.get(msg.displayId);
+      updated.delete(msg.displayId);
+      return next ?? msg;
+    });
+  if (updated.size > 0) return null;
+  const totalCount = session.totalCount === undefined ? undefined : session.totalCount - removed.size;
+  return { ...session, displayMessages, tokenStats: receipt.tokenStats, totalCount };
+}
+
+/**
+ * 截断会话：删除指定消息之后的全部内容
+ *
+ * 写入前经 file_guard 双重备份，截断前的快照可通过 `undoLastOperation` 恢复。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param messageUuid - 截断位置的消息 UUID（不存在时报错）
+ * @param inclusive - 是否同时删除该消息本身
+ * @returns 返回截断后重新转换的 TransformedSession
+ */
+export async function truncateSessionAfter(
+  sessionFilePath: string,
+  messageUuid: string,
+  inclusive: boolean,
+): Promise<TransformedSession> {
+  return invoke<TransformedSession>('truncate_session_after', { sessionFilePath, messageUuid, inclusive });
+}
+
+/**
+ * 把相邻的多条同类型消息合并为一条
+ *
+ * 所选消息须都为 user 或都为 assistant，且之间没有未选中的对话消息；
+ * content 块按文件顺序拼接进第一条，其余条目删除。可通过 `undoLastOperation` 撤销。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param messageUuids - 要合并的消息 UUID 集合（至少 2 条）
+ * @returns 返回合并后重新转换的 TransformedSession
+ */
+export async function mergeMessages(sessionFilePath: string, messageUuids: Set<string>): Promise<TransformedSession> {
+  return invoke<TransformedSession>('merge_messages', {
+    sessionFilePath,
+    messageUuids: Array.from(messageUuids),
+  });
+}
+
+/**
+ * 单个内容块的编辑数据
+ *
+ * 描述对消息 content 数组中某个内容块的文本修改：
+ * - index：内容块在数组中的位置
+ * - text：用户编辑后的新文本
+ */
+export interface BlockEdit {
+  /** 内容块在 message.content 数组中的索引位置 */
+  index: number;
+  /** 用户编辑后的新文本内容 */
+  text: string;
+}
+
+/**
+ * 按内容块索引编辑指定消息
+ *
+ * 通过 Rust 后端在单次 IPC 调用中完成：读取文件 → 按块索引修改 → 写入文件 → 重新 transform。
+ * 每个内容块的 type 和其他元数据保持不变，仅更新对应的文本字段。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param messageUuid - 要编辑的消息的 UUID
+ * @param blockEdits - 按块索引的编辑列表
+ * @returns 返回更新后重新转换的 TransformedSession
+ */
+export async function editMessageContent(
+  sessionFilePath: string,
+  messageUuid: string,
+  blockEdits: BlockEdit[]
+): Promise<TransformedSession> {
+  return invoke<TransformedSession>('edit_message_content', {
+    sessionFilePath,
+    messageUuid,
+    blockEdits,
+  });
+}
+
+/**
+ * 按内容块索引编辑指定消息（回执模式）
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param messageUuid - 要编辑的消息的 UUID
+ * @param blockEdits - 按块索引的编辑列表
+ * @returns 差异回执
+ */
+export async function editMessageContentReceipt(
+  sessionFilePath: string,
+  messageUuid: string,
+  blockEdits: BlockEdit[]
+): Promise<OperationReceipt> {
+  return invoke<OperationReceipt>('edit_message_content', {
+    sessionFilePath,
+    messageUuid,
+    blockEdits,
+    receipt: true,
+  });
+}
+
+/**
+ * 用原始 JSON 整体替换一条消息
+ *
+ * 用于修改 isMeta、parentUuid 等顶层字段。JSON 解析失败时错误信息含行列号；
+ * 缺少 uuid 时沿用原值，修改 uuid 会被拒绝。可通过 `undoLastOperation` 撤销。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param messageUuid - 要替换的消息的 UUID
+ * @param rawJson - 新的消息 JSON 文本
+ * @returns 返回更新后重新转换的 TransformedSession
+ */
+export async function editMessageRaw(
+  sessionFilePath: string,
+  messageUuid: string,
+  rawJson: string
+): Promise<TransformedSession> {
+  return invoke<TransformedSession>('edit_message_raw', { sessionFilePath, messageUuid, rawJson });
+}
+
+/**
+ * 撤销会话最近一次删除 / 编辑消息操作
+ *
+ * 后端为每个会话维护最多 10 步的撤销栈（仅内存，应用重启后清空）。
+ * 文件在该操作之后被外部修改时拒绝撤销，错误信息以 "UndoConflict" 开头。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @returns 被撤销的操作描述、剩余可撤销操作与恢复后的会话数据
+ */
+export async function undoLastOperation(sessionFilePath: string): Promise<UndoResult> {
+  return invoke<UndoResult>('undo_last_operation', { sessionFilePath });
+}
+
+/**
+ * 获取会话可撤销操作的描述
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @returns 操作描述列表（最近的在前）
+ */
+export async function getUndoHistory(sessionFilePath: string): Promise<string[]> {
+  return invoke<string[]>('get_undo_history', { sessionFilePath });
+}
+
+/**
+ * 获取某条消息被 CCR 编辑前的历史版本
+ *
+ * 每次编辑写回前，后端把旧版本追加到 `~/.mo/CCR/edit-history/<sessionId>.jsonl`（每个会话最多 500 条）。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param messageUuid - 消息的 UUID
+ * @returns 历史版本列表（最新在前）
+ */
+export async function getMessageEditHistory(sessionFilePath: string, messageUuid: string): Promise<MessageEditVersion[]> {
+  return invoke<MessageEditVersion[]>('get_message_edit_history', { sessionFilePath, messageUuid });
+}
+
+/**
+ * 用历史版本覆盖当前消息
+ *
+ * 覆盖前当前版本同样记入编辑历史，因此回滚本身也可以撤销。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param messageUuid - 消息的 UUID
+ * @param versionId - 历史版本 ID
+ * @returns 返回回滚后重新转换的 TransformedSession
+ */
+export async function revertMessageToVersion(
+  sessionFilePath: string,
+  messageUuid: string,
+  versionId: string
+): Promise<TransformedSession> {
+  return invoke<TransformedSession>('revert_message_to_version', { sessionFilePath, messageUuid, versionId });
+}
+
+/**
+ * 在指定位置插入一条新的对话消息
+ *
+ * 消息由 Rust 后端按 Claude Code JSONL 结构构造（uuid、parentUuid、timestamp、sessionId、
+ * cwd / version 等字段齐全），插入后修正前后的 parentUuid 链，可通过 `undoLastOperation` 撤销。
+ * user / assistant 消息只能通过此函数插入。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param position - 插入位置
+ * @param role - 消息角色
+ * @param text - 消息文本
+ * @returns 返回插入后重新转换的 TransformedSession
+ */
+export async function insertMessage(
+  sessionFilePath: string,
+  position: InsertPosition,
+  role: 'user' | 'assistant',
+  text: string,
+): Promise<TransformedSession> {
+  return invoke<TransformedSession>('insert_message', { sessionFilePath, position, role, text });
+}
+
+/**
+ * 在指定位置插入一条前端构造好的原始条目
+ *
+ * 用于 custom-title、tag、file-history-snapshot 等非对话条目（后端拒绝 user / assistant 消息）。
+ * 条目带 uuid 时，parentUuid 由 Rust 后端按插入位置设置。可通过 `undoLastOperation` 撤销。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param position - 插入位置
+ * @param newMessage - 前端构造好的完整 SessionMessage JSON 对象
+ * @returns 返回插入后重新转换的 TransformedSession
+ */
+export async function insertRawMessage(
+  sessionFilePath: string,
+  position: InsertPosition,
+  newMessage: Record<string, unknown>,
+): Promise<TransformedSession> {
+  return invoke<TransformedSession>('insert_raw_message', {
+    sessionFilePath,
+    position,
+    newMessage,
+  });
+}
+
+/**
+ * 把选中的消息复制（或移动）到另一个会话
+ *
+ * 副本重新生成 uuid，并在插入点处重新串接 parentUuid；原始时间戳落在插入点前后之间时保留，否则在两者之间插值。
+ * 移动模式下源会话写回失败时撤回目标会话中的副本。两个会话的缓存随之失效，修改可在各自会话中通过 `undoLastOperation` 撤销。
+ *
+ * @param sourcePath - 源会话 JSONL 文件的绝对路径
+ * @param messageUuids - 要复制的消息 UUID 集合
+ * @param targetPath - 目标会话 JSONL 文件的绝对路径
+ * @param insertAfterUuid - 插入到目标会话中此 UUID 消息之后（空字符串表示插入到开头）
+ * @param move - 为 true 时同时从源会话删除这些消息
+ * @returns 复制条数与副本的新 uuid
+ */
+export async function copyMessagesToSession(
+  sourcePath: string,
+  messageUuids: Set<string>,
+  targetPath: string,
+  insertAfterUuid: string,
+  move = false
+): Promise<CopyMessagesResult> {
+  return invoke<CopyMessagesResult>('copy_messages_to_session', {
+    sourcePath,
+    messageUuids: Array.from(messageUuids),
+    targetPath,
+    insertAfterUuid,
+    move,
+  });
+}
+
+/**
+ * 将外部 JSONL 片段（剪贴板粘贴 / 拖拽导入）追加到会话末尾
+ *
+ * Rust 后端逐行严格解析（坏行报错并指出行号），为缺少 uuid 的消息生成新 UUID，
+ * 并把 sessionId 统一为目标会话，经 file_guard 备份后写回，可通过 `undoLastOperation` 撤销。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param rawText - 要追加的 JSONL 文本
+ * @returns 新增条数与追加后重新转换的 TransformedSession
+ */
+export async function appendRawLines(
+  sessionFilePath: string,
+  rawText: string,
+): Promise<AppendLinesResult> {
+  return invoke<AppendLinesResult>('append_raw_lines', { sessionFilePath, rawText });
+}
+
+// ============ 搜索功能 ============
+
+/**
+ * 搜索选项接口
+ *
+ * 与 Rust 后端 `search_session` 命令的 `case_sensitive` / `use_regex` 参数对应，
+ * `types` / `after` / `before` 合并为 `filter` 参数（`SessionSearchFilter`）。
+ */
+export interface SearchOptions {
+  /** 是否大小写敏感（默认 false） */
+  caseSensitive?: boolean;
+  /** 是否启用正则表达式模式（默认 false） */
+  useRegex?: boolean;
+  /** 只搜索这些 displayType 的消息（如 ['user', 'assistant']），空或缺省表示不过滤 */
+  types?: string[];
+  /** 只搜索该时间之后（含）的消息（ISO 8601） */
+  after?: string;
+  /** 只搜索该时间之前（含）的消息（ISO 8601） */
+  before?: string;
+}
+
+/**
+ * 在 Rust 后端搜索会话消息
+ *
+ * 支持三种模式：
+ * - 默认：大小写不敏感的 memchr SIMD 子串搜索（最快）
+ * - caseSensitive：大小写敏感的 memchr SIMD 子串搜索
+ * - useRegex：通过 Rust regex crate 正则匹配（大小写由 caseSensitive 控制）
+ *
+ * 指定 types 时只在对应消息类型上匹配（如排除 tool_result 噪音），
+ * 指定 after / before 时只在该时间范围内（含边界）的消息上匹配。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param query - 搜索查询词
+ * @param options - 搜索选项（可选，默认不敏感+非正则）
+ * @returns 按消息顺序的命中列表（displayId、匹配次数与前 5 个匹配的位置和片段）
+ */
+export async function searchSession(
+  sessionFilePath: string,
+  query: string,
+  options?: SearchOptions,
+): Promise<SearchHit[]> {
+  return invoke<SearchHit[]>('search_session', {
+    sessionFilePath,
+    query,
+    caseSensitive: options?.caseSensitive ?? false,
+    useRegex: options?.useRegex ?? false,
+    filter: {
+      types: options?.types ?? null,
+      after: options?.after ?? null,
+      before: options?.before ?? null,
+    },
+  });
+}
+
+/**
+ * 在所有会话中搜索（全局搜索）
+ *
+ * 默认过滤 system 类型的命中（system-reminder 等注入文本），可通过 `filter.includeSystem` 关闭。
+ * 传入 `options.searchId` 后可用 `cancelGlobalSearch` 取消，取消或超时时返回部分结果。
+ *
+ * @param claudePath - Claude 数据目录路径
+ * @param query - 查询词（字面量或正则表达式）
+ * @param caseSensitive - 是否大小写敏感
+ * @param filter - 项目 / 时间范围 / 消息类型过滤器
+ * @param options - 正则模式、搜索 ID 与超时
+ * @returns 按匹配次数降序的命中列表
+ */
+export async function searchAllSessions(
+  claudePath: string,
+  query: string,
+  caseSensitive = false,
+  filter?: GlobalSearchFilter,
+  options?: GlobalSearchOptions,
+): Promise<GlobalSearchResult> {
+  return invoke<GlobalSearchResult>('search_all_sessions', { claudePath, query, caseSensitive, filter, options });
+}
+
+/**
+ * 取消进行中的全局搜索
+ *
+ * @param searchId - 发起搜索时传入的 `options.searchId`
+ * @returns 找到对应的进行中搜索时为 true
+ */
+export async function cancelGlobalSearch(searchId: string): Promise<boolean> {
+  return invoke<boolean>('cancel_global_search', { searchId });
+}
+
+// ============ 导出功能 ============
+
+/**
+ * 导出会话为指定格式
+ *
+ * 通过 Rust 后端从文件直接读取原始消息并导出。
+ * Markdown 格式仅导出 user/assistant 消息的文本内容。
+ * JSON 格式保留所有消息的完整结构。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param sessionName - 会话名称（用于 Markdown 标题）
+ * @param format - 导出格式："markdown" 或 "json"
+ * @param options - 导出选项（时间显示方式、相对时间语言），仅影响 Markdown
+ * @param filter - 只导出搜索命中的消息（可附带前后各一条上下文），省略时导出整个会话
+ * @returns 返回导出的字符串内容
+ */
+export async function exportSession(
+  sessionFilePath: string,
+  sessionName: string,
+  format: 'markdown' | 'json',
+  options?: ExportOptions,
+  filter?: ExportFilter
+): Promise<string> {
+  return invoke<string>('export_session', { sessionFilePath, sessionName, format, options, filter });
+}
+
+/**
+ * 导出会话到 TEMP 并在系统文件管理器中定位该文件，便于直接分享
+ *
+ * macOS 在 Finder 中显示，Windows 在资源管理器中选中，Linux 打开所在目录。
+ * 调起失败时 `shared` 为 false，前端应提示用户按 `filePath` 手动处理。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param format - 导出格式："markdown" 或 "json"
+ * @param options - 导出选项，仅影响 Markdown
+ * @returns 导出文件路径与是否已调起文件管理器
+ */
+export async function exportAndShare(
+  sessionFilePath: string,
+  format: 'markdown' | 'json',
+  options?: ExportOptions
+): Promise<ShareResult> {
+  return invoke<ShareResult>('export_and_share', { sessionFilePath, format, options });
+}
+
+/**
+ * 根据会话名称生成安全的导出文件名
+ *
+ * 替换非法字符、避开 Windows 保留名并按 UTF-8 字节截断，用作保存对话框的默认文件名。
+ *
+ * @param sessionName - 会话名称
+ * @param extension - 扩展名（不含点）
+ * @returns 清理后的文件名
+ */
+export async function suggestExportFileName(sessionName: string, extension: string): Promise<string> {
+  return invoke<string>('suggest_export_file_name', { sessionName, extension });
+}
+
+/**
+ * 将单条消息格式化为 Markdown / 纯文本 / JSON 字符串
+ *
+ * 由 Rust 后端复用导出的渲染逻辑，工具调用与嵌套 tool_result 完整展开。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param displayId - 目标消息的 displayId
+ * @param format - 格式："markdown"、"plain" 或 "json"
+ * @param options - 时间显示选项，仅影响 Markdown 标题中的时间
+ * @returns 格式化后的字符串，可直接写入剪贴板
+ */
+export async function formatMessage(
+  sessionFilePath: string,
+  displayId: string,
+  format: 'markdown' | 'plain' | 'json',
+  options?: ExportOptions
+): Promise<string> {
+  return invoke<string>('format_message', { sessionFilePath, displayId, format, options });
+}
+
+/**
+ * 根据界面语言推断相对时间文案语言（非中文环境使用英文）
+ */
+export function detectExportLocale(): 'zh' | 'en' {
+  return navigator.language.toLowerCase().startsWith('zh') ? 'zh' : 'en';
+}
+
+/**
+ * 导出项目清单为 CSV 或 JSON 文件
+ *
+ * 汇总每个项目的会话数、消息数、总大小、最近活跃与最早创建时间（本地时区），
+ * 由 Rust 后端直接写入用户选择的路径。
+ *
+ * @param claudePath - Claude 数据目录路径（~/.claude/）
+ * @param format - 导出格式："csv" 或 "json"
+ * @param outputPath - 输出文件路径
+ * @returns 导出的项目数量
+ */
+export async function exportProjectsOverview(
+  claudePath: string,
+  format: 'csv' | 'json',
+  outputPath: string
+): Promise<number> {
+  return invoke<number>('export_projects_overview', { claudePath, format, outputPath });
+}
+
+/**
+ * 诊断会话文件的「读取 → 写回」往返是否无损
+ *
+ * 仅在内存中比对，不修改文件。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @returns 往返校验报告
+ */
+export async function verifyRoundtrip(sessionFilePath: string): Promise<RoundtripReport> {
+  return invoke<RoundtripReport>('verify_roundtrip', { sessionFilePath });
+}
+
+/**
+ * 把非 UTF-8（UTF-16 / GBK）的会话文件转存为 UTF-8（写入前自动备份）
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @returns 转换前的原始编码；文件本身已是 UTF-8 时为 null
+ */
+export async function convertSessionEncoding(sessionFilePath: string): Promise<string | null> {
+  return invoke<string | null>('convert_session_encoding', { sessionFilePath });
+}
+
+// ============ 格式化工具 ============
+
+/**
+ * 删除指定的会话文件
+ *
+ * 通过 Rust 后端从文件系统中永久移除会话的 JSONL 文件。
+ * 同时清除相关的内存缓存。
+ *
+ * @param sessionFilePath - 要删除的会话 JSONL 文件的绝对路径
+ */
+export async function deleteSession(sessionFilePath: string): Promise<void> {
+  return invoke<void>('delete_session', { sessionFilePath });
+}
+
+/**
+ * 归档导出并删除会话
+ *
+ * 先把会话按所选格式导出到归档目录（文件名带日期与预览摘要），校验导出文件存在且非空后
+ * 再删除原文件；任何一步失败都不会删除。批量清理时逐个会话调用，每个会话独立成败。
+ *
+ * @param sessionFilePath - 要归档删除的会话 JSONL 文件的绝对路径
+ * @param archiveDir - 归档目录（不能位于 Claude 数据目录内）
+ * @param formats - 导出格式列表
+ * @returns 导出文件的绝对路径列表
+ */
+export async function archiveAndDeleteSession(
+  sessionFilePath: string,
+  archiveDir: string,
+  formats: ('markdown' | 'json')[],
+): Promise<string[]> {
+  return invoke<string[]>('archive_and_delete_session', { sessionFilePath, archiveDir, formats });
+}
+
+/**
+ * 锁定或解锁会话
+ *
+ * 锁定后后端拒绝对该会话的一切写入（编辑、删除、修复、恢复备份），
+ * 错误信息以 "SessionLocked" 开头；解锁必须显式调用本函数。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param locked - true 锁定，false 解锁
+ */
+export async function setSessionLocked(sessionFilePath: string, locked: boolean): Promise<void> {
+  return invoke<void>('set_session_locked', { sessionFilePath, locked });
+}
+
+/**
+ * 保存会话草稿（下次 resume 时要补充的内容）
+ *
+ * 草稿保存在 `~/.mo/CCR/drafts.json`，不写入会话 JSONL；空白内容等同于删除。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param text - 草稿文本
+ */
+export async function saveSessionDraft(sessionFilePath: string, text: string): Promise<void> {
+  return invoke<void>('save_session_draft', { sessionFilePath, text });
+}
+
+/**
+ * 读取会话草稿
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @returns 草稿文本；没有草稿时为 null
+ */
+export async function getSessionDraft(sessionFilePath: string): Promise<string | null> {
+  return invoke<string | null>('get_session_draft', { sessionFilePath });
+}
+
+/**
+ * 删除会话草稿
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ */
+export async function deleteSessionDraft(sessionFilePath: string): Promise<void> {
+  return invoke<void>('delete_session_draft', { sessionFilePath });
+}
+
+/**
+ * 保存会话阅读状态（滚动位置、展开的块、激活的过滤器）
+ *
+ * 状态按会话路径保存在 `~/.mo/CCR/view-state/` 下，最大 64KB；删除、归档或合并会话时一并清理。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param stateJson - 序列化后的阅读状态 JSON
+ */
+export async function saveViewState(sessionFilePath: string, stateJson: string): Promise<void> {
+  return invoke<void>('save_view_state', { sessionFilePath, stateJson });
+}
+
+/**
+ * 读取会话阅读状态
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @returns 保存时的 JSON 原文；没有保存过时为 null
+ */
+export async function getViewState(sessionFilePath: string): Promise<string | null> {
+  return invoke<string | null>('get_view_state', { sessionFilePath });
+}
+
+/**
+ * 重命名会话
+ *
+ * 标题总是保存为 CCR 别名；`writeNative` 为 true 时还会按会话中实际存在的格式
+ * 写回 `custom-title` 或 `summary` 行，让 Claude Code 的 `--resume` 列表也显示新名字。
+ * 无法确定格式时后端拒绝写回并返回原因（此时别名也不会保存）。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @param title - 新标题；为空时清除别名
+ * @param writeNative - 是否写回 Claude Code 原生标题
+ * @returns 保存后的别名与写回使用的格式
+ */
+export async function renameSession(
+  sessionFilePath: string,
+  title: string,
+  writeNative: boolean
+): Promise<RenameSessionResult> {
+  return invoke<RenameSessionResult>('rename_session', { sessionFilePath, title, writeNative });
+}
+
+/**
+ * 将碎片项目归并回父项目
+ *
+ * 后端会重新复核碎片判定，然后把会话文件移入父项目目录并删除碎片目录，
+ * 草稿、别名与置顶状态随会话迁移。父项目中有同名文件或会话已锁定时拒绝合并。
+ *
+ * @param fragmentName - 碎片项目目录名
+ * @param targetName - 父项目目录名（即 `Project.fragmentOf`）
+ * @param sourceId - 项目所属数据源（即 `Project.source`）；缺省为默认数据源
+ * @returns 移动的会话文件数
+ */
+export async function mergeFragmentProject(
+  fragmentName: string,
+  targetName: string,
+  sourceId?: string
+): Promise<number> {
+  return invoke<number>('merge_fragment_project', { fragmentName, targetName, sourceId });
+}
+
+/**
+ * 比对项目目录名的解码路径与最新会话记录的 cwd
+ *
+ * `consistent` 为 false 时可让用户确认真实路径，再调用 `setProjectPathOverride` 写入。
+ *
+ * @param claudePath - Claude 数据目录路径（~/.claude/）
+ * @param projectName - 项目目录名
+ * @returns 解码路径、cwd 路径、当前覆盖路径与两者是否一致
+ */
+export async function resolveProjectRealPath(claudePath: string, projectName: string): Promise<ProjectPathResolution> {
+  return invoke<ProjectPathResolution>('resolve_project_real_path', { claudePath, projectName });
+}
+
+/**
+ * 设置项目的真实路径覆盖（写入 ~/.mo/CCR/projects-meta.json），之后扫描得到的 `Project.path` 使用该路径
+ *
+ * @param projectName - 项目目录名
+ * @param path - 真实路径（绝对路径）；传空字符串清除覆盖
+ * @returns 覆盖是否发生了变化
+ */
+export async function setProjectPathOverride(projectName: string, path: string): Promise<boolean> {
+  return invoke<boolean>('set_project_path_override', { projectName, path });
+}
+
+/**
+ * 归档项目：默认不再出现在项目列表中（只记录在 ~/.mo/CCR/archived.json，不动原始文件）
+ *
+ * @param projectName - 项目目录名
+ * @param sourceId - 项目所属数据源（即 `Project.source`）；缺省为默认数据源
+ * @returns 归档状态是否发生了变化
+ */
+export async function archiveProject(projectName: string, sourceId?: string): Promise<boolean> {
+  return invoke<boolean>('archive_project', { projectName, sourceId });
+}
+
+/**
+ * 取消归档项目
+ *
+ * @param projectName - 项目目录名
+ * @param sourceId - 项目所属数据源（即 `Project.source`）；缺省为默认数据源
+ * @returns 归档状态是否发生了变化
+ */
+export async function unarchiveProject(projectName: string, sourceId?: string): Promise<boolean> {
+  return invoke<boolean>('unarchive_project', { projectName, sourceId });
+}
+
+/**
+ * 切换项目的置顶状态（保存在 ~/.mo/CCR/pins.json）
+ *
+ * @param projectName - 项目目录名
+ * @param sourceId - 项目所属数据源（即 `Project.source`）；缺省为默认数据源
+ * @returns 切换后是否置顶
+ */
+export async function togglePinProject(projectName: string, sourceId?: string): Promise<boolean> {
+  return invoke<boolean>('toggle_pin_project', { projectName, sourceId });
+}
+
+/**
+ * 切换会话的置顶状态（保存在 ~/.mo/CCR/pins.json）
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @returns 切换后是否置顶
+ */
+export async function togglePinSession(sessionFilePath: string): Promise<boolean> {
+  return invoke<boolean>('toggle_pin_session', { sessionFilePath });
+}
+
+/**
+ * 记录会话在 CCR 中被打开（保存在 ~/.mo/CCR/last-viewed.json）
+ *
+ * 之后文件再被修改时，会话的 `updatedSinceViewed` 为 true，侧边栏显示未读圆点。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ */
+export async function recordSessionOpened(sessionFilePath: string): Promise<void> {
+  return invoke<void>('record_session_opened', { sessionFilePath });
+}
+
+// ============ 多窗口 ============
+
+/**
+ * 在独立窗口中打开会话
+ *
+ * 同一会话已有窗口时聚焦该窗口。窗口标题为会话名称，关闭不影响主窗口。
+ * 移动端（Android / iOS）不支持，错误信息以 "Unsupported" 开头。
+ *
+ * @param sessionFilePath - 会话 JSONL 文件的绝对路径
+ * @returns 会话窗口的 label
+ */
+export async function openSessionWindow(sessionFilePath: string): Promise<string> {
+  return invoke<string>('open_session_window', { sessionFilePath });
+}
+
+/**
+ * 当前窗口启动时要打开的会话
+ *
+ * 由 `open_session_window` 创建的窗口通过初始化脚本注入 `window.__CCR_SESSION_WINDOW__`；
+ * 主窗口没有该值。
+ *
+ * @returns 会话 JSONL 文件的绝对路径；主窗口返回 null
+ */
+export function getSessionWindowTarget(): string | null {
+  const target = (window as { __CCR_SESSION_WINDOW__?: unknown }).__CCR_SESSION_WINDOW__;
+  return typeof target === 'string' ? target : null;
+}
+
+/**
+ * 监听 Claude 数据文件被修改的广播
+ *
+ * 任一窗口经后端写入或删除文件后，所有窗口都会收到 `session-updated` 事件。
+ *
+ * @param handler - 事件回调
+ * @returns 取消监听的函数
+ */
+export async function onSessionUpdated(handler: (event: SessionUpdatedEvent) => void): Promise<UnlistenFn> {
+  return listen<SessionUpdatedEvent>('session-updated', (e) => handler(e.payload));
+}
+
+/**
+ * 开始监听 Claude 数据目录（已在监听时无操作）
+ *
+ * 会话文件变化经后端防抖后以 `claude-data-changed` 事件推送，见 `onClaudeDataChanged`。
+ *
+ * @returns 本次是否新启动了监听
+ */
+export async function startWatching(): Promise<boolean> {
+  return invoke<boolean>('start_watching');
+}
+
+/**
+ * 停止监听 Claude 数据目录
+ *
+ * @returns 本次是否停止了正在运行的监听
+ */
+export async function stopWatching(): Promise<boolean> {
+  return invoke<boolean>('stop_watching');
+}
+
+/**
+ * 监听数据目录中会话文件的变化（需先调用 `startWatching`）
+ *
+ * 后端收到变化时已使对应缓存失效，回调中直接重新加载即可。
+ *
+ * @param handler - 事件回调
+ * @returns 取消监听的函数
+ */
+export async function onClaudeDataChanged(handler: (event: ClaudeDataChangedEvent) => void): Promise<UnlistenFn> {
+  return listen<ClaudeDataChangedEvent>('claude-data-changed', (e) => handler(e.payload));
+}
+
+// ============ 文件系统辅助 ============
+
+/**
+ * 检查指定路径的文件是否存在
+ *
+ * 通过 Rust 后端检查文件系统中文件是否存在。
+ * 用于在渲染工具结果的"打开文件位置"按钮前判断按钮是否应该可用。
+ *
+ * @param filePath - 要检查的文件的绝对路径
+ * @returns 文件存在返回 true，否则返回 false
+ */
+export async function checkFileExists(filePath: string): Promise<boolean> {
+  return invoke<boolean>('check_file_exists', { filePath });
+}
+
+/**
+ * 批量检查一批路径是否存在（单次 IPC，Rust 端并发 stat）
+ *
+ * 路径在后端去重，单次最多 `FILE_EXISTS_BATCH_LIMIT` 个；单个 stat 失败视为不存在。
+ *
+ * @param paths - 要检查的文件绝对路径列表
+ * @returns 路径 → 是否存在 的映射
+ */
+export async function checkFilesExist(paths: string[]): Promise<Record<string, boolean>> {
+  return invoke<Record<string, boolean>>('check_files_exist', { paths });
+}
+
+/** 单批查询的路径上限，对应 Rust `check_files_exist` 的 `MAX_BATCH_PATHS` */
+const FILE_EXISTS_BATCH_LIMIT = 1000;
+
+/** 合并查询的时间窗口（毫秒）：同一帧内可见的消息合并为一次 IPC */
+const FILE_EXISTS_BATCH_DELAY_MS = 16;
+
+/** 等待查询的路径 → 等待结果的回调列表 */
+const pendingFileChecks = new Map<string, Array<(exists: boolean) => void>>();
+
+/** 合并查询的定时器，为 null 表示当前没有待发送的批次 */
+let fileCheckTimer: ReturnType<typeof setTimeout> | null = null;
+
+/**
+ * 检查文件是否存在（自动合并为批量查询）
+ *
+ * 接口与 `checkFileExists` 相同，但同一时间窗口内的所有调用会合并为一次
+ * `check_files_exist`，供大量工具结果在变为可见时各自调用而不产生 IPC 风暴。
+ *
+ * @param filePath - 要检查的文件的绝对路径
+ * @returns 文件存在返回 true，否则（含查询失败）返回 false
+ */
+export function checkFileExistsBatched(filePath: string): Promise<boolean> {
+  return new Promise((resolve) => {
+    const waiters = pendingFileChecks.get(filePath);
+    if (waiters) {
+      waiters.push(resolve);
+    } else {
+      pendingFileChecks.set(filePath, [resolve]);
+    }
+    if (fileCheckTimer === null) {
+      fileCheckTimer = setTimeout(flushFileChecks, FILE_EXISTS_BATCH_DELAY_MS);
+    }
+  });
+}
+
+/** 发送当前积累的批量查询，超过上限时分块发送 */
+async function flushFileChecks(): Promise<void> {
+  fileCheckTimer = null;
+  const batch = new Map(pendingFileChecks);
+  pendingFileChecks.clear();
+
+  const paths = [...batch.keys()];
+  for (let i = 0; i < paths.length; i += FILE_EXISTS_BATCH_LIMIT) {
+    const chunk = paths.slice(i, i + FILE_EXISTS_BATCH_LIMIT);
+    let result: Record<string, boolean> = {};
+    try {
+      result = await checkFilesExist(chunk);
+    } catch (err) {
+      console.error('批量检查文件是否存在失败:', err);
+    }
+    for (const path of chunk) {
+      const exists = result[path] ?? false;
+      batch.get(path)?.forEach((resolve) => resolve(exists));
+    }
+  }
+}
+
+// ============ 实用工具函数 ============
+
+/**
+ * 读取一键 Resume 配置
+ *
+ * 从 `~/.mo/CCR/resume-config.json` 加载用户配置的 Resume 参数。
+ * 配置文件不存在时返回默认空配置（空 flags + 空 customArgs）。
+ *
+ * @returns ResumeConfig 对象
+ */
+export async function readResumeConfig(): Promise<ResumeConfig> {
+  return invoke<ResumeConfig>('read_resume_config');
+}
+
+/**
+ * 保存一键 Resume 配置
+ *
+ * 将 ResumeConfig 序列化为 JSON 并写入 `~/.mo/CCR/resume-config.json`。
+ *
+ * @param config - 要保存的 ResumeConfig 对象
+ */
+export async function saveResumeConfig(config: ResumeConfig): Promise<void> {
+  return invoke<void>('save_resume_config', { config });
+}
+
+/**
+ * 打开系统终端执行 claude --resume 命令
+ *
+ * 在 Rust 后端通过 `std::process::Command` 打开系统终端，
+ * 自动 cd 到项目目录并执行 `claude --resume <sessionId> <flags> <customArgs>`。
+ * 指定 draftSessionPath 时附带该会话的草稿作为首条输入，终端启动后草稿自动清除。
+ * 移动端（Android / iOS）不支持，错误信息以 "Unsupported" 开头。
+ *
+ * @param projectPath - 项目的真实文件系统路径（已解码）
+ * @param sessionId - 会话 UUID
+ * @param draftSessionPath - 附带草稿的会话 JSONL 文件路径（可选）
+ */
+export async function openResumeTerminal(projectPath: string, sessionId: string, draftSessionPath?: string): Promise<void> {
+  return invoke<void>('open_resume_terminal', { projectPath, sessionId, draftSessionPath });
+}
+
+/**
+ * 构建 resume 命令字符串（不执行）
+ *
+ * 调用 Rust 后端构建完整的 `claude --resume <sessionId> <flags> <customArgs>` 命令，
+ * 返回字符串供前端复制到剪贴板。
+ *
+ * @param sessionId - 会话 UUID
+ * @param draftSessionPath - 附带草稿的会话 JSONL 文件路径（可选，复制命令不会清除草稿）
+ * @returns 完整的 resume 命令字符串
+ */
+export async function buildResumeCommand(sessionId: string, draftSessionPath?: string): Promise<string> {
+  return invoke<string>('build_resume_command', { sessionId, draftSessionPath });
+}
+
+// ============ 备份配置 ============
+
 /**
  * 读取备份配置
  *
@@ -519,6 +1857,181 @@ export async function saveBackupConfig(config: BackupConfig): Promise<void> {
   return invoke<void>('save_backup_config', { config });
 }
 
+/**
+ * 按保留策略手动清理 TEMP 下的临时资源（图片、导出残留）
+ *
+ * 临时备份不受影响。应用退出时后端也会按同一策略自动清理。
+ *
+ * @returns 删除文件数与释放的空间
+ */
+export async function cleanupTempArtifacts(): Promise<CleanupReport> {
+  return invoke<CleanupReport>('cleanup_temp_artifacts');
+}
+
+/**
+ * 获取上次异常退出留下的写入残留（会话目录下的 `*.jsonl.tmp`）
+ *
+ * 已排除正在写入中的文件。应用启动时调用一次。
+ *
+ * @returns 残留列表，附带与正式文件的 mtime / 完整性比对结果
+ */
+export async function getRecoveryCandidates(): Promise<RecoveryCandidate[]> {
+  return invoke<RecoveryCandidate[]>('get_recovery_candidates');
+}
+
+/**
+ * 处理一个写入残留
+ *
+ * @param tmpPath - 残留 .tmp 文件的绝对路径
+ * @param action - 处理方式：丢弃 / 覆盖正式文件 / 另存为新会话
+ * @returns 处理后保留的会话文件路径（丢弃时为 null）
+ */
+export async function resolveRecovery(tmpPath: string, action: RecoveryAction): Promise<string | null> {
+  return invoke<string | null>('resolve_recovery', { tmpPath, action });
+}
+
+/**
+ * 启动自检：检查数据目录、读写权限、磁盘空间与 claude CLI 是否可用
+ *
+ * 各项并行检查，单项超时记为失败。用于空状态页排查"界面空白"的原因。
+ *
+ * @returns 逐项检查结果与修复建议
+ */
+export async function runStartupDiagnostics(): Promise<DiagnosticItem[]> {
+  return invoke<DiagnosticItem[]>('run_startup_diagnostics');
+}
+
+/**
+ * 获取引导状态：未安装 Claude Code / 尚无项目 / 正常，以及 claude CLI 是否在 PATH 中
+ *
+ * 只做目录枚举与 PATH 查找，开销很小。项目列表为空时据此选择展示哪种引导页。
+ *
+ * @returns 引导状态、数据目录路径、项目数与 CLI 路径
+ */
+export async function getOnboardingState(): Promise<OnboardingState> {
+  return invoke<OnboardingState>('get_onboarding_state');
+}
+
+/**
+ * 生成脱敏的反馈诊断包（ZIP），供报 issue 时附上
+ *
+ * 包含版本、启动自检、缓存统计、最近命令耗时、日志尾部与勾选会话的结构统计（不含消息正文）。
+ * 单项收集失败不会中断，只在清单中记为缺失。
+ *
+ * @param outputPath - ZIP 输出路径（不能位于 Claude 数据目录内）
+ * @param sessionFilePaths - 可选，需要附带结构统计的问题会话
+ * @returns 诊断包清单
+ */
+export async function generateDiagnosticBundle(outputPath: string, sessionFilePaths?: string[]): Promise<DiagnosticBundleManifest> {
+  return invoke<DiagnosticBundleManifest>('generate_diagnostic_bundle', { outputPath, sessionFilePaths });
+}
+
+/**
+ * 获取最近的命令调用记录，用于排查慢命令
+ *
+ * @returns 最多 500 条记录（最旧的在前），路径参数已脱敏为文件名
+ */
+export async function getCommandTrace(): Promise<CommandTraceEntry[]> {
+  return invoke<CommandTraceEntry[]>('get_command_trace');
+}
+
+/**
+ * 获取会话缓存的估算内存占用与淘汰次数
+ *
+ * @returns 缓存统计；总占用超过上限（limits.json 的 cacheMemoryMb）时缓存会主动淘汰
+ */
+export async function getCacheStats(): Promise<CacheStats> {
+  return invoke<CacheStats>('get_cache_stats');
+}
+
+/**
+ * 对比缓存与磁盘状态（诊断用）
+ *
+ * 会话缓存重新 stat 并重新解析比对 display_id，项目列表缓存与一次快速重扫对比。
+ *
+ * @param invalidate - 是否自动失效不一致的缓存，默认只报告
+ * @returns 检查范围、发现的不一致与失效情况
+ */
+export async function verifyCacheConsistency(invalidate = false): Promise<CacheConsistencyReport> {
+  return invoke<CacheConsistencyReport>('verify_cache_consistency', { invalidate });
+}
+
+/**
+ * 获取本次运行期间的临时备份列表
+ *
+ * @returns 按创建时间顺序排列的 TempBackupEntry 数组
+ */
+export async function getTempBackups(): Promise<TempBackupEntry[]> {
+  return invoke<TempBackupEntry[]>('get_temp_backups');
+}
+
+/**
+ * 从临时备份恢复原文件
+ *
+ * 后端会先预检备份内容，问题行比例过高时不写入并返回 `restored: false`，
+ * 前端展示预检结果后可带 `force` 重新调用。
+ *
+ * @param tempPath - 临时备份文件的绝对路径
+ * @param force - 预检未通过时是否仍然恢复
+ * @returns 预检结果与是否已恢复
+ */
+export async function restoreTempBackup(tempPath: string, force = false): Promise<RestoreResult> {
+  return invoke<RestoreResult>('restore_temp_backup', { tempPath, force });
+}
+
+/**
+ * 从主动备份（.ccbak 文件）恢复原文件
+ *
+ * 预检规则与 `restoreTempBackup` 相同。
+ *
+ * @param backupPath - .ccbak 备份文件的绝对路径
+ * @param force - 预检未通过时是否仍然恢复
+ * @returns 预检结果与是否已恢复
+ */
+export async function restoreAutoBackup(backupPath: string, force = false): Promise<RestoreResult> {
+  return invoke<RestoreResult>('restore_auto_backup', { backupPath, force });
+}
+
+/**
+ * 列出所有会话的备份（临时备份 + .ccbak 主动备份）
+ *
+ * 只读取目录项与元数据，不读取备份内容。
+ *
+ * @param claudePath - Claude 数据目录路径
+ * @param sessionFilePath - 只列出该文件的备份（省略时列出全部）
+ * @param oldestFirst - 是否按时间升序排列（默认最新的在前）
+ * @returns 按创建时间排序的备份列表
+ */
+export async function listAllBackups(
+  claudePath: string,
+  sessionFilePath?: string,
+  oldestFirst = false,
+): Promise<BackupItem[]> {
+  return invoke<BackupItem[]>('list_all_backups', { claudePath, sessionFilePath, oldestFirst });
+}
+
+/**
+ * 删除一个备份文件
+ *
+ * 后端只允许删除临时备份目录下的文件或 `.ccbak` 主动备份。
+ *
+ * @param backupPath - 备份文件的绝对路径
+ */
+export async function deleteBackup(backupPath: string): Promise<void> {
+  return invoke<void>('delete_backup', { backupPath });
+}
+
+/**
+ * 分页读取写入与删除的审计日志（最新的在前）
+ *
+ * @param filter - 查询条件（路径子串、批次 ID、偏移）
+ * @param limit - 本页最多返回的记录数（后端默认 100）
+ * @returns 本页记录、符合条件的总数与是否还有更早的记录
+ */
+export async function readAuditLog(filter?: AuditFilter, limit?: number): Promise<AuditLogPage> {
+  return invoke<AuditLogPage>('read_audit_log', { filter, limit });
+}
+
 // ============ 一键修复 ============
 
 /**
@@ -555,39 +2068,82 @@ export async function executeFixer(
 /**
  * 在系统文件管理器中打开指定文件所在的目录
  *
- * 通过 Tauri 官方 opener 插件调用 OS 原生 API 定位文件：
+ * 由 Rust 后端经 Tauri 官方 opener 插件调用 OS 原生 API 定位文件：
  * - Windows: Shell COM API (SHOpenFolderAndSelectItems)
  * - macOS: NSWorkspace selectFile
  * - Linux: D-Bus org.freedesktop.FileManager1
  *
  * 相比手动拼接 explorer/open/xdg-open 命令，无参数注入风险，无跨平台兼容性问题。
+ * 移动端（Android / iOS）不支持，错误信息以 "Unsupported" 开头。
  *
  * @param filePath - 要在文件管理器中打开的文件绝对路径
  */
 export async function openInExplorer(filePath: string): Promise<void> {
-  const { revealItemInDir } = await import('@tauri-apps/plugin-opener');
-  return revealItemInDir(filePath);
+  return invoke<void>('reveal_in_file_manager', { filePath });
+}
+
+/**
+ * 在系统默认浏览器中打开链接
+ *
+ * 通过 Tauri 官方 opener 插件打开，避免在应用 WebView 内跳转。
+ *
+ * @param url - 要打开的 http/https 链接
+ */
+export async function openUrl(url: string): Promise<void> {
+  const { openUrl: open } = await import('@tauri-apps/plugin-opener');
+  return open(url);
+}
+
+/** 前端格式化使用的显示时区（启动时与设置保存后由 `setFormattingTimezone` 同步） */
+let formattingTimezone: DisplayTimezone = 'local';
+
+/**
+ * 设置 `formatTimestamp` 使用的显示时区
+ *
+ * @param timezone - `'local'`、`'utc'` 或 `±HH:MM` 偏移
+ */
+export function setFormattingTimezone(timezone: DisplayTimezone): void {
+  formattingTimezone = timezone;
+}
+
+/**
+ * 解析固定偏移时区为分钟数，非偏移格式返回 null
+ */
+function parseOffsetMinutes(timezone: DisplayTimezone): number | null {
+  const match = /^([+-])(\d{2}):(\d{2})$/.exec(timezone);
+  if (!match) return null;
+  const minutes = Number(match[2]) * 60 + Number(match[3]);
+  return match[1] === '-' ? -minutes : minutes;
 }
 
 /**
  * 格式化时间戳为中文本地化日期时间字符串
  *
- * 接受多种时间格式输入，统一转换为中文（zh-CN）格式的日期时间字符串。
+ * 接受多种时间格式输入，按显示时区统一转换为中文（zh-CN）格式的日期时间字符串。
  * 此函数为纯计算操作，保留在前端。
  *
  * @param timestamp - 时间戳，支持 ISO 8601 字符串、Unix 毫秒数或 Date 对象
  * @returns 返回格式化后的中文日期时间字符串
  */
 export function formatTimestamp(timestamp: string | number | Date): string {
-  const date = new Date(timestamp);
-  return date.toLocaleString('zh-CN', {
+  let date = new Date(timestamp);
+  const options: Intl.DateTimeFormatOptions = {
     year: 'numeric',
     month: '2-digit',
     day: '2-digit',
     hour: '2-digit',
     minute: '2-digit',
     second: '2-digit',
-  });
+  };
+  const offset = parseOffsetMinutes(formattingTimezone);
+  if (offset !== null) {
+    // 固定偏移：平移后按 UTC 格式化
+    date = new Date(date.getTime() + offset * 60_000);
+    options.timeZone = 'UTC';
+  } else if (formattingTimezone === 'utc') {
+    options.timeZone = 'UTC';
+  }
+  return date.toLocaleString('zh-CN', options);
 }
 
 // =============================================================================